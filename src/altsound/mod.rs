@@ -0,0 +1,424 @@
+//! Reading PinMAME AltSound packages
+//!
+//! An AltSound package replaces the original sounds of a rom. It lives in a folder named
+//! after the rom, eg `VPinMAME/altsound/tz_94h/`, and contains:
+//!
+//! * `altsound.csv` (legacy format) or `g-sound.csv` (G-Sound format) mapping sound
+//!   commands to sample files
+//! * optionally `altsound.ini` with the format and the ducking/pausing configuration per
+//!   sample type
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::altsound::parse_csv;
+//!
+//! let csv = "ID,CHANNEL,DUCK,GAIN,LOOP,STOP,NAME,FNAME\n0x0001,0,100,80,0,0,\"Theme\",\"music/theme.ogg\"\n";
+//! let samples = parse_csv(csv).unwrap();
+//! assert_eq!(samples[0].id, 1);
+//! assert_eq!(samples[0].gain, Some(80));
+//! assert_eq!(samples[0].file_name, "music/theme.ogg");
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::settings::ini::Ini;
+
+pub const LEGACY_CSV: &str = "altsound.csv";
+pub const GSOUND_CSV: &str = "g-sound.csv";
+pub const INI: &str = "altsound.ini";
+
+/// The sample types with their own configuration in `altsound.ini`
+pub const SAMPLE_TYPES: [&str; 5] = ["music", "callout", "sfx", "solo", "overlay"];
+
+/// The format of the package as configured in `altsound.ini`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AltSoundFormat {
+    Legacy,
+    GSound,
+    Other(String),
+}
+
+impl From<&str> for AltSoundFormat {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "altsound" | "legacy" => AltSoundFormat::Legacy,
+            "g-sound" | "gsound" => AltSoundFormat::GSound,
+            _ => AltSoundFormat::Other(value.to_string()),
+        }
+    }
+}
+
+/// A sound command mapped to a sample file
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AltSoundSample {
+    /// The sound command, stored as hex in the csv file
+    pub id: u32,
+    pub channel: Option<i32>,
+    /// Percentage other sounds are reduced with while this one plays
+    pub duck: Option<u32>,
+    /// Volume in percent
+    pub gain: Option<u32>,
+    pub looped: bool,
+    /// Stops the other sounds
+    pub stop: bool,
+    pub name: String,
+    /// Relative to the package folder
+    pub file_name: String,
+    /// Legacy sample group, eg 1 for music
+    pub group: Option<u32>,
+    /// G-Sound sample type, see [`SAMPLE_TYPES`]
+    pub sample_type: Option<String>,
+    /// G-Sound ducking profile
+    pub ducking_profile: Option<u32>,
+}
+
+/// The configuration for one of the [`SAMPLE_TYPES`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SampleTypeConfig {
+    pub name: String,
+    /// Volume in percent
+    pub group_vol: Option<u32>,
+    /// Sample types that are ducked while this type plays
+    pub ducks: Vec<String>,
+    /// Sample types that are paused while this type plays
+    pub pauses: Vec<String>,
+}
+
+/// The contents of `altsound.ini`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AltSoundConfig {
+    pub format: Option<AltSoundFormat>,
+    pub record_sound_cmds: Option<bool>,
+    /// Only the sample types that are present in the file
+    pub sample_types: Vec<SampleTypeConfig>,
+    /// The full file, for settings not covered above
+    pub ini: Ini,
+}
+
+impl AltSoundConfig {
+    pub fn parse(input: &str) -> AltSoundConfig {
+        let ini = Ini::parse(input);
+        let list = |section: &str, key: &str| -> Vec<String> {
+            ini.get(section, key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(|v| v.trim().to_ascii_lowercase())
+                        .filter(|v| !v.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let sample_types = SAMPLE_TYPES
+            .iter()
+            .filter(|name| !ini.entries(name).is_empty())
+            .map(|name| SampleTypeConfig {
+                name: name.to_string(),
+                group_vol: ini.get(name, "group_vol").and_then(|v| v.parse().ok()),
+                ducks: list(name, "ducks"),
+                pauses: list(name, "pauses"),
+            })
+            .collect();
+        AltSoundConfig {
+            format: ini.get("format", "format").map(AltSoundFormat::from),
+            record_sound_cmds: ini
+                .get("system", "record_sound_cmds")
+                .map(|v| v.eq_ignore_ascii_case("true") || v == "1"),
+            sample_types,
+            ini,
+        }
+    }
+}
+
+/// An AltSound package folder
+#[derive(Debug, PartialEq, Clone)]
+pub struct AltSoundPackage {
+    pub dir: PathBuf,
+    /// The name of the csv file that was read
+    pub csv_file: String,
+    pub samples: Vec<AltSoundSample>,
+    pub config: Option<AltSoundConfig>,
+}
+
+impl AltSoundPackage {
+    /// Reads a package folder, the G-Sound csv is preferred if both are present.
+    pub fn read<P: AsRef<Path>>(dir: P) -> io::Result<AltSoundPackage> {
+        let dir = dir.as_ref();
+        let csv_file = [GSOUND_CSV, LEGACY_CSV]
+            .into_iter()
+            .find(|name| dir.join(name).is_file())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No AltSound csv found in {}", dir.display()),
+                )
+            })?;
+        let csv = fs::read(dir.join(csv_file))?;
+        let samples = parse_csv(&String::from_utf8_lossy(&csv))?;
+        let ini_path = dir.join(INI);
+        let config = if ini_path.is_file() {
+            let ini = fs::read(ini_path)?;
+            Some(AltSoundConfig::parse(&String::from_utf8_lossy(&ini)))
+        } else {
+            None
+        };
+        Ok(AltSoundPackage {
+            dir: dir.to_path_buf(),
+            csv_file: csv_file.to_string(),
+            samples,
+            config,
+        })
+    }
+
+    /// The rom this package is for, taken from the folder name
+    pub fn rom(&self) -> Option<&str> {
+        self.dir.file_name().and_then(|name| name.to_str())
+    }
+
+    /// Checks that the package is for `rom` and that all sample files exist.
+    pub fn validate(&self, rom: &str) -> Vec<AltSoundIssue> {
+        let mut issues = Vec::new();
+        match self.rom() {
+            Some(package_rom) if package_rom.eq_ignore_ascii_case(rom) => {}
+            package_rom => issues.push(AltSoundIssue::RomMismatch {
+                expected: rom.to_string(),
+                found: package_rom.unwrap_or_default().to_string(),
+            }),
+        }
+        for sample in &self.samples {
+            let path = sample
+                .file_name
+                .split(['\\', '/'])
+                .filter(|part| !part.is_empty())
+                .fold(self.dir.clone(), |path, part| path.join(part));
+            if !path.is_file() {
+                issues.push(AltSoundIssue::MissingSample {
+                    id: sample.id,
+                    file_name: sample.file_name.clone(),
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// A problem found by [`AltSoundPackage::validate`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AltSoundIssue {
+    /// The package folder is named after another rom
+    RomMismatch { expected: String, found: String },
+    /// A sample file referenced in the csv does not exist
+    MissingSample { id: u32, file_name: String },
+}
+
+impl fmt::Display for AltSoundIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AltSoundIssue::RomMismatch { expected, found } => {
+                write!(f, "Package is for rom {} instead of {}", found, expected)
+            }
+            AltSoundIssue::MissingSample { id, file_name } => {
+                write!(f, "Sample 0x{:04x} not found: {}", id, file_name)
+            }
+        }
+    }
+}
+
+/// Parses an `altsound.csv` or `g-sound.csv` file.
+///
+/// Columns are looked up by their header name, unknown columns are ignored.
+/// Fails if the `ID` or `FNAME` column is missing or if an `ID` is not valid.
+pub fn parse_csv(input: &str) -> io::Result<Vec<AltSoundSample>> {
+    let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<String> = lines
+        .next()
+        .map(split_csv_line)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|h| h.trim().to_ascii_uppercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let required = |name: &str| {
+        column(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("AltSound csv is missing column {}", name),
+            )
+        })
+    };
+    let id_column = required("ID")?;
+    let file_column = required("FNAME")?;
+
+    let mut samples = Vec::new();
+    for line in lines {
+        let values = split_csv_line(line);
+        let get = |index: Option<usize>| -> Option<&str> {
+            index
+                .and_then(|i| values.get(i))
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+        let number = |name: &str| get(column(name)).and_then(|v| v.parse::<u32>().ok());
+        let raw_id = get(Some(id_column)).unwrap_or_default();
+        let id = parse_id(raw_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid AltSound sample id: {}", raw_id),
+            )
+        })?;
+        samples.push(AltSoundSample {
+            id,
+            channel: get(column("CHANNEL")).and_then(|v| v.parse().ok()),
+            duck: number("DUCK"),
+            gain: number("GAIN"),
+            // legacy files use 100 for looping samples
+            looped: number("LOOP").is_some_and(|v| v > 0),
+            stop: number("STOP").is_some_and(|v| v > 0),
+            name: get(column("NAME")).unwrap_or_default().to_string(),
+            file_name: get(Some(file_column)).unwrap_or_default().to_string(),
+            group: number("GROUP"),
+            sample_type: get(column("TYPE")).map(|v| v.to_ascii_lowercase()),
+            ducking_profile: number("DUCKING_PROFILE"),
+        });
+    }
+    Ok(samples)
+}
+
+fn parse_id(id: &str) -> Option<u32> {
+    match id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => u32::from_str_radix(id, 16).ok(),
+    }
+}
+
+/// Splits a csv line on commas, supporting double quoted values with `""` escapes
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                value.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => values.push(std::mem::take(&mut value)),
+            _ => value.push(c),
+        }
+    }
+    values.push(value);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn parse_legacy_csv() -> io::Result<()> {
+        let csv = r#"ID,CHANNEL,DUCK,GAIN,LOOP,STOP,NAME,FNAME,GROUP,SHAKER,SERIAL,PRELOAD,STOPCMD
+0x0002,0,50,100,100,0,"Main, theme","music\main.ogg",1,,,0,
+0x00a1,,0,80,0,1,"Jackpot","callouts/jackpot.ogg",2,,,0,
+"#;
+        let samples = parse_csv(csv)?;
+        assert_eq!(
+            samples,
+            vec![
+                AltSoundSample {
+                    id: 2,
+                    channel: Some(0),
+                    duck: Some(50),
+                    gain: Some(100),
+                    looped: true,
+                    stop: false,
+                    name: "Main, theme".to_string(),
+                    file_name: "music\\main.ogg".to_string(),
+                    group: Some(1),
+                    ..Default::default()
+                },
+                AltSoundSample {
+                    id: 0xa1,
+                    channel: None,
+                    duck: Some(0),
+                    gain: Some(80),
+                    looped: false,
+                    stop: true,
+                    name: "Jackpot".to_string(),
+                    file_name: "callouts/jackpot.ogg".to_string(),
+                    group: Some(2),
+                    ..Default::default()
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_csv_missing_column() {
+        let result = parse_csv("ID,NAME\n0x01,test\n");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_config() {
+        let ini = "[format]\nformat = g-sound\n\n[system]\nrecord_sound_cmds = false\n\n[music]\ngroup_vol = 80\n\n[callout]\nducks = music, sfx\npauses = overlay\n";
+        let config = AltSoundConfig::parse(ini);
+        assert_eq!(config.format, Some(AltSoundFormat::GSound));
+        assert_eq!(config.record_sound_cmds, Some(false));
+        assert_eq!(
+            config.sample_types,
+            vec![
+                SampleTypeConfig {
+                    name: "music".to_string(),
+                    group_vol: Some(80),
+                    ..Default::default()
+                },
+                SampleTypeConfig {
+                    name: "callout".to_string(),
+                    group_vol: None,
+                    ducks: vec!["music".to_string(), "sfx".to_string()],
+                    pauses: vec!["overlay".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_and_validate_package() -> io::Result<()> {
+        let dir = testdir!().join("tz_94h");
+        fs::create_dir_all(dir.join("music"))?;
+        fs::write(dir.join("music").join("main.ogg"), [])?;
+        fs::write(
+            dir.join(LEGACY_CSV),
+            "ID,CHANNEL,DUCK,GAIN,LOOP,STOP,NAME,FNAME\n0x02,0,0,100,0,0,\"Main\",\"music\\main.ogg\"\n0x03,0,0,100,0,0,\"Gone\",\"music\\gone.ogg\"\n",
+        )?;
+
+        let package = AltSoundPackage::read(&dir)?;
+        assert_eq!(package.csv_file, LEGACY_CSV);
+        assert_eq!(package.rom(), Some("tz_94h"));
+        assert_eq!(package.config, None);
+        assert_eq!(
+            package.validate("TZ_94H"),
+            vec![AltSoundIssue::MissingSample {
+                id: 3,
+                file_name: "music\\gone.ogg".to_string()
+            }]
+        );
+        assert_eq!(
+            package.validate("tz_92").first(),
+            Some(&AltSoundIssue::RomMismatch {
+                expected: "tz_92".to_string(),
+                found: "tz_94h".to_string()
+            })
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Base64 encoded values of a backglass
+//!
+//! Images and sounds are stored base64 encoded in the xml attributes. [`Base64Data`] decodes
+//! them while reading, so only the bytes are kept in memory instead of the encoded text,
+//! which is a third larger. The data is encoded again one value at a time when writing.
+//!
+//! Values that would not be written back exactly the same after decoding, like the
+//! `[stripped]` marker of [`super::DirectB2SData::strip_images`] or base64 with line breaks,
+//! are kept as text.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::directb2s::encoded::Base64Data;
+//!
+//! let data = Base64Data::from_base64("UklGRg==");
+//! assert_eq!(data.bytes(), Some(&b"RIFF"[..]));
+//! assert_eq!(data.to_base64(), "UklGRg==");
+//! ```
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Debug, Formatter};
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Base64Data {
+    Decoded(Vec<u8>),
+    /// Text that does not decode to base64 in its canonical form
+    Text(String),
+}
+
+impl Default for Base64Data {
+    fn default() -> Self {
+        Base64Data::Decoded(Vec::new())
+    }
+}
+
+impl Base64Data {
+    /// Decodes the value if encoding the result again gives the same text
+    pub fn from_base64(encoded: &str) -> Self {
+        match STANDARD.decode(encoded) {
+            Ok(bytes) => Base64Data::Decoded(bytes),
+            Err(_) => Base64Data::Text(encoded.to_string()),
+        }
+    }
+
+    /// Like [`Base64Data::from_base64`], the text is dropped as soon as it is decoded
+    pub fn from_base64_string(encoded: String) -> Self {
+        match STANDARD.decode(&encoded) {
+            Ok(bytes) => Base64Data::Decoded(bytes),
+            Err(_) => Base64Data::Text(encoded),
+        }
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Base64Data::Decoded(bytes)
+    }
+
+    /// The decoded data, `None` for values kept as text
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Base64Data::Decoded(bytes) => Some(bytes),
+            Base64Data::Text(_) => None,
+        }
+    }
+
+    pub fn to_base64(&self) -> String {
+        match self {
+            Base64Data::Decoded(bytes) => STANDARD.encode(bytes),
+            Base64Data::Text(text) => text.clone(),
+        }
+    }
+
+    /// The length of the value as written in the file
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Base64Data::Decoded(bytes) => bytes.len().div_ceil(3) * 4,
+            Base64Data::Text(text) => text.len(),
+        }
+    }
+}
+
+impl From<&str> for Base64Data {
+    fn from(encoded: &str) -> Self {
+        Base64Data::from_base64(encoded)
+    }
+}
+
+impl Debug for Base64Data {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "base64 {:?} bytes", self.encoded_len())
+    }
+}
+
+impl Serialize for Base64Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Base64Data::Decoded(bytes) => serializer.serialize_str(&STANDARD.encode(bytes)),
+            Base64Data::Text(text) => serializer.serialize_str(text),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Base64Visitor;
+
+        impl Visitor<'_> for Base64Visitor {
+            type Value = Base64Data;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a base64 encoded string")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Base64Data, E> {
+                Ok(Base64Data::from_base64(value))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, value: String) -> Result<Base64Data, E> {
+                Ok(Base64Data::from_base64_string(value))
+            }
+        }
+
+        deserializer.deserialize_str(Base64Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn keeps_non_canonical_values() {
+        for text in ["[stripped]", "UklG\nRg==", "UklGRg", "UklGRh==", ""] {
+            let data = Base64Data::from_base64(text);
+            assert_eq!(data.to_base64(), text);
+            assert_eq!(data.encoded_len(), text.len());
+        }
+        assert_eq!(
+            Base64Data::from_base64("[stripped]"),
+            Base64Data::Text("[stripped]".to_string())
+        );
+        assert_eq!(Base64Data::from_base64(""), Base64Data::default());
+    }
+
+    #[test]
+    fn encoded_len() {
+        for len in 0..10 {
+            let data = Base64Data::from_bytes(vec![7; len]);
+            assert_eq!(data.encoded_len(), data.to_base64().len());
+        }
+    }
+}
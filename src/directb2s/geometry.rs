@@ -0,0 +1,312 @@
+//! Where the backglass and its bulbs end up on screen
+//!
+//! The bottom of the backglass image usually contains a grill, the area where the DMD or
+//! score displays go. Depending on the player settings the grill is shown, hidden or
+//! reduced to its small height by cutting the bottom of the image off. The cut off image
+//! is then stretched to the screen. Bulb locations are in pixels of the full image,
+//! measured from the top left corner.
+//!
+//! The [`DMDDefaultLocation`](super::DMDDefaultLocation) is the top left corner of the DMD in
+//! pixels of the image it is shown on, the DMD image if the file has one, otherwise the
+//! backglass image. The size of the DMD is not stored, [`DirectB2SData::dmd_area`] takes it
+//! from the caller.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use vpin::directb2s;
+//! use vpin::directb2s::geometry::Grill;
+//!
+//! let file = std::fs::File::open("testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s").unwrap();
+//! let data = directb2s::read(io::BufReader::new(file)).unwrap();
+//! // the image size has to be taken from the decoded backglass image
+//! let image_size = (2857, 2092);
+//! let visible = data.visible_backglass_area(image_size, Grill::Hidden);
+//! println!("visible: {}x{}", visible.width, visible.height);
+//! for bulb in data.illumination.bulb.iter().flatten() {
+//!     if let Some(area) = data.bulb_screen_area(bulb, image_size, Grill::Hidden, (1920, 1080)) {
+//!         println!("{}: {:?}", bulb.name, area);
+//!     }
+//! }
+//! ```
+
+use super::{Bulb, DirectB2SData};
+
+/// How the grill part of the backglass image is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grill {
+    Visible,
+    Hidden,
+    /// Only the small grill height is kept, see [`GrillHeight::small`](super::GrillHeight::small)
+    Small,
+}
+
+/// A rectangle in pixels, from the top left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Area {
+    /// The area as fractions of a `(width, height)`, so 0 to 1 for areas within it
+    pub fn normalized(&self, size: (u32, u32)) -> Area {
+        let (width, height) = (size.0 as f32, size.1 as f32);
+        let ratio = |value: f32, total: f32| if total > 0.0 { value / total } else { 0.0 };
+        Area {
+            x: ratio(self.x, width),
+            y: ratio(self.y, height),
+            width: ratio(self.width, width),
+            height: ratio(self.height, height),
+        }
+    }
+
+    /// The overlap of both areas, `None` if they do not overlap
+    fn intersection(&self, other: &Area) -> Option<Area> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        (right > x && bottom > y).then_some(Area {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
+}
+
+impl DirectB2SData {
+    /// The number of pixels cut off the bottom of the backglass image
+    pub fn grill_crop(&self, grill: Grill) -> u32 {
+        let height = self.grill_height.value_u32().unwrap_or(0);
+        match grill {
+            Grill::Visible => 0,
+            Grill::Hidden => height,
+            Grill::Small => height.saturating_sub(self.grill_height.small_u32().unwrap_or(0)),
+        }
+    }
+
+    /// The part of the backglass image that is shown, for an image of `(width, height)`
+    pub fn visible_backglass_area(&self, image_size: (u32, u32), grill: Grill) -> Area {
+        let (width, height) = image_size;
+        Area {
+            x: 0.0,
+            y: 0.0,
+            width: width as f32,
+            height: height.saturating_sub(self.grill_crop(grill)) as f32,
+        }
+    }
+
+    /// The DMD window in pixels of an image of `(width, height)`, for a DMD of `dmd_size`
+    /// pixels at the [`DMDDefaultLocation`](super::DMDDefaultLocation).
+    ///
+    /// The window is clipped to the image. Returns `None` when the location is not a valid
+    /// number or the window lies outside the image.
+    pub fn dmd_area(&self, image_size: (u32, u32), dmd_size: (u32, u32)) -> Option<Area> {
+        let location = &self.dmd_default_location;
+        let dmd = Area {
+            x: location.loc_x_f32()?,
+            y: location.loc_y_f32()?,
+            width: dmd_size.0 as f32,
+            height: dmd_size.1 as f32,
+        };
+        let image = Area {
+            x: 0.0,
+            y: 0.0,
+            width: image_size.0 as f32,
+            height: image_size.1 as f32,
+        };
+        dmd.intersection(&image)
+    }
+
+    /// Maps a DMD on the backglass image to a screen of `(width, height)` like
+    /// [`Self::bulb_screen_area`] does, the DMD is usually in the grill that can be cut off.
+    ///
+    /// Returns `None` when the DMD is not visible.
+    pub fn dmd_screen_area(
+        &self,
+        image_size: (u32, u32),
+        dmd_size: (u32, u32),
+        grill: Grill,
+        screen_size: (u32, u32),
+    ) -> Option<Area> {
+        let visible = self.visible_backglass_area(image_size, grill);
+        let dmd = self
+            .dmd_area(image_size, dmd_size)?
+            .intersection(&visible)?;
+        let scale_x = screen_size.0 as f32 / visible.width;
+        let scale_y = screen_size.1 as f32 / visible.height;
+        Some(Area {
+            x: dmd.x * scale_x,
+            y: dmd.y * scale_y,
+            width: dmd.width * scale_x,
+            height: dmd.height * scale_y,
+        })
+    }
+
+    /// Maps a backglass bulb to a screen of `(width, height)` showing the visible area.
+    ///
+    /// Bulbs that are cut in half by the crop are clipped. Returns `None` for bulbs on the
+    /// DMD, bulbs without a valid location and bulbs that are cut off completely.
+    pub fn bulb_screen_area(
+        &self,
+        bulb: &Bulb,
+        image_size: (u32, u32),
+        grill: Grill,
+        screen_size: (u32, u32),
+    ) -> Option<Area> {
+        if bulb
+            .parent
+            .as_deref()
+            .is_some_and(|parent| !parent.eq_ignore_ascii_case("Backglass"))
+        {
+            return None;
+        }
+        let visible = self.visible_backglass_area(image_size, grill);
+        if visible.width <= 0.0 || visible.height <= 0.0 {
+            return None;
+        }
+        let x = bulb.loc_x_f32()?;
+        let y = bulb.loc_y_f32()?;
+        let width = bulb.width_f32()?;
+        let height = bulb.height_f32()?.min(visible.height - y);
+        if height <= 0.0 {
+            return None;
+        }
+        let scale_x = screen_size.0 as f32 / visible.width;
+        let scale_y = screen_size.1 as f32 / visible.height;
+        Some(Area {
+            x: x * scale_x,
+            y: y * scale_y,
+            width: width * scale_x,
+            height: height * scale_y,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directb2s::read;
+    use pretty_assertions::assert_eq;
+    use quick_xml::de::from_str;
+
+    const TEST_FILE: &str = "testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s";
+
+    fn bulb(parent: &str, loc_y: u32, height: u32) -> Bulb {
+        from_str(&format!(
+            r#"<Bulb Parent="{}" ID="1" Name="L1" InitialState="0" Intensity="1" DodgeColor="0.0.0" Visible="1" LocX="100" LocY="{}" Width="200" Height="{}" IsImageSnippit="0" Image="" Text="" TextAlignment="0" FontName="" FontSize="10" FontStyle="0"/>"#,
+            parent, loc_y, height
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn grill_cropping() {
+        let doc = std::fs::read_to_string(TEST_FILE).unwrap().replacen(
+            "<GrillHeight Value=\"0\"/>",
+            "<GrillHeight Value=\"300\" Small=\"100\"/>",
+            1,
+        );
+        let data = read(doc.as_bytes()).unwrap();
+        let image_size = (1000, 1000);
+
+        assert_eq!(data.grill_crop(Grill::Visible), 0);
+        assert_eq!(data.grill_crop(Grill::Small), 200);
+        assert_eq!(
+            data.visible_backglass_area(image_size, Grill::Hidden),
+            Area {
+                x: 0.0,
+                y: 0.0,
+                width: 1000.0,
+                height: 700.0,
+            }
+        );
+
+        let screen = (2000, 1400);
+        assert_eq!(
+            data.bulb_screen_area(
+                &bulb("Backglass", 650, 100),
+                image_size,
+                Grill::Hidden,
+                screen
+            ),
+            Some(Area {
+                x: 200.0,
+                y: 1300.0,
+                width: 400.0,
+                height: 100.0,
+            })
+        );
+        assert_eq!(
+            data.bulb_screen_area(
+                &bulb("Backglass", 750, 100),
+                image_size,
+                Grill::Hidden,
+                screen
+            ),
+            None
+        );
+        assert_eq!(
+            data.bulb_screen_area(&bulb("DMD", 0, 100), image_size, Grill::Visible, screen),
+            None
+        );
+    }
+
+    #[test]
+    fn dmd_placement() {
+        let doc = std::fs::read_to_string(TEST_FILE).unwrap().replacen(
+            "<DMDDefaultLocation LocX=\"0\" LocY=\"0\"/>",
+            "<DMDDefaultLocation LocX=\"200\" LocY=\"800\"/>",
+            1,
+        );
+        let data = read(doc.as_bytes()).unwrap();
+        let image_size = (1000, 1000);
+
+        let dmd = data.dmd_area(image_size, (512, 128)).unwrap();
+        assert_eq!(
+            dmd,
+            Area {
+                x: 200.0,
+                y: 800.0,
+                width: 512.0,
+                height: 128.0,
+            }
+        );
+        assert_eq!(
+            dmd.normalized(image_size),
+            Area {
+                x: 0.2,
+                y: 0.8,
+                width: 0.512,
+                height: 0.128,
+            }
+        );
+        // clipped to the image
+        assert_eq!(data.dmd_area(image_size, (1000, 500)).unwrap().width, 800.0);
+
+        assert_eq!(
+            data.dmd_screen_area(image_size, (512, 128), Grill::Visible, (500, 500)),
+            Some(Area {
+                x: 100.0,
+                y: 400.0,
+                width: 256.0,
+                height: 64.0,
+            })
+        );
+        let doc = doc.replacen(
+            "<GrillHeight Value=\"0\"/>",
+            "<GrillHeight Value=\"300\"/>",
+            1,
+        );
+        let data = read(doc.as_bytes()).unwrap();
+        assert_eq!(
+            data.dmd_screen_area(image_size, (512, 128), Grill::Hidden, (500, 500)),
+            None
+        );
+    }
+}
@@ -14,14 +14,102 @@
 //! println!("Author: {}", data.author.value);
 //! ```
 //!
+//! B2S Backglass Designer projects (`.b2s`) can be read with the [`project`] module.
+//!
 use std::fmt::Debug;
 use std::io::BufRead;
 
 use quick_xml::de::*;
 use quick_xml::se::*;
-use serde::{Deserialize, Serialize};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer as SerdeSerializer};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use encoded::Base64Data;
+
+pub mod encoded;
+pub mod geometry;
+pub mod optimize;
+pub mod player;
+pub mod project;
+pub mod timeline;
+mod typed;
+
+/// Attributes and elements that are not part of the model
+///
+/// Some backglass designers add their own attributes and elements. These are kept in
+/// document order so that writing a file does not lose anything. Attribute names are
+/// prefixed with `@`, element text is stored under `$text`. Unknown elements are written
+/// after the known ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Extra(pub Vec<(String, ExtraValue)>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ExtraValue {
+    /// An attribute value or the text of an element
+    Text(String),
+    Element(Extra),
+}
+
+impl Extra {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.0.iter().find_map(|(key, value)| match value {
+            ExtraValue::Text(text) if key.strip_prefix('@') == Some(name) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        let key = format!("@{}", name);
+        let value = ExtraValue::Text(value.to_string());
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key, value)),
+        }
+    }
+}
+
+impl Serialize for Extra {
+    fn serialize<S: SerdeSerializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Extra {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExtraVisitor;
+
+        impl<'de> Visitor<'de> for ExtraVisitor {
+            type Value = Extra;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("attributes and elements")
+            }
+
+            // a map that keeps the order and allows duplicate keys for repeated elements
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Extra, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = access.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Extra(entries))
+            }
+        }
+
+        deserializer.deserialize_map(ExtraVisitor)
+    }
+}
+
 // The xml model is based on this
 // https://github.com/vpinball/b2s-backglass/blob/f43ae8aacbb79d3413531991e4c0156264442c39/b2sbackglassdesigner/b2sbackglassdesigner/classes/CreateCode/Coding.vb#L30
 
@@ -29,45 +117,57 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 pub struct ValueTag {
     #[serde(rename = "@Value")]
     pub value: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ImageValueTag {
-    #[serde(rename = "@Value"/*, serialize_with = "as_str_encoded"*/)]
-    pub value: String,
+    #[serde(rename = "@Value")]
+    pub value: Base64Data,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DestTypeTag {
     #[serde(rename = "@Value")]
     pub value: DestType,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReelRollingDirectionTag {
     #[serde(rename = "@Value")]
     pub value: ReelRollingDirection,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DmdTypeTag {
     #[serde(rename = "@Value")]
     pub value: DMDType,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct ImageTag {
-    #[serde(rename = "@Value"/*, serialize_with = "as_str_encoded"*/)]
-    pub value: String,
+    #[serde(rename = "@Value")]
+    pub value: Base64Data,
     #[serde(rename = "@FileName")]
     pub file_name: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 // debug for ImageTag not showing length of value
 impl Debug for ImageTag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ImageTag")
-            .field("value", &format!("base64 {:?} bytes", self.value.len()))
+            .field("value", &self.value)
             .field("file_name", &self.file_name)
             .finish()
     }
@@ -76,20 +176,22 @@ impl Debug for ImageTag {
 #[derive(Deserialize, Serialize)]
 pub struct OnImageTag {
     #[serde(rename = "@Value")]
-    pub value: String,
+    pub value: Base64Data,
     #[serde(rename = "@FileName")]
     pub file_name: String,
     #[serde(rename = "@RomID", skip_serializing_if = "Option::is_none")]
     pub rom_id: Option<String>,
     #[serde(rename = "@RomIDType", skip_serializing_if = "Option::is_none")]
     pub rom_id_type: Option<RomIDType>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 // debug for ImageTag not showing length of value
 impl Debug for OnImageTag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("OnImageTag")
-            .field("value", &format!("base64 {:?} bytes", self.value.len()))
+            .field("value", &self.value)
             .field("file_name", &self.file_name)
             .finish()
     }
@@ -98,7 +200,7 @@ impl Debug for OnImageTag {
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Images {
     #[serde(rename = "BackglassOffImage", skip_serializing_if = "Option::is_none")]
-    pub backglass_off_image: Option<ValueTag>,
+    pub backglass_off_image: Option<ImageValueTag>,
     #[serde(rename = "BackglassOnImage", skip_serializing_if = "Option::is_none")]
     pub backglass_on_image: Option<OnImageTag>,
     #[serde(rename = "BackglassImage", skip_serializing_if = "Option::is_none")]
@@ -106,9 +208,11 @@ pub struct Images {
     #[serde(rename = "DMDImage", skip_serializing_if = "Option::is_none")]
     pub dmd_image: Option<ImageTag>,
     #[serde(rename = "IlluminationImage", skip_serializing_if = "Option::is_none")]
-    pub illumination_image: Option<ValueTag>,
+    pub illumination_image: Option<ImageValueTag>,
     #[serde(rename = "ThumbnailImage")]
     pub thumbnail_image: ImageValueTag,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -125,6 +229,8 @@ pub struct AnimationStep {
     pub wait_loops_after_off: String,
     #[serde(rename = "@PulseSwitch", skip_serializing_if = "Option::is_none")]
     pub pulse_switch: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -177,12 +283,16 @@ pub struct Animation {
     pub run_animation_til_end: Option<String>,
     #[serde(rename = "AnimationStep", skip_serializing_if = "Option::is_none")]
     pub animation_step: Option<Vec<AnimationStep>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Animations {
     #[serde(rename = "Animation", skip_serializing_if = "Option::is_none")]
     pub animation: Option<Vec<Animation>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -256,9 +366,9 @@ pub struct Bulb {
     #[serde(rename = "@SnippitType", skip_serializing_if = "Option::is_none")]
     pub snippit_type: Option<SnippitType>,
     #[serde(rename = "@Image")]
-    pub image: String,
+    pub image: Base64Data,
     #[serde(rename = "@OffImage", skip_serializing_if = "Option::is_none")]
-    pub off_image: Option<String>,
+    pub off_image: Option<Base64Data>,
     #[serde(rename = "@Text")]
     pub text: String,
     #[serde(rename = "@TextAlignment")]
@@ -269,6 +379,8 @@ pub struct Bulb {
     pub font_size: String,
     #[serde(rename = "@FontStyle")]
     pub font_style: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 // debug for Bulb not showing length of image
@@ -292,12 +404,13 @@ impl Debug for Bulb {
             .field("width", &self.width)
             .field("height", &self.height)
             .field("is_image_snippit", &self.is_image_snippit)
-            .field("image", &format!("base64 {:?} bytes", self.image.len()))
+            .field("image", &self.image)
             .field("text", &self.text)
             .field("text_alignment", &self.text_alignment)
             .field("font_name", &self.font_name)
             .field("font_size", &self.font_size)
             .field("font_style", &self.font_style)
+            .field("extra", &self.extra)
             .finish()
     }
 }
@@ -306,6 +419,8 @@ impl Debug for Bulb {
 pub struct Illumination {
     #[serde(rename = "Bulb", skip_serializing_if = "Option::is_none")]
     pub bulb: Option<Vec<Bulb>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -379,6 +494,8 @@ pub struct Score {
     pub sound9: Option<String>,
     #[serde(rename = "@Sound10", skip_serializing_if = "Option::is_none")]
     pub sound10: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -392,49 +509,97 @@ pub struct Scores {
 
     #[serde(rename = "Score", skip_serializing_if = "Option::is_none")]
     pub score: Option<Vec<Score>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug)]
 pub struct ReelsImage {
-    // TODO there might be dynamic fields here for IntermediateImage0, IntermediateImage1, etc.
-    #[serde(rename = "@Name")]
     pub name: String,
-    #[serde(rename = "@CountOfIntermediates")]
     pub count_of_intermediates: String,
+    pub image: Base64Data,
+    /// Images stored as `IntermediateImage1`, `IntermediateImage2`, ...
+    ///
+    /// The designer writes as many as `count_of_intermediates` says. Numbers that don't
+    /// continue the sequence are kept in `extra`.
+    pub intermediate_images: Vec<Base64Data>,
+    pub extra: Extra,
+}
+
+const INTERMEDIATE_IMAGE_PREFIX: &str = "@IntermediateImage";
+
+#[derive(Deserialize)]
+struct ReelsImageAttributes {
+    #[serde(rename = "@Name")]
+    name: String,
+    #[serde(rename = "@CountOfIntermediates")]
+    count_of_intermediates: String,
     #[serde(rename = "@Image")]
-    pub image: String,
-    // base64 encoded image
-    #[serde(
-        rename = "@IntermediateImage1",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub intermediate_image1: Option<String>,
-    #[serde(
-        rename = "@IntermediateImage2",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub intermediate_image2: Option<String>,
-    #[serde(
-        rename = "@IntermediateImage3",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub intermediate_image3: Option<String>,
-    #[serde(
-        rename = "@IntermediateImage4",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub intermediate_image4: Option<String>,
-    #[serde(
-        rename = "@IntermediateImage5",
-        skip_serializing_if = "Option::is_none"
-    )]
-    pub intermediate_image5: Option<String>,
+    image: Base64Data,
+    #[serde(flatten)]
+    extra: Extra,
+}
+
+impl<'de> Deserialize<'de> for ReelsImage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let attributes = ReelsImageAttributes::deserialize(deserializer)?;
+        let mut numbered = Vec::new();
+        let mut extra = Vec::new();
+        for (key, value) in attributes.extra.0 {
+            let number = key
+                .strip_prefix(INTERMEDIATE_IMAGE_PREFIX)
+                .and_then(|n| n.parse::<usize>().ok());
+            match (number, value) {
+                (Some(number), ExtraValue::Text(text)) => numbered.push((number, key, text)),
+                (_, value) => extra.push((key, value)),
+            }
+        }
+        numbered.sort_by_key(|(number, _, _)| *number);
+        let mut intermediate_images = Vec::new();
+        for (number, key, text) in numbered {
+            if number == intermediate_images.len() + 1 {
+                intermediate_images.push(Base64Data::from_base64_string(text));
+            } else {
+                extra.push((key, ExtraValue::Text(text)));
+            }
+        }
+        Ok(ReelsImage {
+            name: attributes.name,
+            count_of_intermediates: attributes.count_of_intermediates,
+            image: attributes.image,
+            intermediate_images,
+            extra: Extra(extra),
+        })
+    }
+}
+
+impl Serialize for ReelsImage {
+    fn serialize<S: SerdeSerializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(
+            3 + self.intermediate_images.len() + self.extra.0.len(),
+        ))?;
+        map.serialize_entry("@Name", &self.name)?;
+        map.serialize_entry("@CountOfIntermediates", &self.count_of_intermediates)?;
+        map.serialize_entry("@Image", &self.image)?;
+        for (index, image) in self.intermediate_images.iter().enumerate() {
+            map.serialize_entry(
+                &format!("{}{}", INTERMEDIATE_IMAGE_PREFIX, index + 1),
+                image,
+            )?;
+        }
+        for (key, value) in &self.extra.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReelsImages {
     #[serde(rename = "Image", skip_serializing_if = "Option::is_none")]
     pub image: Option<Vec<ReelsImage>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -443,12 +608,16 @@ pub struct ReelsIlluminatedImagesSet {
     pub id: String,
     #[serde(rename = "IlluminatedImage")]
     pub illuminated_image: Vec<ReelsImage>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReelsIlluminatedImages {
     #[serde(rename = "Set", skip_serializing_if = "Option::is_none")]
     pub set: Option<Vec<ReelsIlluminatedImagesSet>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -457,11 +626,45 @@ pub struct Reels {
     pub images: ReelsImages,
     #[serde(rename = "IlluminatedImages")]
     pub illuminated_images: ReelsIlluminatedImages,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A sound embedded by older versions of the designer, referenced by name from the scores
+#[derive(Deserialize, Serialize)]
+pub struct Sound {
+    #[serde(rename = "@Name")]
+    pub name: String,
+    /// base64 encoded wav
+    #[serde(rename = "@Stream", skip_serializing_if = "Option::is_none")]
+    pub stream: Option<Base64Data>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+// debug for Sound not showing the sound data
+impl Debug for Sound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sound")
+            .field("name", &self.name)
+            .field(
+                "stream",
+                &self
+                    .stream
+                    .as_ref()
+                    .map(|s| format!("base64 {:?} bytes", s.encoded_len())),
+            )
+            .finish()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Sounds {
-    // as far as I can see this is not in use
+    // only found in files made by older versions of the designer
+    #[serde(rename = "Sound", skip_serializing_if = "Option::is_none")]
+    pub sound: Option<Vec<Sound>>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -470,6 +673,8 @@ pub struct DMDDefaultLocation {
     pub loc_x: String,
     #[serde(rename = "@LocY")]
     pub loc_y: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -482,6 +687,8 @@ pub struct VRDMDLocation {
     pub width: String,
     #[serde(rename = "@Height")]
     pub height: String,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -490,6 +697,8 @@ pub struct GrillHeight {
     pub value: String,
     #[serde(rename = "@Small", skip_serializing_if = "Option::is_none")]
     pub small: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 /// Root data structure representing a directb2s file
@@ -565,30 +774,32 @@ pub struct DirectB2SData {
     pub sounds: Option<Sounds>,
     #[serde(rename = "Images")]
     pub images: Images,
+    #[serde(flatten)]
+    pub extra: Extra,
 }
 
 impl DirectB2SData {
     /// Replaces image data with "\[stripped\]" for the whole structure
     pub fn strip_images(&mut self) {
         self.images.backglass_image.iter_mut().for_each(|i| {
-            i.value = "[stripped]".to_string();
+            i.value = stripped();
         });
         self.images.dmd_image.iter_mut().for_each(|i| {
-            i.value = "[stripped]".to_string();
+            i.value = stripped();
         });
         self.images.backglass_off_image.iter_mut().for_each(|i| {
-            i.value = "[stripped]".to_string();
+            i.value = stripped();
         });
         self.images.backglass_on_image.iter_mut().for_each(|i| {
-            i.value = "[stripped]".to_string();
+            i.value = stripped();
         });
         self.images.illumination_image.iter_mut().for_each(|i| {
-            i.value = "[stripped]".to_string();
+            i.value = stripped();
         });
-        self.images.thumbnail_image.value = "[stripped]".to_string();
+        self.images.thumbnail_image.value = stripped();
         self.illumination.bulb.iter_mut().for_each(|bulbs| {
             bulbs.iter_mut().for_each(|b| {
-                b.image = "[stripped]".to_string();
+                b.image = stripped();
             });
         });
         self.reels.as_mut().iter_mut().for_each(|reels| {
@@ -605,16 +816,18 @@ impl DirectB2SData {
 
     fn strip_reels_images(images: &mut [ReelsImage]) {
         images.iter_mut().for_each(|i| {
-            i.image = "[stripped]".to_string();
-            i.intermediate_image1 = Some("[stripped]".to_string());
-            i.intermediate_image2 = Some("[stripped]".to_string());
-            i.intermediate_image3 = Some("[stripped]".to_string());
-            i.intermediate_image4 = Some("[stripped]".to_string());
-            i.intermediate_image5 = Some("[stripped]".to_string());
+            i.image = stripped();
+            i.intermediate_images
+                .iter_mut()
+                .for_each(|image| *image = stripped());
         });
     }
 }
 
+fn stripped() -> Base64Data {
+    Base64Data::Text("[stripped]".to_string())
+}
+
 pub fn read<R: BufRead>(reader: R) -> Result<DirectB2SData, DeError> {
     from_reader(reader)
 }
@@ -623,7 +836,8 @@ pub fn write<W: std::fmt::Write>(
     data: &DirectB2SData,
     writer: &mut W,
 ) -> Result<WriteResult, SeError> {
-    let mut ser = Serializer::new(writer);
+    // the flattened extra fields make the root a map, which has no name of its own
+    let mut ser = Serializer::with_root(writer, Some("DirectB2SData"))?;
     ser.indent(' ', 2);
     data.serialize(ser)
 }
@@ -799,3 +1013,140 @@ pub enum ReelRollingDirection {
     Up = 0,
     Down = 1,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const TEST_FILE: &str = "testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s";
+
+    fn with_unknowns(doc: &str) -> String {
+        doc.replacen(
+            "<DirectB2SData Version=\"1.2\">",
+            "<DirectB2SData Version=\"1.2\" Designer=\"someone\">",
+            1,
+        )
+        .replacen(
+            "<DMDDefaultLocation LocX=\"0\" LocY=\"0\"/>",
+            "<DMDDefaultLocation LocX=\"0\" LocY=\"0\" Scale=\"2\"/>",
+            1,
+        )
+        .replacen(
+            "</DirectB2SData>",
+            "<Custom Value=\"a\"><Nested Id=\"1\"/></Custom><Custom Value=\"b\"/><Note>hello</Note></DirectB2SData>",
+            1,
+        )
+    }
+
+    #[test]
+    fn read_unknown_attributes_and_elements() {
+        let doc = with_unknowns(&std::fs::read_to_string(TEST_FILE).unwrap());
+        let data = read(doc.as_bytes()).unwrap();
+
+        assert_eq!(data.extra.attribute("Designer"), Some("someone"));
+        assert_eq!(
+            data.dmd_default_location.extra.attribute("Scale"),
+            Some("2")
+        );
+        let keys = data
+            .extra
+            .0
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["@Designer", "Custom", "Custom", "Note"]);
+        assert!(data.name.extra.is_empty());
+    }
+
+    #[test]
+    fn write_unknown_attributes_and_elements() {
+        let doc = with_unknowns(&std::fs::read_to_string(TEST_FILE).unwrap());
+        let mut data = read(doc.as_bytes()).unwrap();
+        data.name.extra.set_attribute("Comment", "added");
+
+        let mut written = String::new();
+        write(&data, &mut written).unwrap();
+
+        assert!(written.starts_with("<DirectB2SData Version=\"1.2\" Designer=\"someone\">"));
+        assert!(written.contains("<DMDDefaultLocation LocX=\"0\" LocY=\"0\" Scale=\"2\"/>"));
+        assert!(written.contains("Comment=\"added\""));
+        assert!(written.contains("<Custom Value=\"a\">\n    <Nested Id=\"1\"/>\n  </Custom>"));
+        assert!(written.contains("<Custom Value=\"b\"/>"));
+        assert!(written.contains("<Note>hello</Note>"));
+
+        // a second round trip does not change anything
+        let reread = read(written.as_bytes()).unwrap();
+        let mut rewritten = String::new();
+        write(&reread, &mut rewritten).unwrap();
+        assert_eq!(written, rewritten);
+    }
+
+    #[test]
+    fn read_write_sounds() {
+        let doc = std::fs::read_to_string(TEST_FILE).unwrap().replacen(
+            "</DirectB2SData>",
+            "<Sounds><Sound Name=\"bell\" Stream=\"UklGRg==\"/><Sound Name=\"chime\"/></Sounds></DirectB2SData>",
+            1,
+        );
+        let data = read(doc.as_bytes()).unwrap();
+
+        let sounds = data.sounds.as_ref().unwrap().sound.as_ref().unwrap();
+        assert_eq!(sounds.len(), 2);
+        assert_eq!(sounds[0].name, "bell");
+        assert_eq!(
+            sounds[0].stream.as_ref().and_then(|s| s.bytes()),
+            Some(&b"RIFF"[..])
+        );
+        assert_eq!(sounds[1].stream, None);
+
+        let mut written = String::new();
+        write(&data, &mut written).unwrap();
+        assert!(written.contains(
+            "<Sounds>\n    <Sound Name=\"bell\" Stream=\"UklGRg==\"/>\n    <Sound Name=\"chime\"/>\n  </Sounds>"
+        ));
+    }
+
+    #[test]
+    fn reels_image_intermediates() {
+        let xml = r#"<Image Name="EMR_T1_0" CountOfIntermediates="7" Image="a" IntermediateImage2="c" IntermediateImage1="b" IntermediateImage3="d" IntermediateImage4="e" IntermediateImage5="f" IntermediateImage6="g" IntermediateImage7="h" IntermediateImage9="j"/>"#;
+        let image: ReelsImage = from_str(xml).unwrap();
+
+        let intermediates: Vec<String> = image
+            .intermediate_images
+            .iter()
+            .map(Base64Data::to_base64)
+            .collect();
+        assert_eq!(intermediates, vec!["b", "c", "d", "e", "f", "g", "h"]);
+        assert_eq!(image.extra.attribute("IntermediateImage9"), Some("j"));
+
+        let mut written = String::new();
+        image
+            .serialize(Serializer::with_root(&mut written, Some("Image")).unwrap())
+            .unwrap();
+        assert_eq!(
+            written,
+            r#"<Image Name="EMR_T1_0" CountOfIntermediates="7" Image="a" IntermediateImage1="b" IntermediateImage2="c" IntermediateImage3="d" IntermediateImage4="e" IntermediateImage5="f" IntermediateImage6="g" IntermediateImage7="h" IntermediateImage9="j"/>"#
+        );
+    }
+
+    #[test]
+    fn normalize_decimal_separators() {
+        let doc = std::fs::read_to_string(TEST_FILE)
+            .unwrap()
+            .replacen(
+                "<DMDDefaultLocation LocX=\"0\" LocY=\"0\"/>",
+                "<DMDDefaultLocation LocX=\"10,5\" LocY=\"0\"/>",
+                1,
+            )
+            .replacen("LocX=\"272\"", "LocX=\"272,25\"", 1);
+        let mut data = read(doc.as_bytes()).unwrap();
+        data.normalize_decimal_separators();
+
+        assert_eq!(data.dmd_default_location.loc_x, "10.5");
+        let mut written = String::new();
+        write(&data, &mut written).unwrap();
+        assert!(written.contains("LocX=\"272.25\""));
+        assert!(!written.contains("LocX=\"272,25\""));
+    }
+}
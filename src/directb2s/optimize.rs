@@ -0,0 +1,324 @@
+//! Making backglass files smaller by recompressing their images
+//!
+//! The images of a backglass are stored base64 encoded in the xml, often as uncompressed BMP
+//! or as PNG with little compression, which makes backglass files of 100MB and more common.
+//! [`optimize`] re-encodes PNG and BMP images as PNG with the best compression, using a
+//! palette for images with at most 256 colors and leaving out the alpha channel of opaque
+//! images. The pixels stay exactly the same. JPEG images are left as they are.
+//!
+//! The B2S server can not show WebP images, [`OptimizeOptions::webp`] is only useful for
+//! other players.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::io;
+//! use vpin::directb2s;
+//! use vpin::directb2s::optimize::{optimize, OptimizeOptions};
+//!
+//! let file = std::fs::File::open("table.directb2s").unwrap();
+//! let mut data = directb2s::read(io::BufReader::new(file)).unwrap();
+//! let report = optimize(&mut data, &OptimizeOptions::default());
+//! println!("saved {} bytes", report.saved());
+//! ```
+
+use super::encoded::Base64Data;
+use super::{DirectB2SData, ReelsImage};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::codecs::webp::WebPEncoder;
+use image::{ImageFormat, RgbaImage};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OptimizeOptions {
+    /// Also try lossless WebP, not supported by the B2S server
+    pub webp: bool,
+}
+
+/// An image that was replaced by a smaller encoding
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImageSaving {
+    /// Where the image is used, eg `ThumbnailImage` or `Bulb L60`
+    pub location: String,
+    pub original_format: ImageFormat,
+    pub format: ImageFormat,
+    /// Size of the decoded image data in bytes
+    pub original_size: usize,
+    pub optimized_size: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OptimizeReport {
+    /// The images that got smaller, the others are left unchanged
+    pub images: Vec<ImageSaving>,
+}
+
+impl OptimizeReport {
+    /// Bytes saved on the decoded images, the base64 encoding in the file is a third larger
+    pub fn saved(&self) -> usize {
+        self.images
+            .iter()
+            .map(|image| image.original_size - image.optimized_size)
+            .sum()
+    }
+}
+
+/// Re-encodes the PNG and BMP images that can be stored smaller, see the [module](self) docs.
+///
+/// Values that can not be decoded, like stripped images, are left as they are.
+pub fn optimize(data: &mut DirectB2SData, options: &OptimizeOptions) -> OptimizeReport {
+    let mut report = OptimizeReport::default();
+    for (location, value) in image_values(data) {
+        if let Some(saving) = optimize_value(location, value, options) {
+            report.images.push(saving);
+        }
+    }
+    report
+}
+
+/// Every base64 encoded image in the backglass with its location
+fn image_values(data: &mut DirectB2SData) -> Vec<(String, &mut Base64Data)> {
+    let mut values = Vec::new();
+    let images = &mut data.images;
+    if let Some(image) = &mut images.backglass_off_image {
+        values.push(("BackglassOffImage".to_string(), &mut image.value));
+    }
+    if let Some(image) = &mut images.backglass_on_image {
+        values.push(("BackglassOnImage".to_string(), &mut image.value));
+    }
+    if let Some(image) = &mut images.backglass_image {
+        values.push(("BackglassImage".to_string(), &mut image.value));
+    }
+    if let Some(image) = &mut images.dmd_image {
+        values.push(("DMDImage".to_string(), &mut image.value));
+    }
+    if let Some(image) = &mut images.illumination_image {
+        values.push(("IlluminationImage".to_string(), &mut image.value));
+    }
+    values.push((
+        "ThumbnailImage".to_string(),
+        &mut images.thumbnail_image.value,
+    ));
+    for bulb in data.illumination.bulb.iter_mut().flatten() {
+        values.push((format!("Bulb {}", bulb.name), &mut bulb.image));
+    }
+    if let Some(reels) = &mut data.reels {
+        for image in reels.images.image.iter_mut().flatten() {
+            push_reel_values(&mut values, "Reel", image);
+        }
+        for set in reels.illuminated_images.set.iter_mut().flatten() {
+            let prefix = format!("Reel set {}", set.id);
+            for image in &mut set.illuminated_image {
+                push_reel_values(&mut values, &prefix, image);
+            }
+        }
+    }
+    values
+}
+
+fn push_reel_values<'a>(
+    values: &mut Vec<(String, &'a mut Base64Data)>,
+    prefix: &str,
+    image: &'a mut ReelsImage,
+) {
+    let name = &image.name;
+    for (i, intermediate) in image.intermediate_images.iter_mut().enumerate() {
+        values.push((
+            format!("{} {} intermediate {}", prefix, name, i + 1),
+            intermediate,
+        ));
+    }
+    values.push((format!("{} {}", prefix, name), &mut image.image));
+}
+
+fn optimize_value(
+    location: String,
+    value: &mut Base64Data,
+    options: &OptimizeOptions,
+) -> Option<ImageSaving> {
+    let decoded;
+    let original = match value {
+        Base64Data::Decoded(bytes) => bytes.as_slice(),
+        Base64Data::Text(text) => {
+            let encoded: String = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+            decoded = STANDARD.decode(encoded).ok()?;
+            decoded.as_slice()
+        }
+    };
+    let original_format = image::guess_format(original).ok()?;
+    if !matches!(original_format, ImageFormat::Png | ImageFormat::Bmp) {
+        return None;
+    }
+    let rgba = image::load_from_memory_with_format(original, original_format)
+        .ok()?
+        .to_rgba8();
+    let mut candidates = vec![(ImageFormat::Png, encode_png(&rgba)?)];
+    if options.webp {
+        let mut webp = Vec::new();
+        if WebPEncoder::new_lossless(&mut webp)
+            .encode(
+                rgba.as_raw(),
+                rgba.width(),
+                rgba.height(),
+                image::ExtendedColorType::Rgba8,
+            )
+            .is_ok()
+        {
+            candidates.push((ImageFormat::WebP, webp));
+        }
+    }
+    let (format, optimized) = candidates.into_iter().min_by_key(|(_, data)| data.len())?;
+    if optimized.len() >= original.len() {
+        return None;
+    }
+    let saving = ImageSaving {
+        location,
+        original_format,
+        format,
+        original_size: original.len(),
+        optimized_size: optimized.len(),
+    };
+    *value = Base64Data::from_bytes(optimized);
+    Some(saving)
+}
+
+/// A palette PNG if there are at most 256 colors, otherwise RGB or RGBA if there is any
+/// transparency
+fn encode_png(rgba: &RgbaImage) -> Option<Vec<u8>> {
+    let opaque = rgba.pixels().all(|p| p[3] == u8::MAX);
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, rgba.width(), rgba.height());
+    encoder.set_compression(png::Compression::High);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data = match palette(rgba) {
+        Some((colors, indices)) => {
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_palette(
+                colors
+                    .iter()
+                    .flat_map(|c| [c[0], c[1], c[2]])
+                    .collect::<Vec<_>>(),
+            );
+            if !opaque {
+                encoder.set_trns(colors.iter().map(|c| c[3]).collect::<Vec<_>>());
+            }
+            indices
+        }
+        None if opaque => {
+            encoder.set_color(png::ColorType::Rgb);
+            rgba.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect()
+        }
+        None => {
+            encoder.set_color(png::ColorType::Rgba);
+            rgba.as_raw().clone()
+        }
+    };
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(&data).ok()?;
+    writer.finish().ok()?;
+    Some(png)
+}
+
+/// The colors of the image and the color index of every pixel, `None` if there are more than
+/// 256 colors
+fn palette(rgba: &RgbaImage) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    let mut colors: Vec<[u8; 4]> = Vec::new();
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(rgba.len() / 4);
+    for pixel in rgba.pixels() {
+        let index = match lookup.get(&pixel.0) {
+            Some(index) => *index,
+            None => {
+                let index = u8::try_from(colors.len()).ok()?;
+                colors.push(pixel.0);
+                lookup.insert(pixel.0, index);
+                index
+            }
+        };
+        indices.push(index);
+    }
+    Some((colors, indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directb2s::read;
+    use image::{DynamicImage, Rgba};
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    fn encode(image: &RgbaImage, format: ImageFormat) -> Base64Data {
+        let mut data = Cursor::new(Vec::new());
+        let image = match format {
+            ImageFormat::Bmp => {
+                DynamicImage::ImageRgb8(DynamicImage::from(image.clone()).to_rgb8())
+            }
+            _ => DynamicImage::ImageRgba8(image.clone()),
+        };
+        image.write_to(&mut data, format).unwrap();
+        Base64Data::from_bytes(data.into_inner())
+    }
+
+    fn decode(value: &Base64Data) -> (ImageFormat, RgbaImage) {
+        let data = value.bytes().unwrap();
+        let format = image::guess_format(data).unwrap();
+        (format, image::load_from_memory(data).unwrap().to_rgba8())
+    }
+
+    #[test]
+    fn recompress_images() {
+        let file = "testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s";
+        let mut data = read(std::fs::read(file).unwrap().as_slice()).unwrap();
+
+        // few colors, stored as uncompressed bmp
+        let thumbnail = RgbaImage::from_fn(64, 64, |x, _| {
+            if x < 32 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        });
+        data.images.thumbnail_image.value = encode(&thumbnail, ImageFormat::Bmp);
+        // many colors with transparency
+        let bulb_image =
+            RgbaImage::from_fn(64, 64, |x, y| Rgba([x as u8 * 4, y as u8 * 4, 0, 128]));
+        let bulb = &mut data.illumination.bulb.as_mut().unwrap()[0];
+        bulb.image = encode(&bulb_image, ImageFormat::Png);
+        let bulb_name = bulb.name.clone();
+
+        let report = optimize(&mut data, &OptimizeOptions::default());
+
+        let locations: Vec<&str> = report
+            .images
+            .iter()
+            .map(|saving| saving.location.as_str())
+            .collect();
+        assert!(locations.contains(&"ThumbnailImage"));
+        assert!(report.saved() > 0);
+        let thumbnail_saving = &report.images[0];
+        assert_eq!(thumbnail_saving.original_format, ImageFormat::Bmp);
+        assert_eq!(thumbnail_saving.format, ImageFormat::Png);
+        assert_eq!(
+            decode(&data.images.thumbnail_image.value),
+            (ImageFormat::Png, thumbnail)
+        );
+        let bulb = &data.illumination.bulb.as_ref().unwrap()[0];
+        assert_eq!(bulb.name, bulb_name);
+        assert_eq!(decode(&bulb.image).1, bulb_image);
+        // the stripped images are left alone
+        assert_eq!(
+            data.images.backglass_image.as_ref().map(|i| &i.value),
+            Some(&Base64Data::from_base64("[stripped]"))
+        );
+    }
+
+    #[test]
+    fn palette_limit() {
+        let few = RgbaImage::from_fn(16, 16, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        assert_eq!(palette(&few).map(|(colors, _)| colors.len()), Some(256));
+        let many = RgbaImage::from_fn(17, 16, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        assert_eq!(palette(&many), None);
+    }
+}
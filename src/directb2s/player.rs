@@ -0,0 +1,656 @@
+//! Computing which backglass bulbs are lit while a table is running
+//!
+//! A [`BackglassPlayer`] is fed with the lamp, solenoid, GI and B2S data changes coming from
+//! the table or rom. It keeps track of the running animations and tells which bulbs should be
+//! visible, respecting the `DualMode`, `IlluMode`, `RomInverted` and `Visible` settings the
+//! same way the B2S server does.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use vpin::directb2s;
+//! use vpin::directb2s::player::{BackglassMode, BackglassPlayer};
+//!
+//! let file = std::fs::File::open("testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s").unwrap();
+//! let data = directb2s::read(io::BufReader::new(file)).unwrap();
+//! let mut player = BackglassPlayer::new(&data, BackglassMode::Authentic);
+//! player.set_lamp(60, true);
+//! player.tick(20);
+//! for bulb in player.visible_bulbs() {
+//!     println!("{} is lit", bulb.name);
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use super::{Animation, B2SIDType, Bulb, DirectB2SData, DualMode, RomIDType};
+
+/// Which bulbs of a dual mode backglass are used
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BackglassMode {
+    Authentic,
+    Fantasy,
+}
+
+/// How a bulb is rendered
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IlluMode {
+    Standard,
+    /// Rendered on top of the standard bulbs
+    Flasher,
+    Other(u8),
+}
+
+impl From<Option<&String>> for IlluMode {
+    fn from(value: Option<&String>) -> Self {
+        match value.and_then(|v| v.parse::<u8>().ok()) {
+            None | Some(0) => IlluMode::Standard,
+            Some(1) => IlluMode::Flasher,
+            Some(other) => IlluMode::Other(other),
+        }
+    }
+}
+
+/// Which backglass image should be shown
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BackglassImage {
+    /// The regular backglass image
+    Default,
+    /// The `BackglassOnImage`, its rom id is active
+    On,
+    /// The `BackglassOffImage`, the `BackglassOnImage` exists but its rom id is not active
+    Off,
+}
+
+#[derive(Debug)]
+struct BulbState {
+    /// State driven by the rom or B2S data
+    on: bool,
+    /// State forced by a running animation
+    animated: Option<bool>,
+    /// Set while an animation with `LockInvolvedLamps` runs
+    locked: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Phase {
+    On,
+    Off,
+}
+
+#[derive(Debug)]
+struct RunningAnimation {
+    index: usize,
+    step: usize,
+    phase: Phase,
+    /// Intervals left to wait before the next phase
+    wait: u32,
+    /// Milliseconds since the last interval
+    elapsed: u32,
+    loops_done: u32,
+}
+
+/// A running backglass, see the [module documentation](self)
+pub struct BackglassPlayer<'a> {
+    data: &'a DirectB2SData,
+    mode: BackglassMode,
+    bulbs: Vec<&'a Bulb>,
+    states: Vec<BulbState>,
+    lamps: HashMap<u32, bool>,
+    solenoids: HashMap<u32, bool>,
+    gi_strings: HashMap<u32, bool>,
+    b2s_data: HashMap<u32, i32>,
+    animations: Vec<RunningAnimation>,
+}
+
+impl<'a> BackglassPlayer<'a> {
+    /// Creates a player with all bulbs in their initial state. Animations that are configured
+    /// to start with the backglass are started.
+    pub fn new(data: &'a DirectB2SData, mode: BackglassMode) -> Self {
+        let mut player = Self::without_startup_animations(data, mode);
+        let startup: Vec<String> = player
+            .all_animations()
+            .iter()
+            .filter(|a| a.start_animation_at_backglass_startup_bool() == Some(true))
+            .map(|a| a.name.clone())
+            .collect();
+        for name in startup {
+            player.start_animation(&name);
+        }
+        player
+    }
+
+    pub(crate) fn without_startup_animations(data: &'a DirectB2SData, mode: BackglassMode) -> Self {
+        let bulbs: Vec<&Bulb> = data.illumination.bulb.iter().flatten().collect();
+        let states = bulbs
+            .iter()
+            .map(|bulb| BulbState {
+                on: bulb.initial_state_on(),
+                animated: None,
+                locked: false,
+            })
+            .collect();
+        BackglassPlayer {
+            data,
+            mode,
+            bulbs,
+            states,
+            lamps: HashMap::new(),
+            solenoids: HashMap::new(),
+            gi_strings: HashMap::new(),
+            b2s_data: HashMap::new(),
+            animations: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> BackglassMode {
+        self.mode
+    }
+
+    /// The last value set with [`BackglassPlayer::set_data`], 0 if never set
+    pub fn get_data(&self, id: u32) -> i32 {
+        self.b2s_data.get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn set_lamp(&mut self, id: u32, on: bool) {
+        self.lamps.insert(id, on);
+        self.update_rom(RomIDType::Lamp, id, on);
+        self.update_joined_animations('L', id, on);
+    }
+
+    pub fn set_solenoid(&mut self, id: u32, on: bool) {
+        self.solenoids.insert(id, on);
+        self.update_rom(RomIDType::Solenoid, id, on);
+        self.update_joined_animations('S', id, on);
+    }
+
+    /// GI strings are on for any value above 0
+    pub fn set_gi_string(&mut self, id: u32, value: u8) {
+        let on = value > 0;
+        self.gi_strings.insert(id, on);
+        self.update_rom(RomIDType::GIString, id, on);
+        self.update_joined_animations('G', id, on);
+    }
+
+    /// Sets B2S data, like the table script does with `B2SSetData`.
+    ///
+    /// Bulbs with a `B2SValue` are lit when the data equals that value, other bulbs are lit
+    /// for any value that is not 0.
+    pub fn set_data(&mut self, id: u32, value: i32) {
+        self.b2s_data.insert(id, value);
+        for i in 0..self.bulbs.len() {
+            let bulb = self.bulbs[i];
+            if b2s_id(bulb) == Some(id) {
+                let expected = bulb.b2s_value_i32().unwrap_or(0);
+                let on = if expected > 0 {
+                    value == expected
+                } else {
+                    value != 0
+                };
+                self.set_bulb(i, on);
+            }
+        }
+    }
+
+    pub fn start_animation(&mut self, name: &str) {
+        let Some(index) = self
+            .all_animations()
+            .iter()
+            .position(|a| a.name.eq_ignore_ascii_case(name))
+        else {
+            return;
+        };
+        if self.animations.iter().any(|a| a.index == index) {
+            return;
+        }
+        let animation = &self.all_animations()[index];
+        if !self.is_active_in_mode(animation.dual_mode.as_ref()) {
+            return;
+        }
+        let involved = self.involved_bulbs(animation);
+        if animation.all_lights_off_at_animation_start_bool() {
+            for state in self.states.iter_mut() {
+                state.animated = Some(false);
+            }
+        }
+        let lock = animation.lock_involved_lamps_bool() == Some(true);
+        let start_state = light_state(animation.lights_state_at_animation_start.as_deref());
+        for i in involved {
+            let state = &mut self.states[i];
+            state.locked |= lock;
+            if let Some(on) = start_state {
+                state.animated = Some(on);
+            }
+        }
+        let mut running = RunningAnimation {
+            index,
+            step: 0,
+            phase: Phase::On,
+            wait: 0,
+            elapsed: 0,
+            loops_done: 0,
+        };
+        self.apply_phase(&mut running);
+        self.animations.push(running);
+    }
+
+    pub fn stop_animation(&mut self, name: &str) {
+        let Some(pos) = self.animations.iter().position(|a| {
+            self.all_animations()[a.index]
+                .name
+                .eq_ignore_ascii_case(name)
+        }) else {
+            return;
+        };
+        let running = self.animations.remove(pos);
+        self.finish_animation(running.index);
+    }
+
+    /// How many times a running animation went through all its steps
+    pub(crate) fn loops_done(&self, name: &str) -> Option<u32> {
+        self.animations
+            .iter()
+            .find(|a| {
+                self.all_animations()[a.index]
+                    .name
+                    .eq_ignore_ascii_case(name)
+            })
+            .map(|a| a.loops_done)
+    }
+
+    /// Ids of the bulbs switched by an animation
+    pub(crate) fn animation_bulb_ids(&self, animation: &Animation) -> Vec<&'a str> {
+        self.involved_bulbs(animation)
+            .into_iter()
+            .map(|i| self.bulbs[i].id.as_str())
+            .collect()
+    }
+
+    pub fn is_animation_running(&self, name: &str) -> bool {
+        self.animations.iter().any(|a| {
+            self.all_animations()[a.index]
+                .name
+                .eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Advances the running animations
+    pub fn tick(&mut self, elapsed_ms: u32) {
+        let mut running = std::mem::take(&mut self.animations);
+        let mut finished = Vec::new();
+        for animation in running.iter_mut() {
+            let interval = self.all_animations()[animation.index]
+                .interval_u32()
+                .unwrap_or(0)
+                .max(1);
+            animation.elapsed += elapsed_ms;
+            while animation.elapsed >= interval {
+                animation.elapsed -= interval;
+                if animation.wait > 0 {
+                    animation.wait -= 1;
+                    continue;
+                }
+                if !self.advance(animation) {
+                    finished.push(animation.index);
+                    break;
+                }
+            }
+        }
+        running.retain(|a| !finished.contains(&a.index));
+        self.animations = running;
+        for index in finished {
+            self.finish_animation(index);
+        }
+    }
+
+    /// Whether a bulb is lit, not taking into account if it is used in the current mode
+    pub fn is_bulb_on(&self, id: &str) -> bool {
+        self.bulbs
+            .iter()
+            .position(|b| b.id == id)
+            .is_some_and(|i| self.bulb_on(i))
+    }
+
+    /// The bulbs that should be drawn, in drawing order.
+    ///
+    /// Bulbs are sorted by their `ZOrder`, flashers are drawn on top of standard bulbs.
+    pub fn visible_bulbs(&self) -> Vec<&'a Bulb> {
+        let mut visible: Vec<&Bulb> = (0..self.bulbs.len())
+            .filter(|i| self.bulb_on(*i))
+            .map(|i| self.bulbs[i])
+            .filter(|bulb| bulb.visible_bool() != Some(false))
+            .filter(|bulb| self.is_active_in_mode(bulb.dual_mode.as_ref()))
+            .collect();
+        visible.sort_by_key(|bulb| {
+            let flasher = IlluMode::from(bulb.illu_mode.as_ref()) == IlluMode::Flasher;
+            (flasher, bulb.z_order_i32().unwrap_or(0))
+        });
+        visible
+    }
+
+    pub fn backglass_image(&self) -> BackglassImage {
+        let Some(on_image) = &self.data.images.backglass_on_image else {
+            return BackglassImage::Default;
+        };
+        let rom_id = on_image
+            .rom_id
+            .as_ref()
+            .and_then(|id| id.parse::<u32>().ok());
+        let active = match (&on_image.rom_id_type, rom_id) {
+            (Some(rom_id_type), Some(id)) => self.rom_state(rom_id_type, id),
+            _ => false,
+        };
+        if active {
+            BackglassImage::On
+        } else if self.data.images.backglass_off_image.is_some() {
+            BackglassImage::Off
+        } else {
+            BackglassImage::Default
+        }
+    }
+
+    fn all_animations(&self) -> &'a [Animation] {
+        self.data
+            .animations
+            .animation
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    fn is_active_in_mode(&self, dual_mode: Option<&DualMode>) -> bool {
+        matches!(
+            (dual_mode, self.mode),
+            (None | Some(DualMode::Both), _)
+                | (Some(DualMode::Authentic), BackglassMode::Authentic)
+                | (Some(DualMode::Fantasy), BackglassMode::Fantasy)
+        )
+    }
+
+    fn bulb_on(&self, index: usize) -> bool {
+        let state = &self.states[index];
+        state.animated.unwrap_or(state.on)
+    }
+
+    fn set_bulb(&mut self, index: usize, on: bool) {
+        let state = &mut self.states[index];
+        state.on = on;
+        if !state.locked {
+            state.animated = None;
+        }
+    }
+
+    fn rom_state(&self, rom_id_type: &RomIDType, id: u32) -> bool {
+        let states = match rom_id_type {
+            RomIDType::Lamp => &self.lamps,
+            RomIDType::Solenoid => &self.solenoids,
+            RomIDType::GIString => &self.gi_strings,
+            _ => return false,
+        };
+        states.get(&id).copied().unwrap_or(false)
+    }
+
+    fn update_rom(&mut self, rom_id_type: RomIDType, id: u32, on: bool) {
+        for i in 0..self.bulbs.len() {
+            let bulb = self.bulbs[i];
+            let matches =
+                bulb.rom_id_type.as_ref() == Some(&rom_id_type) && bulb.rom_id_u32() == Some(id);
+            if matches {
+                let inverted = bulb.rom_inverted_bool();
+                self.set_bulb(i, on != inverted);
+            }
+        }
+    }
+
+    /// Animations with an `IDJoin` like `L12` or `S5,G1` run while one of the joined lamps,
+    /// solenoids or GI strings is on.
+    fn update_joined_animations(&mut self, prefix: char, id: u32, on: bool) {
+        let joined: Vec<String> = self
+            .all_animations()
+            .iter()
+            .filter(|a| {
+                a.id_join.split(',').any(|join| {
+                    let join = join.trim();
+                    join.chars()
+                        .next()
+                        .is_some_and(|c| c.eq_ignore_ascii_case(&prefix))
+                        && join[1..].parse::<u32>().ok() == Some(id)
+                })
+            })
+            .map(|a| a.name.clone())
+            .collect();
+        for name in joined {
+            if on {
+                self.start_animation(&name);
+            } else {
+                self.stop_animation(&name);
+            }
+        }
+    }
+
+    fn involved_bulbs(&self, animation: &Animation) -> Vec<usize> {
+        let mut involved = Vec::new();
+        for step in animation.animation_step.iter().flatten() {
+            for i in self.step_bulbs(&step.on).chain(self.step_bulbs(&step.off)) {
+                if !involved.contains(&i) {
+                    involved.push(i);
+                }
+            }
+        }
+        involved
+    }
+
+    /// Bulbs in a step are listed by id or name, comma separated
+    fn step_bulbs<'s>(&'s self, list: &'s str) -> impl Iterator<Item = usize> + 's {
+        list.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| {
+                self.bulbs
+                    .iter()
+                    .position(|b| b.id == s)
+                    .or_else(|| self.bulbs.iter().position(|b| b.name == s))
+            })
+    }
+
+    fn apply_phase(&mut self, running: &mut RunningAnimation) {
+        let animation = &self.all_animations()[running.index];
+        let Some(step) = animation
+            .animation_step
+            .as_ref()
+            .and_then(|steps| steps.get(running.step))
+        else {
+            return;
+        };
+        let (list, wait) = match running.phase {
+            Phase::On => (&step.on, step.wait_loops_after_on_u32()),
+            Phase::Off => (&step.off, step.wait_loops_after_off_u32()),
+        };
+        let lit = running.phase == Phase::On;
+        let bulbs: Vec<usize> = self.step_bulbs(list).collect();
+        for i in bulbs {
+            self.states[i].animated = Some(lit);
+        }
+        running.wait = wait.unwrap_or(0);
+    }
+
+    /// Moves to the next phase, returns false when the animation is done
+    fn advance(&mut self, running: &mut RunningAnimation) -> bool {
+        let animation = &self.all_animations()[running.index];
+        let step_count = animation.animation_step.as_ref().map_or(0, Vec::len);
+        if step_count == 0 {
+            return false;
+        }
+        match running.phase {
+            Phase::On => running.phase = Phase::Off,
+            Phase::Off => {
+                running.phase = Phase::On;
+                running.step += 1;
+                if running.step >= step_count {
+                    running.step = 0;
+                    running.loops_done += 1;
+                    // 0 loops means forever
+                    let loops = animation.loops_u32().unwrap_or(0);
+                    if loops > 0 && running.loops_done >= loops {
+                        return false;
+                    }
+                }
+            }
+        }
+        self.apply_phase(running);
+        true
+    }
+
+    fn finish_animation(&mut self, index: usize) {
+        let animation = &self.all_animations()[index];
+        let end_state = light_state(Some(&animation.lights_state_at_animation_end));
+        let all_off = animation.all_lights_off_at_animation_start_bool();
+        let involved = self.involved_bulbs(animation);
+        let still_animated: Vec<usize> = self
+            .animations
+            .iter()
+            .flat_map(|a| self.involved_bulbs(&self.all_animations()[a.index]))
+            .collect();
+        for (i, state) in self.states.iter_mut().enumerate() {
+            if still_animated.contains(&i) {
+                continue;
+            }
+            if involved.contains(&i) {
+                state.locked = false;
+                state.animated = end_state;
+            } else if all_off {
+                state.animated = None;
+            }
+        }
+    }
+}
+
+/// `LightsStateAtAnimationStart/End`: 1 = involved lights off, 2 = involved lights on,
+/// anything else gives control back to the rom
+fn light_state(value: Option<&str>) -> Option<bool> {
+    match value {
+        Some("1") => Some(false),
+        Some("2") => Some(true),
+        _ => None,
+    }
+}
+
+/// B2S ids for bulbs, either set directly or through one of the predefined id types
+fn b2s_id(bulb: &Bulb) -> Option<u32> {
+    match &bulb.b2s_id_type {
+        Some(B2SIDType::NotUsed) | None => bulb.b2s_id_u32(),
+        Some(id_type) => Some(match id_type {
+            B2SIDType::ScoreRolloverPlayer1_25 => 25,
+            B2SIDType::ScoreRolloverPlayer2_26 => 26,
+            B2SIDType::ScoreRolloverPlayer3_27 => 27,
+            B2SIDType::ScoreRolloverPlayer4_28 => 28,
+            B2SIDType::PlayerUp_30 => 30,
+            B2SIDType::CanPlay_31 => 31,
+            B2SIDType::BallInPlay_32 => 32,
+            B2SIDType::Tilt_33 => 33,
+            B2SIDType::Match_34 => 34,
+            B2SIDType::GameOver_35 => 35,
+            B2SIDType::ShootAgain_36 => 36,
+            B2SIDType::NotUsed => unreachable!(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directb2s::{read, Animations, Illumination};
+    use pretty_assertions::assert_eq;
+    use quick_xml::de::from_str;
+    use std::io::BufReader;
+
+    fn bulb(id: &str, extra: &str) -> Bulb {
+        from_str(&format!(
+            r#"<Bulb Parent="Backglass" ID="{id}" Name="B{id}" InitialState="0" Intensity="1" DodgeColor="0.0.0" Visible="1" LocX="0" LocY="0" Width="1" Height="1" IsImageSnippit="0" Image="" Text="" TextAlignment="0" FontName="" FontSize="10" FontStyle="0" {extra}/>"#
+        ))
+        .unwrap()
+    }
+
+    fn backglass(bulbs: Vec<Bulb>, animations: &str) -> DirectB2SData {
+        let file = std::fs::File::open(
+            "testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s",
+        )
+        .unwrap();
+        let mut data = read(BufReader::new(file)).unwrap();
+        data.illumination = Illumination {
+            bulb: Some(bulbs),
+            extra: Default::default(),
+        };
+        data.animations =
+            from_str::<Animations>(&format!("<Animations>{animations}</Animations>")).unwrap();
+        data
+    }
+
+    fn visible_ids(player: &BackglassPlayer) -> Vec<String> {
+        player
+            .visible_bulbs()
+            .iter()
+            .map(|b| b.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn rom_and_dual_mode() {
+        let data = backglass(
+            vec![
+                bulb("1", r#"RomID="5" RomIDType="1" DualMode="1""#),
+                bulb("2", r#"RomID="5" RomIDType="1" DualMode="2""#),
+                bulb("3", r#"RomID="5" RomIDType="1" RomInverted="1""#),
+                bulb("4", r#"RomID="5" RomIDType="1" IlluMode="1""#),
+                bulb("5", r#"B2SIDType="6" B2SValue="2""#),
+            ],
+            "",
+        );
+        let mut player = BackglassPlayer::new(&data, BackglassMode::Authentic);
+        player.set_lamp(5, false);
+        assert_eq!(visible_ids(&player), vec!["3"]);
+
+        player.set_lamp(5, true);
+        player.set_data(31, 2);
+        assert_eq!(visible_ids(&player), vec!["1", "5", "4"]);
+
+        player.set_data(31, 1);
+        let fantasy = {
+            let mut player = BackglassPlayer::new(&data, BackglassMode::Fantasy);
+            player.set_lamp(5, true);
+            visible_ids(&player)
+        };
+        assert_eq!(visible_ids(&player), vec!["1", "4"]);
+        assert_eq!(fantasy, vec!["2", "4"]);
+    }
+
+    #[test]
+    fn animation_steps() {
+        let animation = r#"<Animation Name="Attract" Parent="Backglass" Interval="100" Loops="1" IDJoin="S3" StartAnimationAtBackglassStartup="0" LightsStateAtAnimationEnd="0" LockInvolvedLamps="1" HideScoreDisplays="0" BringToFront="0">
+  <AnimationStep Step="1" On="1" WaitLoopsAfterOn="1" Off="1" WaitLoopsAfterOff="0"/>
+  <AnimationStep Step="2" On="2" WaitLoopsAfterOn="0" Off="" WaitLoopsAfterOff="0"/>
+</Animation>"#;
+        let data = backglass(
+            vec![bulb("1", r#"RomID="1" RomIDType="1""#), bulb("2", "")],
+            animation,
+        );
+        let mut player = BackglassPlayer::new(&data, BackglassMode::Authentic);
+
+        player.set_solenoid(3, true);
+        assert!(player.is_animation_running("Attract"));
+        assert_eq!(visible_ids(&player), vec!["1"]);
+
+        // locked, the lamp change is ignored while animating
+        player.set_lamp(1, false);
+        player.tick(100);
+        assert_eq!(visible_ids(&player), vec!["1"]);
+        player.tick(100);
+        assert_eq!(visible_ids(&player), Vec::<String>::new());
+        player.tick(100);
+        assert_eq!(visible_ids(&player), vec!["2"]);
+        player.tick(200);
+        assert!(!player.is_animation_running("Attract"));
+        // control is given back to the rom
+        assert_eq!(visible_ids(&player), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,248 @@
+//! Reading B2S Backglass Designer project files (`.b2s`)
+//!
+//! The designer stores its projects as xml that is closely related to the `directb2s` files
+//! it creates. The table settings, animations, bulbs, scores and reels use the same elements,
+//! so they are read into the same types as [`DirectB2SData`](super::DirectB2SData). Projects
+//! also keep the designer state that never ends up in the runtime file, like the list of
+//! imported images. Settings that are not yet filled in are missing from the file, which is
+//! why most fields are optional here. Anything that is not modelled ends up in `extra`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::io;
+//! use vpin::directb2s::project;
+//!
+//! let file = std::fs::File::open("Police Force.b2s").unwrap();
+//! let project = project::read(io::BufReader::new(file)).unwrap();
+//! if let Some(name) = &project.name {
+//!     println!("Project: {}", name.value);
+//! }
+//! for image in project.images.iter().flat_map(|i| i.image.iter().flatten()) {
+//!     println!("Image: {} ({})", image.name, image.file_name.as_deref().unwrap_or("-"));
+//! }
+//! ```
+
+use std::fmt::Debug;
+use std::io::BufRead;
+
+use quick_xml::de::{from_reader, DeError};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Animations, DMDDefaultLocation, Extra, GrillHeight, Illumination, ImageTag, ImageValueTag,
+    OnImageTag, Reels, Scores, Sounds, VRDMDLocation, ValueTag,
+};
+
+/// An image imported into the designer, available to be used as backglass, bulb or reel
+#[derive(Deserialize, Serialize)]
+pub struct ProjectImage {
+    #[serde(rename = "@Name")]
+    pub name: String,
+    #[serde(rename = "@Type", skip_serializing_if = "Option::is_none")]
+    pub image_type: Option<String>,
+    #[serde(rename = "@FileName", skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// base64 encoded image
+    #[serde(rename = "@Value")]
+    pub value: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+// debug for ProjectImage not showing the image data
+impl Debug for ProjectImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProjectImage")
+            .field("name", &self.name)
+            .field("image_type", &self.image_type)
+            .field("file_name", &self.file_name)
+            .field("value", &format!("base64 {:?} bytes", self.value.len()))
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProjectImages {
+    #[serde(rename = "BackglassOffImage", skip_serializing_if = "Option::is_none")]
+    pub backglass_off_image: Option<ValueTag>,
+    #[serde(rename = "BackglassOnImage", skip_serializing_if = "Option::is_none")]
+    pub backglass_on_image: Option<OnImageTag>,
+    #[serde(rename = "BackglassImage", skip_serializing_if = "Option::is_none")]
+    pub backglass_image: Option<ImageTag>,
+    #[serde(rename = "DMDImage", skip_serializing_if = "Option::is_none")]
+    pub dmd_image: Option<ImageTag>,
+    #[serde(rename = "IlluminationImage", skip_serializing_if = "Option::is_none")]
+    pub illumination_image: Option<ValueTag>,
+    #[serde(rename = "ThumbnailImage", skip_serializing_if = "Option::is_none")]
+    pub thumbnail_image: Option<ImageValueTag>,
+    /// All images imported into the project
+    #[serde(rename = "Image", skip_serializing_if = "Option::is_none")]
+    pub image: Option<Vec<ProjectImage>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Root data structure representing a `.b2s` designer project
+#[derive(Debug, Deserialize, Serialize)]
+pub struct B2SProjectData {
+    #[serde(rename = "@Version")]
+    pub version: String,
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<ValueTag>,
+    #[serde(rename = "TableType", skip_serializing_if = "Option::is_none")]
+    pub table_type: Option<ValueTag>,
+    #[serde(rename = "DMDType", skip_serializing_if = "Option::is_none")]
+    pub dmd_type: Option<ValueTag>,
+    #[serde(rename = "DMDDefaultLocation", skip_serializing_if = "Option::is_none")]
+    pub dmd_default_location: Option<DMDDefaultLocation>,
+    #[serde(rename = "VRDMDLocation", skip_serializing_if = "Option::is_none")]
+    pub vr_dmd_location: Option<VRDMDLocation>,
+    #[serde(rename = "GrillHeight", skip_serializing_if = "Option::is_none")]
+    pub grill_height: Option<GrillHeight>,
+    #[serde(rename = "ProjectGUID", skip_serializing_if = "Option::is_none")]
+    pub project_guid: Option<ValueTag>,
+    #[serde(rename = "ProjectGUID2", skip_serializing_if = "Option::is_none")]
+    pub project_guid2: Option<ValueTag>,
+    #[serde(rename = "AssemblyGUID", skip_serializing_if = "Option::is_none")]
+    pub assembly_guid: Option<ValueTag>,
+    #[serde(rename = "VSName", skip_serializing_if = "Option::is_none")]
+    pub vsname: Option<ValueTag>,
+    #[serde(rename = "DualBackglass", skip_serializing_if = "Option::is_none")]
+    pub dual_backglass: Option<ValueTag>,
+    #[serde(rename = "Author", skip_serializing_if = "Option::is_none")]
+    pub author: Option<ValueTag>,
+    #[serde(rename = "Artwork", skip_serializing_if = "Option::is_none")]
+    pub artwork: Option<ValueTag>,
+    #[serde(rename = "GameName", skip_serializing_if = "Option::is_none")]
+    pub game_name: Option<ValueTag>,
+    #[serde(rename = "AddEMDefaults", skip_serializing_if = "Option::is_none")]
+    pub add_em_defaults: Option<ValueTag>,
+    #[serde(rename = "CommType", skip_serializing_if = "Option::is_none")]
+    pub comm_type: Option<ValueTag>,
+    #[serde(rename = "DestType", skip_serializing_if = "Option::is_none")]
+    pub dest_type: Option<ValueTag>,
+    #[serde(rename = "NumberOfPlayers", skip_serializing_if = "Option::is_none")]
+    pub number_of_players: Option<ValueTag>,
+    #[serde(rename = "B2SDataCount", skip_serializing_if = "Option::is_none")]
+    pub b2s_data_count: Option<ValueTag>,
+    #[serde(rename = "ReelType", skip_serializing_if = "Option::is_none")]
+    pub reel_type: Option<ValueTag>,
+    #[serde(rename = "UseDream7LEDs", skip_serializing_if = "Option::is_none")]
+    pub use_dream7_leds: Option<ValueTag>,
+    #[serde(rename = "D7Glow", skip_serializing_if = "Option::is_none")]
+    pub d7_glow: Option<ValueTag>,
+    #[serde(rename = "D7Thickness", skip_serializing_if = "Option::is_none")]
+    pub d7_thickness: Option<ValueTag>,
+    #[serde(rename = "D7Shear", skip_serializing_if = "Option::is_none")]
+    pub d7_shear: Option<ValueTag>,
+    #[serde(rename = "ReelColor", skip_serializing_if = "Option::is_none")]
+    pub reel_color: Option<ValueTag>,
+    #[serde(
+        rename = "ReelRollingDirection",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reel_rolling_direction: Option<ValueTag>,
+    #[serde(
+        rename = "ReelRollingInterval",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reel_rolling_interval: Option<ValueTag>,
+    #[serde(
+        rename = "ReelIntermediateImageCount",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reel_intermediate_image_count: Option<ValueTag>,
+    #[serde(rename = "Animations", skip_serializing_if = "Option::is_none")]
+    pub animations: Option<Animations>,
+    #[serde(rename = "Scores", skip_serializing_if = "Option::is_none")]
+    pub scores: Option<Scores>,
+    #[serde(rename = "Reels", skip_serializing_if = "Option::is_none")]
+    pub reels: Option<Reels>,
+    #[serde(rename = "Illumination", skip_serializing_if = "Option::is_none")]
+    pub illumination: Option<Illumination>,
+    #[serde(rename = "Sounds", skip_serializing_if = "Option::is_none")]
+    pub sounds: Option<Sounds>,
+    #[serde(rename = "Images", skip_serializing_if = "Option::is_none")]
+    pub images: Option<ProjectImages>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+pub fn read<R: BufRead>(reader: R) -> Result<B2SProjectData, DeError> {
+    from_reader(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PROJECT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<B2SBackglassData Version="1.2">
+  <Name Value="Police Force"/>
+  <TableType Value="3"/>
+  <DMDType Value="1"/>
+  <ProjectGUID Value="8a9bcb1e-2e3c-4f1d-9d26-5f0c1d2e3f40"/>
+  <Author Value="someone"/>
+  <GameName Value="polic_l4"/>
+  <Animations>
+    <Animation Name="Attract" Parent="Backglass" Interval="100" Loops="0" IDJoin="" StartAnimationAtBackglassStartup="1" LightsStateAtAnimationEnd="0" LockInvolvedLamps="0" HideScoreDisplays="0" BringToFront="0">
+      <AnimationStep Step="1" On="1" WaitLoopsAfterOn="1" Off="1" WaitLoopsAfterOff="0"/>
+    </Animation>
+  </Animations>
+  <Illumination>
+    <Bulb ID="1" Name="Light 1" Parent="Backglass" B2SID="5" RomID="12" RomIDType="1" InitialState="0" DualMode="0" Intensity="1" LightColor="255.255.255" DodgeColor="255.255.255" Visible="1" LocX="10" LocY="20" Width="30" Height="40" IsImageSnippit="0" Image="[image]" Text="" TextAlignment="1" FontName="Tahoma" FontSize="12" FontStyle="0"/>
+  </Illumination>
+  <Images>
+    <Image Name="Backglass" Type="0" FileName="C:\backglass.png" Value="[image]"/>
+    <Image Name="Bulb" Value="[image]"/>
+  </Images>
+  <Designer ZoomLevel="100"/>
+</B2SBackglassData>"#;
+
+    #[test]
+    fn read_project() {
+        let project = read(PROJECT.as_bytes()).unwrap();
+
+        assert_eq!(project.version, "1.2");
+        assert_eq!(project.name.unwrap().value, "Police Force");
+        assert_eq!(project.game_name.unwrap().value, "polic_l4");
+        // settings the project does not contain yet
+        assert!(project.grill_height.is_none());
+        assert!(project.scores.is_none());
+
+        let animations = project.animations.unwrap().animation.unwrap();
+        assert_eq!(animations[0].name, "Attract");
+        assert_eq!(animations[0].animation_step.as_ref().unwrap().len(), 1);
+        let bulbs = project.illumination.unwrap().bulb.unwrap();
+        assert_eq!(bulbs[0].name, "Light 1");
+
+        let images = project.images.unwrap();
+        assert!(images.backglass_image.is_none());
+        let names = images
+            .image
+            .unwrap()
+            .iter()
+            .map(|i| (i.name.clone(), i.file_name.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (
+                    "Backglass".to_string(),
+                    Some("C:\\backglass.png".to_string())
+                ),
+                ("Bulb".to_string(), None),
+            ]
+        );
+
+        let keys = project
+            .extra
+            .0
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["Designer"]);
+    }
+}
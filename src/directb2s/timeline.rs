@@ -0,0 +1,197 @@
+//! Evaluating backglass animations into a frame-by-frame schedule
+//!
+//! The schedule is computed with a [`BackglassPlayer`], so it follows exactly the same rules
+//! for intervals, waits, loops and the lights state at the start and end of the animation.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use vpin::directb2s;
+//! use vpin::directb2s::timeline;
+//!
+//! let file = std::fs::File::open("testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s").unwrap();
+//! let data = directb2s::read(io::BufReader::new(file)).unwrap();
+//! for animation in data.animations.animation.iter().flatten() {
+//!     let timeline = timeline::evaluate(&data, &animation.name).unwrap();
+//!     println!("{} takes {}ms", timeline.name, timeline.duration);
+//! }
+//! ```
+
+use super::player::{BackglassMode, BackglassPlayer};
+use super::{DirectB2SData, DualMode};
+
+/// Safety limit for animations that never end
+const MAX_INTERVALS: u32 = 100_000;
+
+/// The state of the bulbs involved in an animation, from `time` on until the next frame
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    /// Milliseconds since the start of the animation
+    pub time: u32,
+    /// Ids of the involved bulbs that are lit
+    pub lit: Vec<String>,
+}
+
+/// The result of [`evaluate`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Timeline {
+    pub name: String,
+    /// Ids of all bulbs switched by the animation
+    pub bulbs: Vec<String>,
+    /// Only contains a frame when the lit bulbs change
+    pub frames: Vec<Frame>,
+    /// Milliseconds until the animation ends, or until the first loop is done if it repeats
+    pub duration: u32,
+    /// Animations with `Loops` set to 0 run until they are stopped, only the first loop is
+    /// in the schedule
+    pub repeats: bool,
+}
+
+/// Evaluates an animation as if it was started on a dark backglass.
+///
+/// Returns `None` if there is no animation with that name.
+pub fn evaluate(data: &DirectB2SData, name: &str) -> Option<Timeline> {
+    let animation = data
+        .animations
+        .animation
+        .iter()
+        .flatten()
+        .find(|a| a.name.eq_ignore_ascii_case(name))?;
+    let mode = match animation.dual_mode {
+        Some(DualMode::Fantasy) => BackglassMode::Fantasy,
+        _ => BackglassMode::Authentic,
+    };
+    let interval = animation.interval_u32().unwrap_or(0).max(1);
+    let repeats = animation.loops_u32().unwrap_or(0) == 0;
+
+    let mut player = BackglassPlayer::without_startup_animations(data, mode);
+    let bulbs: Vec<String> = player
+        .animation_bulb_ids(animation)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let lit = |player: &BackglassPlayer| -> Vec<String> {
+        bulbs
+            .iter()
+            .filter(|id| player.is_bulb_on(id))
+            .cloned()
+            .collect()
+    };
+
+    player.start_animation(&animation.name);
+    let mut frames = vec![Frame {
+        time: 0,
+        lit: lit(&player),
+    }];
+    let mut time = 0;
+    for _ in 0..MAX_INTERVALS {
+        let running = match player.loops_done(&animation.name) {
+            None => false,
+            Some(loops_done) => !(repeats && loops_done > 0),
+        };
+        if !running {
+            break;
+        }
+        player.tick(interval);
+        time += interval;
+        let current = lit(&player);
+        if frames.last().is_some_and(|frame| frame.lit != current) {
+            frames.push(Frame { time, lit: current });
+        }
+    }
+    Some(Timeline {
+        name: animation.name.clone(),
+        bulbs,
+        frames,
+        duration: time,
+        repeats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::directb2s::{read, Animations, Bulb, Illumination};
+    use pretty_assertions::assert_eq;
+    use quick_xml::de::from_str;
+    use std::io::BufReader;
+
+    fn backglass(animation: &str) -> DirectB2SData {
+        let file = std::fs::File::open(
+            "testdata/Police Force (Williams 1989) FULL DMD.stripped.directb2s",
+        )
+        .unwrap();
+        let mut data = read(BufReader::new(file)).unwrap();
+        let bulbs = ["1", "2"]
+            .iter()
+            .map(|id| {
+                from_str::<Bulb>(&format!(
+                    r#"<Bulb Parent="Backglass" ID="{id}" Name="B{id}" InitialState="0" Intensity="1" DodgeColor="0.0.0" Visible="1" LocX="0" LocY="0" Width="1" Height="1" IsImageSnippit="0" Image="" Text="" TextAlignment="0" FontName="" FontSize="10" FontStyle="0"/>"#
+                ))
+                .unwrap()
+            })
+            .collect();
+        data.illumination = Illumination {
+            bulb: Some(bulbs),
+            extra: Default::default(),
+        };
+        data.animations =
+            from_str::<Animations>(&format!("<Animations>{animation}</Animations>")).unwrap();
+        data
+    }
+
+    #[test]
+    fn evaluate_chase() {
+        let data = backglass(
+            r#"<Animation Name="Chase" Parent="Backglass" Interval="50" Loops="2" IDJoin="" StartAnimationAtBackglassStartup="0" LightsStateAtAnimationEnd="2" LockInvolvedLamps="0" HideScoreDisplays="0" BringToFront="0">
+  <AnimationStep Step="1" On="1" WaitLoopsAfterOn="1" Off="1" WaitLoopsAfterOff="0"/>
+  <AnimationStep Step="2" On="2" WaitLoopsAfterOn="0" Off="2" WaitLoopsAfterOff="0"/>
+</Animation>"#,
+        );
+
+        let timeline = evaluate(&data, "chase").unwrap();
+
+        let frame = |time: u32, lit: &[&str]| Frame {
+            time,
+            lit: lit.iter().map(|s| s.to_string()).collect(),
+        };
+        assert_eq!(
+            timeline,
+            Timeline {
+                name: "Chase".to_string(),
+                bulbs: vec!["1".to_string(), "2".to_string()],
+                frames: vec![
+                    frame(0, &["1"]),
+                    frame(100, &[]),
+                    frame(150, &["2"]),
+                    frame(200, &[]),
+                    frame(250, &["1"]),
+                    frame(350, &[]),
+                    frame(400, &["2"]),
+                    frame(450, &[]),
+                    // lights state at animation end: involved lights on
+                    frame(500, &["1", "2"]),
+                ],
+                duration: 500,
+                repeats: false,
+            }
+        );
+    }
+
+    #[test]
+    fn evaluate_endless() {
+        let data = backglass(
+            r#"<Animation Name="Blink" Parent="Backglass" Interval="100" Loops="0" IDJoin="" StartAnimationAtBackglassStartup="1" LightsStateAtAnimationEnd="0" LockInvolvedLamps="0" HideScoreDisplays="0" BringToFront="0">
+  <AnimationStep Step="1" On="B1" WaitLoopsAfterOn="0" Off="B1" WaitLoopsAfterOff="0"/>
+</Animation>"#,
+        );
+
+        let timeline = evaluate(&data, "Blink").unwrap();
+
+        assert!(timeline.repeats);
+        assert_eq!(timeline.duration, 200);
+        assert_eq!(timeline.frames.len(), 3);
+        assert_eq!(evaluate(&data, "missing"), None);
+    }
+}
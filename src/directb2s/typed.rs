@@ -0,0 +1,463 @@
+//! Typed accessors for the string fields of the directb2s model
+//!
+//! The model keeps the values exactly as they are in the file so that a round trip does
+//! not change anything. These accessors parse the values and write them back using the
+//! conventions found in the file: numbers without trailing `.0` and booleans as `1`/`0`,
+//! or `True`/`False` if that is what the field contained before. Setting a value equal to
+//! the one in the field keeps the text as it was.
+//!
+//! Numbers are always written with a `.` as decimal separator, whatever the locale of the
+//! system. Files saved by the B2S designer on a system with a `,` locale can contain numbers
+//! like `20,5`, [`DirectB2SData::normalize_decimal_separators`] rewrites those.
+
+use super::{
+    Animation, AnimationStep, Bulb, DMDDefaultLocation, DirectB2SData, GrillHeight, Score,
+};
+
+/// Parses a number, accepting a `,` as decimal separator as written by some locales
+pub(crate) fn parse_f32(value: &str) -> Option<f32> {
+    let value = value.trim();
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.replace(',', ".").parse().ok())
+}
+
+/// Formats a number with `.` as decimal separator and without a trailing `.0`.
+///
+/// Other tools can not read `NaN` or `inf`, those are written as `0`.
+pub(crate) fn format_f32(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "0".to_string()
+    }
+}
+
+/// Formats a number like [`format_f32`], keeping the previous text if it holds the same number
+pub(crate) fn format_f32_like(previous: &str, value: f32) -> String {
+    if parse_f32(previous) == Some(value) {
+        previous.to_string()
+    } else {
+        format_f32(value)
+    }
+}
+
+/// Rewrites a number written with a `,` as decimal separator, other values are left as is
+fn normalize_f32(value: &mut String) {
+    if value.contains(',') {
+        if let Some(number) = parse_f32(value) {
+            *value = format_f32(number);
+        }
+    }
+}
+
+pub(crate) fn parse_i32(value: &str) -> Option<i32> {
+    value.trim().parse().ok()
+}
+
+pub(crate) fn parse_u32(value: &str) -> Option<u32> {
+    value.trim().parse().ok()
+}
+
+/// Formats an integer, keeping the previous text if it holds the same number
+pub(crate) fn format_u32_like(previous: &str, value: u32) -> String {
+    if parse_u32(previous) == Some(value) {
+        previous.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Accepts `1`/`0` and `True`/`False` in any case
+pub(crate) fn parse_bool(value: &str) -> Option<bool> {
+    let value = value.trim();
+    if value == "1" || value.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if value == "0" || value.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Formats a boolean in the same style as the previous value
+pub(crate) fn format_bool(previous: &str, value: bool) -> String {
+    let word_style = previous
+        .trim()
+        .chars()
+        .next()
+        .is_some_and(char::is_alphabetic);
+    match (word_style, value) {
+        (true, true) => "True".to_string(),
+        (true, false) => "False".to_string(),
+        (false, true) => "1".to_string(),
+        (false, false) => "0".to_string(),
+    }
+}
+
+impl Bulb {
+    pub fn id_u32(&self) -> Option<u32> {
+        parse_u32(&self.id)
+    }
+
+    pub fn rom_id_u32(&self) -> Option<u32> {
+        self.rom_id.as_deref().and_then(parse_u32)
+    }
+
+    pub fn set_rom_id_u32(&mut self, rom_id: Option<u32>) {
+        let previous = self.rom_id.as_deref().unwrap_or_default();
+        self.rom_id = rom_id.map(|id| format_u32_like(previous, id));
+    }
+
+    pub fn rom_inverted_bool(&self) -> bool {
+        self.rom_inverted
+            .as_deref()
+            .and_then(parse_bool)
+            .unwrap_or(false)
+    }
+
+    pub fn set_rom_inverted_bool(&mut self, inverted: bool) {
+        let previous = self.rom_inverted.as_deref().unwrap_or_default();
+        self.rom_inverted = Some(format_bool(previous, inverted));
+    }
+
+    pub fn b2s_id_u32(&self) -> Option<u32> {
+        self.b2s_id.as_deref().and_then(parse_u32)
+    }
+
+    pub fn b2s_value_i32(&self) -> Option<i32> {
+        self.b2s_value.as_deref().and_then(parse_i32)
+    }
+
+    pub fn initial_state_on(&self) -> bool {
+        parse_i32(&self.initial_state) == Some(1)
+    }
+
+    pub fn intensity_u32(&self) -> Option<u32> {
+        parse_u32(&self.intensity)
+    }
+
+    pub fn set_intensity_u32(&mut self, intensity: u32) {
+        self.intensity = format_u32_like(&self.intensity, intensity);
+    }
+
+    pub fn z_order_i32(&self) -> Option<i32> {
+        self.z_order.as_deref().and_then(parse_i32)
+    }
+
+    pub fn visible_bool(&self) -> Option<bool> {
+        parse_bool(&self.visible)
+    }
+
+    pub fn set_visible_bool(&mut self, visible: bool) {
+        self.visible = format_bool(&self.visible, visible);
+    }
+
+    pub fn is_image_snippit_bool(&self) -> Option<bool> {
+        parse_bool(&self.is_image_snippit)
+    }
+
+    pub fn loc_x_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_x)
+    }
+
+    pub fn set_loc_x_f32(&mut self, loc_x: f32) {
+        self.loc_x = format_f32_like(&self.loc_x, loc_x);
+    }
+
+    pub fn loc_y_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_y)
+    }
+
+    pub fn set_loc_y_f32(&mut self, loc_y: f32) {
+        self.loc_y = format_f32_like(&self.loc_y, loc_y);
+    }
+
+    pub fn width_f32(&self) -> Option<f32> {
+        parse_f32(&self.width)
+    }
+
+    pub fn set_width_f32(&mut self, width: f32) {
+        self.width = format_f32_like(&self.width, width);
+    }
+
+    pub fn height_f32(&self) -> Option<f32> {
+        parse_f32(&self.height)
+    }
+
+    pub fn set_height_f32(&mut self, height: f32) {
+        self.height = format_f32_like(&self.height, height);
+    }
+
+    pub fn font_size_f32(&self) -> Option<f32> {
+        parse_f32(&self.font_size)
+    }
+}
+
+impl Score {
+    pub fn id_u32(&self) -> Option<u32> {
+        parse_u32(&self.id)
+    }
+
+    pub fn digits_u32(&self) -> Option<u32> {
+        parse_u32(&self.digits)
+    }
+
+    pub fn spacing_i32(&self) -> Option<i32> {
+        parse_i32(&self.spacing)
+    }
+
+    pub fn loc_x_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_x)
+    }
+
+    pub fn set_loc_x_f32(&mut self, loc_x: f32) {
+        self.loc_x = format_f32_like(&self.loc_x, loc_x);
+    }
+
+    pub fn loc_y_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_y)
+    }
+
+    pub fn set_loc_y_f32(&mut self, loc_y: f32) {
+        self.loc_y = format_f32_like(&self.loc_y, loc_y);
+    }
+
+    pub fn width_f32(&self) -> Option<f32> {
+        parse_f32(&self.width)
+    }
+
+    pub fn set_width_f32(&mut self, width: f32) {
+        self.width = format_f32_like(&self.width, width);
+    }
+
+    pub fn height_f32(&self) -> Option<f32> {
+        parse_f32(&self.height)
+    }
+
+    pub fn set_height_f32(&mut self, height: f32) {
+        self.height = format_f32_like(&self.height, height);
+    }
+}
+
+impl Animation {
+    /// Milliseconds between steps
+    pub fn interval_u32(&self) -> Option<u32> {
+        parse_u32(&self.interval)
+    }
+
+    pub fn set_interval_u32(&mut self, interval: u32) {
+        self.interval = format_u32_like(&self.interval, interval);
+    }
+
+    /// 0 means the animation runs until it is stopped
+    pub fn loops_u32(&self) -> Option<u32> {
+        parse_u32(&self.loops)
+    }
+
+    pub fn set_loops_u32(&mut self, loops: u32) {
+        self.loops = format_u32_like(&self.loops, loops);
+    }
+
+    pub fn start_animation_at_backglass_startup_bool(&self) -> Option<bool> {
+        parse_bool(&self.start_animation_at_backglass_startup)
+    }
+
+    pub fn set_start_animation_at_backglass_startup_bool(&mut self, start: bool) {
+        self.start_animation_at_backglass_startup =
+            format_bool(&self.start_animation_at_backglass_startup, start);
+    }
+
+    pub fn lock_involved_lamps_bool(&self) -> Option<bool> {
+        parse_bool(&self.lock_involved_lamps)
+    }
+
+    pub fn set_lock_involved_lamps_bool(&mut self, lock: bool) {
+        self.lock_involved_lamps = format_bool(&self.lock_involved_lamps, lock);
+    }
+
+    pub fn hide_score_displays_bool(&self) -> Option<bool> {
+        parse_bool(&self.hide_score_displays)
+    }
+
+    pub fn bring_to_front_bool(&self) -> Option<bool> {
+        parse_bool(&self.bring_to_front)
+    }
+
+    pub fn all_lights_off_at_animation_start_bool(&self) -> bool {
+        self.all_lights_off_at_animation_start
+            .as_deref()
+            .and_then(parse_bool)
+            .unwrap_or(false)
+    }
+
+    pub fn run_animation_til_end_bool(&self) -> bool {
+        self.run_animation_til_end
+            .as_deref()
+            .and_then(parse_bool)
+            .unwrap_or(false)
+    }
+}
+
+impl AnimationStep {
+    pub fn step_u32(&self) -> Option<u32> {
+        parse_u32(&self.step)
+    }
+
+    pub fn wait_loops_after_on_u32(&self) -> Option<u32> {
+        parse_u32(&self.wait_loops_after_on)
+    }
+
+    pub fn set_wait_loops_after_on_u32(&mut self, wait: u32) {
+        self.wait_loops_after_on = format_u32_like(&self.wait_loops_after_on, wait);
+    }
+
+    pub fn wait_loops_after_off_u32(&self) -> Option<u32> {
+        parse_u32(&self.wait_loops_after_off)
+    }
+
+    pub fn set_wait_loops_after_off_u32(&mut self, wait: u32) {
+        self.wait_loops_after_off = format_u32_like(&self.wait_loops_after_off, wait);
+    }
+}
+
+impl DMDDefaultLocation {
+    pub fn loc_x_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_x)
+    }
+
+    pub fn loc_y_f32(&self) -> Option<f32> {
+        parse_f32(&self.loc_y)
+    }
+}
+
+impl GrillHeight {
+    pub fn value_u32(&self) -> Option<u32> {
+        parse_u32(&self.value)
+    }
+
+    pub fn small_u32(&self) -> Option<u32> {
+        self.small.as_deref().and_then(parse_u32)
+    }
+}
+
+impl DirectB2SData {
+    /// Rewrites the locations and sizes that use a `,` as decimal separator to use a `.`,
+    /// so tools that do not expect a `,` can read the file
+    pub fn normalize_decimal_separators(&mut self) {
+        let location = &mut self.dmd_default_location;
+        normalize_f32(&mut location.loc_x);
+        normalize_f32(&mut location.loc_y);
+        for bulb in self.illumination.bulb.iter_mut().flatten() {
+            for value in [
+                &mut bulb.loc_x,
+                &mut bulb.loc_y,
+                &mut bulb.width,
+                &mut bulb.height,
+                &mut bulb.font_size,
+            ] {
+                normalize_f32(value);
+            }
+        }
+        let scores = self
+            .scores
+            .iter_mut()
+            .flat_map(|s| s.score.iter_mut().flatten());
+        for score in scores {
+            for value in [
+                &mut score.loc_x,
+                &mut score.loc_y,
+                &mut score.width,
+                &mut score.height,
+            ] {
+                normalize_f32(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use quick_xml::de::from_str;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse_f32(" 12 "), Some(12.0));
+        assert_eq!(parse_f32("1,5"), Some(1.5));
+        assert_eq!(parse_f32("abc"), None);
+        assert_eq!(parse_bool("True"), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+        assert_eq!(format_bool("False", true), "True");
+        assert_eq!(format_bool("0", true), "1");
+        assert_eq!(format_bool("", false), "0");
+    }
+
+    #[test]
+    fn bulb_accessors() {
+        let mut bulb: Bulb = from_str(
+            r#"<Bulb Parent="Backglass" ID="17" Name="L60" RomID="60" RomIDType="1" RomInverted="False" InitialState="1" Intensity="3" DodgeColor="0.0.0" Visible="True" LocX="10" LocY="20,5" Width="2857" Height="2092" IsImageSnippit="1" Image="" Text="" TextAlignment="0" FontName="" FontSize="1000" FontStyle="0"/>"#,
+        )
+        .unwrap();
+
+        assert_eq!(bulb.id_u32(), Some(17));
+        assert_eq!(bulb.rom_id_u32(), Some(60));
+        assert!(!bulb.rom_inverted_bool());
+        assert!(bulb.initial_state_on());
+        assert_eq!(bulb.visible_bool(), Some(true));
+        assert_eq!(bulb.loc_y_f32(), Some(20.5));
+        assert_eq!(bulb.width_f32(), Some(2857.0));
+
+        bulb.set_visible_bool(false);
+        bulb.set_rom_inverted_bool(true);
+        bulb.set_loc_x_f32(12.0);
+        bulb.set_height_f32(100.25);
+        assert_eq!(bulb.visible, "False");
+        assert_eq!(bulb.rom_inverted.as_deref(), Some("True"));
+        assert_eq!(bulb.loc_x, "12");
+        assert_eq!(bulb.height, "100.25");
+    }
+
+    #[test]
+    fn unchanged_numbers_keep_their_text() {
+        let mut bulb: Bulb = from_str(
+            r#"<Bulb Parent="Backglass" ID="17" Name="L60" RomID=" 60" InitialState="1" Intensity="03" DodgeColor="0.0.0" Visible="1" LocX="10" LocY="20,5" Width="2857" Height="2092" IsImageSnippit="0" Image="" Text="" TextAlignment="0" FontName="" FontSize="1000" FontStyle="0"/>"#,
+        )
+        .unwrap();
+        assert_eq!(bulb.intensity_u32(), Some(3));
+
+        bulb.set_rom_id_u32(Some(60));
+        bulb.set_intensity_u32(3);
+        bulb.set_loc_y_f32(20.5);
+        assert_eq!(bulb.rom_id.as_deref(), Some(" 60"));
+        assert_eq!(bulb.intensity, "03");
+        assert_eq!(bulb.loc_y, "20,5");
+
+        bulb.set_rom_id_u32(Some(61));
+        bulb.set_intensity_u32(4);
+        bulb.set_loc_y_f32(21.5);
+        assert_eq!(bulb.rom_id.as_deref(), Some("61"));
+        assert_eq!(bulb.intensity, "4");
+        assert_eq!(bulb.loc_y, "21.5");
+    }
+
+    #[test]
+    fn numbers_use_a_dot() {
+        let mut rng = StdRng::seed_from_u64(991);
+        for _ in 0..10_000 {
+            let value = f32::from_bits(rng.gen());
+            let formatted = format_f32(value);
+            assert!(!formatted.contains(','), "{}", formatted);
+            if value.is_finite() {
+                assert_eq!(parse_f32(&formatted), Some(value), "{}", formatted);
+            } else {
+                assert_eq!(formatted, "0");
+            }
+        }
+    }
+}
@@ -3,8 +3,20 @@
 //!
 //! It provides a set of tools to work with the various file formats used by the different applications.
 //!
-//! The main focus is on the Visual Pinball X (VPX) file format, but it also provides tools for backglass DirectB2S and Point of View POV files.
+//! The main focus is on the Visual Pinball X (VPX) file format, but it also provides tools for backglass DirectB2S, Point of View POV and `VPinballX.ini` settings files.
+
+pub mod altsound;
 
 pub mod directb2s;
 
+pub mod names;
+
+pub mod paths;
+
+pub mod prelude;
+
+pub mod settings;
+
+pub mod vps;
+
 pub mod vpx;
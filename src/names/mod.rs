@@ -0,0 +1,216 @@
+//! Parsing and formatting table file names
+//!
+//! Most tables are distributed with a file name following the convention
+//! `Title (Manufacturer Year) Author vVersion.vpx`, eg `Twilight Zone (Bally 1993) VPW v1.0.vpx`.
+//! Everything after the title is optional and there are a lot of variations in the wild.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::names::parse_table_filename;
+//!
+//! let name = parse_table_filename("Twilight Zone (Bally 1993) VPW v1.0.vpx");
+//! assert_eq!(name.title, "Twilight Zone");
+//! assert_eq!(name.manufacturer.as_deref(), Some("Bally"));
+//! assert_eq!(name.year, Some(1993));
+//! assert_eq!(name.author.as_deref(), Some("VPW"));
+//! assert_eq!(name.version.as_deref(), Some("1.0"));
+//! assert_eq!(name.to_string(), "Twilight Zone (Bally 1993) VPW v1.0");
+//! ```
+
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Extensions that are removed before parsing, any other extension is considered part of the name
+/// as a version like `v1.0` would otherwise be seen as an extension.
+const KNOWN_EXTENSIONS: [&str; 9] = [
+    "vpx",
+    "vpt",
+    "directb2s",
+    "vbs",
+    "ini",
+    "pov",
+    "fpt",
+    "zip",
+    "rar",
+];
+
+/// The parts of a table file name
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TableName {
+    pub title: String,
+    pub manufacturer: Option<String>,
+    pub year: Option<u16>,
+    /// The author(s) and any other remarks like `Mod` or `FULL DMD`
+    pub author: Option<String>,
+    /// The version without the `v` prefix
+    pub version: Option<String>,
+}
+
+impl TableName {
+    /// Formats the name as a file name with the given extension
+    pub fn file_name(&self, extension: &str) -> String {
+        format!("{}.{}", self, extension)
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)?;
+        match (&self.manufacturer, self.year) {
+            (Some(manufacturer), Some(year)) => write!(f, " ({} {})", manufacturer, year)?,
+            (Some(manufacturer), None) => write!(f, " ({})", manufacturer)?,
+            (None, Some(year)) => write!(f, " ({})", year)?,
+            (None, None) => {}
+        }
+        if let Some(author) = &self.author {
+            write!(f, " {}", author)?;
+        }
+        if let Some(version) = &self.version {
+            write!(f, " v{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a table file name or path.
+///
+/// This never fails, if the name does not follow the convention the whole name ends up
+/// in the title.
+pub fn parse_table_filename<P: AsRef<Path>>(file_name: P) -> TableName {
+    let file_name = file_name
+        .as_ref()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = strip_known_extension(&file_name).replace('_', " ");
+    let stem = stem.trim();
+
+    let re = Regex::new(
+        r"^(?P<title>.*?)\s*\((?:(?P<manufacturer>[^()]*?)\s*)?(?P<year>\d{4})?\)\s*(?P<rest>.*)$",
+    )
+    .unwrap();
+    let Some(captures) = re.captures(stem).filter(|c| !c["title"].is_empty()) else {
+        let (rest, version) = split_version(stem);
+        return TableName {
+            title: rest.to_string(),
+            version,
+            ..Default::default()
+        };
+    };
+
+    let manufacturer = captures
+        .name("manufacturer")
+        .map(|m| m.as_str().trim().to_string())
+        .filter(|m| !m.is_empty());
+    let year = captures.name("year").and_then(|y| y.as_str().parse().ok());
+    let rest = captures["rest"].trim_start_matches(['-', ' ']);
+    let (author, version) = split_version(rest);
+    let author = Some(author.to_string()).filter(|a| !a.is_empty());
+    TableName {
+        title: captures["title"].to_string(),
+        manufacturer,
+        year,
+        author,
+        version,
+    }
+}
+
+fn strip_known_extension(file_name: &str) -> &str {
+    match file_name.rsplit_once('.') {
+        Some((stem, extension))
+            if KNOWN_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension)) =>
+        {
+            stem
+        }
+        _ => file_name,
+    }
+}
+
+/// Splits off a trailing version like `v1.0`, `V2`, `version 1.2.1` or `1.0.2`.
+///
+/// Without a `v` prefix the version needs a dot, otherwise we would take the last part of
+/// titles like `Apollo 13`.
+fn split_version(text: &str) -> (&str, Option<String>) {
+    let re = Regex::new(
+        r"(?i)^(?P<rest>.*?)\s*(?:(?:\bv|\bversion\s*)(?P<prefixed>\d+(?:\.\d+)*[a-z]?)|\b(?P<plain>\d+(?:\.\d+)+[a-z]?))$",
+    )
+    .unwrap();
+    match re.captures(text) {
+        Some(captures) => {
+            let version = captures
+                .name("prefixed")
+                .or(captures.name("plain"))
+                .map(|v| v.as_str().to_string());
+            let rest = captures
+                .name("rest")
+                .map(|r| r.as_str())
+                .unwrap_or_default();
+            (rest, version)
+        }
+        None => (text, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_full_name() {
+        let name = parse_table_filename("/tables/Twilight Zone (Bally 1993) VPW v1.0.vpx");
+        assert_eq!(
+            name,
+            TableName {
+                title: "Twilight Zone".to_string(),
+                manufacturer: Some("Bally".to_string()),
+                year: Some(1993),
+                author: Some("VPW".to_string()),
+                version: Some("1.0".to_string()),
+            }
+        );
+        assert_eq!(
+            name.file_name("directb2s"),
+            "Twilight Zone (Bally 1993) VPW v1.0.directb2s"
+        );
+    }
+
+    #[test]
+    fn parse_variations() {
+        let name = parse_table_filename("Diner (Williams 1990) VPW Mod 1.0.2.vpx");
+        assert_eq!(name.author.as_deref(), Some("VPW Mod"));
+        assert_eq!(name.version.as_deref(), Some("1.0.2"));
+
+        let name = parse_table_filename("Police Force (Williams 1989) FULL DMD.directb2s");
+        assert_eq!(name.title, "Police Force");
+        assert_eq!(name.author.as_deref(), Some("FULL DMD"));
+        assert_eq!(name.version, None);
+
+        let name = parse_table_filename("Apollo 13 (Sega 1995).vpx");
+        assert_eq!(name.title, "Apollo 13");
+        assert_eq!(name.author, None);
+
+        let name = parse_table_filename("Mystery Castle (1993) - JPSalas_V1.1a.vpx");
+        assert_eq!(name.manufacturer, None);
+        assert_eq!(name.year, Some(1993));
+        assert_eq!(name.author.as_deref(), Some("JPSalas"));
+        assert_eq!(name.version.as_deref(), Some("1.1a"));
+        assert_eq!(name.to_string(), "Mystery Castle (1993) JPSalas v1.1a");
+    }
+
+    #[test]
+    fn parse_without_convention() {
+        let name = parse_table_filename("my_table v2.vpx");
+        assert_eq!(name.title, "my table");
+        assert_eq!(name.version.as_deref(), Some("2"));
+        assert_eq!(name.manufacturer, None);
+
+        let name = parse_table_filename("Apollo 13.vpx");
+        assert_eq!(name.title, "Apollo 13");
+        assert_eq!(name.version, None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Resolving the Music, Roms, AltSound and AltColor folders for a table
+//!
+//! Depending on how Visual Pinball was installed these folders live in different places:
+//!
+//! * [`Layout::Desktop`]: the default VPinball install on Windows
+//!   ```text
+//!   VisualPinball/Tables/table.vpx
+//!   VisualPinball/Music/
+//!   VisualPinball/VPinMAME/roms/
+//!   VisualPinball/VPinMAME/altsound/<rom>/
+//!   VisualPinball/VPinMAME/altcolor/<rom>/
+//!   ```
+//! * [`Layout::Popper`]: a PinUP Popper cabinet install, like desktop but with PinUP media
+//!   ```text
+//!   vPinball/VisualPinball/Tables/table.vpx
+//!   vPinball/PinUPSystem/POPMedia/Visual Pinball X/
+//!   ```
+//! * [`Layout::Standalone`]: VPX Standalone, everything is in the table folder
+//!   ```text
+//!   table/table.vpx
+//!   table/music/
+//!   table/pinmame/roms/
+//!   table/pinmame/altsound/<rom>/
+//!   table/pinmame/altcolor/<rom>/
+//!   ```
+//!   If the table folder has no `pinmame` folder `~/.pinmame` is used.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::paths::{self, Overrides};
+//!
+//! let table = "testdata/completely_blank_table_10_7_4.vpx";
+//! let layout = paths::detect_layout(table);
+//! let paths = paths::resolve(table, layout, &Overrides::default());
+//! println!("roms: {}", paths.roms.display());
+//! println!("rom file: {}", paths.rom_file("tz_94h").display());
+//! ```
+
+use std::path::{Path, PathBuf};
+
+/// A known directory layout of a Visual Pinball install
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+    Desktop,
+    Standalone,
+    Popper,
+}
+
+/// Folders to use instead of the ones from the [`Layout`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Overrides {
+    pub music: Option<PathBuf>,
+    pub roms: Option<PathBuf>,
+    pub altsound: Option<PathBuf>,
+    pub altcolor: Option<PathBuf>,
+}
+
+/// The resolved folders for a table
+///
+/// The folders are not guaranteed to exist.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TablePaths {
+    pub layout: Layout,
+    pub music: PathBuf,
+    pub roms: PathBuf,
+    /// Contains a folder per rom
+    pub altsound: PathBuf,
+    /// Contains a folder per rom
+    pub altcolor: PathBuf,
+    /// The PinUP Popper media folder, only for [`Layout::Popper`]
+    pub pup_media: Option<PathBuf>,
+}
+
+impl TablePaths {
+    pub fn rom_file(&self, rom: &str) -> PathBuf {
+        self.roms.join(format!("{}.zip", rom))
+    }
+
+    pub fn altsound_dir(&self, rom: &str) -> PathBuf {
+        self.altsound.join(rom)
+    }
+
+    pub fn altcolor_dir(&self, rom: &str) -> PathBuf {
+        self.altcolor.join(rom)
+    }
+}
+
+/// Guesses the layout by looking at the folders around the table.
+///
+/// If nothing matches we fall back to [`Layout::Desktop`] on Windows and
+/// [`Layout::Standalone`] on other platforms.
+pub fn detect_layout<P: AsRef<Path>>(table_path: P) -> Layout {
+    let table_dir = table_dir(table_path.as_ref());
+    if table_dir.join("pinmame").is_dir() || table_dir.join("music").is_dir() {
+        return Layout::Standalone;
+    }
+    if let Some(root) = table_dir.parent() {
+        if root.join("VPinMAME").is_dir() || root.join("Music").is_dir() {
+            let popper = root
+                .parent()
+                .map(|install| install.join("PinUPSystem").is_dir())
+                .unwrap_or(false);
+            return if popper {
+                Layout::Popper
+            } else {
+                Layout::Desktop
+            };
+        }
+    }
+    if cfg!(windows) {
+        Layout::Desktop
+    } else {
+        Layout::Standalone
+    }
+}
+
+/// Resolves the folders for a table using a layout, overrides take precedence.
+pub fn resolve<P: AsRef<Path>>(table_path: P, layout: Layout, overrides: &Overrides) -> TablePaths {
+    let table_dir = table_dir(table_path.as_ref());
+    let (music, pinmame, pup_media) = match layout {
+        Layout::Desktop | Layout::Popper => {
+            // tables are in VisualPinball/Tables
+            let root = table_dir.parent().unwrap_or(&table_dir).to_path_buf();
+            let pup_media = match layout {
+                Layout::Popper => root.parent().map(|install| {
+                    install
+                        .join("PinUPSystem")
+                        .join("POPMedia")
+                        .join("Visual Pinball X")
+                }),
+                _ => None,
+            };
+            (root.join("Music"), root.join("VPinMAME"), pup_media)
+        }
+        Layout::Standalone => {
+            let local = table_dir.join("pinmame");
+            let pinmame = if local.is_dir() {
+                local
+            } else {
+                home_pinmame().unwrap_or(local)
+            };
+            (table_dir.join("music"), pinmame, None)
+        }
+    };
+    TablePaths {
+        layout,
+        music: overrides.music.clone().unwrap_or(music),
+        roms: overrides.roms.clone().unwrap_or(pinmame.join("roms")),
+        altsound: overrides
+            .altsound
+            .clone()
+            .unwrap_or(pinmame.join("altsound")),
+        altcolor: overrides
+            .altcolor
+            .clone()
+            .unwrap_or(pinmame.join("altcolor")),
+        pup_media,
+    }
+}
+
+fn table_dir(table_path: &Path) -> PathBuf {
+    table_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+fn home_pinmame() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".pinmame"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use testdir::testdir;
+
+    #[test]
+    fn detect_standalone() -> std::io::Result<()> {
+        let dir = testdir!();
+        let table_dir = dir.join("Blank");
+        fs::create_dir_all(table_dir.join("pinmame").join("roms"))?;
+        let table = table_dir.join("Blank.vpx");
+
+        assert_eq!(detect_layout(&table), Layout::Standalone);
+        let paths = resolve(&table, Layout::Standalone, &Overrides::default());
+        assert_eq!(
+            paths.rom_file("tz_94h"),
+            table_dir.join("pinmame").join("roms").join("tz_94h.zip")
+        );
+        assert_eq!(paths.music, table_dir.join("music"));
+        Ok(())
+    }
+
+    #[test]
+    fn detect_popper() -> std::io::Result<()> {
+        let dir = testdir!();
+        let root = dir.join("VisualPinball");
+        fs::create_dir_all(root.join("Tables"))?;
+        fs::create_dir_all(root.join("VPinMAME"))?;
+        fs::create_dir_all(dir.join("PinUPSystem"))?;
+        let table = root.join("Tables").join("Blank.vpx");
+
+        assert_eq!(detect_layout(&table), Layout::Popper);
+        let paths = resolve(&table, Layout::Popper, &Overrides::default());
+        assert_eq!(
+            paths.altsound_dir("tz_94h"),
+            root.join("VPinMAME").join("altsound").join("tz_94h")
+        );
+        assert_eq!(
+            paths.pup_media,
+            Some(
+                dir.join("PinUPSystem")
+                    .join("POPMedia")
+                    .join("Visual Pinball X")
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_with_overrides() {
+        let overrides = Overrides {
+            roms: Some(PathBuf::from("/roms")),
+            ..Default::default()
+        };
+        let paths = resolve(
+            "VisualPinball/Tables/Blank.vpx",
+            Layout::Desktop,
+            &overrides,
+        );
+        assert_eq!(paths.roms, PathBuf::from("/roms"));
+        assert_eq!(paths.music, PathBuf::from("VisualPinball/Music"));
+        assert_eq!(
+            paths.altcolor,
+            PathBuf::from("VisualPinball/VPinMAME/altcolor")
+        );
+        assert_eq!(paths.pup_media, None);
+    }
+}
@@ -0,0 +1,34 @@
+//! The commonly used types and functions in one place
+//!
+//! The functions are renamed after the format they work on, `vpx::read` becomes
+//! [`read_vpx`] and `vpx::expanded::read` becomes [`read_expanded`]. The modules are
+//! exported as well for everything else.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::prelude::*;
+//!
+//! let vpx: VPX = read_vpx(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for item in &vpx.gameitems {
+//!     if let GameItemEnum::Flipper(flipper) = item {
+//!         println!("flipper {}", flipper.name);
+//!     }
+//! }
+//! ```
+
+pub use crate::directb2s::{self, read as read_directb2s, write as write_directb2s, DirectB2SData};
+pub use crate::vpx::{
+    self,
+    expanded::{
+        self, read as read_expanded, write as write_expanded, WriteError as ExpandedWriteError,
+    },
+    gamedata::GameData,
+    gameitem::GameItemEnum,
+    image::ImageData,
+    open as open_vpx, read as read_vpx,
+    sound::SoundData,
+    write as write_vpx, VpxBuilder, VpxFile, VPX,
+};
+pub use quick_xml::{DeError as DirectB2SReadError, SeError as DirectB2SWriteError};
@@ -0,0 +1,243 @@
+//! Minimal ini file model that keeps comments, blank lines and ordering intact
+//!
+//! Section and key names are matched case-insensitive, like VPinball does.
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Line {
+    Entry {
+        key: String,
+        value: String,
+        /// The original line, so unchanged entries are written back as they were read
+        raw: Option<String>,
+    },
+    /// Comments, blank lines and anything else we don't understand
+    Other(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Section {
+    name: String,
+    lines: Vec<Line>,
+}
+
+/// An ini document
+///
+/// Lines before the first section header are kept in a nameless section. A leading byte order
+/// mark and `\r\n` line endings are written back like they were read.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ini {
+    sections: Vec<Section>,
+    bom: bool,
+    newline: &'static str,
+}
+
+impl Default for Ini {
+    fn default() -> Self {
+        Ini {
+            sections: vec![Section {
+                name: String::new(),
+                lines: Vec::new(),
+            }],
+            bom: false,
+            newline: "\n",
+        }
+    }
+}
+
+impl Ini {
+    pub fn parse(input: &str) -> Ini {
+        let mut ini = Ini::default();
+        let input = match input.strip_prefix('\u{feff}') {
+            Some(rest) => {
+                ini.bom = true;
+                rest
+            }
+            None => input,
+        };
+        if input.contains("\r\n") {
+            ini.newline = "\r\n";
+        }
+        for line in input.lines() {
+            let trimmed = line.trim();
+            if let Some(name) = trimmed
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                ini.sections.push(Section {
+                    name: name.trim().to_string(),
+                    lines: Vec::new(),
+                });
+                continue;
+            }
+            let parsed = match trimmed.split_once('=') {
+                Some((key, value)) if !trimmed.starts_with(';') && !trimmed.starts_with('#') => {
+                    Line::Entry {
+                        key: key.trim().to_string(),
+                        value: value.trim().to_string(),
+                        raw: Some(line.to_string()),
+                    }
+                }
+                _ => Line::Other(line.to_string()),
+            };
+            ini.sections.last_mut().unwrap().lines.push(parsed);
+        }
+        ini
+    }
+
+    /// Names of all sections, in file order
+    pub fn sections(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .filter(|s| !s.name.is_empty())
+            .map(|s| s.name.as_str())
+            .collect()
+    }
+
+    /// All key/value pairs of a section, in file order
+    pub fn entries(&self, section: &str) -> Vec<(&str, &str)> {
+        self.sections
+            .iter()
+            .filter(|s| s.name.eq_ignore_ascii_case(section))
+            .flat_map(|s| s.lines.iter())
+            .filter_map(|line| match line {
+                Line::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+                Line::Other(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries(section)
+            .into_iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    /// Sets a value, adding the section and/or key if they don't exist yet
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let index = match self
+            .sections
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(section))
+        {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section {
+                    name: section.to_string(),
+                    lines: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        };
+        let lines = &mut self.sections[index].lines;
+        for line in lines.iter_mut() {
+            if let Line::Entry {
+                key: k,
+                value: v,
+                raw,
+            } = line
+            {
+                if k.eq_ignore_ascii_case(key) {
+                    *v = value.to_string();
+                    *raw = None;
+                    return;
+                }
+            }
+        }
+        // keep trailing blank lines at the end of the section
+        let insert_at = lines
+            .iter()
+            .rposition(|line| !matches!(line, Line::Other(other) if other.trim().is_empty()))
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+        lines.insert(
+            insert_at,
+            Line::Entry {
+                key: key.to_string(),
+                value: value.to_string(),
+                raw: None,
+            },
+        );
+    }
+
+    /// Removes a key, returning the old value
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<String> {
+        for s in self
+            .sections
+            .iter_mut()
+            .filter(|s| s.name.eq_ignore_ascii_case(section))
+        {
+            let pos = s.lines.iter().position(
+                |line| matches!(line, Line::Entry { key: k, .. } if k.eq_ignore_ascii_case(key)),
+            );
+            if let Some(pos) = pos {
+                if let Line::Entry { value, .. } = s.lines.remove(pos) {
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Ini {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            write!(f, "\u{feff}")?;
+        }
+        let newline = self.newline;
+        for section in &self.sections {
+            if !section.name.is_empty() {
+                write!(f, "[{}]{}", section.name, newline)?;
+            }
+            for line in &section.lines {
+                match line {
+                    Line::Entry { raw: Some(raw), .. } => write!(f, "{}{}", raw, newline)?,
+                    Line::Entry { key, value, .. } => write!(f, "{} = {}{}", key, value, newline)?,
+                    Line::Other(other) => write!(f, "{}{}", other, newline)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_and_write_keeps_comments() {
+        let input = "; VPinballX settings\n[Player]\nBGSet = 1\n; comment\nPlayMusic=0\n\n[Standalone]\nHaptics = 1\n";
+        let mut ini = Ini::parse(input);
+        assert_eq!(ini.sections(), vec!["Player", "Standalone"]);
+        assert_eq!(ini.get("player", "playmusic"), Some("0"));
+
+        ini.set("Player", "PlaySound", "1");
+        ini.set("Player", "BGSet", "0");
+        ini.set("TableOverride", "Exposure", "1.5");
+        assert_eq!(ini.remove("Standalone", "Haptics"), Some("1".to_string()));
+
+        assert_eq!(
+            ini.to_string(),
+            "; VPinballX settings\n[Player]\nBGSet = 0\n; comment\nPlayMusic=0\nPlaySound = 1\n\n[Standalone]\n[TableOverride]\nExposure = 1.5\n"
+        );
+    }
+
+    #[test]
+    fn keeps_byte_order_mark_and_crlf() {
+        let input = "\u{feff}[Player]\r\nBGSet = 1\r\n\r\n[Standalone]\r\nHaptics = 1\r\n";
+        let mut ini = Ini::parse(input);
+        assert_eq!(ini.sections(), vec!["Player", "Standalone"]);
+        assert_eq!(ini.to_string(), input);
+
+        ini.set("Player", "PlayMusic", "0");
+        assert_eq!(
+            ini.to_string(),
+            "\u{feff}[Player]\r\nBGSet = 1\r\nPlayMusic = 0\r\n\r\n[Standalone]\r\nHaptics = 1\r\n"
+        );
+    }
+}
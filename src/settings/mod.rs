@@ -0,0 +1,404 @@
+//! Reading and writing Visual Pinball `VPinballX.ini` settings files
+//!
+//! VPinball 10.8 and VPX Standalone store their settings in an ini file. The same format is
+//! used for the per-table `<tablename>.ini` files that override the global settings.
+//!
+//! Unknown sections, keys and comments are kept as they are when writing the file back.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::settings::Settings;
+//!
+//! let mut settings = Settings::parse("[Player]\nPlayMusic = 1\n");
+//! let mut player = settings.player();
+//! assert_eq!(player.play_music, Some(true));
+//! player.play_music = Some(false);
+//! settings.set_player(&player);
+//! assert_eq!(settings.to_string(), "[Player]\nPlayMusic = 0\n");
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use self::ini::Ini;
+
+pub mod ini;
+pub mod table;
+
+pub const PLAYER_SECTION: &str = "Player";
+pub const STANDALONE_SECTION: &str = "Standalone";
+pub const TABLE_OVERRIDE_SECTION: &str = "TableOverride";
+
+/// A `VPinballX.ini` or per-table ini file
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Settings {
+    pub ini: Ini,
+}
+
+impl Settings {
+    pub fn parse(input: &str) -> Settings {
+        Settings {
+            ini: Ini::parse(input),
+        }
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Settings> {
+        let bytes = fs::read(path)?;
+        Ok(Settings::parse(&String::from_utf8_lossy(&bytes)))
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.ini.get(section, key)
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        self.ini.set(section, key, value)
+    }
+
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<String> {
+        self.ini.remove(section, key)
+    }
+
+    pub fn player(&self) -> PlayerSettings {
+        let section = Section::new(&self.ini, PLAYER_SECTION);
+        PlayerSettings {
+            bg_set: section.get("BGSet"),
+            playfield_full_screen: section.get("PlayfieldFullScreen"),
+            playfield_width: section.get("PlayfieldWidth"),
+            playfield_height: section.get("PlayfieldHeight"),
+            max_framerate: section.get("MaxFramerate"),
+            sync_mode: section.get("SyncMode"),
+            aa_factor: section.get("AAFactor"),
+            msaa_samples: section.get("MSAASamples"),
+            fxaa: section.get("FXAA"),
+            sharpen: section.get("Sharpen"),
+            disable_ao: section.get("DisableAO"),
+            dynamic_ao: section.get("DynamicAO"),
+            ss_refl: section.get("SSRefl"),
+            pf_reflection: section.get("PFReflection"),
+            force_bloom_off: section.get("ForceBloomOff"),
+            override_table_emission_scale: section.get("OverrideTableEmissionScale"),
+            emission_scale: section.get("EmissionScale"),
+            ball_trail: section.get("BallTrail"),
+            play_music: section.get("PlayMusic"),
+            play_sound: section.get("PlaySound"),
+            music_volume: section.get("MusicVolume"),
+            sound_volume: section.get("SoundVolume"),
+        }
+    }
+
+    /// Stores the player settings, fields that are `None` are left untouched
+    pub fn set_player(&mut self, player: &PlayerSettings) {
+        let mut section = SectionMut::new(&mut self.ini, PLAYER_SECTION);
+        section.set("BGSet", &player.bg_set);
+        section.set("PlayfieldFullScreen", &player.playfield_full_screen);
+        section.set("PlayfieldWidth", &player.playfield_width);
+        section.set("PlayfieldHeight", &player.playfield_height);
+        section.set("MaxFramerate", &player.max_framerate);
+        section.set("SyncMode", &player.sync_mode);
+        section.set("AAFactor", &player.aa_factor);
+        section.set("MSAASamples", &player.msaa_samples);
+        section.set("FXAA", &player.fxaa);
+        section.set("Sharpen", &player.sharpen);
+        section.set("DisableAO", &player.disable_ao);
+        section.set("DynamicAO", &player.dynamic_ao);
+        section.set("SSRefl", &player.ss_refl);
+        section.set("PFReflection", &player.pf_reflection);
+        section.set("ForceBloomOff", &player.force_bloom_off);
+        section.set(
+            "OverrideTableEmissionScale",
+            &player.override_table_emission_scale,
+        );
+        section.set("EmissionScale", &player.emission_scale);
+        section.set("BallTrail", &player.ball_trail);
+        section.set("PlayMusic", &player.play_music);
+        section.set("PlaySound", &player.play_sound);
+        section.set("MusicVolume", &player.music_volume);
+        section.set("SoundVolume", &player.sound_volume);
+    }
+
+    pub fn standalone(&self) -> StandaloneSettings {
+        let section = Section::new(&self.ini, STANDALONE_SECTION);
+        StandaloneSettings {
+            vpreg_path: section.get("VPRegPath"),
+            pinmame_path: section.get("PinMAMEPath"),
+            pinmame_ini_path: section.get("PinMAMEIniPath"),
+            alt_color: section.get("AltColor"),
+            alt_sound: section.get("AltSound"),
+            haptics: section.get("Haptics"),
+            reset_log_on_play: section.get("ResetLogOnPlay"),
+            rendering_mode_override: section.get("RenderingModeOverride"),
+        }
+    }
+
+    /// Stores the standalone settings, fields that are `None` are left untouched
+    pub fn set_standalone(&mut self, standalone: &StandaloneSettings) {
+        let mut section = SectionMut::new(&mut self.ini, STANDALONE_SECTION);
+        section.set("VPRegPath", &standalone.vpreg_path);
+        section.set("PinMAMEPath", &standalone.pinmame_path);
+        section.set("PinMAMEIniPath", &standalone.pinmame_ini_path);
+        section.set("AltColor", &standalone.alt_color);
+        section.set("AltSound", &standalone.alt_sound);
+        section.set("Haptics", &standalone.haptics);
+        section.set("ResetLogOnPlay", &standalone.reset_log_on_play);
+        section.set("RenderingModeOverride", &standalone.rendering_mode_override);
+    }
+
+    pub fn table_override(&self) -> TableOverrideSettings {
+        let section = Section::new(&self.ini, TABLE_OVERRIDE_SECTION);
+        TableOverrideSettings {
+            difficulty: section.get("Difficulty"),
+            exposure: section.get("Exposure"),
+            tone_mapper: section.get("ToneMapper"),
+        }
+    }
+
+    /// Stores the table overrides, fields that are `None` are left untouched
+    pub fn set_table_override(&mut self, table_override: &TableOverrideSettings) {
+        let mut section = SectionMut::new(&mut self.ini, TABLE_OVERRIDE_SECTION);
+        section.set("Difficulty", &table_override.difficulty);
+        section.set("Exposure", &table_override.exposure);
+        section.set("ToneMapper", &table_override.tone_mapper);
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ini.fmt(f)
+    }
+}
+
+/// The `[Player]` section
+///
+/// Values that are missing or can not be parsed are `None`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PlayerSettings {
+    /// 0 = desktop, 1 = fullscreen, 2 = full single screen (FSS)
+    pub bg_set: Option<u32>,
+    pub playfield_full_screen: Option<bool>,
+    pub playfield_width: Option<u32>,
+    pub playfield_height: Option<u32>,
+    pub max_framerate: Option<f32>,
+    pub sync_mode: Option<u32>,
+    /// Supersampling factor
+    pub aa_factor: Option<f32>,
+    pub msaa_samples: Option<u32>,
+    pub fxaa: Option<u32>,
+    pub sharpen: Option<u32>,
+    pub disable_ao: Option<bool>,
+    pub dynamic_ao: Option<bool>,
+    /// Screen space reflections
+    pub ss_refl: Option<bool>,
+    /// Playfield reflection quality, 0 disables reflections
+    pub pf_reflection: Option<u32>,
+    pub force_bloom_off: Option<bool>,
+    /// Use [`PlayerSettings::emission_scale`] instead of the day/night value of the table
+    pub override_table_emission_scale: Option<bool>,
+    /// Day/night, 0.0 is night and 1.0 is day
+    pub emission_scale: Option<f32>,
+    pub ball_trail: Option<bool>,
+    pub play_music: Option<bool>,
+    pub play_sound: Option<bool>,
+    pub music_volume: Option<i32>,
+    pub sound_volume: Option<i32>,
+}
+
+/// The `[Standalone]` section, only used by VPX Standalone
+///
+/// Values that are missing or can not be parsed are `None`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct StandaloneSettings {
+    pub vpreg_path: Option<String>,
+    pub pinmame_path: Option<String>,
+    pub pinmame_ini_path: Option<String>,
+    pub alt_color: Option<bool>,
+    pub alt_sound: Option<bool>,
+    pub haptics: Option<bool>,
+    pub reset_log_on_play: Option<bool>,
+    pub rendering_mode_override: Option<i32>,
+}
+
+/// The `[TableOverride]` section, only useful in per-table ini files
+///
+/// Values that are missing or can not be parsed are `None`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TableOverrideSettings {
+    pub difficulty: Option<f32>,
+    pub exposure: Option<f32>,
+    /// See [`crate::vpx::gamedata::ToneMapper`]
+    pub tone_mapper: Option<u32>,
+}
+
+/// Default location of `VPinballX.ini` for the current platform.
+///
+/// On Windows this is `%APPDATA%\VPinballX\VPinballX.ini`, on other platforms
+/// (VPX Standalone) `~/.vpinball/VPinballX.ini`.
+pub fn default_ini_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("APPDATA").map(|appdata| {
+            PathBuf::from(appdata)
+                .join("VPinballX")
+                .join("VPinballX.ini")
+        })
+    } else {
+        std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".vpinball").join("VPinballX.ini"))
+    }
+}
+
+/// Values as they are stored in the ini file
+trait IniValue: Sized {
+    fn from_ini(value: &str) -> Option<Self>;
+    fn to_ini(&self) -> String;
+}
+
+impl IniValue for bool {
+    fn from_ini(value: &str) -> Option<Self> {
+        match value {
+            "1" => Some(true),
+            "0" => Some(false),
+            _ if value.eq_ignore_ascii_case("true") => Some(true),
+            _ if value.eq_ignore_ascii_case("false") => Some(false),
+            _ => None,
+        }
+    }
+
+    fn to_ini(&self) -> String {
+        if *self { "1" } else { "0" }.to_string()
+    }
+}
+
+impl IniValue for u32 {
+    fn from_ini(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn to_ini(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl IniValue for i32 {
+    fn from_ini(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn to_ini(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Always written with a `.` as decimal separator, a `,` written by other tools is accepted
+impl IniValue for f32 {
+    fn from_ini(value: &str) -> Option<Self> {
+        value
+            .parse()
+            .ok()
+            .or_else(|| value.replace(',', ".").parse().ok())
+    }
+
+    fn to_ini(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl IniValue for String {
+    fn from_ini(value: &str) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn to_ini(&self) -> String {
+        self.clone()
+    }
+}
+
+struct Section<'a> {
+    ini: &'a Ini,
+    name: &'a str,
+}
+
+impl<'a> Section<'a> {
+    fn new(ini: &'a Ini, name: &'a str) -> Self {
+        Section { ini, name }
+    }
+
+    fn get<T: IniValue>(&self, key: &str) -> Option<T> {
+        self.ini.get(self.name, key).and_then(T::from_ini)
+    }
+}
+
+struct SectionMut<'a> {
+    ini: &'a mut Ini,
+    name: &'a str,
+}
+
+impl<'a> SectionMut<'a> {
+    fn new(ini: &'a mut Ini, name: &'a str) -> Self {
+        SectionMut { ini, name }
+    }
+
+    fn set<T: IniValue>(&mut self, key: &str, value: &Option<T>) {
+        if let Some(value) = value {
+            self.ini.set(self.name, key, &value.to_ini());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn read_write_typed_sections() -> io::Result<()> {
+        let input = "[Player]\nBGSet = 1\nPFReflection = 4\nMaxFramerate = 60.5\nSoundVolume = invalid\n\n[Standalone]\nPinMAMEPath = /home/pin/.pinmame/\nAltSound = 1\n";
+        let mut settings = Settings::parse(input);
+
+        let player = settings.player();
+        assert_eq!(player.bg_set, Some(1));
+        assert_eq!(player.pf_reflection, Some(4));
+        assert_eq!(player.max_framerate, Some(60.5));
+        assert_eq!(player.sound_volume, None);
+        let standalone = settings.standalone();
+        assert_eq!(
+            standalone.pinmame_path,
+            Some("/home/pin/.pinmame/".to_string())
+        );
+        assert_eq!(standalone.alt_sound, Some(true));
+        assert_eq!(settings.table_override(), TableOverrideSettings::default());
+
+        settings.set_player(&PlayerSettings {
+            pf_reflection: Some(0),
+            ..Default::default()
+        });
+        settings.set_table_override(&TableOverrideSettings {
+            exposure: Some(1.2),
+            ..Default::default()
+        });
+
+        let path = testdir!().join("VPinballX.ini");
+        settings.write(&path)?;
+        let read = Settings::read(&path)?;
+        assert_eq!(read.player().pf_reflection, Some(0));
+        assert_eq!(read.player().bg_set, Some(1));
+        assert_eq!(read.get("Player", "SoundVolume"), Some("invalid"));
+        assert_eq!(read.table_override().exposure, Some(1.2));
+        Ok(())
+    }
+
+    #[test]
+    fn decimal_separators() {
+        let mut settings = Settings::parse("[TableOverride]\nExposure = 1,5\n");
+        assert_eq!(settings.table_override().exposure, Some(1.5));
+        settings.set_table_override(&settings.table_override());
+        assert_eq!(settings.get("TableOverride", "Exposure"), Some("1.5"));
+    }
+}
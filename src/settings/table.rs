@@ -0,0 +1,207 @@
+//! Per-table `<tablename>.ini` files
+//!
+//! VPinball 10.8 looks for an ini file next to the table with the same name. Any setting in
+//! there overrides the global `VPinballX.ini` for that table only.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::settings::table::{self, TableTweaks};
+//! use vpin::vpx::VPX;
+//!
+//! let vpx = VPX::default();
+//! let tweaks = TableTweaks {
+//!     disable_reflections: true,
+//!     day_night: Some(0.2),
+//!     ..Default::default()
+//! };
+//! let settings = table::generate(&vpx, &tweaks);
+//! assert_eq!(table::TableTweaks::from_settings(&settings), tweaks);
+//! println!("{}", table::table_ini_path("tables/Blank.vpx").display());
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{Settings, PLAYER_SECTION, TABLE_OVERRIDE_SECTION};
+use crate::vpx::gamedata::ToneMapper;
+use crate::vpx::VPX;
+
+/// Commonly used per-table settings
+///
+/// Settings that are `None` (or `false`) are not overridden, the global setting is used.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TableTweaks {
+    /// Turns off the playfield reflections
+    pub disable_reflections: bool,
+    /// Day/night, 0.0 is night and 1.0 is day
+    pub day_night: Option<f32>,
+    pub difficulty: Option<f32>,
+    pub tone_mapper: Option<ToneMapper>,
+    pub exposure: Option<f32>,
+}
+
+impl TableTweaks {
+    /// Extracts the tweaks from an existing per-table ini file
+    pub fn from_settings(settings: &Settings) -> TableTweaks {
+        let player = settings.player();
+        let table_override = settings.table_override();
+        TableTweaks {
+            disable_reflections: player.pf_reflection == Some(0),
+            day_night: player
+                .emission_scale
+                .filter(|_| player.override_table_emission_scale == Some(true)),
+            difficulty: table_override.difficulty,
+            tone_mapper: table_override.tone_mapper.map(ToneMapper::from),
+            exposure: table_override.exposure,
+        }
+    }
+}
+
+/// The location of the per-table ini file for a `vpx` file
+pub fn table_ini_path<P: AsRef<Path>>(vpx_path: P) -> PathBuf {
+    vpx_path.as_ref().with_extension("ini")
+}
+
+/// Reads the per-table ini file for a `vpx` file, returns `None` if there is none
+pub fn read<P: AsRef<Path>>(vpx_path: P) -> io::Result<Option<Settings>> {
+    let path = table_ini_path(vpx_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Settings::read(path).map(Some)
+}
+
+/// Generates a new per-table ini file
+pub fn generate(vpx: &VPX, tweaks: &TableTweaks) -> Settings {
+    let mut settings = Settings::default();
+    apply(vpx, tweaks, &mut settings);
+    settings
+}
+
+/// Updates an existing per-table ini file with the tweaks.
+///
+/// Tweaks that match what is stored in the table are not written, and existing overrides
+/// for them are removed. Other settings in the file are left untouched.
+pub fn apply(vpx: &VPX, tweaks: &TableTweaks, settings: &mut Settings) {
+    let gamedata = &vpx.gamedata;
+
+    if tweaks.disable_reflections {
+        settings.set(PLAYER_SECTION, "PFReflection", "0");
+    } else if settings.player().pf_reflection == Some(0) {
+        settings.remove(PLAYER_SECTION, "PFReflection");
+    }
+
+    match tweaks
+        .day_night
+        .filter(|day_night| *day_night != gamedata.global_emission_scale)
+    {
+        Some(day_night) => {
+            settings.set(PLAYER_SECTION, "OverrideTableEmissionScale", "1");
+            settings.set(PLAYER_SECTION, "EmissionScale", &day_night.to_string());
+        }
+        None => {
+            settings.remove(PLAYER_SECTION, "OverrideTableEmissionScale");
+            settings.remove(PLAYER_SECTION, "EmissionScale");
+        }
+    }
+
+    match tweaks
+        .difficulty
+        .filter(|difficulty| *difficulty != gamedata.global_difficulty)
+    {
+        Some(difficulty) => {
+            settings.set(
+                TABLE_OVERRIDE_SECTION,
+                "Difficulty",
+                &difficulty.to_string(),
+            );
+        }
+        None => {
+            settings.remove(TABLE_OVERRIDE_SECTION, "Difficulty");
+        }
+    }
+
+    let table_tone_mapper = gamedata.tone_mapper.unwrap_or(ToneMapper::Reinhard);
+    match tweaks
+        .tone_mapper
+        .filter(|tone_mapper| *tone_mapper != table_tone_mapper)
+    {
+        Some(tone_mapper) => {
+            settings.set(
+                TABLE_OVERRIDE_SECTION,
+                "ToneMapper",
+                &u32::from(&tone_mapper).to_string(),
+            );
+        }
+        None => {
+            settings.remove(TABLE_OVERRIDE_SECTION, "ToneMapper");
+        }
+    }
+
+    match tweaks.exposure {
+        Some(exposure) => {
+            settings.set(TABLE_OVERRIDE_SECTION, "Exposure", &exposure.to_string());
+        }
+        None => {
+            settings.remove(TABLE_OVERRIDE_SECTION, "Exposure");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn generate_and_parse() {
+        let mut vpx = VPX::default();
+        vpx.gamedata.global_difficulty = 0.5;
+        vpx.gamedata.tone_mapper = Some(ToneMapper::AgX);
+        let tweaks = TableTweaks {
+            disable_reflections: true,
+            day_night: Some(0.25),
+            difficulty: Some(0.5),
+            tone_mapper: Some(ToneMapper::Filmic),
+            exposure: None,
+        };
+
+        let settings = generate(&vpx, &tweaks);
+
+        assert_eq!(
+            settings.to_string(),
+            "[Player]\nPFReflection = 0\nOverrideTableEmissionScale = 1\nEmissionScale = 0.25\n[TableOverride]\nToneMapper = 2\n"
+        );
+        assert_eq!(
+            TableTweaks::from_settings(&settings),
+            TableTweaks {
+                difficulty: None,
+                ..tweaks
+            }
+        );
+    }
+
+    #[test]
+    fn apply_keeps_other_settings() {
+        let vpx = VPX::default();
+        let mut settings = Settings::parse(
+            "[Player]\nBGSet = 1\nPFReflection = 0\n[TableOverride]\nExposure = 1.5\n",
+        );
+
+        apply(&vpx, &TableTweaks::default(), &mut settings);
+
+        assert_eq!(
+            settings.to_string(),
+            "[Player]\nBGSet = 1\n[TableOverride]\n"
+        );
+    }
+
+    #[test]
+    fn ini_path() {
+        assert_eq!(
+            table_ini_path("tables/My Table (1990).vpx"),
+            PathBuf::from("tables/My Table (1990).ini")
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Models for the [Virtual Pinball Spreadsheet](https://virtualpinballspreadsheet.github.io/) database
+//!
+//! The database is published as a single json file `vpsdb.json` containing all known games
+//! with the table, backglass, rom and other files made for them. Use [`find_matches`] to find
+//! the database entry for a local table.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpin::vps::{self, LocalTable};
+//!
+//! let file = std::fs::File::open("vpsdb.json").unwrap();
+//! let games = vps::read(std::io::BufReader::new(file)).unwrap();
+//! let table = LocalTable {
+//!     file_name: "Twilight Zone (Bally 1993) VPW v1.0.vpx".to_string(),
+//!     rom: Some("tz_94h".to_string()),
+//!     ..Default::default()
+//! };
+//! if let Some(found) = vps::find_matches(&games, &table).first() {
+//!     println!("{} ({:?})", found.game.name, found.reasons);
+//! }
+//! ```
+
+use crate::names::parse_table_filename;
+use crate::vpx::dependencies::find_rom_name;
+use crate::vpx::VPX;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::io;
+use std::path::Path;
+
+/// A game (physical or original pinball machine) in the database
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Game {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+    #[serde(default)]
+    pub year: Option<u16>,
+    /// `EM`, `SS`, `PM` (pure mechanical) or `DG` (digital/original)
+    #[serde(rename = "type", default)]
+    pub game_type: Option<String>,
+    #[serde(default)]
+    pub players: Option<u8>,
+    #[serde(default)]
+    pub ipdb_url: Option<String>,
+    #[serde(default)]
+    pub theme: Vec<String>,
+    #[serde(default)]
+    pub designers: Vec<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub img_url: Option<String>,
+    /// Milliseconds since the unix epoch
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+    #[serde(default)]
+    pub last_created_at: Option<u64>,
+    #[serde(default)]
+    pub table_files: Vec<TableFile>,
+    #[serde(default)]
+    pub b2s_files: Vec<Resource>,
+    /// The rom name is in the [`Resource::version`] field
+    #[serde(default)]
+    pub rom_files: Vec<Resource>,
+    #[serde(default)]
+    pub alt_sound_files: Vec<Resource>,
+    #[serde(default)]
+    pub alt_color_files: Vec<Resource>,
+    #[serde(default)]
+    pub pup_pack_files: Vec<Resource>,
+    #[serde(default)]
+    pub pov_files: Vec<Resource>,
+    #[serde(default)]
+    pub topper_files: Vec<Resource>,
+    #[serde(default)]
+    pub wheel_art_files: Vec<Resource>,
+    #[serde(default)]
+    pub media_pack_files: Vec<Resource>,
+    #[serde(default)]
+    pub sound_files: Vec<Resource>,
+}
+
+impl Game {
+    /// The rom names used by the tables for this game
+    pub fn rom_names(&self) -> impl Iterator<Item = &str> {
+        self.rom_files
+            .iter()
+            .filter_map(|rom| rom.version.as_deref())
+    }
+}
+
+/// A table file for a game
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableFile {
+    pub id: String,
+    /// `VPX`, `FP`, `VP9`, ...
+    #[serde(default)]
+    pub table_format: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub edition: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<Url>,
+    #[serde(default)]
+    pub img_url: Option<String>,
+    /// Hex encoded hash of the table file.
+    ///
+    /// The public database does not contain hashes, but collections built on top of it do.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+}
+
+/// Any other file for a game, like a backglass, rom or pup pack
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    pub id: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<Url>,
+    #[serde(default)]
+    pub img_url: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct Url {
+    pub url: String,
+    #[serde(default)]
+    pub broken: bool,
+}
+
+/// Reads the `vpsdb.json` database
+pub fn read<R: io::Read>(reader: R) -> io::Result<Vec<Game>> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// What we know about a table on disk
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LocalTable {
+    pub file_name: String,
+    /// The name in the table info
+    pub table_name: Option<String>,
+    pub rom: Option<String>,
+    /// Hex encoded hash of the table file, computed the same way as the hashes in the database
+    pub hash: Option<String>,
+}
+
+impl LocalTable {
+    /// Collects the table name and rom name from a loaded vpx file
+    pub fn from_vpx<P: AsRef<Path>>(path: P, vpx: &VPX) -> LocalTable {
+        LocalTable {
+            file_name: path
+                .as_ref()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            table_name: vpx.info.table_name.clone(),
+            rom: find_rom_name(&vpx.gamedata.code.string),
+            hash: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MatchReason {
+    Hash,
+    Rom,
+    Name,
+    Manufacturer,
+    Year,
+}
+
+/// A database game matching a local table
+#[derive(Debug, PartialEq, Clone)]
+pub struct VpsMatch<'a> {
+    pub game: &'a Game,
+    /// Only set when the table file could be identified by its hash
+    pub table_file: Option<&'a TableFile>,
+    pub reasons: Vec<MatchReason>,
+    /// Higher is better
+    pub score: u32,
+}
+
+/// Finds the games matching a local table, best match first.
+///
+/// A hash match always wins. Otherwise the rom name and the title parsed from the file name
+/// or table name are used, with the manufacturer and year to break ties. Games that only
+/// match on manufacturer or year are not returned.
+pub fn find_matches<'a>(games: &'a [Game], table: &LocalTable) -> Vec<VpsMatch<'a>> {
+    let parsed = parse_table_filename(&table.file_name);
+    let titles: Vec<String> = [Some(parsed.title.as_str()), table.table_name.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(normalize)
+        .filter(|title| !title.is_empty())
+        .collect();
+
+    let mut matches: Vec<VpsMatch> = games
+        .iter()
+        .filter_map(|game| {
+            let mut reasons = Vec::new();
+            let mut score = 0;
+            let table_file = table.hash.as_ref().and_then(|hash| {
+                game.table_files.iter().find(|file| {
+                    file.hash
+                        .as_ref()
+                        .is_some_and(|h| h.eq_ignore_ascii_case(hash))
+                })
+            });
+            if table_file.is_some() {
+                reasons.push(MatchReason::Hash);
+                score += 1000;
+            }
+            if let Some(rom) = &table.rom {
+                if game.rom_names().any(|name| name.eq_ignore_ascii_case(rom)) {
+                    reasons.push(MatchReason::Rom);
+                    score += 50;
+                }
+            }
+            if titles.contains(&normalize(&game.name)) {
+                reasons.push(MatchReason::Name);
+                score += 30;
+            }
+            if reasons.is_empty() {
+                return None;
+            }
+            let same_manufacturer = parsed
+                .manufacturer
+                .as_ref()
+                .zip(game.manufacturer.as_ref())
+                .is_some_and(|(a, b)| normalize(a) == normalize(b));
+            if same_manufacturer {
+                reasons.push(MatchReason::Manufacturer);
+                score += 10;
+            }
+            if parsed.year.is_some() && parsed.year == game.year {
+                reasons.push(MatchReason::Year);
+                score += 10;
+            }
+            Some(VpsMatch {
+                game,
+                table_file,
+                reasons,
+                score,
+            })
+        })
+        .collect();
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+/// Lowercase alphanumeric characters only, without a leading "the"
+fn normalize(name: &str) -> String {
+    let name = name.trim();
+    let name = match name.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("the ") => &name[4..],
+        _ => name,
+    };
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const DB: &str = r#"[
+      {
+        "id": "tz",
+        "name": "Twilight Zone",
+        "manufacturer": "Bally",
+        "year": 1993,
+        "type": "SS",
+        "players": 4,
+        "theme": ["Fantasy"],
+        "updatedAt": 1700000000000,
+        "tableFiles": [
+          {
+            "id": "tz-vpw",
+            "tableFormat": "VPX",
+            "authors": ["VPW"],
+            "version": "1.0",
+            "urls": [{"url": "https://example.com/tz", "broken": false}],
+            "hash": "ABCDEF"
+          }
+        ],
+        "romFiles": [{"id": "tz-rom", "version": "tz_94h"}],
+        "someNewField": true
+      },
+      {
+        "id": "tz-mod",
+        "name": "The Twilight Zone",
+        "manufacturer": "Stern",
+        "year": 2020
+      },
+      {
+        "id": "pf",
+        "name": "Police Force",
+        "manufacturer": "Williams",
+        "year": 1989,
+        "romFiles": [{"id": "pf-rom", "version": "polic_l4"}]
+      }
+    ]"#;
+
+    #[test]
+    fn read_database() {
+        let games = read(DB.as_bytes()).unwrap();
+        assert_eq!(games.len(), 3);
+        let tz = &games[0];
+        assert_eq!(tz.game_type.as_deref(), Some("SS"));
+        assert_eq!(tz.table_files[0].urls[0].url, "https://example.com/tz");
+        assert_eq!(tz.rom_names().collect::<Vec<_>>(), vec!["tz_94h"]);
+        assert!(games[1].table_files.is_empty());
+    }
+
+    #[test]
+    fn match_by_name_and_rom() {
+        let games = read(DB.as_bytes()).unwrap();
+        let table = LocalTable {
+            file_name: "Twilight Zone (Bally 1993) VPW v1.0.vpx".to_string(),
+            rom: Some("TZ_94H".to_string()),
+            ..Default::default()
+        };
+
+        let matches = find_matches(&games, &table);
+
+        let found = matches
+            .iter()
+            .map(|m| (m.game.id.as_str(), m.reasons.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "tz",
+                    vec![
+                        MatchReason::Rom,
+                        MatchReason::Name,
+                        MatchReason::Manufacturer,
+                        MatchReason::Year
+                    ]
+                ),
+                ("tz-mod", vec![MatchReason::Name]),
+            ]
+        );
+        assert_eq!(matches[0].table_file, None);
+    }
+
+    #[test]
+    fn match_by_hash() {
+        let games = read(DB.as_bytes()).unwrap();
+        let table = LocalTable {
+            file_name: "tz.vpx".to_string(),
+            hash: Some("abcdef".to_string()),
+            ..Default::default()
+        };
+
+        let matches = find_matches(&games, &table);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].reasons, vec![MatchReason::Hash]);
+        assert_eq!(matches[0].table_file.map(|f| f.id.as_str()), Some("tz-vpw"));
+    }
+
+    #[test]
+    fn local_table_from_vpx() {
+        let mut vpx = VPX::default();
+        vpx.info.table_name = Some("Police Force".to_string());
+        vpx.set_script("Const cGameName = \"polic_l4\"".to_string());
+
+        let table = LocalTable::from_vpx("/tables/pf.vpx", &vpx);
+
+        assert_eq!(table.file_name, "pf.vpx");
+        let games = read(DB.as_bytes()).unwrap();
+        let matches = find_matches(&games, &table);
+        assert_eq!(matches[0].game.id, "pf");
+        assert_eq!(
+            matches[0].reasons,
+            vec![MatchReason::Rom, MatchReason::Name]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Provenance of images and sounds: who made them, under which license and where they come from
+//!
+//! Visual Pinball has no place for this information, so it is stored as json in the extra
+//! stream [`ASSET_METADATA_STREAM`], which Visual Pinball ignores. Assets are referenced by
+//! name. In an expanded directory the metadata is part of the entries in `images.json` and
+//! `sounds.json`.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::VPX;
+//! use vpin::vpx::assetmeta::{read_asset_metadata, write_asset_metadata, AssetMetadata};
+//!
+//! let mut vpx = VPX::default();
+//! let mut index = read_asset_metadata(&vpx).unwrap();
+//! index.images.insert(
+//!     "Playfield".to_string(),
+//!     AssetMetadata {
+//!         author: Some("someone".to_string()),
+//!         license: Some("CC-BY-4.0".to_string()),
+//!         source_url: None,
+//!     },
+//! );
+//! write_asset_metadata(&mut vpx, &index).unwrap();
+//! assert_eq!(read_asset_metadata(&vpx).unwrap(), index);
+//! ```
+
+use super::{ExtraStream, VPX};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Path of the extra stream, see [`ExtraStream::path`]
+pub const ASSET_METADATA_STREAM: &str = "GameStg/AssetMetadata";
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct AssetMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Preferably an SPDX license identifier, eg `CC-BY-4.0`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+impl AssetMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none() && self.license.is_none() && self.source_url.is_none()
+    }
+}
+
+/// The metadata of all assets of a table, by asset name
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct AssetMetadataIndex {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub images: BTreeMap<String, AssetMetadata>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sounds: BTreeMap<String, AssetMetadata>,
+}
+
+impl AssetMetadataIndex {
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty() && self.sounds.is_empty()
+    }
+}
+
+/// Reads the metadata stream, an empty index if the table has none.
+pub fn read_asset_metadata(vpx: &VPX) -> io::Result<AssetMetadataIndex> {
+    match vpx
+        .extra_streams
+        .iter()
+        .find(|stream| stream.path == ASSET_METADATA_STREAM)
+    {
+        Some(stream) => serde_json::from_slice(&stream.data).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid asset metadata: {}", e),
+            )
+        }),
+        None => Ok(AssetMetadataIndex::default()),
+    }
+}
+
+/// Replaces the metadata stream, entries without any metadata are left out.
+///
+/// The stream is removed if nothing is left.
+pub fn write_asset_metadata(vpx: &mut VPX, index: &AssetMetadataIndex) -> io::Result<()> {
+    let without_empty = |entries: &BTreeMap<String, AssetMetadata>| {
+        entries
+            .iter()
+            .filter(|(_, metadata)| !metadata.is_empty())
+            .map(|(name, metadata)| (name.clone(), metadata.clone()))
+            .collect()
+    };
+    let index = AssetMetadataIndex {
+        images: without_empty(&index.images),
+        sounds: without_empty(&index.sounds),
+    };
+    let position = vpx
+        .extra_streams
+        .iter()
+        .position(|stream| stream.path == ASSET_METADATA_STREAM);
+    if index.is_empty() {
+        if let Some(position) = position {
+            vpx.extra_streams.remove(position);
+        }
+        return Ok(());
+    }
+    let data = serde_json::to_vec_pretty(&index)?;
+    match position {
+        Some(position) => vpx.extra_streams[position].data = data,
+        None => vpx.extra_streams.push(ExtraStream {
+            path: ASSET_METADATA_STREAM.to_string(),
+            data,
+        }),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn round_trip_through_vpx_file() -> io::Result<()> {
+        let mut vpx = VPX::default();
+        let mut index = AssetMetadataIndex::default();
+        index.sounds.insert(
+            "Knocker".to_string(),
+            AssetMetadata {
+                source_url: Some("https://example.com/knocker".to_string()),
+                ..Default::default()
+            },
+        );
+        index
+            .images
+            .insert("Empty".to_string(), AssetMetadata::default());
+        write_asset_metadata(&mut vpx, &index)?;
+        index.images.clear();
+
+        let path = testdir!().join("test.vpx");
+        crate::vpx::write(&path, &vpx)?;
+        let read = crate::vpx::read(&path)?;
+        assert_eq!(read_asset_metadata(&read)?, index);
+
+        write_asset_metadata(&mut vpx, &AssetMetadataIndex::default())?;
+        assert_eq!(vpx.extra_streams, vec![]);
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! Checks for images that waste memory or cause rendering problems
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::audit::{audit_images, ImageAuditSettings};
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let audit = audit_images(&vpx.images, &ImageAuditSettings::default());
+//! println!("estimated VRAM: {} MiB", audit.estimated_vram / 1024 / 1024);
+//! for issue in audit.issues {
+//!     println!("{}", issue);
+//! }
+//! ```
+
+use super::image::ImageData;
+use image::ImageReader;
+use std::fmt;
+use std::io::Cursor;
+
+/// Thresholds for [`audit_images`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImageAuditSettings {
+    /// Textures wider or higher than this are reported, many GPUs can not handle more than 4096
+    pub max_dimension: u32,
+    /// Non power of two textures need more memory on some hardware
+    pub check_power_of_two: bool,
+    /// Reported if the estimated VRAM of all images together exceeds this number of bytes
+    pub vram_budget: Option<u64>,
+}
+
+impl Default for ImageAuditSettings {
+    fn default() -> Self {
+        ImageAuditSettings {
+            max_dimension: 4096,
+            check_power_of_two: true,
+            vram_budget: None,
+        }
+    }
+}
+
+/// A problem found by [`audit_images`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ImageIssue {
+    /// The width/height stored in the vpx does not match the encoded image
+    DimensionMismatch {
+        name: String,
+        recorded: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// The encoded image could not be read
+    Unreadable {
+        name: String,
+    },
+    Oversized {
+        name: String,
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    },
+    NonPowerOfTwo {
+        name: String,
+        width: u32,
+        height: u32,
+    },
+    VramBudgetExceeded {
+        estimated: u64,
+        budget: u64,
+    },
+}
+
+impl fmt::Display for ImageIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageIssue::DimensionMismatch {
+                name,
+                recorded,
+                actual,
+            } => write!(
+                f,
+                "Image {} is {}x{} but recorded as {}x{}",
+                name, actual.0, actual.1, recorded.0, recorded.1
+            ),
+            ImageIssue::Unreadable { name } => write!(f, "Image {} can not be read", name),
+            ImageIssue::Oversized {
+                name,
+                width,
+                height,
+                max_dimension,
+            } => write!(
+                f,
+                "Image {} is {}x{}, larger than {}",
+                name, width, height, max_dimension
+            ),
+            ImageIssue::NonPowerOfTwo {
+                name,
+                width,
+                height,
+            } => write!(
+                f,
+                "Image {} is {}x{}, not a power of two",
+                name, width, height
+            ),
+            ImageIssue::VramBudgetExceeded { estimated, budget } => write!(
+                f,
+                "Images need an estimated {} bytes of VRAM, more than {}",
+                estimated, budget
+            ),
+        }
+    }
+}
+
+/// The result of [`audit_images`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImageAudit {
+    pub issues: Vec<ImageIssue>,
+    /// Uncompressed 32 bit textures including mipmaps, in bytes
+    pub estimated_vram: u64,
+}
+
+/// Checks the dimensions of all images and estimates the VRAM they need.
+///
+/// Linked images without data are skipped.
+pub fn audit_images(images: &[ImageData], settings: &ImageAuditSettings) -> ImageAudit {
+    let mut issues = Vec::new();
+    let mut estimated_vram = 0;
+    for image in images {
+        let (width, height) = match &image.jpeg {
+            Some(jpeg) => {
+                let dimensions = ImageReader::new(Cursor::new(&jpeg.data))
+                    .with_guessed_format()
+                    .ok()
+                    .and_then(|reader| reader.into_dimensions().ok());
+                match dimensions {
+                    Some(actual) => {
+                        if actual != (image.width, image.height) {
+                            issues.push(ImageIssue::DimensionMismatch {
+                                name: image.name.clone(),
+                                recorded: (image.width, image.height),
+                                actual,
+                            });
+                        }
+                        actual
+                    }
+                    None => {
+                        issues.push(ImageIssue::Unreadable {
+                            name: image.name.clone(),
+                        });
+                        (image.width, image.height)
+                    }
+                }
+            }
+            None if image.bits.is_some() => (image.width, image.height),
+            None => continue,
+        };
+        if width > settings.max_dimension || height > settings.max_dimension {
+            issues.push(ImageIssue::Oversized {
+                name: image.name.clone(),
+                width,
+                height,
+                max_dimension: settings.max_dimension,
+            });
+        }
+        if settings.check_power_of_two && !(width.is_power_of_two() && height.is_power_of_two()) {
+            issues.push(ImageIssue::NonPowerOfTwo {
+                name: image.name.clone(),
+                width,
+                height,
+            });
+        }
+        estimated_vram += texture_vram(width, height);
+    }
+    if let Some(budget) = settings.vram_budget {
+        if estimated_vram > budget {
+            issues.push(ImageIssue::VramBudgetExceeded {
+                estimated: estimated_vram,
+                budget,
+            });
+        }
+    }
+    ImageAudit {
+        issues,
+        estimated_vram,
+    }
+}
+
+/// 4 bytes per pixel, the mipmap chain adds about a third
+fn texture_vram(width: u32, height: u32) -> u64 {
+    let base = width as u64 * height as u64 * 4;
+    base + base / 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::image::{ImageDataBits, ImageDataJpeg};
+    use image::{ImageFormat, RgbImage};
+    use pretty_assertions::assert_eq;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        RgbImage::new(width, height)
+            .write_to(&mut data, ImageFormat::Png)
+            .unwrap();
+        data.into_inner()
+    }
+
+    fn image(name: &str, width: u32, height: u32, data: Vec<u8>) -> ImageData {
+        ImageData {
+            name: name.to_string(),
+            internal_name: None,
+            path: format!("{}.png", name),
+            width,
+            height,
+            link: None,
+            alpha_test_value: -1.0,
+            is_opaque: None,
+            is_signed: None,
+            jpeg: Some(ImageDataJpeg {
+                path: format!("{}.png", name),
+                name: name.to_string(),
+                internal_name: None,
+                data,
+            }),
+            bits: None,
+        }
+    }
+
+    #[test]
+    fn audit() {
+        let mut bits = image("bits", 8192, 16, vec![]);
+        bits.jpeg = None;
+        bits.bits = Some(ImageDataBits {
+            lzw_compressed_data: vec![],
+        });
+        let images = vec![
+            image("ok", 16, 16, png(16, 16)),
+            image("mismatch", 32, 32, png(16, 8)),
+            image("npot", 10, 16, png(10, 16)),
+            image("broken", 16, 16, vec![1, 2, 3]),
+            bits,
+        ];
+        let settings = ImageAuditSettings {
+            vram_budget: Some(1024),
+            ..Default::default()
+        };
+
+        let audit = audit_images(&images, &settings);
+
+        let expected_vram = [16 * 16, 16 * 8, 10 * 16, 16 * 16, 8192 * 16]
+            .iter()
+            .map(|pixels| texture_vram(*pixels, 1))
+            .sum::<u64>();
+        assert_eq!(audit.estimated_vram, expected_vram);
+        assert_eq!(
+            audit.issues,
+            vec![
+                ImageIssue::DimensionMismatch {
+                    name: "mismatch".to_string(),
+                    recorded: (32, 32),
+                    actual: (16, 8),
+                },
+                ImageIssue::NonPowerOfTwo {
+                    name: "npot".to_string(),
+                    width: 10,
+                    height: 16,
+                },
+                ImageIssue::Unreadable {
+                    name: "broken".to_string(),
+                },
+                ImageIssue::Oversized {
+                    name: "bits".to_string(),
+                    width: 8192,
+                    height: 16,
+                    max_dimension: 4096,
+                },
+                ImageIssue::VramBudgetExceeded {
+                    estimated: expected_vram,
+                    budget: 1024,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Timestamped copies of a table, taken before it is changed in place
+//!
+//! A backup of `table.vpx` is named `table.vpx.20241231-235959-123.bak`, the time is in UTC.
+//! In a shared [`BackupPolicy::dir`] the name also holds a hash of the directory of the
+//! table, `table.vpx.3f1c4e0a9b2d7e85.20241231-235959-123.bak`, so tables with the same
+//! name in different directories keep their own backups. After taking a snapshot the oldest backups of the same file are removed so at most
+//! [`BackupPolicy::keep`] are left.
+//!
+//! [`super::importvbs_with_backup`] and [`super::repair::repair_with_backup`] take a
+//! snapshot before they change the file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpin::vpx::backup;
+//!
+//! let backup = backup::snapshot("table.vpx").unwrap();
+//! println!("backed up to {}", backup.display());
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::Fnv64;
+
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Where backups go and how many are kept
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BackupPolicy {
+    /// Directory for the backups, next to the file when `None`
+    pub dir: Option<PathBuf>,
+    /// The number of backups to keep per file, `0` keeps all of them
+    pub keep: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        BackupPolicy { dir: None, keep: 5 }
+    }
+}
+
+/// Backs up a file next to it, keeping the 5 most recent backups
+pub fn snapshot<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    snapshot_with_policy(path, &BackupPolicy::default())
+}
+
+/// Backs up a file and removes the backups the policy does not keep.
+///
+/// Returns the path of the new backup.
+pub fn snapshot_with_policy<P: AsRef<Path>>(path: P, policy: &BackupPolicy) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    let prefix = backup_prefix(path, policy)?;
+    let dir = backup_dir(path, policy);
+    std::fs::create_dir_all(&dir)?;
+    let mut time = SystemTime::now();
+    let backup_path = loop {
+        let candidate = dir.join(format!(
+            "{}.{}.{}",
+            prefix,
+            timestamp(time),
+            BACKUP_EXTENSION
+        ));
+        if !candidate.exists() {
+            break candidate;
+        }
+        // two snapshots within the same millisecond
+        time += Duration::from_millis(1);
+    };
+    std::fs::copy(path, &backup_path)?;
+    if policy.keep > 0 {
+        let backups = list(path, policy)?;
+        let remove = backups.len().saturating_sub(policy.keep);
+        for old in &backups[..remove] {
+            std::fs::remove_file(old)?;
+        }
+    }
+    Ok(backup_path)
+}
+
+/// The backups of a file, oldest first
+pub fn list<P: AsRef<Path>>(path: P, policy: &BackupPolicy) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let prefix = format!("{}.", backup_prefix(path, policy)?);
+    let suffix = format!(".{}", BACKUP_EXTENSION);
+    let dir = backup_dir(path, policy);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some(stamp) = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(&suffix))
+        {
+            if is_timestamp(stamp) {
+                backups.push(entry.path());
+            }
+        }
+    }
+    // the timestamps sort chronologically
+    backups.sort();
+    Ok(backups)
+}
+
+fn file_name(path: &Path) -> io::Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a file path: {}", path.display()),
+            )
+        })
+}
+
+/// The file name, followed by a hash of the directory of the file for a shared backup dir
+fn backup_prefix(path: &Path, policy: &BackupPolicy) -> io::Result<String> {
+    let file_name = file_name(path)?;
+    if policy.dir.is_none() {
+        return Ok(file_name);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = std::fs::canonicalize(parent).or_else(|_| std::path::absolute(parent))?;
+    let mut hasher = Fnv64::new();
+    hasher.update(parent.to_string_lossy().as_bytes());
+    Ok(format!("{}.{:016x}", file_name, hasher.finish()))
+}
+
+fn backup_dir(path: &Path, policy: &BackupPolicy) -> PathBuf {
+    match &policy.dir {
+        Some(dir) => dir.clone(),
+        None => path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(".")),
+    }
+}
+
+/// `YYYYMMDD-HHMMSS-mmm` in UTC
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let seconds_of_day = seconds % 86400;
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+fn is_timestamp(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.len() == 19
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 15 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// Converts days since 1970-01-01 to a date, see
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101-000000-000");
+        let time = UNIX_EPOCH + Duration::from_millis(1_735_689_599_123);
+        assert_eq!(timestamp(time), "20241231-235959-123");
+        assert_eq!(civil_from_days(19417), (2023, 3, 1));
+        assert!(is_timestamp("20241231-235959-123"));
+        assert!(!is_timestamp("20241231-235959"));
+    }
+
+    #[test]
+    fn snapshots_are_pruned() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("table.vpx");
+        let policy = BackupPolicy {
+            dir: Some(dir.join("backups")),
+            keep: 2,
+        };
+        assert_eq!(list(&path, &policy)?, Vec::<PathBuf>::new());
+
+        let mut snapshots = Vec::new();
+        for content in ["one", "two", "three"] {
+            std::fs::write(&path, content)?;
+            snapshots.push(snapshot_with_policy(&path, &policy)?);
+        }
+        std::fs::write(dir.join("backups").join("other.vpx.bak"), "other")?;
+
+        assert_eq!(list(&path, &policy)?, snapshots[1..]);
+        assert_eq!(std::fs::read_to_string(&snapshots[2])?, "three");
+        assert!(!snapshots[0].exists());
+        Ok(())
+    }
+
+    #[test]
+    fn same_file_names_in_a_shared_dir() -> io::Result<()> {
+        let dir = testdir!();
+        let policy = BackupPolicy {
+            dir: Some(dir.join("backups")),
+            keep: 1,
+        };
+        let a = dir.join("a").join("table.vpx");
+        let b = dir.join("b").join("table.vpx");
+        for path in [&a, &b] {
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, path.to_string_lossy().as_bytes())?;
+        }
+
+        let backup_a = snapshot_with_policy(&a, &policy)?;
+        let backup_b = snapshot_with_policy(&b, &policy)?;
+
+        assert_ne!(backup_a, backup_b);
+        assert_eq!(list(&a, &policy)?, vec![backup_a.clone()]);
+        assert_eq!(list(&b, &policy)?, vec![backup_b]);
+        assert_eq!(std::fs::read_to_string(&backup_a)?, a.to_string_lossy());
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
 use encoding_rs::mem::{decode_latin1, encode_latin1_lossy};
 use nom::number::complete::{le_f32, le_f64, le_i16, le_i32, le_i64, le_u16, le_u32, le_u64};
 use nom::ToUsize;
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use utf16string::WStr;
 
 use super::model::{StringEncoding, StringWithEncoding};
@@ -25,6 +29,8 @@ pub struct BiffReader<'a> {
     record_start: usize,
     tag: String,
     warn_remaining: bool,
+    /// `Some` for checked readers, holds the first error shared with all child readers
+    error: Option<Rc<RefCell<Option<String>>>>,
 }
 // TODO make private
 /**
@@ -34,6 +40,14 @@ pub const RECORD_TAG_LEN: u32 = 4;
 
 pub const WARN: bool = true;
 
+/// Conversion from a stored value that fails for values without a matching variant.
+pub(crate) trait CheckedFrom<T>: Sized {
+    /// Returned instead of an invalid value by a checked reader, which records the error.
+    const FALLBACK: Self;
+
+    fn checked_from(value: T) -> Result<Self, String>;
+}
+
 impl<'a> BiffReader<'a> {
     pub fn new(data: &'a [u8]) -> Self {
         let reader: BiffReader<'a> = BiffReader {
@@ -43,10 +57,21 @@ impl<'a> BiffReader<'a> {
             record_start: 0,
             tag: "".to_string(),
             warn_remaining: true,
+            error: None,
         };
         reader
     }
 
+    /// Creates a reader that does not panic on invalid data.
+    ///
+    /// The first error is recorded and the reader jumps to the end of the data, after that all
+    /// reads return zero values. Use [`BiffReader::finish`] to get the error.
+    pub fn new_checked(data: &'a [u8]) -> Self {
+        let mut reader = BiffReader::new(data);
+        reader.error = Some(Rc::new(RefCell::new(None)));
+        reader
+    }
+
     pub fn with_remaining(data: &'a [u8], bytes_in_record_remaining: usize) -> Self {
         let reader: BiffReader<'a> = BiffReader {
             data,
@@ -55,10 +80,18 @@ impl<'a> BiffReader<'a> {
             record_start: 0,
             tag: "".to_string(),
             warn_remaining: true,
+            error: None,
         };
         reader
     }
 
+    /// Creates a reader for nested data that records its errors like this reader does.
+    pub(crate) fn nested_reader<'b>(&self, data: &'b [u8]) -> BiffReader<'b> {
+        let mut reader = BiffReader::new(data);
+        reader.error.clone_from(&self.error);
+        reader
+    }
+
     /**
      * Useful if you just want to read a bunch of tags and don't care about the data
      */
@@ -66,6 +99,70 @@ impl<'a> BiffReader<'a> {
         self.warn_remaining = false;
     }
 
+    /// Returns the first error a checked reader or any of its child readers ran into.
+    pub fn finish(&self) -> io::Result<()> {
+        match self.error.as_ref().and_then(|error| error.borrow().clone()) {
+            Some(message) => Err(io::Error::new(io::ErrorKind::InvalidData, message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Panics with the message, or for a checked reader records it and moves to the end.
+    pub(crate) fn fail(&mut self, message: String) {
+        match &self.error {
+            None => panic!("{}", message),
+            Some(error) => {
+                error.borrow_mut().get_or_insert(message);
+            }
+        }
+        self.pos = self.data.len();
+        self.bytes_in_record_remaining = 0;
+        self.tag = "ENDB".to_string();
+    }
+
+    fn failed(&self) -> bool {
+        self.error
+            .as_ref()
+            .is_some_and(|error| error.borrow().is_some())
+    }
+
+    /// Returns the next `count` bytes, or an empty slice after failing if there are not enough.
+    fn take(&mut self, count: usize) -> &'a [u8] {
+        match self.pos.checked_add(count) {
+            Some(end) if end <= self.data.len() => {
+                let data = &self.data[self.pos..end];
+                self.pos = end;
+                data
+            }
+            _ => {
+                self.fail(format!(
+                    "Unexpected end of biff stream at {}/{} while reading {} bytes for {}",
+                    self.pos,
+                    self.data.len(),
+                    count,
+                    self.tag
+                ));
+                &[]
+            }
+        }
+    }
+
+    fn consume(&mut self, count: usize) {
+        if self.error.is_some() && self.bytes_in_record_remaining < count {
+            self.fail(format!(
+                "{} bytes remaining in record {}, but {} bytes requested",
+                self.bytes_in_record_remaining, self.tag, count
+            ));
+        } else {
+            self.bytes_in_record_remaining -= count;
+        }
+    }
+
+    /// Limits a count read from the data to what the remaining data can hold
+    fn capacity(&self, count: usize, item_size: usize) -> usize {
+        count.min(self.data.len().saturating_sub(self.pos) / item_size)
+    }
+
     pub fn pos(&self) -> usize {
         self.pos
     }
@@ -79,14 +176,12 @@ impl<'a> BiffReader<'a> {
     }
 
     pub fn get(&mut self, count: usize) -> &[u8] {
-        self.bytes_in_record_remaining -= count;
+        self.consume(count);
         self.get_no_remaining_update(count)
     }
 
     pub fn get_no_remaining_update(&mut self, count: usize) -> &[u8] {
-        let p = self.pos;
-        self.pos += count;
-        &self.data[p..p + count]
+        self.take(count)
     }
 
     pub fn remaining_in_record(&mut self) -> usize {
@@ -94,71 +189,76 @@ impl<'a> BiffReader<'a> {
     }
 
     pub fn get_bool(&mut self) -> bool {
-        let all = &self.data[self.pos..self.pos + 4];
+        let all = self.take(4);
         // Any other value is suspicious as it is not a boolean
         if all != [0, 0, 0, 0] && all != [1, 0, 0, 0] {
-            panic!("Unexpected bytes for bool: {:?}", all);
+            if !all.is_empty() {
+                self.fail(format!("Unexpected bytes for bool: {:?}", all));
+            }
+            return false;
         }
-        let b = self.data[self.pos] != 0;
-        self.pos += 4;
-        self.bytes_in_record_remaining -= 4;
+        let b = all[0] != 0;
+        self.consume(4);
         b
     }
 
     pub fn get_u8(&mut self) -> u8 {
         let i = self.get_u8_no_remaining_update();
-        self.bytes_in_record_remaining -= 1;
+        self.consume(1);
         i
     }
 
     pub fn get_u8_no_remaining_update(&mut self) -> u8 {
-        let i = self.data[self.pos];
-        self.pos += 1;
-        i
+        self.take(1).first().copied().unwrap_or_default()
     }
 
     pub fn get_u16(&mut self) -> u16 {
         let res = self.get_u16_no_remaining_update();
-        self.bytes_in_record_remaining -= 2;
+        self.consume(2);
         res
     }
 
     pub fn get_u16_no_remaining_update(&mut self) -> u16 {
-        let i: Result<(&[u8], u16), nom::Err<()>> = le_u16(&self.data[self.pos..]);
-        self.pos += 2;
-        i.unwrap().1
+        let i: Result<(&[u8], u16), nom::Err<()>> = le_u16(self.take(2));
+        i.map_or(0, |(_, i)| i)
     }
 
     pub fn get_u32(&mut self) -> u32 {
         let res = self.get_u32_no_remaining_update();
-        self.bytes_in_record_remaining -= 4;
+        self.consume(4);
         res
     }
 
     pub fn get_u32_no_remaining_update(&mut self) -> u32 {
-        let i: Result<(&[u8], u32), nom::Err<()>> = le_u32(&self.data[self.pos..]);
-        self.pos += 4;
-        i.unwrap().1
+        let i: Result<(&[u8], u32), nom::Err<()>> = le_u32(self.take(4));
+        i.map_or(0, |(_, i)| i)
+    }
+
+    /// Reads a `u32` and converts it, failing for values without a matching variant.
+    pub(crate) fn get_u32_checked<T: CheckedFrom<u32>>(&mut self) -> T {
+        let value = self.get_u32();
+        T::checked_from(value).unwrap_or_else(|message| {
+            self.fail(message);
+            T::FALLBACK
+        })
     }
 
     pub fn get_32(&mut self) -> i32 {
         let res = self.get_32_no_remaining_update();
-        self.bytes_in_record_remaining -= 4;
+        self.consume(4);
         res
     }
     pub fn get_32_no_remaining_update(&mut self) -> i32 {
-        let i: Result<(&[u8], i32), nom::Err<()>> = le_i32(&self.data[self.pos..]);
-        self.pos += 4;
-        i.unwrap().1
+        let i: Result<(&[u8], i32), nom::Err<()>> = le_i32(self.take(4));
+        i.map_or(0, |(_, i)| i)
     }
 
     pub fn get_f32(&mut self) -> f32 {
-        let data = &self.data[self.pos..self.pos + 4];
+        let data = self.take(4);
         let i: Result<(&[u8], f32), nom::Err<()>> = le_f32(data);
-        self.pos += 4;
-        self.bytes_in_record_remaining -= 4;
+        self.consume(4);
 
-        let res = i.unwrap().1;
+        let res = i.map_or(0.0, |(_, i)| i);
         if res.is_nan() {
             eprintln!("NaN value found in f32 for tag {}: {:?}", self.tag, data);
         }
@@ -166,17 +266,7 @@ impl<'a> BiffReader<'a> {
     }
 
     pub fn get_str(&mut self, count: usize) -> String {
-        let mut pos_0 = count;
-        // find the end of the string
-        for p in 0..count {
-            if self.data[self.pos + p] == 0 {
-                pos_0 = p;
-                break;
-            }
-        }
-        let data = &self.data[self.pos..self.pos + pos_0];
-        let s = decode_latin1(data);
-        self.pos += count;
+        let s = decode_latin1(until_nul(self.take(count)));
         self.sub_remaining(count);
         s.to_string()
     }
@@ -197,34 +287,14 @@ impl<'a> BiffReader<'a> {
         //
         // https://github.com/vpinball/vpinball/blob/5ac9cfcb19e721ed9373465866cb724a655ad55f/codeview.cpp#L1761-L1767
 
-        let mut pos_0 = count;
         // find the end of the 0-terminated string
-        for p in 0..count {
-            if self.data[self.pos + p] == 0 {
-                pos_0 = p;
-                break;
-            }
-        }
-        let data = &self.data[self.pos..self.pos + pos_0];
-
-        self.pos += count;
+        let data = until_nul(self.take(count));
         let s: StringWithEncoding = data.into();
         s
     }
 
     pub fn get_str_no_remaining_update(&mut self, count: usize) -> String {
-        let mut pos_0 = count;
-        // find the end of the string
-        for p in 0..count {
-            if self.data[self.pos + p] == 0 {
-                pos_0 = p;
-                break;
-            }
-        }
-        let data = &self.data[self.pos..self.pos + pos_0];
-
-        let s = decode_latin1(data);
-        self.pos += count;
+        let s = decode_latin1(until_nul(self.take(count)));
         s.to_string()
     }
 
@@ -240,12 +310,17 @@ impl<'a> BiffReader<'a> {
 
     pub fn get_wide_string(&mut self) -> String {
         let count = self.get_u32().to_usize();
-        let data = &self.data[self.pos..self.pos + count];
+        let data = self.take(count);
         // hmm, this ? seems to be different for nom and utf16string
         // see https://docs.rs/utf16string/latest/utf16string/
-        let i = WStr::from_utf16le(data).unwrap().to_utf8();
-        self.pos += count;
-        self.bytes_in_record_remaining -= count;
+        let i = match WStr::from_utf16le(data) {
+            Ok(s) => s.to_utf8(),
+            Err(e) => {
+                self.fail(format!("Invalid wide string for {}: {}", self.tag, e));
+                return String::new();
+            }
+        };
+        self.consume(count);
         i
     }
 
@@ -269,159 +344,188 @@ impl<'a> BiffReader<'a> {
     }
 
     pub fn get_double(&mut self) -> f64 {
-        let i: Result<(&[u8], f64), nom::Err<()>> = le_f64(&self.data[self.pos..]);
-        self.pos += 8;
-        self.bytes_in_record_remaining -= 8;
-        i.unwrap().1
+        let i: Result<(&[u8], f64), nom::Err<()>> = le_f64(self.take(8));
+        self.consume(8);
+        i.map_or(0.0, |(_, i)| i)
     }
 
     pub fn get_i16(&mut self) -> i16 {
-        let i: Result<(&[u8], i16), nom::Err<()>> = le_i16(&self.data[self.pos..]);
-        self.pos += 2;
-        self.bytes_in_record_remaining -= 2;
-        i.unwrap().1
+        let i: Result<(&[u8], i16), nom::Err<()>> = le_i16(self.take(2));
+        self.consume(2);
+        i.map_or(0, |(_, i)| i)
     }
 
     pub fn get_i32(&mut self) -> i32 {
-        let i: Result<(&[u8], i32), nom::Err<()>> = le_i32(&self.data[self.pos..]);
-        self.pos += 4;
-        self.bytes_in_record_remaining -= 4;
-        i.unwrap().1
+        let i: Result<(&[u8], i32), nom::Err<()>> = le_i32(self.take(4));
+        self.consume(4);
+        i.map_or(0, |(_, i)| i)
+    }
+
+    /// Reads an `i32` and converts it, failing for values without a matching variant.
+    pub(crate) fn get_i32_checked<T: CheckedFrom<i32>>(&mut self) -> T {
+        let value = self.get_i32();
+        T::checked_from(value).unwrap_or_else(|message| {
+            self.fail(message);
+            T::FALLBACK
+        })
     }
 
     pub fn get_i64(&mut self) -> i64 {
-        let i: Result<(&[u8], i64), nom::Err<()>> = le_i64(&self.data[self.pos..]);
-        self.pos += 8;
-        self.bytes_in_record_remaining -= 8;
-        i.unwrap().1
+        let i: Result<(&[u8], i64), nom::Err<()>> = le_i64(self.take(8));
+        self.consume(8);
+        i.map_or(0, |(_, i)| i)
     }
 
     pub fn get_u64(&mut self) -> u64 {
-        let i: Result<(&[u8], u64), nom::Err<()>> = le_u64(&self.data[self.pos..]);
-        self.pos += 8;
-        self.bytes_in_record_remaining -= 8;
-        i.unwrap().1
+        let i: Result<(&[u8], u64), nom::Err<()>> = le_u64(self.take(8));
+        self.consume(8);
+        i.map_or(0, |(_, i)| i)
     }
 
     pub fn get_u32_array(&mut self, count: usize) -> Vec<u32> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 4));
         for _ in 0..count {
             v.push(self.get_u32());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_u16_array(&mut self, count: usize) -> Vec<u16> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 2));
         for _ in 0..count {
             v.push(self.get_u16());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_i16_array(&mut self, count: usize) -> Vec<i16> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 2));
         for _ in 0..count {
             v.push(self.get_i16());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_i32_array(&mut self, count: usize) -> Vec<i32> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 4));
         for _ in 0..count {
             v.push(self.get_i32());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_i64_array(&mut self, count: usize) -> Vec<i64> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 8));
         for _ in 0..count {
             v.push(self.get_i64());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_u64_array(&mut self, count: usize) -> Vec<u64> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 8));
         for _ in 0..count {
             v.push(self.get_u64());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_f32_array(&mut self, count: usize) -> Vec<f32> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 4));
         for _ in 0..count {
             v.push(self.get_f32());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_f64_array(&mut self, count: usize) -> Vec<f64> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 8));
         for _ in 0..count {
             v.push(self.get_double());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_string_array(&mut self, count: usize) -> Vec<String> {
-        let mut v = Vec::with_capacity(count);
+        let mut v = Vec::with_capacity(self.capacity(count, 4));
         for _ in 0..count {
             v.push(self.get_string().to_string());
+            if self.failed() {
+                break;
+            }
         }
         v
     }
 
     pub fn get_record_data(&mut self, with_tag: bool) -> Vec<u8> {
         let d = if with_tag {
-            &self.data[self.pos - 4..self.pos + self.bytes_in_record_remaining]
+            self.pos -= 4;
+            self.take(4 + self.bytes_in_record_remaining)
         } else {
             if self.pos + self.bytes_in_record_remaining >= self.data.len() {
-                panic!("range is too big for {}", self.tag);
+                self.fail(format!("range is too big for {}", self.tag));
+                return Vec::new();
             }
-            &self.data[self.pos..self.pos + self.bytes_in_record_remaining]
+            self.take(self.bytes_in_record_remaining)
         };
-        self.pos += self.bytes_in_record_remaining;
         self.bytes_in_record_remaining = 0;
         d.to_vec()
     }
 
     pub fn get_data_no_remaining_update(&mut self) -> Vec<u8> {
         let len = self.get_u32_no_remaining_update() as usize;
-        let data = &self.data[self.pos..self.pos + len];
-
-        self.pos += len;
+        let data = self.take(len);
         self.bytes_in_record_remaining = 0;
         data.to_vec()
     }
 
     pub fn get_data(&mut self, count: usize) -> &[u8] {
-        let d = &self.data[self.pos..self.pos + count];
-        self.pos += count;
+        let d = self.take(count);
         self.bytes_in_record_remaining = 0;
         d
     }
 
     pub(crate) fn get_remaining(&self) -> &[u8] {
-        &self.data[self.pos..]
+        self.data.get(self.pos..).unwrap_or_default()
     }
 
     pub fn skip(&mut self, count: usize) {
-        self.pos += count;
-        self.bytes_in_record_remaining -= count;
+        self.take(count);
+        self.consume(count);
     }
 
     pub fn skip_end_tag(&mut self, count: usize) {
-        self.pos += count;
+        self.take(count);
         self.bytes_in_record_remaining = 0;
     }
 
     pub fn skip_tag(&mut self) -> usize {
         let remaining = self.bytes_in_record_remaining;
-        self.pos += remaining;
+        self.take(remaining);
         self.bytes_in_record_remaining = 0;
         remaining
     }
@@ -436,22 +540,34 @@ impl<'a> BiffReader<'a> {
             }
             self.skip(self.bytes_in_record_remaining);
         }
+        if self.failed() {
+            return None;
+        }
         self.record_start = self.pos;
         if self.pos >= self.data.len() {
-            panic!(
+            self.fail(format!(
                 "Unexpected end of biff stream at {}/{} while reading next tag. Missing ENDB?",
                 self.pos(),
                 self.data.len()
-            );
+            ));
+            return None;
         }
         self.bytes_in_record_remaining = self.get_u32_no_remaining_update().to_usize();
         let tag = self.get_str(RECORD_TAG_LEN.try_into().unwrap());
+        if self.failed() {
+            return None;
+        }
         if tag.is_empty() {
-            panic!("Empty tag at {}/{}", self.pos(), self.data.len());
+            self.fail(format!("Empty tag at {}/{}", self.pos(), self.data.len()));
+            return None;
         }
         self.tag = tag;
         if self.warn_remaining && self.tag == "ENDB" && self.pos < self.data.len() {
-            panic!("{} Remaining bytes after ENDB", self.data.len() - self.pos);
+            self.fail(format!(
+                "{} Remaining bytes after ENDB",
+                self.data.len() - self.pos
+            ));
+            return None;
         }
         if self.is_eof() {
             None
@@ -460,23 +576,24 @@ impl<'a> BiffReader<'a> {
         }
     }
 
-    pub fn child_reader(&mut self) -> BiffReader {
+    pub fn child_reader(&mut self) -> BiffReader<'_> {
         BiffReader {
-            data: &self.data[self.pos..],
+            data: self.data.get(self.pos..).unwrap_or_default(),
             pos: 0,
             bytes_in_record_remaining: 0,
             record_start: 0,
             tag: "".to_string(),
             warn_remaining: false,
+            error: self.error.clone(),
         }
     }
 
     fn sub_remaining(&mut self, count: usize) {
         if self.bytes_in_record_remaining < count {
-            panic!(
+            self.fail(format!(
                 "WARN: {} bytes remaining in record {}, but {} bytes requested",
                 self.bytes_in_record_remaining, self.tag, count
-            );
+            ));
         } else {
             self.bytes_in_record_remaining -= count;
         }
@@ -484,27 +601,27 @@ impl<'a> BiffReader<'a> {
 
     pub fn data_until(&mut self, tag: &[u8]) -> Vec<u8> {
         // read bytes until we see tag and return it, put pos to the beginning of the tag
-        let mut pos = self.pos;
-        let mut found = false;
-        while pos < self.data.len() {
-            if &self.data[pos..pos + tag.len()] == tag {
-                found = true;
-                break;
-            }
-            pos += 1;
-        }
-        if !found {
-            panic!("Tag {:?} not found", tag);
-        }
+        let found = self
+            .get_remaining()
+            .windows(tag.len())
+            .position(|window| window == tag);
         // go back one u32 to the tag size
-        pos -= 4;
-        let data = &self.data[self.pos..pos];
-        self.pos = pos;
+        let Some(len) = found.and_then(|offset| offset.checked_sub(4)) else {
+            self.fail(format!("Tag {:?} not found", tag));
+            return Vec::new();
+        };
+        let data = self.take(len);
         self.bytes_in_record_remaining = 0;
         data.to_vec()
     }
 }
 
+/// Cuts the data at the first 0 byte
+fn until_nul(data: &[u8]) -> &[u8] {
+    let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+    &data[..end]
+}
+
 pub struct BiffWriter {
     data: Vec<u8>,
     tag_start: usize,
@@ -594,6 +711,8 @@ impl BiffWriter {
         self.data.extend_from_slice(&value.to_le_bytes());
     }
 
+    /// Writes the exact bits, unlike text formats these need no canonicalization as every
+    /// value, including NaN payloads, has a single representation.
     pub fn write_f32(&mut self, value: f32) {
         self.record_size += 4;
         self.data.extend_from_slice(&value.to_le_bytes());
@@ -774,6 +893,144 @@ impl BiffWriter {
     }
 }
 
+/// Statistics for a single tag as found in a BIFF stream
+#[derive(Debug, PartialEq, Clone)]
+pub struct TagStats {
+    pub tag: String,
+    /// Number of times the tag occurs in the stream
+    pub count: usize,
+    /// Sum of the data sizes of all occurrences, excluding the tag itself
+    pub total_size: usize,
+    pub min_size: usize,
+    pub max_size: usize,
+}
+
+/// All tags found in a single stream of a compound file
+#[derive(Debug, PartialEq, Clone)]
+pub struct StreamTagCensus {
+    pub path: PathBuf,
+    /// Tags in order of first occurrence
+    pub tags: Vec<TagStats>,
+}
+
+/// Lists all tags, their sizes and counts for every BIFF stream in a `vpx` file.
+///
+/// This is a debugging aid for reverse engineering, it helps finding tags that are not yet
+/// supported by this library in tables found in the wild.
+///
+/// Sound streams are skipped as they are only partially BIFF encoded.
+pub fn tag_census<P: AsRef<Path>>(path: P) -> io::Result<Vec<StreamTagCensus>> {
+    let mut comp = cfb::open(path)?;
+    let stream_paths: Vec<PathBuf> = comp
+        .walk()
+        .filter(|entry| entry.is_stream())
+        .map(|entry| entry.path().to_path_buf())
+        .filter(|path| is_biff_stream(path))
+        .collect();
+    let mut census = Vec::with_capacity(stream_paths.len());
+    for path in stream_paths {
+        let mut data = Vec::new();
+        comp.open_stream(&path)?.read_to_end(&mut data)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        // game items start with a 32 bit item type
+        let skip = if name.starts_with("GameItem") { 4 } else { 0 };
+        let tags = biff_tag_census(data.get(skip..).unwrap_or_default());
+        census.push(StreamTagCensus { path, tags });
+    }
+    Ok(census)
+}
+
+pub(crate) fn is_biff_stream(path: &Path) -> bool {
+    if !path.parent().is_some_and(|p| p.ends_with("GameStg")) {
+        return false;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name == "GameData"
+        || name == "CustomInfoTags"
+        || ["GameItem", "Image", "Font", "Collection"]
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Lists all tags, their sizes and counts for a single BIFF stream.
+///
+/// Sub-records of nested `JPEG` and `DPNT` records are reported with a `JPEG/` or `DPNT/` prefix.
+pub fn biff_tag_census(data: &[u8]) -> Vec<TagStats> {
+    let mut stats: Vec<TagStats> = Vec::new();
+    walk_records(&mut BiffReader::new(data), |tag, size| {
+        add_tag_stats(&mut stats, tag, size)
+    });
+    stats
+}
+
+/// Returns the length of the BIFF data up to and including the `ENDB` record.
+///
+/// Any bytes after this position are not part of the BIFF stream.
+pub(crate) fn biff_data_len(data: &[u8]) -> io::Result<usize> {
+    let mut reader = BiffReader::new_checked(data);
+    let len = walk_records(&mut reader, |_, _| {});
+    reader.finish()?;
+    Ok(len)
+}
+
+/// Walks all records in a BIFF stream, calling `on_record` with the tag and data size of each
+/// record. Returns the position right after the `ENDB` record.
+fn walk_records<F: FnMut(&str, usize)>(reader: &mut BiffReader<'_>, mut on_record: F) -> usize {
+    reader.disable_warn_remaining();
+    while let Some(tag) = reader.next(false) {
+        match tag.as_str() {
+            "CODE" => {
+                // the record size only covers the tag, the length is prefixed to the data
+                let len = reader.get_u32_no_remaining_update() as usize;
+                reader.get_no_remaining_update(len);
+                on_record(&tag, len);
+            }
+            "FONT" => {
+                let font_data = reader.data_until("ENDB".as_bytes());
+                on_record(&tag, font_data.len());
+            }
+            "BITS" => {
+                let bits_data = reader.data_until("ALTV".as_bytes());
+                on_record(&tag, bits_data.len());
+            }
+            "JPEG" | "DPNT" => {
+                // these records contain a nested BIFF stream terminated by its own ENDB
+                let mut sub_reader = reader.child_reader();
+                while let Some(sub_tag) = sub_reader.next(false) {
+                    let sub_data = sub_reader.get_record_data(false);
+                    on_record(&format!("{}/{}", tag, sub_tag), sub_data.len());
+                }
+                let pos = sub_reader.pos();
+                on_record(&tag, pos);
+                reader.skip_end_tag(pos);
+            }
+            _ => {
+                let size = reader.skip_tag();
+                on_record(&tag, size);
+            }
+        }
+    }
+    reader.pos()
+}
+
+fn add_tag_stats(stats: &mut Vec<TagStats>, tag: &str, size: usize) {
+    match stats.iter_mut().find(|s| s.tag == tag) {
+        Some(s) => {
+            s.count += 1;
+            s.total_size += size;
+            s.min_size = s.min_size.min(size);
+            s.max_size = s.max_size.max(size);
+        }
+        None => stats.push(TagStats {
+            tag: tag.to_string(),
+            count: 1,
+            total_size: size,
+            min_size: size,
+            max_size: size,
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -799,4 +1056,83 @@ mod tests {
         reader.next(false);
         assert_eq!(reader.is_eof(), true);
     }
+
+    #[test]
+    fn checked_reader_truncated() {
+        let mut writer = BiffWriter::new();
+        writer.write_tagged_u32("TEST", 1);
+        writer.write_tagged_u32("MORE", 2);
+        writer.close(true);
+        let data = &writer.get_data()[..22];
+        let mut reader = BiffReader::new_checked(data);
+        assert_eq!(reader.next(false), Some("TEST".to_string()));
+        assert_eq!(reader.get_u32(), 1);
+        assert_eq!(reader.next(false), Some("MORE".to_string()));
+        assert_eq!(reader.get_u32(), 0);
+        assert_eq!(reader.next(false), None);
+        let error = reader.finish().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unexpected end of biff stream at 20/22 while reading 4 bytes for MORE"
+        );
+    }
+
+    #[test]
+    fn data_len_ignores_trailing_bytes() {
+        let mut writer = BiffWriter::new();
+        writer.write_tagged_u32("TEST", 1);
+        writer.close(true);
+        let mut data = writer.get_data().to_vec();
+        let len = data.len();
+        data.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(biff_data_len(&data).unwrap(), len);
+        assert!(biff_data_len(&data[..len - 2]).is_err());
+    }
+
+    #[test]
+    fn census_counts_tags() {
+        let mut writer = BiffWriter::new();
+        writer.write_tagged_u32("TEST", 1);
+        writer.write_tagged_string("NAME", "test");
+        writer.write_tagged_u32("TEST", 2);
+        writer.close(true);
+        let stats = biff_tag_census(writer.get_data());
+        assert_eq!(
+            stats,
+            vec![
+                TagStats {
+                    tag: "TEST".to_string(),
+                    count: 2,
+                    total_size: 8,
+                    min_size: 4,
+                    max_size: 4,
+                },
+                TagStats {
+                    tag: "NAME".to_string(),
+                    count: 1,
+                    total_size: 8,
+                    min_size: 8,
+                    max_size: 8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn census_blank_table() -> io::Result<()> {
+        let census = tag_census("testdata/completely_blank_table_10_7_4.vpx")?;
+        let gamedata = census
+            .iter()
+            .find(|c| c.path.ends_with("GameData"))
+            .expect("GameData stream");
+        let code = gamedata.tags.iter().find(|t| t.tag == "CODE").unwrap();
+        assert_eq!(code.count, 1);
+        let image = census
+            .iter()
+            .find(|c| c.path.ends_with("Image0"))
+            .expect("Image0 stream");
+        assert!(image.tags.iter().any(|t| t.tag == "JPEG/DATA"));
+        assert!(census.iter().all(|c| !c.path.ends_with("Version")));
+        Ok(())
+    }
 }
@@ -0,0 +1,409 @@
+//! Bounding boxes of game items, for minimaps, selection tools and sanity checks
+//!
+//! Boxes are in vpx coordinates, x to the right, y towards the player and z up. The heights
+//! from [`bounds`] are relative to the surface an item is placed on, [`placed_bounds`] looks
+//! up the surface to get the height on the table.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::bounds::items_outside_playfield;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for item in &vpx.gameitems {
+//!     if let Some(bounds) = vpx::bounds(item) {
+//!         println!("{} {:?}", item.name(), bounds.size());
+//!     }
+//! }
+//! for item in items_outside_playfield(&vpx) {
+//!     println!("{} is outside the playfield", item.name());
+//! }
+//! ```
+
+use super::gameitem::dragpoint::DragPoint;
+use super::gameitem::primitive::Primitive;
+use super::gameitem::ramp::{Ramp, RampType};
+use super::gameitem::vertex2d::Vertex2D;
+use super::gameitem::GameItemEnum;
+use super::mesh::ramp::sections;
+use super::mesh::surface::SurfaceHeightResolver;
+use super::mesh::{place_primitive_vertex, spline, Mesh};
+use super::VPX;
+
+/// An axis aligned bounding box
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    /// The smallest box containing all points, `None` when there are no points
+    pub fn from_points<I: IntoIterator<Item = [f32; 3]>>(points: I) -> Option<Aabb> {
+        points.into_iter().fold(None, |bounds, point| {
+            let point_bounds = Aabb {
+                min: point,
+                max: point,
+            };
+            Some(match bounds {
+                Some(bounds) => bounds.union(&point_bounds),
+                None => point_bounds,
+            })
+        })
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: [0, 1, 2].map(|axis| self.min[axis].min(other.min[axis])),
+            max: [0, 1, 2].map(|axis| self.max[axis].max(other.max[axis])),
+        }
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| self.max[axis] - self.min[axis])
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) / 2.0)
+    }
+
+    /// Whether the other box lies within this one when looking from above
+    pub fn contains_xy(&self, other: &Aabb) -> bool {
+        (0..2).all(|axis| other.min[axis] >= self.min[axis] && other.max[axis] <= self.max[axis])
+    }
+
+    /// Whether the boxes overlap when looking from above, touching counts as overlapping
+    pub fn intersects_xy(&self, other: &Aabb) -> bool {
+        (0..2).all(|axis| other.min[axis] <= self.max[axis] && other.max[axis] >= self.min[axis])
+    }
+
+    /// Distance from a point to the box when looking from above, 0 for points inside
+    pub fn distance_xy(&self, [x, y]: [f32; 2]) -> f32 {
+        let dx = (self.min[0] - x).max(x - self.max[0]).max(0.0);
+        let dy = (self.min[1] - y).max(y - self.max[1]).max(0.0);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Grows the box by a margin on every side, only in x and y
+    fn grow_xy(&self, margin: f32) -> Aabb {
+        Aabb {
+            min: [self.min[0] - margin, self.min[1] - margin, self.min[2]],
+            max: [self.max[0] + margin, self.max[1] + margin, self.max[2]],
+        }
+    }
+}
+
+/// The bounding box of an item on the playfield.
+///
+/// Items defined by drag points use the interpolated curve, primitives use their mesh and
+/// other items an analytic shape from their position and size. Some shapes are approximate:
+/// hit targets use a box of their size, built-in primitive shapes the unit cube the shape
+/// fits in and the tilt of flashers is ignored.
+///
+/// Returns `None` for items that are not drawn on the playfield, like timers, light
+/// sequencers and backglass items, and for primitives with an unreadable mesh.
+pub fn bounds(item: &GameItemEnum) -> Option<Aabb> {
+    match item {
+        GameItemEnum::Wall(wall) => {
+            drag_points_bounds(&wall.drag_points, wall.height_bottom, wall.height_top)
+        }
+        GameItemEnum::Flipper(flipper) => {
+            let base = circle(&flipper.center, flipper.base_radius, 0.0, flipper.height);
+            // the tip sweeps an arc, its extremes are at the end angles and the axes between
+            let (from, to) = if flipper.start_angle <= flipper.end_angle {
+                (flipper.start_angle, flipper.end_angle)
+            } else {
+                (flipper.end_angle, flipper.start_angle)
+            };
+            let axes = ((from / 90.0).ceil() as i32..=(to / 90.0).floor() as i32)
+                .map(|quarter| quarter as f32 * 90.0);
+            let bounds = [from, to]
+                .into_iter()
+                .chain(axes)
+                .map(|angle| {
+                    let (sin, cos) = angle.to_radians().sin_cos();
+                    let tip = Vertex2D {
+                        x: flipper.center.x + sin * flipper.flipper_radius_max,
+                        y: flipper.center.y - cos * flipper.flipper_radius_max,
+                    };
+                    circle(&tip, flipper.end_radius, 0.0, flipper.height)
+                })
+                .fold(base, |bounds, tip| bounds.union(&tip));
+            Some(bounds)
+        }
+        GameItemEnum::Plunger(plunger) => {
+            let center = &plunger.center;
+            Some(Aabb {
+                min: [center.x - plunger.width, center.y - plunger.stroke, 0.0],
+                max: [center.x + plunger.width, center.y + plunger.height, 0.0],
+            })
+        }
+        GameItemEnum::Bumper(bumper) => Some(circle(
+            &bumper.center,
+            bumper.radius,
+            0.0,
+            bumper.height_scale,
+        )),
+        GameItemEnum::Trigger(trigger) if !trigger.drag_points.is_empty() => {
+            drag_points_bounds(&trigger.drag_points, 0.0, trigger.hit_height)
+        }
+        GameItemEnum::Trigger(trigger) => Some(circle(
+            &trigger.center,
+            trigger.radius,
+            0.0,
+            trigger.hit_height,
+        )),
+        GameItemEnum::Light(light) if !light.drag_points.is_empty() => {
+            let height = light.height.unwrap_or(0.0);
+            drag_points_bounds(&light.drag_points, height, height)
+        }
+        GameItemEnum::Light(light) => {
+            let height = light.height.unwrap_or(0.0);
+            Some(circle(&light.center, light.falloff_radius, height, height))
+        }
+        GameItemEnum::Kicker(kicker) => Some(circle(&kicker.center, kicker.radius, 0.0, 0.0)),
+        GameItemEnum::Decal(decal) => rotated_rectangle(
+            &decal.center,
+            decal.width / 2.0,
+            decal.height / 2.0,
+            decal.rotation,
+            [0.0, 0.0],
+        ),
+        GameItemEnum::Gate(gate) => {
+            // the wire swings around the hinge at the top
+            let half = gate.length / 2.0;
+            let z = [gate.height - half, gate.height + half];
+            rotated_rectangle(&gate.center, half, 0.0, gate.rotation, z)
+        }
+        GameItemEnum::Spinner(spinner) => {
+            let half = spinner.length / 2.0;
+            let z = [spinner.height - half, spinner.height + half];
+            rotated_rectangle(&spinner.center, half, 0.0, spinner.rotation, z)
+        }
+        GameItemEnum::Ramp(ramp) => ramp_bounds(ramp),
+        GameItemEnum::Primitive(primitive) => primitive_bounds(primitive),
+        GameItemEnum::Flasher(flasher) => {
+            drag_points_bounds(&flasher.drag_points, flasher.height, flasher.height)
+        }
+        GameItemEnum::Rubber(rubber) => {
+            let radius = rubber.thickness as f32 / 2.0;
+            drag_points_bounds(
+                &rubber.drag_points,
+                rubber.height - radius,
+                rubber.height + radius,
+            )
+            .map(|bounds| bounds.grow_xy(radius))
+        }
+        GameItemEnum::HitTarget(target) => {
+            let position = &target.position;
+            let center = Vertex2D {
+                x: position.x,
+                y: position.y,
+            };
+            let z = [position.z, position.z + target.size.z];
+            rotated_rectangle(
+                &center,
+                target.size.x / 2.0,
+                target.size.y / 2.0,
+                target.rot_z,
+                z,
+            )
+        }
+        GameItemEnum::Timer(_)
+        | GameItemEnum::LightSequencer(_)
+        | GameItemEnum::TextBox(_)
+        | GameItemEnum::Reel(_)
+        | GameItemEnum::Generic(_, _)
+        | GameItemEnum::Corrupt(_, _, _) => None,
+    }
+}
+
+/// The bounding box of an item raised to the surface it is placed on, like an upper
+/// playfield. The surface height is taken at the center of the box.
+///
+/// Items without a surface field have absolute heights and are returned as [`bounds`] does.
+pub fn placed_bounds(item: &GameItemEnum, surfaces: &SurfaceHeightResolver) -> Option<Aabb> {
+    let bounds = bounds(item)?;
+    let Some(surface) = item.surface() else {
+        return Some(bounds);
+    };
+    let [x, y, _] = bounds.center();
+    let height = surfaces.height(surface, x, y);
+    Some(Aabb {
+        min: [bounds.min[0], bounds.min[1], bounds.min[2] + height],
+        max: [bounds.max[0], bounds.max[1], bounds.max[2] + height],
+    })
+}
+
+/// The playfield from the table dimensions, up to the glass
+pub fn playfield_bounds(vpx: &VPX) -> Aabb {
+    let gamedata = &vpx.gamedata;
+    Aabb {
+        min: [gamedata.left, gamedata.top, 0.0],
+        max: [gamedata.right, gamedata.bottom, gamedata.glass_top_height],
+    }
+}
+
+/// Items that are not completely within the playfield when looking from above
+pub fn items_outside_playfield(vpx: &VPX) -> Vec<&GameItemEnum> {
+    let playfield = playfield_bounds(vpx);
+    vpx.gameitems
+        .iter()
+        .filter(|item| bounds(item).is_some_and(|bounds| !playfield.contains_xy(&bounds)))
+        .collect()
+}
+
+fn circle(center: &Vertex2D, radius: f32, z_min: f32, z_max: f32) -> Aabb {
+    Aabb {
+        min: [center.x - radius, center.y - radius, z_min],
+        max: [center.x + radius, center.y + radius, z_max],
+    }
+}
+
+/// A rectangle around a center rotated in degrees, with half its width along x before rotating
+fn rotated_rectangle(
+    center: &Vertex2D,
+    half_width: f32,
+    half_height: f32,
+    degrees: f32,
+    [z_min, z_max]: [f32; 2],
+) -> Option<Aabb> {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+    Aabb::from_points(corners.into_iter().flat_map(|(dx, dy)| {
+        let x = dx * half_width;
+        let y = dy * half_height;
+        let rotated = [center.x + x * cos - y * sin, center.y + x * sin + y * cos];
+        [
+            [rotated[0], rotated[1], z_min],
+            [rotated[0], rotated[1], z_max],
+        ]
+    }))
+}
+
+fn drag_points_bounds(drag_points: &[DragPoint], z_min: f32, z_max: f32) -> Option<Aabb> {
+    Aabb::from_points(
+        spline(drag_points, true)
+            .into_iter()
+            .flat_map(|[x, y, z]| [[x, y, z + z_min], [x, y, z + z_max]]),
+    )
+}
+
+fn ramp_bounds(ramp: &Ramp) -> Option<Aabb> {
+    let (half_width, height) = match ramp.ramp_type {
+        RampType::Flat => (
+            0.0,
+            ramp.left_wall_height_visible
+                .max(ramp.right_wall_height_visible),
+        ),
+        _ => (ramp.wire_distance_x / 2.0, ramp.wire_distance_y),
+    };
+    Aabb::from_points(sections(ramp).into_iter().flat_map(|section| {
+        let half = half_width.max(section.width / 2.0);
+        let [x, y, z] = section.center;
+        let [rx, ry, _] = section.right;
+        [-half, half].into_iter().flat_map(move |offset| {
+            let (x, y) = (x + rx * offset, y + ry * offset);
+            [[x, y, z], [x, y, z + height]]
+        })
+    }))
+}
+
+fn primitive_bounds(primitive: &Primitive) -> Option<Aabb> {
+    if primitive.use_3d_mesh {
+        let mesh = Mesh::from_primitive_transformed(primitive).ok()??;
+        return Aabb::from_points(mesh.vertices.iter().map(|vertex| vertex.position));
+    }
+    let corners =
+        (0..8).map(|corner| [1, 2, 4].map(|bit| if corner & bit == 0 { -0.5 } else { 0.5 }));
+    Aabb::from_points(corners.map(|corner| place_primitive_vertex(primitive, corner)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::flipper::Flipper;
+    use crate::vpx::gameitem::timer::Timer;
+    use crate::vpx::gameitem::wall::Wall;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bounds_per_item() {
+        let drag_points = [(100.0, 100.0), (200.0, 100.0), (150.0, 300.0)]
+            .iter()
+            .map(|&(x, y)| DragPoint {
+                x,
+                y,
+                ..Default::default()
+            })
+            .collect();
+        let wall = Wall {
+            height_top: 50.0,
+            drag_points,
+            ..Default::default()
+        };
+        assert_eq!(
+            bounds(&GameItemEnum::Wall(wall)),
+            Some(Aabb {
+                min: [100.0, 100.0, 0.0],
+                max: [200.0, 300.0, 50.0],
+            })
+        );
+
+        // a left flipper pointing down to the right, swinging up past the horizontal
+        let mut flipper = Flipper::default();
+        flipper.center = Vertex2D { x: 0.0, y: 0.0 };
+        flipper.start_angle = 120.0;
+        flipper.end_angle = 70.0;
+        flipper.flipper_radius_max = 100.0;
+        flipper.base_radius = 20.0;
+        flipper.end_radius = 10.0;
+        let flipper = bounds(&GameItemEnum::Flipper(flipper)).unwrap();
+        assert_eq!(flipper.max[0], 110.0);
+        assert_eq!(flipper.min[0], -20.0);
+        assert!((flipper.max[1] - (100.0 * 60f32.to_radians().cos() + 10.0)).abs() < 1e-3);
+
+        assert_eq!(bounds(&GameItemEnum::Timer(Timer::default())), None);
+    }
+
+    #[test]
+    fn bounds_on_upper_playfield() {
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "UpperPF".to_string(),
+            height_top: 60.0,
+            ..Default::default()
+        }));
+        let mut flipper = Flipper::default();
+        flipper.surface = "UpperPF".to_string();
+        flipper.height = 50.0;
+        let flipper = GameItemEnum::Flipper(flipper);
+        let surfaces = SurfaceHeightResolver::new(&vpx);
+        let placed = placed_bounds(&flipper, &surfaces).unwrap();
+        assert_eq!((placed.min[2], placed.max[2]), (60.0, 110.0));
+        let wall = &vpx.gameitems[0];
+        assert_eq!(placed_bounds(wall, &surfaces), bounds(wall));
+    }
+
+    #[test]
+    fn outside_playfield() -> testresult::TestResult {
+        let mut vpx = crate::vpx::read(&std::path::PathBuf::from(
+            "testdata/completely_blank_table_10_7_4.vpx",
+        ))?;
+        let before = items_outside_playfield(&vpx).len();
+        let mut flipper = Flipper::default();
+        flipper.name = "Outside".to_string();
+        flipper.center = Vertex2D {
+            x: vpx.gamedata.right + 10.0,
+            y: 100.0,
+        };
+        vpx.add_game_item(GameItemEnum::Flipper(flipper));
+        let outside = items_outside_playfield(&vpx);
+        assert_eq!(outside.len(), before + 1);
+        assert!(outside.iter().any(|item| item.name() == "Outside"));
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! Creating a table from scratch
+//!
+//! [`VpxBuilder`] starts from the same empty table [`super::new_minimal_vpx`] writes. The
+//! counts in the game data are set from the added items when building, the MAC is generated
+//! when the table is written.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::builder::VpxBuilder;
+//! use vpin::vpx::gameitem::wall::Wall;
+//! use vpin::vpx::gameitem::GameItemEnum;
+//!
+//! let vpx = VpxBuilder::new()
+//!     .table_name("My Table")
+//!     .author_name("Me")
+//!     .script("Option Explicit\n")
+//!     .game_item(GameItemEnum::Wall(Wall {
+//!         name: "Wall1".to_string(),
+//!         ..Default::default()
+//!     }))
+//!     .build();
+//! assert_eq!(vpx.gamedata.gameitems_size, 1);
+//! ```
+
+use super::collection::Collection;
+use super::font::FontData;
+use super::gameitem::GameItemEnum;
+use super::image::ImageData;
+use super::sound::SoundData;
+use super::tableinfo::TableInfo;
+use super::version::Version;
+use super::VPX;
+use std::io;
+use std::path::Path;
+
+/// Builds a [`VPX`], see the [module docs](self)
+#[derive(Debug)]
+pub struct VpxBuilder {
+    vpx: VPX,
+}
+
+impl Default for VpxBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VpxBuilder {
+    pub fn new() -> Self {
+        VpxBuilder {
+            vpx: VPX {
+                info: TableInfo::new(),
+                version: Version::new(1072),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The file format version, 1072 by default
+    pub fn version(mut self, version: Version) -> Self {
+        self.vpx.version = version;
+        self
+    }
+
+    /// Replaces all table info, including the custom properties
+    pub fn info(mut self, info: TableInfo) -> Self {
+        self.vpx.custominfotags = sorted_keys(&info);
+        self.vpx.info = info;
+        self
+    }
+
+    pub fn table_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.vpx.info.table_name = Some(name.into());
+        self
+    }
+
+    pub fn author_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.vpx.info.author_name = Some(name.into());
+        self
+    }
+
+    /// Adds a custom info property, replacing the value if the property exists
+    pub fn info_property<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        if !self.vpx.custominfotags.contains(&key) {
+            self.vpx.custominfotags.push(key.clone());
+        }
+        self.vpx.info.properties.insert(key, value.into());
+        self
+    }
+
+    pub fn script<S: Into<String>>(mut self, script: S) -> Self {
+        self.vpx.set_script(script.into());
+        self
+    }
+
+    pub fn game_item(mut self, item: GameItemEnum) -> Self {
+        self.vpx.gameitems.push(item);
+        self
+    }
+
+    /// Adds an image, replacing an image with the same name ignoring case
+    pub fn image(mut self, image: ImageData) -> Self {
+        self.vpx.add_or_replace_image(image);
+        self
+    }
+
+    pub fn sound(mut self, sound: SoundData) -> Self {
+        self.vpx.sounds.push(sound);
+        self
+    }
+
+    pub fn font(mut self, font: FontData) -> Self {
+        self.vpx.fonts.push(font);
+        self
+    }
+
+    pub fn collection(mut self, collection: Collection) -> Self {
+        self.vpx.collections.push(collection);
+        self
+    }
+
+    /// The table with the counts in the game data matching the added items
+    pub fn build(mut self) -> VPX {
+        let gamedata = &mut self.vpx.gamedata;
+        gamedata.gameitems_size = self.vpx.gameitems.len() as u32;
+        gamedata.images_size = self.vpx.images.len() as u32;
+        gamedata.sounds_size = self.vpx.sounds.len() as u32;
+        gamedata.fonts_size = self.vpx.fonts.len() as u32;
+        gamedata.collections_size = self.vpx.collections.len() as u32;
+        self.vpx
+    }
+
+    /// Builds the table and writes it to a new `vpx` file
+    pub fn write<P: AsRef<Path>>(self, path: P) -> io::Result<VPX> {
+        let vpx = self.build();
+        super::write(path, &vpx)?;
+        Ok(vpx)
+    }
+}
+
+/// The custom property names in a stable order, the info stores them in a map
+fn sorted_keys(info: &TableInfo) -> Vec<String> {
+    let mut keys: Vec<String> = info.properties.keys().cloned().collect();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::image::ImageDataJpeg;
+    use crate::vpx::{read, verify, VerifyResult};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn build_and_write() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("built.vpx");
+        let vpx = VpxBuilder::new()
+            .table_name("Built")
+            .info_property("Rom", "built_l1")
+            .script("Option Explicit\n")
+            .game_item(GameItemEnum::Wall(Wall {
+                name: "Wall1".to_string(),
+                ..Default::default()
+            }))
+            .image(ImageData {
+                name: "pf".to_string(),
+                path: "pf.png".to_string(),
+                width: 1,
+                height: 1,
+                jpeg: Some(ImageDataJpeg {
+                    path: "pf.png".to_string(),
+                    name: "pf".to_string(),
+                    internal_name: None,
+                    data: vec![1, 2, 3],
+                }),
+                ..Default::default()
+            })
+            .collection(Collection {
+                name: "Walls".to_string(),
+                items: vec!["Wall1".to_string()],
+                fire_events: false,
+                stop_single_events: false,
+                group_elements: true,
+            })
+            .write(&path)?;
+
+        assert_eq!(vpx.gamedata.gameitems_size, 1);
+        assert_eq!(vpx.gamedata.images_size, 1);
+        assert_eq!(vpx.gamedata.collections_size, 1);
+        assert!(matches!(verify(&path), VerifyResult::Ok(_)));
+        assert_eq!(read(&path)?, vpx);
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! A summary of a table for attract mode screens and launchers
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let card = vpx::card(&vpx);
+//! println!("{:?} by {:?}", card.name, card.author);
+//! for line in card.wrapped_rules(40) {
+//!     println!("{}", line);
+//! }
+//! ```
+
+use super::stats::table_geometry_estimate;
+use super::tableinfo::windows_1252_char;
+use super::VPX;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TableCard {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub release_date: Option<String>,
+    pub blurb: Option<String>,
+    pub description: Option<String>,
+    /// The rules with hard line breaks inside paragraphs removed, see [`clean_text`]
+    pub rules: Option<String>,
+    pub stats: CardStats,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CardStats {
+    /// The Visual Pinball version the table was saved with, eg `10.7`
+    pub file_version: String,
+    /// Number of game items per type, sorted by type name
+    pub item_counts: Vec<(String, usize)>,
+    pub images: usize,
+    pub sounds: usize,
+    /// Estimated triangle count, see [`crate::vpx::stats`]
+    pub triangles: usize,
+}
+
+impl TableCard {
+    /// The rules wrapped to lines of at most `width` characters, paragraphs are separated by
+    /// an empty line.
+    pub fn wrapped_rules(&self, width: usize) -> Vec<String> {
+        self.rules
+            .as_deref()
+            .map(|rules| wrap(rules, width))
+            .unwrap_or_default()
+    }
+}
+
+/// Collects the table info and some statistics of a table.
+///
+/// Text fields are cleaned up with [`clean_text`], fields that end up empty are `None`.
+pub fn card(vpx: &VPX) -> TableCard {
+    let info = &vpx.info;
+    let field = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(clean_text)
+            .filter(|text| !text.is_empty())
+    };
+    let mut item_counts: Vec<(String, usize)> = Vec::new();
+    for item in &vpx.gameitems {
+        let type_name = item.type_name();
+        match item_counts.iter_mut().find(|(name, _)| *name == type_name) {
+            Some((_, count)) => *count += 1,
+            None => item_counts.push((type_name, 1)),
+        }
+    }
+    item_counts.sort();
+    TableCard {
+        name: field(&info.table_name),
+        author: field(&info.author_name),
+        version: field(&info.table_version),
+        release_date: field(&info.release_date),
+        blurb: field(&info.table_blurb),
+        description: field(&info.table_description),
+        rules: field(&info.table_rules),
+        stats: CardStats {
+            file_version: vpx.version.to_string(),
+            item_counts,
+            images: vpx.images.len(),
+            sounds: vpx.sounds.len(),
+            triangles: table_geometry_estimate(vpx).triangles,
+        },
+    }
+}
+
+/// Cleans up text typed in the table info dialog.
+///
+/// * Text that was saved as UTF-8 but read as Windows-1252 (`donâ€™t`) is repaired.
+/// * Line endings are normalized, control characters and trailing whitespace are removed.
+/// * Lines within a paragraph are joined, list items (`-`, `*`, `•`, `1.`, `1)`) and lines
+///   following a line ending in `:` stay on their own line.
+/// * Paragraphs are separated by a single empty line.
+pub fn clean_text(text: &str) -> String {
+    let text = fix_encoding(text);
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let mut paragraphs: Vec<Vec<String>> = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line: String = line
+            .chars()
+            .map(|c| if c == '\t' || c == '\u{a0}' { ' ' } else { c })
+            .filter(|c| !c.is_control() && *c != '\u{fffd}')
+            .collect();
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        match current.last_mut() {
+            Some(previous) if !previous.ends_with(':') && !is_list_item(&line) => {
+                previous.push(' ');
+                previous.push_str(&line);
+            }
+            _ => current.push(line),
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+        .iter()
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Wraps every line of a text at word boundaries to at most `width` characters.
+///
+/// Words longer than the width get a line of their own. Empty lines are kept.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            let length = current.chars().count();
+            if length > 0 && length + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+fn is_list_item(line: &str) -> bool {
+    if line.starts_with(['-', '*', '•']) {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && line[digits..].starts_with(['.', ')'])
+}
+
+/// Undoes UTF-8 that was decoded as Windows-1252, other text is returned unchanged
+fn fix_encoding(text: &str) -> String {
+    if !text.contains(['Ã', 'Â', 'â']) {
+        return text.to_string();
+    }
+    let bytes: Option<Vec<u8>> = text.chars().map(windows_1252_byte).collect();
+    bytes
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| text.to_string())
+}
+
+fn windows_1252_byte(c: char) -> Option<u8> {
+    // also covers the bytes Windows-1252 leaves undefined
+    if (c as u32) < 0x100 {
+        return Some(c as u8);
+    }
+    (0x80..0xa0).find(|byte| windows_1252_char(*byte) == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::flipper::Flipper;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::GameItemEnum;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn clean_rules() {
+        let rules = "RULES:\r\n  Hit the  ramps to\r\nlight the jackpot.\r\n\r\n\r\n\
+            - Spinner\r\nadds 1000\r\n* 2) Donâ€™t drain\tÂ\u{a0}\r\n1. First\r\n";
+        assert_eq!(
+            clean_text(rules),
+            "RULES:\nHit the ramps to light the jackpot.\n\n- Spinner adds 1000\n* 2) Don’t drain\n1. First"
+        );
+        // text that only looks broken stays as is
+        assert_eq!(clean_text("Âme 😀"), "Âme 😀");
+    }
+
+    #[test]
+    fn wrap_text() {
+        assert_eq!(
+            wrap("one two three four\n\nsupercalifragilistic x", 9),
+            vec!["one two", "three", "four", "", "supercalifragilistic", "x"]
+        );
+    }
+
+    #[test]
+    fn table_card() {
+        let mut vpx = VPX::default();
+        vpx.info.table_name = Some("Test Table".to_string());
+        vpx.info.author_name = Some("  ".to_string());
+        vpx.info.table_rules = Some("Shoot\r\nthe ramp".to_string());
+        vpx.add_game_item(GameItemEnum::Light(Light::default()));
+        vpx.add_game_item(GameItemEnum::Flipper(Flipper::default()));
+        vpx.add_game_item(GameItemEnum::Light(Light::default()));
+
+        let card = card(&vpx);
+
+        assert_eq!(card.name, Some("Test Table".to_string()));
+        assert_eq!(card.author, None);
+        assert_eq!(card.rules, Some("Shoot the ramp".to_string()));
+        assert_eq!(card.wrapped_rules(10), vec!["Shoot the", "ramp"]);
+        assert_eq!(
+            card.stats.item_counts,
+            vec![("Flipper".to_string(), 1), ("Light".to_string(), 2)]
+        );
+        assert_eq!(card.stats.triangles, 0);
+    }
+}
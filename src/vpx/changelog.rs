@@ -0,0 +1,165 @@
+//! A log of the changes made to a table by the operations in this library
+//!
+//! Operations on a [`VPX`] like [`super::edit::rename_item`],
+//! [`super::edit::mirror_horizontal`], [`super::import::items`] and [`VPX::add_font`] record
+//! what they changed in [`VPX::changelog`], so a tool can show the changes before saving. The
+//! log is not part of the `vpx` format, [`write_to_info`] keeps it in a custom info property
+//! of the table.
+//!
+//! Operations that only get a single item, like [`super::mesh::fix::fix_winding`] and
+//! [`super::mesh::external::inline_mesh`], or that work on files, like the repairs in
+//! [`super::repair`] and the font helpers in [`super::expanded`], have no table to log to.
+//! Their callers can record what they changed.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx::{changelog, edit, read};
+//!
+//! let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+//! let mut vpx = read(&path).unwrap();
+//! edit::mirror_horizontal(&mut vpx).unwrap();
+//! print!("{}", vpx.changelog);
+//! changelog::write_to_info(&mut vpx);
+//! ```
+
+use super::VPX;
+use std::fmt;
+
+/// The custom info property [`write_to_info`] writes to
+pub const INFO_PROPERTY: &str = "ModificationLog";
+
+/// A single change
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Modification {
+    /// The operation that made the change, eg `rename_item`
+    pub operation: String,
+    pub description: String,
+}
+
+impl fmt::Display for Modification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.operation, self.description)
+    }
+}
+
+/// The changes in the order they were made
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ModificationLog {
+    pub entries: Vec<Modification>,
+}
+
+impl ModificationLog {
+    pub fn record<O: Into<String>, D: Into<String>>(&mut self, operation: O, description: D) {
+        self.entries.push(Modification {
+            operation: operation.into(),
+            description: description.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl fmt::Display for ModificationLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the log to the [`INFO_PROPERTY`] custom info property and clears it, so the
+/// changes are saved with the table.
+pub fn write_to_info(vpx: &mut VPX) {
+    if vpx.changelog.is_empty() {
+        return;
+    }
+    let key = INFO_PROPERTY.to_string();
+    if !vpx.custominfotags.contains(&key) {
+        vpx.custominfotags.push(key.clone());
+    }
+    let value = vpx.info.properties.entry(key).or_default();
+    value.push_str(&vpx.changelog.to_string());
+    vpx.changelog.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::gameitem::GameItemEnum;
+    use crate::vpx::import::{self, Selection};
+    use crate::vpx::{edit, read, write};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn operations_are_logged_and_saved() -> std::io::Result<()> {
+        let mut from = VPX::default();
+        from.add_game_item(GameItemEnum::Wall(Wall {
+            name: "Wall1".to_string(),
+            ..Default::default()
+        }));
+        let mut vpx = VPX::default();
+        vpx.set_script("Sub Wall1_Hit\nEnd Sub\n".to_string());
+        import::items(&from, &mut vpx, &Selection::items(&["Wall1"]))?;
+        edit::rename_item(&mut vpx, "Wall1", "Guide")?;
+        edit::rename_script_identifier(&mut vpx, "Wall1", "Guide");
+        edit::rename_script_identifier(&mut vpx, "NotThere", "Other");
+
+        let operations: Vec<&str> = vpx
+            .changelog
+            .entries
+            .iter()
+            .map(|entry| entry.operation.as_str())
+            .collect();
+        assert_eq!(
+            operations,
+            vec!["import", "rename_item", "rename_script_identifier"]
+        );
+
+        write_to_info(&mut vpx);
+        assert!(vpx.changelog.is_empty());
+        let dir = testdir!();
+        let path = dir.join("logged.vpx");
+        write(&path, &vpx)?;
+        let read_vpx = read(&path)?;
+        assert_eq!(read_vpx.custominfotags, vec![INFO_PROPERTY.to_string()]);
+        assert_eq!(
+            read_vpx
+                .info
+                .properties
+                .get(INFO_PROPERTY)
+                .map(String::as_str),
+            Some(
+                "import: Added game item Wall1\n\
+                 rename_item: Renamed game item Wall1 to Guide\n\
+                 rename_script_identifier: Renamed 1 script references from Wall1 to Guide\n"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn font_operations_are_logged() {
+        let mut vpx = VPX::default();
+        vpx.add_font("Digital", vec![1]);
+        vpx.add_font("digital", vec![2]);
+        vpx.remove_font("DIGITAL");
+        vpx.remove_font("Missing");
+        assert_eq!(
+            vpx.changelog.to_string(),
+            "add_font: Added font Digital\n\
+             add_font: Replaced font digital\n\
+             remove_font: Removed font digital\n"
+        );
+    }
+}
@@ -74,7 +74,6 @@ pub fn write(collection: &Collection) -> Vec<u8> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use pretty_assertions::assert_eq;
 
     #[test]
     fn write_read() {
@@ -137,6 +137,18 @@ impl Color {
         Self { r, g, b, unused: 0 }
     }
 
+    /// Converts the sRGB encoded color to linear rgb, like Visual Pinball does before lighting
+    pub fn to_linear(&self) -> [f32; 3] {
+        [self.r, self.g, self.b].map(|channel| {
+            let c = channel as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    }
+
     pub fn biff_read(reader: &mut BiffReader<'_>) -> Color {
         // since we read in little endian, we need to read the color in BGR0 format
         let r = reader.get_u8();
@@ -168,6 +180,15 @@ impl std::fmt::Display for Color {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_linear() {
+        let [r, g, b] = Color::rgb(255, 128, 10).to_linear();
+        assert_eq!(r, 1.0);
+        assert!((g - 0.2158605).abs() < 1e-6);
+        assert!((b - 0.003_035_27).abs() < 1e-6);
+        assert_eq!(Color::BLACK.to_linear(), [0.0, 0.0, 0.0]);
+    }
+
     #[test]
     fn test_color_serde() {
         let color = Color::rgb(0x12, 0x34, 0x56);
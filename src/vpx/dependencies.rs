@@ -0,0 +1,286 @@
+//! Scanning table scripts for external dependencies
+//!
+//! Tables using an external DMD (FlexDMD, UltraDMD or PinUP Player) reference their assets
+//! from the script. These assets are not part of the `vpx` file and need to be distributed
+//! alongside the table.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::dependencies::{find_dmd_dependencies, DmdDependency};
+//!
+//! let script = r#"
+//! FlexDMD.ProjectFolder = "./MyTable/"
+//! Set img = FlexDMD.NewImage("logo", "logo.png")
+//! "#;
+//! let dependencies = find_dmd_dependencies(script);
+//! assert_eq!(dependencies, vec![
+//!     DmdDependency::FlexDmdProjectFolder("./MyTable/".to_string()),
+//!     DmdDependency::FlexDmdAsset("logo.png".to_string()),
+//! ]);
+//! ```
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// An external DMD asset or folder referenced by a table script
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DmdDependency {
+    /// `FlexDMD.ProjectFolder`, relative to the table directory
+    FlexDmdProjectFolder(String),
+    /// An image, video or font loaded by FlexDMD, relative to the FlexDMD project folder
+    FlexDmdAsset(String),
+    /// A folder passed to `UltraDMD.SetProjectFolder`, relative to the table directory
+    UltraDmdProjectFolder(String),
+    /// A PinUP Player pack, located in the `PUPVideos` folder
+    PupPack(String),
+}
+
+/// A [`DmdDependency`] resolved to a location on disk
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ResolvedDmdDependency {
+    pub dependency: DmdDependency,
+    /// `None` if the location could not be determined, eg a PuP pack without a PUPVideos folder
+    pub path: Option<PathBuf>,
+    pub exists: bool,
+}
+
+// FlexDMD embedded resources and vpx images are not external files
+const FLEXDMD_INTERNAL_PREFIXES: [&str; 2] = ["flexdmd.resources.", "vpx."];
+
+/// Scans a script for FlexDMD, UltraDMD and PinUP Player references.
+///
+/// Only string literals are detected, paths built at runtime from variables are not.
+/// Commented out code is ignored. Every dependency is only listed once.
+pub fn find_dmd_dependencies(script: &str) -> Vec<DmdDependency> {
+    let flex_project_folder = Regex::new(r#"(?i)\.ProjectFolder\s*=\s*"([^"]+)""#).unwrap();
+    let flex_asset =
+        Regex::new(r#"(?i)\.New(?:Image|Video)\s*\(\s*"[^"]*"\s*,\s*"([^"]+)""#).unwrap();
+    let flex_font = Regex::new(r#"(?i)\.NewFont\s*\(\s*"([^"]+)""#).unwrap();
+    let ultra_project_folder =
+        Regex::new(r#"(?i)\.SetProjectFolder\s*\(?[^"\r\n]*"([^"]+)""#).unwrap();
+    let pup_init = Regex::new(r#"(?i)\.B2SInit\s*\(?\s*"[^"]*"\s*,\s*"([^"]+)""#).unwrap();
+    let pup_pack = Regex::new(r#"(?i)\bcPuPPack\s*=\s*"([^"]+)""#).unwrap();
+
+    let mut dependencies = Vec::new();
+    let mut add = |dependency: DmdDependency| {
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+    };
+    for line in script.lines() {
+        let code = strip_comment(line);
+        for c in flex_project_folder.captures_iter(code) {
+            add(DmdDependency::FlexDmdProjectFolder(c[1].to_string()));
+        }
+        for c in flex_asset
+            .captures_iter(code)
+            .chain(flex_font.captures_iter(code))
+        {
+            if let Some(asset) = flexdmd_asset_path(&c[1]) {
+                add(DmdDependency::FlexDmdAsset(asset));
+            }
+        }
+        for c in ultra_project_folder.captures_iter(code) {
+            add(DmdDependency::UltraDmdProjectFolder(c[1].to_string()));
+        }
+        for c in pup_init
+            .captures_iter(code)
+            .chain(pup_pack.captures_iter(code))
+        {
+            add(DmdDependency::PupPack(c[1].to_string()));
+        }
+    }
+    dependencies
+}
+
+/// Finds the PinMAME rom name, usually set with `Const cGameName = "rom"`.
+///
+/// Commented out code is ignored, the first match is returned.
+pub fn find_rom_name(script: &str) -> Option<String> {
+    let game_name = Regex::new(r#"(?i)\bcGameName\s*=\s*"([^"]+)""#).unwrap();
+    script.lines().find_map(|line| {
+        game_name
+            .captures(strip_comment(line))
+            .map(|c| c[1].to_string())
+    })
+}
+
+/// Resolves dependencies against the directory the table is in and checks if they exist.
+///
+/// FlexDMD assets are resolved against the last FlexDMD project folder in the list, or the
+/// table directory if there is none. PuP packs can only be resolved if the `PUPVideos`
+/// folder is provided.
+pub fn resolve_dmd_dependencies(
+    dependencies: &[DmdDependency],
+    table_dir: &Path,
+    pup_videos_dir: Option<&Path>,
+) -> Vec<ResolvedDmdDependency> {
+    let flex_project_dir = dependencies
+        .iter()
+        .rev()
+        .find_map(|d| match d {
+            DmdDependency::FlexDmdProjectFolder(folder) => {
+                Some(join_script_path(table_dir, folder))
+            }
+            _ => None,
+        })
+        .unwrap_or_else(|| table_dir.to_path_buf());
+    dependencies
+        .iter()
+        .map(|dependency| {
+            let path = match dependency {
+                DmdDependency::FlexDmdProjectFolder(folder)
+                | DmdDependency::UltraDmdProjectFolder(folder) => {
+                    Some(join_script_path(table_dir, folder))
+                }
+                DmdDependency::FlexDmdAsset(asset) => {
+                    Some(join_script_path(&flex_project_dir, asset))
+                }
+                DmdDependency::PupPack(pack) => {
+                    pup_videos_dir.map(|dir| join_script_path(dir, pack))
+                }
+            };
+            let exists = path.as_ref().is_some_and(|p| p.exists());
+            ResolvedDmdDependency {
+                dependency: dependency.clone(),
+                path,
+                exists,
+            }
+        })
+        .collect()
+}
+
+/// FlexDMD asset strings can contain options after a `&`, eg `image.png&region=0,0,32,32`
+fn flexdmd_asset_path(asset: &str) -> Option<String> {
+    let path = asset.split('&').next().unwrap_or_default().trim();
+    let lower = path.to_ascii_lowercase();
+    if path.is_empty()
+        || FLEXDMD_INTERNAL_PREFIXES
+            .iter()
+            .any(|p| lower.starts_with(p))
+    {
+        None
+    } else {
+        Some(path.to_string())
+    }
+}
+
+/// Joins a windows style path as found in scripts to a base directory
+fn join_script_path(base: &Path, script_path: &str) -> PathBuf {
+    script_path
+        .split(['\\', '/'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .fold(base.to_path_buf(), |path, part| path.join(part))
+}
+
+/// Removes a trailing `'` comment from a line of VBScript, ignoring quotes in strings.
+pub(crate) fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '\'' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    let trimmed = line.trim_start();
+    if trimmed
+        .get(..3)
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("rem"))
+        && trimmed[3..].starts_with(char::is_whitespace)
+    {
+        return "";
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn find_all_dependencies() {
+        let script = r#"
+Sub InitFlex
+    Set FlexDMD = CreateObject("FlexDMD.FlexDMD")
+    FlexDMD.ProjectFolder = ".\MyTable.FlexDMD\"
+    Set font = FlexDMD.NewFont("FlexDMD.Resources.udmd-f4by5.fnt", vbWhite, vbBlack, 0)
+    Set font2 = FlexDMD.NewFont("fonts/big.fnt", vbWhite, vbBlack, 0)
+    scene.AddActor FlexDMD.NewImage("bg", "bg.png&region=0,0,128,32")
+    scene.AddActor FlexDMD.NewImage("vpx", "VPX.playfield")
+    scene.AddActor FlexDMD.NewVideo("intro", "intro.gif")
+    ' scene.AddActor FlexDMD.NewVideo("old", "old.gif")
+    Rem scene.AddActor FlexDMD.NewVideo("older", "older.gif")
+    scene.AddActor FlexDMD.NewImage("bg2", "bg.png")
+End Sub
+UltraDMD.SetProjectFolder TablesDirectory & "\MyTable.UltraDMD"
+Const cPuPPack = "mytable" ' the pup pack
+PuPlayer.B2SInit "", "mytable"
+"#;
+        let dependencies = find_dmd_dependencies(script);
+        assert_eq!(
+            dependencies,
+            vec![
+                DmdDependency::FlexDmdProjectFolder(".\\MyTable.FlexDMD\\".to_string()),
+                DmdDependency::FlexDmdAsset("fonts/big.fnt".to_string()),
+                DmdDependency::FlexDmdAsset("bg.png".to_string()),
+                DmdDependency::FlexDmdAsset("intro.gif".to_string()),
+                DmdDependency::UltraDmdProjectFolder("\\MyTable.UltraDMD".to_string()),
+                DmdDependency::PupPack("mytable".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_rom() {
+        let script = r#"
+' Const cGameName = "old"
+Const cGameName="tz_94h" ' the rom
+"#;
+        assert_eq!(find_rom_name(script), Some("tz_94h".to_string()));
+        assert_eq!(find_rom_name("Option Explicit"), None);
+    }
+
+    #[test]
+    fn resolve_dependencies() {
+        let dir: PathBuf = testdir!();
+        let flex_dir = dir.join("MyTable.FlexDMD");
+        std::fs::create_dir_all(&flex_dir).unwrap();
+        std::fs::write(flex_dir.join("bg.png"), []).unwrap();
+        let dependencies = vec![
+            DmdDependency::FlexDmdProjectFolder(".\\MyTable.FlexDMD\\".to_string()),
+            DmdDependency::FlexDmdAsset("bg.png".to_string()),
+            DmdDependency::FlexDmdAsset("missing.gif".to_string()),
+            DmdDependency::PupPack("mytable".to_string()),
+        ];
+        let resolved = resolve_dmd_dependencies(&dependencies, &dir, None);
+        assert_eq!(
+            resolved,
+            vec![
+                ResolvedDmdDependency {
+                    dependency: dependencies[0].clone(),
+                    path: Some(flex_dir.clone()),
+                    exists: true,
+                },
+                ResolvedDmdDependency {
+                    dependency: dependencies[1].clone(),
+                    path: Some(flex_dir.join("bg.png")),
+                    exists: true,
+                },
+                ResolvedDmdDependency {
+                    dependency: dependencies[2].clone(),
+                    path: Some(flex_dir.join("missing.gif")),
+                    exists: false,
+                },
+                ResolvedDmdDependency {
+                    dependency: dependencies[3].clone(),
+                    path: None,
+                    exists: false,
+                },
+            ]
+        );
+    }
+}
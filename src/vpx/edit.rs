@@ -0,0 +1,761 @@
+//! Editing operations that keep a table consistent
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx::{edit, read};
+//!
+//! let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+//! let mut vpx = read(&path).unwrap();
+//! edit::rename_item(&mut vpx, "LeftFlipper", "FlipperLeft").unwrap();
+//! let replaced = edit::rename_script_identifier(&mut vpx, "LeftFlipper", "FlipperLeft");
+//! println!("{} script references updated", replaced);
+//! ```
+
+use std::io;
+
+use regex::Regex;
+
+use super::expanded::{compress_data, decompress_data};
+use super::gameitem::dragpoint::DragPoint;
+use super::gameitem::primitive::Primitive;
+use super::gameitem::ramp::RampType;
+use super::gameitem::vertex2d::Vertex2D;
+use super::gameitem::vertex3d::Vertex3D;
+use super::gameitem::GameItemEnum;
+use super::math::Mat3;
+use super::mesh::Mesh;
+use super::VPX;
+
+/// What was updated by [`rename_item`]
+#[derive(Debug, PartialEq, Default)]
+pub struct RenameResult {
+    /// Names of the collections that contained the item
+    pub collections: Vec<String>,
+    /// Names of the items that were placed on the renamed item
+    pub surface_references: Vec<String>,
+}
+
+/// Renames a game item and updates all references to it in collections and items placed
+/// on it (eg a light on a wall).
+///
+/// Names are matched case-insensitive, like VPinball does. The script is not updated,
+/// use [`rename_script_identifier`] for that.
+///
+/// Fails if there is no item named `old` or if an item named `new` already exists.
+pub fn rename_item(vpx: &mut VPX, old: &str, new: &str) -> io::Result<RenameResult> {
+    if new.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "New item name can not be empty",
+        ));
+    }
+    let index = vpx
+        .gameitems
+        .iter()
+        .position(|item| item.name().eq_ignore_ascii_case(old))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Game item not found: {}", old),
+            )
+        })?;
+    let conflict = vpx
+        .gameitems
+        .iter()
+        .enumerate()
+        .any(|(i, item)| i != index && item.name().eq_ignore_ascii_case(new));
+    if conflict {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Game item already exists: {}", new),
+        ));
+    }
+
+    let old_name = vpx.gameitems[index].name().to_string();
+    vpx.gameitems[index].set_name(new.to_string());
+    vpx.changelog.record(
+        "rename_item",
+        format!("Renamed game item {} to {}", old_name, new),
+    );
+
+    let mut result = RenameResult::default();
+    for collection in vpx.collections.iter_mut() {
+        let mut found = false;
+        for item in collection.items.iter_mut() {
+            if item.eq_ignore_ascii_case(old) {
+                *item = new.to_string();
+                found = true;
+            }
+        }
+        if found {
+            result.collections.push(collection.name.clone());
+        }
+    }
+    for item in vpx.gameitems.iter_mut() {
+        if let Some(surface) = item.surface_mut() {
+            if surface.eq_ignore_ascii_case(old) {
+                *surface = new.to_string();
+                result.surface_references.push(item.name().to_string());
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Renames an identifier in the table script, including event handlers like `Old_Hit`.
+///
+/// String literals, comments and member accesses (`obj.Old`) are left untouched.
+/// Returns the number of replacements.
+pub fn rename_script_identifier(vpx: &mut VPX, old: &str, new: &str) -> usize {
+    let (script, count) = replace_identifier(&vpx.gamedata.code.string, old, new);
+    if count > 0 {
+        vpx.gamedata.code.string = script;
+        vpx.changelog.record(
+            "rename_script_identifier",
+            format!(
+                "Renamed {} script references from {} to {}",
+                count, old, new
+            ),
+        );
+    }
+    count
+}
+
+fn replace_identifier(script: &str, old: &str, new: &str) -> (String, usize) {
+    let re = Regex::new(&format!(r"(?i)\b{}(_\w*)?\b", regex::escape(old))).unwrap();
+    let mut result = String::with_capacity(script.len());
+    let mut count = 0;
+    // split_inclusive keeps the original line endings
+    for line in script.split_inclusive('\n') {
+        let code_end = code_length(line);
+        let mut last = 0;
+        for captures in re.captures_iter(&line[..code_end]) {
+            let m = captures.get(0).unwrap();
+            let before = &line[..m.start()];
+            if in_string(before) || before.ends_with('.') {
+                continue;
+            }
+            result.push_str(&line[last..m.start()]);
+            result.push_str(new);
+            // the case-insensitive match can have another length than the old name
+            result.push_str(captures.get(1).map_or("", |suffix| suffix.as_str()));
+            last = m.end();
+            count += 1;
+        }
+        result.push_str(&line[last..]);
+    }
+    (result, count)
+}
+
+/// Length of a line of VBScript without its trailing comment
+pub(crate) fn code_length(line: &str) -> usize {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '\'' if !in_string => return i,
+            _ => {}
+        }
+    }
+    line.len()
+}
+
+fn in_string(before: &str) -> bool {
+    before.chars().filter(|c| *c == '"').count() % 2 == 1
+}
+
+/// Moves, rotates, scales or mirrors game items: every position `p` becomes
+/// `matrix * p + offset`.
+///
+/// Centers, drag points, rotations and sizes of all playfield items are updated. Backglass
+/// items (text boxes, reels and backglass decals and lights) are left alone. Only primitives
+/// and hit targets have an absolute height, for other items the z of the offset is ignored.
+///
+/// Positions follow the matrix exactly. Sizes and radii are scaled by the average scale
+/// factor, so with a non-uniform scale round items stay round. Primitive rotations are only
+/// exact when the matrix is a rotation, mirror and/or uniform scale.
+///
+/// When the matrix mirrors the playfield the winding of closed drag point shapes is kept and
+/// the left and right side of ramps are swapped. The meshes of primitives are mirrored with
+/// their triangles turned around, so they keep facing outwards.
+///
+/// Fails when the mesh of a primitive can not be decoded, the items before it are already
+/// transformed.
+pub fn transform(items: &mut [GameItemEnum], matrix: &Mat3, offset: [f32; 3]) -> io::Result<()> {
+    let transformation = Transformation::new(matrix, offset);
+    for item in items {
+        transformation.apply(item)?;
+    }
+    Ok(())
+}
+
+/// Mirrors the playfield left to right around the center of the table.
+///
+/// Flippers, the plunger and everything else end up on the other side with their angles
+/// mirrored, while walls keep facing outwards. Names and the script are not changed, so a
+/// `LeftFlipper` ends up on the right. Images are not mirrored either.
+///
+/// Fails when the mesh of a primitive can not be decoded, see [`transform`].
+pub fn mirror_horizontal(vpx: &mut VPX) -> io::Result<()> {
+    let offset = [vpx.gamedata.left + vpx.gamedata.right, 0.0, 0.0];
+    transform(&mut vpx.gameitems, &Mat3::mirror_x(), offset)?;
+    vpx.changelog
+        .record("mirror_horizontal", "Mirrored the playfield left to right");
+    Ok(())
+}
+
+struct Transformation<'a> {
+    matrix: &'a Mat3,
+    offset: [f32; 3],
+    /// Scale factor for lengths on the playfield
+    scale_2d: f32,
+    mirrored: bool,
+}
+
+impl<'a> Transformation<'a> {
+    fn new(matrix: &'a Mat3, offset: [f32; 3]) -> Self {
+        let m = &matrix.0;
+        let determinant_2d = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        Transformation {
+            matrix,
+            offset,
+            scale_2d: determinant_2d.abs().sqrt(),
+            mirrored: determinant_2d < 0.0,
+        }
+    }
+
+    fn point_2d(&self, point: &mut Vertex2D) {
+        let [x, y, _] = self.matrix.transform([point.x, point.y, 0.0]);
+        point.x = x + self.offset[0];
+        point.y = y + self.offset[1];
+    }
+
+    fn xy(&self, x: &mut f32, y: &mut f32) {
+        let mut point = Vertex2D { x: *x, y: *y };
+        self.point_2d(&mut point);
+        *x = point.x;
+        *y = point.y;
+    }
+
+    fn point_3d(&self, point: &mut Vertex3D) {
+        let [x, y, z] = self.matrix.transform([point.x, point.y, point.z]);
+        point.x = x + self.offset[0];
+        point.y = y + self.offset[1];
+        point.z = z + self.offset[2];
+    }
+
+    /// Rotation in degrees around the z axis, 0 points to the top of the playfield
+    fn angle(&self, degrees: &mut f32) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let [x, y, _] = self.matrix.transform([sin, -cos, 0.0]);
+        let rotated = x.atan2(-y).to_degrees();
+        // stay close to the original value to keep angles like 270 or -121 recognizable
+        let reference = if self.mirrored { -*degrees } else { *degrees };
+        *degrees = rotated + ((reference - rotated) / 360.0).round() * 360.0;
+    }
+
+    fn length(&self, length: &mut f32) {
+        *length *= self.scale_2d;
+    }
+
+    fn drag_points(&self, drag_points: &mut [DragPoint], closed: bool) {
+        for point in drag_points.iter_mut() {
+            let [x, y, z] = self.matrix.transform([point.x, point.y, point.z]);
+            point.x = x + self.offset[0];
+            point.y = y + self.offset[1];
+            point.z = z;
+        }
+        if closed && self.mirrored && !drag_points.is_empty() {
+            // the slingshot flag belongs to the segment starting at a point
+            let count = drag_points.len();
+            let slingshots: Vec<Option<bool>> =
+                drag_points.iter().map(|p| p.is_slingshot).collect();
+            drag_points.reverse();
+            for (index, point) in drag_points.iter_mut().enumerate() {
+                point.is_slingshot = slingshots[(2 * count - 2 - index) % count];
+            }
+        }
+    }
+
+    fn apply(&self, item: &mut GameItemEnum) -> io::Result<()> {
+        match item {
+            GameItemEnum::Wall(wall) => self.drag_points(&mut wall.drag_points, true),
+            GameItemEnum::Flipper(flipper) => {
+                self.point_2d(&mut flipper.center);
+                self.angle(&mut flipper.start_angle);
+                self.angle(&mut flipper.end_angle);
+                self.length(&mut flipper.base_radius);
+                self.length(&mut flipper.end_radius);
+                self.length(&mut flipper.flipper_radius_max);
+                self.length(&mut flipper.flipper_radius_min);
+            }
+            GameItemEnum::Timer(timer) => self.point_2d(&mut timer.center),
+            GameItemEnum::Plunger(plunger) => self.point_2d(&mut plunger.center),
+            GameItemEnum::TextBox(_) | GameItemEnum::Reel(_) => {}
+            GameItemEnum::Light(light) if light.is_backglass => {}
+            GameItemEnum::Decal(decal) if decal.backglass => {}
+            GameItemEnum::Bumper(bumper) => {
+                self.point_2d(&mut bumper.center);
+                self.angle(&mut bumper.orientation);
+                self.length(&mut bumper.radius);
+            }
+            GameItemEnum::Trigger(trigger) => {
+                self.point_2d(&mut trigger.center);
+                self.angle(&mut trigger.rotation);
+                self.length(&mut trigger.radius);
+                self.drag_points(&mut trigger.drag_points, true);
+            }
+            GameItemEnum::Light(light) => {
+                self.point_2d(&mut light.center);
+                self.length(&mut light.falloff_radius);
+                self.length(&mut light.mesh_radius);
+                self.drag_points(&mut light.drag_points, true);
+            }
+            GameItemEnum::Kicker(kicker) => {
+                self.point_2d(&mut kicker.center);
+                self.angle(&mut kicker.orientation);
+                self.length(&mut kicker.radius);
+            }
+            GameItemEnum::Decal(decal) => {
+                self.point_2d(&mut decal.center);
+                self.angle(&mut decal.rotation);
+                self.length(&mut decal.width);
+                self.length(&mut decal.height);
+            }
+            GameItemEnum::Gate(gate) => {
+                self.point_2d(&mut gate.center);
+                self.angle(&mut gate.rotation);
+                self.length(&mut gate.length);
+            }
+            GameItemEnum::Spinner(spinner) => {
+                self.point_2d(&mut spinner.center);
+                self.angle(&mut spinner.rotation);
+                self.length(&mut spinner.length);
+            }
+            GameItemEnum::Ramp(ramp) => {
+                // ramps keep running from bottom to top
+                self.drag_points(&mut ramp.drag_points, false);
+                self.length(&mut ramp.width_bottom);
+                self.length(&mut ramp.width_top);
+                self.length(&mut ramp.wire_distance_x);
+                if self.mirrored {
+                    std::mem::swap(&mut ramp.left_wall_height, &mut ramp.right_wall_height);
+                    std::mem::swap(
+                        &mut ramp.left_wall_height_visible,
+                        &mut ramp.right_wall_height_visible,
+                    );
+                    ramp.ramp_type = match ramp.ramp_type {
+                        RampType::ThreeWireLeft => RampType::ThreeWireRight,
+                        RampType::ThreeWireRight => RampType::ThreeWireLeft,
+                        ref other => other.clone(),
+                    };
+                }
+            }
+            GameItemEnum::LightSequencer(sequencer) => {
+                self.point_2d(&mut sequencer.center);
+                self.xy(&mut sequencer.pos_x, &mut sequencer.pos_y);
+            }
+            GameItemEnum::Primitive(primitive) => self.primitive(primitive)?,
+            GameItemEnum::Flasher(flasher) => {
+                self.xy(&mut flasher.pos_x, &mut flasher.pos_y);
+                self.drag_points(&mut flasher.drag_points, true);
+                if self.mirrored {
+                    flasher.rot_z = -flasher.rot_z;
+                }
+            }
+            GameItemEnum::Rubber(rubber) => {
+                self.drag_points(&mut rubber.drag_points, true);
+                rubber.thickness = (rubber.thickness as f32 * self.scale_2d).round() as i32;
+                if self.mirrored {
+                    rubber.rot_z = -rubber.rot_z;
+                }
+            }
+            GameItemEnum::HitTarget(target) => {
+                self.point_3d(&mut target.position);
+                self.angle(&mut target.rot_z);
+                let scale = self.matrix.determinant().abs().cbrt();
+                target.size.x *= scale;
+                target.size.y *= scale;
+                target.size.z *= scale;
+            }
+            GameItemEnum::Generic(_, _) | GameItemEnum::Corrupt(_, _, _) => {}
+        }
+        Ok(())
+    }
+
+    /// Visual Pinball places a vertex `v` of a primitive at
+    /// `position + objrot * rot * (size * v + translation)` where `rot` and `objrot` are
+    /// rotations around z, then y, then x, so `rotation_x * rotation_y * rotation_z`, like
+    /// [`crate::vpx::mesh`] places the vertices.
+    fn primitive(&self, primitive: &mut Primitive) -> io::Result<()> {
+        self.point_3d(&mut primitive.position);
+        let scale = self.matrix.determinant().abs().cbrt();
+        if scale == 0.0 {
+            return Ok(());
+        }
+        let mirrored = self.matrix.determinant() < 0.0;
+        // a mirror in x commutes with the rotations when their y and z angles change sign
+        if mirrored {
+            mirror_mesh(primitive)?;
+        }
+        let rt = &mut primitive.rot_and_tra;
+        let size = &mut primitive.size;
+        if mirrored {
+            rt[3] = -rt[3];
+            for index in [1, 2, 7, 8] {
+                rt[index] = -rt[index];
+            }
+        }
+        size.x *= scale;
+        size.y *= scale;
+        size.z *= scale;
+        for translation in &mut rt[3..6] {
+            *translation *= scale;
+        }
+        // the remaining rotation of the matrix, made orthonormal
+        let mut rotation = Mat3(self.matrix.0.map(|row| row.map(|value| value / scale)));
+        if mirrored {
+            rotation = rotation.mul(&Mat3::mirror_x());
+        }
+        let rotation = orthonormalize(&rotation);
+        let object_rotation = Mat3::rotation_x(rt[6])
+            .mul(&Mat3::rotation_y(rt[7]))
+            .mul(&Mat3::rotation_z(rt[8]));
+        let [x, y, z] = euler_xyz(&rotation.mul(&object_rotation));
+        rt[6] = x;
+        rt[7] = y;
+        rt[8] = z;
+        Ok(())
+    }
+}
+
+/// Mirrors the mesh and the animation frames of a primitive in x, turning the triangles
+/// around so they keep facing outwards.
+///
+/// Primitives without a 3D mesh are generated shapes that are left as they are.
+fn mirror_mesh(primitive: &mut Primitive) -> io::Result<()> {
+    let Some(mut mesh) = Mesh::from_primitive(primitive)? else {
+        return Ok(());
+    };
+    for vertex in &mut mesh.vertices {
+        vertex.position[0] = -vertex.position[0];
+        vertex.normal[0] = -vertex.normal[0];
+    }
+    for triangle in mesh.indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+    mesh.write_to_primitive(primitive)?;
+    if let (Some(frames), Some(lengths)) = (
+        &mut primitive.compressed_animation_vertices_data,
+        &mut primitive.compressed_animation_vertices_len,
+    ) {
+        for (frame, length) in frames.iter_mut().zip(lengths.iter_mut()) {
+            let mut data = decompress_data(frame)?;
+            // frames hold the position and normal of every vertex as 6 floats
+            for vertex in data.chunks_exact_mut(24) {
+                for offset in [0, 12] {
+                    let x = f32::from_le_bytes(vertex[offset..offset + 4].try_into().unwrap());
+                    vertex[offset..offset + 4].copy_from_slice(&(-x).to_le_bytes());
+                }
+            }
+            *frame = compress_data(&data)?;
+            *length = frame.len() as u32;
+        }
+    }
+    Ok(())
+}
+
+fn orthonormalize(matrix: &Mat3) -> Mat3 {
+    let m = &matrix.0;
+    let column = |index: usize| [m[0][index], m[1][index], m[2][index]];
+    let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let normalize = |a: [f32; 3]| {
+        let length = dot(a, a).sqrt();
+        a.map(|value| value / length)
+    };
+    let x = normalize(column(0));
+    let y = column(1);
+    let y = normalize([0, 1, 2].map(|i| y[i] - dot(x, y) * x[i]));
+    let z = [
+        x[1] * y[2] - x[2] * y[1],
+        x[2] * y[0] - x[0] * y[2],
+        x[0] * y[1] - x[1] * y[0],
+    ];
+    Mat3([0, 1, 2].map(|row| [x[row], y[row], z[row]]))
+}
+
+/// Angles in degrees so that `rotation_x(x) * rotation_y(y) * rotation_z(z)` equals the rotation
+fn euler_xyz(rotation: &Mat3) -> [f32; 3] {
+    let r = &rotation.0;
+    let y = r[0][2].clamp(-1.0, 1.0).asin();
+    let (x, z) = if y.cos() > 1e-6 {
+        ((-r[1][2]).atan2(r[2][2]), (-r[0][1]).atan2(r[0][0]))
+    } else {
+        (r[2][1].atan2(r[1][1]), 0.0)
+    };
+    [x.to_degrees(), y.to_degrees(), z.to_degrees()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::collection::Collection;
+    use crate::vpx::gameitem::decal::Decal;
+    use crate::vpx::gameitem::flipper::Flipper;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::mesh::{Mesh, Vertex};
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    fn test_vpx() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "Wall1".to_string(),
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::Light(Light {
+            name: "Light1".to_string(),
+            surface: "wall1".to_string(),
+            ..Default::default()
+        }));
+        vpx.collections.push(Collection {
+            name: "Walls".to_string(),
+            items: vec!["Wall1".to_string()],
+            fire_events: false,
+            stop_single_events: false,
+            group_elements: true,
+        });
+        vpx
+    }
+
+    #[test]
+    fn rename_item_updates_references() -> io::Result<()> {
+        let mut vpx = test_vpx();
+        let result = rename_item(&mut vpx, "Wall1", "Sling")?;
+        assert_eq!(
+            result,
+            RenameResult {
+                collections: vec!["Walls".to_string()],
+                surface_references: vec!["Light1".to_string()],
+            }
+        );
+        assert_eq!(vpx.gameitems[0].name(), "Sling");
+        assert_eq!(vpx.gameitems[1].surface(), Some("Sling"));
+        assert_eq!(vpx.collections[0].items, vec!["Sling".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn rename_item_conflict() {
+        let mut vpx = test_vpx();
+        let result = rename_item(&mut vpx, "Wall1", "LIGHT1");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        let result = rename_item(&mut vpx, "Missing", "Other");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn rename_script() {
+        let script = "Sub Wall1_Hit()\r\n    wall1.IsDropped = True ' Wall1\r\n    x.Wall1 = \"Wall1\" : Wall10.Visible = 1\r\nEnd Sub\r\n";
+        let (updated, count) = replace_identifier(script, "Wall1", "Sling");
+        assert_eq!(
+            updated,
+            "Sub Sling_Hit()\r\n    Sling.IsDropped = True ' Wall1\r\n    x.Wall1 = \"Wall1\" : Wall10.Visible = 1\r\nEnd Sub\r\n"
+        );
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn rename_script_case_folding() {
+        // the long s and the Kelvin sign fold to ASCII letters but take more bytes
+        let script = "\u{17f}ling_Hit : \u{212a}icker.Kick\n";
+        let (updated, count) = replace_identifier(script, "sling", "Wall1");
+        assert_eq!(updated, "Wall1_Hit : \u{212a}icker.Kick\n");
+        assert_eq!(count, 1);
+        let (updated, count) = replace_identifier(script, "Kicker", "Saucer");
+        assert_eq!(updated, "\u{17f}ling_Hit : Saucer.Kick\n");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn transform_flipper_mirrored() {
+        let mut flipper = Flipper::default();
+        flipper.center = Vertex2D { x: 100.0, y: 200.0 };
+        flipper.start_angle = 121.0;
+        flipper.end_angle = 70.0;
+        flipper.base_radius = 20.0;
+        let mut items = vec![GameItemEnum::Flipper(flipper)];
+        let matrix = Mat3::mirror_x().mul(&Mat3::scale(2.0, 2.0, 2.0));
+        transform(&mut items, &matrix, [1000.0, 0.0, 0.0]).unwrap();
+        let GameItemEnum::Flipper(flipper) = &items[0] else {
+            unreachable!()
+        };
+        assert_eq!(flipper.center, Vertex2D { x: 800.0, y: 400.0 });
+        assert!((flipper.start_angle + 121.0).abs() < 1e-3);
+        assert!((flipper.end_angle + 70.0).abs() < 1e-3);
+        assert_eq!(flipper.base_radius, 40.0);
+    }
+
+    #[test]
+    fn transform_primitive_matches_mesh() {
+        let mesh = Mesh {
+            name: "Tetra".to_string(),
+            vertices: [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ]
+            .map(|position| Vertex {
+                position,
+                normal: [0.0, 0.0, 1.0],
+                uv: [0.0, 0.0],
+            })
+            .to_vec(),
+            indices: vec![0, 1, 2, 0, 3, 1, 0, 2, 3, 1, 3, 2],
+        };
+        let mut primitive: Primitive = Faker.fake();
+        primitive.position = Vertex3D::new(300.0, 400.0, 10.0);
+        primitive.size = Vertex3D::new(50.0, 60.0, 70.0);
+        primitive.rot_and_tra = [10.0, 20.0, 30.0, 5.0, 6.0, 7.0, 40.0, 50.0, 60.0];
+        primitive.compressed_animation_vertices_data = None;
+        mesh.write_to_primitive(&mut primitive).unwrap();
+        let before = Mesh::from_primitive_transformed(&primitive)
+            .unwrap()
+            .unwrap();
+        let matrix = Mat3::scale(1.5, 1.5, 1.5)
+            .mul(&Mat3::mirror_x())
+            .mul(&Mat3::rotation_z(35.0));
+        let offset = [20.0, -30.0, 5.0];
+
+        let mut items = vec![GameItemEnum::Primitive(primitive)];
+        transform(&mut items, &matrix, offset).unwrap();
+
+        let GameItemEnum::Primitive(primitive) = &items[0] else {
+            unreachable!()
+        };
+        let after = Mesh::from_primitive_transformed(primitive)
+            .unwrap()
+            .unwrap();
+        for (a, b) in before.vertices.iter().zip(&after.vertices) {
+            let expected = matrix.transform(a.position);
+            for axis in 0..3 {
+                let expected = expected[axis] + offset[axis];
+                assert!(
+                    (expected - b.position[axis]).abs() < 1e-2,
+                    "{:?} != {:?}",
+                    expected,
+                    b.position
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_primitive_mesh() -> io::Result<()> {
+        let mut primitive: Primitive = Faker.fake();
+        primitive.position = Vertex3D::new(300.0, 400.0, 10.0);
+        primitive.size = Vertex3D::new(50.0, 60.0, 70.0);
+        primitive.rot_and_tra = [10.0, 20.0, 30.0, 5.0, 6.0, 7.0, 40.0, 50.0, 60.0];
+        primitive.compressed_animation_vertices_len = None;
+        primitive.compressed_animation_vertices_data = None;
+        primitive.mesh_file_name = Some("screw.obj".to_string());
+        crate::vpx::mesh::external::inline_mesh(&mut primitive, "testdata".as_ref())?;
+        let before = Mesh::from_primitive_transformed(&primitive)?.unwrap();
+
+        let mut items = vec![GameItemEnum::Primitive(primitive)];
+        transform(&mut items, &Mat3::mirror_x(), [1000.0, 0.0, 0.0])?;
+
+        let GameItemEnum::Primitive(primitive) = &items[0] else {
+            unreachable!()
+        };
+        assert_eq!(primitive.size, Vertex3D::new(50.0, 60.0, 70.0));
+        let after = Mesh::from_primitive_transformed(primitive)?.unwrap();
+        for (a, b) in before.vertices.iter().zip(&after.vertices) {
+            let expected = [1000.0 - a.position[0], a.position[1], a.position[2]];
+            let expected_normal = [-a.normal[0], a.normal[1], a.normal[2]];
+            for axis in 0..3 {
+                assert!((expected[axis] - b.position[axis]).abs() < 1e-2);
+                assert!((expected_normal[axis] - b.normal[axis]).abs() < 1e-3);
+            }
+        }
+        // the triangles still face the same way as their normals
+        let consistency = crate::vpx::mesh::fix::normal_consistency(&before)?;
+        assert_eq!(
+            crate::vpx::mesh::fix::normal_consistency(&after)?,
+            consistency
+        );
+        assert!(consistency.agreeing > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn transform_skips_backglass_items() {
+        let light = Light {
+            center: Vertex2D { x: 100.0, y: 200.0 },
+            is_backglass: true,
+            ..Default::default()
+        };
+        let mut decal = Decal::default();
+        decal.center = Vertex2D { x: 100.0, y: 200.0 };
+        decal.backglass = true;
+        let mut items = vec![
+            GameItemEnum::Light(light.clone()),
+            GameItemEnum::Decal(decal.clone()),
+        ];
+        transform(&mut items, &Mat3::mirror_x(), [1000.0, 0.0, 0.0]).unwrap();
+        assert_eq!(
+            items,
+            vec![GameItemEnum::Light(light), GameItemEnum::Decal(decal)]
+        );
+    }
+
+    #[test]
+    fn mirror_table() -> io::Result<()> {
+        let path = std::path::PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+        let original = crate::vpx::read(&path)?;
+        let mut vpx = crate::vpx::read(&path)?;
+        let width = vpx.gamedata.left + vpx.gamedata.right;
+
+        mirror_horizontal(&mut vpx)?;
+
+        for (before, after) in original.gameitems.iter().zip(&vpx.gameitems) {
+            match (before, after) {
+                (GameItemEnum::Flipper(before), GameItemEnum::Flipper(after)) => {
+                    assert_eq!(after.center.x, width - before.center.x);
+                    assert_eq!(after.center.y, before.center.y);
+                    assert!((after.start_angle + before.start_angle).abs() < 1e-3);
+                    assert!((after.end_angle + before.end_angle).abs() < 1e-3);
+                }
+                (GameItemEnum::Plunger(before), GameItemEnum::Plunger(after)) => {
+                    assert_eq!(after.center.x, width - before.center.x);
+                }
+                (GameItemEnum::Wall(before), GameItemEnum::Wall(after)) => {
+                    let area = |points: &[DragPoint]| {
+                        let n = points.len();
+                        (0..n)
+                            .map(|i| {
+                                let (a, b) = (&points[i], &points[(i + 1) % n]);
+                                a.x * b.y - b.x * a.y
+                            })
+                            .sum::<f32>()
+                    };
+                    // the walls still face outwards
+                    assert_eq!(
+                        area(&after.drag_points).signum(),
+                        area(&before.drag_points).signum()
+                    );
+                }
+                _ => {}
+            }
+        }
+        assert!(vpx
+            .gameitems
+            .iter()
+            .any(|item| matches!(item, GameItemEnum::Flipper(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Files holding a single game item, to keep a library of reusable parts
+//!
+//! An element file contains one game item exactly as it is stored in the `GameStg/GameItem*`
+//! streams of a `vpx` file: the item type followed by the BIFF records. Visual Pinball does
+//! not define a file format for single items, so these files are only understood by tools
+//! using this library. Images and materials are not included, [`inject`] uses the ones with
+//! the same name in the target table.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::element::{read_element, write_element};
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let path = std::env::temp_dir().join("LeftFlipper.elt");
+//! let flipper = vpx.gameitems.iter().find(|i| i.name() == "LeftFlipper").unwrap();
+//! write_element(&path, flipper).unwrap();
+//! let read = read_element(&path).unwrap();
+//! assert_eq!(&read, flipper);
+//! ```
+
+use super::gameitem::{self, GameItemEnum};
+use super::import::{self, ConflictStrategy, ImportResult, Selection};
+use super::VPX;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The extension used by [`read_library`]
+pub const ELEMENT_EXTENSION: &str = "elt";
+
+pub fn read_element<P: AsRef<Path>>(path: P) -> io::Result<GameItemEnum> {
+    let data = std::fs::read(&path)?;
+    if data.len() < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not an element file", path.as_ref().display()),
+        ));
+    }
+    match gameitem::read_forgiving(0, &data) {
+        GameItemEnum::Corrupt(_, error, _) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Failed to read element {}: {}",
+                path.as_ref().display(),
+                error
+            ),
+        )),
+        item => Ok(item),
+    }
+}
+
+pub fn write_element<P: AsRef<Path>>(path: P, item: &GameItemEnum) -> io::Result<()> {
+    std::fs::write(path, gameitem::write(item))
+}
+
+/// Reads all element files in a directory, sorted by file name
+pub fn read_library<P: AsRef<Path>>(dir: P) -> io::Result<Vec<GameItemEnum>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(ELEMENT_EXTENSION))
+    });
+    paths.sort();
+    paths.iter().map(read_element).collect()
+}
+
+/// Adds elements to a table, name conflicts are handled like [`import::items`] does
+pub fn inject(
+    into: &mut VPX,
+    elements: Vec<GameItemEnum>,
+    on_conflict: ConflictStrategy,
+) -> io::Result<ImportResult> {
+    let mut from = VPX::default();
+    let mut selection = Selection {
+        on_conflict,
+        ..Default::default()
+    };
+    for element in elements {
+        selection.items.push(element.name().to_string());
+        from.add_game_item(element);
+    }
+    import::items(&from, into, &selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::flipper::Flipper;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::import::ImportAction;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn library_round_trip_and_inject() -> io::Result<()> {
+        let dir = testdir!();
+        let mut flipper = Flipper::default();
+        flipper.name = "StandardFlipper".to_string();
+        let flipper = GameItemEnum::Flipper(flipper);
+        let guide = GameItemEnum::Wall(Wall {
+            name: "LaneGuide".to_string(),
+            ..Default::default()
+        });
+        write_element(dir.join("b_flipper.elt"), &flipper)?;
+        write_element(dir.join("a_guide.ELT"), &guide)?;
+        std::fs::write(dir.join("notes.txt"), "not an element")?;
+        std::fs::write(dir.join("broken.elt"), [1, 2])?;
+        assert!(read_library(&dir).is_err());
+        std::fs::remove_file(dir.join("broken.elt"))?;
+
+        let library = read_library(&dir)?;
+        assert_eq!(library, vec![guide, flipper]);
+
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "LaneGuide".to_string(),
+            ..Default::default()
+        }));
+        let result = inject(&mut vpx, library, ConflictStrategy::Rename)?;
+        assert_eq!(
+            result
+                .items
+                .iter()
+                .map(|i| i.action.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                ImportAction::Renamed("LaneGuide_2".to_string()),
+                ImportAction::Added
+            ]
+        );
+        assert_eq!(vpx.gameitems.len(), 3);
+        Ok(())
+    }
+}
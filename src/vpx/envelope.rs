@@ -0,0 +1,199 @@
+//! The range of motion and the physics of the moving parts of a table
+//!
+//! Gates, spinners and flippers swing between two angles, how they move depends on their
+//! damping, elasticity and for flippers the strength and return strength. [`motion_envelopes`]
+//! collects these so tables can be compared without reading the script or the `vpx` format,
+//! [`write_motion_envelopes`] writes them as json, eg next to an exported mesh.
+//!
+//! Angles are in degrees like in the editor. Flippers that override the table physics with a
+//! physics set use the values of that set in Visual Pinball, the values here are the ones
+//! stored on the flipper.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::envelope::motion_envelopes;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for flipper in motion_envelopes(&vpx).flippers {
+//!     println!("{} swings {} degrees", flipper.name, flipper.swing);
+//! }
+//! ```
+
+use super::gameitem::flipper::Flipper;
+use super::gameitem::gate::Gate;
+use super::gameitem::spinner::Spinner;
+use super::gameitem::GameItemEnum;
+use super::VPX;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GateEnvelope {
+    pub name: String,
+    pub angle_min: f32,
+    pub angle_max: f32,
+    /// Swings both ways, otherwise the gate only opens towards `angle_max`
+    pub two_way: bool,
+    pub elasticity: f32,
+    pub friction: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub damping: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gravity_factor: Option<f32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SpinnerEnvelope {
+    pub name: String,
+    /// Equal to `angle_max` for a spinner that turns freely
+    pub angle_min: f32,
+    pub angle_max: f32,
+    pub elasticity: f32,
+    pub damping: f32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct FlipperEnvelope {
+    pub name: String,
+    pub start_angle: f32,
+    pub end_angle: f32,
+    /// `end_angle - start_angle`, negative for a flipper that swings counterclockwise
+    pub swing: f32,
+    pub strength: f32,
+    /// The strength pulling the flipper back down, relative to `strength`
+    pub return_strength: f32,
+    pub mass: f32,
+    pub elasticity: f32,
+    pub elasticity_falloff: f32,
+    pub friction: f32,
+    pub ramp_up: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub torque_damping: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub torque_damping_angle: Option<f32>,
+}
+
+/// The envelopes of all gates, spinners and flippers in table order
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct MotionEnvelopes {
+    pub gates: Vec<GateEnvelope>,
+    pub spinners: Vec<SpinnerEnvelope>,
+    pub flippers: Vec<FlipperEnvelope>,
+}
+
+impl From<&Gate> for GateEnvelope {
+    fn from(gate: &Gate) -> Self {
+        GateEnvelope {
+            name: gate.name.clone(),
+            angle_min: gate.angle_min,
+            angle_max: gate.angle_max,
+            two_way: gate.two_way,
+            elasticity: gate.elasticity,
+            friction: gate.friction,
+            damping: gate.damping,
+            gravity_factor: gate.gravity_factor,
+        }
+    }
+}
+
+impl From<&Spinner> for SpinnerEnvelope {
+    fn from(spinner: &Spinner) -> Self {
+        SpinnerEnvelope {
+            name: spinner.name.clone(),
+            angle_min: spinner.angle_min,
+            angle_max: spinner.angle_max,
+            elasticity: spinner.elasticity,
+            damping: spinner.damping,
+        }
+    }
+}
+
+impl From<&Flipper> for FlipperEnvelope {
+    fn from(flipper: &Flipper) -> Self {
+        FlipperEnvelope {
+            name: flipper.name.clone(),
+            start_angle: flipper.start_angle,
+            end_angle: flipper.end_angle,
+            swing: flipper.end_angle - flipper.start_angle,
+            strength: flipper.strength,
+            return_strength: flipper.return_,
+            mass: flipper.mass,
+            elasticity: flipper.elasticity,
+            elasticity_falloff: flipper.elasticity_falloff,
+            friction: flipper.friction,
+            ramp_up: flipper.ramp_up,
+            torque_damping: flipper.torque_damping,
+            torque_damping_angle: flipper.torque_damping_angle,
+        }
+    }
+}
+
+pub fn motion_envelopes(vpx: &VPX) -> MotionEnvelopes {
+    let mut envelopes = MotionEnvelopes::default();
+    for item in &vpx.gameitems {
+        match item {
+            GameItemEnum::Gate(gate) => envelopes.gates.push(gate.into()),
+            GameItemEnum::Spinner(spinner) => envelopes.spinners.push(spinner.into()),
+            GameItemEnum::Flipper(flipper) => envelopes.flippers.push(flipper.into()),
+            _ => {}
+        }
+    }
+    envelopes
+}
+
+/// Writes the [`motion_envelopes`] of a table as pretty printed json
+pub fn write_motion_envelopes<P: AsRef<Path>>(vpx: &VPX, path: P) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &motion_envelopes(vpx))?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn envelopes_json() -> io::Result<()> {
+        let mut vpx = VPX::default();
+        let mut flipper = Flipper::default();
+        flipper.name = "LeftFlipper".to_string();
+        flipper.start_angle = 121.0;
+        flipper.end_angle = 70.0;
+        flipper.strength = 2200.0;
+        flipper.return_ = 0.058;
+        vpx.add_game_item(GameItemEnum::Flipper(flipper));
+        vpx.add_game_item(GameItemEnum::Gate(Gate {
+            name: "Gate1".to_string(),
+            angle_min: 0.0,
+            angle_max: 90.0,
+            damping: Some(0.985),
+            gravity_factor: None,
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::Spinner(Spinner::default()));
+
+        let envelopes = motion_envelopes(&vpx);
+        assert_eq!(envelopes.flippers.len(), 1);
+        assert_eq!(envelopes.flippers[0].swing, -51.0);
+        assert_eq!(envelopes.flippers[0].return_strength, 0.058);
+        assert_eq!(envelopes.gates[0].damping, Some(0.985));
+        assert_eq!(envelopes.spinners.len(), 1);
+
+        let path = testdir!().join("envelopes.json");
+        write_motion_envelopes(&vpx, &path)?;
+        let json: serde_json::Value = serde_json::from_reader(File::open(&path)?)?;
+        assert_eq!(json["flippers"][0]["name"], "LeftFlipper");
+        assert_eq!(json["gates"][0]["angle_max"], 90.0);
+        assert!(json["gates"][0].get("gravity_factor").is_none());
+        let read: MotionEnvelopes = serde_json::from_value(json)?;
+        assert_eq!(read, envelopes);
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+//! Links the game items to the script subs that handle their events
+//!
+//! Visual Pinball calls a sub named after the object and the event, like `Trigger1_Hit` or
+//! `Table1_KeyDown`. Items in a collection with events enabled also fire the collection
+//! events, like `Targets_Hit(index)`. The script is scanned line by line, so subs attached
+//! in other ways, eg with `GetRef`, are not found. This is a best effort.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::events::script_events;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let events = script_events(&vpx);
+//! for handler in &events.handlers {
+//!     println!("line {}: {} handles {}", handler.line, handler.sub, handler.event);
+//! }
+//! for issue in &events.issues {
+//!     println!("{}", issue);
+//! }
+//! ```
+
+use super::dependencies::strip_comment;
+use super::gameitem::GameItemEnum;
+use super::VPX;
+use regex::Regex;
+use std::fmt;
+
+/// Events fired by Visual Pinball objects, sub names ending in anything else are not handlers
+const EVENTS: [&str; 19] = [
+    "Animate",
+    "Collide",
+    "Dropped",
+    "Exit",
+    "Hit",
+    "Init",
+    "KeyDown",
+    "KeyUp",
+    "LimitBOS",
+    "LimitEOS",
+    "MusicDone",
+    "OptionEvent",
+    "Paused",
+    "Raised",
+    "Slingshot",
+    "Spin",
+    "Timer",
+    "Unhit",
+    "UnPaused",
+];
+
+/// What the object part of a handler name refers to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EventTarget {
+    GameItem,
+    Collection,
+    Table,
+    /// Nothing in the table has this name
+    Unknown,
+}
+
+/// A sub that handles an event
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EventHandler {
+    pub sub: String,
+    /// The object name as written in the script
+    pub object: String,
+    pub event: String,
+    pub target: EventTarget,
+    pub line: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EventIssue {
+    /// A trigger, kicker or enabled timer without a handler, also not through a collection
+    MissingHandler { item: String, event: String },
+    /// A handler for an object that is not in the table
+    OrphanHandler { sub: String, line: usize },
+}
+
+impl fmt::Display for EventIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventIssue::MissingHandler { item, event } => {
+                write!(f, "{} has no {}_{} handler", item, item, event)
+            }
+            EventIssue::OrphanHandler { sub, line } => write!(
+                f,
+                "Script line {} defines {} but there is no such object",
+                line, sub
+            ),
+        }
+    }
+}
+
+/// The result of [`script_events`]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct EventReport {
+    /// All handlers in script order
+    pub handlers: Vec<EventHandler>,
+    pub issues: Vec<EventIssue>,
+}
+
+impl EventReport {
+    /// The handlers of an item or collection, names are compared ignoring case like
+    /// VBScript does.
+    ///
+    /// Handlers of the collections the item is part of are not included.
+    pub fn handlers_for(&self, name: &str) -> Vec<&EventHandler> {
+        self.handlers
+            .iter()
+            .filter(|handler| handler.object.eq_ignore_ascii_case(name))
+            .collect()
+    }
+}
+
+/// Finds the event handlers in the script and checks them against the game items.
+pub fn script_events(vpx: &VPX) -> EventReport {
+    let handlers = find_handlers(&vpx.gamedata.code.string, |object| {
+        if object.eq_ignore_ascii_case(&vpx.gamedata.name) {
+            EventTarget::Table
+        } else if vpx
+            .gameitems
+            .iter()
+            .any(|item| item.name().eq_ignore_ascii_case(object))
+        {
+            EventTarget::GameItem
+        } else if vpx
+            .collections
+            .iter()
+            .any(|collection| collection.name.eq_ignore_ascii_case(object))
+        {
+            EventTarget::Collection
+        } else {
+            EventTarget::Unknown
+        }
+    });
+
+    let handled = |name: &str, events: &[&str]| {
+        let collections = vpx
+            .collections
+            .iter()
+            .filter(|c| c.fire_events && c.items.iter().any(|i| i.eq_ignore_ascii_case(name)))
+            .map(|c| c.name.as_str());
+        let mut objects = std::iter::once(name).chain(collections);
+        objects.any(|object| {
+            handlers.iter().any(|handler| {
+                handler.object.eq_ignore_ascii_case(object)
+                    && events.iter().any(|e| handler.event.eq_ignore_ascii_case(e))
+            })
+        })
+    };
+
+    let mut issues = Vec::new();
+    for item in &vpx.gameitems {
+        let (event, events): (&str, &[&str]) = match item {
+            GameItemEnum::Trigger(_) | GameItemEnum::Kicker(_) => ("Hit", &["Hit", "Unhit"]),
+            GameItemEnum::Timer(timer) if timer.is_timer_enabled => ("Timer", &["Timer"]),
+            _ => continue,
+        };
+        if !handled(item.name(), events) {
+            issues.push(EventIssue::MissingHandler {
+                item: item.name().to_string(),
+                event: event.to_string(),
+            });
+        }
+    }
+    issues.extend(
+        handlers
+            .iter()
+            .filter(|handler| handler.target == EventTarget::Unknown)
+            .map(|handler| EventIssue::OrphanHandler {
+                sub: handler.sub.clone(),
+                line: handler.line,
+            }),
+    );
+    EventReport { handlers, issues }
+}
+
+fn find_handlers<F: Fn(&str) -> EventTarget>(script: &str, target: F) -> Vec<EventHandler> {
+    let sub = Regex::new(r"(?i)^\s*(?:(?:public|private)\s+)?sub\s+([a-z][a-z0-9_]*)").unwrap();
+    let mut handlers = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let Some(c) = sub.captures(strip_comment(line)) else {
+            continue;
+        };
+        let name = &c[1];
+        // item names can contain underscores, the event is the last part
+        let Some((object, event)) = name.rsplit_once('_') else {
+            continue;
+        };
+        let Some(event) = EVENTS.iter().find(|e| e.eq_ignore_ascii_case(event)) else {
+            continue;
+        };
+        if object.is_empty() {
+            continue;
+        }
+        handlers.push(EventHandler {
+            sub: name.to_string(),
+            object: object.to_string(),
+            event: event.to_string(),
+            target: target(object),
+            line: index + 1,
+        });
+    }
+    handlers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::collection::Collection;
+    use crate::vpx::gameitem::kicker::Kicker;
+    use crate::vpx::gameitem::timer::Timer;
+    use crate::vpx::gameitem::trigger::Trigger;
+    use pretty_assertions::assert_eq;
+
+    fn table() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.gamedata.name = "Table1".to_string();
+        for name in ["sw_left", "sw_right", "sw_top"] {
+            vpx.add_game_item(GameItemEnum::Trigger(Trigger {
+                name: name.to_string(),
+                ..Default::default()
+            }));
+        }
+        let mut kicker = Kicker::default();
+        kicker.name = "Drain".to_string();
+        vpx.add_game_item(GameItemEnum::Kicker(kicker));
+        vpx.add_game_item(GameItemEnum::Timer(Timer {
+            name: "GameTimer".to_string(),
+            is_timer_enabled: true,
+            ..Default::default()
+        }));
+        vpx.collections.push(Collection {
+            name: "Lanes".to_string(),
+            items: vec!["sw_top".to_string()],
+            fire_events: true,
+            stop_single_events: false,
+            group_elements: true,
+        });
+        vpx.set_script(
+            r#"Option Explicit
+Sub Table1_KeyDown(ByVal keycode)
+End Sub
+Sub sw_left_Hit : Controller.Switch(1) = 1 : End Sub
+Private Sub SW_RIGHT_unhit()
+End Sub
+Sub Lanes_Hit(idx)
+End Sub
+' Sub Drain_Hit()
+Sub OldKicker_Hit()
+End Sub
+Sub UpdateLights()
+End Sub
+"#
+            .to_string(),
+        );
+        vpx
+    }
+
+    #[test]
+    fn cross_reference() {
+        let report = script_events(&table());
+
+        let handlers: Vec<(&str, &str, EventTarget, usize)> = report
+            .handlers
+            .iter()
+            .map(|h| (h.object.as_str(), h.event.as_str(), h.target, h.line))
+            .collect();
+        assert_eq!(
+            handlers,
+            vec![
+                ("Table1", "KeyDown", EventTarget::Table, 2),
+                ("sw_left", "Hit", EventTarget::GameItem, 4),
+                ("SW_RIGHT", "Unhit", EventTarget::GameItem, 5),
+                ("Lanes", "Hit", EventTarget::Collection, 7),
+                ("OldKicker", "Hit", EventTarget::Unknown, 10),
+            ]
+        );
+        assert_eq!(report.handlers_for("sw_right")[0].sub, "SW_RIGHT_unhit");
+        assert_eq!(
+            report.issues,
+            vec![
+                EventIssue::MissingHandler {
+                    item: "Drain".to_string(),
+                    event: "Hit".to_string(),
+                },
+                EventIssue::MissingHandler {
+                    item: "GameTimer".to_string(),
+                    event: "Timer".to_string(),
+                },
+                EventIssue::OrphanHandler {
+                    sub: "OldKicker_Hit".to_string(),
+                    line: 10,
+                },
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use byteorder::{LittleEndian, ReadBytesExt};
 use bytes::{Buf, BufMut, BytesMut};
 use std::collections::HashSet;
 use std::error::Error;
@@ -5,21 +6,28 @@ use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
 use std::io::{self, Read, Write};
 use std::iter::Zip;
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
 use std::slice::Iter;
 use std::{fs::File, path::Path};
 
 use cfb::CompoundFile;
 use flate2::read::ZlibDecoder;
 use image::DynamicImage;
-use serde::de;
+use serde::{de, Serialize};
 use serde_json::Value;
 
-use super::{gameitem, read_gamedata, Version, VPX};
+use super::{gameitem, read_gamedata, ExtraStream, Fnv64, Version, VPX};
 
+use super::assetmeta::{
+    read_asset_metadata, write_asset_metadata, AssetMetadataIndex, ASSET_METADATA_STREAM,
+};
 use super::collection::Collection;
 use super::font;
 use super::gamedata::{GameData, GameDataJson};
+use super::mesh::weld;
+#[cfg(feature = "profiling")]
+use super::profile::Profile;
+use super::profile::Profiler;
 use super::sound;
 use super::sound::{read_sound, write_sound, SoundData, SoundDataJson};
 use super::version;
@@ -36,8 +44,9 @@ use crate::vpx::material::{
     Material, MaterialJson, SaveMaterial, SaveMaterialJson, SavePhysicsMaterial,
     SavePhysicsMaterialJson,
 };
+use crate::vpx::math::canonical_f64;
 use crate::vpx::model::Vertex3dNoTex2;
-use crate::vpx::obj::{read_obj_file, write_obj, ObjData};
+use crate::vpx::obj::{read_obj_file, write_obj, ObjData, ObjNormal};
 use crate::vpx::renderprobe::{RenderProbeJson, RenderProbeWithGarbage};
 use crate::vpx::tableinfo::TableInfo;
 
@@ -79,84 +88,493 @@ impl From<serde_json::Error> for WriteError {
     }
 }
 
+/// Options for [`write_with_options`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct WriteOptions {
+    /// Name image and sound files after a hash of their contents instead of the item name.
+    ///
+    /// The index files `images.json` and `sounds.json` map the names to the files. Renaming an
+    /// image or sound then only changes the index, which keeps diffs small when the expanded
+    /// directory is stored in git. Items with the same contents share a file.
+    pub content_addressed_files: bool,
+    /// Formatting of the game item files and the `gameitems.json` index
+    pub gameitems_json: JsonFormat,
+    /// Put the game item files in a subdirectory per item type, eg `gameitems/walls/`.
+    ///
+    /// The file names in `gameitems.json` include the subdirectory, so reading does not depend
+    /// on this option.
+    pub gameitems_per_type_dirs: bool,
+    /// Allow paths longer than the 260 characters Windows supports by default.
+    ///
+    /// Without this, file names made from item names are shortened so the paths within the
+    /// expanded directory are at most 160 characters, which leaves 100 characters for the
+    /// location of the expanded directory itself. The limit does not depend on where the
+    /// directory is written, so the same table always gives the same file names. Enable it
+    /// when long path support is turned on in Windows and git, or when the directory is never
+    /// used on Windows.
+    pub long_paths: bool,
+    /// How characters outside ASCII in item names end up in file names
+    pub file_name_encoding: FileNameEncoding,
+}
+
+/// How item names with characters outside ASCII, like accents or CJK, are turned into file
+/// names.
+///
+/// The original names are stored in the json files, so reading gives the same names
+/// for every option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum FileNameEncoding {
+    /// Keep the characters, file names look like the item names
+    #[default]
+    Unicode,
+    /// Replace the characters with an ASCII approximation, eg `Café` becomes `Cafe`
+    Transliterate,
+    /// Replace the UTF-8 bytes with `%XX`, eg `Café` becomes `Caf%C3%A9`
+    PercentEncode,
+}
+
+impl FileNameEncoding {
+    fn encode(&self, name: &str) -> String {
+        match self {
+            FileNameEncoding::Unicode => name.to_string(),
+            FileNameEncoding::Transliterate => deunicode::deunicode_with_tofu(name, "_"),
+            FileNameEncoding::PercentEncode => {
+                let mut encoded = String::with_capacity(name.len());
+                for c in name.chars() {
+                    if c.is_ascii() && c != '%' {
+                        encoded.push(c);
+                    } else {
+                        let mut buf = [0; 4];
+                        for byte in c.encode_utf8(&mut buf).bytes() {
+                            encoded.push_str(&format!("%{:02X}", byte));
+                        }
+                    }
+                }
+                encoded
+            }
+        }
+    }
+}
+
+/// How JSON files are written
+///
+/// With the default options the model is serialized as is. Setting `sort_keys` or
+/// `float_precision` first converts the model to a json value and writes every float as the
+/// `f64` with the shortest decimal representation that reads back to the same `f32`, see
+/// [`canonical_f64`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct JsonFormat {
+    /// One value per line with indentation, otherwise without any whitespace
+    pub pretty: bool,
+    /// Sort object keys alphabetically instead of keeping the field order of the model
+    pub sort_keys: bool,
+    /// Round floats to this number of decimals, this loses precision so reading the files
+    /// back will not give the exact same table.
+    pub float_precision: Option<usize>,
+}
+
+impl Default for JsonFormat {
+    fn default() -> Self {
+        JsonFormat {
+            pretty: true,
+            sort_keys: false,
+            float_precision: None,
+        }
+    }
+}
+
 pub fn write<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
-    // write the version as utf8 to version.txt
-    let version_path = expanded_dir.as_ref().join("version.txt");
-    let mut version_file = File::create(version_path)?;
-    let version_string = vpx.version.to_u32_string();
-    version_file.write_all(version_string.as_bytes())?;
-
-    // write the screenshot as a png
-    if let Some(screenshot) = &vpx.info.screenshot {
-        let screenshot_path = expanded_dir.as_ref().join("screenshot.png");
-        let mut screenshot_file = File::create(screenshot_path)?;
-        screenshot_file.write_all(screenshot)?;
-    }
-
-    // write table metadata as json
-    write_info(&vpx, expanded_dir)?;
-
-    // collections
-    let collections_json_path = expanded_dir.as_ref().join("collections.json");
-    let mut collections_json_file = File::create(collections_json_path)?;
-    let json_collections = collections_json(&vpx.collections);
-    serde_json::to_writer_pretty(&mut collections_json_file, &json_collections)?;
-    write_gameitems(vpx, expanded_dir)?;
-    write_images(vpx, expanded_dir)?;
-    write_sounds(vpx, expanded_dir)?;
-    write_fonts(vpx, expanded_dir)?;
-    write_game_data(vpx, expanded_dir)?;
-    if vpx.gamedata.materials.is_some() {
-        write_materials(vpx, expanded_dir)?;
-    } else {
-        write_old_materials(vpx, expanded_dir)?;
-        write_old_materials_physics(vpx, expanded_dir)?;
+    write_with_options(vpx, expanded_dir, &WriteOptions::default())
+}
+
+pub fn write_with_options<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    write_with_profiler(vpx, expanded_dir, options, &mut Profiler::default())
+}
+
+/// Like [`write_with_options`] but returns how long writing every part of the table took
+#[cfg(feature = "profiling")]
+pub fn write_profiled<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<Profile, WriteError> {
+    let mut profiler = Profiler::default();
+    write_with_profiler(vpx, expanded_dir, options, &mut profiler)?;
+    Ok(profiler.finish())
+}
+
+fn write_with_profiler<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+    profiler: &mut Profiler,
+) -> Result<(), WriteError> {
+    profiler.time("info", || -> Result<(), WriteError> {
+        // write the version as utf8 to version.txt
+        let version_path = expanded_dir.as_ref().join("version.txt");
+        let mut version_file = File::create(version_path)?;
+        let version_string = vpx.version.to_u32_string();
+        version_file.write_all(version_string.as_bytes())?;
+
+        // write the screenshot as a png
+        if let Some(screenshot) = &vpx.info.screenshot {
+            let screenshot_path = expanded_dir.as_ref().join("screenshot.png");
+            let mut screenshot_file = File::create(screenshot_path)?;
+            screenshot_file.write_all(screenshot)?;
+        }
+
+        // write table metadata as json
+        write_info(&vpx, expanded_dir)?;
+        Ok(())
+    })?;
+
+    profiler.time("collections", || -> Result<(), WriteError> {
+        let collections_json_path = expanded_dir.as_ref().join("collections.json");
+        let mut collections_json_file = File::create(collections_json_path)?;
+        let json_collections = collections_json(&vpx.collections);
+        serde_json::to_writer_pretty(&mut collections_json_file, &json_collections)?;
+        Ok(())
+    })?;
+    profiler.time("gameitems", || write_gameitems(vpx, expanded_dir, options))?;
+    profiler.time("images", || write_images(vpx, expanded_dir, options))?;
+    profiler.time("sounds", || write_sounds(vpx, expanded_dir, options))?;
+    profiler.time("fonts", || write_fonts(vpx, expanded_dir, options))?;
+    profiler.time("extra_streams", || write_extra_streams(vpx, expanded_dir))?;
+    profiler.time("gamedata", || -> Result<(), WriteError> {
+        write_game_data(vpx, expanded_dir)?;
+        if vpx.gamedata.materials.is_some() {
+            write_materials(vpx, expanded_dir)?;
+        } else {
+            write_old_materials(vpx, expanded_dir)?;
+            write_old_materials_physics(vpx, expanded_dir)?;
+        }
+        write_renderprobes(vpx, expanded_dir)?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Options for [`read_with_options`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ReadOptions {
+    /// Merge duplicate vertices of primitive obj files and re-index them.
+    ///
+    /// Positions, normals and texture coordinates are compared by rounding them to a multiple
+    /// of this value, `Some(0.0)` only merges exact duplicates. Meshes exported from Blender
+    /// often split vertices that could be shared. Primitives with animation frames are never
+    /// welded since the frames have to keep the vertex order.
+    pub weld_tolerance: Option<f32>,
+    /// Fail when the dimensions of an image can not be read from its file.
+    ///
+    /// By default this is only reported in [`MigrationReport::image_probe_failures`], the
+    /// dimensions from `images.json` are used, or 0 when they are not there. Visual Pinball
+    /// reads the dimensions from the image data when loading the table.
+    pub strict_image_probing: bool,
+}
+
+/// A file in the layout written by older vpxtool versions that was read in place of
+/// the file the current layout expects.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LegacyFile {
+    /// Path of the legacy file, relative to the expanded dir
+    pub found: PathBuf,
+    /// Path the current layout uses, relative to the expanded dir
+    pub expected: PathBuf,
+}
+
+/// Lists the legacy files that were picked up while reading an expanded dir.
+///
+/// Writing the table again with [`write`] produces the current layout, after which the
+/// legacy files can be removed.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MigrationReport {
+    pub legacy_files: Vec<LegacyFile>,
+    /// Images whose dimensions could not be read from their file, see
+    /// [`ReadOptions::strict_image_probing`]
+    pub image_probe_failures: Vec<ImageProbeFailure>,
+}
+
+/// An image file the `image` crate could not read the dimensions of, like some HDR or BMP
+/// variants
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImageProbeFailure {
+    /// Path of the image file, relative to the expanded dir
+    pub file: PathBuf,
+    pub error: String,
+}
+
+impl Display for ImageProbeFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to read image dimensions for {}: {}",
+            self.file.display(),
+            self.error
+        )
+    }
+}
+
+impl MigrationReport {
+    /// No legacy files were read, image probe failures are not taken into account
+    pub fn is_empty(&self) -> bool {
+        self.legacy_files.is_empty()
+    }
+
+    /// Returns the current path if it exists, otherwise the first existing legacy path,
+    /// which is recorded in the report. Falls back to the current path when none exist.
+    fn resolve(&mut self, expanded_dir: &Path, current: &Path, legacy: &[PathBuf]) -> PathBuf {
+        let current_path = expanded_dir.join(current);
+        if current_path.exists() {
+            return current_path;
+        }
+        match legacy.iter().find(|l| expanded_dir.join(l).exists()) {
+            Some(found) => {
+                self.legacy_files.push(LegacyFile {
+                    found: found.clone(),
+                    expected: current.to_path_buf(),
+                });
+                expanded_dir.join(found)
+            }
+            None => current_path,
+        }
+    }
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for legacy_file in &self.legacy_files {
+            writeln!(
+                f,
+                "{} -> {}",
+                legacy_file.found.display(),
+                legacy_file.expected.display()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The files of an expanded dir that are referenced but missing, and the files in the asset
+/// dirs nothing refers to, all relative to the expanded dir.
+///
+/// Reading fails with this as the error when files are missing, use [`file_report`] to get
+/// it back from the [`io::Error`]. Unexpected files are only listed, they are often left
+/// behind when an asset is renamed by hand.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FileReport {
+    pub missing: Vec<PathBuf>,
+    pub unexpected: Vec<PathBuf>,
+}
+
+impl Display for FileReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} missing files:", self.missing.len())?;
+        for file in &self.missing {
+            writeln!(f, "  {}", file.display())?;
+        }
+        if !self.unexpected.is_empty() {
+            writeln!(f, "{} unexpected files:", self.unexpected.len())?;
+            for file in &self.unexpected {
+                writeln!(f, "  {}", file.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for FileReport {}
+
+/// The [`FileReport`] reading an expanded dir failed with, if any
+pub fn file_report(error: &io::Error) -> Option<&FileReport> {
+    error.get_ref()?.downcast_ref()
+}
+
+/// The asset dirs that are checked for unexpected files
+const ASSET_DIRS: [&str; 5] = ["images", "sounds", "fonts", "gameitems", "extra_streams"];
+
+/// Keeps track of the asset files while reading, so all missing files are reported at once
+#[derive(Debug, Default)]
+struct FileCheck {
+    expected: HashSet<PathBuf>,
+    missing: Vec<PathBuf>,
+}
+
+impl FileCheck {
+    /// The full path of a file that is expected to exist, `None` if it is missing
+    fn expect(&mut self, expanded_dir: &Path, relative: PathBuf) -> Option<PathBuf> {
+        let path = expanded_dir.join(&relative);
+        if path.is_file() {
+            self.expected.insert(relative);
+            Some(path)
+        } else {
+            self.missing.push(relative);
+            None
+        }
+    }
+
+    /// Marks a file that may exist as known
+    fn allow(&mut self, expanded_dir: &Path, path: &Path) {
+        if let Ok(relative) = path.strip_prefix(expanded_dir) {
+            self.expected.insert(relative.to_path_buf());
+        }
+    }
+
+    fn finish(self, expanded_dir: &Path) -> io::Result<()> {
+        if self.missing.is_empty() {
+            return Ok(());
+        }
+        let mut unexpected = Vec::new();
+        for dir in ASSET_DIRS {
+            list_files(expanded_dir, Path::new(dir), &mut unexpected)?;
+        }
+        unexpected.retain(|file| !self.expected.contains(file));
+        unexpected.sort();
+        let report = FileReport {
+            missing: self.missing,
+            unexpected,
+        };
+        Err(io::Error::new(io::ErrorKind::NotFound, report))
+    }
+}
+
+/// Adds all files under a dir relative to the expanded dir, recursively
+fn list_files(expanded_dir: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let full_dir = expanded_dir.join(dir);
+    if !full_dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(full_dir)? {
+        let entry = entry?;
+        let relative = dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            list_files(expanded_dir, &relative, files)?;
+        } else {
+            files.push(relative);
+        }
     }
-    write_renderprobes(vpx, expanded_dir)?;
     Ok(())
 }
 
 pub fn read<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<VPX> {
-    // read the version
-    let version_path = expanded_dir.as_ref().join("version.txt");
-    if !version_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Version file not found: {}", version_path.display()),
-        ));
+    read_with_options(expanded_dir, &ReadOptions::default())
+}
+
+pub fn read_with_options<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+) -> io::Result<VPX> {
+    let (vpx, report) = read_with_report(expanded_dir, options)?;
+    for failure in &report.image_probe_failures {
+        eprintln!("{}", failure);
     }
-    let mut version_file = File::open(&version_path)?;
-    let mut version_string = String::new();
-    version_file.read_to_string(&mut version_string)?;
-    let version = Version::parse(&version_string).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            format!("Could not parse version {}: {}", &version_string, e),
-        )
-    })?;
+    if !report.is_empty() {
+        eprintln!(
+            "Read legacy vpxtool layout from {}, write the table again to migrate:\n{}",
+            expanded_dir.as_ref().display(),
+            report
+        );
+    }
+    Ok(vpx)
+}
 
-    let screenshot = expanded_dir.as_ref().join("screenshot.png");
-    let screenshot = if screenshot.exists() {
-        let mut screenshot_file = File::open(&screenshot)?;
-        let mut screenshot = Vec::new();
-        screenshot_file.read_to_end(&mut screenshot)?;
-        Some(screenshot)
-    } else {
-        None
-    };
+/// Like [`read_with_options`] but also returns which files of the layout written by older
+/// vpxtool versions were read.
+///
+/// Older versions wrote the raw `GameStg/Version` stream instead of `version.txt`.
+pub fn read_with_report<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+) -> io::Result<(VPX, MigrationReport)> {
+    read_with_profiler(expanded_dir, options, &mut Profiler::default())
+}
 
-    let (info, custominfotags) = read_info(expanded_dir, screenshot)?;
-    let collections = read_collections(expanded_dir)?;
-    let gameitems = read_gameitems(expanded_dir)?;
-    let images = read_images(expanded_dir)?;
-    let sounds = read_sounds(expanded_dir)?;
-    let fonts = read_fonts(expanded_dir)?;
-    let mut gamedata = read_game_data(expanded_dir)?;
+/// Like [`read_with_options`] but also returns how long reading every part of the table took
+#[cfg(feature = "profiling")]
+pub fn read_profiled<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+) -> io::Result<(VPX, Profile)> {
+    let mut profiler = Profiler::default();
+    let (vpx, _) = read_with_profiler(expanded_dir, options, &mut profiler)?;
+    Ok((vpx, profiler.finish()))
+}
+
+fn read_with_profiler<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+    profiler: &mut Profiler,
+) -> io::Result<(VPX, MigrationReport)> {
+    let mut report = MigrationReport::default();
+    let (version, info, custominfotags) = profiler.time("info", || -> io::Result<_> {
+        let version = read_version(expanded_dir, &mut report)?;
+
+        let screenshot = expanded_dir.as_ref().join("screenshot.png");
+        let screenshot = if screenshot.exists() {
+            let mut screenshot_file = File::open(&screenshot)?;
+            let mut screenshot = Vec::new();
+            screenshot_file.read_to_end(&mut screenshot)?;
+            Some(screenshot)
+        } else {
+            None
+        };
+
+        let (info, custominfotags) = read_info(expanded_dir, screenshot)?;
+        Ok((version, info, custominfotags))
+    })?;
+    let collections = profiler.time("collections", || read_collections(expanded_dir))?;
+    let mut files = FileCheck::default();
+    let gameitems = profiler.time("gameitems", || {
+        read_gameitems(expanded_dir, options, &mut files)
+    })?;
+    let mut metadata = AssetMetadataIndex::default();
+    let images = profiler.time("images", || {
+        read_images(
+            expanded_dir,
+            options,
+            &mut metadata,
+            &mut report,
+            &mut files,
+        )
+    })?;
+    let sounds = profiler.time("sounds", || {
+        read_sounds(expanded_dir, &mut metadata, &mut files)
+    })?;
+    let fonts = profiler.time("fonts", || read_fonts(expanded_dir, &mut files))?;
+    let extra_streams = profiler.time("extra_streams", || {
+        read_extra_streams(expanded_dir, &mut files)
+    })?;
+    files.finish(expanded_dir.as_ref())?;
+    let mut gamedata = profiler.time("gamedata", || read_gamedata_with_materials(expanded_dir))?;
     gamedata.collections_size = collections.len() as u32;
     gamedata.gameitems_size = gameitems.len() as u32;
     gamedata.images_size = images.len() as u32;
     gamedata.sounds_size = sounds.len() as u32;
     gamedata.fonts_size = fonts.len() as u32;
+
+    let mut vpx = VPX {
+        custominfotags,
+        info,
+        version,
+        gamedata,
+        gameitems,
+        images,
+        sounds,
+        fonts,
+        collections,
+        extra_streams,
+        changelog: Default::default(),
+    };
+    write_asset_metadata(&mut vpx, &metadata)?;
+    Ok((vpx, report))
+}
+
+/// The game data including the materials and render probes, which have their own files
+fn read_gamedata_with_materials<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<GameData> {
+    let mut gamedata = read_game_data(expanded_dir)?;
     let materials_opt = read_materials(expanded_dir)?;
     match materials_opt {
         Some(materials) => {
@@ -174,19 +592,39 @@ pub fn read<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<VPX> {
         }
     }
     gamedata.render_probes = read_renderprobes(expanded_dir)?;
+    Ok(gamedata)
+}
 
-    let vpx = VPX {
-        custominfotags,
-        info,
-        version,
-        gamedata,
-        gameitems,
-        images,
-        sounds,
-        fonts,
-        collections,
-    };
-    Ok(vpx)
+fn read_version<P: AsRef<Path>>(
+    expanded_dir: &P,
+    report: &mut MigrationReport,
+) -> io::Result<Version> {
+    let legacy_version_path = Path::new("GameStg").join("Version");
+    let version_path = report.resolve(
+        expanded_dir.as_ref(),
+        Path::new("version.txt"),
+        std::slice::from_ref(&legacy_version_path),
+    );
+    if !version_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Version file not found: {}", version_path.display()),
+        ));
+    }
+    let mut version_file = File::open(&version_path)?;
+    if version_path.ends_with(&legacy_version_path) {
+        // the raw stream as stored in the vpx file
+        let version = version_file.read_u32::<LittleEndian>()?;
+        return Ok(Version::new(version));
+    }
+    let mut version_string = String::new();
+    version_file.read_to_string(&mut version_string)?;
+    Version::parse(&version_string).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse version {}: {}", &version_string, e),
+        )
+    })
 }
 
 fn write_game_data<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
@@ -234,40 +672,56 @@ where
     }
     let mut game_data_file = File::open(&game_data_path)?;
     serde_json::from_reader(&mut game_data_file).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to parse/read json {}: {}", path.display(), e),
-        )
+        io::Error::other(format!(
+            "Failed to parse/read json {}: {}",
+            path.display(),
+            e
+        ))
     })
 }
 
-fn write_images<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
+fn write_images<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    let metadata = read_asset_metadata(vpx)?;
     // create an image index
     let images_index_path = expanded_dir.as_ref().join("images.json");
     let mut images_index_file = File::create(images_index_path)?;
     // on macOS/windows the file system is case-insensitive
-    let mut image_names_lower: HashSet<String> = HashSet::new();
-    let mut image_names_dupe_counter = 0;
+    let mut file_name_gen =
+        FileNameGen::dedup().with_max_len(max_file_name_len("images", EXTENSION_ROOM, options));
     let mut json_images = Vec::with_capacity(vpx.sounds.len());
     let images: io::Result<Vec<(String, &ImageData)>> = vpx
         .images
         .iter()
         .map(|image| {
             let mut json = ImageDataJson::from_image_data(image);
-            let lower_name = image.name.to_lowercase();
-            if image_names_lower.contains(&lower_name) {
-                image_names_dupe_counter += 1;
-                let name_dedup = format!("{}_dedup{}", image.name, image_names_dupe_counter);
+            json.metadata = metadata.images.get(&image.name).cloned();
+            let name_dedup =
+                file_name_gen.ensure_unique(options.file_name_encoding.encode(&image.name));
+            if name_dedup != image.name {
                 eprintln!(
-                    "Image name {} is not unique, renaming file to {}",
+                    "Image name {} is not unique or not a valid file name, renaming file to {}",
                     image.name, &name_dedup
                 );
                 json.name_dedup = Some(name_dedup);
             }
-            image_names_lower.insert(lower_name);
 
-            let actual_name = json.name_dedup.as_ref().unwrap_or(&image.name);
-            let file_name = format!("{}.{}", actual_name, image.ext());
+            let file_name = if options.content_addressed_files && !image.is_link() {
+                let data = match (&image.jpeg, &image.bits) {
+                    (Some(jpeg), _) => &jpeg.data,
+                    (None, Some(bits)) => &bits.lzw_compressed_data,
+                    (None, None) => &Vec::new(),
+                };
+                let file_name = format!("{}.{}", content_hash(data), image.ext());
+                json.file = Some(file_name.clone());
+                file_name
+            } else {
+                let actual_name = json.name_dedup.as_ref().unwrap_or(&image.name);
+                format!("{}.{}", actual_name, image.ext())
+            };
 
             if let Some(jpeg) = &image.jpeg {
                 // Only if the actual image dimensions are different from
@@ -340,6 +794,9 @@ fn write_images<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), Write
                     format!("Image has no data: {}", file_path.display()),
                 ))
             }
+        } else if options.content_addressed_files {
+            // same contents as an image that was already written
+            Ok(())
         } else {
             Err(io::Error::new(
                 io::ErrorKind::AlreadyExists,
@@ -353,6 +810,13 @@ fn write_images<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), Write
     Ok(())
 }
 
+/// 64 bit FNV-1a hash as hex, stable across platforms and releases
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    let mut hasher = Fnv64::new();
+    hasher.write(data);
+    format!("{:016x}", hasher.finish())
+}
+
 fn write_image_bmp(
     file_path: &PathBuf,
     lzw_compressed_data: &[u8],
@@ -375,14 +839,11 @@ fn write_image_bmp(
         );
     }
     image_to_save.save(file_path).map_err(|image_error| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!(
-                "Failed to write bitmap to {}: {}",
-                file_path.display(),
-                image_error
-            ),
-        )
+        io::Error::other(format!(
+            "Failed to write bitmap to {}: {}",
+            file_path.display(),
+            image_error
+        ))
     })
 }
 
@@ -416,12 +877,24 @@ fn swap_red_and_blue(data: &[u8]) -> Vec<u8> {
     swapped
 }
 
-fn read_images<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<ImageData>> {
+fn read_images<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+    metadata: &mut AssetMetadataIndex,
+    report: &mut MigrationReport,
+    files: &mut FileCheck,
+) -> io::Result<Vec<ImageData>> {
     // TODO do we actually need an index?
     let images_index_path = expanded_dir.as_ref().join("images.json");
     let images_index_json: Vec<ImageDataJson> = read_json(images_index_path)?;
-    let images_dir = expanded_dir.as_ref().join("images");
-    let images: io::Result<Vec<ImageData>> = images_index_json
+    metadata
+        .images
+        .extend(images_index_json.iter().filter_map(|json| {
+            json.metadata
+                .clone()
+                .map(|metadata| (json.name.clone(), metadata))
+        }));
+    let images: io::Result<Vec<Option<ImageData>>> = images_index_json
         .into_iter()
         .map(|image_data_json| {
             if image_data_json.is_link() {
@@ -431,15 +904,20 @@ fn read_images<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<ImageData>> {
                     image_data_json.height.unwrap_or(0),
                     None,
                 );
-                Ok(image)
+                Ok(Some(image))
             } else {
-                let file_name = image_data_json
-                    .name_dedup
-                    .as_ref()
-                    .unwrap_or(&image_data_json.name);
-                let full_file_name = format!("{}.{}", file_name, image_data_json.ext());
-                let file_path = images_dir.join(&full_file_name);
-                if file_path.exists() {
+                let full_file_name = match &image_data_json.file {
+                    Some(file) => plain_file_name(file)?.to_string(),
+                    None => {
+                        let file_name = image_data_json
+                            .name_dedup
+                            .as_ref()
+                            .unwrap_or(&image_data_json.name);
+                        format!("{}.{}", file_name, image_data_json.ext())
+                    }
+                };
+                let relative_path = Path::new("images").join(&full_file_name);
+                if let Some(file_path) = files.expect(expanded_dir.as_ref(), relative_path) {
                     let mut image_file = File::open(&file_path)?;
                     let mut image_data = Vec::new();
                     image_file.read_to_end(&mut image_data)?;
@@ -458,44 +936,40 @@ fn read_images<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<ImageData>> {
                         )
                     } else {
                         // use image library to get the actual dimensions
-                        let dimensions_from_file = read_image_dimensions(&file_path)?;
-
-                        let width = match image_data_json.width {
-                            Some(w) => {
-                                if let Some(dimensions) = dimensions_from_file {
-                                    if w != dimensions.0 {
-                                        eprintln!(
-                                            "Image width override for {} in json ({}) vs in image ({})",
-                                            full_file_name, w, dimensions.0
-                                        );
-                                    }
+                        let dimensions_from_file = match probe_image_dimensions(&image_data) {
+                            Ok(dimensions) => Some(dimensions),
+                            Err(error) => {
+                                let failure = ImageProbeFailure {
+                                    file: Path::new("images").join(&full_file_name),
+                                    error,
+                                };
+                                if options.strict_image_probing {
+                                    return Err(io::Error::new(
+                                        io::ErrorKind::InvalidData,
+                                        failure.to_string(),
+                                    ));
                                 }
-                                w
+                                report.image_probe_failures.push(failure);
+                                None
                             }
-                            None =>
-                                match dimensions_from_file {
-                                    Some((width_file, _)) => width_file,
-                                    None => return Err(io::Error::new(io::ErrorKind::InvalidData, "Image width not provided and could not be read from file")),
-                                }
                         };
 
-                        let height = match image_data_json.height {
-                            Some(h) => {
-                                if let Some(dimensions) = dimensions_from_file {
-                                    if h != dimensions.1 {
-                                        eprintln!(
-                                            "Image height override for {} in json ({}) vs in image ({})",
-                                            full_file_name, h, dimensions.1
-                                        );
-                                    }
+                        let (width, height) = match dimensions_from_file {
+                            Some((width_file, height_file)) => {
+                                let width = image_data_json.width.unwrap_or(width_file);
+                                let height = image_data_json.height.unwrap_or(height_file);
+                                if width != width_file || height != height_file {
+                                    eprintln!(
+                                        "Image dimensions override for {} in json ({}x{}) vs in image ({}x{})",
+                                        full_file_name, width, height, width_file, height_file
+                                    );
                                 }
-                                h
+                                (width, height)
                             }
-                            None =>
-                                match dimensions_from_file {
-                                    Some((_, height_file)) => height_file,
-                                    None => return Err(io::Error::new(io::ErrorKind::InvalidData, "Image height not provided and could not be read from file")),
-                                }
+                            None => (
+                                image_data_json.width.unwrap_or(0),
+                                image_data_json.height.unwrap_or(0),
+                            ),
                         };
 
                         let mut image = image_data_json.to_image_data(width, height, None);
@@ -504,34 +978,23 @@ fn read_images<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<ImageData>> {
                         }
                         image
                     };
-                    Ok(image)
+                    Ok(Some(image))
                 } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::NotFound,
-                        format!("Image file not found: {}", file_path.display()),
-                    ))
+                    Ok(None)
                 }
             }
         })
         .collect();
-    images
+    Ok(images?.into_iter().flatten().collect())
 }
 
-fn read_image_dimensions(file_path: &PathBuf) -> io::Result<Option<(u32, u32)>> {
-    let decoder = image::ImageReader::open(file_path)?.with_guessed_format()?;
-    let dimensions_from_file = match decoder.into_dimensions() {
-        Ok(dimensions) => Some(dimensions),
-        Err(image_error) => {
-            // one issue we encountered is https://github.com/image-rs/image/issues/2231
-            eprintln!(
-                "Failed to read image dimensions for {}: {}",
-                file_path.display(),
-                image_error
-            );
-            None
-        }
-    };
-    Ok(dimensions_from_file)
+fn probe_image_dimensions(data: &[u8]) -> Result<(u32, u32), String> {
+    // one issue we encountered is https://github.com/image-rs/image/issues/2231
+    image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_dimensions()
+        .map_err(|e| e.to_string())
 }
 
 fn read_image_dimensions_from_file_steam(
@@ -598,45 +1061,59 @@ fn read_image_bmp(data: &[u8]) -> io::Result<ImageBmp> {
     Ok(image_bmp)
 }
 
-fn write_sounds<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
+fn write_sounds<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    let metadata = read_asset_metadata(vpx)?;
     let sounds_index_path = expanded_dir.as_ref().join("sounds.json");
     let mut sounds_index_file = File::create(sounds_index_path)?;
     // on macOS/windows the file system is case-insensitive
-    let mut sound_names_lower: HashSet<String> = HashSet::new();
-    let mut sound_names_dupe_counter = 0;
+    let mut file_name_gen =
+        FileNameGen::dedup().with_max_len(max_file_name_len("sounds", EXTENSION_ROOM, options));
     let mut json_sounds = Vec::with_capacity(vpx.sounds.len());
-    let sounds: Vec<(String, &SoundData)> = vpx
+    let sounds: Vec<(String, Vec<u8>)> = vpx
         .sounds
         .iter()
         .map(|sound| {
             let mut json = SoundDataJson::from_sound_data(sound);
-            let lower_name = sound.name.to_lowercase();
-            if sound_names_lower.contains(&lower_name) {
-                sound_names_dupe_counter += 1;
-                let name_dedup = format!("{}_dedup{}", sound.name, sound_names_dupe_counter);
+            json.metadata = metadata.sounds.get(&sound.name).cloned();
+            let name_dedup =
+                file_name_gen.ensure_unique(options.file_name_encoding.encode(&sound.name));
+            if name_dedup != sound.name {
                 eprintln!(
-                    "Sound name {} is not unique, renaming file to {}",
+                    "Sound name {} is not unique or not a valid file name, renaming file to {}",
                     sound.name, &name_dedup
                 );
                 json.name_dedup = Some(name_dedup);
             }
-            sound_names_lower.insert(lower_name);
 
-            let actual_name = json.name_dedup.as_ref().unwrap_or(&sound.name);
-            let file_name = format!("{}.{}", actual_name, sound.ext());
+            let data = write_sound(sound);
+            let file_name = if options.content_addressed_files {
+                let file_name = format!("{}.{}", content_hash(&data), sound.ext());
+                json.file = Some(file_name.clone());
+                file_name
+            } else {
+                let actual_name = json.name_dedup.as_ref().unwrap_or(&sound.name);
+                format!("{}.{}", actual_name, sound.ext())
+            };
             json_sounds.push(json);
-            (file_name, sound)
+            (file_name, data)
         })
         .collect();
     serde_json::to_writer_pretty(&mut sounds_index_file, &json_sounds)?;
 
     let sounds_dir = expanded_dir.as_ref().join("sounds");
     std::fs::create_dir_all(&sounds_dir)?;
-    sounds.iter().try_for_each(|(sound_file_name, sound)| {
+    sounds.iter().try_for_each(|(sound_file_name, data)| {
         let sound_path = sounds_dir.join(sound_file_name);
         if !sound_path.exists() {
             let mut file = File::create(sound_path)?;
-            file.write_all(&write_sound(sound))
+            file.write_all(data)
+        } else if options.content_addressed_files {
+            // same contents as a sound that was already written
+            Ok(())
         } else {
             Err(io::Error::new(
                 io::ErrorKind::AlreadyExists,
@@ -650,90 +1127,272 @@ fn write_sounds<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), Write
     Ok(())
 }
 
-fn read_sounds<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<SoundData>> {
+fn read_sounds<P: AsRef<Path>>(
+    expanded_dir: &P,
+    metadata: &mut AssetMetadataIndex,
+    files: &mut FileCheck,
+) -> io::Result<Vec<SoundData>> {
     let sounds_json_path = expanded_dir.as_ref().join("sounds.json");
     if !sounds_json_path.exists() {
         println!("No sounds.json found");
         return Ok(vec![]);
     }
     let sounds_json: Vec<SoundDataJson> = read_json(&sounds_json_path)?;
+    metadata
+        .sounds
+        .extend(sounds_json.iter().filter_map(|json| {
+            json.metadata
+                .clone()
+                .map(|metadata| (json.name.clone(), metadata))
+        }));
     // for each item in the index read the items
-    let sounds_dir = expanded_dir.as_ref().join("sounds");
-    let sounds: io::Result<Vec<SoundData>> = sounds_json
+    let sounds: io::Result<Vec<Option<SoundData>>> = sounds_json
         .into_iter()
         .map(|sound_data_json| {
             let mut sound = sound_data_json.to_sound_data();
-            let file_name = sound_data_json.name_dedup.as_ref().unwrap_or(&sound.name);
-            let full_file_name = format!("{}.{}", file_name, sound.ext());
-            let file_path = sounds_dir.join(full_file_name);
-            if file_path.exists() {
+            let full_file_name = match &sound_data_json.file {
+                Some(file) => plain_file_name(file)?.to_string(),
+                None => {
+                    let file_name = sound_data_json.name_dedup.as_ref().unwrap_or(&sound.name);
+                    format!("{}.{}", file_name, sound.ext())
+                }
+            };
+            let relative_path = Path::new("sounds").join(full_file_name);
+            if let Some(file_path) = files.expect(expanded_dir.as_ref(), relative_path) {
                 let mut sound_file = File::open(&file_path)?;
                 let mut sound_data = Vec::new();
                 sound_file.read_to_end(&mut sound_data)?;
                 read_sound(&sound_data, &mut sound);
-                Ok(sound)
+                Ok(Some(sound))
             } else {
-                Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("Sound file not found: {}", file_path.display()),
-                ))
+                Ok(None)
             }
         })
         .collect();
-    sounds
+    Ok(sounds?.into_iter().flatten().collect())
 }
 
-fn write_fonts<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
+fn write_fonts<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
     let fonts_json_path = expanded_dir.as_ref().join("fonts.json");
     let mut fonts_index_file = File::create(fonts_json_path)?;
-    let fonts_index: Vec<FontDataJson> =
-        vpx.fonts.iter().map(FontDataJson::from_font_data).collect();
+    // on macOS/windows the file system is case-insensitive
+    let fonts_dir = expanded_dir.as_ref().join("fonts");
+    let mut file_name_gen =
+        FileNameGen::dedup().with_max_len(max_file_name_len("fonts", EXTENSION_ROOM, options));
+    let mut fonts_index: Vec<FontDataJson> = Vec::with_capacity(vpx.fonts.len());
+    let mut file_names: Vec<String> = Vec::with_capacity(vpx.fonts.len());
+    for font in &vpx.fonts {
+        let mut json = FontDataJson::from_font_data(font);
+        let name_dedup = file_name_gen.ensure_unique(options.file_name_encoding.encode(&font.name));
+        file_names.push(format!("{}.{}", name_dedup, font.ext()));
+        if name_dedup != font.name {
+            eprintln!(
+                "Font name {} is not unique or not a valid file name, renaming file to {}",
+                font.name, &name_dedup
+            );
+            json.name_dedup = Some(name_dedup);
+        }
+        fonts_index.push(json);
+    }
     serde_json::to_writer_pretty(&mut fonts_index_file, &fonts_index)?;
 
+    std::fs::create_dir_all(&fonts_dir)?;
+    vpx.fonts
+        .iter()
+        .zip(file_names)
+        .try_for_each(|(font, file_name)| {
+            let font_path = fonts_dir.join(file_name);
+            let mut file = File::create(font_path)?;
+            file.write_all(&font.data)
+        })?;
+    Ok(())
+}
+
+/// Adds a font to an expanded table or replaces the font with the same name, like
+/// [`VPX::add_font`]
+pub fn add_font<P: AsRef<Path>>(expanded_dir: &P, name: &str, ttf: &[u8]) -> io::Result<()> {
+    let mut fonts_index = remove_font_entry(expanded_dir.as_ref(), name)?.0;
+    let mut file_name_gen = FileNameGen::dedup();
+    for json in &fonts_index {
+        file_name_gen.ensure_unique(font_file_stem(json));
+    }
+    let font = FontData {
+        name: name.to_string(),
+        path: format!("{}.ttf", name),
+        data: ttf.to_vec(),
+    };
+    let mut json = FontDataJson::from_font_data(&font);
+    let name_dedup = file_name_gen.ensure_unique(font.name.clone());
+    if name_dedup != font.name {
+        json.name_dedup = Some(name_dedup);
+    }
     let fonts_dir = expanded_dir.as_ref().join("fonts");
     std::fs::create_dir_all(&fonts_dir)?;
-    vpx.fonts.iter().try_for_each(|font| {
-        let file_name = format!("{}.{}", font.name, font.ext());
-        let font_path = fonts_dir.join(file_name);
-        let mut file = File::create(font_path)?;
-        file.write_all(&font.data)
-    })?;
+    std::fs::write(fonts_dir.join(font_file_name(&json)), &font.data)?;
+    fonts_index.push(json);
+    write_fonts_index(expanded_dir.as_ref(), &fonts_index)
+}
+
+/// Removes a font from an expanded table, like [`VPX::remove_font`]. Returns `false` if
+/// there is no font with that name.
+pub fn remove_font<P: AsRef<Path>>(expanded_dir: &P, name: &str) -> io::Result<bool> {
+    let (fonts_index, removed) = remove_font_entry(expanded_dir.as_ref(), name)?;
+    if removed {
+        write_fonts_index(expanded_dir.as_ref(), &fonts_index)?;
+    }
+    Ok(removed)
+}
+
+/// The fonts index without the named font, the file of the font is deleted
+fn remove_font_entry(expanded_dir: &Path, name: &str) -> io::Result<(Vec<FontDataJson>, bool)> {
+    let fonts_index_path = expanded_dir.join("fonts.json");
+    let mut fonts_index: Vec<FontDataJson> = if fonts_index_path.exists() {
+        read_json(fonts_index_path)?
+    } else {
+        vec![]
+    };
+    let position = fonts_index
+        .iter()
+        .position(|json| json.to_font_data().name.eq_ignore_ascii_case(name));
+    let Some(position) = position else {
+        return Ok((fonts_index, false));
+    };
+    let json = fonts_index.remove(position);
+    let font_path = expanded_dir.join("fonts").join(font_file_name(&json));
+    if font_path.exists() {
+        std::fs::remove_file(font_path)?;
+    }
+    Ok((fonts_index, true))
+}
+
+fn font_file_stem(json: &FontDataJson) -> String {
+    json.name_dedup
+        .clone()
+        .unwrap_or_else(|| json.to_font_data().name)
+}
+
+fn font_file_name(json: &FontDataJson) -> String {
+    format!("{}.{}", font_file_stem(json), json.to_font_data().ext())
+}
+
+fn write_fonts_index(expanded_dir: &Path, fonts_index: &[FontDataJson]) -> io::Result<()> {
+    let mut fonts_index_file = File::create(expanded_dir.join("fonts.json"))?;
+    serde_json::to_writer_pretty(&mut fonts_index_file, fonts_index)?;
     Ok(())
 }
 
-fn read_fonts<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<FontData>> {
+fn read_fonts<P: AsRef<Path>>(
+    expanded_dir: &P,
+    files: &mut FileCheck,
+) -> io::Result<Vec<FontData>> {
     let fonts_index_path = expanded_dir.as_ref().join("fonts.json");
     if !fonts_index_path.exists() {
         println!("No fonts.json found");
         return Ok(vec![]);
     }
     let fonts_json: Vec<FontDataJson> = read_json(fonts_index_path)?;
-    let fonts_index: Vec<FontData> = fonts_json
-        .iter()
-        .map(|font_data_json| font_data_json.to_font_data())
-        .collect();
     // for each item in the index read the items
-    let fonts_dir = expanded_dir.as_ref().join("fonts");
-    let fonts: io::Result<Vec<FontData>> = fonts_index
+    let fonts_dir = Path::new("fonts");
+    let fonts: io::Result<Vec<Option<FontData>>> = fonts_json
         .into_iter()
-        .map(|mut font| {
-            let file_name = format!("{}.{}", font.name, font.ext());
-            let font_path = fonts_dir.join(file_name);
+        .map(|font_data_json| {
+            let mut font = font_data_json.to_font_data();
+            let file_name = font_data_json.name_dedup.as_ref().unwrap_or(&font.name);
+            let file_name = format!("{}.{}", file_name, font.ext());
+            let font_path = expanded_dir.as_ref().join(fonts_dir).join(&file_name);
             if font_path.exists() {
+                files.allow(expanded_dir.as_ref(), &font_path);
                 let mut font_file = File::open(&font_path)?;
                 let mut font_data = Vec::new();
                 font_file.read_to_end(&mut font_data)?;
                 font.data = font_data;
-                Ok(font)
+                Ok(Some(font))
             } else {
-                Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("Font file not found: {}", font_path.display()),
-                ))
+                files.missing.push(fonts_dir.join(file_name));
+                Ok(None)
             }
         })
         .collect();
-    fonts
+    Ok(fonts?.into_iter().flatten().collect())
+}
+
+/// Writes the [`VPX::extra_streams`] as files in `extra_streams/`, keeping the storages of
+/// the compound file as directories. `extra_streams.json` lists the stream paths in order.
+///
+/// The asset metadata stream is left out as the metadata is stored with the images and
+/// sounds.
+fn write_extra_streams<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> io::Result<()> {
+    let extra_streams: Vec<&ExtraStream> = vpx
+        .extra_streams
+        .iter()
+        .filter(|extra_stream| extra_stream.path != ASSET_METADATA_STREAM)
+        .collect();
+    if extra_streams.is_empty() {
+        return Ok(());
+    }
+    let paths: Vec<&str> = extra_streams.iter().map(|e| e.path.as_str()).collect();
+    let index_file = File::create(expanded_dir.as_ref().join("extra_streams.json"))?;
+    serde_json::to_writer_pretty(index_file, &paths)?;
+    for extra_stream in extra_streams {
+        let stream_path = expanded_dir
+            .as_ref()
+            .join(extra_stream_file(&extra_stream.path)?);
+        if let Some(parent) = stream_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(stream_path, &extra_stream.data)?;
+    }
+    Ok(())
+}
+
+fn read_extra_streams<P: AsRef<Path>>(
+    expanded_dir: &P,
+    files: &mut FileCheck,
+) -> io::Result<Vec<ExtraStream>> {
+    let index_path = expanded_dir.as_ref().join("extra_streams.json");
+    if !index_path.exists() {
+        return Ok(vec![]);
+    }
+    let paths: Vec<String> = read_json(index_path)?;
+    let mut extra_streams = Vec::with_capacity(paths.len());
+    for path in paths {
+        if let Some(stream_path) = files.expect(expanded_dir.as_ref(), extra_stream_file(&path)?) {
+            let data = std::fs::read(stream_path)?;
+            extra_streams.push(ExtraStream { path, data });
+        }
+    }
+    Ok(extra_streams)
+}
+
+/// The file of an extra stream relative to the expanded dir
+fn extra_stream_file(path: &str) -> io::Result<PathBuf> {
+    path.split('/')
+        .try_fold(PathBuf::from("extra_streams"), |file, part| {
+            Ok(file.join(plain_file_name(part)?))
+        })
+}
+
+/// Checks that a file name read from an index file or stream path is a single plain file
+/// name, so it can't point outside the expanded dir.
+fn plain_file_name(name: &str) -> io::Result<&str> {
+    let mut components = Path::new(name).components();
+    let plain = !name.contains(['/', '\\', ':'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+    if plain {
+        Ok(name)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid file name: {:?}", name),
+        ))
+    }
 }
 
 fn write_materials<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
@@ -838,43 +1497,205 @@ struct GameItemInfoJson {
 }
 
 /// Abstraction for making sure file names are unique
-#[derive(Default)]
+///
+/// Names are compared case-insensitively so the expanded dir can be checked out on
+/// case-insensitive file systems (Windows, macOS) and still produces the same names as on
+/// case-sensitive ones. The generated names only depend on the order of the names passed in.
 struct FileNameGen {
     used_names_lowercase: HashSet<String>,
+    separator: &'static str,
+    /// Maximum number of characters of a generated name
+    max_len: Option<usize>,
+    /// The last counter used for any name, when counting is shared between all names
+    shared_counter: Option<usize>,
+}
+
+impl Default for FileNameGen {
+    fn default() -> Self {
+        // There is a chance that the name we give is already used in one of the next files.
+        // Therefore, we use double underscores to increase the chance it is unique.
+        FileNameGen::with_separator("__")
+    }
 }
 
 impl FileNameGen {
+    fn with_separator(separator: &'static str) -> Self {
+        FileNameGen {
+            used_names_lowercase: HashSet::new(),
+            separator,
+            max_len: None,
+            shared_counter: None,
+        }
+    }
+
+    /// The `_dedup` naming used for images, sounds and fonts.
+    ///
+    /// Expanded dirs written before all names were deduplicated case-insensitively
+    /// numbered the renamed files with one counter for all names, eg `A_dedup1`,
+    /// `B_dedup2`. Keeping that numbering avoids renaming files in existing repos.
+    fn dedup() -> Self {
+        FileNameGen {
+            shared_counter: Some(0),
+            ..FileNameGen::with_separator("_dedup")
+        }
+    }
+
+    fn with_max_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Makes the name safe to use as a file name on every os and unique ignoring case
     fn ensure_unique(&mut self, file_name: String) -> String {
-        let lower_name = file_name.to_lowercase();
-        if !self.used_names_lowercase.contains(&lower_name) {
-            self.used_names_lowercase.insert(lower_name.clone());
+        let file_name = safe_file_stem(&file_name);
+        let file_name = truncate_chars(&file_name, self.max_len);
+        if self.used_names_lowercase.insert(file_name.to_lowercase()) {
             return file_name;
         }
-
-        let mut counter = 1;
-        let mut unique_name;
+        let mut counter = self.shared_counter.unwrap_or_default() + 1;
         loop {
-            // There is a chance that the name we give is already used in one of the next files.
-            // Therefore, we use double underscores to increase the chance it is unique.
-            unique_name = format!("{}__{}", file_name, counter);
-            let unique_name_lower = unique_name.to_lowercase();
-            if !self.used_names_lowercase.contains(&unique_name_lower) {
-                self.used_names_lowercase.insert(unique_name_lower);
-                break;
+            let suffix = format!("{}{}", self.separator, counter);
+            let max_len = self
+                .max_len
+                .map(|max_len| max_len.saturating_sub(suffix.chars().count()));
+            let unique_name = format!("{}{}", truncate_chars(&file_name, max_len), suffix);
+            if self.used_names_lowercase.insert(unique_name.to_lowercase()) {
+                if let Some(shared_counter) = &mut self.shared_counter {
+                    *shared_counter = counter;
+                }
+                return unique_name;
             }
             counter += 1;
         }
-        unique_name
     }
 }
 
-fn write_gameitems<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), WriteError> {
+/// Names Windows reserves for devices, also when followed by an extension
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters Windows does not allow in file names, next to the control characters
+const WINDOWS_INVALID_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// The longest path within the expanded directory, the Windows limit of 260 characters
+/// without long path support minus room for the location of the expanded directory
+const MAX_RELATIVE_PATH: usize = 160;
+
+/// Room for the extension of image, sound and font files
+const EXTENSION_ROOM: usize = 8;
+
+/// Room for the longest suffix of game item files, like `_anim_1000.obj`
+const GAMEITEM_SUFFIX_ROOM: usize = 16;
+
+/// Room for the longest type directory, `lightsequencers/`
+const GAMEITEM_TYPE_DIR_ROOM: usize = 16;
+
+/// Replaces what Windows does not allow in a file name.
+///
+/// Invalid characters and trailing dots and spaces, which Windows silently drops, become
+/// underscores. Reserved device names like `CON` get an underscore in front.
+fn safe_file_stem(name: &str) -> String {
+    let trailing = name.len() - name.trim_end_matches(['.', ' ']).len();
+    let mut stem: String = name[..name.len() - trailing]
+        .chars()
+        .map(|c| {
+            if c.is_control() || WINDOWS_INVALID_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    stem.extend(std::iter::repeat_n('_', trailing));
+    let base = stem.split('.').next().unwrap_or_default().trim_end();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(base))
+    {
+        stem.insert(0, '_');
+    }
+    stem
+}
+
+/// Keeps at most `max_len` characters, without splitting percent encoded characters
+fn truncate_chars(name: &str, max_len: Option<usize>) -> String {
+    let Some(max_len) = max_len else {
+        return name.to_string();
+    };
+    let mut end = 0;
+    let mut len = 0;
+    while end < name.len() {
+        let rest = &name[end..];
+        let unit = escaped_char_len(rest)
+            .unwrap_or_else(|| rest.chars().next().map(char::len_utf8).unwrap_or(1));
+        let unit_len = rest[..unit].chars().count();
+        if len + unit_len > max_len {
+            break;
+        }
+        len += unit_len;
+        end += unit;
+    }
+    name[..end].to_string()
+}
+
+/// The length of the percent encoded UTF-8 character at the start of the name, eg `%C3%A9`
+fn escaped_char_len(name: &str) -> Option<usize> {
+    let byte = |i: usize| {
+        name.get(i * 3..i * 3 + 3)
+            .and_then(|escape| escape.strip_prefix('%'))
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+    };
+    let len = match byte(0)? {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    if (1..len).all(|i| byte(i).is_some_and(|b| b & 0xc0 == 0x80)) {
+        Some(len * 3)
+    } else {
+        Some(3)
+    }
+}
+
+/// The maximum length of generated file names in a directory of the expanded directory, so
+/// the paths stay within [`MAX_RELATIVE_PATH`]. `reserved` is the room needed for extensions
+/// and suffixes.
+fn max_file_name_len(dir_name: &str, reserved: usize, options: &WriteOptions) -> Option<usize> {
+    if options.long_paths {
+        return None;
+    }
+    // the directory, a separator and the file name
+    Some(MAX_RELATIVE_PATH - dir_name.len() - 1 - reserved)
+}
+
+fn write_gameitems<P: AsRef<Path>>(
+    vpx: &VPX,
+    expanded_dir: &P,
+    options: &WriteOptions,
+) -> Result<(), WriteError> {
+    let json_format = &options.gameitems_json;
     let gameitems_dir = expanded_dir.as_ref().join("gameitems");
     std::fs::create_dir_all(&gameitems_dir)?;
-    let mut file_name_gen = FileNameGen::default();
+    let mut reserved = GAMEITEM_SUFFIX_ROOM;
+    if options.gameitems_per_type_dirs {
+        reserved += GAMEITEM_TYPE_DIR_ROOM;
+    }
+    let mut file_name_gen =
+        FileNameGen::default().with_max_len(max_file_name_len("gameitems", reserved, options));
     let mut files: Vec<GameItemInfoJson> = Vec::new();
     for gameitem in &vpx.gameitems {
-        let file_name = gameitem_filename_stem(&mut file_name_gen, gameitem);
+        let mut file_name =
+            gameitem_filename_stem(&mut file_name_gen, gameitem, &options.file_name_encoding);
+        if options.gameitems_per_type_dirs {
+            let type_dir = gameitem_type_dir(gameitem);
+            std::fs::create_dir_all(gameitems_dir.join(&type_dir))?;
+            // always a forward slash so the index is the same on every os
+            file_name = format!("{}/{}", type_dir, file_name);
+        }
         let file_name_json = format!("{}.json", &file_name);
         let gameitem_info = GameItemInfoJson {
             file_name: file_name_json.clone(),
@@ -892,28 +1713,155 @@ fn write_gameitems<P: AsRef<Path>>(vpx: &VPX, expanded_dir: &P) -> Result<(), Wr
                 format!("GameItem file already exists: {}", gameitem_path.display()),
             )));
         }
-        let gameitem_file = File::create(&gameitem_path)?;
-        serde_json::to_writer_pretty(&gameitem_file, &gameitem)?;
-        write_gameitem_binaries(&gameitems_dir, gameitem, file_name)?;
+        let gameitem_file = File::create(&gameitem_path)?;
+        write_json(&gameitem_file, &gameitem_to_json(gameitem)?, json_format)?;
+        write_gameitem_binaries(&gameitems_dir, gameitem, file_name)?;
+    }
+    // write the gameitems index as array with names being the type and the name
+    let gameitems_index_path = expanded_dir.as_ref().join("gameitems.json");
+    let mut gameitems_index_file = File::create(gameitems_index_path)?;
+    write_json(&mut gameitems_index_file, &files, json_format)?;
+    Ok(())
+}
+
+/// The key holding the item type in the game item files
+const GAMEITEM_TYPE_KEY: &str = "type";
+/// The key holding the data of items that are not a struct, like [`GameItemEnum::Generic`]
+const GAMEITEM_VALUE_KEY: &str = "value";
+
+/// A game item as a json object with its type in a `type` field, eg
+/// `{"type": "Wall", "name": "Wall1", ...}`, so the file can be read without knowing its name.
+fn gameitem_to_json(gameitem: &GameItemEnum) -> serde_json::Result<Value> {
+    let Value::Object(tagged) = serde_json::to_value(gameitem)? else {
+        unreachable!("game items serialize to an object");
+    };
+    let (item_type, value) = tagged
+        .into_iter()
+        .next()
+        .expect("game items serialize to an object with one entry");
+    let mut object = serde_json::Map::new();
+    object.insert(GAMEITEM_TYPE_KEY.to_string(), Value::String(item_type));
+    match value {
+        Value::Object(fields) => object.extend(fields),
+        value => {
+            object.insert(GAMEITEM_VALUE_KEY.to_string(), value);
+        }
+    }
+    Ok(Value::Object(object))
+}
+
+/// Reads a game item written by [`gameitem_to_json`], or in the older form without a `type`
+/// field where the item is nested under its type: `{"Wall": {"name": "Wall1", ...}}`
+fn gameitem_from_json(value: Value) -> serde_json::Result<GameItemEnum> {
+    let tagged = match value {
+        Value::Object(mut object) => match object.shift_remove(GAMEITEM_TYPE_KEY) {
+            Some(Value::String(item_type)) => {
+                let value = match object.shift_remove(GAMEITEM_VALUE_KEY) {
+                    Some(value) if object.is_empty() => value,
+                    Some(value) => {
+                        object.insert(GAMEITEM_VALUE_KEY.to_string(), value);
+                        Value::Object(object)
+                    }
+                    None => Value::Object(object),
+                };
+                let mut tagged = serde_json::Map::new();
+                tagged.insert(item_type, value);
+                Value::Object(tagged)
+            }
+            Some(item_type) => {
+                return Err(de::Error::invalid_type(
+                    de::Unexpected::Other(&item_type.to_string()),
+                    &"a game item type name",
+                ))
+            }
+            None => Value::Object(object),
+        },
+        value => value,
+    };
+    serde_json::from_value(tagged)
+}
+
+fn write_json<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    format: &JsonFormat,
+) -> serde_json::Result<()> {
+    if !format.sort_keys && format.float_precision.is_none() {
+        return write_json_value(writer, value, format.pretty);
+    }
+    let mut value = serde_json::to_value(value)?;
+    normalize_json(&mut value, format);
+    write_json_value(writer, &value, format.pretty)
+}
+
+fn write_json_value<W: Write, T: Serialize>(
+    writer: W,
+    value: &T,
+    pretty: bool,
+) -> serde_json::Result<()> {
+    if pretty {
+        serde_json::to_writer_pretty(writer, value)
+    } else {
+        serde_json::to_writer(writer, value)
+    }
+}
+
+fn normalize_json(value: &mut Value, format: &JsonFormat) {
+    match value {
+        Value::Object(map) => {
+            if format.sort_keys {
+                map.sort_keys();
+            }
+            map.values_mut()
+                .for_each(|value| normalize_json(value, format));
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| normalize_json(value, format)),
+        Value::Number(number) if number.is_f64() => {
+            // converting to a value widens the f32 to f64 which adds a lot of digits
+            let float = number.as_f64().unwrap_or_default() as f32;
+            let float = match format.float_precision {
+                Some(precision) => format!("{:.*}", precision, float).parse().unwrap_or(float),
+                None => float,
+            };
+            if let Some(normalized) = serde_json::Number::from_f64(canonical_f64(float)) {
+                *number = normalized;
+            }
+        }
+        _ => {}
     }
-    // write the gameitems index as array with names being the type and the name
-    let gameitems_index_path = expanded_dir.as_ref().join("gameitems.json");
-    let mut gameitems_index_file = File::create(gameitems_index_path)?;
-    serde_json::to_writer_pretty(&mut gameitems_index_file, &files)?;
-    Ok(())
 }
 
-fn gameitem_filename_stem(file_name_gen: &mut FileNameGen, gameitem: &GameItemEnum) -> String {
+fn gameitem_filename_stem(
+    file_name_gen: &mut FileNameGen,
+    gameitem: &GameItemEnum,
+    encoding: &FileNameEncoding,
+) -> String {
     let mut name = gameitem.name().to_string();
     if name.is_empty() {
         name = "unnamed".to_string();
     }
     // escape any characters that are not allowed in file names, for any os
     name = name.replace(|c: char| !c.is_alphanumeric(), "_");
+    // transliteration can add spaces and punctuation, the only % are from percent encoding
+    name = encoding
+        .encode(&name)
+        .replace(|c: char| !c.is_alphanumeric() && c != '%', "_");
     let file_name = format!("{}.{}", gameitem.type_name(), name);
     file_name_gen.ensure_unique(file_name)
 }
 
+/// Lowercase plural of the type name, eg `walls` or `textboxes`
+pub(crate) fn gameitem_type_dir(gameitem: &GameItemEnum) -> String {
+    let type_name = gameitem.type_name().to_lowercase();
+    if type_name.ends_with('x') || type_name.ends_with('s') {
+        format!("{}es", type_name)
+    } else {
+        format!("{}s", type_name)
+    }
+}
+
 // This is how they were compressed using zlib
 //
 // const mz_ulong slen = (mz_ulong)(sizeof(Vertex3dNoTex2)*m_mesh.NumVertices());
@@ -921,14 +1869,14 @@ fn gameitem_filename_stem(file_name_gen: &mut FileNameGen, gameitem: &GameItemEn
 // mz_uint8 * c = (mz_uint8 *)malloc(clen);
 // if (compress2(c, &clen, (const unsigned char *)m_mesh.m_vertices.data(), slen, MZ_BEST_COMPRESSION) != Z_OK)
 // ShowError("Could not compress primitive vertex data");
-fn decompress_data(compressed_data: &[u8]) -> io::Result<Vec<u8>> {
+pub(crate) fn decompress_data(compressed_data: &[u8]) -> io::Result<Vec<u8>> {
     let mut decoder = ZlibDecoder::new(compressed_data);
     let mut decompressed_data = Vec::new();
     decoder.read_to_end(&mut decompressed_data)?;
     Ok(decompressed_data)
 }
 
-fn compress_data(data: &[u8]) -> io::Result<Vec<u8>> {
+pub(crate) fn compress_data(data: &[u8]) -> io::Result<Vec<u8>> {
     // before 10.6.1, compression was always LZW
     // "abuses the VP-Image-LZW compressor"
     // see https://github.com/vpinball/vpinball/commit/09f5510d676cd6b204350dfc4a93b9bf93284c56
@@ -937,10 +1885,10 @@ fn compress_data(data: &[u8]) -> io::Result<Vec<u8>> {
     encoder.finish()
 }
 
-const BYTES_PER_VERTEX: usize = 32;
+pub(crate) const BYTES_PER_VERTEX: usize = 32;
 
 /// when there are more than 65535 vertices we use 4 bytes per index value
-const MAX_VERTICES_FOR_2_BYTE_INDEX: usize = 65535;
+pub(crate) const MAX_VERTICES_FOR_2_BYTE_INDEX: usize = 65535;
 
 /// for primitives we write fields m3cx, m3ci and m3ay's to separate files with bin extension
 fn write_gameitem_binaries(
@@ -956,9 +1904,8 @@ fn write_gameitem_binaries(
             if let Some(indices_data) = &primitive.compressed_indices_data {
                 let (vertices, indices) = read_mesh(primitive, vertices_data, indices_data)?;
                 let obj_path = gameitems_dir.join(format!("{}.obj", json_file_name));
-                write_obj(gameitem.name().to_string(), &vertices, &indices, &obj_path).map_err(
-                    |e| WriteError::Io(io::Error::new(io::ErrorKind::Other, format!("{}", e))),
-                )?;
+                write_obj(gameitem.name().to_string(), &vertices, &indices, &obj_path)
+                    .map_err(|e| WriteError::Io(io::Error::other(format!("{}", e))))?;
 
                 if let Some(animation_frames) = &primitive.compressed_animation_vertices_data {
                     if let Some(compressed_lengths) = &primitive.compressed_animation_vertices_len {
@@ -1018,7 +1965,7 @@ fn write_animation_frames_to_objs(
             indices,
             &obj_path,
         )
-        .map_err(|e| WriteError::Io(io::Error::new(io::ErrorKind::Other, format!("{}", e))))?;
+        .map_err(|e| WriteError::Io(io::Error::other(format!("{}", e))))?;
     }
     Ok(())
 }
@@ -1254,7 +2201,11 @@ fn read_vertex_index_from_vpx(bytes_per_index: u8, buff: &mut BytesMut) -> i64 {
     }
 }
 
-fn read_gameitems<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<GameItemEnum>> {
+fn read_gameitems<P: AsRef<Path>>(
+    expanded_dir: &P,
+    options: &ReadOptions,
+    files: &mut FileCheck,
+) -> io::Result<Vec<GameItemEnum>> {
     let gameitems_index_path = expanded_dir.as_ref().join("gameitems.json");
     if !gameitems_index_path.exists() {
         println!("No gameitems.json found");
@@ -1263,40 +2214,68 @@ fn read_gameitems<P: AsRef<Path>>(expanded_dir: &P) -> io::Result<Vec<GameItemEn
     let gameitems_index: Vec<GameItemInfoJson> = read_json(gameitems_index_path)?;
     // for each item in the index read the items
     let gameitems_dir = expanded_dir.as_ref().join("gameitems");
-    let gameitems: io::Result<Vec<GameItemEnum>> = gameitems_index
+    let gameitems: io::Result<Vec<Option<GameItemEnum>>> = gameitems_index
         .into_iter()
         .map(|gameitem_info| {
-            let gameitem_path = gameitems_dir.join(&gameitem_info.file_name);
-            if gameitem_path.exists() {
-                let mut item: GameItemEnum = read_json(&gameitem_path)?;
+            let relative_path = Path::new("gameitems").join(&gameitem_info.file_name);
+            if let Some(gameitem_path) = files.expect(expanded_dir.as_ref(), relative_path) {
+                let value: Value = read_json(&gameitem_path)?;
+                let mut item = gameitem_from_json(value).map_err(|e| {
+                    io::Error::other(format!(
+                        "Failed to parse/read json {}: {}",
+                        gameitem_path.display(),
+                        e
+                    ))
+                })?;
                 item.set_locked(gameitem_info.is_locked);
                 item.set_editor_layer(gameitem_info.editor_layer);
                 item.set_editor_layer_name(gameitem_info.editor_layer_name);
                 item.set_editor_layer_visibility(gameitem_info.editor_layer_visibility);
-                read_gameitem_binaries(&gameitems_dir, gameitem_info.file_name, item)
+                read_gameitem_binaries(
+                    expanded_dir.as_ref(),
+                    &gameitems_dir,
+                    gameitem_info.file_name,
+                    item,
+                    options,
+                    files,
+                )
+                .map(Some)
             } else {
-                Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("GameItem file not found: {}", gameitem_path.display()),
-                ))
+                Ok(None)
             }
         })
         .collect();
-    gameitems
+    Ok(gameitems?.into_iter().flatten().collect())
 }
 
 /// for primitives we read fields m3cx, m3ci and m3ay's from separate files with bin extension
+///
+/// Animation frames need the mesh, a missing mesh is recorded in `files` when there are frames.
 fn read_gameitem_binaries(
+    expanded_dir: &Path,
     gameitems_dir: &Path,
     gameitem_file_name: String,
     mut item: GameItemEnum,
+    options: &ReadOptions,
+    files: &mut FileCheck,
 ) -> io::Result<GameItemEnum> {
     if let GameItemEnum::Primitive(primitive) = &mut item {
         let gameitem_file_name = gameitem_file_name.trim_end_matches(".json");
         let obj_path = gameitems_dir.join(format!("{}.obj", gameitem_file_name));
+        let frame0_file_name = animation_frame_file_name(gameitem_file_name, 0);
+        let frame0_path = gameitems_dir.join(frame0_file_name);
+        if frame0_path.exists() && !obj_path.exists() {
+            files.expect(
+                expanded_dir,
+                Path::new("gameitems").join(format!("{}.obj", gameitem_file_name)),
+            );
+            return Ok(item);
+        }
         if obj_path.exists() {
+            files.allow(expanded_dir, &obj_path);
+            let weld_tolerance = options.weld_tolerance.filter(|_| !frame0_path.exists());
             let (vertices_len, indices_len, compressed_vertices, compressed_indices) =
-                read_obj(&obj_path)?;
+                read_obj(&obj_path, weld_tolerance)?;
             primitive.num_vertices = Some(vertices_len as u32);
             primitive.compressed_vertices_len = Some(compressed_vertices.len() as u32);
             primitive.compressed_vertices_data = Some(compressed_vertices);
@@ -1304,8 +2283,6 @@ fn read_gameitem_binaries(
             primitive.compressed_indices_len = Some(compressed_indices.len() as u32);
             primitive.compressed_indices_data = Some(compressed_indices);
         }
-        let frame0_file_name = animation_frame_file_name(gameitem_file_name, 0);
-        let frame0_path = gameitems_dir.join(frame0_file_name);
         if frame0_path.exists() {
             // we have animation frames
             let mut frame = 0;
@@ -1314,6 +2291,7 @@ fn read_gameitem_binaries(
                 let frame_path =
                     gameitems_dir.join(animation_frame_file_name(gameitem_file_name, frame));
                 if frame_path.exists() {
+                    files.allow(expanded_dir, &frame_path);
                     let animation_frame = read_obj_as_frame(&frame_path)?;
                     frames.push(animation_frame);
                     frame += 1;
@@ -1348,19 +2326,24 @@ fn animation_frame_file_name(gameitem_file_name: &str, index: usize) -> String {
     format!("{}_anim_{}.obj", gameitem_file_name, index)
 }
 
-fn read_obj(obj_path: &PathBuf) -> io::Result<(usize, usize, Vec<u8>, Vec<u8>)> {
+fn read_obj(
+    obj_path: &PathBuf,
+    weld_tolerance: Option<f32>,
+) -> io::Result<(usize, usize, Vec<u8>, Vec<u8>)> {
     let ObjData {
         name: _,
         vertices,
         texture_coordinates,
         normals,
         indices,
+        face_vertices: _,
     } = read_obj_file(obj_path).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Error reading obj {}: {}", obj_path.display(), e),
-        )
+        io::Error::other(format!("Error reading obj {}: {}", obj_path.display(), e))
     })?;
+    let (vertices, texture_coordinates, normals, indices) = match weld_tolerance {
+        Some(tolerance) => weld_obj(vertices, texture_coordinates, normals, &indices, tolerance),
+        None => (vertices, texture_coordinates, normals, indices),
+    };
 
     // zip the vertices, texture coordinates and normals into a single buffer
     let mut vpx_vertices = BytesMut::with_capacity(vertices.len() * 32);
@@ -1419,6 +2402,47 @@ fn read_obj(obj_path: &PathBuf) -> io::Result<(usize, usize, Vec<u8>, Vec<u8>)>
     ))
 }
 
+type ObjVertices = (
+    Vec<(f64, f64, f64, Option<f64>)>,
+    Vec<(f64, Option<f64>, Option<f64>)>,
+    Vec<ObjNormal>,
+    Vec<i64>,
+);
+
+fn weld_obj(
+    vertices: Vec<(f64, f64, f64, Option<f64>)>,
+    texture_coordinates: Vec<(f64, Option<f64>, Option<f64>)>,
+    normals: Vec<ObjNormal>,
+    indices: &[i64],
+    tolerance: f32,
+) -> ObjVertices {
+    let attributes: Vec<[f32; 8]> = vertices
+        .iter()
+        .zip(texture_coordinates.iter())
+        .zip(normals.iter())
+        .map(|((v, vt), (n, _))| {
+            [
+                v.0 as f32,
+                v.1 as f32,
+                v.2 as f32,
+                n.0 as f32,
+                n.1 as f32,
+                n.2 as f32,
+                vt.0 as f32,
+                vt.1.unwrap_or(0.0) as f32,
+            ]
+        })
+        .collect();
+    let indices: Vec<u32> = indices.iter().map(|&i| i as u32).collect();
+    let (indices, kept) = weld(&attributes, &indices, tolerance);
+    (
+        kept.iter().map(|&i| vertices[i]).collect(),
+        kept.iter().map(|&i| texture_coordinates[i]).collect(),
+        kept.iter().map(|&i| normals[i]).collect(),
+        indices.into_iter().map(|i| i as i64).collect(),
+    )
+}
+
 fn read_obj_as_frame(obj_path: &PathBuf) -> io::Result<Vec<VertData>> {
     let ObjData {
         name: _,
@@ -1426,11 +2450,9 @@ fn read_obj_as_frame(obj_path: &PathBuf) -> io::Result<Vec<VertData>> {
         texture_coordinates: _,
         normals,
         indices: _,
+        face_vertices: _,
     } = read_obj_file(obj_path).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Error reading obj {}: {}", obj_path.display(), e),
-        )
+        io::Error::other(format!("Error reading obj {}: {}", obj_path.display(), e))
     })?;
     let mut vertices: Vec<VertData> = Vec::with_capacity(obj_vertices.len());
     for (v, vn) in obj_vertices.iter().zip(normals.iter()) {
@@ -1620,7 +2642,8 @@ pub fn extract_directory_list(vpx_file_path: &Path) -> Vec<String> {
             .unwrap();
         let gameitem = gameitem::read(&input);
         let mut gameitem_path = gameitems_path.clone();
-        let file_name_stem = gameitem_filename_stem(&mut file_name_gen, &gameitem);
+        let file_name_stem =
+            gameitem_filename_stem(&mut file_name_gen, &gameitem, &FileNameEncoding::default());
         gameitem_path.push(format!("{}.json", file_name_stem));
         files.push(gameitem_path.to_string_lossy().to_string());
     }
@@ -1701,6 +2724,7 @@ fn retrieve_entries_from_compound_file(comp: &CompoundFile<File>) -> Vec<String>
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::vpx::biff::BiffWriter;
     use crate::vpx::gameitem;
     use crate::vpx::gameitem::GameItemEnum;
     use crate::vpx::image::ImageDataJpeg;
@@ -1871,7 +2895,12 @@ mod test {
                     100,
                     gameitem::generic::Generic {
                         name: "test gameitem".to_string(),
-                        fields: vec![],
+                        data: {
+                            let mut writer = BiffWriter::new();
+                            writer.write_tagged_wide_string("NAME", "test gameitem");
+                            writer.close(true);
+                            writer.get_data().to_vec()
+                        },
                     },
                 ),
             ],
@@ -1970,6 +2999,8 @@ mod test {
                     group_elements: true,
                 },
             ],
+            extra_streams: vec![],
+            changelog: Default::default(),
         };
 
         write(&vpx, &expanded_path)?;
@@ -1979,6 +3010,536 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_read_obj_weld() -> TestResult {
+        let obj_path = testdir!().join("quad.obj");
+        // a quad as two separate triangles, like a mesh exported with split normals
+        std::fs::write(
+            &obj_path,
+            "o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+vn 0 0 1
+vn 0 0 1
+vn 0 0 1
+vn 0 0 1
+vn 0 0 1
+f 1/1/1 2/2/2 3/3/3
+f 4/4/4 5/5/5 6/6/6
+",
+        )?;
+
+        let (vertices_len, indices_len, _, _) = read_obj(&obj_path, None)?;
+        assert_eq!((vertices_len, indices_len), (6, 6));
+
+        let (vertices_len, indices_len, _, compressed_indices) = read_obj(&obj_path, Some(0.0))?;
+        assert_eq!((vertices_len, indices_len), (4, 6));
+        let mut indices = BytesMut::from(decompress_data(&compressed_indices)?.as_slice());
+        let indices: Vec<u16> = (0..6).map(|_| indices.get_u16_le()).collect();
+        // reversed winding because of the inverted z axis
+        assert_eq!(indices, vec![2, 1, 0, 3, 2, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_files_pass_through() -> TestResult {
+        let dir = testdir!();
+        let pixels =
+            image::RgbImage::from_fn(3, 2, |x, y| image::Rgb([x as u8 * 80, y as u8 * 120, 7]));
+        let mut vpx = VPX::default();
+        for format in [
+            image::ImageFormat::Png,
+            image::ImageFormat::Jpeg,
+            image::ImageFormat::WebP,
+        ] {
+            let mut data = Vec::new();
+            pixels.write_to(&mut std::io::Cursor::new(&mut data), format)?;
+            let ext = format.extensions_str()[0];
+            vpx.add_or_replace_image(ImageData {
+                name: ext.to_string(),
+                path: format!("image.{}", ext),
+                width: 3,
+                height: 2,
+                jpeg: Some(ImageDataJpeg {
+                    path: format!("image.{}", ext),
+                    name: ext.to_string(),
+                    internal_name: None,
+                    data,
+                }),
+                ..Default::default()
+            });
+        }
+        let checksums: Vec<Option<u32>> = vpx.images.iter().map(ImageData::checksum).collect();
+        assert!(checksums.iter().all(Option::is_some));
+
+        let vpx_path = dir.join("test.vpx");
+        crate::vpx::write(&vpx_path, &vpx)?;
+        let from_vpx = crate::vpx::read(&vpx_path)?;
+        let expanded_path = dir.join("expanded");
+        std::fs::create_dir(&expanded_path)?;
+        write(&from_vpx, &expanded_path)?;
+        let from_expanded = read(&expanded_path)?;
+
+        for images in [&from_vpx.images, &from_expanded.images] {
+            let read: Vec<Option<u32>> = images.iter().map(ImageData::checksum).collect();
+            assert_eq!(read, checksums);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_is_deterministic() -> TestResult {
+        let dir = testdir!();
+        let vpx = crate::vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx"))?;
+        let files = |name: &str| -> TestResult<Vec<(PathBuf, Vec<u8>)>> {
+            let expanded_dir = dir.join(name);
+            std::fs::create_dir(&expanded_dir)?;
+            write(&vpx, &expanded_dir)?;
+            let mut files = Vec::new();
+            let mut dirs = vec![expanded_dir.clone()];
+            while let Some(current) = dirs.pop() {
+                for entry in std::fs::read_dir(current)? {
+                    let path = entry?.path();
+                    if path.is_dir() {
+                        dirs.push(path);
+                    } else {
+                        let relative = path.strip_prefix(&expanded_dir)?.to_path_buf();
+                        files.push((relative, std::fs::read(&path)?));
+                    }
+                }
+            }
+            files.sort();
+            Ok(files)
+        };
+        let first = files("first")?;
+        assert_eq!(first, files("second")?);
+        // f32 values in obj files are written in their shortest form
+        let obj: String = first
+            .iter()
+            .filter(|(path, _)| path.extension() == Some(OsStr::new("obj")))
+            .map(|(_, data)| String::from_utf8_lossy(data).to_string())
+            .collect();
+        assert!(!obj.contains("0.0000009999999974752427"));
+        assert!(obj.contains(" 0.000001 "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_gameitems_json_format() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        let mut flipper = gameitem::flipper::Flipper::default();
+        flipper.base_radius = 0.1;
+        vpx.add_game_item(GameItemEnum::Flipper(flipper));
+        let path = Path::new("gameitems").join("Flipper.unnamed.json");
+
+        let lossless = WriteOptions {
+            gameitems_json: JsonFormat {
+                pretty: false,
+                sort_keys: true,
+                float_precision: None,
+            },
+            ..Default::default()
+        };
+        std::fs::create_dir(dir.join("lossless"))?;
+        write_with_options(&vpx, &dir.join("lossless"), &lossless)?;
+        let json = std::fs::read_to_string(dir.join("lossless").join(&path))?;
+        assert!(!json.contains('\n'));
+        assert!(json.contains("\"base_radius\":0.1,"));
+        let value: Value = serde_json::from_str(&json)?;
+        assert_eq!(value["type"], "Flipper");
+        let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+        assert_eq!(read(&dir.join("lossless"))?.gameitems, vpx.gameitems);
+
+        let rounded = WriteOptions {
+            gameitems_json: JsonFormat {
+                float_precision: Some(0),
+                ..lossless.gameitems_json
+            },
+            ..Default::default()
+        };
+        std::fs::create_dir(dir.join("rounded"))?;
+        write_with_options(&vpx, &dir.join("rounded"), &rounded)?;
+        let json = std::fs::read_to_string(dir.join("rounded").join(&path))?;
+        assert!(json.contains("\"base_radius\":0.0,"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_gameitems_per_type_dirs() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(gameitem::wall::Wall {
+            name: "Wall001".to_string(),
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::TextBox(gameitem::textbox::TextBox::default()));
+        vpx.add_game_item(GameItemEnum::Light(Default::default()));
+        let options = WriteOptions {
+            gameitems_per_type_dirs: true,
+            ..Default::default()
+        };
+        write_with_options(&vpx, &dir, &options)?;
+
+        assert!(dir
+            .join("gameitems")
+            .join("walls")
+            .join("Wall.Wall001.json")
+            .exists());
+        assert!(dir
+            .join("gameitems")
+            .join("textboxes")
+            .join("TextBox.unnamed.json")
+            .exists());
+        let index: Vec<GameItemInfoJson> = read_json(dir.join("gameitems.json"))?;
+        assert_eq!(index[0].file_name, "walls/Wall.Wall001.json");
+
+        assert_eq!(read(&dir)?.gameitems, vpx.gameitems);
+        Ok(())
+    }
+
+    #[test]
+    fn test_content_addressed_files() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        for name in ["first", "FIRST", "second"] {
+            let data = if name == "second" {
+                vec![4, 5]
+            } else {
+                vec![1, 2, 3]
+            };
+            vpx.images.push(ImageData {
+                name: name.to_string(),
+                path: format!("{}.png", name),
+                width: 1,
+                height: 1,
+                jpeg: Some(ImageDataJpeg {
+                    path: format!("{}.png", name),
+                    name: name.to_string(),
+                    internal_name: None,
+                    data,
+                }),
+                ..Default::default()
+            });
+            vpx.sounds.push(SoundData {
+                name: name.to_string(),
+                path: format!("{}.ogg", name),
+                wave_form: WaveForm::new(),
+                data: vec![7, 8, 9],
+                internal_name: String::new(),
+                fade: 0,
+                volume: 0,
+                balance: 0,
+                output_target: OutputTarget::Table,
+            });
+        }
+        let options = WriteOptions {
+            content_addressed_files: true,
+            ..Default::default()
+        };
+        write_with_options(&vpx, &dir, &options)?;
+
+        let file_names = |sub_dir: &str| -> io::Result<Vec<String>> {
+            let mut names = std::fs::read_dir(dir.join(sub_dir))?
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect::<io::Result<Vec<_>>>()?;
+            names.sort();
+            Ok(names)
+        };
+        let mut expected_images = vec![
+            format!("{}.png", content_hash(&[1, 2, 3])),
+            format!("{}.png", content_hash(&[4, 5])),
+        ];
+        expected_images.sort();
+        assert_eq!(file_names("images")?, expected_images);
+        assert_eq!(file_names("sounds")?.len(), 1);
+
+        let read = read(&dir)?;
+        assert_eq!(read.images, vpx.images);
+        assert_eq!(read.sounds, vpx.sounds);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_legacy_layout() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        vpx.info.table_name = Some("legacy".to_string());
+        write(&vpx, &dir)?;
+
+        let (_, report) = read_with_report(&dir, &ReadOptions::default())?;
+        assert!(report.is_empty());
+
+        // move everything to where older vpxtool versions put it
+        std::fs::remove_file(dir.join("version.txt"))?;
+        std::fs::create_dir(dir.join("GameStg"))?;
+        std::fs::write(
+            dir.join("GameStg").join("Version"),
+            vpx.version.u32().to_le_bytes(),
+        )?;
+
+        let (read_vpx, report) = read_with_report(&dir, &ReadOptions::default())?;
+        assert_eq!(read_vpx.version, vpx.version);
+        assert_eq!(read_vpx.info.table_name, vpx.info.table_name);
+        let found: Vec<PathBuf> = report
+            .legacy_files
+            .iter()
+            .map(|f| f.found.clone())
+            .collect();
+        assert_eq!(found, vec![Path::new("GameStg").join("Version")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_animated_image_round_trip() -> TestResult {
+        use ::image::codecs::gif::{GifEncoder, Repeat};
+        use ::image::{Delay, Frame, RgbaImage};
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder.set_repeat(Repeat::Infinite)?;
+            for color in [0, 255] {
+                let buffer = RgbaImage::from_pixel(3, 2, ::image::Rgba([color, 0, 0, 255]));
+                let delay = Delay::from_numer_denom_ms(40, 1);
+                encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay))?;
+            }
+        }
+        let mut vpx = VPX::default();
+        vpx.images.push(ImageData {
+            name: "flasher".to_string(),
+            path: "flasher.gif".to_string(),
+            width: 3,
+            height: 2,
+            jpeg: Some(ImageDataJpeg {
+                path: "flasher.gif".to_string(),
+                name: "flasher".to_string(),
+                internal_name: None,
+                data: gif.clone(),
+            }),
+            ..Default::default()
+        });
+        let dir = testdir!();
+        write(&vpx, &dir)?;
+        assert_eq!(std::fs::read(dir.join("images").join("flasher.gif"))?, gif);
+
+        let read_vpx = read(&dir)?;
+        assert_eq!(read_vpx.images, vpx.images);
+        let animation = read_vpx.images[0].animation().unwrap();
+        assert_eq!(animation.frame_delays_ms, vec![40, 40]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_probe_failure() -> TestResult {
+        let mut vpx = VPX::default();
+        vpx.images.push(ImageData {
+            name: "broken".to_string(),
+            path: "broken.png".to_string(),
+            width: 2,
+            height: 3,
+            jpeg: Some(ImageDataJpeg {
+                path: "broken.png".to_string(),
+                name: "broken".to_string(),
+                internal_name: None,
+                data: vec![0x89, b'P', b'N', b'G', 1, 2, 3],
+            }),
+            ..Default::default()
+        });
+        let dir = testdir!();
+        write(&vpx, &dir)?;
+
+        let (read_vpx, report) = read_with_report(&dir, &ReadOptions::default())?;
+        assert_eq!(read_vpx.images, vpx.images);
+        assert_eq!(report.image_probe_failures.len(), 1);
+        assert_eq!(
+            report.image_probe_failures[0].file,
+            Path::new("images").join("broken.png")
+        );
+        assert!(report.is_empty());
+
+        let strict = ReadOptions {
+            strict_image_probing: true,
+            ..Default::default()
+        };
+        let error = read_with_report(&dir, &strict).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // without dimensions in the json
+        let images_json_path = dir.join("images.json");
+        let mut images_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&images_json_path)?)?;
+        let image_json = images_json[0].as_object_mut().unwrap();
+        image_json.remove("width");
+        image_json.remove("height");
+        std::fs::write(&images_json_path, serde_json::to_string(&images_json)?)?;
+        let (read_vpx, report) = read_with_report(&dir, &ReadOptions::default())?;
+        assert_eq!(
+            (read_vpx.images[0].width, read_vpx.images[0].height),
+            (0, 0)
+        );
+        assert_eq!(report.image_probe_failures.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_streams_round_trip() -> TestResult {
+        use crate::vpx::assetmeta::AssetMetadata;
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        vpx.images.push(ImageData {
+            name: "Playfield".to_string(),
+            path: "Playfield.png".to_string(),
+            width: 1,
+            height: 1,
+            jpeg: Some(ImageDataJpeg {
+                path: "Playfield.png".to_string(),
+                name: "Playfield".to_string(),
+                internal_name: None,
+                data: vec![1, 2, 3],
+            }),
+            ..Default::default()
+        });
+        let mut index = AssetMetadataIndex::default();
+        index.images.insert(
+            "Playfield".to_string(),
+            AssetMetadata {
+                author: Some("someone".to_string()),
+                ..Default::default()
+            },
+        );
+        write_asset_metadata(&mut vpx, &index)?;
+        vpx.extra_streams.push(ExtraStream {
+            path: "GameStg/PinUP".to_string(),
+            data: vec![1, 2, 3],
+        });
+        vpx.extra_streams.push(ExtraStream {
+            path: "Toolkit/Nested/Data".to_string(),
+            data: vec![4, 5],
+        });
+        let vpx_path = dir.join("test.vpx");
+        crate::vpx::write(&vpx_path, &vpx)?;
+        let from_vpx = crate::vpx::read(&vpx_path)?;
+
+        let expanded_dir = dir.join("expanded");
+        std::fs::create_dir(&expanded_dir)?;
+        write(&from_vpx, &expanded_dir)?;
+        let paths: Vec<String> = read_json(expanded_dir.join("extra_streams.json"))?;
+        assert_eq!(paths, vec!["GameStg/PinUP", "Toolkit/Nested/Data"]);
+        assert_eq!(
+            std::fs::read(
+                expanded_dir
+                    .join("extra_streams")
+                    .join("Toolkit")
+                    .join("Nested")
+                    .join("Data")
+            )?,
+            vec![4, 5]
+        );
+        let from_expanded = read(&expanded_dir)?;
+
+        let round_trip_path = dir.join("round_trip.vpx");
+        crate::vpx::write(&round_trip_path, &from_expanded)?;
+        let round_trip = crate::vpx::read(&round_trip_path)?;
+        assert_eq!(round_trip.extra_streams, from_vpx.extra_streams);
+        assert_eq!(read_asset_metadata(&round_trip)?, index);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_file_name() {
+        assert_eq!(plain_file_name("Data").unwrap(), "Data");
+        assert_eq!(plain_file_name("image.v2.png").unwrap(), "image.v2.png");
+        for name in [
+            "",
+            ".",
+            "..",
+            "/etc",
+            "a/b",
+            "..\\b",
+            "C:",
+            "C:evil",
+            "\\\\server",
+        ] {
+            assert_eq!(
+                plain_file_name(name).unwrap_err().kind(),
+                io::ErrorKind::InvalidData,
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_extra_stream_outside_expanded_dir() -> TestResult {
+        let dir = testdir!();
+        write(&VPX::default(), &dir)?;
+        for path in ["../outside", "/etc/passwd", "GameStg//Data", "C:/Data"] {
+            std::fs::write(
+                dir.join("extra_streams.json"),
+                serde_json::to_string(&[path])?,
+            )?;
+            let error = read(&dir).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", path);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_asset_metadata_round_trip() -> TestResult {
+        use crate::vpx::assetmeta::AssetMetadata;
+        let mut vpx = VPX::default();
+        vpx.images.push(ImageData {
+            name: "Playfield".to_string(),
+            path: "playfield.png".to_string(),
+            width: 1,
+            height: 1,
+            jpeg: Some(ImageDataJpeg {
+                path: "playfield.png".to_string(),
+                name: "Playfield".to_string(),
+                internal_name: None,
+                data: vec![1, 2, 3],
+            }),
+            ..Default::default()
+        });
+        let mut index = AssetMetadataIndex::default();
+        index.images.insert(
+            "Playfield".to_string(),
+            AssetMetadata {
+                author: Some("someone".to_string()),
+                license: Some("CC-BY-4.0".to_string()),
+                source_url: None,
+            },
+        );
+        write_asset_metadata(&mut vpx, &index)?;
+        let dir = testdir!();
+        write(&vpx, &dir)?;
+
+        let images_json: serde_json::Value =
+            serde_json::from_reader(File::open(dir.join("images.json"))?)?;
+        assert_eq!(
+            images_json[0]["metadata"],
+            serde_json::json!({"author": "someone", "license": "CC-BY-4.0"})
+        );
+
+        let read_vpx = read(&dir)?;
+        assert_eq!(read_asset_metadata(&read_vpx)?, index);
+        Ok(())
+    }
+
     #[test]
     fn test_file_name_gen() {
         let mut file_name_gen = FileNameGen::default();
@@ -1992,5 +3553,420 @@ mod test {
         assert_eq!("test__2".to_string(), future);
         let last = file_name_gen.ensure_unique("test".to_string());
         assert_eq!("test__3".to_string(), last);
+        let upper = file_name_gen.ensure_unique("TEST".to_string());
+        assert_eq!("TEST__4".to_string(), upper);
+    }
+
+    #[test]
+    fn test_file_name_gen_dedup() {
+        // the numbering of expanded dirs written by earlier versions
+        let mut file_name_gen = FileNameGen::dedup();
+        let names: Vec<String> = ["Image", "image", "Sound", "sound", "IMAGE"]
+            .into_iter()
+            .map(|name| file_name_gen.ensure_unique(name.to_string()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Image",
+                "image_dedup1",
+                "Sound",
+                "sound_dedup2",
+                "IMAGE_dedup3"
+            ]
+        );
+        let taken = file_name_gen.ensure_unique("image_dedup4".to_string());
+        assert_eq!(taken, "image_dedup4");
+        let skipped = file_name_gen.ensure_unique("image".to_string());
+        assert_eq!(skipped, "image_dedup5");
+    }
+
+    #[test]
+    fn test_safe_file_stem() {
+        assert_eq!(safe_file_stem("Wall1"), "Wall1");
+        assert_eq!(safe_file_stem("CON"), "_CON");
+        assert_eq!(safe_file_stem("con.txt"), "_con.txt");
+        assert_eq!(safe_file_stem("Com1"), "_Com1");
+        assert_eq!(safe_file_stem("Console"), "Console");
+        assert_eq!(safe_file_stem("a:b?"), "a_b_");
+        assert_eq!(safe_file_stem("left/right\\"), "left_right_");
+        assert_eq!(safe_file_stem("name. "), "name__");
+    }
+
+    #[test]
+    fn test_file_name_gen_max_len() {
+        let mut file_name_gen = FileNameGen::default().with_max_len(Some(6));
+        assert_eq!(
+            file_name_gen.ensure_unique("abcdefgh".to_string()),
+            "abcdef"
+        );
+        assert_eq!(
+            file_name_gen.ensure_unique("abcdefxy".to_string()),
+            "abc__1"
+        );
+        assert_eq!(file_name_gen.ensure_unique("ab".to_string()), "ab");
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        // file names of existing content addressed directories depend on this
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_max_file_name_len() {
+        assert_eq!(
+            max_file_name_len("images", 8, &WriteOptions::default()),
+            Some(MAX_RELATIVE_PATH - 6 - 1 - 8)
+        );
+        let options = WriteOptions {
+            long_paths: true,
+            ..Default::default()
+        };
+        assert_eq!(max_file_name_len("images", 8, &options), None);
+    }
+
+    #[test]
+    fn test_truncate_keeps_escapes() {
+        // é is %C3%A9, 日 is %E6%97%A5
+        let name = "ab%C3%A9%E6%97%A5";
+        assert_eq!(truncate_chars(name, Some(3)), "ab");
+        assert_eq!(truncate_chars(name, Some(7)), "ab");
+        assert_eq!(truncate_chars(name, Some(8)), "ab%C3%A9");
+        assert_eq!(truncate_chars(name, Some(16)), "ab%C3%A9");
+        assert_eq!(truncate_chars(name, Some(17)), name);
+        assert_eq!(truncate_chars("a%41%zz", Some(4)), "a%41");
+        assert_eq!(truncate_chars("a%zzb", Some(3)), "a%z");
+        assert_eq!(truncate_chars("Café", Some(3)), "Caf");
+    }
+
+    #[test]
+    fn test_file_names_do_not_depend_on_location() -> TestResult {
+        let mut vpx = VPX::default();
+        vpx.fonts.push(FontData {
+            name: "n".repeat(300),
+            path: "long.ttf".to_string(),
+            data: vec![4, 5, 6],
+        });
+        let dir = testdir!();
+        let deep_dir = dir.join("d".repeat(100)).join("e".repeat(100));
+        for expanded_dir in [dir.join("shallow"), deep_dir.clone()] {
+            std::fs::create_dir_all(&expanded_dir)?;
+            write(&vpx, &expanded_dir)?;
+        }
+        let file_names = |dir: &Path| -> io::Result<Vec<String>> {
+            std::fs::read_dir(dir.join("fonts"))?
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect()
+        };
+        assert_eq!(file_names(&dir.join("shallow"))?, file_names(&deep_dir)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_windows_unsafe_names_round_trip() -> TestResult {
+        let mut vpx = VPX::default();
+        for name in ["CON", "aux", "x:y", "trailing."] {
+            vpx.add_game_item(GameItemEnum::Wall(gameitem::wall::Wall {
+                name: name.to_string(),
+                ..Default::default()
+            }));
+            vpx.images.push(ImageData {
+                name: name.to_string(),
+                path: format!("{}.png", name),
+                width: 1,
+                height: 1,
+                jpeg: Some(ImageDataJpeg {
+                    path: format!("{}.png", name),
+                    name: name.to_string(),
+                    internal_name: None,
+                    data: vec![1, 2, 3],
+                }),
+                ..Default::default()
+            });
+        }
+        let long_name = "n".repeat(300);
+        vpx.fonts.push(FontData {
+            name: long_name.clone(),
+            path: format!("{}.ttf", long_name),
+            data: vec![4, 5, 6],
+        });
+
+        let dir = testdir!();
+        write(&vpx, &dir)?;
+
+        let mut image_names = std::fs::read_dir(dir.join("images"))?
+            .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<Vec<_>>>()?;
+        image_names.sort();
+        assert_eq!(
+            image_names,
+            vec!["_CON.png", "_aux.png", "trailing_.png", "x_y.png"]
+        );
+        for entry in std::fs::read_dir(dir.join("fonts"))? {
+            let path = Path::new("fonts").join(entry?.file_name());
+            assert!(path.as_os_str().len() <= MAX_RELATIVE_PATH);
+        }
+
+        let read_vpx = read(&dir)?;
+        assert_eq!(read_vpx.gameitems, vpx.gameitems);
+        assert_eq!(read_vpx.images, vpx.images);
+        assert_eq!(read_vpx.fonts, vpx.fonts);
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_name_encoding() {
+        assert_eq!(FileNameEncoding::Unicode.encode("Café 東京"), "Café 東京");
+        assert_eq!(FileNameEncoding::Transliterate.encode("Café"), "Cafe");
+        assert_eq!(
+            FileNameEncoding::PercentEncode.encode("Café 100%"),
+            "Caf%C3%A9 100%25"
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_names_round_trip() -> TestResult {
+        let mut vpx = VPX::default();
+        for name in ["Café", "東京"] {
+            vpx.add_game_item(GameItemEnum::Wall(gameitem::wall::Wall {
+                name: name.to_string(),
+                ..Default::default()
+            }));
+            vpx.fonts.push(FontData {
+                name: name.to_string(),
+                path: format!("{}.ttf", name),
+                data: vec![4, 5, 6],
+            });
+        }
+        let file_names = |dir: &Path| -> io::Result<Vec<String>> {
+            let mut names = std::fs::read_dir(dir)?
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect::<io::Result<Vec<_>>>()?;
+            names.sort();
+            Ok(names)
+        };
+
+        let dir = testdir!();
+        for (encoding, fonts, walls) in [
+            (
+                FileNameEncoding::Unicode,
+                ["Café.ttf", "東京.ttf"],
+                ["Wall.Café.json", "Wall.東京.json"],
+            ),
+            (
+                FileNameEncoding::Transliterate,
+                ["Cafe.ttf", "Dong Jing.ttf"],
+                ["Wall.Cafe.json", "Wall.Dong_Jing.json"],
+            ),
+            (
+                FileNameEncoding::PercentEncode,
+                ["Caf%C3%A9.ttf", "%E6%9D%B1%E4%BA%AC.ttf"],
+                ["Wall.%E6%9D%B1%E4%BA%AC.json", "Wall.Caf%C3%A9.json"],
+            ),
+        ] {
+            let expanded_dir = dir.join(format!("{:?}", encoding));
+            std::fs::create_dir(&expanded_dir)?;
+            let options = WriteOptions {
+                file_name_encoding: encoding,
+                ..Default::default()
+            };
+            write_with_options(&vpx, &expanded_dir, &options)?;
+
+            let mut expected_fonts = fonts.to_vec();
+            expected_fonts.sort();
+            assert_eq!(file_names(&expanded_dir.join("fonts"))?, expected_fonts);
+            let gameitem_files: Vec<String> = file_names(&expanded_dir.join("gameitems"))?
+                .into_iter()
+                .filter(|name| name.starts_with("Wall."))
+                .collect();
+            assert_eq!(gameitem_files, walls);
+
+            let read_vpx = read(&expanded_dir)?;
+            assert_eq!(read_vpx.gameitems, vpx.gameitems);
+            assert_eq!(read_vpx.fonts, vpx.fonts);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_names_unique_ignoring_case() -> TestResult {
+        let mut vpx = VPX::default();
+        for name in ["Wall", "wall", "wall__1"] {
+            let wall = gameitem::wall::Wall {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            vpx.add_game_item(GameItemEnum::Wall(wall));
+        }
+        for name in ["Image", "image", "image_dedup1"] {
+            vpx.images.push(ImageData {
+                name: name.to_string(),
+                path: format!("{}.png", name),
+                width: 1,
+                height: 1,
+                jpeg: Some(ImageDataJpeg {
+                    path: format!("{}.png", name),
+                    name: name.to_string(),
+                    internal_name: None,
+                    data: vec![1, 2, 3],
+                }),
+                ..Default::default()
+            });
+        }
+        for name in ["Font", "font", "FONT"] {
+            vpx.fonts.push(FontData {
+                name: name.to_string(),
+                path: format!("{}.ttf", name),
+                data: vec![4, 5, 6],
+            });
+        }
+
+        // the same names regardless of the file system the dir is checked out on
+        let dir = testdir!();
+        for expanded_dir in ["first", "second"] {
+            std::fs::create_dir(dir.join(expanded_dir))?;
+            write(&vpx, &dir.join(expanded_dir))?;
+        }
+        let file_names = |expanded_dir: &Path, sub_dir: &str| -> io::Result<Vec<String>> {
+            let mut names = std::fs::read_dir(expanded_dir.join(sub_dir))?
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().to_string()))
+                .collect::<io::Result<Vec<_>>>()?;
+            names.sort();
+            Ok(names)
+        };
+        for sub_dir in ["gameitems", "images", "fonts"] {
+            let names = file_names(&dir.join("first"), sub_dir)?;
+            let lower: HashSet<String> = names.iter().map(|n| n.to_lowercase()).collect();
+            assert_eq!(lower.len(), names.len(), "{:?}", names);
+            assert_eq!(names, file_names(&dir.join("second"), sub_dir)?);
+        }
+        assert_eq!(
+            file_names(&dir.join("first"), "images")?,
+            vec!["Image.png", "image_dedup1.png", "image_dedup1_dedup2.png"]
+        );
+        assert_eq!(
+            file_names(&dir.join("first"), "fonts")?,
+            vec!["FONT_dedup2.ttf", "Font.ttf", "font_dedup1.ttf"]
+        );
+
+        let read_vpx = read(&dir.join("first"))?;
+        assert_eq!(read_vpx.gameitems, vpx.gameitems);
+        assert_eq!(read_vpx.images, vpx.images);
+        assert_eq!(read_vpx.fonts, vpx.fonts);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_and_remove_font() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        vpx.add_font("Digital", vec![1, 2, 3]);
+        write(&vpx, &dir)?;
+
+        add_font(&dir, "Score", &[4, 5])?;
+        add_font(&dir, "digital", &[6])?;
+        let read_vpx = read(&dir)?;
+        let fonts: Vec<(&str, &[u8])> = read_vpx
+            .fonts
+            .iter()
+            .map(|f| (f.name.as_str(), f.data.as_slice()))
+            .collect();
+        assert_eq!(fonts, vec![("Score", &[4, 5][..]), ("digital", &[6][..])]);
+        assert_eq!(read_vpx.gamedata.fonts_size, 2);
+
+        assert!(remove_font(&dir, "SCORE")?);
+        assert!(!remove_font(&dir, "Missing")?);
+        assert!(!dir.join("fonts").join("Score.ttf").exists());
+        let read_vpx = read(&dir)?;
+        assert_eq!(read_vpx.fonts.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_and_unexpected_files() -> TestResult {
+        let dir = testdir!();
+        let mut vpx = VPX::default();
+        for name in ["Wall1", "Wall2"] {
+            vpx.add_game_item(GameItemEnum::Wall(gameitem::wall::Wall {
+                name: name.to_string(),
+                ..Default::default()
+            }));
+        }
+        for name in ["one", "two"] {
+            vpx.images.push(ImageData {
+                name: name.to_string(),
+                path: format!("{}.png", name),
+                width: 1,
+                height: 1,
+                jpeg: Some(ImageDataJpeg {
+                    path: format!("{}.png", name),
+                    name: name.to_string(),
+                    internal_name: None,
+                    data: vec![1, 2, 3],
+                }),
+                ..Default::default()
+            });
+        }
+        write(&vpx, &dir)?;
+
+        std::fs::remove_file(dir.join("images").join("one.png"))?;
+        std::fs::remove_file(dir.join("gameitems").join("Wall.Wall2.json"))?;
+        std::fs::write(dir.join("images").join("renamed.png"), [1, 2, 3])?;
+
+        let error = read(&dir).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            file_report(&error),
+            Some(&FileReport {
+                missing: vec![
+                    Path::new("gameitems").join("Wall.Wall2.json"),
+                    Path::new("images").join("one.png"),
+                ],
+                unexpected: vec![Path::new("images").join("renamed.png")],
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_gameitem_type_field() -> TestResult {
+        let wall = GameItemEnum::Wall(gameitem::wall::Wall {
+            name: "Wall1".to_string(),
+            ..Default::default()
+        });
+        let json = gameitem_to_json(&wall)?;
+        assert_eq!(json.as_object().unwrap().keys().next().unwrap(), "type");
+        assert_eq!(json["type"], "Wall");
+        assert_eq!(json["name"], "Wall1");
+        assert_eq!(gameitem_from_json(json)?, wall);
+
+        // files written before the type field was added
+        let legacy = serde_json::to_value(&wall)?;
+        assert!(legacy.get("Wall").is_some());
+        assert_eq!(gameitem_from_json(legacy)?, wall);
+
+        let generic = GameItemEnum::Generic(
+            100,
+            gameitem::generic::Generic {
+                name: "Future".to_string(),
+                data: {
+                    let mut writer = BiffWriter::new();
+                    writer.write_tagged_wide_string("NAME", "Future");
+                    writer.write_tagged_data("DATA", &[1, 2]);
+                    writer.close(true);
+                    writer.get_data().to_vec()
+                },
+            },
+        );
+        let json = gameitem_to_json(&generic)?;
+        assert_eq!(json["type"], "Generic");
+        assert_eq!(json["value"][0], 100);
+        assert_eq!(gameitem_from_json(json)?, generic);
+
+        let invalid = serde_json::json!({"type": 1, "name": "Wall1"});
+        assert!(gameitem_from_json(invalid).is_err());
+        Ok(())
     }
 }
@@ -16,6 +16,9 @@ pub struct FontData {
 pub(crate) struct FontDataJson {
     name: String,
     path: String,
+    // in case we have a duplicate name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name_dedup: Option<String>,
 }
 
 impl FontDataJson {
@@ -23,6 +26,7 @@ impl FontDataJson {
         Self {
             name: font_data.name.clone(),
             path: font_data.path.clone(),
+            name_dedup: None,
         }
     }
     pub fn to_font_data(&self) -> FontData {
@@ -48,7 +52,7 @@ impl fmt::Debug for FontData {
 impl FontData {
     pub(crate) fn ext(&self) -> String {
         // TODO we might want to also check the jpeg fsPath
-        match self.path.split('.').last() {
+        match self.path.split('.').next_back() {
             Some(ext) => ext.to_string(),
             None => "bin".to_string(),
         }
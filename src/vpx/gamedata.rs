@@ -5,8 +5,9 @@ use super::{
     model::StringWithEncoding,
     version::Version,
 };
-use crate::vpx::biff::{BiffRead, BiffWrite};
+use crate::vpx::biff::{BiffRead, BiffWrite, CheckedFrom};
 use crate::vpx::color::Color;
+use crate::vpx::image::ImageData;
 use crate::vpx::json::F32WithNanInf;
 use crate::vpx::material::{Material, SaveMaterial, SavePhysicsMaterial};
 use crate::vpx::math::{dequantize_u8, quantize_u8};
@@ -14,6 +15,9 @@ use crate::vpx::renderprobe::RenderProbeWithGarbage;
 use bytes::{Buf, BufMut, BytesMut};
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
 
 #[derive(Debug, PartialEq, Dummy, Clone, Copy)]
 pub enum ViewLayoutMode {
@@ -25,17 +29,25 @@ pub enum ViewLayoutMode {
     Window = 2,
 }
 
-impl From<u32> for ViewLayoutMode {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for ViewLayoutMode {
+    const FALLBACK: Self = ViewLayoutMode::Legacy;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => ViewLayoutMode::Legacy,
-            1 => ViewLayoutMode::Camera,
-            2 => ViewLayoutMode::Window,
-            _ => panic!("Invalid ViewLayoutMode {}", value),
+            0 => Ok(ViewLayoutMode::Legacy),
+            1 => Ok(ViewLayoutMode::Camera),
+            2 => Ok(ViewLayoutMode::Window),
+            _ => Err(format!("Invalid ViewLayoutMode {}", value)),
         }
     }
 }
 
+impl From<u32> for ViewLayoutMode {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&ViewLayoutMode> for u32 {
     fn from(value: &ViewLayoutMode) -> Self {
         match value {
@@ -111,6 +123,98 @@ impl<'de> Deserialize<'de> for ViewLayoutMode {
     }
 }
 
+/// One of the three view setups of a table, see [`GameData::view_settings`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ViewSetupId {
+    Desktop = 0,
+    Fullscreen = 1,
+    /// Full single screen, the playfield and backglass on a single screen in portrait
+    FullSingleScreen = 2,
+}
+
+impl ViewSetupId {
+    /// In the order Visual Pinball stores them
+    pub const ALL: [ViewSetupId; 3] = [
+        ViewSetupId::Desktop,
+        ViewSetupId::Fullscreen,
+        ViewSetupId::FullSingleScreen,
+    ];
+}
+
+/// The camera settings of one view, the `bg_*` fields of [`GameData`] for a [`ViewSetupId`]
+///
+/// The fields marked with 10.8 are `None` for tables saved by older versions.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ViewSettings {
+    /// 10.8
+    pub mode: Option<ViewLayoutMode>,
+    /// Viewport rotation in degrees
+    pub rotation: f32,
+    /// Camera inclination in degrees for legacy mode, otherwise the look at point as a
+    /// percentage of the table height
+    pub inclination: f32,
+    /// Legacy: A skewing angle that deforms the table to make it look 'good'
+    pub layback: f32,
+    /// Field of view in degrees
+    pub fov: f32,
+    /// View position, relative to the table bounds for legacy mode, otherwise relative to the
+    /// bottom center of the table
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub offset_z: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub scale_z: f32,
+    /// 10.8, camera and window: horizontal frustum offset
+    pub view_horizontal_offset: Option<f32>,
+    /// 10.8, camera and window: vertical frustum offset
+    pub view_vertical_offset: Option<f32>,
+    /// 10.8, window: upper window border offset from the left and right table bounds
+    pub window_top_x_offset: Option<f32>,
+    /// 10.8, window: upper window border Y coordinate, relative to the table top
+    pub window_top_y_offset: Option<f32>,
+    /// 10.8, window: upper window border Z coordinate, relative to the playfield
+    pub window_top_z_offset: Option<f32>,
+    /// 10.8, window: lower window border offset from the left and right table bounds
+    pub window_bottom_x_offset: Option<f32>,
+    /// 10.8, window: lower window border Y coordinate, relative to the table bottom
+    pub window_bottom_y_offset: Option<f32>,
+    /// 10.8, window: lower window border Z coordinate, relative to the playfield
+    pub window_bottom_z_offset: Option<f32>,
+}
+
+impl ViewSettings {
+    pub fn new() -> Self {
+        ViewSettings {
+            mode: None,
+            rotation: 0.0,
+            inclination: 0.0,
+            layback: 0.0,
+            fov: 45.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            offset_z: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            scale_z: 1.0,
+            view_horizontal_offset: None,
+            view_vertical_offset: None,
+            window_top_x_offset: None,
+            window_top_y_offset: None,
+            window_top_z_offset: None,
+            window_bottom_x_offset: None,
+            window_bottom_y_offset: None,
+            window_bottom_z_offset: None,
+        }
+    }
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // TODO switch to a array of 3 view modes like in the original code
 #[derive(Debug, PartialEq)]
 pub struct ViewSetup {
@@ -273,7 +377,342 @@ impl<'de> Deserialize<'de> for ToneMapper {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// How the ball decal image is applied to the ball
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BallDecalMode {
+    /// The decal is used as scratches on the ball
+    Scratches,
+    /// The decal is used as a logo on the ball
+    Logo,
+}
+
+/// A table setting that can also be left to the setting of the player
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableOverride {
+    /// Stored as -1
+    UseGlobal,
+    Off,
+    On,
+}
+
+impl From<i32> for TableOverride {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => TableOverride::Off,
+            value if value < 0 => TableOverride::UseGlobal,
+            _ => TableOverride::On,
+        }
+    }
+}
+
+impl From<TableOverride> for i32 {
+    fn from(value: TableOverride) -> Self {
+        match value {
+            TableOverride::UseGlobal => -1,
+            TableOverride::Off => 0,
+            TableOverride::On => 1,
+        }
+    }
+}
+
+/// How the balls are rendered, see [`GameData::ball_settings`]
+///
+/// Settings missing in older tables get the Visual Pinball defaults.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BallSettings {
+    pub image: Option<String>,
+    pub decal_image: Option<String>,
+    pub decal_mode: BallDecalMode,
+    /// Map the image on the ball as a sphere instead of as an environment reflection
+    pub spherical_mapping: bool,
+    pub reflection: TableOverride,
+    /// Strength of the ball reflection on the playfield
+    pub playfield_reflection_strength: f32,
+    pub trail: TableOverride,
+    /// From 0 to 1, stored with 8 bits of precision
+    pub trail_strength: f32,
+    /// Scales the intensity of the reflections of bulb lights on the ball
+    pub bulb_intensity_scale: f32,
+}
+
+impl BallSettings {
+    pub const DEFAULT_SPHERICAL_MAPPING: bool = true;
+    pub const DEFAULT_PLAYFIELD_REFLECTION_STRENGTH: f32 = 1.0;
+    pub const DEFAULT_TRAIL_STRENGTH: f32 = 0.4901961;
+    pub const DEFAULT_BULB_INTENSITY_SCALE: f32 = 1.0;
+}
+
+impl Default for BallSettings {
+    fn default() -> Self {
+        BallSettings {
+            image: None,
+            decal_image: None,
+            decal_mode: BallDecalMode::Scratches,
+            spherical_mapping: Self::DEFAULT_SPHERICAL_MAPPING,
+            reflection: TableOverride::UseGlobal,
+            playfield_reflection_strength: Self::DEFAULT_PLAYFIELD_REFLECTION_STRENGTH,
+            trail: TableOverride::UseGlobal,
+            trail_strength: Self::DEFAULT_TRAIL_STRENGTH,
+            bulb_intensity_scale: Self::DEFAULT_BULB_INTENSITY_SCALE,
+        }
+    }
+}
+
+/// The table level settings that reference an image
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TableImage {
+    Playfield,
+    ColorGrade,
+    Ball,
+    BallDecal,
+    Environment,
+}
+
+impl TableImage {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TableImage::Playfield => "playfield image",
+            TableImage::ColorGrade => "color grade",
+            TableImage::Ball => "ball image",
+            TableImage::BallDecal => "ball decal",
+            TableImage::Environment => "environment image",
+        }
+    }
+}
+
+/// The playfield rectangle in VPU, see [`GameData::dimensions`]
+///
+/// Visual Pinball does not require the rectangle to start at the origin, although `left` and
+/// `top` are 0 for almost every table. The mesh builders in [`crate::vpx::mesh`] use it in two
+/// ways that only differ for tables with another origin: the playfield texture is stretched
+/// over the rectangle, see [`TableDimensions::uv`], while walls and world aligned ramps scale
+/// the positions by the table size without moving them, see [`TableDimensions::world_uv`].
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TableDimensions {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl TableDimensions {
+    pub fn from_gamedata(gamedata: &GameData) -> Self {
+        TableDimensions {
+            left: gamedata.left,
+            top: gamedata.top,
+            right: gamedata.right,
+            bottom: gamedata.bottom,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left && x <= self.right && y >= self.top && y <= self.bottom
+    }
+
+    /// Texture coordinates stretching an image over the rectangle, like the playfield
+    pub fn uv(&self, x: f32, y: f32) -> [f32; 2] {
+        [
+            ratio(x - self.left, self.width()),
+            ratio(y - self.top, self.height()),
+        ]
+    }
+
+    /// Texture coordinates for world aligned images, like on wall tops and flat ramps.
+    ///
+    /// Like Visual Pinball the origin is not subtracted, only the size is taken into account.
+    pub fn world_uv(&self, x: f32, y: f32) -> [f32; 2] {
+        [ratio(x, self.width()), ratio(y, self.height())]
+    }
+}
+
+/// 0 for an empty table instead of infinity or NaN
+fn ratio(value: f32, size: f32) -> f32 {
+    if size != 0.0 {
+        value / size
+    } else {
+        0.0
+    }
+}
+
+/// The playfield slope and nudge settings of a table, see [`GameData::tilt_settings`]
+///
+/// The tilt sensitivity itself is a setting of the player, not of the table.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TiltSettings {
+    /// Playfield slope in degrees at difficulty 0
+    pub slope_min: f32,
+    /// Playfield slope in degrees at difficulty 1
+    pub slope_max: f32,
+    /// From 0 to 1, the editor shows this as a percentage.
+    ///
+    /// Picks the slope between min and max and also scales the flipper and scatter settings.
+    pub difficulty: f32,
+    /// Seconds it takes the table to settle after a nudge
+    pub nudge_time: f32,
+    /// Strength of a mechanical plunger in percent, 100 is unchanged
+    pub plunger_normalize: u32,
+    /// Filter the readings of a mechanical plunger
+    pub plunger_filter: bool,
+}
+
+impl TiltSettings {
+    /// Slopes outside this range are not used by real tables
+    pub const SLOPE_RANGE: RangeInclusive<f32> = 0.0..=20.0;
+    pub const DIFFICULTY_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+    /// Longer nudge times make the table unplayable after a nudge
+    pub const NUDGE_TIME_RANGE: RangeInclusive<f32> = 0.0..=20.0;
+    pub const PLUNGER_NORMALIZE_RANGE: RangeInclusive<u32> = 0..=200;
+
+    /// The slope Visual Pinball uses, between min and max depending on the difficulty
+    pub fn effective_slope(&self) -> f32 {
+        self.slope_min + (self.slope_max - self.slope_min) * self.difficulty
+    }
+
+    /// Checks the settings against the documented ranges
+    pub fn validate(&self) -> Vec<TiltSettingsIssue> {
+        let mut issues = Vec::new();
+        let mut check = |setting: &'static str, value: f32, range: &RangeInclusive<f32>| {
+            if !range.contains(&value) {
+                issues.push(TiltSettingsIssue::OutOfRange {
+                    setting,
+                    value,
+                    min: *range.start(),
+                    max: *range.end(),
+                });
+            }
+        };
+        check("slope_min", self.slope_min, &Self::SLOPE_RANGE);
+        check("slope_max", self.slope_max, &Self::SLOPE_RANGE);
+        check("difficulty", self.difficulty, &Self::DIFFICULTY_RANGE);
+        check("nudge_time", self.nudge_time, &Self::NUDGE_TIME_RANGE);
+        check(
+            "plunger_normalize",
+            self.plunger_normalize as f32,
+            &((*Self::PLUNGER_NORMALIZE_RANGE.start() as f32)
+                ..=(*Self::PLUNGER_NORMALIZE_RANGE.end() as f32)),
+        );
+        if self.slope_min > self.slope_max {
+            issues.push(TiltSettingsIssue::SlopeMinAboveMax {
+                min: self.slope_min,
+                max: self.slope_max,
+            });
+        }
+        issues
+    }
+}
+
+/// The scene lighting of a table, see [`GameData::lighting_settings`]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LightingSettings {
+    /// Ambient light added to every material, best kept black
+    pub ambient: Color,
+    /// Color of the two scene lights
+    pub emission: Color,
+    /// Height of the scene lights above the playfield in VPU
+    pub light_height: f32,
+    /// Range of the scene lights in VPU
+    pub light_range: f32,
+    /// Intensity of the scene lights
+    pub light_emission_scale: f32,
+    /// Intensity of the environment image lighting
+    pub env_emission_scale: f32,
+    /// The day/night setting, from 0 for night to 1 for day, scales all scene lighting
+    pub day_night: f32,
+    /// Strength of the ambient occlusion
+    pub ao_scale: f32,
+    /// Use the day/night setting of the table instead of the one of the player
+    pub overwrite_global_day_night: bool,
+}
+
+impl LightingSettings {
+    /// The day/night value as shown on the 0 to 100 slider of the editor
+    pub fn day_night_slider(&self) -> f32 {
+        to_slider(self.day_night)
+    }
+
+    /// Sets the day/night value from the 0 to 100 slider of the editor, values outside the
+    /// slider range are clamped
+    pub fn set_day_night_slider(&mut self, slider: f32) {
+        self.day_night = from_slider(slider);
+    }
+
+    /// The environment emission as shown on the 0 to 100 slider of the editor
+    pub fn env_emission_slider(&self) -> f32 {
+        to_slider(self.env_emission_scale)
+    }
+
+    /// Sets the environment emission from the 0 to 100 slider of the editor, values outside
+    /// the slider range are clamped
+    pub fn set_env_emission_slider(&mut self, slider: f32) {
+        self.env_emission_scale = from_slider(slider);
+    }
+}
+
+/// A stored scale as shown on a 0 to 100 slider of the editor
+pub fn to_slider(scale: f32) -> f32 {
+    scale * 100.0
+}
+
+/// The stored scale for a position of a 0 to 100 slider of the editor
+pub fn from_slider(slider: f32) -> f32 {
+    slider.clamp(0.0, 100.0) / 100.0
+}
+
+/// A problem found by [`TiltSettings::validate`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum TiltSettingsIssue {
+    OutOfRange {
+        setting: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    SlopeMinAboveMax {
+        min: f32,
+        max: f32,
+    },
+}
+
+impl fmt::Display for TiltSettingsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TiltSettingsIssue::OutOfRange {
+                setting,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "{} is {}, expected between {} and {}",
+                setting, value, min, max
+            ),
+            TiltSettingsIssue::SlopeMinAboveMax { min, max } => write!(
+                f,
+                "Minimum slope {} is larger than the maximum slope {}",
+                min, max
+            ),
+        }
+    }
+}
+
+/// A table level image reference that does not point to an existing image
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MissingImage {
+    pub usage: TableImage,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct GameData {
     pub left: f32,   // LEFT 1
     pub top: f32,    // TOPX 2
@@ -282,7 +721,8 @@ pub struct GameData {
     /// CLMO
     /// During the 10.8.0 development cycle, this field was added but later again removed
     /// Has meanwhile been replaced by the new [`GameData::bg_view_mode_desktop`],
-    /// [`GameData::bg_view_mode_fullscreen`] and [`GameData::bg_view_mode_ffs`] fields
+    /// [`GameData::bg_view_mode_fullscreen`] and
+    /// [`GameData::bg_view_mode_full_single_screen`] fields
     /// See [the related commit](https://github.com/vpinball/vpinball/commit/5087b3c51b99676f91b02ee4b0c0af4b89b6afda)
     /// CLM_RELATIVE = 0, // All tables before 10.8 used a camera position relative to a fitting of a set of bounding vertices (not all parts)
     /// CLM_ABSOLUTE = 1 // Position camera relative to the bottom center of the table
@@ -939,6 +1379,207 @@ impl GameDataJson {
 }
 
 impl GameData {
+    /// The playfield rectangle from [`GameData::left`], [`GameData::top`],
+    /// [`GameData::right`] and [`GameData::bottom`]
+    pub fn dimensions(&self) -> TableDimensions {
+        TableDimensions::from_gamedata(self)
+    }
+
+    /// The view setup for desktop, fullscreen or full single screen.
+    ///
+    /// Tables saved before full single screen was added have no values for it, those are
+    /// taken from the desktop view.
+    pub fn view_settings(&self, id: ViewSetupId) -> ViewSettings {
+        match id {
+            ViewSetupId::Desktop => ViewSettings {
+                mode: self.bg_view_mode_desktop,
+                rotation: self.bg_rotation_desktop,
+                inclination: self.bg_inclination_desktop,
+                layback: self.bg_layback_desktop,
+                fov: self.bg_fov_desktop,
+                offset_x: self.bg_offset_x_desktop,
+                offset_y: self.bg_offset_y_desktop,
+                offset_z: self.bg_offset_z_desktop,
+                scale_x: self.bg_scale_x_desktop,
+                scale_y: self.bg_scale_y_desktop,
+                scale_z: self.bg_scale_z_desktop,
+                view_horizontal_offset: self.bg_view_horizontal_offset_desktop,
+                view_vertical_offset: self.bg_view_vertical_offset_desktop,
+                window_top_x_offset: self.bg_window_top_x_offset_desktop,
+                window_top_y_offset: self.bg_window_top_y_offset_desktop,
+                window_top_z_offset: self.bg_window_top_z_offset_desktop,
+                window_bottom_x_offset: self.bg_window_bottom_x_offset_desktop,
+                window_bottom_y_offset: self.bg_window_bottom_y_offset_desktop,
+                window_bottom_z_offset: self.bg_window_bottom_z_offset_desktop,
+            },
+            ViewSetupId::Fullscreen => ViewSettings {
+                mode: self.bg_view_mode_fullscreen,
+                rotation: self.bg_rotation_fullscreen,
+                inclination: self.bg_inclination_fullscreen,
+                layback: self.bg_layback_fullscreen,
+                fov: self.bg_fov_fullscreen,
+                offset_x: self.bg_offset_x_fullscreen,
+                offset_y: self.bg_offset_y_fullscreen,
+                offset_z: self.bg_offset_z_fullscreen,
+                scale_x: self.bg_scale_x_fullscreen,
+                scale_y: self.bg_scale_y_fullscreen,
+                scale_z: self.bg_scale_z_fullscreen,
+                view_horizontal_offset: self.bg_view_horizontal_offset_fullscreen,
+                view_vertical_offset: self.bg_view_vertical_offset_fullscreen,
+                window_top_x_offset: self.bg_window_top_x_offset_fullscreen,
+                window_top_y_offset: self.bg_window_top_y_offset_fullscreen,
+                window_top_z_offset: self.bg_window_top_z_offset_fullscreen,
+                window_bottom_x_offset: self.bg_window_bottom_x_offset_fullscreen,
+                window_bottom_y_offset: self.bg_window_bottom_y_offset_fullscreen,
+                window_bottom_z_offset: self.bg_window_bottom_z_offset_fullscreen,
+            },
+            ViewSetupId::FullSingleScreen => {
+                let desktop = self.view_settings(ViewSetupId::Desktop);
+                ViewSettings {
+                    mode: self.bg_view_mode_full_single_screen,
+                    rotation: self
+                        .bg_rotation_full_single_screen
+                        .unwrap_or(desktop.rotation),
+                    inclination: self
+                        .bg_inclination_full_single_screen
+                        .unwrap_or(desktop.inclination),
+                    layback: self
+                        .bg_layback_full_single_screen
+                        .unwrap_or(desktop.layback),
+                    fov: self.bg_fov_full_single_screen.unwrap_or(desktop.fov),
+                    offset_x: self
+                        .bg_offset_x_full_single_screen
+                        .unwrap_or(desktop.offset_x),
+                    offset_y: self
+                        .bg_offset_y_full_single_screen
+                        .unwrap_or(desktop.offset_y),
+                    offset_z: self
+                        .bg_offset_z_full_single_screen
+                        .unwrap_or(desktop.offset_z),
+                    scale_x: self
+                        .bg_scale_x_full_single_screen
+                        .unwrap_or(desktop.scale_x),
+                    scale_y: self
+                        .bg_scale_y_full_single_screen
+                        .unwrap_or(desktop.scale_y),
+                    scale_z: self
+                        .bg_scale_z_full_single_screen
+                        .unwrap_or(desktop.scale_z),
+                    view_horizontal_offset: self.bg_view_horizontal_offset_full_single_screen,
+                    view_vertical_offset: self.bg_view_vertical_offset_full_single_screen,
+                    window_top_x_offset: self.bg_window_top_x_offset_full_single_screen,
+                    window_top_y_offset: self.bg_window_top_y_offset_full_single_screen,
+                    window_top_z_offset: self.bg_window_top_z_offset_full_single_screen,
+                    window_bottom_x_offset: self.bg_window_bottom_x_offset_full_single_screen,
+                    window_bottom_y_offset: self.bg_window_bottom_y_offset_full_single_screen,
+                    window_bottom_z_offset: self.bg_window_bottom_z_offset_full_single_screen,
+                }
+            }
+        }
+    }
+
+    /// All three view setups in the order of [`ViewSetupId::ALL`]
+    pub fn all_view_settings(&self) -> [ViewSettings; 3] {
+        ViewSetupId::ALL.map(|id| self.view_settings(id))
+    }
+
+    pub fn set_view_settings(&mut self, id: ViewSetupId, settings: &ViewSettings) {
+        match id {
+            ViewSetupId::Desktop => {
+                self.bg_view_mode_desktop = settings.mode;
+                self.bg_rotation_desktop = settings.rotation;
+                self.bg_inclination_desktop = settings.inclination;
+                self.bg_layback_desktop = settings.layback;
+                self.bg_fov_desktop = settings.fov;
+                self.bg_offset_x_desktop = settings.offset_x;
+                self.bg_offset_y_desktop = settings.offset_y;
+                self.bg_offset_z_desktop = settings.offset_z;
+                self.bg_scale_x_desktop = settings.scale_x;
+                self.bg_scale_y_desktop = settings.scale_y;
+                self.bg_scale_z_desktop = settings.scale_z;
+                self.bg_view_horizontal_offset_desktop = settings.view_horizontal_offset;
+                self.bg_view_vertical_offset_desktop = settings.view_vertical_offset;
+                self.bg_window_top_x_offset_desktop = settings.window_top_x_offset;
+                self.bg_window_top_y_offset_desktop = settings.window_top_y_offset;
+                self.bg_window_top_z_offset_desktop = settings.window_top_z_offset;
+                self.bg_window_bottom_x_offset_desktop = settings.window_bottom_x_offset;
+                self.bg_window_bottom_y_offset_desktop = settings.window_bottom_y_offset;
+                self.bg_window_bottom_z_offset_desktop = settings.window_bottom_z_offset;
+            }
+            ViewSetupId::Fullscreen => {
+                self.bg_view_mode_fullscreen = settings.mode;
+                self.bg_rotation_fullscreen = settings.rotation;
+                self.bg_inclination_fullscreen = settings.inclination;
+                self.bg_layback_fullscreen = settings.layback;
+                self.bg_fov_fullscreen = settings.fov;
+                self.bg_offset_x_fullscreen = settings.offset_x;
+                self.bg_offset_y_fullscreen = settings.offset_y;
+                self.bg_offset_z_fullscreen = settings.offset_z;
+                self.bg_scale_x_fullscreen = settings.scale_x;
+                self.bg_scale_y_fullscreen = settings.scale_y;
+                self.bg_scale_z_fullscreen = settings.scale_z;
+                self.bg_view_horizontal_offset_fullscreen = settings.view_horizontal_offset;
+                self.bg_view_vertical_offset_fullscreen = settings.view_vertical_offset;
+                self.bg_window_top_x_offset_fullscreen = settings.window_top_x_offset;
+                self.bg_window_top_y_offset_fullscreen = settings.window_top_y_offset;
+                self.bg_window_top_z_offset_fullscreen = settings.window_top_z_offset;
+                self.bg_window_bottom_x_offset_fullscreen = settings.window_bottom_x_offset;
+                self.bg_window_bottom_y_offset_fullscreen = settings.window_bottom_y_offset;
+                self.bg_window_bottom_z_offset_fullscreen = settings.window_bottom_z_offset;
+            }
+            ViewSetupId::FullSingleScreen => {
+                self.bg_view_mode_full_single_screen = settings.mode;
+                self.bg_rotation_full_single_screen = Some(settings.rotation);
+                self.bg_inclination_full_single_screen = Some(settings.inclination);
+                self.bg_layback_full_single_screen = Some(settings.layback);
+                self.bg_fov_full_single_screen = Some(settings.fov);
+                self.bg_offset_x_full_single_screen = Some(settings.offset_x);
+                self.bg_offset_y_full_single_screen = Some(settings.offset_y);
+                self.bg_offset_z_full_single_screen = Some(settings.offset_z);
+                self.bg_scale_x_full_single_screen = Some(settings.scale_x);
+                self.bg_scale_y_full_single_screen = Some(settings.scale_y);
+                self.bg_scale_z_full_single_screen = Some(settings.scale_z);
+                self.bg_view_horizontal_offset_full_single_screen = settings.view_horizontal_offset;
+                self.bg_view_vertical_offset_full_single_screen = settings.view_vertical_offset;
+                self.bg_window_top_x_offset_full_single_screen = settings.window_top_x_offset;
+                self.bg_window_top_y_offset_full_single_screen = settings.window_top_y_offset;
+                self.bg_window_top_z_offset_full_single_screen = settings.window_top_z_offset;
+                self.bg_window_bottom_x_offset_full_single_screen = settings.window_bottom_x_offset;
+                self.bg_window_bottom_y_offset_full_single_screen = settings.window_bottom_y_offset;
+                self.bg_window_bottom_z_offset_full_single_screen = settings.window_bottom_z_offset;
+            }
+        }
+    }
+
+    /// Replaces the view setup `to` with a copy of `from`, eg to start a full single screen
+    /// setup from the desktop one
+    pub fn copy_view_settings(&mut self, from: ViewSetupId, to: ViewSetupId) {
+        let settings = self.view_settings(from);
+        self.set_view_settings(to, &settings);
+    }
+
+    /// The slope, difficulty, nudge and plunger settings
+    pub fn tilt_settings(&self) -> TiltSettings {
+        TiltSettings {
+            slope_min: self.angle_tilt_min,
+            slope_max: self.angle_tilt_max,
+            difficulty: self.global_difficulty,
+            nudge_time: self.nudge_time,
+            plunger_normalize: self.plunger_normalize,
+            plunger_filter: self.plunger_filter,
+        }
+    }
+
+    /// Stores the settings as they are, use [`TiltSettings::validate`] to check them first
+    pub fn set_tilt_settings(&mut self, settings: &TiltSettings) {
+        self.angle_tilt_min = settings.slope_min;
+        self.angle_tilt_max = settings.slope_max;
+        self.global_difficulty = settings.difficulty;
+        self.nudge_time = settings.nudge_time;
+        self.plunger_normalize = settings.plunger_normalize;
+        self.plunger_filter = settings.plunger_filter;
+    }
+
     pub fn set_code(&mut self, script: String) {
         self.code = StringWithEncoding::new(script);
     }
@@ -950,6 +1591,207 @@ impl GameData {
     pub fn set_ball_trail_strength(&mut self, value: f32) {
         self.ball_trail_strength = Some(quantize_u8(8, value) as u32);
     }
+
+    pub fn lighting_settings(&self) -> LightingSettings {
+        LightingSettings {
+            ambient: self.light_ambient,
+            emission: self.light0_emission,
+            light_height: self.light_height,
+            light_range: self.light_range,
+            light_emission_scale: self.light_emission_scale,
+            env_emission_scale: self.env_emission_scale,
+            day_night: self.global_emission_scale,
+            ao_scale: self.ao_scale,
+            overwrite_global_day_night: self.overwrite_global_day_night.unwrap_or(false),
+        }
+    }
+
+    pub fn set_lighting_settings(&mut self, settings: &LightingSettings) {
+        self.light_ambient = settings.ambient;
+        self.light0_emission = settings.emission;
+        self.light_height = settings.light_height;
+        self.light_range = settings.light_range;
+        self.light_emission_scale = settings.light_emission_scale;
+        self.env_emission_scale = settings.env_emission_scale;
+        self.global_emission_scale = settings.day_night;
+        self.ao_scale = settings.ao_scale;
+        set_optional(
+            &mut self.overwrite_global_day_night,
+            settings.overwrite_global_day_night,
+            false,
+        );
+    }
+
+    pub fn ball_settings(&self) -> BallSettings {
+        let defaults = BallSettings::default();
+        BallSettings {
+            image: self.get_ball_image().map(str::to_string),
+            decal_image: self.get_ball_decal_image().map(str::to_string),
+            decal_mode: self.get_ball_decal_mode(),
+            spherical_mapping: self
+                .ball_spherical_mapping
+                .unwrap_or(defaults.spherical_mapping),
+            reflection: self
+                .use_reflection_for_balls
+                .map(TableOverride::from)
+                .unwrap_or(defaults.reflection),
+            playfield_reflection_strength: self
+                .ball_playfield_reflection_strength
+                .unwrap_or(defaults.playfield_reflection_strength),
+            trail: self
+                .use_trail_for_balls
+                .map(TableOverride::from)
+                .unwrap_or(defaults.trail),
+            trail_strength: self
+                .get_ball_trail_strength()
+                .unwrap_or(defaults.trail_strength),
+            bulb_intensity_scale: self
+                .default_bulb_intensity_scale_on_ball
+                .unwrap_or(defaults.bulb_intensity_scale),
+        }
+    }
+
+    /// Optional fields that are missing stay missing when they get the default value, so
+    /// writing back unchanged settings does not change the table.
+    pub fn set_ball_settings(&mut self, settings: &BallSettings) {
+        let defaults = BallSettings::default();
+        self.set_ball_image(settings.image.clone());
+        self.set_ball_decal_image(settings.decal_image.clone());
+        self.set_ball_decal_mode(settings.decal_mode);
+        set_optional(
+            &mut self.ball_spherical_mapping,
+            settings.spherical_mapping,
+            defaults.spherical_mapping,
+        );
+        set_optional(
+            &mut self.use_reflection_for_balls,
+            settings.reflection.into(),
+            defaults.reflection.into(),
+        );
+        set_optional(
+            &mut self.ball_playfield_reflection_strength,
+            settings.playfield_reflection_strength,
+            defaults.playfield_reflection_strength,
+        );
+        set_optional(
+            &mut self.use_trail_for_balls,
+            settings.trail.into(),
+            defaults.trail.into(),
+        );
+        if self.ball_trail_strength.is_some() || settings.trail_strength != defaults.trail_strength
+        {
+            self.set_ball_trail_strength(settings.trail_strength);
+        }
+        set_optional(
+            &mut self.default_bulb_intensity_scale_on_ball,
+            settings.bulb_intensity_scale,
+            defaults.bulb_intensity_scale,
+        );
+    }
+
+    pub fn get_playfield_image(&self) -> Option<&str> {
+        non_empty(&self.image)
+    }
+
+    pub fn set_playfield_image(&mut self, name: Option<String>) {
+        self.image = name.unwrap_or_default();
+    }
+
+    /// The color grade lookup table (LUT) image
+    pub fn get_color_grade_image(&self) -> Option<&str> {
+        non_empty(&self.image_color_grade)
+    }
+
+    pub fn set_color_grade_image(&mut self, name: Option<String>) {
+        self.image_color_grade = name.unwrap_or_default();
+    }
+
+    pub fn get_ball_image(&self) -> Option<&str> {
+        non_empty(&self.ball_image)
+    }
+
+    pub fn set_ball_image(&mut self, name: Option<String>) {
+        self.ball_image = name.unwrap_or_default();
+    }
+
+    pub fn get_ball_decal_image(&self) -> Option<&str> {
+        non_empty(&self.ball_image_front)
+    }
+
+    pub fn set_ball_decal_image(&mut self, name: Option<String>) {
+        self.ball_image_front = name.unwrap_or_default();
+    }
+
+    pub fn get_ball_decal_mode(&self) -> BallDecalMode {
+        if self.ball_decal_mode {
+            BallDecalMode::Logo
+        } else {
+            BallDecalMode::Scratches
+        }
+    }
+
+    pub fn set_ball_decal_mode(&mut self, mode: BallDecalMode) {
+        self.ball_decal_mode = mode == BallDecalMode::Logo;
+    }
+
+    pub fn get_env_image(&self) -> Option<&str> {
+        self.env_image.as_deref().and_then(non_empty)
+    }
+
+    pub fn set_env_image(&mut self, name: Option<String>) {
+        self.env_image = name;
+    }
+
+    /// The images referenced by the table settings, empty references are left out
+    pub fn table_images(&self) -> Vec<(TableImage, &str)> {
+        [
+            (TableImage::Playfield, self.get_playfield_image()),
+            (TableImage::ColorGrade, self.get_color_grade_image()),
+            (TableImage::Ball, self.get_ball_image()),
+            (TableImage::BallDecal, self.get_ball_decal_image()),
+            (TableImage::Environment, self.get_env_image()),
+        ]
+        .into_iter()
+        .filter_map(|(usage, name)| name.map(|name| (usage, name)))
+        .collect()
+    }
+
+    /// Names of the images used by the table settings
+    pub fn referenced_images(&self) -> Vec<&str> {
+        self.table_images()
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect()
+    }
+
+    /// Checks that every image referenced by the table settings exists in `images`.
+    ///
+    /// Names are matched case-insensitive, like VPinball does.
+    pub fn missing_images(&self, images: &[ImageData]) -> Vec<MissingImage> {
+        self.table_images()
+            .into_iter()
+            .filter(|(_, name)| !images.iter().any(|i| i.name.eq_ignore_ascii_case(name)))
+            .map(|(usage, name)| MissingImage {
+                usage,
+                name: name.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Leaves a missing value missing when it is set to the default
+fn set_optional<T: PartialEq>(field: &mut Option<T>, value: T, default: T) {
+    if field.is_some() || value != default {
+        *field = Some(value);
+    }
+}
+
+fn non_empty(name: &str) -> Option<&str> {
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
 }
 
 impl Default for GameData {
@@ -1450,7 +2292,18 @@ pub fn write_all_gamedata_records(gamedata: &GameData, version: &Version) -> Vec
 }
 
 pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
-    let mut reader = BiffReader::new(input);
+    read_gamedata_with(&mut BiffReader::new(input), version)
+}
+
+/// Reads the game data, returning an error instead of panicking when it can not be parsed.
+pub fn try_read_all_gamedata_records(input: &[u8], version: &Version) -> io::Result<GameData> {
+    let mut reader = BiffReader::new_checked(input);
+    let gamedata = read_gamedata_with(&mut reader, version);
+    reader.finish()?;
+    Ok(gamedata)
+}
+
+fn read_gamedata_with(reader: &mut BiffReader<'_>, version: &Version) -> GameData {
     let mut gamedata = GameData::default();
     let mut previous_tag = String::new();
     loop {
@@ -1460,15 +2313,13 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
         }
         let tag = reader.tag();
 
-        let reader: &mut BiffReader<'_> = &mut reader;
-
         match tag.as_str() {
             "LEFT" => gamedata.left = reader.get_f32(),
             "TOPX" => gamedata.top = reader.get_f32(),
             "RGHT" => gamedata.right = reader.get_f32(),
             "BOTM" => gamedata.bottom = reader.get_f32(),
             "CLMO" => gamedata.camera_layout_mode = Some(reader.get_u32()),
-            "VSM0" => gamedata.bg_view_mode_desktop = Some(reader.get_u32().into()),
+            "VSM0" => gamedata.bg_view_mode_desktop = Some(reader.get_u32_checked()),
             "ROTA" => gamedata.bg_rotation_desktop = reader.get_f32(),
             "INCL" => gamedata.bg_inclination_desktop = reader.get_f32(),
             "LAYB" => gamedata.bg_layback_desktop = reader.get_f32(),
@@ -1493,7 +2344,7 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
             "WBX0" => gamedata.bg_window_bottom_x_offset_desktop = Some(reader.get_f32()),
             "WBY0" => gamedata.bg_window_bottom_y_offset_desktop = Some(reader.get_f32()),
             "WBZ0" => gamedata.bg_window_bottom_z_offset_desktop = Some(reader.get_f32()),
-            "VSM1" => gamedata.bg_view_mode_fullscreen = Some(reader.get_u32().into()),
+            "VSM1" => gamedata.bg_view_mode_fullscreen = Some(reader.get_u32_checked()),
             "ROTF" => gamedata.bg_rotation_fullscreen = reader.get_f32(),
             "INCF" => gamedata.bg_inclination_fullscreen = reader.get_f32(),
             "LAYF" => gamedata.bg_layback_fullscreen = reader.get_f32(),
@@ -1512,7 +2363,7 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
             "WBX1" => gamedata.bg_window_bottom_x_offset_fullscreen = Some(reader.get_f32()),
             "WBY1" => gamedata.bg_window_bottom_y_offset_fullscreen = Some(reader.get_f32()),
             "WBZ1" => gamedata.bg_window_bottom_z_offset_fullscreen = Some(reader.get_f32()),
-            "VSM2" => gamedata.bg_view_mode_full_single_screen = Some(reader.get_u32().into()),
+            "VSM2" => gamedata.bg_view_mode_full_single_screen = Some(reader.get_u32_checked()),
             "ROFS" => gamedata.bg_rotation_full_single_screen = Some(reader.get_f32()),
             "INFS" => gamedata.bg_inclination_full_single_screen = Some(reader.get_f32()),
             "LAFS" => gamedata.bg_layback_full_single_screen = Some(reader.get_f32()),
@@ -1621,8 +2472,13 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
                 let mut materials: Vec<SaveMaterial> = Vec::new();
                 let mut buff = BytesMut::from(data.as_slice());
                 for _ in 0..gamedata.materials_size {
-                    let material = SaveMaterial::read(&mut buff);
-                    materials.push(material);
+                    match SaveMaterial::read(&mut buff) {
+                        Ok(material) => materials.push(material),
+                        Err(e) => {
+                            reader.fail(e.to_string());
+                            break;
+                        }
+                    }
                 }
                 gamedata.materials_old = materials;
             }
@@ -1631,16 +2487,20 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
                 let mut materials: Vec<SavePhysicsMaterial> = Vec::new();
                 let mut buff = BytesMut::from(data.as_slice());
                 for _ in 0..gamedata.materials_size {
-                    let material = SavePhysicsMaterial::read(&mut buff);
-                    materials.push(material);
+                    match SavePhysicsMaterial::read(&mut buff) {
+                        Ok(material) => materials.push(material),
+                        Err(e) => {
+                            reader.fail(e.to_string());
+                            break;
+                        }
+                    }
                 }
                 gamedata.materials_physics_old = Some(materials);
             }
             // see https://github.com/vpinball/vpinball/blob/1a994086a6092733272fda36a2f449753a1ca21a/pintable.cpp#L4429
             "MATR" => {
                 let data = reader.get_record_data(false).to_vec();
-                let mut reader = BiffReader::new(&data);
-                let material = Material::biff_read(&mut reader);
+                let material = Material::biff_read(&mut reader.nested_reader(&data));
                 gamedata
                     .materials
                     .get_or_insert_with(Vec::new)
@@ -1648,8 +2508,8 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
             }
             "RPRB" => {
                 let data = reader.get_record_data(false).to_vec();
-                let mut reader = BiffReader::new(&data);
-                let render_probe = RenderProbeWithGarbage::biff_read(&mut reader);
+                let render_probe =
+                    RenderProbeWithGarbage::biff_read(&mut reader.nested_reader(&data));
                 gamedata
                     .render_probes
                     .get_or_insert_with(Vec::new)
@@ -1663,8 +2523,10 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
             "NAME" => gamedata.name = reader.get_wide_string(),
             "CCUS" => {
                 let data = reader.get_record_data(false);
-                let custom_colors = read_colors(data);
-                gamedata.custom_colors = custom_colors;
+                match read_colors(data) {
+                    Ok(custom_colors) => gamedata.custom_colors = custom_colors,
+                    Err(e) => reader.fail(e.to_string()),
+                }
             }
             "SECB" => gamedata.protection_data = Some(reader.get_record_data(false).to_vec()),
             "CODE" => {
@@ -1683,16 +2545,22 @@ pub fn read_all_gamedata_records(input: &[u8], version: &Version) -> GameData {
     gamedata
 }
 
-fn read_colors(data: Vec<u8>) -> [Color; 16] {
+fn read_colors(data: Vec<u8>) -> io::Result<[Color; 16]> {
     // COLORREF: 0x00BBGGRR
     // sizeof(COLORREF) * 16
+    if data.len() < 4 * 16 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Expected 16 custom colors, got {} bytes", data.len()),
+        ));
+    }
     let mut colors = Vec::new();
     let mut buff = BytesMut::from(data.as_slice());
     for _ in 0..16 {
         let color = Color::from_win_color(buff.get_u32_le());
         colors.push(color);
     }
-    <[Color; 16]>::try_from(colors).unwrap()
+    Ok(<[Color; 16]>::try_from(colors).unwrap())
 }
 
 fn write_colors(colors: &[Color; 16]) -> Vec<u8> {
@@ -1882,6 +2750,63 @@ mod tests {
         assert_eq!(gamedata, read_game_data);
     }
 
+    #[test]
+    fn table_images() {
+        let mut gamedata = GameData::default();
+        gamedata.set_playfield_image(Some("Playfield".to_string()));
+        gamedata.set_color_grade_image(Some("LUT".to_string()));
+        gamedata.set_ball_decal_mode(BallDecalMode::Logo);
+        gamedata.set_env_image(Some(String::new()));
+
+        assert!(gamedata.ball_decal_mode);
+        assert_eq!(gamedata.get_ball_decal_mode(), BallDecalMode::Logo);
+        assert_eq!(gamedata.get_env_image(), None);
+        assert_eq!(gamedata.referenced_images(), vec!["Playfield", "LUT"]);
+
+        let image = ImageData {
+            name: "playfield".to_string(),
+            internal_name: None,
+            path: "playfield.png".to_string(),
+            width: 1,
+            height: 1,
+            link: None,
+            alpha_test_value: -1.0,
+            is_opaque: None,
+            is_signed: None,
+            jpeg: None,
+            bits: None,
+        };
+        assert_eq!(
+            gamedata.missing_images(&[image]),
+            vec![MissingImage {
+                usage: TableImage::ColorGrade,
+                name: "LUT".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn try_read_truncated() {
+        let game_data = GameData::default();
+        let version: Version = Version::new(1074);
+        let bytes = write_all_gamedata_records(&game_data, &version);
+        let error = try_read_all_gamedata_records(&bytes[..bytes.len() / 2], &version).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn try_read_too_few_materials() {
+        let game_data = GameData {
+            materials_size: 3,
+            materials_old: vec![Faker.fake()],
+            ..Default::default()
+        };
+        let version: Version = Version::new(1074);
+        let bytes = write_all_gamedata_records(&game_data, &version);
+        let error = try_read_all_gamedata_records(&bytes, &version).unwrap_err();
+        assert_eq!(error.to_string(), "No more bytes to read SaveMaterial from");
+    }
+
     #[test]
     fn test_write_read_colors() {
         let mut colors = [Color::RED; 16];
@@ -1889,7 +2814,157 @@ mod tests {
             *color = Faker.fake();
         }
         let bytes = write_colors(&colors);
-        let read_colors = read_colors(bytes);
+        let read_colors = read_colors(bytes).unwrap();
         assert_eq!(colors, read_colors);
     }
+
+    #[test]
+    fn table_dimensions_with_offset() {
+        let gamedata = GameData {
+            left: 100.0,
+            top: 50.0,
+            right: 1100.0,
+            bottom: 2050.0,
+            ..Default::default()
+        };
+        let dimensions = gamedata.dimensions();
+        assert_eq!((dimensions.width(), dimensions.height()), (1000.0, 2000.0));
+        assert!(dimensions.contains(100.0, 2050.0));
+        assert!(!dimensions.contains(50.0, 100.0));
+        assert_eq!(dimensions.uv(600.0, 1050.0), [0.5, 0.5]);
+        assert_eq!(dimensions.world_uv(600.0, 1050.0), [0.6, 0.525]);
+        assert_eq!(TableDimensions::default().uv(1.0, 1.0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn all_view_settings() {
+        let mut gamedata = GameData {
+            bg_fov_desktop: 50.0,
+            bg_view_mode_fullscreen: Some(ViewLayoutMode::Camera),
+            ..Default::default()
+        };
+        gamedata.bg_rotation_full_single_screen = None;
+        let [desktop, fullscreen, fss] = gamedata.all_view_settings();
+        assert_eq!(desktop.fov, 50.0);
+        assert_eq!(fullscreen.mode, Some(ViewLayoutMode::Camera));
+        // missing full single screen values come from the desktop view
+        assert_eq!(fss.rotation, desktop.rotation);
+
+        let mut setup = gamedata.view_settings(ViewSetupId::Fullscreen);
+        setup.window_top_z_offset = Some(12.5);
+        gamedata.set_view_settings(ViewSetupId::Fullscreen, &setup);
+        assert_eq!(gamedata.bg_window_top_z_offset_fullscreen, Some(12.5));
+
+        gamedata.copy_view_settings(ViewSetupId::Fullscreen, ViewSetupId::FullSingleScreen);
+        assert_eq!(gamedata.view_settings(ViewSetupId::FullSingleScreen), setup);
+        assert_eq!(
+            gamedata.bg_rotation_full_single_screen,
+            Some(setup.rotation)
+        );
+        assert_eq!(
+            gamedata.bg_view_mode_full_single_screen,
+            Some(ViewLayoutMode::Camera)
+        );
+        assert_eq!(gamedata.view_settings(ViewSetupId::Desktop), desktop);
+    }
+
+    #[test]
+    fn tilt_settings() {
+        let mut gamedata = GameData::default();
+        let mut settings = gamedata.tilt_settings();
+        assert!(settings.validate().is_empty());
+        assert_eq!(settings.effective_slope(), 6.0);
+
+        settings.slope_min = 4.0;
+        settings.slope_max = 8.0;
+        settings.difficulty = 0.5;
+        gamedata.set_tilt_settings(&settings);
+        assert_eq!(gamedata.angle_tilt_min, 4.0);
+        assert_eq!(gamedata.tilt_settings(), settings);
+        assert_eq!(settings.effective_slope(), 6.0);
+
+        settings.slope_min = 25.0;
+        settings.plunger_normalize = 300;
+        let issues = settings.validate();
+        assert_eq!(
+            issues,
+            vec![
+                TiltSettingsIssue::OutOfRange {
+                    setting: "slope_min",
+                    value: 25.0,
+                    min: 0.0,
+                    max: 20.0,
+                },
+                TiltSettingsIssue::OutOfRange {
+                    setting: "plunger_normalize",
+                    value: 300.0,
+                    min: 0.0,
+                    max: 200.0,
+                },
+                TiltSettingsIssue::SlopeMinAboveMax {
+                    min: 25.0,
+                    max: 8.0,
+                },
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "slope_min is 25, expected between 0 and 20"
+        );
+    }
+
+    #[test]
+    fn ball_settings() {
+        let mut gamedata = GameData::default();
+        let settings = gamedata.ball_settings();
+        assert_eq!(settings, BallSettings::default());
+
+        gamedata.set_ball_settings(&settings);
+        assert_eq!(gamedata, GameData::default());
+
+        let settings = BallSettings {
+            image: Some("ball".to_string()),
+            decal_mode: BallDecalMode::Logo,
+            reflection: TableOverride::Off,
+            trail: TableOverride::On,
+            trail_strength: 0.2,
+            ..Default::default()
+        };
+        gamedata.set_ball_settings(&settings);
+        assert_eq!(gamedata.ball_image, "ball");
+        assert_eq!(gamedata.use_reflection_for_balls, Some(0));
+        assert_eq!(gamedata.use_trail_for_balls, Some(1));
+        assert_eq!(gamedata.ball_spherical_mapping, None);
+        let read = gamedata.ball_settings();
+        assert_eq!(read.trail_strength, dequantize_u8(8, quantize_u8(8, 0.2)));
+        assert_eq!(
+            read,
+            BallSettings {
+                trail_strength: read.trail_strength,
+                ..settings
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&read).unwrap()["reflection"],
+            serde_json::json!("off")
+        );
+    }
+
+    #[test]
+    fn lighting_settings() {
+        let mut gamedata = GameData::default();
+        let mut settings = gamedata.lighting_settings();
+        assert_eq!(settings.day_night_slider(), 52.0);
+        gamedata.set_lighting_settings(&settings);
+        assert_eq!(gamedata, GameData::default());
+
+        settings.set_day_night_slider(150.0);
+        settings.set_env_emission_slider(25.0);
+        settings.overwrite_global_day_night = true;
+        gamedata.set_lighting_settings(&settings);
+        assert_eq!(gamedata.global_emission_scale, 1.0);
+        assert_eq!(gamedata.env_emission_scale, 0.25);
+        assert_eq!(gamedata.overwrite_global_day_night, Some(true));
+        assert_eq!(gamedata.lighting_settings().env_emission_slider(), 25.0);
+    }
 }
@@ -10,6 +10,7 @@ pub mod hittarget;
 pub mod kicker;
 pub mod light;
 pub mod lightsequencer;
+pub mod physics;
 pub mod plunger;
 pub mod primitive;
 pub mod ramp;
@@ -25,8 +26,10 @@ pub mod vertex3d;
 pub mod vertex4d;
 pub mod wall;
 
+use self::physics::PhysicsFlags;
 use crate::vpx::biff::BiffRead;
 use serde::{Deserialize, Serialize};
+use std::io;
 
 use super::biff::{BiffReader, BiffWrite, BiffWriter};
 
@@ -38,7 +41,7 @@ trait GameItem: BiffRead {
 }
 
 #[allow(clippy::large_enum_variant)]
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
 // #[serde(tag = "type")]
 pub enum GameItemEnum {
     Wall(wall::Wall),
@@ -61,6 +64,10 @@ pub enum GameItemEnum {
     Rubber(rubber::Rubber),
     HitTarget(hittarget::HitTarget),
     Generic(u32, generic::Generic),
+    /// An item that failed to parse, holding the item index, the error and the raw stream bytes.
+    ///
+    /// Only produced by [`read_forgiving`], the raw bytes are written back unchanged.
+    Corrupt(u32, String, Vec<u8>),
 }
 
 impl GameItemEnum {
@@ -88,6 +95,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => rubber.editor_layer_visibility,
             GameItemEnum::HitTarget(hittarget) => hittarget.editor_layer_visibility,
             GameItemEnum::Generic(_item_type, _generic) => None,
+            GameItemEnum::Corrupt(..) => None,
         }
     }
 
@@ -113,6 +121,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => &rubber.editor_layer_name,
             GameItemEnum::HitTarget(hittarget) => &hittarget.editor_layer_name,
             GameItemEnum::Generic(_item_type, _generic) => &None,
+            GameItemEnum::Corrupt(..) => &None,
         }
     }
 
@@ -138,6 +147,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => Some(rubber.editor_layer),
             GameItemEnum::HitTarget(hittarget) => Some(hittarget.editor_layer),
             GameItemEnum::Generic(_item_type, _generic) => None,
+            GameItemEnum::Corrupt(..) => None,
         }
     }
 
@@ -163,6 +173,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => Some(rubber.is_locked),
             GameItemEnum::HitTarget(hittarget) => Some(hittarget.is_locked),
             GameItemEnum::Generic(_item_type, _generic) => None,
+            GameItemEnum::Corrupt(..) => None,
         }
     }
 
@@ -262,6 +273,7 @@ impl GameItemEnum {
                 }
             }
             GameItemEnum::Generic(_item_type, _generic) => {}
+            GameItemEnum::Corrupt(..) => {}
         }
     }
 
@@ -361,6 +373,7 @@ impl GameItemEnum {
                 }
             }
             GameItemEnum::Generic(_item_type, _generic) => {}
+            GameItemEnum::Corrupt(..) => {}
         }
     }
 
@@ -388,6 +401,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => rubber.editor_layer_name = editor_layer_name,
             GameItemEnum::HitTarget(hittarget) => hittarget.editor_layer_name = editor_layer_name,
             GameItemEnum::Generic(_item_type, _generic) => {}
+            GameItemEnum::Corrupt(..) => {}
         }
     }
 
@@ -437,6 +451,7 @@ impl GameItemEnum {
                 hittarget.editor_layer_visibility = editor_layer_visibility
             }
             GameItemEnum::Generic(_item_type, _generic) => {}
+            GameItemEnum::Corrupt(..) => {}
         }
     }
 }
@@ -464,6 +479,311 @@ impl GameItemEnum {
             GameItemEnum::Rubber(rubber) => &rubber.name,
             GameItemEnum::HitTarget(hittarget) => &hittarget.name,
             GameItemEnum::Generic(_item_type, generic) => generic.name(),
+            GameItemEnum::Corrupt(..) => "",
+        }
+    }
+
+    pub(crate) fn set_name(&mut self, name: String) {
+        match self {
+            GameItemEnum::Wall(wall) => wall.name = name,
+            GameItemEnum::Flipper(flipper) => flipper.name = name,
+            GameItemEnum::Timer(timer) => timer.name = name,
+            GameItemEnum::Plunger(plunger) => plunger.name = name,
+            GameItemEnum::TextBox(textbox) => textbox.name = name,
+            GameItemEnum::Bumper(bumper) => bumper.name = name,
+            GameItemEnum::Trigger(trigger) => trigger.name = name,
+            GameItemEnum::Light(light) => light.name = name,
+            GameItemEnum::Kicker(kicker) => kicker.name = name,
+            GameItemEnum::Decal(decal) => decal.name = name,
+            GameItemEnum::Gate(gate) => gate.name = name,
+            GameItemEnum::Spinner(spinner) => spinner.name = name,
+            GameItemEnum::Ramp(ramp) => ramp.name = name,
+            GameItemEnum::Reel(reel) => reel.name = name,
+            GameItemEnum::LightSequencer(lightsequencer) => lightsequencer.name = name,
+            GameItemEnum::Primitive(primitive) => primitive.name = name,
+            GameItemEnum::Flasher(flasher) => flasher.name = name,
+            GameItemEnum::Rubber(rubber) => rubber.name = name,
+            GameItemEnum::HitTarget(hittarget) => hittarget.name = name,
+            GameItemEnum::Generic(_item_type, generic) => generic.name = name,
+            GameItemEnum::Corrupt(..) => {}
+        }
+    }
+
+    /// The name of the wall or ramp this item is placed on, if the item type supports it.
+    ///
+    /// An empty string means the item is placed on the playfield.
+    pub fn surface(&self) -> Option<&str> {
+        match self {
+            GameItemEnum::Bumper(bumper) => Some(&bumper.surface),
+            GameItemEnum::Decal(decal) => Some(&decal.surface),
+            GameItemEnum::Flipper(flipper) => Some(&flipper.surface),
+            GameItemEnum::Gate(gate) => Some(&gate.surface),
+            GameItemEnum::Kicker(kicker) => Some(&kicker.surface),
+            GameItemEnum::Light(light) => Some(&light.surface),
+            GameItemEnum::Plunger(plunger) => Some(&plunger.surface),
+            GameItemEnum::Spinner(spinner) => Some(&spinner.surface),
+            GameItemEnum::Trigger(trigger) => Some(&trigger.surface),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn surface_mut(&mut self) -> Option<&mut String> {
+        match self {
+            GameItemEnum::Bumper(bumper) => Some(&mut bumper.surface),
+            GameItemEnum::Decal(decal) => Some(&mut decal.surface),
+            GameItemEnum::Flipper(flipper) => Some(&mut flipper.surface),
+            GameItemEnum::Gate(gate) => Some(&mut gate.surface),
+            GameItemEnum::Kicker(kicker) => Some(&mut kicker.surface),
+            GameItemEnum::Light(light) => Some(&mut light.surface),
+            GameItemEnum::Plunger(plunger) => Some(&mut plunger.surface),
+            GameItemEnum::Spinner(spinner) => Some(&mut spinner.surface),
+            GameItemEnum::Trigger(trigger) => Some(&mut trigger.surface),
+            _ => None,
+        }
+    }
+
+    /// The physics flags of items the ball can collide with
+    pub fn physics_flags(&self) -> Option<&dyn PhysicsFlags> {
+        match self {
+            GameItemEnum::Wall(wall) => Some(wall),
+            GameItemEnum::Rubber(rubber) => Some(rubber),
+            GameItemEnum::Primitive(primitive) => Some(primitive),
+            GameItemEnum::Ramp(ramp) => Some(ramp),
+            GameItemEnum::HitTarget(hittarget) => Some(hittarget),
+            GameItemEnum::Bumper(bumper) => Some(bumper),
+            GameItemEnum::Gate(gate) => Some(gate),
+            GameItemEnum::Flipper(flipper) => Some(flipper),
+            GameItemEnum::Kicker(kicker) => Some(kicker),
+            GameItemEnum::Trigger(trigger) => Some(trigger),
+            GameItemEnum::Spinner(spinner) => Some(spinner),
+            GameItemEnum::Plunger(plunger) => Some(plunger),
+            _ => None,
+        }
+    }
+
+    pub fn physics_flags_mut(&mut self) -> Option<&mut dyn PhysicsFlags> {
+        match self {
+            GameItemEnum::Wall(wall) => Some(wall),
+            GameItemEnum::Rubber(rubber) => Some(rubber),
+            GameItemEnum::Primitive(primitive) => Some(primitive),
+            GameItemEnum::Ramp(ramp) => Some(ramp),
+            GameItemEnum::HitTarget(hittarget) => Some(hittarget),
+            GameItemEnum::Bumper(bumper) => Some(bumper),
+            GameItemEnum::Gate(gate) => Some(gate),
+            GameItemEnum::Flipper(flipper) => Some(flipper),
+            GameItemEnum::Kicker(kicker) => Some(kicker),
+            GameItemEnum::Trigger(trigger) => Some(trigger),
+            GameItemEnum::Spinner(spinner) => Some(spinner),
+            GameItemEnum::Plunger(plunger) => Some(plunger),
+            _ => None,
+        }
+    }
+
+    /// Whether the item is rendered, `None` for item types without a visibility setting.
+    ///
+    /// Walls are visible when their top or their sides are.
+    pub fn is_visible(&self) -> Option<bool> {
+        match self {
+            GameItemEnum::Flasher(flasher) => Some(flasher.is_visible),
+            GameItemEnum::Flipper(flipper) => Some(flipper.is_visible),
+            GameItemEnum::Gate(gate) => Some(gate.is_visible),
+            GameItemEnum::HitTarget(hittarget) => Some(hittarget.is_visible),
+            GameItemEnum::Light(light) => light.visible,
+            GameItemEnum::Plunger(plunger) => Some(plunger.is_visible),
+            GameItemEnum::Primitive(primitive) => Some(primitive.is_visible),
+            GameItemEnum::Ramp(ramp) => Some(ramp.is_visible),
+            GameItemEnum::Reel(reel) => Some(reel.is_visible),
+            GameItemEnum::Rubber(rubber) => Some(rubber.is_visible),
+            GameItemEnum::Spinner(spinner) => Some(spinner.is_visible),
+            GameItemEnum::Trigger(trigger) => Some(trigger.is_visible),
+            GameItemEnum::Wall(wall) => Some(wall.is_top_bottom_visible || wall.is_side_visible),
+            _ => None,
+        }
+    }
+
+    /// Names of the images used by this item
+    pub fn referenced_images(&self) -> Vec<&str> {
+        let names = match self {
+            GameItemEnum::Decal(decal) => vec![decal.image.as_str()],
+            GameItemEnum::Flasher(flasher) => {
+                vec![flasher.image_a.as_str(), flasher.image_b.as_str()]
+            }
+            GameItemEnum::Flipper(flipper) => flipper.image.as_deref().into_iter().collect(),
+            GameItemEnum::HitTarget(hittarget) => vec![hittarget.image.as_str()],
+            GameItemEnum::Light(light) => vec![light.off_image.as_str()],
+            GameItemEnum::Plunger(plunger) => vec![plunger.image.as_str()],
+            GameItemEnum::Primitive(primitive) => [
+                Some(primitive.image.as_str()),
+                primitive.normal_map.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Ramp(ramp) => vec![ramp.image.as_str()],
+            GameItemEnum::Reel(reel) => vec![reel.image.as_str()],
+            GameItemEnum::Rubber(rubber) => vec![rubber.image.as_str()],
+            GameItemEnum::Spinner(spinner) => vec![spinner.image.as_str()],
+            GameItemEnum::Wall(wall) => vec![wall.image.as_str(), wall.side_image.as_str()],
+            _ => vec![],
+        };
+        names.into_iter().filter(|name| !name.is_empty()).collect()
+    }
+
+    pub(crate) fn referenced_images_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            GameItemEnum::Decal(decal) => vec![&mut decal.image],
+            GameItemEnum::Flasher(flasher) => vec![&mut flasher.image_a, &mut flasher.image_b],
+            GameItemEnum::Flipper(flipper) => flipper.image.as_mut().into_iter().collect(),
+            GameItemEnum::HitTarget(hittarget) => vec![&mut hittarget.image],
+            GameItemEnum::Light(light) => vec![&mut light.off_image],
+            GameItemEnum::Plunger(plunger) => vec![&mut plunger.image],
+            GameItemEnum::Primitive(primitive) => {
+                [Some(&mut primitive.image), primitive.normal_map.as_mut()]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            GameItemEnum::Ramp(ramp) => vec![&mut ramp.image],
+            GameItemEnum::Reel(reel) => vec![&mut reel.image],
+            GameItemEnum::Rubber(rubber) => vec![&mut rubber.image],
+            GameItemEnum::Spinner(spinner) => vec![&mut spinner.image],
+            GameItemEnum::Wall(wall) => vec![&mut wall.image, &mut wall.side_image],
+            _ => vec![],
+        }
+    }
+
+    /// Names of the materials used by this item, including physics materials
+    pub fn referenced_materials(&self) -> Vec<&str> {
+        let names = match self {
+            GameItemEnum::Bumper(bumper) => [
+                Some(bumper.cap_material.as_str()),
+                Some(bumper.base_material.as_str()),
+                Some(bumper.socket_material.as_str()),
+                bumper.ring_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Decal(decal) => vec![decal.material.as_str()],
+            GameItemEnum::Flipper(flipper) => {
+                vec![flipper.material.as_str(), flipper.rubber_material.as_str()]
+            }
+            GameItemEnum::Gate(gate) => vec![gate.material.as_str()],
+            GameItemEnum::HitTarget(hittarget) => [
+                Some(hittarget.material.as_str()),
+                hittarget.physics_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Kicker(kicker) => vec![kicker.material.as_str()],
+            GameItemEnum::Plunger(plunger) => vec![plunger.material.as_str()],
+            GameItemEnum::Primitive(primitive) => [
+                Some(primitive.material.as_str()),
+                primitive.physics_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Ramp(ramp) => [
+                Some(ramp.material.as_str()),
+                ramp.physics_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Rubber(rubber) => [
+                Some(rubber.material.as_str()),
+                rubber.physics_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Spinner(spinner) => vec![spinner.material.as_str()],
+            GameItemEnum::Trigger(trigger) => vec![trigger.material.as_str()],
+            GameItemEnum::Wall(wall) => [
+                Some(wall.side_material.as_str()),
+                Some(wall.top_material.as_str()),
+                Some(wall.slingshot_material.as_str()),
+                wall.physics_material.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            _ => vec![],
+        };
+        names.into_iter().filter(|name| !name.is_empty()).collect()
+    }
+
+    pub(crate) fn referenced_materials_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            GameItemEnum::Bumper(bumper) => [
+                Some(&mut bumper.cap_material),
+                Some(&mut bumper.base_material),
+                Some(&mut bumper.socket_material),
+                bumper.ring_material.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Decal(decal) => vec![&mut decal.material],
+            GameItemEnum::Flipper(flipper) => {
+                vec![&mut flipper.material, &mut flipper.rubber_material]
+            }
+            GameItemEnum::Gate(gate) => vec![&mut gate.material],
+            GameItemEnum::HitTarget(hittarget) => [
+                Some(&mut hittarget.material),
+                hittarget.physics_material.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Kicker(kicker) => vec![&mut kicker.material],
+            GameItemEnum::Plunger(plunger) => vec![&mut plunger.material],
+            GameItemEnum::Primitive(primitive) => [
+                Some(&mut primitive.material),
+                primitive.physics_material.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            GameItemEnum::Ramp(ramp) => [Some(&mut ramp.material), ramp.physics_material.as_mut()]
+                .into_iter()
+                .flatten()
+                .collect(),
+            GameItemEnum::Rubber(rubber) => {
+                [Some(&mut rubber.material), rubber.physics_material.as_mut()]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+            GameItemEnum::Spinner(spinner) => vec![&mut spinner.material],
+            GameItemEnum::Trigger(trigger) => vec![&mut trigger.material],
+            GameItemEnum::Wall(wall) => [
+                Some(&mut wall.side_material),
+                Some(&mut wall.top_material),
+                Some(&mut wall.slingshot_material),
+                wall.physics_material.as_mut(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Names of the sounds played by this item, only reels refer to a sound
+    pub fn referenced_sounds(&self) -> Vec<&str> {
+        match self {
+            GameItemEnum::Reel(reel) if !reel.sound.is_empty() => vec![reel.sound.as_str()],
+            _ => vec![],
+        }
+    }
+
+    pub(crate) fn referenced_sounds_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            GameItemEnum::Reel(reel) => vec![&mut reel.sound],
+            _ => vec![],
         }
     }
 
@@ -489,6 +809,7 @@ impl GameItemEnum {
             GameItemEnum::Rubber(_) => "Rubber".to_string(),
             GameItemEnum::HitTarget(_) => "HitTarget".to_string(),
             GameItemEnum::Generic(item_type, _) => format!("Generic_{}", item_type),
+            GameItemEnum::Corrupt(index, _, _) => format!("Corrupt_{}", index),
         }
     }
 
@@ -518,11 +839,70 @@ impl GameItemEnum {
             "Flasher" => ITEM_TYPE_FLASHER,
             "Rubber" => ITEM_TYPE_RUBBER,
             "HitTarget" => ITEM_TYPE_HIT_TARGET,
-            _ => unimplemented!("type_id for {}", type_name),
+            _ => match type_name
+                .strip_prefix("Generic_")
+                .and_then(|id| id.parse().ok())
+            {
+                Some(item_type) => item_type,
+                None => unimplemented!("type_id for {}", type_name),
+            },
+        }
+    }
+
+    /// The type id of an item type this library does not know about, see [`generic`]
+    pub fn unknown_item_type(&self) -> Option<u32> {
+        match self {
+            GameItemEnum::Generic(item_type, _) => Some(*item_type),
+            _ => None,
         }
     }
 }
 
+/// The distinct unknown item type ids in a list of items, sorted
+pub fn unknown_item_types(gameitems: &[GameItemEnum]) -> Vec<u32> {
+    let mut item_types: Vec<u32> = gameitems
+        .iter()
+        .filter_map(GameItemEnum::unknown_item_type)
+        .collect();
+    item_types.sort_unstable();
+    item_types.dedup();
+    item_types
+}
+
+/// The registry of known item types, the name for a type id as used by
+/// [`GameItemEnum::type_name`].
+///
+/// Items with a type id that is not in here are read as [`GameItemEnum::Generic`].
+pub fn item_type_name(item_type: u32) -> Option<&'static str> {
+    let name = match item_type {
+        ITEM_TYPE_WALL => "Wall",
+        ITEM_TYPE_FLIPPER => "Flipper",
+        ITEM_TYPE_TIMER => "Timer",
+        ITEM_TYPE_PLUNGER => "Plunger",
+        ITEM_TYPE_TEXT_BOX => "TextBox",
+        ITEM_TYPE_BUMPER => "Bumper",
+        ITEM_TYPE_TRIGGER => "Trigger",
+        ITEM_TYPE_LIGHT => "Light",
+        ITEM_TYPE_KICKER => "Kicker",
+        ITEM_TYPE_DECAL => "Decal",
+        ITEM_TYPE_GATE => "Gate",
+        ITEM_TYPE_SPINNER => "Spinner",
+        ITEM_TYPE_RAMP => "Ramp",
+        ITEM_TYPE_TABLE => "Table",
+        ITEM_TYPE_LIGHT_CENTER => "LightCenter",
+        ITEM_TYPE_DRAG_POINT => "DragPoint",
+        ITEM_TYPE_COLLECTION => "Collection",
+        ITEM_TYPE_REEL => "Reel",
+        ITEM_TYPE_LIGHT_SEQUENCER => "LightSequencer",
+        ITEM_TYPE_PRIMITIVE => "Primitive",
+        ITEM_TYPE_FLASHER => "Flasher",
+        ITEM_TYPE_RUBBER => "Rubber",
+        ITEM_TYPE_HIT_TARGET => "HitTarget",
+        _ => return None,
+    };
+    Some(name)
+}
+
 // Item types:
 // 0: Wall
 // 1: Flipper
@@ -599,44 +979,62 @@ const ITEM_TYPE_HIT_TARGET: u32 = 22;
 // ];
 
 pub fn read(input: &[u8]) -> GameItemEnum {
-    let mut reader = BiffReader::new(input);
+    read_with(&mut BiffReader::new(input))
+}
+
+/// Reads a game item, returning an error instead of panicking when the data can not be parsed.
+pub fn try_read(input: &[u8]) -> io::Result<GameItemEnum> {
+    let mut reader = BiffReader::new_checked(input);
+    let item = read_with(&mut reader);
+    reader.finish()?;
+    Ok(item)
+}
+
+fn read_with(reader: &mut BiffReader<'_>) -> GameItemEnum {
     let item_type = reader.get_u32_no_remaining_update();
     match item_type {
-        ITEM_TYPE_WALL => GameItemEnum::Wall(wall::Wall::biff_read(&mut reader)),
-        ITEM_TYPE_FLIPPER => GameItemEnum::Flipper(flipper::Flipper::biff_read(&mut reader)),
-        ITEM_TYPE_TIMER => GameItemEnum::Timer(timer::Timer::biff_read(&mut reader)),
-        ITEM_TYPE_PLUNGER => GameItemEnum::Plunger(plunger::Plunger::biff_read(&mut reader)),
-        ITEM_TYPE_TEXT_BOX => GameItemEnum::TextBox(textbox::TextBox::biff_read(&mut reader)),
-        ITEM_TYPE_BUMPER => GameItemEnum::Bumper(bumper::Bumper::biff_read(&mut reader)),
-        ITEM_TYPE_TRIGGER => GameItemEnum::Trigger(trigger::Trigger::biff_read(&mut reader)),
-        ITEM_TYPE_LIGHT => GameItemEnum::Light(light::Light::biff_read(&mut reader)),
-        ITEM_TYPE_KICKER => GameItemEnum::Kicker(kicker::Kicker::biff_read(&mut reader)),
-        ITEM_TYPE_DECAL => GameItemEnum::Decal(decal::Decal::biff_read(&mut reader)),
-        ITEM_TYPE_GATE => GameItemEnum::Gate(gate::Gate::biff_read(&mut reader)),
-        ITEM_TYPE_SPINNER => GameItemEnum::Spinner(spinner::Spinner::biff_read(&mut reader)),
-        ITEM_TYPE_RAMP => GameItemEnum::Ramp(ramp::Ramp::biff_read(&mut reader)),
-        ITEM_TYPE_TABLE => panic!("Table should not be read on it's own"),
-        ITEM_TYPE_LIGHT_CENTER => panic!("LightCenter should not be read on it's own"),
-        ITEM_TYPE_DRAG_POINT => panic!("DragPoint should not be read on it's own"),
-        ITEM_TYPE_COLLECTION => panic!("Collection should not be read on it's own"),
-        ITEM_TYPE_REEL => GameItemEnum::Reel(reel::Reel::biff_read(&mut reader)),
-        ITEM_TYPE_LIGHT_SEQUENCER => {
-            GameItemEnum::LightSequencer(lightsequencer::LightSequencer::biff_read(&mut reader))
-        }
-        ITEM_TYPE_PRIMITIVE => {
-            GameItemEnum::Primitive(primitive::Primitive::biff_read(&mut reader))
+        ITEM_TYPE_WALL => GameItemEnum::Wall(wall::Wall::biff_read(reader)),
+        ITEM_TYPE_FLIPPER => GameItemEnum::Flipper(flipper::Flipper::biff_read(reader)),
+        ITEM_TYPE_TIMER => GameItemEnum::Timer(timer::Timer::biff_read(reader)),
+        ITEM_TYPE_PLUNGER => GameItemEnum::Plunger(plunger::Plunger::biff_read(reader)),
+        ITEM_TYPE_TEXT_BOX => GameItemEnum::TextBox(textbox::TextBox::biff_read(reader)),
+        ITEM_TYPE_BUMPER => GameItemEnum::Bumper(bumper::Bumper::biff_read(reader)),
+        ITEM_TYPE_TRIGGER => GameItemEnum::Trigger(trigger::Trigger::biff_read(reader)),
+        ITEM_TYPE_LIGHT => GameItemEnum::Light(light::Light::biff_read(reader)),
+        ITEM_TYPE_KICKER => GameItemEnum::Kicker(kicker::Kicker::biff_read(reader)),
+        ITEM_TYPE_DECAL => GameItemEnum::Decal(decal::Decal::biff_read(reader)),
+        ITEM_TYPE_GATE => GameItemEnum::Gate(gate::Gate::biff_read(reader)),
+        ITEM_TYPE_SPINNER => GameItemEnum::Spinner(spinner::Spinner::biff_read(reader)),
+        ITEM_TYPE_RAMP => GameItemEnum::Ramp(ramp::Ramp::biff_read(reader)),
+        ITEM_TYPE_TABLE | ITEM_TYPE_LIGHT_CENTER | ITEM_TYPE_DRAG_POINT | ITEM_TYPE_COLLECTION => {
+            reader.fail(format!(
+                "{} should not be read on it's own",
+                item_type_name(item_type).unwrap_or_default()
+            ));
+            // a checked reader is at the end now, this reads nothing
+            GameItemEnum::Generic(item_type, generic::Generic::biff_read(reader))
         }
-        ITEM_TYPE_FLASHER => GameItemEnum::Flasher(flasher::Flasher::biff_read(&mut reader)),
-        ITEM_TYPE_RUBBER => GameItemEnum::Rubber(rubber::Rubber::biff_read(&mut reader)),
-        ITEM_TYPE_HIT_TARGET => {
-            GameItemEnum::HitTarget(hittarget::HitTarget::biff_read(&mut reader))
+        ITEM_TYPE_REEL => GameItemEnum::Reel(reel::Reel::biff_read(reader)),
+        ITEM_TYPE_LIGHT_SEQUENCER => {
+            GameItemEnum::LightSequencer(lightsequencer::LightSequencer::biff_read(reader))
         }
+        ITEM_TYPE_PRIMITIVE => GameItemEnum::Primitive(primitive::Primitive::biff_read(reader)),
+        ITEM_TYPE_FLASHER => GameItemEnum::Flasher(flasher::Flasher::biff_read(reader)),
+        ITEM_TYPE_RUBBER => GameItemEnum::Rubber(rubber::Rubber::biff_read(reader)),
+        ITEM_TYPE_HIT_TARGET => GameItemEnum::HitTarget(hittarget::HitTarget::biff_read(reader)),
         other_item_type => {
-            GameItemEnum::Generic(other_item_type, generic::Generic::biff_read(&mut reader))
+            GameItemEnum::Generic(other_item_type, generic::Generic::biff_read(reader))
         }
     }
 }
 
+/// Reads a game item, returning [`GameItemEnum::Corrupt`] instead of failing when the data can
+/// not be parsed. This allows repair tools to access the rest of the table.
+pub fn read_forgiving(index: u32, input: &[u8]) -> GameItemEnum {
+    try_read(input)
+        .unwrap_or_else(|error| GameItemEnum::Corrupt(index, error.to_string(), input.to_vec()))
+}
+
 pub(crate) fn write(gameitem: &GameItemEnum) -> Vec<u8> {
     match gameitem {
         GameItemEnum::Wall(wall) => write_with_type(ITEM_TYPE_WALL, wall),
@@ -660,11 +1058,8 @@ pub(crate) fn write(gameitem: &GameItemEnum) -> Vec<u8> {
         GameItemEnum::Flasher(flasher) => write_with_type(ITEM_TYPE_FLASHER, flasher),
         GameItemEnum::Rubber(rubber) => write_with_type(ITEM_TYPE_RUBBER, rubber),
         GameItemEnum::HitTarget(hittarget) => write_with_type(ITEM_TYPE_HIT_TARGET, hittarget),
-        // GameItemEnum::Generic(item_type, generic) => write_with_type(*item_type, generic),
-        _ => {
-            unimplemented!("write gameitem {:?}", gameitem);
-            //vec![]
-        }
+        GameItemEnum::Generic(item_type, generic) => write_with_type(*item_type, generic),
+        GameItemEnum::Corrupt(_index, _error, raw_bytes) => raw_bytes.clone(),
     }
 }
 
@@ -674,3 +1069,88 @@ fn write_with_type<T: BiffWrite>(item_type: u32, item: &T) -> Vec<u8> {
     item.biff_write(&mut writer);
     writer.get_data().to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn read_forgiving_valid() {
+        let timer = timer::Timer::default();
+        let data = write(&GameItemEnum::Timer(timer));
+        let item = read_forgiving(0, &data);
+        assert_eq!(item, GameItemEnum::Timer(timer::Timer::default()));
+    }
+
+    #[test]
+    fn read_forgiving_truncated() {
+        let timer = timer::Timer::default();
+        let data = write(&GameItemEnum::Timer(timer));
+        let truncated = &data[..data.len() / 2];
+        let item = read_forgiving(3, truncated);
+        match &item {
+            GameItemEnum::Corrupt(index, _error, raw_bytes) => {
+                assert_eq!(*index, 3);
+                assert_eq!(raw_bytes, truncated);
+            }
+            other => panic!("Expected a corrupt item, got {:?}", other),
+        }
+        // raw bytes are written back unchanged
+        assert_eq!(write(&item), truncated);
+    }
+
+    #[test]
+    fn read_forgiving_invalid_enum_value() {
+        let mut writer = BiffWriter::new();
+        writer.write_u32(ITEM_TYPE_GATE);
+        writer.write_tagged_u32("GATY", 99);
+        writer.close(true);
+        let data = writer.get_data();
+        let item = read_forgiving(5, data);
+        match &item {
+            GameItemEnum::Corrupt(index, error, raw_bytes) => {
+                assert_eq!(*index, 5);
+                assert_eq!(error, "Unknown GateType: 99");
+                assert_eq!(raw_bytes, data);
+            }
+            other => panic!("Expected a corrupt item, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn try_read_item_that_is_not_standalone() {
+        let mut writer = BiffWriter::new();
+        writer.write_u32(ITEM_TYPE_DRAG_POINT);
+        writer.close(true);
+        let error = try_read(writer.get_data()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "DragPoint should not be read on it's own"
+        );
+    }
+
+    #[test]
+    fn unknown_item_type_round_trip() {
+        let item = GameItemEnum::Generic(
+            100,
+            generic::Generic {
+                name: "Future".to_string(),
+                data: {
+                    let mut writer = BiffWriter::new();
+                    writer.write_tagged_wide_string("NAME", "Future");
+                    writer.write_tagged_data("WHAT", &[1, 2, 3, 4]);
+                    writer.close(true);
+                    writer.get_data().to_vec()
+                },
+            },
+        );
+        let data = write(&item);
+        assert_eq!(read(&data), item);
+        assert_eq!(item.unknown_item_type(), Some(100));
+        assert_eq!(GameItemEnum::type_id(&item.type_name()), 100);
+        assert_eq!(item_type_name(100), None);
+        assert_eq!(item_type_name(ITEM_TYPE_TEXT_BOX), Some("TextBox"));
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{vertex2d::Vertex2D, GameItem};
 
-#[derive(Debug, Dummy, PartialEq)]
+#[derive(Debug, Dummy, PartialEq, Clone)]
 pub struct Bumper {
     pub center: Vertex2D,
     pub radius: f32,
@@ -25,7 +25,7 @@ pub struct Bumper {
     pub socket_material: String,
     pub ring_material: Option<String>,
     // RIMA (added in ?)
-    surface: String,
+    pub(crate) surface: String,
     pub name: String,
     pub is_cap_visible: bool,
     pub is_base_visible: bool,
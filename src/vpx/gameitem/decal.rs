@@ -1,5 +1,5 @@
 use crate::vpx::{
-    biff::{self, BiffRead, BiffReader, BiffWrite},
+    biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom},
     color::Color,
 };
 use fake::Dummy;
@@ -30,16 +30,27 @@ impl From<&DecalType> for u32 {
     }
 }
 
-impl From<u32> for DecalType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for DecalType {
+    const FALLBACK: Self = DecalType::Text;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => DecalType::Text,
-            1 => DecalType::Image,
-            _ => panic!("Invalid value for DecalType: {}, we expect 0, 1", value),
+            0 => Ok(DecalType::Text),
+            1 => Ok(DecalType::Image),
+            _ => Err(format!(
+                "Invalid value for DecalType: {}, we expect 0, 1",
+                value
+            )),
         }
     }
 }
 
+impl From<u32> for DecalType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 /// A serializer for DecalType that writes it as lowercase
 impl Serialize for DecalType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -100,17 +111,28 @@ impl From<&SizingType> for u32 {
     }
 }
 
-impl From<u32> for SizingType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for SizingType {
+    const FALLBACK: Self = SizingType::AutoSize;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => SizingType::AutoSize,
-            1 => SizingType::AutoWidth,
-            2 => SizingType::ManualSize,
-            _ => panic!("Invalid value for SizingType: {}, we expect 0, 1, 2", value),
+            0 => Ok(SizingType::AutoSize),
+            1 => Ok(SizingType::AutoWidth),
+            2 => Ok(SizingType::ManualSize),
+            _ => Err(format!(
+                "Invalid value for SizingType: {}, we expect 0, 1, 2",
+                value
+            )),
         }
     }
 }
 
+impl From<u32> for SizingType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 /// A serializer for SizingType that writes it as lowercase
 impl Serialize for SizingType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -164,7 +186,7 @@ impl<'de> Deserialize<'de> for SizingType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Decal {
     pub center: Vertex2D,
     pub width: f32,
@@ -260,6 +282,16 @@ impl DecalJson {
     }
 }
 
+impl Decal {
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn set_font(&mut self, font: Font) {
+        self.font = font;
+    }
+}
+
 impl Default for Decal {
     fn default() -> Self {
         Self {
@@ -347,7 +379,7 @@ impl BiffRead for Decal {
                     decal.text = reader.get_string();
                 }
                 "TYPE" => {
-                    decal.decal_type = reader.get_u32().into();
+                    decal.decal_type = reader.get_u32_checked();
                 }
                 "MATR" => {
                     decal.material = reader.get_string();
@@ -356,7 +388,7 @@ impl BiffRead for Decal {
                     decal.color = Color::biff_read(reader);
                 }
                 "SIZE" => {
-                    decal.sizing_type = reader.get_u32().into();
+                    decal.sizing_type = reader.get_u32_checked();
                 }
                 "VERT" => {
                     decal.vertical_text = reader.get_bool();
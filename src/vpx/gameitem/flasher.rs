@@ -1,6 +1,6 @@
 use crate::vpx::gameitem::ramp_image_alignment::RampImageAlignment;
 use crate::vpx::{
-    biff::{self, BiffRead, BiffReader, BiffWrite},
+    biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom},
     color::Color,
 };
 use fake::Dummy;
@@ -19,19 +19,27 @@ pub enum Filter {
     Screen = 4,
 }
 
-impl From<u32> for Filter {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for Filter {
+    const FALLBACK: Self = Filter::None;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => Filter::None,
-            1 => Filter::Additive,
-            2 => Filter::Overlay,
-            3 => Filter::Multiply,
-            4 => Filter::Screen,
-            _ => panic!("Invalid Filter value {}", value),
+            0 => Ok(Filter::None),
+            1 => Ok(Filter::Additive),
+            2 => Ok(Filter::Overlay),
+            3 => Ok(Filter::Multiply),
+            4 => Ok(Filter::Screen),
+            _ => Err(format!("Invalid Filter value {}", value)),
         }
     }
 }
 
+impl From<u32> for Filter {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&Filter> for u32 {
     fn from(value: &Filter) -> Self {
         match value {
@@ -101,7 +109,7 @@ impl<'de> Deserialize<'de> for Filter {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Flasher {
     pub height: f32,
     pub pos_x: f32,
@@ -359,10 +367,10 @@ impl BiffRead for Flasher {
                     flasher.depth_bias = reader.get_f32();
                 }
                 "ALGN" => {
-                    flasher.image_alignment = reader.get_u32().into();
+                    flasher.image_alignment = reader.get_u32_checked();
                 }
                 "FILT" => {
-                    flasher.filter = reader.get_u32().into();
+                    flasher.filter = reader.get_u32_checked();
                 }
                 "FIAM" => {
                     flasher.filter_amount = reader.get_u32();
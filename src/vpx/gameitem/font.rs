@@ -111,7 +111,7 @@ pub const CHARSET_EXTENDED: u16 = 255;
 
 /// This is a font reference some primitives use.
 /// In vpinball represented as serialized win32 FONTDESC struct
-#[derive(PartialEq, Debug, Dummy)]
+#[derive(PartialEq, Debug, Dummy, Clone)]
 pub struct Font {
     /// from https://learn.microsoft.com/en-us/windows/win32/lwef/fontcharset-property
     /// An integer value that specifies the character set used by the font. The following are some
@@ -182,6 +182,11 @@ impl Font {
             name,
         }
     }
+
+    /// The font family, eg `Arial`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl Default for Font {
@@ -200,7 +205,9 @@ impl Default for Font {
 impl BiffRead for Font {
     fn biff_read(reader: &mut BiffReader<'_>) -> Font {
         let version = reader.get_u8_no_remaining_update();
-        assert_eq!(version, EXPECTED_FONTDESC_VERSION, "Font version is not 1");
+        if version != EXPECTED_FONTDESC_VERSION {
+            reader.fail(format!("Font version is {}, expected 1", version));
+        }
         let charset = reader.get_u16_no_remaining_update();
         let style = reader.get_u8_no_remaining_update();
         let weight = reader.get_u16_no_remaining_update();
@@ -1,4 +1,4 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite};
+use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom};
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -12,18 +12,26 @@ pub enum GateType {
     LongPlate = 4,
 }
 
-impl From<u32> for GateType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for GateType {
+    const FALLBACK: Self = GateType::WireW;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            1 => GateType::WireW,
-            2 => GateType::WireRectangle,
-            3 => GateType::Plate,
-            4 => GateType::LongPlate,
-            _ => panic!("Unknown GateType: {}", value),
+            1 => Ok(GateType::WireW),
+            2 => Ok(GateType::WireRectangle),
+            3 => Ok(GateType::Plate),
+            4 => Ok(GateType::LongPlate),
+            _ => Err(format!("Unknown GateType: {}", value)),
         }
     }
 }
 
+impl From<u32> for GateType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<GateType> for u32 {
     fn from(gate_type: GateType) -> Self {
         match gate_type {
@@ -69,7 +77,7 @@ impl<'de> Deserialize<'de> for GateType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Gate {
     pub center: Vertex2D,                    // 1 VCEN
     pub length: f32,                         // 2 LGTH
@@ -327,7 +335,7 @@ impl BiffRead for Gate {
                     gate.is_reflection_enabled = Some(reader.get_bool());
                 }
                 "GATY" => {
-                    gate.gate_type = Some(reader.get_u32().into());
+                    gate.gate_type = Some(reader.get_u32_checked());
                 }
 
                 // shared
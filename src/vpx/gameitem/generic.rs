@@ -1,21 +1,33 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader};
+//! Game items of a type this library does not know about
+//!
+//! Newer Visual Pinball versions can add item types. Their type id is not in the registry in
+//! [`super::item_type_name`] so they are read as a [`Generic`] item that keeps the raw BIFF data,
+//! which is written back unchanged. To support a new item type, add a module with a struct
+//! implementing `BiffRead` and `BiffWrite`, a variant to [`super::GameItemEnum`] and its type id
+//! to the registry.
+
+use crate::vpx::biff::{BiffRead, BiffReader, BiffWrite, BiffWriter};
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
+use super::font::Font;
 use super::GameItem;
 
 /**
  * FOr any items that have a type that we don't know about, we can use this
  */
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Generic {
+    /// The name from the `NAME` record, changing it replaces that record when writing
     pub name: String,
-    pub fields: Vec<(String, Vec<u8>)>,
+    /// The BIFF data of the item as read, without the item type
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct GenericJson {
     name: String,
-    fields: Vec<(String, Vec<u8>)>,
+    data: Vec<u8>,
 }
 
 impl Serialize for Generic {
@@ -25,7 +37,7 @@ impl Serialize for Generic {
     {
         GenericJson {
             name: self.name.clone(),
-            fields: self.fields.clone(),
+            data: self.data.clone(),
         }
         .serialize(serializer)
     }
@@ -39,7 +51,7 @@ impl<'de> serde::Deserialize<'de> for Generic {
         let json = GenericJson::deserialize(deserializer)?;
         Ok(Generic {
             name: json.name,
-            fields: json.fields,
+            data: json.data,
         })
     }
 }
@@ -50,27 +62,130 @@ impl GameItem for Generic {
     }
 }
 
+/// Finds the `NAME` record, returning the name and the range of the whole record.
+///
+/// The records are walked the way the known item types store them: `DPNT` holds a nested
+/// stream and `FONT` a font description, neither is covered by the record size.
+fn find_name(data: &[u8]) -> Option<(String, Range<usize>)> {
+    let mut reader = BiffReader::new_checked(data);
+    reader.disable_warn_remaining();
+    let mut start = reader.pos();
+    while let Some(tag) = reader.next(false) {
+        match tag.as_str() {
+            "NAME" => {
+                let name = reader.get_wide_string();
+                return reader.finish().ok().map(|_| (name, start..reader.pos()));
+            }
+            "DPNT" => {
+                let mut sub_reader = reader.child_reader();
+                while sub_reader.next(false).is_some() {
+                    sub_reader.skip_tag();
+                }
+                let pos = sub_reader.pos();
+                reader.skip_end_tag(pos);
+            }
+            "FONT" => {
+                Font::biff_read(&mut reader);
+            }
+            _ => {
+                reader.skip_tag();
+            }
+        }
+        start = reader.pos();
+    }
+    None
+}
+
 impl BiffRead for Generic {
     fn biff_read(reader: &mut BiffReader<'_>) -> Self {
-        let mut name = Default::default();
-        let mut fields: Vec<(String, Vec<u8>)> = Vec::new();
+        let data = reader.get_remaining().to_vec();
+        reader.skip_end_tag(data.len());
+        let name = find_name(&data).map(|(name, _)| name).unwrap_or_default();
+        Self { name, data }
+    }
+}
 
-        loop {
-            reader.next(biff::WARN);
-            if reader.is_eof() {
-                break;
-            }
-            let tag = reader.tag();
-            let tag_str = tag.as_str();
-            match tag_str {
-                "NAME" => {
-                    name = reader.get_wide_string();
-                }
-                _ => {
-                    fields.push((tag_str.to_string(), reader.get_record_data(false).to_vec()));
-                }
+impl BiffWrite for Generic {
+    fn biff_write(&self, writer: &mut BiffWriter) {
+        if self.data.is_empty() {
+            writer.write_tagged_wide_string("NAME", &self.name);
+            writer.close(true);
+            return;
+        }
+        match find_name(&self.data) {
+            Some((name, range)) if name != self.name => {
+                writer.write_data(&self.data[..range.start]);
+                writer.write_tagged_wide_string("NAME", &self.name);
+                writer.write_data(&self.data[range.end..]);
             }
+            // without a NAME record there is nothing to rename
+            _ => writer.write_data(&self.data),
         }
-        Self { name, fields }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::gameitem::{self, GameItemEnum};
+    use pretty_assertions::assert_eq;
+
+    /// Writes the item and replaces its type id with one that is not in the registry
+    fn write_as_unknown(item: &GameItemEnum) -> Vec<u8> {
+        let mut data = gameitem::write(item);
+        data[..4].copy_from_slice(&100u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn write_read() {
+        let mut writer = BiffWriter::new();
+        writer.write_tagged_data("VCEN", &[1, 2, 3, 4, 5, 6, 7, 8]);
+        writer.write_tagged_wide_string("NAME", "future item");
+        writer.write_tagged_u32("TMON", 1);
+        writer.close(true);
+        let generic = Generic::biff_read(&mut BiffReader::new(writer.get_data()));
+        assert_eq!(generic.name, "future item");
+        let mut writer = BiffWriter::new();
+        Generic::biff_write(&generic, &mut writer);
+        let generic_read = Generic::biff_read(&mut BiffReader::new(writer.get_data()));
+        assert_eq!(generic, generic_read);
+    }
+
+    #[test]
+    fn wall_with_drag_points_round_trips_byte_exact() {
+        let wall = Wall {
+            name: "Wall1".to_string(),
+            drag_points: vec![DragPoint::default(), DragPoint::default()],
+            ..Default::default()
+        };
+        let data = write_as_unknown(&GameItemEnum::Wall(wall));
+        let item = gameitem::read(&data);
+        match &item {
+            GameItemEnum::Generic(100, generic) => assert_eq!(generic.name, "Wall1"),
+            other => panic!("Expected a generic item, got {:?}", other),
+        }
+        assert_eq!(gameitem::write(&item), data);
+    }
+
+    #[test]
+    fn rename_replaces_the_name_record() {
+        let write_future = |name: &str| {
+            let mut writer = BiffWriter::new();
+            writer.write_tagged_without_size("FONT", &Font::default());
+            writer.write_tagged_without_size("DPNT", &DragPoint::default());
+            writer.write_tagged_wide_string("NAME", name);
+            writer.write_tagged_u32("TMON", 1);
+            writer.close(true);
+            writer.get_data().to_vec()
+        };
+        let mut generic = Generic::biff_read(&mut BiffReader::new(&write_future("Future")));
+        assert_eq!(generic.name, "Future");
+        generic.name = "Renamed".to_string();
+        let mut writer = BiffWriter::new();
+        generic.biff_write(&mut writer);
+        assert_eq!(writer.get_data(), write_future("Renamed"));
     }
 }
@@ -1,4 +1,4 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite};
+use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom};
 use fake::Dummy;
 use serde::{Deserialize, Serialize};
 
@@ -17,23 +17,31 @@ pub enum TargetType {
     HitTargetSlim = 9,
 }
 
-impl From<u32> for TargetType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for TargetType {
+    const FALLBACK: Self = TargetType::DropTargetBeveled;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            1 => TargetType::DropTargetBeveled,
-            2 => TargetType::DropTargetSimple,
-            3 => TargetType::HitTargetRound,
-            4 => TargetType::HitTargetRectangle,
-            5 => TargetType::HitFatTargetRectangle,
-            6 => TargetType::HitFatTargetSquare,
-            7 => TargetType::DropTargetFlatSimple,
-            8 => TargetType::HitFatTargetSlim,
-            9 => TargetType::HitTargetSlim,
-            _ => panic!("Invalid TargetType value {}", value),
+            1 => Ok(TargetType::DropTargetBeveled),
+            2 => Ok(TargetType::DropTargetSimple),
+            3 => Ok(TargetType::HitTargetRound),
+            4 => Ok(TargetType::HitTargetRectangle),
+            5 => Ok(TargetType::HitFatTargetRectangle),
+            6 => Ok(TargetType::HitFatTargetSquare),
+            7 => Ok(TargetType::DropTargetFlatSimple),
+            8 => Ok(TargetType::HitFatTargetSlim),
+            9 => Ok(TargetType::HitTargetSlim),
+            _ => Err(format!("Invalid TargetType value {}", value)),
         }
     }
 }
 
+impl From<u32> for TargetType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&TargetType> for u32 {
     fn from(value: &TargetType) -> Self {
         match value {
@@ -145,7 +153,7 @@ impl<'de> Deserialize<'de> for TargetType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct HitTarget {
     pub position: Vertex3D,
     pub size: Vertex3D,
@@ -444,7 +452,7 @@ impl BiffRead for HitTarget {
                     image = reader.get_string();
                 }
                 "TRTY" => {
-                    target_type = reader.get_u32().into();
+                    target_type = reader.get_u32_checked();
                 }
                 "NAME" => {
                     name = reader.get_wide_string();
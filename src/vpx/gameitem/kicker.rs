@@ -1,4 +1,4 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite};
+use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom};
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -15,21 +15,29 @@ pub enum KickerType {
     Cup2 = 6,
 }
 
-impl From<u32> for KickerType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for KickerType {
+    const FALLBACK: Self = KickerType::Invisible;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => KickerType::Invisible,
-            1 => KickerType::Hole,
-            2 => KickerType::Cup,
-            3 => KickerType::HoleSimple,
-            4 => KickerType::Williams,
-            5 => KickerType::Gottlieb,
-            6 => KickerType::Cup2,
-            _ => panic!("Invalid KickerType value {}", value),
+            0 => Ok(KickerType::Invisible),
+            1 => Ok(KickerType::Hole),
+            2 => Ok(KickerType::Cup),
+            3 => Ok(KickerType::HoleSimple),
+            4 => Ok(KickerType::Williams),
+            5 => Ok(KickerType::Gottlieb),
+            6 => Ok(KickerType::Cup2),
+            _ => Err(format!("Invalid KickerType value {}", value)),
         }
     }
 }
 
+impl From<u32> for KickerType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&KickerType> for u32 {
     fn from(value: &KickerType) -> Self {
         match value {
@@ -130,21 +138,21 @@ impl<'de> Deserialize<'de> for KickerType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Kicker {
-    center: Vertex2D,
-    radius: f32,
+    pub center: Vertex2D,
+    pub radius: f32,
     is_timer_enabled: bool,
     timer_interval: i32,
-    material: String,
-    surface: String,
-    is_enabled: bool,
+    pub material: String,
+    pub(crate) surface: String,
+    pub is_enabled: bool,
     pub name: String,
-    kicker_type: KickerType,
+    pub kicker_type: KickerType,
     scatter: f32,
     hit_accuracy: f32,
     hit_height: Option<f32>, // KHHI (was missing in 10.01)
-    orientation: f32,
+    pub orientation: f32,
     fall_through: bool,
     legacy_mode: bool,
 
@@ -307,7 +315,7 @@ impl BiffRead for Kicker {
                     kicker.name = reader.get_wide_string();
                 }
                 "TYPE" => {
-                    kicker.kicker_type = reader.get_u32().into();
+                    kicker.kicker_type = reader.get_u32_checked();
                 }
                 "KSCT" => {
                     kicker.scatter = reader.get_f32();
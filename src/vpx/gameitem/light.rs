@@ -1,6 +1,6 @@
 use crate::vpx::json::F32WithNanInf;
 use crate::vpx::{
-    biff::{self, BiffRead, BiffReader, BiffWrite},
+    biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom},
     color::Color,
 };
 use fake::Dummy;
@@ -14,16 +14,24 @@ pub enum ShadowMode {
     RaytracedBallShadows = 1,
 }
 
-impl From<u32> for ShadowMode {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for ShadowMode {
+    const FALLBACK: Self = ShadowMode::None;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => ShadowMode::None,
-            1 => ShadowMode::RaytracedBallShadows,
-            _ => panic!("Unknown value for ShadowMode: {}", value),
+            0 => Ok(ShadowMode::None),
+            1 => Ok(ShadowMode::RaytracedBallShadows),
+            _ => Err(format!("Unknown value for ShadowMode: {}", value)),
         }
     }
 }
 
+impl From<u32> for ShadowMode {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&ShadowMode> for u32 {
     fn from(value: &ShadowMode) -> Self {
         match value {
@@ -103,17 +111,25 @@ pub enum Fader {
     Incandescent = 2,
 }
 
-impl From<u32> for Fader {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for Fader {
+    const FALLBACK: Self = Fader::None;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => Fader::None,
-            1 => Fader::Linear,
-            2 => Fader::Incandescent,
-            _ => panic!("Unknown value for Fader: {}", value),
+            0 => Ok(Fader::None),
+            1 => Ok(Fader::Linear),
+            2 => Ok(Fader::Incandescent),
+            _ => Err(format!("Unknown value for Fader: {}", value)),
         }
     }
 }
 
+impl From<u32> for Fader {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&Fader> for u32 {
     fn from(value: &Fader) -> Self {
         match value {
@@ -190,7 +206,7 @@ impl<'de> Deserialize<'de> for Fader {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Light {
     pub center: Vertex2D,    // VCEN
     pub height: Option<f32>, // HGHT added in 10.8
@@ -464,6 +480,82 @@ impl Default for Light {
     }
 }
 
+impl Light {
+    /// Light attenuation at a distance from the light center, as used by the VPinball light
+    /// shader: `pow(1 - saturate(distance / falloff_radius), falloff_power)`
+    pub fn falloff_attenuation(&self, distance: f32) -> f32 {
+        if self.falloff_radius <= 0.0 {
+            return 0.0;
+        }
+        let len = (distance / self.falloff_radius).clamp(0.0, 1.0);
+        (1.0 - len).powf(self.falloff_power)
+    }
+
+    /// Linear rgb light contribution at a distance from the light center, not clamped.
+    ///
+    /// The color fades from `color2` at the center to `color` at the falloff radius.
+    pub fn falloff_color(&self, distance: f32) -> [f32; 3] {
+        self.falloff_color_scaled(distance, self.intensity)
+    }
+
+    /// Renders a square preview of the light falloff as seen from above, the light center
+    /// being in the middle of the image and the falloff radius touching the image borders.
+    ///
+    /// The color channels hold the sRGB encoded light contribution, the alpha channel the light
+    /// coverage.
+    pub fn falloff_preview(&self, size: u32) -> ::image::RgbaImage {
+        self.render_preview(size, self.intensity)
+    }
+
+    /// Like [`Light::falloff_preview`] but for the light transmitted through the playfield,
+    /// which is scaled by the transmission scale.
+    pub fn transmission_preview(&self, size: u32) -> ::image::RgbaImage {
+        self.render_preview(size, self.intensity * self.transmission_scale)
+    }
+
+    fn falloff_color_scaled(&self, distance: f32, intensity: f32) -> [f32; 3] {
+        let len = if self.falloff_radius <= 0.0 {
+            1.0
+        } else {
+            (distance / self.falloff_radius).clamp(0.0, 1.0)
+        };
+        let atten = self.falloff_attenuation(distance) * intensity;
+        let mix = len.sqrt();
+        let edge = self.color.to_linear();
+        let center = self.color2.to_linear();
+        [0, 1, 2].map(|i| (center[i] + (edge[i] - center[i]) * mix) * atten)
+    }
+
+    fn render_preview(&self, size: u32, intensity: f32) -> ::image::RgbaImage {
+        let half = size as f32 / 2.0;
+        ::image::RgbaImage::from_fn(size, size, |x, y| {
+            // sample at the pixel center
+            let dx = (x as f32 + 0.5 - half) / half;
+            let dy = (y as f32 + 0.5 - half) / half;
+            let distance = (dx * dx + dy * dy).sqrt() * self.falloff_radius;
+            let color = self.falloff_color_scaled(distance, intensity);
+            let alpha = self.falloff_attenuation(distance) * intensity;
+            let to_u8 = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            ::image::Rgba([
+                to_u8(linear_to_srgb(color[0])),
+                to_u8(linear_to_srgb(color[1])),
+                to_u8(linear_to_srgb(color[2])),
+                to_u8(alpha),
+            ])
+        })
+    }
+}
+
+/// Encodes a linear color channel as sRGB, the inverse of [`Color::to_linear`]
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 impl BiffRead for Light {
     fn biff_read(reader: &mut BiffReader<'_>) -> Light {
         let mut light = Light::default();
@@ -510,8 +602,8 @@ impl BiffRead for Light {
                 "BMSC" => light.mesh_radius = reader.get_f32(),
                 "BMVA" => light.bulb_modulate_vs_add = reader.get_f32(),
                 "BHHI" => light.bulb_halo_height = reader.get_f32(),
-                "SHDW" => light.shadows = Some(reader.get_u32().into()),
-                "FADE" => light.fader = Some(reader.get_u32().into()),
+                "SHDW" => light.shadows = Some(reader.get_u32_checked()),
+                "FADE" => light.fader = Some(reader.get_u32_checked()),
                 "VSBL" => light.visible = Some(reader.get_bool()),
                 // many of these
                 "DPNT" => {
@@ -654,6 +746,67 @@ mod tests {
         assert_eq!(light, light_read);
     }
 
+    #[test]
+    fn test_falloff() {
+        let light = Light {
+            falloff_radius: 100.0,
+            falloff_power: 2.0,
+            color: Color::rgb(255, 0, 0),
+            color2: Color::rgb(0, 0, 255),
+            intensity: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(light.falloff_attenuation(0.0), 1.0);
+        assert_eq!(light.falloff_attenuation(50.0), 0.25);
+        assert_eq!(light.falloff_attenuation(150.0), 0.0);
+        assert_eq!(light.falloff_color(0.0), [0.0, 0.0, 2.0]);
+        assert_eq!(light.falloff_color(100.0), [0.0, 0.0, 0.0]);
+        let color = light.falloff_color(25.0);
+        // sqrt(0.25) = 0.5 mix, attenuation 0.5625 * 2
+        assert_eq!(color, [0.5625, 0.0, 0.5625]);
+    }
+
+    #[test]
+    fn test_falloff_preview() {
+        let light = Light {
+            falloff_radius: 50.0,
+            falloff_power: 2.0,
+            color: Color::WHITE,
+            color2: Color::WHITE,
+            intensity: 1.0,
+            transmission_scale: 0.5,
+            ..Default::default()
+        };
+        let preview = light.falloff_preview(64);
+        assert_eq!(preview.dimensions(), (64, 64));
+        let center = preview.get_pixel(32, 32);
+        let corner = preview.get_pixel(0, 0);
+        assert!(center[3] > 240);
+        assert_eq!(corner[3], 0);
+        let transmission = light.transmission_preview(64);
+        assert!(transmission.get_pixel(32, 32)[3] < center[3]);
+    }
+
+    #[test]
+    fn test_falloff_preview_is_srgb() {
+        // without falloff the whole light has the same contribution
+        let light = Light {
+            falloff_radius: 50.0,
+            falloff_power: 0.0,
+            color: Color::WHITE,
+            color2: Color::WHITE,
+            intensity: 0.5,
+            ..Default::default()
+        };
+        let preview = light.falloff_preview(4);
+        assert_eq!(
+            preview.get_pixel(1, 1),
+            &::image::Rgba([188, 188, 188, 128])
+        );
+        let [r, _, _] = Color::rgb(188, 0, 0).to_linear();
+        assert!((linear_to_srgb(r) * 255.0 - 188.0).abs() < 1e-3);
+    }
+
     #[test]
     fn test_fader_json() {
         let sizing_type = Fader::Linear;
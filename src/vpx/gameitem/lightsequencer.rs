@@ -4,13 +4,13 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::vertex2d::Vertex2D;
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct LightSequencer {
-    center: Vertex2D,
-    collection: String,
-    pos_x: f32,
-    pos_y: f32,
-    update_interval: u32,
+    pub center: Vertex2D,
+    pub collection: String,
+    pub pos_x: f32,
+    pub pos_y: f32,
+    pub update_interval: u32,
     is_timer_enabled: bool,
     timer_interval: i32,
     pub name: String,
@@ -0,0 +1,376 @@
+//! Uniform access to the physics flags of items the ball can collide with
+//!
+//! Every item type names and stores these flags a bit differently, some flags were only
+//! added in later versions and are optional. Getters return `None` when the item type has no
+//! such flag or when the file does not store it. Spinners and plungers always collide with
+//! the ball, they have none of the flags.
+
+use super::bumper::Bumper;
+use super::flipper::Flipper;
+use super::gate::Gate;
+use super::hittarget::HitTarget;
+use super::kicker::Kicker;
+use super::plunger::Plunger;
+use super::primitive::Primitive;
+use super::ramp::Ramp;
+use super::rubber::Rubber;
+use super::spinner::Spinner;
+use super::trigger::Trigger;
+use super::wall::Wall;
+
+pub trait PhysicsFlags {
+    fn is_collidable(&self) -> Option<bool>;
+
+    /// Returns `false` if the item type has no collidable flag
+    fn set_collidable(&mut self, collidable: bool) -> bool;
+
+    /// Whether the item fires `_Hit` events to the script
+    fn hit_event(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns `false` if the item type has no hit event flag
+    fn set_hit_event(&mut self, _hit_event: bool) -> bool {
+        false
+    }
+
+    /// Whether the physics values of the item are used instead of its physics material
+    fn overwrite_physics(&self) -> Option<bool> {
+        None
+    }
+
+    /// Returns `false` if the item type has no overwrite physics flag
+    fn set_overwrite_physics(&mut self, _overwrite_physics: bool) -> bool {
+        false
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl PhysicsFlags for Wall {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        Some(self.hit_event)
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.hit_event = hit_event;
+        true
+    }
+
+    fn overwrite_physics(&self) -> Option<bool> {
+        self.overwrite_physics
+    }
+
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        self.overwrite_physics = Some(overwrite_physics);
+        true
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        self.physics_material.as_deref()
+    }
+}
+
+impl PhysicsFlags for Rubber {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        Some(self.hit_event)
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.hit_event = hit_event;
+        true
+    }
+
+    fn overwrite_physics(&self) -> Option<bool> {
+        self.overwrite_physics
+    }
+
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        self.overwrite_physics = Some(overwrite_physics);
+        true
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        self.physics_material.as_deref()
+    }
+}
+
+impl PhysicsFlags for Primitive {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        Some(self.hit_event)
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.hit_event = hit_event;
+        true
+    }
+
+    fn overwrite_physics(&self) -> Option<bool> {
+        self.overwrite_physics
+    }
+
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        self.overwrite_physics = Some(overwrite_physics);
+        true
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        self.physics_material.as_deref()
+    }
+}
+
+impl PhysicsFlags for Ramp {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        self.hit_event
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.hit_event = Some(hit_event);
+        true
+    }
+
+    fn overwrite_physics(&self) -> Option<bool> {
+        self.overwrite_physics
+    }
+
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        self.overwrite_physics = Some(overwrite_physics);
+        true
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        self.physics_material.as_deref()
+    }
+}
+
+impl PhysicsFlags for HitTarget {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        Some(self.use_hit_event)
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.use_hit_event = hit_event;
+        true
+    }
+
+    fn overwrite_physics(&self) -> Option<bool> {
+        self.overwrite_physics
+    }
+
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        self.overwrite_physics = Some(overwrite_physics);
+        true
+    }
+
+    fn physics_material(&self) -> Option<&str> {
+        self.physics_material.as_deref()
+    }
+}
+
+impl PhysicsFlags for Bumper {
+    fn is_collidable(&self) -> Option<bool> {
+        self.is_collidable
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = Some(collidable);
+        true
+    }
+
+    fn hit_event(&self) -> Option<bool> {
+        self.hit_event
+    }
+
+    fn set_hit_event(&mut self, hit_event: bool) -> bool {
+        self.hit_event = Some(hit_event);
+        true
+    }
+}
+
+impl PhysicsFlags for Gate {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_collidable)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_collidable = collidable;
+        true
+    }
+}
+
+impl PhysicsFlags for Flipper {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_enabled)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_enabled = collidable;
+        true
+    }
+
+    /// The flipper stores which of the global physics sets replaces its values, 0 for none
+    fn overwrite_physics(&self) -> Option<bool> {
+        Some(self.override_physics != 0)
+    }
+
+    /// Keeps the selected physics set, enabling uses the first set
+    fn set_overwrite_physics(&mut self, overwrite_physics: bool) -> bool {
+        if !overwrite_physics {
+            self.override_physics = 0;
+        } else if self.override_physics == 0 {
+            self.override_physics = 1;
+        }
+        true
+    }
+}
+
+impl PhysicsFlags for Kicker {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_enabled)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_enabled = collidable;
+        true
+    }
+}
+
+impl PhysicsFlags for Trigger {
+    fn is_collidable(&self) -> Option<bool> {
+        Some(self.is_enabled)
+    }
+
+    fn set_collidable(&mut self, collidable: bool) -> bool {
+        self.is_enabled = collidable;
+        true
+    }
+}
+
+impl PhysicsFlags for Spinner {
+    fn is_collidable(&self) -> Option<bool> {
+        None
+    }
+
+    fn set_collidable(&mut self, _collidable: bool) -> bool {
+        false
+    }
+}
+
+impl PhysicsFlags for Plunger {
+    fn is_collidable(&self) -> Option<bool> {
+        None
+    }
+
+    fn set_collidable(&mut self, _collidable: bool) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::GameItemEnum;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn flags_on_all_colliding_items() {
+        let mut items = vec![
+            GameItemEnum::Wall(Wall::default()),
+            GameItemEnum::Rubber(Rubber::default()),
+            GameItemEnum::Primitive(Faker.fake()),
+            GameItemEnum::Ramp(Ramp::default()),
+            GameItemEnum::HitTarget(HitTarget::default()),
+            GameItemEnum::Bumper(Bumper::default()),
+            GameItemEnum::Gate(Gate::default()),
+            GameItemEnum::Flipper(Flipper::default()),
+            GameItemEnum::Kicker(Kicker::default()),
+            GameItemEnum::Trigger(Trigger::default()),
+            GameItemEnum::Spinner(Spinner::default()),
+            GameItemEnum::Plunger(Plunger::default()),
+        ];
+        for item in &mut items {
+            let flags = item.physics_flags_mut().unwrap();
+            let has_collidable = flags.set_collidable(false);
+            let has_hit_event = flags.set_hit_event(true);
+            let has_overwrite_physics = flags.set_overwrite_physics(true);
+
+            let flags = item.physics_flags().unwrap();
+            assert_eq!(
+                flags.is_collidable(),
+                Some(false).filter(|_| has_collidable)
+            );
+            assert_eq!(flags.hit_event(), Some(true).filter(|_| has_hit_event));
+            assert_eq!(
+                flags.overwrite_physics(),
+                Some(true).filter(|_| has_overwrite_physics)
+            );
+        }
+        let GameItemEnum::Gate(gate) = &items[6] else {
+            unreachable!()
+        };
+        assert!(!gate.is_collidable);
+        let GameItemEnum::HitTarget(target) = &items[4] else {
+            unreachable!()
+        };
+        assert!(target.use_hit_event);
+        let GameItemEnum::Flipper(flipper) = &items[7] else {
+            unreachable!()
+        };
+        assert!(!flipper.is_enabled);
+        assert_eq!(flipper.override_physics, 1);
+    }
+
+    #[test]
+    fn no_flags_for_other_items() {
+        let light = GameItemEnum::Light(Default::default());
+        assert!(light.physics_flags().is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite};
+use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom};
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -14,18 +14,26 @@ pub enum PlungerType {
     Custom = 3,
 }
 
-impl From<u32> for PlungerType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for PlungerType {
+    const FALLBACK: Self = PlungerType::Unknown;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => PlungerType::Unknown,
-            1 => PlungerType::Modern,
-            2 => PlungerType::Flat,
-            3 => PlungerType::Custom,
-            _ => panic!("Invalid PlungerType value {}", value),
+            0 => Ok(PlungerType::Unknown),
+            1 => Ok(PlungerType::Modern),
+            2 => Ok(PlungerType::Flat),
+            3 => Ok(PlungerType::Custom),
+            _ => Err(format!("Invalid PlungerType value {}", value)),
         }
     }
 }
 
+impl From<u32> for PlungerType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&PlungerType> for u32 {
     fn from(value: &PlungerType) -> Self {
         match value {
@@ -106,19 +114,19 @@ impl<'de> Deserialize<'de> for PlungerType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Plunger {
     pub center: Vertex2D,
-    width: f32,
-    height: f32,
+    pub width: f32,
+    pub height: f32,
     z_adjust: f32,
-    stroke: f32,
+    pub stroke: f32,
     speed_pull: f32,
     speed_fire: f32,
     plunger_type: PlungerType,
     anim_frames: u32,
-    material: String,
-    image: String,
+    pub material: String,
+    pub image: String,
     mech_strength: f32,
     is_mech_plunger: bool,
     auto_plunger: bool,
@@ -127,9 +135,9 @@ pub struct Plunger {
     momentum_xfer: f32,
     is_timer_enabled: bool,
     timer_interval: i32,
-    is_visible: bool,
+    pub is_visible: bool,
     is_reflection_enabled: Option<bool>, // REEN (was missing in 10.01)
-    surface: String,
+    pub(crate) surface: String,
     pub name: String,
     tip_shape: String,
     rod_diam: f32,
@@ -369,7 +377,7 @@ impl BiffRead for Plunger {
                     plunger.speed_fire = reader.get_f32();
                 }
                 "TYPE" => {
-                    plunger.plunger_type = reader.get_u32().into();
+                    plunger.plunger_type = reader.get_u32_checked();
                 }
                 "ANFR" => {
                     plunger.anim_frames = reader.get_u32();
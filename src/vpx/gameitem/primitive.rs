@@ -7,7 +7,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::vertex3d::Vertex3D;
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Primitive {
     pub position: Vertex3D,                                       // 0 VPOS
     pub size: Vertex3D,                                           // 1 VSIZ
@@ -1,4 +1,4 @@
-use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite};
+use crate::vpx::biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom};
 use crate::vpx::gameitem::ramp_image_alignment::RampImageAlignment;
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -15,20 +15,28 @@ pub enum RampType {
     OneWire = 5,
 }
 
-impl From<u32> for RampType {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for RampType {
+    const FALLBACK: Self = RampType::Flat;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => RampType::Flat,
-            1 => RampType::FourWire,
-            2 => RampType::TwoWire,
-            3 => RampType::ThreeWireLeft,
-            4 => RampType::ThreeWireRight,
-            5 => RampType::OneWire,
-            _ => panic!("Invalid RampType {}", value),
+            0 => Ok(RampType::Flat),
+            1 => Ok(RampType::FourWire),
+            2 => Ok(RampType::TwoWire),
+            3 => Ok(RampType::ThreeWireLeft),
+            4 => Ok(RampType::ThreeWireRight),
+            5 => Ok(RampType::OneWire),
+            _ => Err(format!("Invalid RampType {}", value)),
         }
     }
 }
 
+impl From<u32> for RampType {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&RampType> for u32 {
     fn from(value: &RampType) -> Self {
         match value {
@@ -123,7 +131,7 @@ impl<'de> Deserialize<'de> for RampType {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Ramp {
     pub height_bottom: f32,                  // 1
     pub height_top: f32,                     // 2
@@ -156,7 +164,7 @@ pub struct Ramp {
     pub physics_material: Option<String>,    // MAPH 29 (added in 10.?)
     pub overwrite_physics: Option<bool>,     // OVPH 30 (added in 10.?)
 
-    drag_points: Vec<DragPoint>,
+    pub drag_points: Vec<DragPoint>,
 
     // these are shared between all items
     pub is_locked: bool,
@@ -378,7 +386,7 @@ impl BiffRead for Ramp {
                     ramp.timer_interval = reader.get_i32();
                 }
                 "TYPE" => {
-                    ramp.ramp_type = reader.get_u32().into();
+                    ramp.ramp_type = reader.get_u32_checked();
                 }
                 "NAME" => {
                     ramp.name = reader.get_wide_string();
@@ -387,7 +395,7 @@ impl BiffRead for Ramp {
                     ramp.image = reader.get_string();
                 }
                 "ALGN" => {
-                    ramp.image_alignment = reader.get_u32().into();
+                    ramp.image_alignment = reader.get_u32_checked();
                 }
                 "IMGW" => {
                     ramp.image_walls = reader.get_bool();
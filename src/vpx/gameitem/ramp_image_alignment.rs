@@ -1,3 +1,4 @@
+use crate::vpx::biff::CheckedFrom;
 use fake::Dummy;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -10,17 +11,25 @@ pub enum RampImageAlignment {
     Unknown = 2,
 }
 
-impl From<u32> for RampImageAlignment {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for RampImageAlignment {
+    const FALLBACK: Self = RampImageAlignment::World;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => RampImageAlignment::World,
-            1 => RampImageAlignment::Wrap,
-            2 => RampImageAlignment::Unknown,
-            _ => panic!("Invalid RampImageAlignment {}", value),
+            0 => Ok(RampImageAlignment::World),
+            1 => Ok(RampImageAlignment::Wrap),
+            2 => Ok(RampImageAlignment::Unknown),
+            _ => Err(format!("Invalid RampImageAlignment {}", value)),
         }
     }
 }
 
+impl From<u32> for RampImageAlignment {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&RampImageAlignment> for u32 {
     fn from(value: &RampImageAlignment) -> Self {
         match value {
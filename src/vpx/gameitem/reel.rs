@@ -7,7 +7,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::vertex2d::Vertex2D;
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Reel {
     ver1: Vertex2D,    // position on map (top right corner)
     ver2: Vertex2D,    // position on map (top right corner)
@@ -15,8 +15,8 @@ pub struct Reel {
     is_timer_enabled: bool,
     timer_interval: i32,
     is_transparent: bool, // is the background transparent
-    image: String,
-    sound: String, // sound to play for each turn of a digit
+    pub image: String,
+    pub sound: String, // sound to play for each turn of a digit
     pub name: String,
     width: f32,        // size of each reel
     height: f32,       // size of each reel
@@ -26,7 +26,7 @@ pub struct Reel {
     digit_range: u32,  // max number of digits per reel (usually 9)
     update_interval: u32,
     use_image_grid: bool,
-    is_visible: bool,
+    pub is_visible: bool,
     images_per_grid_row: u32,
 
     // these are shared between all items
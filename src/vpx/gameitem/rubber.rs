@@ -4,7 +4,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::dragpoint::DragPoint;
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Rubber {
     pub height: f32,
     pub hit_height: Option<f32>, // HTHI (added in 10.?)
@@ -38,7 +38,7 @@ pub struct Rubber {
     // default "Layer_{editor_layer + 1}"
     pub editor_layer_visibility: Option<bool>,
 
-    drag_points: Vec<DragPoint>,
+    pub drag_points: Vec<DragPoint>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -4,23 +4,23 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::vertex2d::Vertex2D;
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Spinner {
-    center: Vertex2D,
-    rotation: f32,
+    pub center: Vertex2D,
+    pub rotation: f32,
     is_timer_enabled: bool,
     timer_interval: i32,
-    height: f32,
-    length: f32,
-    damping: f32,
-    angle_max: f32,
-    angle_min: f32,
-    elasticity: f32,
-    is_visible: bool,
+    pub height: f32,
+    pub length: f32,
+    pub damping: f32,
+    pub angle_max: f32,
+    pub angle_min: f32,
+    pub elasticity: f32,
+    pub is_visible: bool,
     show_bracket: bool,
-    material: String,
-    image: String,
-    surface: String,
+    pub material: String,
+    pub image: String,
+    pub(crate) surface: String,
     pub name: String,
     pub is_reflection_enabled: Option<bool>, // added in ?
 
@@ -1,6 +1,6 @@
 use crate::vpx::gameitem::font::FontJson;
 use crate::vpx::{
-    biff::{self, BiffRead, BiffReader, BiffWrite},
+    biff::{self, BiffRead, BiffReader, BiffWrite, CheckedFrom},
     color::Color,
     gameitem::font::Font,
 };
@@ -17,17 +17,25 @@ enum TextAlignment {
     Right = 2,
 }
 
-impl From<u32> for TextAlignment {
-    fn from(value: u32) -> Self {
+impl CheckedFrom<u32> for TextAlignment {
+    const FALLBACK: Self = TextAlignment::Left;
+
+    fn checked_from(value: u32) -> Result<Self, String> {
         match value {
-            0 => TextAlignment::Left,
-            1 => TextAlignment::Center,
-            2 => TextAlignment::Right,
-            _ => panic!("Invalid value for TextAlignment: {}", value),
+            0 => Ok(TextAlignment::Left),
+            1 => Ok(TextAlignment::Center),
+            2 => Ok(TextAlignment::Right),
+            _ => Err(format!("Invalid value for TextAlignment: {}", value)),
         }
     }
 }
 
+impl From<u32> for TextAlignment {
+    fn from(value: u32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&TextAlignment> for u32 {
     fn from(value: &TextAlignment) -> Self {
         match value {
@@ -103,14 +111,14 @@ impl<'de> Deserialize<'de> for TextAlignment {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct TextBox {
     ver1: Vertex2D,         // VER1
     ver2: Vertex2D,         // VER2
     back_color: Color,      // CLRB
     font_color: Color,      // CLRF
     intensity_scale: f32,   // INSC
-    text: String,           // TEXT
+    pub text: String,       // TEXT
     is_timer_enabled: bool, // TMON
     timer_interval: i32,    // TMIN
     pub name: String,       // NAME
@@ -211,6 +219,16 @@ impl<'de> Deserialize<'de> for TextBox {
     }
 }
 
+impl TextBox {
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn set_font(&mut self, font: Font) {
+        self.font = font;
+    }
+}
+
 impl Default for TextBox {
     fn default() -> Self {
         Self {
@@ -275,7 +293,7 @@ impl BiffRead for TextBox {
                     textbox.name = reader.get_wide_string();
                 }
                 "ALGN" => {
-                    textbox.align = reader.get_u32().into();
+                    textbox.align = reader.get_u32_checked();
                 }
                 "TRNS" => {
                     textbox.is_transparent = reader.get_bool();
@@ -133,7 +133,7 @@ impl<'de> Deserialize<'de> for TriggerShape {
     }
 }
 
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Trigger {
     pub center: Vertex2D,
     pub radius: f32,
@@ -168,7 +168,7 @@ pub struct Trigger {
     // default "Layer_{editor_layer + 1}"
     pub editor_layer_visibility: Option<bool>,
 
-    drag_points: Vec<DragPoint>,
+    pub drag_points: Vec<DragPoint>,
 }
 
 #[derive(Serialize, Deserialize)]
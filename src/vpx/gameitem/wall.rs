@@ -7,7 +7,7 @@ use super::dragpoint::DragPoint;
 /**
  * Surface
  */
-#[derive(Debug, PartialEq, Dummy)]
+#[derive(Debug, PartialEq, Dummy, Clone)]
 pub struct Wall {
     pub hit_event: bool,
     pub is_droppable: bool,
@@ -49,7 +49,7 @@ pub struct Wall {
     // default "Layer_{editor_layer + 1}"
     pub editor_layer_visibility: Option<bool>,
 
-    drag_points: Vec<DragPoint>,
+    pub drag_points: Vec<DragPoint>,
 }
 
 #[derive(Serialize, Deserialize)]
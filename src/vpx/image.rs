@@ -2,6 +2,7 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use super::assetmeta::AssetMetadata;
 use super::biff::{self, BiffRead, BiffReader, BiffWrite, BiffWriter};
 
 #[derive(PartialEq, Clone)]
@@ -96,13 +97,186 @@ impl ImageData {
         self.link == Some(1)
     }
 
+    /// CRC-32 of the encoded image file (png, jpeg, webp, ...), `None` for bitmaps and links.
+    ///
+    /// Reading and writing vpx files and expanded directories never re-encodes these files,
+    /// so the checksum only changes when the image is replaced or explicitly converted, like
+    /// with [`crate::vpx::VpxFile::images_to_webp`].
+    pub fn checksum(&self) -> Option<u32> {
+        self.jpeg.as_ref().map(|jpeg| {
+            let mut crc = flate2::Crc::new();
+            crc.update(&jpeg.data);
+            crc.sum()
+        })
+    }
+
     pub(crate) fn ext(&self) -> String {
         // TODO we might want to also check the jpeg fsPath
-        match self.path.split('.').last() {
+        match self.path.split('.').next_back() {
             Some(ext) => ext.to_string(),
             None => "bin".to_string(),
         }
     }
+
+    /// The frames of an animated GIF or APNG, `None` for still images.
+    ///
+    /// Visual Pinball only shows the first frame, but the file is kept as is so the
+    /// animation survives reading and writing vpx files and expanded directories.
+    pub fn animation(&self) -> Option<ImageAnimation> {
+        self.jpeg
+            .as_ref()
+            .and_then(|jpeg| read_animation(&jpeg.data))
+    }
+}
+
+/// Frame timing of an animated image
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImageAnimation {
+    /// How long each frame is shown in milliseconds
+    pub frame_delays_ms: Vec<u32>,
+    /// How many times the animation is played, 0 means forever
+    pub loop_count: u32,
+}
+
+impl ImageAnimation {
+    pub fn frame_count(&self) -> usize {
+        self.frame_delays_ms.len()
+    }
+
+    /// Duration of a single play of the animation in milliseconds
+    pub fn duration_ms(&self) -> u32 {
+        self.frame_delays_ms.iter().sum()
+    }
+}
+
+/// Reads the frame timing from GIF or APNG data without decoding the frames.
+///
+/// Returns `None` for other formats, images with a single frame and data that can't be
+/// parsed.
+pub fn read_animation(data: &[u8]) -> Option<ImageAnimation> {
+    let animation = if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        read_gif_animation(data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        read_apng_animation(data)
+    } else {
+        None
+    }?;
+    if animation.frame_count() > 1 {
+        Some(animation)
+    } else {
+        None
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+fn read_u16_le(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u16_be(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32_be(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Size of a color table as indicated by the packed field of a GIF descriptor
+fn gif_color_table_size(packed: u8) -> usize {
+    if packed & 0x80 != 0 {
+        3 * (1 << ((packed & 0x07) + 1))
+    } else {
+        0
+    }
+}
+
+/// Returns the position after a sequence of GIF data sub-blocks
+fn skip_gif_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let size = *data.get(pos)? as usize;
+        pos += 1;
+        if size == 0 {
+            return Some(pos);
+        }
+        pos += size;
+    }
+}
+
+// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+fn read_gif_animation(data: &[u8]) -> Option<ImageAnimation> {
+    let mut pos = 13 + gif_color_table_size(*data.get(10)?);
+    let mut frame_delays_ms = Vec::new();
+    // a gif without the netscape extension plays once
+    let mut loop_count = 1;
+    let mut delay_ms = 0;
+    loop {
+        match *data.get(pos)? {
+            // extension
+            0x21 => {
+                let label = *data.get(pos + 1)?;
+                let block = pos + 2;
+                match label {
+                    // graphic control extension, the delay is in hundredths of a second
+                    0xF9 => delay_ms = read_u16_le(data, block + 2)? as u32 * 10,
+                    // application extension
+                    0xFF if data.get(block + 1..block + 12)? == b"NETSCAPE2.0" => {
+                        loop_count = read_u16_le(data, block + 14)? as u32;
+                    }
+                    _ => {}
+                }
+                pos = skip_gif_sub_blocks(data, block)?;
+            }
+            // image descriptor
+            0x2C => {
+                frame_delays_ms.push(delay_ms);
+                delay_ms = 0;
+                let color_table_size = gif_color_table_size(*data.get(pos + 9)?);
+                // skip the descriptor, color table and lzw minimum code size
+                pos = skip_gif_sub_blocks(data, pos + 10 + color_table_size + 1)?;
+            }
+            // trailer
+            0x3B => break,
+            _ => return None,
+        }
+    }
+    Some(ImageAnimation {
+        frame_delays_ms,
+        loop_count,
+    })
+}
+
+// https://wiki.mozilla.org/APNG_Specification
+fn read_apng_animation(data: &[u8]) -> Option<ImageAnimation> {
+    let mut pos = PNG_SIGNATURE.len();
+    let mut frame_delays_ms = Vec::new();
+    let mut loop_count = None;
+    while pos < data.len() {
+        let length = read_u32_be(data, pos)? as usize;
+        let chunk_type = data.get(pos + 4..pos + 8)?;
+        let chunk = pos + 8;
+        match chunk_type {
+            b"acTL" => loop_count = Some(read_u32_be(data, chunk + 4)?),
+            b"fcTL" => {
+                let numerator = read_u16_be(data, chunk + 20)? as u32;
+                // a denominator of 0 means hundredths of a second
+                let denominator = match read_u16_be(data, chunk + 22)? {
+                    0 => 100,
+                    d => d as u32,
+                };
+                frame_delays_ms.push(numerator * 1000 / denominator);
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        // data and crc
+        pos = chunk + length + 4;
+    }
+    // without an acTL chunk this is a regular png
+    loop_count.map(|loop_count| ImageAnimation {
+        frame_delays_ms,
+        loop_count,
+    })
 }
 
 #[derive(PartialEq, Debug, Serialize, Deserialize)]
@@ -135,6 +309,12 @@ pub(crate) struct ImageDataJson {
     // in case we have a duplicate name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) name_dedup: Option<String>,
+    // file name when it is not based on the name, see `WriteOptions::content_addressed_files`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) file: Option<String>,
+    // provenance, stored in the vpx file in an extra stream, see `crate::vpx::assetmeta`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) metadata: Option<AssetMetadata>,
 }
 
 impl ImageDataJson {
@@ -179,6 +359,8 @@ impl ImageDataJson {
             jpeg_internal_name,
             jpeg_path,
             name_dedup: None,
+            file: None,
+            metadata: None,
         }
     }
 
@@ -227,14 +409,14 @@ impl ImageDataJson {
 
     pub(crate) fn ext(&self) -> String {
         // TODO we might want to also check the jpeg fsPath
-        match self.path.split('.').last() {
+        match self.path.split('.').next_back() {
             Some(ext) => ext.to_string(),
             None => "bin".to_string(),
         }
     }
 
     pub(crate) fn is_bmp(&self) -> bool {
-        self.ext().to_ascii_lowercase() == "bmp"
+        self.ext().eq_ignore_ascii_case("bmp")
     }
 }
 
@@ -485,6 +667,93 @@ mod test {
         assert_eq!(reader.remaining_in_record(), 0);
     }
 
+    fn animated_gif() -> Vec<u8> {
+        use ::image::codecs::gif::{GifEncoder, Repeat};
+        use ::image::{Delay, Frame, RgbaImage};
+        let mut gif = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder.set_repeat(Repeat::Infinite).unwrap();
+            for (delay, color) in [(100, 0), (250, 255)] {
+                let buffer = RgbaImage::from_pixel(2, 2, ::image::Rgba([color, 0, 0, 255]));
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder
+                    .encode_frame(Frame::from_parts(buffer, 0, 0, delay))
+                    .unwrap();
+            }
+        }
+        gif
+    }
+
+    fn png_chunk(png: &mut Vec<u8>, chunk_type: &[u8], data: &[u8]) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        png.extend_from_slice(chunk_type);
+        png.extend_from_slice(data);
+        // the crc is not checked
+        png.extend_from_slice(&[0, 0, 0, 0]);
+    }
+
+    fn fctl(delay_num: u16, delay_den: u16) -> Vec<u8> {
+        let mut data = vec![0; 20];
+        data.extend_from_slice(&delay_num.to_be_bytes());
+        data.extend_from_slice(&delay_den.to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data
+    }
+
+    #[test]
+    fn test_read_animation_gif() {
+        let animation = read_animation(&animated_gif()).unwrap();
+        assert_eq!(
+            animation,
+            ImageAnimation {
+                frame_delays_ms: vec![100, 250],
+                loop_count: 0,
+            }
+        );
+        assert_eq!(animation.frame_count(), 2);
+        assert_eq!(animation.duration_ms(), 350);
+    }
+
+    #[test]
+    fn test_read_animation_apng() {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png_chunk(&mut png, b"IHDR", &[0; 13]);
+        let mut actl = 3u32.to_be_bytes().to_vec();
+        actl.extend_from_slice(&2u32.to_be_bytes());
+        png_chunk(&mut png, b"acTL", &actl);
+        png_chunk(&mut png, b"fcTL", &fctl(1, 10));
+        png_chunk(&mut png, b"IDAT", &[1, 2, 3]);
+        png_chunk(&mut png, b"fcTL", &fctl(5, 0));
+        png_chunk(&mut png, b"fdAT", &[1, 2, 3]);
+        png_chunk(&mut png, b"fcTL", &fctl(1, 1));
+        png_chunk(&mut png, b"fdAT", &[1, 2, 3]);
+        png_chunk(&mut png, b"IEND", &[]);
+        assert_eq!(
+            read_animation(&png),
+            Some(ImageAnimation {
+                frame_delays_ms: vec![100, 50, 1000],
+                loop_count: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_animation_still() {
+        let mut png = Vec::new();
+        ::image::RgbaImage::new(1, 1)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                ::image::ImageFormat::Png,
+            )
+            .unwrap();
+        assert_eq!(read_animation(&png), None);
+        assert_eq!(read_animation(&[1, 2, 3]), None);
+        // truncated data
+        let gif = animated_gif();
+        assert_eq!(read_animation(&gif[..gif.len() / 2]), None);
+    }
+
     #[test]
     fn test_write_read() {
         let image: ImageData = ImageData {
@@ -0,0 +1,236 @@
+//! How much each image of a table is used
+//!
+//! [`image_usage`] lists for every image the items and table settings using it and the area
+//! of the texture the meshes of walls, ramps and primitives cover. Images that are only used
+//! by invisible items, or not at all, are candidates for removal or a lower resolution.
+//!
+//! The covered area is the sum of the triangle areas in texture space, `1.0` is the whole
+//! texture once. Repeated textures add up to more than `1.0`. Items without a generated mesh,
+//! like flashers and decals, are listed but add nothing to the area.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::imageusage::image_usage;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for usage in image_usage(&vpx).unwrap() {
+//!     if usage.is_unused() || usage.only_invisible() {
+//!         println!("{} can be removed", usage.image);
+//!     }
+//! }
+//! ```
+
+use super::gamedata::TableImage;
+use super::gameitem::GameItemEnum;
+use super::mesh::ramp::ramp;
+use super::mesh::wall::{wall, WallMeshes};
+use super::mesh::Mesh;
+use super::VPX;
+use std::io;
+
+/// A game item using an image
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImageUser {
+    pub item: String,
+    pub item_type: String,
+    /// Items without a visibility setting count as visible
+    pub visible: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImageUsage {
+    pub image: String,
+    pub users: Vec<ImageUser>,
+    pub table_images: Vec<TableImage>,
+    /// The area of the texture covered by the item meshes
+    pub uv_area: f32,
+}
+
+impl ImageUsage {
+    pub fn is_unused(&self) -> bool {
+        self.users.is_empty() && self.table_images.is_empty()
+    }
+
+    /// Only used by items that are not rendered
+    pub fn only_invisible(&self) -> bool {
+        !self.users.is_empty()
+            && self.table_images.is_empty()
+            && self.users.iter().all(|user| !user.visible)
+    }
+}
+
+/// The usage of every image in the table, in the order of the images.
+///
+/// Names are matched case-insensitive, like Visual Pinball does.
+pub fn image_usage(vpx: &VPX) -> io::Result<Vec<ImageUsage>> {
+    let mut usages: Vec<ImageUsage> = vpx
+        .images
+        .iter()
+        .map(|image| ImageUsage {
+            image: image.name.clone(),
+            users: vec![],
+            table_images: vec![],
+            uv_area: 0.0,
+        })
+        .collect();
+    let index = |name: &str| {
+        usages
+            .iter()
+            .position(|usage| usage.image.eq_ignore_ascii_case(name))
+    };
+    let mut table_images = Vec::new();
+    for (table_image, name) in vpx.gamedata.table_images() {
+        if let Some(i) = index(name) {
+            table_images.push((i, table_image));
+        }
+    }
+    let mut users = Vec::new();
+    for item in &vpx.gameitems {
+        let mut names = item.referenced_images();
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        for name in names {
+            if let Some(i) = index(name) {
+                users.push((i, item));
+            }
+        }
+    }
+    let mut areas = Vec::new();
+    for item in &vpx.gameitems {
+        for (name, area) in item_uv_areas(vpx, item)? {
+            if let Some(i) = index(name) {
+                areas.push((i, area));
+            }
+        }
+    }
+
+    for (i, table_image) in table_images {
+        usages[i].table_images.push(table_image);
+    }
+    for (i, item) in users {
+        usages[i].users.push(ImageUser {
+            item: item.name().to_string(),
+            item_type: item.type_name(),
+            visible: item.is_visible().unwrap_or(true),
+        });
+    }
+    for (i, area) in areas {
+        usages[i].uv_area += area;
+    }
+    Ok(usages)
+}
+
+/// The texture area of the meshes of an item per image name
+fn item_uv_areas<'a>(vpx: &VPX, item: &'a GameItemEnum) -> io::Result<Vec<(&'a str, f32)>> {
+    let areas = match item {
+        GameItemEnum::Wall(w) => {
+            let WallMeshes { side, top } = wall(vpx, w);
+            vec![
+                (w.image.as_str(), uv_area(&top)),
+                (w.side_image.as_str(), uv_area(&side)),
+            ]
+        }
+        GameItemEnum::Ramp(r) => vec![(r.image.as_str(), uv_area(&ramp(vpx, r)))],
+        GameItemEnum::Primitive(p) => Mesh::from_primitive(p)?
+            .map(|mesh| (p.image.as_str(), uv_area(&mesh)))
+            .into_iter()
+            .collect(),
+        _ => vec![],
+    };
+    Ok(areas
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .collect())
+}
+
+/// The sum of the triangle areas in texture space
+fn uv_area(mesh: &Mesh) -> f32 {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|t| {
+            let [u0, v0] = mesh.vertices[t[0] as usize].uv;
+            let [u1, v1] = mesh.vertices[t[1] as usize].uv;
+            let [u2, v2] = mesh.vertices[t[2] as usize].uv;
+            ((u1 - u0) * (v2 - v0) - (u2 - u0) * (v1 - v0)).abs() / 2.0
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::decal::Decal;
+    use crate::vpx::gameitem::primitive::Primitive;
+    use crate::vpx::image::ImageData;
+    use crate::vpx::mesh::Vertex;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    fn image(name: &str) -> ImageData {
+        ImageData {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn usage_per_image() -> io::Result<()> {
+        let mut vpx = VPX {
+            images: vec![
+                image("Playfield"),
+                image("Quad"),
+                image("Hidden"),
+                image("Unused"),
+            ],
+            ..Default::default()
+        };
+        vpx.gamedata
+            .set_playfield_image(Some("playfield".to_string()));
+
+        let vertex = |u: f32, v: f32| Vertex {
+            position: [u, v, 0.0],
+            normal: [0.0, 0.0, 1.0],
+            uv: [u, v],
+        };
+        let quad = Mesh {
+            name: "quad".to_string(),
+            vertices: vec![
+                vertex(0.0, 0.0),
+                vertex(0.5, 0.0),
+                vertex(0.5, 0.5),
+                vertex(0.0, 0.5),
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        let primitive = |name: &str, image: &str, is_visible: bool| -> io::Result<GameItemEnum> {
+            let mut primitive: Primitive = Faker.fake();
+            primitive.name = name.to_string();
+            primitive.image = image.to_string();
+            primitive.is_visible = is_visible;
+            primitive.compressed_animation_vertices_len = None;
+            primitive.compressed_animation_vertices_data = None;
+            quad.write_to_primitive(&mut primitive)?;
+            Ok(GameItemEnum::Primitive(primitive))
+        };
+        vpx.add_game_item(primitive("Quad", "QUAD", true)?);
+        vpx.add_game_item(primitive("Hidden", "Hidden", false)?);
+        let mut decal = Decal::default();
+        decal.name = "Decal".to_string();
+        decal.image = "Quad".to_string();
+        vpx.add_game_item(GameItemEnum::Decal(decal));
+
+        let usages = image_usage(&vpx)?;
+        assert_eq!(usages[0].table_images, vec![TableImage::Playfield]);
+        assert!(usages[0].users.is_empty());
+        let users: Vec<&str> = usages[1].users.iter().map(|u| u.item.as_str()).collect();
+        assert_eq!(users, vec!["Quad", "Decal"]);
+        assert_eq!(usages[1].uv_area, 0.25);
+        assert!(!usages[1].only_invisible());
+        assert!(usages[2].only_invisible());
+        assert_eq!(usages[2].users[0].item_type, "Primitive");
+        assert!(usages[3].is_unused());
+        Ok(())
+    }
+}
@@ -0,0 +1,617 @@
+//! Importing game items from one table into another
+//!
+//! Items are copied together with the images and materials they use. When a name already
+//! exists in the target table the [`ConflictStrategy`] decides what happens. Resources that
+//! are identical in both tables are always reused.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx::import::{self, ConflictStrategy, Selection};
+//! use vpin::vpx::read;
+//!
+//! let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+//! let from = read(&path).unwrap();
+//! let mut into = read(&path).unwrap();
+//! let mut selection = Selection::items(&["LeftFlipper", "RightFlipper"]);
+//! selection.on_conflict = ConflictStrategy::Rename;
+//! let result = import::items(&from, &mut into, &selection).unwrap();
+//! println!("imported {} items", result.items.len());
+//! ```
+
+use std::io;
+
+use super::gamedata::GameData;
+use super::material::{Material, SaveMaterial, SavePhysicsMaterial};
+use super::sound::SoundData;
+use super::VPX;
+
+/// What to do when a name already exists in the target table
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ConflictStrategy {
+    /// Keep what is in the target table, imported items will use the existing resource.
+    /// Conflicting items are not imported.
+    #[default]
+    Skip,
+    /// Overwrite what is in the target table
+    Replace,
+    /// Import under a new name by adding a numeric suffix, eg `Flipper_2`
+    Rename,
+}
+
+/// What to import with [`items`]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Selection {
+    /// Names of the game items to import
+    pub items: Vec<String>,
+    /// Names of the sounds to import.
+    /// The sounds of imported reels come along, other sounds are only referenced from the
+    /// script, so they have to be selected explicitly.
+    pub sounds: Vec<String>,
+    pub on_conflict: ConflictStrategy,
+}
+
+impl Selection {
+    pub fn items<S: AsRef<str>>(names: &[S]) -> Self {
+        Selection {
+            items: names.iter().map(|n| n.as_ref().to_string()).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ImportAction {
+    Added,
+    Replaced,
+    /// Imported under a new name
+    Renamed(String),
+    /// Not imported, either because of [`ConflictStrategy::Skip`] or because an identical
+    /// resource already exists
+    Skipped,
+}
+
+/// A single imported item or resource
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Imported {
+    /// The name in the source table
+    pub name: String,
+    pub action: ImportAction,
+}
+
+/// What was done by [`items`]
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct ImportResult {
+    pub items: Vec<Imported>,
+    pub images: Vec<Imported>,
+    pub materials: Vec<Imported>,
+    pub sounds: Vec<Imported>,
+}
+
+/// Copies the selected game items from one table into another, together with the images,
+/// materials and sounds they reference.
+///
+/// Names are matched case-insensitive, like VPinball does. If an imported item is placed on
+/// another imported item that got renamed, the reference is updated. Collections and the
+/// script are not imported.
+///
+/// Fails if a selected item or sound does not exist in the source table.
+pub fn items(from: &VPX, into: &mut VPX, selection: &Selection) -> io::Result<ImportResult> {
+    let strategy = selection.on_conflict;
+    let mut source_items = Vec::with_capacity(selection.items.len());
+    for name in &selection.items {
+        let item = from
+            .gameitems
+            .iter()
+            .find(|item| item.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| not_found("Game item", name))?;
+        source_items.push(item.clone());
+    }
+    let mut source_sounds = Vec::with_capacity(selection.sounds.len());
+    for name in &selection.sounds {
+        let sound = from
+            .sounds
+            .iter()
+            .find(|sound| sound.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| not_found("Sound", name))?;
+        source_sounds.push(sound.clone());
+    }
+    for sound_name in unique_names(source_items.iter().flat_map(|i| i.referenced_sounds())) {
+        let selected = source_sounds
+            .iter()
+            .any(|s| s.name.eq_ignore_ascii_case(&sound_name));
+        // a dangling reference in the source table is left as is
+        let sound = from
+            .sounds
+            .iter()
+            .find(|sound| sound.name.eq_ignore_ascii_case(&sound_name));
+        if let (false, Some(sound)) = (selected, sound) {
+            source_sounds.push(sound.clone());
+        }
+    }
+
+    let mut result = ImportResult::default();
+
+    for image_name in unique_names(source_items.iter().flat_map(|i| i.referenced_images())) {
+        let Some(image) = from
+            .images
+            .iter()
+            .find(|image| image.name.eq_ignore_ascii_case(&image_name))
+        else {
+            // dangling reference in the source table
+            continue;
+        };
+        let existing = into
+            .images
+            .iter()
+            .find(|i| i.name.eq_ignore_ascii_case(&image_name));
+        let action = match existing {
+            None => ImportAction::Added,
+            Some(existing) if existing == image => ImportAction::Skipped,
+            Some(_) => conflict_action(strategy, &image_name, |n| {
+                into.images.iter().any(|i| i.name.eq_ignore_ascii_case(n))
+            }),
+        };
+        match &action {
+            ImportAction::Added | ImportAction::Replaced => {
+                into.add_or_replace_image(image.clone());
+            }
+            ImportAction::Renamed(new_name) => {
+                let mut image = image.clone();
+                image.name = new_name.clone();
+                if image.internal_name.is_some() {
+                    image.internal_name = Some(new_name.to_lowercase());
+                }
+                into.add_or_replace_image(image);
+                for item in source_items.iter_mut() {
+                    rename_reference(item.referenced_images_mut(), &image_name, new_name);
+                }
+            }
+            ImportAction::Skipped => {}
+        }
+        result.images.push(Imported {
+            name: image_name,
+            action,
+        });
+    }
+
+    for material_name in unique_names(source_items.iter().flat_map(|i| i.referenced_materials())) {
+        let Some(material) = find_material(&from.gamedata, &material_name) else {
+            continue;
+        };
+        let action = match find_material(&into.gamedata, &material_name) {
+            None => ImportAction::Added,
+            Some(existing) if existing == material => ImportAction::Skipped,
+            Some(_) => conflict_action(strategy, &material_name, |n| {
+                find_material(&into.gamedata, n).is_some()
+            }),
+        };
+        match &action {
+            ImportAction::Added | ImportAction::Replaced => {
+                put_material(&mut into.gamedata, material);
+            }
+            ImportAction::Renamed(new_name) => {
+                let mut material = material;
+                material.name = new_name.clone();
+                put_material(&mut into.gamedata, material);
+                for item in source_items.iter_mut() {
+                    rename_reference(item.referenced_materials_mut(), &material_name, new_name);
+                }
+            }
+            ImportAction::Skipped => {}
+        }
+        result.materials.push(Imported {
+            name: material_name,
+            action,
+        });
+    }
+
+    for sound in source_sounds {
+        let existing = into
+            .sounds
+            .iter()
+            .position(|s| s.name.eq_ignore_ascii_case(&sound.name));
+        let action = match existing {
+            None => ImportAction::Added,
+            Some(pos) if into.sounds[pos] == sound => ImportAction::Skipped,
+            Some(_) => conflict_action(strategy, &sound.name, |n| {
+                into.sounds.iter().any(|s| s.name.eq_ignore_ascii_case(n))
+            }),
+        };
+        let name = sound.name.clone();
+        match &action {
+            ImportAction::Added => add_sound(into, sound),
+            ImportAction::Replaced => {
+                if let Some(pos) = existing {
+                    into.sounds[pos] = sound;
+                }
+            }
+            ImportAction::Renamed(new_name) => {
+                for item in source_items.iter_mut() {
+                    rename_reference(item.referenced_sounds_mut(), &sound.name, new_name);
+                }
+                add_sound(
+                    into,
+                    SoundData {
+                        name: new_name.clone(),
+                        ..sound
+                    },
+                )
+            }
+            ImportAction::Skipped => {}
+        }
+        result.sounds.push(Imported { name, action });
+    }
+
+    // decide on all item names first so surface references between imported items can be
+    // updated before adding them
+    let mut renamed_items: Vec<(String, String)> = Vec::new();
+    let mut item_actions = Vec::with_capacity(source_items.len());
+    for item in &source_items {
+        let name = item.name().to_string();
+        let existing = into
+            .gameitems
+            .iter()
+            .position(|i| i.name().eq_ignore_ascii_case(&name));
+        let action = match existing {
+            None => ImportAction::Added,
+            Some(_) => conflict_action(strategy, &name, |n| {
+                into.gameitems
+                    .iter()
+                    .any(|i| i.name().eq_ignore_ascii_case(n))
+                    || renamed_items
+                        .iter()
+                        .any(|(_, new)| new.eq_ignore_ascii_case(n))
+            }),
+        };
+        if let ImportAction::Renamed(new_name) = &action {
+            renamed_items.push((name.clone(), new_name.clone()));
+        }
+        item_actions.push((existing, action));
+    }
+    for (mut item, (existing, action)) in source_items.into_iter().zip(item_actions) {
+        let name = item.name().to_string();
+        if let Some(surface) = item.surface_mut() {
+            if let Some((_, new)) = renamed_items
+                .iter()
+                .find(|(old, _)| old.eq_ignore_ascii_case(surface))
+            {
+                *surface = new.clone();
+            }
+        }
+        match &action {
+            ImportAction::Added => {
+                into.add_game_item(item);
+            }
+            ImportAction::Replaced => {
+                if let Some(pos) = existing {
+                    into.gameitems[pos] = item;
+                }
+            }
+            ImportAction::Renamed(new_name) => {
+                item.set_name(new_name.clone());
+                into.add_game_item(item);
+            }
+            ImportAction::Skipped => {}
+        }
+        result.items.push(Imported { name, action });
+    }
+
+    for (kind, imported) in [
+        ("game item", &result.items),
+        ("image", &result.images),
+        ("material", &result.materials),
+        ("sound", &result.sounds),
+    ] {
+        for Imported { name, action } in imported {
+            let description = match action {
+                ImportAction::Added => format!("Added {} {}", kind, name),
+                ImportAction::Replaced => format!("Replaced {} {}", kind, name),
+                ImportAction::Renamed(new_name) => {
+                    format!("Added {} {} as {}", kind, name, new_name)
+                }
+                ImportAction::Skipped => continue,
+            };
+            into.changelog.record("import", description);
+        }
+    }
+
+    Ok(result)
+}
+
+fn not_found(kind: &str, name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found: {}", kind, name),
+    )
+}
+
+fn conflict_action<F: Fn(&str) -> bool>(
+    strategy: ConflictStrategy,
+    name: &str,
+    exists: F,
+) -> ImportAction {
+    match strategy {
+        ConflictStrategy::Skip => ImportAction::Skipped,
+        ConflictStrategy::Replace => ImportAction::Replaced,
+        ConflictStrategy::Rename => {
+            let new_name = (2..)
+                .map(|n| format!("{}_{}", name, n))
+                .find(|candidate| !exists(candidate))
+                .unwrap();
+            ImportAction::Renamed(new_name)
+        }
+    }
+}
+
+/// Deduplicates names case-insensitive, keeping the first occurrence
+fn unique_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if !unique.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            unique.push(name.to_string());
+        }
+    }
+    unique
+}
+
+fn rename_reference(references: Vec<&mut String>, old: &str, new: &str) {
+    for reference in references {
+        if reference.eq_ignore_ascii_case(old) {
+            *reference = new.to_string();
+        }
+    }
+}
+
+/// Looks up a material, converting from the legacy format for tables before 10.8
+pub(crate) fn find_material(gamedata: &GameData, name: &str) -> Option<Material> {
+    match &gamedata.materials {
+        Some(materials) => materials
+            .iter()
+            .find(|m| m.name.eq_ignore_ascii_case(name))
+            .cloned(),
+        None => {
+            let save_material = gamedata
+                .materials_old
+                .iter()
+                .find(|m| m.name.eq_ignore_ascii_case(name))?;
+            let physics = gamedata
+                .materials_physics_old
+                .as_ref()
+                .and_then(|p| p.iter().find(|m| m.name.eq_ignore_ascii_case(name)));
+            Some(Material::from_legacy(save_material, physics))
+        }
+    }
+}
+
+/// Adds or replaces a material, keeping the legacy lists in sync
+pub(crate) fn put_material(gamedata: &mut GameData, material: Material) {
+    fn upsert<T>(list: &mut Vec<T>, value: T, name: impl Fn(&T) -> &str) {
+        let new_name = name(&value).to_string();
+        match list
+            .iter()
+            .position(|v| name(v).eq_ignore_ascii_case(&new_name))
+        {
+            Some(pos) => list[pos] = value,
+            None => list.push(value),
+        }
+    }
+    upsert(
+        &mut gamedata.materials_old,
+        SaveMaterial::from(&material),
+        |m| &m.name,
+    );
+    if let Some(physics) = gamedata.materials_physics_old.as_mut() {
+        upsert(physics, SavePhysicsMaterial::from(&material), |m| &m.name);
+    }
+    if let Some(materials) = gamedata.materials.as_mut() {
+        upsert(materials, material, |m| &m.name);
+    }
+    gamedata.materials_size = gamedata.materials_old.len() as u32;
+}
+
+fn add_sound(into: &mut VPX, sound: SoundData) {
+    into.sounds.push(sound);
+    into.gamedata.sounds_size = into.sounds.len() as u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::reel::Reel;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::gameitem::GameItemEnum;
+    use crate::vpx::image::ImageData;
+    use crate::vpx::sound::OutputTarget;
+    use pretty_assertions::assert_eq;
+
+    fn image(name: &str, width: u32) -> ImageData {
+        ImageData {
+            name: name.to_string(),
+            internal_name: None,
+            path: format!("{}.png", name),
+            width,
+            height: 1,
+            link: None,
+            alpha_test_value: -1.0,
+            is_opaque: None,
+            is_signed: None,
+            jpeg: None,
+            bits: None,
+        }
+    }
+
+    fn source_vpx() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "Wall1".to_string(),
+            image: "Wood".to_string(),
+            top_material: "Plastic".to_string(),
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::Light(Light {
+            name: "Light1".to_string(),
+            surface: "Wall1".to_string(),
+            off_image: "Insert".to_string(),
+            ..Default::default()
+        }));
+        vpx.add_or_replace_image(image("Wood", 2));
+        vpx.add_or_replace_image(image("Insert", 2));
+        let mut material = Material::default();
+        material.name = "Plastic".to_string();
+        material.roughness = 0.5;
+        put_material(&mut vpx.gamedata, material);
+        vpx
+    }
+
+    fn target_vpx() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "wall1".to_string(),
+            ..Default::default()
+        }));
+        vpx.add_or_replace_image(image("Wood", 1));
+        vpx.add_or_replace_image(image("Insert", 2));
+        vpx
+    }
+
+    #[test]
+    fn import_rename_conflicts() -> io::Result<()> {
+        let from = source_vpx();
+        let mut into = target_vpx();
+        let mut selection = Selection::items(&["Wall1", "Light1"]);
+        selection.on_conflict = ConflictStrategy::Rename;
+
+        let result = items(&from, &mut into, &selection)?;
+
+        assert_eq!(
+            result,
+            ImportResult {
+                items: vec![
+                    Imported {
+                        name: "Wall1".to_string(),
+                        action: ImportAction::Renamed("Wall1_2".to_string())
+                    },
+                    Imported {
+                        name: "Light1".to_string(),
+                        action: ImportAction::Added
+                    },
+                ],
+                images: vec![
+                    Imported {
+                        name: "Wood".to_string(),
+                        action: ImportAction::Renamed("Wood_2".to_string())
+                    },
+                    Imported {
+                        name: "Insert".to_string(),
+                        action: ImportAction::Skipped
+                    },
+                ],
+                materials: vec![Imported {
+                    name: "Plastic".to_string(),
+                    action: ImportAction::Added
+                }],
+                sounds: vec![],
+            }
+        );
+        assert_eq!(into.gamedata.gameitems_size, 3);
+        assert_eq!(into.gamedata.images_size, 3);
+        assert_eq!(into.gamedata.materials_size, 1);
+        assert_eq!(into.gamedata.materials_old[0].name, "Plastic");
+        let wall = &into.gameitems[1];
+        assert_eq!(wall.name(), "Wall1_2");
+        assert_eq!(wall.referenced_images(), vec!["Wood_2"]);
+        assert_eq!(into.gameitems[2].surface(), Some("Wall1_2"));
+        Ok(())
+    }
+
+    #[test]
+    fn import_skip_conflicts() -> io::Result<()> {
+        let from = source_vpx();
+        let mut into = target_vpx();
+        let selection = Selection::items(&["Wall1"]);
+
+        let result = items(&from, &mut into, &selection)?;
+
+        assert_eq!(
+            result.items,
+            vec![Imported {
+                name: "Wall1".to_string(),
+                action: ImportAction::Skipped
+            }]
+        );
+        assert_eq!(into.gameitems.len(), 1);
+        assert_eq!(into.images[0].width, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn import_replace_conflicts() -> io::Result<()> {
+        let from = source_vpx();
+        let mut into = target_vpx();
+        let mut selection = Selection::items(&["wall1"]);
+        selection.on_conflict = ConflictStrategy::Replace;
+
+        items(&from, &mut into, &selection)?;
+
+        assert_eq!(into.gameitems.len(), 1);
+        assert_eq!(into.gameitems[0].name(), "Wall1");
+        assert_eq!(into.images[0].width, 2);
+        assert_eq!(into.gamedata.images_size, 2);
+        Ok(())
+    }
+
+    fn sound(name: &str, data: Vec<u8>) -> SoundData {
+        SoundData {
+            name: name.to_string(),
+            path: format!("{}.wav", name),
+            wave_form: Default::default(),
+            data,
+            internal_name: String::new(),
+            fade: 0,
+            volume: 0,
+            balance: 0,
+            output_target: OutputTarget::Table,
+        }
+    }
+
+    #[test]
+    fn import_reel_sounds() -> io::Result<()> {
+        let mut from = VPX::default();
+        for (name, sound_name) in [("Reel1", "Tick"), ("Reel2", "Missing")] {
+            let mut reel = Reel::default();
+            reel.name = name.to_string();
+            reel.sound = sound_name.to_string();
+            from.add_game_item(GameItemEnum::Reel(reel));
+        }
+        from.sounds.push(sound("Tick", vec![1]));
+        let mut into = VPX::default();
+        into.sounds.push(sound("tick", vec![2]));
+        let mut selection = Selection::items(&["Reel1", "Reel2"]);
+        selection.on_conflict = ConflictStrategy::Rename;
+
+        let result = items(&from, &mut into, &selection)?;
+
+        assert_eq!(
+            result.sounds,
+            vec![Imported {
+                name: "Tick".to_string(),
+                action: ImportAction::Renamed("Tick_2".to_string())
+            }]
+        );
+        assert_eq!(into.gamedata.sounds_size, 2);
+        assert_eq!(into.gameitems[0].referenced_sounds(), vec!["Tick_2"]);
+        assert_eq!(into.gameitems[1].referenced_sounds(), vec!["Missing"]);
+        Ok(())
+    }
+
+    #[test]
+    fn import_missing_item() {
+        let from = source_vpx();
+        let mut into = target_vpx();
+        let result = items(&from, &mut into, &Selection::items(&["Missing"]));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
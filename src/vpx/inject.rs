@@ -0,0 +1,715 @@
+//! Adding code to a table script at well defined places
+//!
+//! Tournament and challenge tools need the same hooks in every table, eg exporting the score
+//! when it changes. Splicing strings into the script breaks on the many ways tables are
+//! written, these helpers find the subs and functions first with [`procedures`] and only
+//! change the script around them.
+//!
+//! [`append_to_init`] runs code at the end of the table init sub, [`wrap`] runs code before
+//! and after a sub or function, like `AddScore`, by renaming the original and calling it from
+//! a new one with the same name and parameters.
+//!
+//! # Limitations
+//!
+//! The script is split into statements by a small VBScript tokenizer that knows about
+//! strings, comments, `:` statement separators and `_` line continuations, so a `Sub` or a
+//! `Name =` inside a string or a comment is never taken for code. It does not build a syntax
+//! tree, procedures inside a class or defined on a single line can not be changed.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::inject::{append_to_init, wrap};
+//!
+//! let script = "Sub Table1_Init\n    LoadEM\nEnd Sub\n\nSub AddScore(points)\n    Score = Score + points\nEnd Sub\n";
+//! let script = append_to_init(script, "Table1", "ExportScore 0").unwrap();
+//! let script = wrap(&script, "AddScore", "", "ExportScore Score").unwrap();
+//! assert!(script.contains("Sub AddScore_Unwrapped(points)"));
+//! ```
+
+use super::VPX;
+use std::io;
+use std::ops::Range;
+
+/// Suffix of the original procedure renamed by [`wrap`]
+pub const UNWRAPPED_SUFFIX: &str = "_Unwrapped";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ProcedureKind {
+    Sub,
+    Function,
+}
+
+impl ProcedureKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            ProcedureKind::Sub => "Sub",
+            ProcedureKind::Function => "Function",
+        }
+    }
+}
+
+/// A sub or function in a script, lines are 1-based
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Procedure {
+    pub kind: ProcedureKind,
+    pub name: String,
+    /// The parameters without the parentheses, with whitespace collapsed
+    pub parameters: String,
+    /// The line with the `Sub` or `Function` keyword
+    pub start_line: usize,
+    /// The line with `End Sub` or `End Function`
+    pub end_line: usize,
+}
+
+/// The subs and functions outside classes, in script order
+pub fn procedures(script: &str) -> Vec<Procedure> {
+    locate(script)
+        .1
+        .into_iter()
+        .map(|located| located.procedure)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum TokenKind {
+    /// A keyword, identifier or number
+    Word,
+    String,
+    Symbol,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// The bytes of the token in the script
+    range: Range<usize>,
+    /// 1-based
+    line: usize,
+}
+
+/// Splits a script into statements at line ends and `:` separators.
+///
+/// Comments are left out and lines continued with `_` are joined, so a statement can span
+/// several lines. Empty statements are skipped.
+fn statements(script: &str) -> Vec<Vec<Token>> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut statement = Vec::new();
+    let mut line = 1;
+    let mut index = 0;
+    let skip_to_line_end = |index: usize| {
+        script[index..]
+            .find('\n')
+            .map_or(script.len(), |end| index + end)
+    };
+    while index < bytes.len() {
+        let start = index;
+        let kind = match bytes[index] {
+            b'\n' | b':' => {
+                if bytes[index] == b'\n' {
+                    line += 1;
+                }
+                if !statement.is_empty() {
+                    statements.push(std::mem::take(&mut statement));
+                }
+                index += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' => {
+                index += 1;
+                continue;
+            }
+            b'\'' => {
+                index = skip_to_line_end(index);
+                continue;
+            }
+            b'_' if script[index + 1..]
+                .split('\n')
+                .next()
+                .is_some_and(|rest| rest.trim().is_empty()) =>
+            {
+                // the statement continues on the next line
+                index = skip_to_line_end(index);
+                if index < bytes.len() {
+                    line += 1;
+                    index += 1;
+                }
+                continue;
+            }
+            b'"' => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != b'\n' {
+                    index += 1;
+                    if bytes[index - 1] == b'"' {
+                        // a quote in a string is written as two quotes
+                        if bytes.get(index) != Some(&b'"') {
+                            break;
+                        }
+                        index += 1;
+                    }
+                }
+                TokenKind::String
+            }
+            b'[' => {
+                index = script[index..]
+                    .find([']', '\n'])
+                    .map_or(script.len(), |end| index + end + 1);
+                TokenKind::Word
+            }
+            byte if byte.is_ascii_alphanumeric() => {
+                while index < bytes.len()
+                    && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_')
+                {
+                    index += 1;
+                }
+                if script[start..index].eq_ignore_ascii_case("rem") {
+                    index = skip_to_line_end(index);
+                    continue;
+                }
+                TokenKind::Word
+            }
+            _ => {
+                index += script[index..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Symbol
+            }
+        };
+        statement.push(Token {
+            kind,
+            range: start..index,
+            line,
+        });
+    }
+    if !statement.is_empty() {
+        statements.push(statement);
+    }
+    statements
+}
+
+/// A procedure with the places in the script [`wrap`] and [`append_to_init`] change
+#[derive(Debug)]
+struct Located {
+    procedure: Procedure,
+    /// The name after the `Sub` or `Function` keyword
+    name: Range<usize>,
+    /// The indices of the statements in the body
+    body: Range<usize>,
+    /// The `End Sub` or `End Function` statement
+    end: Range<usize>,
+}
+
+/// The statements of a script and the procedures outside classes
+fn locate(script: &str) -> (Vec<Vec<Token>>, Vec<Located>) {
+    let statements = statements(script);
+    let is_word = |token: Option<&Token>, word: &str| {
+        token.is_some_and(|token| {
+            token.kind == TokenKind::Word && script[token.range.clone()].eq_ignore_ascii_case(word)
+        })
+    };
+    let mut located = Vec::new();
+    let mut open: Option<Located> = None;
+    let mut in_class = false;
+    for (index, statement) in statements.iter().enumerate() {
+        let mut tokens = statement.iter().peekable();
+        if let Some(procedure) = &mut open {
+            let kind = procedure.procedure.kind.keyword();
+            if statement.len() == 2 && is_word(tokens.next(), "end") && is_word(tokens.next(), kind)
+            {
+                procedure.procedure.end_line = statement[0].line;
+                procedure.body.end = index;
+                procedure.end = statement[0].range.start..statement[1].range.end;
+                located.extend(open.take());
+            }
+            continue;
+        }
+        if in_class {
+            in_class = !(is_word(tokens.next(), "end") && is_word(tokens.next(), "class"));
+            continue;
+        }
+        if is_word(tokens.peek().copied(), "public") || is_word(tokens.peek().copied(), "private") {
+            tokens.next();
+        }
+        if is_word(tokens.peek().copied(), "class") {
+            in_class = true;
+            continue;
+        }
+        if is_word(tokens.peek().copied(), "default") {
+            tokens.next();
+        }
+        let keyword = tokens.next();
+        let kind = if is_word(keyword, "sub") {
+            ProcedureKind::Sub
+        } else if is_word(keyword, "function") {
+            ProcedureKind::Function
+        } else {
+            continue;
+        };
+        let Some(name) = tokens.next().filter(|name| name.kind == TokenKind::Word) else {
+            continue;
+        };
+        let mut parameters = String::new();
+        let mut previous: Option<&Token> = None;
+        if tokens
+            .next_if(|token| &script[token.range.clone()] == "(")
+            .is_some()
+        {
+            let inside: Vec<&Token> = tokens.collect();
+            let close = inside
+                .iter()
+                .rposition(|token| &script[token.range.clone()] == ")")
+                .unwrap_or(inside.len());
+            for token in &inside[..close] {
+                let text = &script[token.range.clone()];
+                let after_comma = previous.is_some_and(|p| &script[p.range.clone()] == ",");
+                let between_words = token.kind == TokenKind::Word
+                    && previous.is_some_and(|p| p.kind == TokenKind::Word);
+                if after_comma || between_words {
+                    parameters.push(' ');
+                }
+                parameters.push_str(text);
+                previous = Some(token);
+            }
+        }
+        open = Some(Located {
+            procedure: Procedure {
+                kind,
+                name: script[name.range.clone()].to_string(),
+                parameters,
+                start_line: statement[0].line,
+                end_line: statement[0].line,
+            },
+            name: name.range.clone(),
+            body: index + 1..index + 1,
+            end: 0..0,
+        });
+    }
+    (statements, located)
+}
+
+/// Adds code at the end of the `<table>_Init` sub, the sub is added if there is none.
+///
+/// The code does not run if the init sub leaves early with `Exit Sub`.
+pub fn append_to_init(script: &str, table_name: &str, code: &str) -> io::Result<String> {
+    let name = format!("{}_Init", table_name);
+    let newline = newline(script);
+    let Some((statements, init)) = find(script, &name)? else {
+        let mut script = script.to_string();
+        if !script.is_empty() && !script.ends_with('\n') {
+            script.push_str(newline);
+        }
+        script.push_str(&format!("{}Sub {}{}", newline, name, newline));
+        script.push_str(&indent(code, "    ", newline));
+        script.push_str(&format!("End Sub{}", newline));
+        return Ok(script);
+    };
+    if init.procedure.kind != ProcedureKind::Sub {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is a function, not a sub", name),
+        ));
+    }
+    let body_indent = body_indent(script, &statements, &init);
+    let code = indent(code, &body_indent, newline);
+    let end_line_start = line_start(script, init.end.start);
+    let mut script = script.to_string();
+    if script[end_line_start..init.end.start]
+        .chars()
+        .all(|c| c.is_whitespace() || c == ':')
+    {
+        script.insert_str(end_line_start, &code);
+    } else {
+        // `End Sub` follows another statement on its line, eg `LoadEM : End Sub`
+        let previous = statements[init.body.end - 1].last().unwrap().range.end;
+        script.replace_range(previous..init.end.start, &format!("{}{}", newline, code));
+    }
+    Ok(script)
+}
+
+/// Runs `before` and `after` around every call of a sub or function.
+///
+/// The original is renamed with the [`UNWRAPPED_SUFFIX`] and a new procedure with the same
+/// name and parameters calls it, so calls from anywhere in the script and from Visual
+/// Pinball go through the new one. A function returns the result of the original, `after`
+/// runs before it returns. `ByRef` parameters are passed on, changes made by the original
+/// are seen by the caller.
+pub fn wrap(script: &str, name: &str, before: &str, after: &str) -> io::Result<String> {
+    let (statements, located) = find(script, name)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No sub or function named {}", name),
+        )
+    })?;
+    let procedure = &located.procedure;
+    let unwrapped = format!("{}{}", procedure.name, UNWRAPPED_SUFFIX);
+    if find(script, &unwrapped)?.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is already wrapped", name),
+        ));
+    }
+    let newline = newline(script);
+    let indent_text = body_indent(script, &statements, &located);
+
+    // replacements in script order
+    let mut renames = vec![located.name.clone()];
+    // a function assigns its result to its own name, eg `GetScore = 1` or `Set GetObj = x`
+    if procedure.kind == ProcedureKind::Function {
+        for statement in &statements[located.body.clone()] {
+            let text = |index: usize| statement.get(index).map(|t| &script[t.range.clone()]);
+            let target = if text(0).is_some_and(|t| t.eq_ignore_ascii_case("set")) {
+                1
+            } else {
+                0
+            };
+            if text(target).is_some_and(|t| t.eq_ignore_ascii_case(&procedure.name))
+                && text(target + 1) == Some("=")
+            {
+                renames.push(statement[target].range.clone());
+            }
+        }
+    }
+
+    let arguments = arguments(&procedure.parameters);
+    let keyword = procedure.kind.keyword();
+    let mut wrapper = String::new();
+    wrapper.push_str(newline);
+    if procedure.parameters.is_empty() {
+        wrapper.push_str(&format!("{} {}{}", keyword, procedure.name, newline));
+    } else {
+        wrapper.push_str(&format!(
+            "{} {}({}){}",
+            keyword, procedure.name, procedure.parameters, newline
+        ));
+    }
+    wrapper.push_str(&indent(before, &indent_text, newline));
+    let call = match (procedure.kind, arguments.is_empty()) {
+        (ProcedureKind::Sub, true) => unwrapped.clone(),
+        (ProcedureKind::Sub, false) => format!("{} {}", unwrapped, arguments),
+        (ProcedureKind::Function, _) => {
+            format!("{} = {}({})", procedure.name, unwrapped, arguments)
+        }
+    };
+    wrapper.push_str(&format!("{}{}{}", indent_text, call, newline));
+    wrapper.push_str(&indent(after, &indent_text, newline));
+    wrapper.push_str(&format!("End {}", keyword));
+
+    let end_is_last_on_line = statements
+        .get(located.body.end + 1)
+        .is_none_or(|next| next[0].line > statements[located.body.end].last().unwrap().line);
+    let mut result = String::with_capacity(script.len() + wrapper.len());
+    let mut last = 0;
+    for rename in renames {
+        result.push_str(&script[last..rename.start]);
+        result.push_str(&unwrapped);
+        last = rename.end;
+    }
+    if end_is_last_on_line {
+        let line_end = script[located.end.end..]
+            .find('\n')
+            .map(|end| located.end.end + end + 1);
+        let line_end = line_end.unwrap_or(script.len());
+        result.push_str(&script[last..line_end]);
+        if line_end == script.len() && !script.ends_with('\n') {
+            result.push_str(newline);
+        }
+        result.push_str(&wrapper);
+        result.push_str(newline);
+        result.push_str(&script[line_end..]);
+    } else {
+        // the statements after `End Sub` follow the new procedure
+        result.push_str(&script[last..located.end.end]);
+        result.push_str(newline);
+        result.push_str(&wrapper);
+        result.push_str(&script[located.end.end..]);
+    }
+    Ok(result)
+}
+
+/// [`append_to_init`] on the script of a table, using the table name
+pub fn append_to_table_init(vpx: &mut VPX, code: &str) -> io::Result<()> {
+    let script = append_to_init(&vpx.gamedata.code.string, &vpx.gamedata.name, code)?;
+    vpx.set_script(script);
+    vpx.changelog.record(
+        "append_to_table_init",
+        format!("Added code to the end of {}_Init", vpx.gamedata.name),
+    );
+    Ok(())
+}
+
+/// The procedure with a name, an error if it is defined more than once
+fn find(script: &str, name: &str) -> io::Result<Option<(Vec<Vec<Token>>, Located)>> {
+    let (statements, located) = locate(script);
+    let mut found = located
+        .into_iter()
+        .filter(|l| l.procedure.name.eq_ignore_ascii_case(name));
+    let procedure = found.next();
+    if found.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is defined more than once", name),
+        ));
+    }
+    match procedure {
+        Some(l) if l.procedure.start_line == l.procedure.end_line => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is defined on a single line", name),
+        )),
+        other => Ok(other.map(|l| (statements, l))),
+    }
+}
+
+/// Tables written on Windows use `\r\n`, that is kept for the added lines
+fn newline(script: &str) -> &'static str {
+    if script.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// The byte offset of the start of the line containing an offset
+fn line_start(script: &str, offset: usize) -> usize {
+    script[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+/// The indentation of the line with the first statement in the body, 4 spaces for an empty
+/// body
+fn body_indent(script: &str, statements: &[Vec<Token>], located: &Located) -> String {
+    statements[located.body.clone()]
+        .first()
+        .map(|statement| {
+            script[line_start(script, statement[0].range.start)..]
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect()
+        })
+        .filter(|indent: &String| !indent.is_empty())
+        .unwrap_or_else(|| "    ".to_string())
+}
+
+fn indent(code: &str, indent: &str, newline: &str) -> String {
+    code.lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                newline.to_string()
+            } else {
+                format!("{}{}{}", indent, line.trim_end_matches('\r'), newline)
+            }
+        })
+        .collect()
+}
+
+/// The parameter names to pass on, `ByVal a, ByRef b()` becomes `a, b`
+fn arguments(parameters: &str) -> String {
+    parameters
+        .split(',')
+        .map(|p| {
+            let p = p.trim();
+            let p = ["byval ", "byref "]
+                .iter()
+                .find_map(|keyword| {
+                    p.get(..keyword.len())
+                        .filter(|start| start.eq_ignore_ascii_case(keyword))
+                        .map(|_| p[keyword.len()..].trim_start())
+                })
+                .unwrap_or(p);
+            p.trim_end_matches("()").trim()
+        })
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SCRIPT: &str = r#"Option Explicit
+
+Sub Table1_Init
+	LoadEM
+	If x Then Exit Sub ' End Sub in a comment
+End Sub
+
+Public Sub AddScore(ByVal points, _
+    ByRef multiplier)
+	Score = Score + points * multiplier
+End Sub
+
+Function GetScore()
+	GetScore = Score
+End Function
+
+Class Helper
+	Sub AddScore(points) : End Sub
+End Class
+
+Sub Short : Score = 0 : End Sub
+"#;
+
+    #[test]
+    fn find_procedures() {
+        let found: Vec<(String, usize, usize)> = procedures(SCRIPT)
+            .into_iter()
+            .map(|p| (p.name, p.start_line, p.end_line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Table1_Init".to_string(), 3, 6),
+                ("AddScore".to_string(), 8, 11),
+                ("GetScore".to_string(), 13, 15),
+                ("Short".to_string(), 21, 21),
+            ]
+        );
+        assert_eq!(
+            procedures(SCRIPT)[1].parameters,
+            "ByVal points, ByRef multiplier"
+        );
+    }
+
+    #[test]
+    fn ignore_comments_and_strings() {
+        let script = "' Sub Commented\nRem Sub Remark\nx = \"Sub InString\" : y = 1\nSub Real\n    s = \"End Sub\"\nEnd Sub\n";
+        let found = procedures(script);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "Real");
+        assert_eq!((found[0].start_line, found[0].end_line), (4, 6));
+    }
+
+    #[test]
+    fn tokenize_statements() {
+        let script =
+            "x = \"a : b ' \"\"c\"\"\" : y = 1 ' z = 2\nCall Foo(1, _\n  2) : Rem : w = 3\n";
+        let texts: Vec<Vec<(&str, usize)>> = statements(script)
+            .iter()
+            .map(|statement| {
+                statement
+                    .iter()
+                    .map(|token| (&script[token.range.clone()], token.line))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                vec![("x", 1), ("=", 1), ("\"a : b ' \"\"c\"\"\"", 1)],
+                vec![("y", 1), ("=", 1), ("1", 1)],
+                vec![
+                    ("Call", 2),
+                    ("Foo", 2),
+                    ("(", 2),
+                    ("1", 2),
+                    (",", 2),
+                    ("2", 3),
+                    (")", 3)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn procedures_between_statement_separators() -> io::Result<()> {
+        let script = "x = 1 : Sub Foo(a)\n    Bar a\nEnd Sub : y = 2\nSub Baz\nEnd Sub\n";
+        let found: Vec<(String, usize, usize)> = procedures(script)
+            .into_iter()
+            .map(|p| (p.name, p.start_line, p.end_line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("Foo".to_string(), 1, 3), ("Baz".to_string(), 4, 5)]
+        );
+
+        let script = wrap(script, "Foo", "", "Done")?;
+        assert_eq!(
+            script,
+            "x = 1 : Sub Foo_Unwrapped(a)\n    Bar a\nEnd Sub\n\nSub Foo(a)\n    Foo_Unwrapped a\n    Done\nEnd Sub : y = 2\nSub Baz\nEnd Sub\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wrap_function_ignores_strings_and_comments() -> io::Result<()> {
+        let script = "Function GetScore()\n\tMsg = \"GetScore = 1\" ' GetScore = 2\n\tSet GetScore = Score : Log \"x\"\nEnd Function\n";
+        let script = wrap(script, "GetScore", "", "")?;
+        assert!(script.starts_with(
+            "Function GetScore_Unwrapped()\n\tMsg = \"GetScore = 1\" ' GetScore = 2\n\tSet GetScore_Unwrapped = Score : Log \"x\"\nEnd Function\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn append_to_init_after_statement_on_end_line() -> io::Result<()> {
+        let script = append_to_init(
+            "Sub Table1_Init\n    LoadEM : End Sub\n",
+            "Table1",
+            "ExportScore 0",
+        )?;
+        assert_eq!(
+            script,
+            "Sub Table1_Init\n    LoadEM\n    ExportScore 0\nEnd Sub\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn append_to_existing_and_missing_init() -> io::Result<()> {
+        let script = append_to_init(SCRIPT, "table1", "ExportScore 0\nDebug.Print 1")?;
+        assert!(script.contains(
+            "\tIf x Then Exit Sub ' End Sub in a comment\n\tExportScore 0\n\tDebug.Print 1\nEnd Sub\n"
+        ));
+
+        let script = append_to_init("Dim x\r\n", "Table1", "ExportScore 0")?;
+        assert_eq!(
+            script,
+            "Dim x\r\n\r\nSub Table1_Init\r\n    ExportScore 0\r\nEnd Sub\r\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wrap_sub_and_function() -> io::Result<()> {
+        let script = wrap(SCRIPT, "addscore", "ExportStart", "ExportScore Score")?;
+        assert!(script.contains("Public Sub AddScore_Unwrapped(ByVal points, _\n"));
+        assert!(script.contains(
+            "End Sub\n\nSub AddScore(ByVal points, ByRef multiplier)\n\tExportStart\n\tAddScore_Unwrapped points, multiplier\n\tExportScore Score\nEnd Sub\n"
+        ));
+        // the sub in the class is left alone
+        assert!(script.contains("\tSub AddScore(points) : End Sub\n"));
+
+        let script = wrap(&script, "GetScore", "", "Exported = True")?;
+        assert!(script.contains(
+            "Function GetScore_Unwrapped()\n\tGetScore_Unwrapped = Score\nEnd Function\n\nFunction GetScore\n\tGetScore = GetScore_Unwrapped()\n\tExported = True\nEnd Function\n"
+        ));
+
+        let error = wrap(&script, "AddScore", "", "").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            wrap(SCRIPT, "Missing", "", "").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            wrap(SCRIPT, "Short", "", "").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn table_init() -> io::Result<()> {
+        let mut vpx = VPX::default();
+        vpx.gamedata.name = "Table1".to_string();
+        vpx.set_script("Sub Table1_Init()\nEnd Sub\n".to_string());
+        append_to_table_init(&mut vpx, "ExportScore 0")?;
+        assert_eq!(
+            vpx.gamedata.code.string,
+            "Sub Table1_Init()\n    ExportScore 0\nEnd Sub\n"
+        );
+        assert_eq!(
+            vpx.changelog.to_string(),
+            "append_to_table_init: Added code to the end of Table1_Init\n"
+        );
+        Ok(())
+    }
+}
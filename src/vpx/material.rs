@@ -1,8 +1,8 @@
 use crate::vpx::biff;
-use crate::vpx::biff::{BiffRead, BiffReader, BiffWrite, BiffWriter};
+use crate::vpx::biff::{BiffRead, BiffReader, BiffWrite, BiffWriter, CheckedFrom};
 use crate::vpx::color::Color;
 use crate::vpx::json::F32WithNanInf;
-use crate::vpx::math::quantize_u8;
+use crate::vpx::math::{dequantize_u8, quantize_u8};
 use bytes::{Buf, BufMut, BytesMut};
 use encoding_rs::mem::{decode_latin1, encode_latin1_lossy};
 use fake::Dummy;
@@ -12,6 +12,11 @@ use std::io;
 
 const MAX_NAME_BUFFER: usize = 32;
 
+/// Size of a [`SaveMaterial`] record
+pub(crate) const SAVE_MATERIAL_SIZE: usize = 76;
+/// Size of a [`SavePhysicsMaterial`] record
+const SAVE_PHYSICS_MATERIAL_SIZE: usize = 48;
+
 #[derive(Dummy, Debug, Clone, PartialEq)]
 pub enum MaterialType {
     Unknown = -1, // found in Hot Line (Williams 1966) SG1bsoN.vpx
@@ -19,17 +24,25 @@ pub enum MaterialType {
     Metal = 1,
 }
 
-impl From<i32> for MaterialType {
-    fn from(value: i32) -> Self {
+impl CheckedFrom<i32> for MaterialType {
+    const FALLBACK: Self = MaterialType::Unknown;
+
+    fn checked_from(value: i32) -> Result<Self, String> {
         match value {
-            -1 => MaterialType::Unknown,
-            0 => MaterialType::Basic,
-            1 => MaterialType::Metal,
-            _ => panic!("Invalid MaterialType {}", value),
+            -1 => Ok(MaterialType::Unknown),
+            0 => Ok(MaterialType::Basic),
+            1 => Ok(MaterialType::Metal),
+            _ => Err(format!("Invalid MaterialType {}", value)),
         }
     }
 }
 
+impl From<i32> for MaterialType {
+    fn from(value: i32) -> Self {
+        Self::checked_from(value).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&MaterialType> for i32 {
     fn from(value: &MaterialType) -> Self {
         match value {
@@ -93,7 +106,7 @@ impl<'de> Deserialize<'de> for MaterialType {
 /**
  * Only used for backward compatibility loading and saving (VPX version < 10.8)
 */
-#[derive(Dummy, Debug, PartialEq)]
+#[derive(Dummy, Debug, PartialEq, Clone)]
 pub struct SaveMaterial {
     pub name: String,
     /**
@@ -258,13 +271,15 @@ impl SaveMaterialJson {
 }
 
 impl SaveMaterial {
-    pub(crate) fn read(bytes: &mut BytesMut) -> SaveMaterial {
-        if !bytes.has_remaining() {
-            panic!("No more bytes to read SaveMaterial from");
+    pub(crate) fn read(bytes: &mut BytesMut) -> io::Result<SaveMaterial> {
+        if bytes.remaining() < SAVE_MATERIAL_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "No more bytes to read SaveMaterial from",
+            ));
         }
-        // total should be 76 bytes
         // string can have max size of 32 bytes (including null terminator)
-        let name = read_padded_cstring(bytes, MAX_NAME_BUFFER).unwrap();
+        let name = read_padded_cstring(bytes, MAX_NAME_BUFFER)?;
         let base_color = bytes.get_u32_le();
         let glossy_color = bytes.get_u32_le();
         let clearcoat_color = bytes.get_u32_le();
@@ -283,7 +298,7 @@ impl SaveMaterial {
         // TODO split opacity_active_edge_alpha into on/off and edge weight
         get_padding_3_validate(bytes);
 
-        SaveMaterial {
+        Ok(SaveMaterial {
             name,
             base_color: Color::from_win_color(base_color),
             glossy_color: Color::from_win_color(glossy_color),
@@ -296,7 +311,7 @@ impl SaveMaterial {
             thickness,
             opacity,
             opacity_active_edge_alpha,
-        }
+        })
     }
 
     pub(crate) fn write(&self, bytes: &mut BytesMut) {
@@ -330,9 +345,9 @@ impl SaveMaterial {
 /**
  * Only used for backward compatibility loading and saving (VPX version < 10.8)
  */
-#[derive(Dummy, Debug, PartialEq)]
+#[derive(Dummy, Debug, PartialEq, Clone)]
 pub struct SavePhysicsMaterial {
-    name: String,
+    pub(crate) name: String,
     elasticity: f32,
     elasticity_falloff: f32,
     friction: f32,
@@ -382,25 +397,27 @@ impl SavePhysicsMaterialJson {
 }
 
 impl SavePhysicsMaterial {
-    pub(crate) fn read(bytes: &mut BytesMut) -> SavePhysicsMaterial {
-        if !bytes.has_remaining() {
-            panic!("No more bytes to read SavePhysicsMaterial from");
+    pub(crate) fn read(bytes: &mut BytesMut) -> io::Result<SavePhysicsMaterial> {
+        if bytes.remaining() < SAVE_PHYSICS_MATERIAL_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "No more bytes to read SavePhysicsMaterial from",
+            ));
         }
-        // total should be 24 bytes
         // string can have max size of 32 bytes (including null terminator)
-        let name = read_padded_cstring(bytes, MAX_NAME_BUFFER).unwrap();
+        let name = read_padded_cstring(bytes, MAX_NAME_BUFFER)?;
         let elasticity = bytes.get_f32_le();
         let elasticity_falloff = bytes.get_f32_le();
         let friction = bytes.get_f32_le();
         let scatter_angle = bytes.get_f32_le();
 
-        SavePhysicsMaterial {
+        Ok(SavePhysicsMaterial {
             name,
             elasticity,
             elasticity_falloff,
             friction,
             scatter_angle,
-        }
+        })
     }
 
     pub(crate) fn write(&self, bytes: &mut BytesMut) {
@@ -438,12 +455,8 @@ fn write_padded_cstring(str: &str, bytes: &mut BytesMut, len: usize) {
  */
 fn read_padded_cstring(bytes: &mut BytesMut, len: usize) -> Result<String, io::Error> {
     let cname = bytes.copy_to_bytes(len);
-    let cstr = CStr::from_bytes_until_nul(&cname).map_err(|_e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to read null-terminated string from bytes",
-        )
-    })?;
+    let cstr = CStr::from_bytes_until_nul(&cname)
+        .map_err(|_e| io::Error::other("Failed to read null-terminated string from bytes"))?;
     let s = decode_latin1(cstr.to_bytes());
     Ok(s.to_string())
 }
@@ -455,7 +468,7 @@ fn get_padding_3_validate(bytes: &mut BytesMut) {
     //assert_eq!(padding.to_vec(), [0, 0, 0]);
 }
 
-#[derive(Dummy, Debug, PartialEq)]
+#[derive(Dummy, Debug, PartialEq, Clone)]
 pub struct Material {
     pub name: String,
 
@@ -475,10 +488,10 @@ pub struct Material {
     pub opacity_active: bool,
 
     // physic properties
-    elasticity: f32,
-    elasticity_falloff: f32,
-    friction: f32,
-    scatter_angle: f32,
+    pub elasticity: f32,
+    pub elasticity_falloff: f32,
+    pub friction: f32,
+    pub scatter_angle: f32,
 
     refraction_tint: Color, // 10.8+ only
 }
@@ -552,6 +565,46 @@ impl MaterialJson {
     }
 }
 
+impl Material {
+    /// Converts the legacy (< 10.8) material representation, undoing the quantization
+    pub(crate) fn from_legacy(
+        save_material: &SaveMaterial,
+        physics: Option<&SavePhysicsMaterial>,
+    ) -> Self {
+        // m_fGlossyImageLerp = 1.f - dequantizeUnsigned<8>(mats[i].fGlossyImageLerp);
+        // m_fThickness = dequantizeUnsigned<8>(mats[i].fThickness);
+        // m_bOpacityActive = !!(mats[i].bOpacityActive_fEdgeAlpha & 1);
+        // m_fEdgeAlpha = dequantizeUnsigned<7>(mats[i].bOpacityActive_fEdgeAlpha >> 1);
+        let mut material = Material {
+            name: save_material.name.clone(),
+            type_: if save_material.is_metal {
+                MaterialType::Metal
+            } else {
+                MaterialType::Basic
+            },
+            wrap_lighting: save_material.wrap_lighting,
+            roughness: save_material.roughness,
+            glossy_image_lerp: 1.0 - dequantize_u8(8, save_material.glossy_image_lerp),
+            thickness: dequantize_u8(8, save_material.thickness),
+            edge: save_material.edge,
+            edge_alpha: dequantize_u8(7, save_material.opacity_active_edge_alpha >> 1),
+            opacity: save_material.opacity,
+            base_color: save_material.base_color,
+            glossy_color: save_material.glossy_color,
+            clearcoat_color: save_material.clearcoat_color,
+            opacity_active: save_material.opacity_active_edge_alpha & 1 == 1,
+            ..Default::default()
+        };
+        if let Some(physics) = physics {
+            material.elasticity = physics.elasticity;
+            material.elasticity_falloff = physics.elasticity_falloff;
+            material.friction = physics.friction;
+            material.scatter_angle = physics.scatter_angle;
+        }
+        material
+    }
+}
+
 impl Default for Material {
     fn default() -> Self {
         Material {
@@ -638,7 +691,7 @@ impl BiffRead for Material {
             let tag = reader.tag();
             let tag_str = tag.as_str();
             match tag_str {
-                "TYPE" => material.type_ = reader.get_i32().into(),
+                "TYPE" => material.type_ = reader.get_i32_checked(),
                 "NAME" => material.name = reader.get_string(),
                 "WLIG" => material.wrap_lighting = reader.get_f32(),
                 "ROUG" => material.roughness = reader.get_f32(),
@@ -708,7 +761,7 @@ mod tests {
         save_material.write(&mut bytes);
         // is there a better way to reset the cursor?
         bytes = BytesMut::from(bytes.to_vec().as_slice());
-        let read_save_material = SaveMaterial::read(&mut bytes);
+        let read_save_material = SaveMaterial::read(&mut bytes).unwrap();
         assert_eq!(save_material, read_save_material);
     }
 
@@ -719,7 +772,7 @@ mod tests {
         save_physics_material.write(&mut bytes);
         // is there a better way to reset the cursor?
         bytes = BytesMut::from(bytes.to_vec().as_slice());
-        let read_save_physics_material = SavePhysicsMaterial::read(&mut bytes);
+        let read_save_physics_material = SavePhysicsMaterial::read(&mut bytes).unwrap();
         assert_eq!(save_physics_material, read_save_physics_material);
     }
 
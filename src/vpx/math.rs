@@ -17,6 +17,15 @@ fn precise_divide(a: f32, b: f32) -> f32 {
     (a as f64 / b as f64) as f32
 }
 
+/// The f64 with the shortest decimal representation that reads back as the same f32.
+///
+/// Widening with `as f64` keeps the exact binary value which prints as eg
+/// `0.0000009999999974752427` instead of `0.000001`. Text formats should be written with this
+/// so the output only changes when the value does.
+pub fn canonical_f64(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value as f64)
+}
+
 #[inline]
 pub fn dequantize_unsigned_percent(i: u32) -> f32 {
     const N: f32 = 100.0;
@@ -60,10 +69,103 @@ pub fn dequantize_u8(bits: u8, i: u8) -> f32 {
     precise_divide(i as f32, n as f32).min(1.0)
 }
 
+/// A 3x3 matrix in row major order that transforms column vectors
+///
+/// In table coordinates x goes to the right, y towards the player and z up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3(pub [[f32; 3]; 3]);
+
+impl Mat3 {
+    pub const IDENTITY: Mat3 = Mat3([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    pub fn scale(x: f32, y: f32, z: f32) -> Mat3 {
+        Mat3([[x, 0.0, 0.0], [0.0, y, 0.0], [0.0, 0.0, z]])
+    }
+
+    /// Rotation around the z axis, positive angles turn clockwise when looking at the playfield
+    /// like rotations in Visual Pinball
+    pub fn rotation_z(degrees: f32) -> Mat3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat3([[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]])
+    }
+
+    pub fn rotation_x(degrees: f32) -> Mat3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat3([[1.0, 0.0, 0.0], [0.0, cos, -sin], [0.0, sin, cos]])
+    }
+
+    pub fn rotation_y(degrees: f32) -> Mat3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Mat3([[cos, 0.0, sin], [0.0, 1.0, 0.0], [-sin, 0.0, cos]])
+    }
+
+    /// Mirrors left and right
+    pub fn mirror_x() -> Mat3 {
+        Mat3::scale(-1.0, 1.0, 1.0)
+    }
+
+    pub fn transform(&self, v: [f32; 3]) -> [f32; 3] {
+        let m = &self.0;
+        [0, 1, 2].map(|row| m[row][0] * v[0] + m[row][1] * v[1] + m[row][2] * v[2])
+    }
+
+    /// The matrix that first applies `other` and then `self`
+    pub fn mul(&self, other: &Mat3) -> Mat3 {
+        let (a, b) = (&self.0, &other.0);
+        Mat3([0, 1, 2].map(|row| {
+            [0, 1, 2].map(|column| {
+                a[row][0] * b[0][column] + a[row][1] * b[1][column] + a[row][2] * b[2][column]
+            })
+        }))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_canonical_f64() {
+        assert_eq!(canonical_f64(1e-6).to_string(), "0.000001");
+        assert_eq!(canonical_f64(-0.1).to_string(), "-0.1");
+        for value in [
+            1e-6,
+            0.1,
+            123.456,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+            -0.0,
+            f32::INFINITY,
+        ] {
+            assert_eq!((canonical_f64(value) as f32).to_bits(), value.to_bits());
+        }
+        assert!(canonical_f64(f32::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_mat3() {
+        let rotation = Mat3::rotation_z(90.0);
+        let [x, y, z] = rotation.transform([0.0, -1.0, 0.0]);
+        // up on the playfield turns to the right
+        assert!((x - 1.0).abs() < 1e-6 && y.abs() < 1e-6 && z == 0.0);
+        let mirrored = Mat3::mirror_x().mul(&rotation);
+        assert!((mirrored.transform([0.0, -1.0, 0.0])[0] + 1.0).abs() < 1e-6);
+        assert!((mirrored.determinant() + 1.0).abs() < 1e-6);
+        assert_eq!(
+            Mat3::IDENTITY
+                .mul(&Mat3::scale(2.0, 3.0, 4.0))
+                .determinant(),
+            24.0
+        );
+    }
+
     #[test]
     fn test_quantize_u8_8() {
         assert_eq!(quantize_u8(8, 0.0), 0);
@@ -0,0 +1,252 @@
+//! Visual Pinball material library files (`.mat`)
+//!
+//! The material manager of the Visual Pinball editor exports and imports selected materials
+//! as `.mat` files, which is how material libraries are shared in the community. The file
+//! holds a version, the number of materials and for every material the legacy
+//! [`SaveMaterial`] record followed by its elasticity, elasticity falloff, friction and
+//! scatter angle, all little endian.
+//!
+//! The legacy record stores some values quantized, reading a library that was written from
+//! table materials gives materials that can differ slightly in glossy image lerp, thickness
+//! and edge alpha.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::matlib;
+//!
+//! let mut vpx = vpx::read(&PathBuf::from("table.vpx")).unwrap();
+//! let library = matlib::read("plastics.mat").unwrap();
+//! let result = matlib::apply(&mut vpx, &library);
+//! println!("added {:?}, replaced {:?}", result.added, result.replaced);
+//! ```
+
+use super::import::{find_material, put_material};
+use super::material::{Material, SaveMaterial, SAVE_MATERIAL_SIZE};
+use super::VPX;
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use std::path::Path;
+
+/// The only version Visual Pinball reads and writes
+pub const MATLIB_VERSION: i32 = 1;
+
+/// Elasticity, elasticity falloff, friction and scatter angle
+const PHYSICS_SIZE: usize = 4 * 4;
+/// Names are stored in 32 bytes including the terminating 0
+const MAX_NAME_LEN: usize = 31;
+
+/// What [`apply`] did with the library materials
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ApplyResult {
+    pub added: Vec<String>,
+    /// Table materials with the same name, compared case-insensitive
+    pub replaced: Vec<String>,
+}
+
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<Material>> {
+    from_bytes(&std::fs::read(path)?)
+}
+
+pub fn write<P: AsRef<Path>>(path: P, materials: &[Material]) -> io::Result<()> {
+    std::fs::write(path, to_bytes(materials)?)
+}
+
+pub fn from_bytes(data: &[u8]) -> io::Result<Vec<Material>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    if data.len() < 8 {
+        return Err(invalid("Material library is too short".to_string()));
+    }
+    let mut bytes = BytesMut::from(data);
+    let version = bytes.get_i32_le();
+    if version != MATLIB_VERSION {
+        return Err(invalid(format!(
+            "Unsupported material library version {}",
+            version
+        )));
+    }
+    let count = bytes.get_i32_le();
+    let expected = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(SAVE_MATERIAL_SIZE + PHYSICS_SIZE));
+    if expected != Some(bytes.len()) {
+        return Err(invalid(format!(
+            "Material library with {} materials has {} bytes of material data",
+            count,
+            bytes.len()
+        )));
+    }
+    let mut materials = Vec::new();
+    while bytes.has_remaining() {
+        let save_material = SaveMaterial::read(&mut bytes)?;
+        let mut material = Material::from_legacy(&save_material, None);
+        material.elasticity = bytes.get_f32_le();
+        material.elasticity_falloff = bytes.get_f32_le();
+        material.friction = bytes.get_f32_le();
+        material.scatter_angle = bytes.get_f32_le();
+        materials.push(material);
+    }
+    Ok(materials)
+}
+
+pub fn to_bytes(materials: &[Material]) -> io::Result<Vec<u8>> {
+    if let Some(material) = materials
+        .iter()
+        .find(|m| m.name.chars().count() > MAX_NAME_LEN)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Material name {} is longer than {} characters",
+                material.name, MAX_NAME_LEN
+            ),
+        ));
+    }
+    let count = i32::try_from(materials.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Too many materials"))?;
+    let mut bytes =
+        BytesMut::with_capacity(8 + materials.len() * (SAVE_MATERIAL_SIZE + PHYSICS_SIZE));
+    bytes.put_i32_le(MATLIB_VERSION);
+    bytes.put_i32_le(count);
+    for material in materials {
+        SaveMaterial::from(material).write(&mut bytes);
+        bytes.put_f32_le(material.elasticity);
+        bytes.put_f32_le(material.elasticity_falloff);
+        bytes.put_f32_le(material.friction);
+        bytes.put_f32_le(material.scatter_angle);
+    }
+    Ok(bytes.to_vec())
+}
+
+/// All materials of a table, converted from the legacy format for tables before 10.8
+pub fn table_materials(vpx: &VPX) -> Vec<Material> {
+    let gamedata = &vpx.gamedata;
+    match &gamedata.materials {
+        Some(materials) => materials.clone(),
+        None => gamedata
+            .materials_old
+            .iter()
+            .filter_map(|m| find_material(gamedata, &m.name))
+            .collect(),
+    }
+}
+
+/// Adds the materials to a table, replacing table materials with the same name
+pub fn apply(vpx: &mut VPX, materials: &[Material]) -> ApplyResult {
+    let mut result = ApplyResult::default();
+    for material in materials {
+        let action = match find_material(&vpx.gamedata, &material.name) {
+            Some(existing) => {
+                result.replaced.push(existing.name);
+                "Replaced"
+            }
+            None => {
+                result.added.push(material.name.clone());
+                "Added"
+            }
+        };
+        put_material(&mut vpx.gamedata, material.clone());
+        vpx.changelog.record(
+            "apply_materials",
+            format!("{} material {}", action, material.name),
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::color::Color;
+    use crate::vpx::material::MaterialType;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    fn material(name: &str) -> Material {
+        let mut material = Material::default();
+        material.name = name.to_string();
+        material.type_ = MaterialType::Metal;
+        material.base_color = Color::from_rgb(0x123456);
+        material.roughness = 0.75;
+        material.opacity = 0.5;
+        material.opacity_active = true;
+        // values that survive the quantization
+        material.glossy_image_lerp = 1.0;
+        material.thickness = 1.0;
+        material.edge_alpha = 1.0;
+        material.elasticity = 0.3;
+        material.elasticity_falloff = 0.1;
+        material.friction = 0.6;
+        material.scatter_angle = 5.0;
+        material
+    }
+
+    #[test]
+    fn read_write() -> io::Result<()> {
+        let path = testdir!().join("library.mat");
+        let materials = vec![material("Plastic"), material("Metal Rails")];
+        write(&path, &materials)?;
+        let data = std::fs::read(&path)?;
+        assert_eq!(data.len(), 8 + 2 * 92);
+        assert_eq!(&data[..8], &[1, 0, 0, 0, 2, 0, 0, 0]);
+
+        let read_materials = read(&path)?;
+        assert_eq!(read_materials.len(), 2);
+        assert_eq!(read_materials[1].name, "Metal Rails");
+        assert_eq!(read_materials[1].type_, MaterialType::Metal);
+        assert_eq!(read_materials[1].base_color, Color::from_rgb(0x123456));
+        assert_eq!(read_materials[1].friction, 0.6);
+        assert_eq!(read_materials[1].scatter_angle, 5.0);
+        assert!(read_materials[1].opacity_active);
+        assert_eq!(read_materials[1].thickness, materials[1].thickness);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_files() {
+        let mut data = to_bytes(&[material("Plastic")]).unwrap();
+        data[0] = 2;
+        assert_eq!(
+            from_bytes(&data).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        data[0] = 1;
+        data.pop();
+        assert_eq!(
+            from_bytes(&data).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        let long_name = material(&"x".repeat(32));
+        assert_eq!(
+            to_bytes(&[long_name]).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn apply_to_legacy_table() {
+        let mut vpx = VPX::default();
+        put_material(&mut vpx.gamedata, material("Plastic"));
+        let mut plastic = material("plastic");
+        plastic.friction = 0.9;
+
+        let result = apply(&mut vpx, &[plastic, material("Rubber")]);
+        assert_eq!(
+            result,
+            ApplyResult {
+                added: vec!["Rubber".to_string()],
+                replaced: vec!["Plastic".to_string()],
+            }
+        );
+        let materials = table_materials(&vpx);
+        let names: Vec<&str> = materials.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["plastic", "Rubber"]);
+        assert_eq!(vpx.gamedata.materials_size, 2);
+        assert_eq!(
+            vpx.changelog.to_string(),
+            "apply_materials: Replaced material plastic\napply_materials: Added material Rubber\n"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Meshes for the collision shapes of a table
+//!
+//! These follow the physics geometry instead of the visuals, so differences between what
+//! the ball hits and what is rendered become visible when both are shown together.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::mesh::collision::{collider_layers, colliders};
+//! use vpin::vpx::mesh::glb::write_glb;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let colliders = colliders(&vpx).unwrap();
+//! let mut glb = Vec::new();
+//! write_glb(&collider_layers(colliders), &mut glb).unwrap();
+//! ```
+
+use super::glb::{GlbMaterial, Layer, LayerMesh};
+use super::wall::{wall as wall_meshes, WallMeshes};
+use super::{spline, tube, Mesh, TUBE_SIDES};
+use crate::vpx::gameitem::rubber::Rubber;
+use crate::vpx::gameitem::wall::Wall;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+use std::io;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ColliderKind {
+    Wall,
+    Rubber,
+    /// A primitive that is collidable and not a toy, its collision geometry is the visual mesh
+    Primitive,
+}
+
+impl ColliderKind {
+    pub fn layer_name(&self) -> &'static str {
+        match self {
+            ColliderKind::Wall => "colliders_walls",
+            ColliderKind::Rubber => "colliders_rubbers",
+            ColliderKind::Primitive => "colliders_primitives",
+        }
+    }
+
+    /// RGBA color for the layer, semi transparent so the visuals stay visible
+    pub fn color(&self) -> [f32; 4] {
+        match self {
+            ColliderKind::Wall => [1.0, 0.0, 0.0, 0.5],
+            ColliderKind::Rubber => [0.0, 1.0, 0.0, 0.5],
+            ColliderKind::Primitive => [1.0, 0.0, 1.0, 0.5],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Collider {
+    pub kind: ColliderKind,
+    pub mesh: Mesh,
+}
+
+/// Builds the collision meshes for all collidable walls, rubbers and primitives.
+///
+/// Rubber rotations are not applied.
+pub fn colliders(vpx: &VPX) -> io::Result<Vec<Collider>> {
+    let mut colliders = Vec::new();
+    for item in &vpx.gameitems {
+        let collider = match item {
+            GameItemEnum::Wall(wall) if wall.is_collidable => {
+                wall_collider(vpx, wall).map(|mesh| Collider {
+                    kind: ColliderKind::Wall,
+                    mesh,
+                })
+            }
+            GameItemEnum::Rubber(rubber) if rubber.is_collidable => {
+                rubber_collider(rubber).map(|mesh| Collider {
+                    kind: ColliderKind::Rubber,
+                    mesh,
+                })
+            }
+            GameItemEnum::Primitive(primitive) if primitive.is_collidable && !primitive.is_toy => {
+                Mesh::from_primitive_transformed(primitive)?.map(|mesh| Collider {
+                    kind: ColliderKind::Primitive,
+                    mesh,
+                })
+            }
+            _ => None,
+        };
+        colliders.extend(collider);
+    }
+    Ok(colliders)
+}
+
+/// Groups colliders in one GLB layer per [`ColliderKind`], empty layers are left out
+pub fn collider_layers(colliders: Vec<Collider>) -> Vec<Layer> {
+    [
+        ColliderKind::Wall,
+        ColliderKind::Rubber,
+        ColliderKind::Primitive,
+    ]
+    .into_iter()
+    .filter_map(|kind| {
+        let material = GlbMaterial::color(kind.layer_name(), kind.color());
+        let meshes: Vec<LayerMesh> = colliders
+            .iter()
+            .filter(|collider| collider.kind == kind)
+            .map(|collider| LayerMesh {
+                mesh: collider.mesh.clone(),
+                material: material.clone(),
+            })
+            .collect();
+        (!meshes.is_empty()).then(|| Layer {
+            name: kind.layer_name().to_string(),
+            meshes,
+        })
+    })
+    .collect()
+}
+
+/// The sides from bottom to top height and the top surface
+fn wall_collider(vpx: &VPX, wall: &Wall) -> Option<Mesh> {
+    let WallMeshes { mut side, top } = wall_meshes(vpx, wall);
+    if side.vertices.is_empty() {
+        return None;
+    }
+    side.append(top);
+    Some(side)
+}
+
+/// A tube with the rubber thickness as diameter at the hit height
+fn rubber_collider(rubber: &Rubber) -> Option<Mesh> {
+    let height = rubber.hit_height.unwrap_or(rubber.height);
+    let mut center: Vec<[f32; 3]> = spline(&rubber.drag_points, true)
+        .iter()
+        .map(|p| [p[0], p[1], height])
+        .collect();
+    center.dedup();
+    if center.len() < 2 {
+        return None;
+    }
+    let radius = rubber.thickness as f32 / 2.0;
+    Some(tube(&rubber.name, &center, radius, TUBE_SIDES, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use crate::vpx::gameitem::primitive::Primitive;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    fn points(coordinates: &[(f32, f32)]) -> Vec<DragPoint> {
+        coordinates
+            .iter()
+            .map(|&(x, y)| DragPoint {
+                x,
+                y,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_colliders() {
+        let mut vpx = VPX::default();
+        let mut wall = Wall {
+            name: "Wall".to_string(),
+            is_collidable: true,
+            height_bottom: 0.0,
+            height_top: 50.0,
+            ..Default::default()
+        };
+        // concave L shape, clockwise on screen
+        wall.drag_points = points(&[
+            (0.0, 0.0),
+            (0.0, 20.0),
+            (20.0, 20.0),
+            (20.0, 10.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+        ]);
+        vpx.add_game_item(GameItemEnum::Wall(wall));
+        let mut rubber = Rubber {
+            name: "Rubber".to_string(),
+            is_collidable: true,
+            height: 25.0,
+            hit_height: None,
+            thickness: 8,
+            ..Default::default()
+        };
+        rubber.drag_points = points(&[(0.0, 0.0), (100.0, 0.0), (50.0, 50.0)]);
+        vpx.add_game_item(GameItemEnum::Rubber(rubber));
+        let mut hidden = Rubber {
+            is_collidable: false,
+            ..Default::default()
+        };
+        hidden.drag_points = points(&[(0.0, 0.0), (100.0, 0.0), (50.0, 50.0)]);
+        vpx.add_game_item(GameItemEnum::Rubber(hidden));
+        let mut toy: Primitive = Faker.fake();
+        toy.is_collidable = true;
+        toy.is_toy = true;
+        vpx.add_game_item(GameItemEnum::Primitive(toy));
+
+        let colliders = colliders(&vpx).unwrap();
+
+        assert_eq!(
+            colliders.iter().map(|c| c.kind).collect::<Vec<_>>(),
+            vec![ColliderKind::Wall, ColliderKind::Rubber]
+        );
+        let wall = &colliders[0].mesh;
+        // 6 sides of 2 triangles and a top of 4 triangles
+        assert_eq!(wall.triangle_count(), 16);
+        let top_area: f32 = wall.indices[36..]
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|i| wall.vertices[i as usize].position);
+                ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+            })
+            .sum();
+        assert_eq!(top_area, 300.0);
+        assert!(wall
+            .vertices
+            .iter()
+            .all(|v| v.position[2] == 0.0 || v.position[2] == 50.0));
+
+        let rubber = &colliders[1].mesh;
+        assert_eq!(rubber.vertices.len(), 3 * TUBE_SIDES);
+        assert_eq!(rubber.triangle_count(), 3 * TUBE_SIDES * 2);
+        let top = rubber
+            .vertices
+            .iter()
+            .map(|v| v.position[2])
+            .fold(0.0, f32::max);
+        assert!((top - 29.0).abs() < 1e-4);
+
+        let layers = collider_layers(colliders);
+        assert_eq!(
+            layers.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(),
+            vec!["colliders_walls", "colliders_rubbers"]
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Primitive meshes and the `obj` files they were imported from
+//!
+//! [`Primitive::mesh_file_name`] holds the path of the `obj` file a mesh was imported from,
+//! the editor re-imports the mesh from there. The mesh itself is always stored in the table,
+//! Visual Pinball needs it to render the primitive. Authors often save the absolute path on
+//! their own machine, so [`resolve_mesh_file`] also looks for the file name next to the
+//! table.
+//!
+//! The `obj` files use the same conventions as the editor import and the expanded format:
+//! z is inverted and so is the winding of the triangles. Modelling tools often share a
+//! position between face corners with different texture coordinates or normals, the
+//! vertices are rebuilt from the indices of every face corner in that case.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use vpin::vpx;
+//! use vpin::vpx::gameitem::GameItemEnum;
+//! use vpin::vpx::mesh::external::inline_mesh;
+//!
+//! let path = Path::new("table.vpx");
+//! let mut vpx = vpx::read(&path.to_path_buf()).unwrap();
+//! for item in vpx.gameitems.iter_mut() {
+//!     if let GameItemEnum::Primitive(primitive) = item {
+//!         // reload the meshes that were changed in a modelling tool
+//!         inline_mesh(primitive, path.parent().unwrap()).unwrap();
+//!     }
+//! }
+//! ```
+
+use super::{Mesh, Vertex};
+use crate::vpx::gameitem::primitive::Primitive;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::model::Vertex3dNoTex2;
+use crate::vpx::obj::{read_obj_file, write_obj};
+use crate::vpx::VPX;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A primitive that refers to an `obj` file that can not be found
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MissingMeshFile {
+    pub primitive: String,
+    pub mesh_file_name: String,
+}
+
+impl fmt::Display for MissingMeshFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Primitive {} refers to missing mesh file {}",
+            self.primitive, self.mesh_file_name
+        )
+    }
+}
+
+/// Finds the `obj` file of a primitive, relative paths are relative to `table_dir`.
+///
+/// Falls back to the file name in `table_dir`, paths can use `\` as separator.
+pub fn resolve_mesh_file(primitive: &Primitive, table_dir: &Path) -> Option<PathBuf> {
+    let mesh_file_name = primitive.mesh_file_name.as_ref()?;
+    if mesh_file_name.is_empty() {
+        return None;
+    }
+    let normalized = mesh_file_name.replace('\\', "/");
+    let given = table_dir.join(&normalized);
+    if given.is_file() {
+        return Some(given);
+    }
+    let file_name = normalized.rsplit('/').next()?;
+    let next_to_table = table_dir.join(file_name);
+    next_to_table.is_file().then_some(next_to_table)
+}
+
+/// The primitives with a mesh file name that can not be resolved, see [`resolve_mesh_file`]
+pub fn missing_mesh_files(vpx: &VPX, table_dir: &Path) -> Vec<MissingMeshFile> {
+    vpx.gameitems
+        .iter()
+        .filter_map(|item| match item {
+            GameItemEnum::Primitive(primitive) => Some(primitive),
+            _ => None,
+        })
+        .filter(|primitive| {
+            primitive
+                .mesh_file_name
+                .as_ref()
+                .is_some_and(|name| !name.is_empty())
+                && resolve_mesh_file(primitive, table_dir).is_none()
+        })
+        .map(|primitive| MissingMeshFile {
+            primitive: primitive.name.clone(),
+            mesh_file_name: primitive.mesh_file_name.clone().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Replaces the mesh of a primitive with the one in its `obj` file.
+///
+/// Returns `false` if the primitive has no mesh file name, fails if the file can not be found.
+pub fn inline_mesh(primitive: &mut Primitive, table_dir: &Path) -> io::Result<bool> {
+    if primitive
+        .mesh_file_name
+        .as_ref()
+        .is_none_or(|name| name.is_empty())
+    {
+        return Ok(false);
+    }
+    let path = resolve_mesh_file(primitive, table_dir).ok_or_else(|| {
+        let missing = MissingMeshFile {
+            primitive: primitive.name.clone(),
+            mesh_file_name: primitive.mesh_file_name.clone().unwrap_or_default(),
+        };
+        io::Error::new(io::ErrorKind::NotFound, missing.to_string())
+    })?;
+    let mesh = read_mesh_file(&path)?;
+    mesh.write_to_primitive(primitive)?;
+    Ok(true)
+}
+
+/// Writes the mesh of a primitive to an `obj` file and makes the primitive refer to it.
+///
+/// The mesh stays in the table. Returns `false` if the primitive has no 3D mesh.
+pub fn externalize_mesh(primitive: &mut Primitive, obj_path: &Path) -> io::Result<bool> {
+    let Some(mesh) = Mesh::from_primitive(primitive)? else {
+        return Ok(false);
+    };
+    let vertices: Vec<([u8; 32], Vertex3dNoTex2)> = mesh
+        .vertices
+        .iter()
+        .map(|v| {
+            let mut bytes = [0u8; 32];
+            let values = v.position.iter().chain(&v.normal).chain(&v.uv);
+            for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+            let vertex = Vertex3dNoTex2 {
+                x: v.position[0],
+                y: v.position[1],
+                z: -v.position[2],
+                nx: v.normal[0],
+                ny: v.normal[1],
+                nz: -v.normal[2],
+                tu: v.uv[0],
+                tv: v.uv[1],
+            };
+            (bytes, vertex)
+        })
+        .collect();
+    let indices: Vec<i64> = mesh
+        .indices
+        .chunks_exact(3)
+        .flat_map(|t| [t[2] as i64, t[1] as i64, t[0] as i64])
+        .collect();
+    write_obj(
+        primitive.name.clone(),
+        &vertices,
+        &indices,
+        &obj_path.to_path_buf(),
+    )
+    .map_err(|e| io::Error::other(format!("Error writing {}: {}", obj_path.display(), e)))?;
+    primitive.mesh_file_name = Some(obj_path.display().to_string());
+    Ok(true)
+}
+
+/// Reads a mesh from an `obj` file.
+///
+/// When every face corner uses the same index for the position, texture coordinate and
+/// normal, like the files written by [`externalize_mesh`], the vertices are read in file
+/// order. Otherwise every distinct combination of indices becomes a vertex. A corner
+/// without a texture coordinate or normal gets zeros.
+fn read_mesh_file(path: &Path) -> io::Result<Mesh> {
+    let obj = read_obj_file(&path.to_path_buf())
+        .map_err(|e| io::Error::other(format!("Error reading {}: {}", path.display(), e)))?;
+    let vertex = |v: usize, vt: Option<usize>, vn: Option<usize>| {
+        let invalid = |kind: &str, index: usize| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid {} index {} in {}", kind, index + 1, path.display()),
+            )
+        };
+        let p = obj.vertices.get(v).ok_or_else(|| invalid("vertex", v))?;
+        let uv = match vt {
+            Some(vt) => {
+                let t = obj
+                    .texture_coordinates
+                    .get(vt)
+                    .ok_or_else(|| invalid("texture coordinate", vt))?;
+                [t.0 as f32, t.1.unwrap_or(0.0) as f32]
+            }
+            None => [0.0, 0.0],
+        };
+        let normal = match vn {
+            Some(vn) => {
+                let (n, _) = obj.normals.get(vn).ok_or_else(|| invalid("normal", vn))?;
+                [n.0 as f32, n.1 as f32, -(n.2 as f32)]
+            }
+            None => [0.0, 0.0, 0.0],
+        };
+        Ok::<_, io::Error>(Vertex {
+            position: [p.0 as f32, p.1 as f32, -(p.2 as f32)],
+            normal,
+            uv,
+        })
+    };
+    // relative indices count back from the end
+    let resolve = |index: i64, len: usize| {
+        if index < 0 {
+            (len as i64 + index + 1).max(0) as usize
+        } else {
+            index as usize
+        }
+    };
+    let matching = obj.vertices.len() == obj.texture_coordinates.len()
+        && obj.vertices.len() == obj.normals.len()
+        && obj
+            .face_vertices
+            .iter()
+            .all(|(v, vt, vn)| *vt == Some(*v) && *vn == Some(*v));
+    let mut vertices = Vec::new();
+    let mut corners = Vec::with_capacity(obj.face_vertices.len());
+    if matching {
+        for index in 0..obj.vertices.len() {
+            vertices.push(vertex(index, Some(index), Some(index))?);
+        }
+        for (v, _, _) in &obj.face_vertices {
+            corners.push(resolve(*v, obj.vertices.len()) as u32);
+        }
+    } else {
+        let mut shared: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+        for (v, vt, vn) in &obj.face_vertices {
+            let key = (
+                resolve(*v, obj.vertices.len()),
+                vt.map(|vt| resolve(vt, obj.texture_coordinates.len())),
+                vn.map(|vn| resolve(vn, obj.normals.len())),
+            );
+            let index = match shared.get(&key) {
+                Some(index) => *index,
+                None => {
+                    vertices.push(vertex(key.0, key.1, key.2)?);
+                    let index = vertices.len() as u32 - 1;
+                    shared.insert(key, index);
+                    index
+                }
+            };
+            corners.push(index);
+        }
+    }
+    let indices = corners
+        .chunks_exact(3)
+        .flat_map(|t| [t[2], t[1], t[0]])
+        .collect();
+    Ok(Mesh {
+        name: obj.name,
+        vertices,
+        indices,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn inline_mesh_with_separate_indices() -> io::Result<()> {
+        let dir = testdir!();
+        // a quad sharing its positions and normal, with a texture seam on the diagonal
+        let obj = "o quad\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                   vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvt 0.5 0.5\n\
+                   vn 0 0 -1\n\
+                   f 1/1/1 2/2/1 3/3/1\nf 1/5/1 3/3/1 4/4/1\n";
+        std::fs::write(dir.join("quad.obj"), obj)?;
+        let mut primitive: Primitive = Faker.fake();
+        primitive.mesh_file_name = Some("quad.obj".to_string());
+        primitive.compressed_animation_vertices_len = None;
+        primitive.compressed_animation_vertices_data = None;
+
+        assert!(inline_mesh(&mut primitive, &dir)?);
+
+        let mesh = Mesh::from_primitive(&primitive)?.unwrap();
+        let vertices: Vec<([f32; 3], [f32; 3], [f32; 2])> = mesh
+            .vertices
+            .iter()
+            .map(|v| (v.position, v.normal, v.uv))
+            .collect();
+        assert_eq!(
+            vertices,
+            vec![
+                ([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0]),
+                ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0]),
+                ([1.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0]),
+                ([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.5, 0.5]),
+                ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0]),
+            ]
+        );
+        assert_eq!(mesh.indices, vec![2, 1, 0, 4, 2, 3]);
+
+        std::fs::write(
+            dir.join("quad.obj"),
+            format!("{}f 1/9/1 2/2/1 3/3/1\n", obj),
+        )?;
+        let error = inline_mesh(&mut primitive, &dir).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    #[test]
+    fn externalize_and_inline() -> io::Result<()> {
+        let dir = testdir!();
+        let vertex = |x: f32, y: f32, z: f32| Vertex {
+            position: [x, y, z],
+            normal: [0.0, 0.0, 1.0],
+            uv: [x, y],
+        };
+        let mesh = Mesh {
+            name: "triangle".to_string(),
+            vertices: vec![
+                vertex(0.0, 0.0, 1.0),
+                vertex(1.0, 0.0, 2.0),
+                vertex(1.0, 1.0, 3.0),
+            ],
+            indices: vec![0, 1, 2],
+        };
+        let mut primitive: Primitive = Faker.fake();
+        primitive.name = "triangle".to_string();
+        primitive.compressed_animation_vertices_len = None;
+        primitive.compressed_animation_vertices_data = None;
+        mesh.write_to_primitive(&mut primitive)?;
+
+        assert!(externalize_mesh(&mut primitive, &dir.join("triangle.obj"))?);
+
+        // the path as saved on another machine
+        primitive.mesh_file_name = Some("C:\\Users\\someone\\triangle.obj".to_string());
+        assert_eq!(
+            resolve_mesh_file(&primitive, &dir),
+            Some(dir.join("triangle.obj"))
+        );
+        Mesh::default().write_to_primitive(&mut primitive)?;
+        assert!(inline_mesh(&mut primitive, &dir)?);
+        let read = Mesh::from_primitive(&primitive)?.unwrap();
+        assert_eq!(read.vertices, mesh.vertices);
+        assert_eq!(read.indices, mesh.indices);
+
+        let mut vpx = VPX::default();
+        primitive.mesh_file_name = Some("missing.obj".to_string());
+        vpx.add_game_item(GameItemEnum::Primitive(primitive));
+        assert_eq!(
+            missing_mesh_files(&vpx, &dir),
+            vec![MissingMeshFile {
+                primitive: "triangle".to_string(),
+                mesh_file_name: "missing.obj".to_string(),
+            }]
+        );
+        Ok(())
+    }
+}
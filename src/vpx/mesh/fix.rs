@@ -0,0 +1,290 @@
+//! Repairs for imported primitive meshes that render black or with a mirrored texture
+//!
+//! Visual Pinball culls the back of triangles, so a triangle with reversed winding is only
+//! visible from behind, where it is lit from the wrong side. The vertex normals tell which
+//! side is meant to be the front. A stored triangle `a, b, c` faces the way of
+//! `(b - a) x (c - a)`, [`normal_consistency`] compares that with the vertex normals.
+//!
+//! Texture coordinates use `v = 0` for the top of the image. Meshes exported for tools that
+//! use `v = 0` for the bottom show a vertically mirrored texture. Mirroring turns the
+//! triangles around in texture space, [`uv_orientation`] counts them. Symmetric models often
+//! share mirrored texture islands on purpose, so this is only a hint when most triangles
+//! are mirrored.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::gameitem::primitive::Primitive;
+//! use vpin::vpx::mesh::{check_primitive, fix_winding, flip_v};
+//!
+//! fn repair(primitive: &mut Primitive) -> std::io::Result<()> {
+//!     if let Some(check) = check_primitive(primitive)? {
+//!         if check.winding_reversed() {
+//!             fix_winding(primitive)?;
+//!         }
+//!         if check.v_flipped() {
+//!             flip_v(primitive)?;
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::render::vertex;
+use super::{cross, sub, Mesh, Vertex};
+use crate::vpx::gameitem::primitive::Primitive;
+use std::io;
+
+/// How the winding of the triangles compares with their vertex normals
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct NormalConsistency {
+    /// Triangles facing the same way as their vertex normals
+    pub agreeing: usize,
+    /// Triangles facing away from their vertex normals
+    pub opposing: usize,
+    /// Triangles without area or without usable normals
+    pub degenerate: usize,
+}
+
+impl NormalConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.opposing == 0
+    }
+}
+
+/// How the triangles are oriented in texture space, see the [module docs](self)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct UvOrientation {
+    pub matching: usize,
+    pub mirrored: usize,
+    /// Triangles without area in texture space
+    pub degenerate: usize,
+}
+
+/// The result of [`check_primitive`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct MeshCheck {
+    pub normals: NormalConsistency,
+    pub uvs: UvOrientation,
+}
+
+impl MeshCheck {
+    /// Any triangle faces away from its vertex normals, [`fix_winding`] turns those around
+    pub fn winding_reversed(&self) -> bool {
+        !self.normals.is_consistent()
+    }
+
+    /// Most of the triangles are mirrored in texture space, [`flip_v`] might fix this
+    pub fn v_flipped(&self) -> bool {
+        self.uvs.mirrored > self.uvs.matching
+    }
+}
+
+/// Compares the winding of every triangle with the sum of its vertex normals.
+///
+/// Fails for indices that point outside the vertices.
+pub fn normal_consistency(mesh: &Mesh) -> io::Result<NormalConsistency> {
+    let mut result = NormalConsistency::default();
+    for triangle in mesh.indices.chunks_exact(3) {
+        match facing(mesh, triangle)? {
+            Some(true) => result.agreeing += 1,
+            Some(false) => result.opposing += 1,
+            None => result.degenerate += 1,
+        }
+    }
+    Ok(result)
+}
+
+/// Counts the triangles that are mirrored in texture space.
+///
+/// Fails for indices that point outside the vertices.
+pub fn uv_orientation(mesh: &Mesh) -> io::Result<UvOrientation> {
+    let mut result = UvOrientation::default();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = triangle_vertices(mesh, triangle)?.map(|v| v.uv);
+        let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        // meshes exported by Visual Pinball have a negative area in texture space
+        if area < 0.0 {
+            result.matching += 1;
+        } else if area > 0.0 {
+            result.mirrored += 1;
+        } else {
+            result.degenerate += 1;
+        }
+    }
+    Ok(result)
+}
+
+/// Checks the mesh of a primitive, `None` if the primitive has no 3D mesh
+pub fn check_primitive(primitive: &Primitive) -> io::Result<Option<MeshCheck>> {
+    let Some(mesh) = Mesh::from_primitive(primitive)? else {
+        return Ok(None);
+    };
+    Ok(Some(MeshCheck {
+        normals: normal_consistency(&mesh)?,
+        uvs: uv_orientation(&mesh)?,
+    }))
+}
+
+/// Turns around the triangles that face away from their vertex normals.
+///
+/// Returns the number of triangles that were changed.
+pub fn fix_winding(primitive: &mut Primitive) -> io::Result<usize> {
+    let Some(mut mesh) = Mesh::from_primitive(primitive)? else {
+        return Ok(0);
+    };
+    let mut fixed = 0;
+    for t in 0..mesh.triangle_count() {
+        let triangle = &mesh.indices[t * 3..t * 3 + 3];
+        if facing(&mesh, triangle)? == Some(false) {
+            mesh.indices.swap(t * 3 + 1, t * 3 + 2);
+            fixed += 1;
+        }
+    }
+    if fixed > 0 {
+        mesh.write_to_primitive(primitive)?;
+    }
+    Ok(fixed)
+}
+
+/// Mirrors the texture coordinates vertically, `v` becomes `1 - v`.
+///
+/// Returns `false` if the primitive has no 3D mesh.
+pub fn flip_v(primitive: &mut Primitive) -> io::Result<bool> {
+    let Some(mut mesh) = Mesh::from_primitive(primitive)? else {
+        return Ok(false);
+    };
+    for vertex in &mut mesh.vertices {
+        vertex.uv[1] = 1.0 - vertex.uv[1];
+    }
+    mesh.write_to_primitive(primitive)?;
+    Ok(true)
+}
+
+/// Whether the triangle faces the same way as its vertex normals
+fn facing(mesh: &Mesh, triangle: &[u32]) -> io::Result<Option<bool>> {
+    let [a, b, c] = triangle_vertices(mesh, triangle)?;
+    let face = cross(sub(b.position, a.position), sub(c.position, a.position));
+    let normal: [f32; 3] =
+        std::array::from_fn(|axis| a.normal[axis] + b.normal[axis] + c.normal[axis]);
+    let dot: f32 = (0..3).map(|axis| face[axis] * normal[axis]).sum();
+    Ok(if dot > 0.0 {
+        Some(true)
+    } else if dot < 0.0 {
+        Some(false)
+    } else {
+        None
+    })
+}
+
+fn triangle_vertices<'a>(mesh: &'a Mesh, triangle: &[u32]) -> io::Result<[&'a Vertex; 3]> {
+    Ok([
+        vertex(&mesh.vertices, triangle[0])?,
+        vertex(&mesh.vertices, triangle[1])?,
+        vertex(&mesh.vertices, triangle[2])?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    /// Two triangles of a square facing up, the second one has reversed winding
+    fn square() -> Mesh {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: [x, y, 0.0],
+            normal: [0.0, 0.0, 1.0],
+            uv: [x, y],
+        };
+        Mesh {
+            name: "square".to_string(),
+            vertices: vec![
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 1.0),
+                vertex(0.0, 1.0),
+            ],
+            indices: vec![0, 1, 2, 0, 3, 2],
+        }
+    }
+
+    fn primitive(mesh: &Mesh) -> io::Result<Primitive> {
+        let mut primitive: Primitive = Faker.fake();
+        primitive.compressed_animation_vertices_len = None;
+        primitive.compressed_animation_vertices_data = None;
+        mesh.write_to_primitive(&mut primitive)?;
+        Ok(primitive)
+    }
+
+    #[test]
+    fn check_square() -> io::Result<()> {
+        let mesh = square();
+        assert_eq!(
+            normal_consistency(&mesh)?,
+            NormalConsistency {
+                agreeing: 1,
+                opposing: 1,
+                degenerate: 0,
+            }
+        );
+        assert_eq!(
+            uv_orientation(&mesh)?,
+            UvOrientation {
+                matching: 1,
+                mirrored: 1,
+                degenerate: 0,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn index_out_of_range() -> io::Result<()> {
+        let mut mesh = square();
+        mesh.indices = vec![0, 1, 4];
+        assert_eq!(
+            normal_consistency(&mesh).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            uv_orientation(&mesh).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        let mut primitive = primitive(&mesh)?;
+        assert!(check_primitive(&primitive).is_err());
+        assert!(fix_winding(&mut primitive).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn fix_primitive_winding() -> io::Result<()> {
+        let mut primitive = primitive(&square())?;
+        assert!(check_primitive(&primitive)?.unwrap().winding_reversed());
+
+        assert_eq!(fix_winding(&mut primitive)?, 1);
+
+        let mesh = Mesh::from_primitive(&primitive)?.unwrap();
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert!(!check_primitive(&primitive)?.unwrap().winding_reversed());
+        assert_eq!(fix_winding(&mut primitive)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn flip_primitive_v() -> io::Result<()> {
+        let mut mesh = square();
+        mesh.indices = vec![0, 1, 2];
+        let mut primitive = primitive(&mesh)?;
+        assert!(check_primitive(&primitive)?.unwrap().v_flipped());
+
+        assert!(flip_v(&mut primitive)?);
+
+        let flipped = Mesh::from_primitive(&primitive)?.unwrap();
+        assert_eq!(flipped.vertices[2].uv, [1.0, 0.0]);
+        assert!(!check_primitive(&primitive)?.unwrap().v_flipped());
+        assert!(!check_primitive(&primitive)?.unwrap().winding_reversed());
+        Ok(())
+    }
+}
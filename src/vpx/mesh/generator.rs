@@ -0,0 +1,207 @@
+//! Choosing the meshes for the items of a table
+//!
+//! A [`MeshGenerator`] turns a game item into meshes with materials. [`MeshGenerators`] asks
+//! the registered generators in order and falls back to [`BuiltinMeshes`] when none of them
+//! handles the item, so a crate can replace the meshes of some items, for example with a more
+//! detailed flipper model, and keep the generated meshes for everything else.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::gameitem::GameItemEnum;
+//! use vpin::vpx::mesh::generator::{MeshGenerator, MeshGenerators};
+//! use vpin::vpx::mesh::glb::LayerMesh;
+//! use vpin::vpx::VPX;
+//!
+//! struct NoFlippers;
+//!
+//! impl MeshGenerator for NoFlippers {
+//!     fn generate(&self, _vpx: &VPX, item: &GameItemEnum) -> io::Result<Option<Vec<LayerMesh>>> {
+//!         match item {
+//!             GameItemEnum::Flipper(_) => Ok(Some(vec![])),
+//!             _ => Ok(None),
+//!         }
+//!     }
+//! }
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let mut generators = MeshGenerators::default();
+//! generators.register(NoFlippers);
+//! let layers = generators.layers(&vpx).unwrap();
+//! ```
+
+use super::glb::{GlbMaterial, Layer, LayerMesh};
+use super::ramp::{ramp, ramp_material};
+use super::wall::visible_wall_meshes;
+use super::Mesh;
+use crate::vpx::expanded::gameitem_type_dir;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+use std::io;
+
+/// Generates the meshes of game items
+pub trait MeshGenerator {
+    /// The meshes for an item, `None` to leave the item to the next generator.
+    ///
+    /// An empty list leaves the item out.
+    fn generate(&self, vpx: &VPX, item: &GameItemEnum) -> io::Result<Option<Vec<LayerMesh>>>;
+}
+
+/// The meshes this library generates: the visible parts of walls and ramps and the meshes of
+/// visible primitives
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuiltinMeshes;
+
+impl MeshGenerator for BuiltinMeshes {
+    fn generate(&self, vpx: &VPX, item: &GameItemEnum) -> io::Result<Option<Vec<LayerMesh>>> {
+        let meshes = match item {
+            GameItemEnum::Wall(w) => visible_wall_meshes(vpx, w),
+            GameItemEnum::Ramp(r) if r.is_visible => vec![LayerMesh {
+                mesh: ramp(vpx, r),
+                material: ramp_material(vpx, r),
+            }],
+            GameItemEnum::Primitive(p) if p.is_visible => Mesh::from_primitive_transformed(p)?
+                .map(|mesh| LayerMesh {
+                    mesh,
+                    material: GlbMaterial::from_table(vpx, &p.material, &p.image),
+                })
+                .into_iter()
+                .collect(),
+            GameItemEnum::Ramp(_) | GameItemEnum::Primitive(_) => vec![],
+            _ => return Ok(None),
+        };
+        Ok(Some(meshes))
+    }
+}
+
+/// The registered generators, asked in the order they were registered before the
+/// [`BuiltinMeshes`]
+#[derive(Default)]
+pub struct MeshGenerators {
+    generators: Vec<Box<dyn MeshGenerator>>,
+}
+
+impl MeshGenerators {
+    pub fn register<G: MeshGenerator + 'static>(&mut self, generator: G) -> &mut Self {
+        self.generators.push(Box::new(generator));
+        self
+    }
+
+    /// The meshes of the first generator that handles the item, empty if none does
+    pub fn generate(&self, vpx: &VPX, item: &GameItemEnum) -> io::Result<Vec<LayerMesh>> {
+        let builtin: &dyn MeshGenerator = &BuiltinMeshes;
+        for generator in self.generators.iter().map(|g| g.as_ref()).chain([builtin]) {
+            if let Some(meshes) = generator.generate(vpx, item)? {
+                return Ok(meshes);
+            }
+        }
+        Ok(vec![])
+    }
+
+    /// The meshes of all items, one layer per item type named like the type dirs of the
+    /// expanded format, eg `walls`. Layers are in the order their first item is in the table,
+    /// types without meshes are left out.
+    pub fn layers(&self, vpx: &VPX) -> io::Result<Vec<Layer>> {
+        let mut layers: Vec<Layer> = Vec::new();
+        for item in &vpx.gameitems {
+            let meshes = self.generate(vpx, item)?;
+            if meshes.is_empty() {
+                continue;
+            }
+            let name = gameitem_type_dir(item);
+            match layers.iter_mut().find(|layer| layer.name == name) {
+                Some(layer) => layer.meshes.extend(meshes),
+                None => layers.push(Layer { name, meshes }),
+            }
+        }
+        Ok(layers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use crate::vpx::gameitem::flipper::Flipper;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::mesh::wall::walls_layer;
+    use pretty_assertions::assert_eq;
+
+    /// Replaces flippers with a single triangle
+    struct TriangleFlippers;
+
+    impl MeshGenerator for TriangleFlippers {
+        fn generate(&self, _vpx: &VPX, item: &GameItemEnum) -> io::Result<Option<Vec<LayerMesh>>> {
+            let GameItemEnum::Flipper(flipper) = item else {
+                return Ok(None);
+            };
+            let mut mesh = Mesh {
+                name: flipper.name.clone(),
+                ..Default::default()
+            };
+            mesh.vertices.resize(3, Default::default());
+            mesh.indices = vec![0, 1, 2];
+            Ok(Some(vec![LayerMesh {
+                mesh,
+                material: GlbMaterial::color("flipper", [1.0, 0.0, 0.0, 1.0]),
+            }]))
+        }
+    }
+
+    /// Leaves out all walls
+    struct NoWalls;
+
+    impl MeshGenerator for NoWalls {
+        fn generate(&self, _vpx: &VPX, item: &GameItemEnum) -> io::Result<Option<Vec<LayerMesh>>> {
+            Ok(matches!(item, GameItemEnum::Wall(_)).then(Vec::new))
+        }
+    }
+
+    fn table() -> VPX {
+        let mut vpx = VPX::default();
+        let drag_point = |x: f32, y: f32| DragPoint {
+            x,
+            y,
+            ..Default::default()
+        };
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "Wall1".to_string(),
+            drag_points: vec![
+                drag_point(0.0, 0.0),
+                drag_point(100.0, 0.0),
+                drag_point(100.0, 100.0),
+            ],
+            ..Default::default()
+        }));
+        let mut flipper = Flipper::default();
+        flipper.name = "LeftFlipper".to_string();
+        vpx.add_game_item(GameItemEnum::Flipper(flipper));
+        vpx
+    }
+
+    #[test]
+    fn builtin_meshes() -> io::Result<()> {
+        let vpx = table();
+        let layers = MeshGenerators::default().layers(&vpx)?;
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, "walls");
+        assert_eq!(layers[0].meshes, walls_layer(&vpx).meshes);
+        Ok(())
+    }
+
+    #[test]
+    fn registered_generators_come_first() -> io::Result<()> {
+        let vpx = table();
+        let mut generators = MeshGenerators::default();
+        generators.register(TriangleFlippers).register(NoWalls);
+        let layers = generators.layers(&vpx)?;
+        let names: Vec<&str> = layers.iter().map(|layer| layer.name.as_str()).collect();
+        assert_eq!(names, vec!["flippers"]);
+        assert_eq!(layers[0].meshes[0].mesh.name, "LeftFlipper");
+        assert_eq!(layers[0].meshes[0].mesh.triangle_count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,676 @@
+//! Minimal binary glTF (GLB) writer for meshes
+//!
+//! Every [`Layer`] becomes a node in the scene with one child node per mesh. Layers can be
+//! toggled in most viewers. Textures are embedded in the binary chunk, or stored once in a
+//! [`TextureCache`] shared by many exports with [`write_glb_with_cache`].
+//!
+//! Vpx coordinates are converted to the glTF convention of y up and meters.
+
+use super::Mesh;
+use crate::vpx::expanded::content_hash;
+use crate::vpx::VPX;
+use image::ImageFormat;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Size of a Visual Pinball Unit in meters
+pub const VPU_IN_METERS: f32 = 0.00053975;
+
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A; // "JSON"
+const CHUNK_BIN: u32 = 0x004E_4942; // "BIN\0"
+
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const FILTER_NEAREST: u32 = 9728;
+const FILTER_LINEAR: u32 = 9729;
+const FILTER_LINEAR_MIPMAP_LINEAR: u32 = 9987;
+const WRAP_CLAMP_TO_EDGE: u32 = 33071;
+const WRAP_MIRRORED_REPEAT: u32 = 33648;
+const WRAP_REPEAT: u32 = 10497;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub meshes: Vec<LayerMesh>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LayerMesh {
+    pub mesh: Mesh,
+    pub material: GlbMaterial,
+}
+
+/// Materials are shared between meshes by name
+#[derive(Debug, PartialEq, Clone)]
+pub struct GlbMaterial {
+    pub name: String,
+    /// Linear RGBA base color, multiplied with the texture
+    pub color: [f32; 4],
+    pub texture: Option<Texture>,
+    /// Blend with the background, set automatically when the color alpha is below 1
+    pub blend: bool,
+}
+
+impl GlbMaterial {
+    pub fn color(name: &str, color: [f32; 4]) -> Self {
+        GlbMaterial {
+            name: name.to_string(),
+            color,
+            texture: None,
+            blend: color[3] < 1.0,
+        }
+    }
+
+    /// A table material by name with an optional table image as texture.
+    ///
+    /// The opacity is only applied when it is active on the material, like Visual Pinball
+    /// does. Images in other formats than png or jpeg are left out since glTF does not support
+    /// them.
+    pub fn from_table(vpx: &VPX, material: &str, image_name: &str) -> Self {
+        let gamedata = &vpx.gamedata;
+        let found = match &gamedata.materials {
+            Some(materials) => materials
+                .iter()
+                .find(|m| m.name.eq_ignore_ascii_case(material))
+                .map(|m| (m.base_color.to_linear(), m.opacity_active, m.opacity)),
+            None => gamedata
+                .materials_old
+                .iter()
+                .find(|m| m.name.eq_ignore_ascii_case(material))
+                .map(|m| {
+                    let active = m.opacity_active_edge_alpha & 1 == 1;
+                    (m.base_color.to_linear(), active, m.opacity)
+                }),
+        };
+        let (rgb, opacity_active, opacity) = found.unwrap_or(([1.0; 3], false, 1.0));
+        let alpha = if opacity_active {
+            opacity.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        // glTF base colors are linear, table colors are sRGB encoded
+        let color = [rgb[0], rgb[1], rgb[2], alpha];
+
+        let image = vpx
+            .images
+            .iter()
+            .find(|image| !image_name.is_empty() && image.name.eq_ignore_ascii_case(image_name));
+        let texture = image
+            .and_then(|image| image.jpeg.as_ref())
+            .and_then(|jpeg| {
+                let mime_type = match image::guess_format(&jpeg.data).ok()? {
+                    ImageFormat::Png => "image/png",
+                    ImageFormat::Jpeg => "image/jpeg",
+                    _ => return None,
+                };
+                Some(Texture {
+                    mime_type: mime_type.to_string(),
+                    data: jpeg.data.clone(),
+                    sampler: None,
+                })
+            });
+        let transparent_image =
+            texture.is_some() && image.is_some_and(|i| i.is_opaque == Some(false));
+        let name = match image_name {
+            "" => material.to_string(),
+            image => format!("{}_{}", material, image),
+        };
+        GlbMaterial {
+            name,
+            color,
+            texture,
+            blend: alpha < 1.0 || transparent_image,
+        }
+    }
+}
+
+/// Texture filtering, the values of Visual Pinball's `SamplerFilter`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SamplerFilter {
+    None,
+    Point,
+    Bilinear,
+    Trilinear,
+    Anisotropic,
+    /// A value without a variant, kept as is
+    Other(u32),
+}
+
+impl From<u32> for SamplerFilter {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => SamplerFilter::None,
+            1 => SamplerFilter::Point,
+            2 => SamplerFilter::Bilinear,
+            3 => SamplerFilter::Trilinear,
+            4 => SamplerFilter::Anisotropic,
+            other => SamplerFilter::Other(other),
+        }
+    }
+}
+
+impl From<SamplerFilter> for u32 {
+    fn from(value: SamplerFilter) -> Self {
+        match value {
+            SamplerFilter::None => 0,
+            SamplerFilter::Point => 1,
+            SamplerFilter::Bilinear => 2,
+            SamplerFilter::Trilinear => 3,
+            SamplerFilter::Anisotropic => 4,
+            SamplerFilter::Other(other) => other,
+        }
+    }
+}
+
+/// What happens outside the 0 to 1 texture coordinate range, the values of Visual Pinball's
+/// `SamplerAddressMode`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SamplerAddressMode {
+    Repeat,
+    Clamp,
+    Mirror,
+    /// A value without a variant, kept as is
+    Other(u32),
+}
+
+impl From<u32> for SamplerAddressMode {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => SamplerAddressMode::Repeat,
+            1 => SamplerAddressMode::Clamp,
+            2 => SamplerAddressMode::Mirror,
+            other => SamplerAddressMode::Other(other),
+        }
+    }
+}
+
+impl From<SamplerAddressMode> for u32 {
+    fn from(value: SamplerAddressMode) -> Self {
+        match value {
+            SamplerAddressMode::Repeat => 0,
+            SamplerAddressMode::Clamp => 1,
+            SamplerAddressMode::Mirror => 2,
+            SamplerAddressMode::Other(other) => other,
+        }
+    }
+}
+
+/// How a texture is sampled, only used for the export as the table does not store this per image
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Sampler {
+    pub filter: SamplerFilter,
+    pub address_u: SamplerAddressMode,
+    pub address_v: SamplerAddressMode,
+}
+
+/// An encoded image, glTF only supports png and jpeg
+#[derive(Debug, PartialEq, Clone)]
+pub struct Texture {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    /// Filtering and addressing for the glTF sampler, the viewer decides when `None`
+    pub sampler: Option<Sampler>,
+}
+
+impl Texture {
+    fn extension(&self) -> &str {
+        match self.mime_type.as_str() {
+            "image/jpeg" => "jpg",
+            _ => "png",
+        }
+    }
+}
+
+/// A directory with textures named after the hash of their data, so textures shared by many
+/// tables are written once
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextureCache {
+    dir: PathBuf,
+    uri_prefix: String,
+}
+
+impl TextureCache {
+    /// The GLB files refer to the textures as `uri_prefix` followed by the file name, viewers
+    /// resolve this relative to the GLB file, eg `../textures/`.
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(dir: P, uri_prefix: S) -> Self {
+        TextureCache {
+            dir: dir.into(),
+            uri_prefix: uri_prefix.into(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes the texture if it is not in the cache yet and returns its uri
+    pub fn store(&self, texture: &Texture) -> io::Result<String> {
+        let file_name = format!("{}.{}", content_hash(&texture.data), texture.extension());
+        let path = self.dir.join(&file_name);
+        if !path.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            // concurrent exports never see a partially written texture
+            let temp_path = self
+                .dir
+                .join(format!(".{}.{}.tmp", file_name, std::process::id()));
+            std::fs::write(&temp_path, &texture.data)?;
+            std::fs::rename(&temp_path, &path)?;
+        }
+        Ok(format!("{}{}", self.uri_prefix, file_name))
+    }
+}
+
+/// Writes the layers as a single GLB file. Meshes without triangles are skipped.
+pub fn write_glb<W: Write>(layers: &[Layer], writer: &mut W) -> io::Result<()> {
+    write_glb_textures(layers, writer, None)
+}
+
+/// Like [`write_glb`] but the textures are stored in the cache instead of in the GLB file
+pub fn write_glb_with_cache<W: Write>(
+    layers: &[Layer],
+    writer: &mut W,
+    cache: &TextureCache,
+) -> io::Result<()> {
+    write_glb_textures(layers, writer, Some(cache))
+}
+
+fn write_glb_textures<W: Write>(
+    layers: &[Layer],
+    writer: &mut W,
+    cache: Option<&TextureCache>,
+) -> io::Result<()> {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut meshes = Vec::new();
+    let mut materials: Vec<Value> = Vec::new();
+    let mut material_names: Vec<&str> = Vec::new();
+    let mut textures = Vec::new();
+    let mut images = Vec::new();
+    let mut samplers: Vec<Value> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut scene_nodes = Vec::new();
+
+    for layer in layers {
+        let mut children = Vec::new();
+        for LayerMesh { mesh, material } in layer
+            .meshes
+            .iter()
+            .filter(|layer_mesh| layer_mesh.mesh.triangle_count() > 0)
+        {
+            let material = match material_names.iter().position(|n| *n == material.name) {
+                Some(index) => index,
+                None => {
+                    let [r, g, b, a] = material.color;
+                    let mut pbr = json!({
+                        "baseColorFactor": [r, g, b, a],
+                        "metallicFactor": 0.0,
+                        "roughnessFactor": 1.0,
+                    });
+                    if let Some(texture) = &material.texture {
+                        match cache {
+                            Some(cache) => {
+                                let uri = cache.store(texture)?;
+                                images.push(json!({"uri": uri, "mimeType": texture.mime_type}));
+                            }
+                            None => {
+                                let view = buffer_views.len();
+                                buffer_views.push(json!({
+                                    "buffer": 0,
+                                    "byteOffset": bin.len(),
+                                    "byteLength": texture.data.len(),
+                                }));
+                                bin.extend_from_slice(&texture.data);
+                                pad(&mut bin, 0);
+                                images.push(
+                                    json!({"bufferView": view, "mimeType": texture.mime_type}),
+                                );
+                            }
+                        }
+                        let mut gltf_texture = json!({"source": images.len() - 1});
+                        if let Some(sampler) = &texture.sampler {
+                            let sampler = gltf_sampler(sampler);
+                            let index = match samplers.iter().position(|s| *s == sampler) {
+                                Some(index) => index,
+                                None => {
+                                    samplers.push(sampler);
+                                    samplers.len() - 1
+                                }
+                            };
+                            gltf_texture["sampler"] = json!(index);
+                        }
+                        textures.push(gltf_texture);
+                        pbr["baseColorTexture"] = json!({"index": textures.len() - 1});
+                    }
+                    materials.push(json!({
+                        "name": material.name,
+                        "pbrMetallicRoughness": pbr,
+                        "alphaMode": if material.blend || a < 1.0 { "BLEND" } else { "OPAQUE" },
+                        "doubleSided": true,
+                    }));
+                    material_names.push(&material.name);
+                    materials.len() - 1
+                }
+            };
+            let mut positions = Vec::with_capacity(mesh.vertices.len() * 12);
+            let mut normals = Vec::with_capacity(mesh.vertices.len() * 12);
+            let mut uvs = Vec::with_capacity(mesh.vertices.len() * 8);
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            for vertex in &mesh.vertices {
+                let [x, y, z] = vertex.position;
+                let position = [x * VPU_IN_METERS, z * VPU_IN_METERS, y * VPU_IN_METERS];
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(position[axis]);
+                    max[axis] = max[axis].max(position[axis]);
+                }
+                let [nx, ny, nz] = vertex.normal;
+                positions.extend(position.iter().flat_map(|v| v.to_le_bytes()));
+                normals.extend([nx, nz, ny].iter().flat_map(|v| v.to_le_bytes()));
+                uvs.extend(vertex.uv.iter().flat_map(|v| v.to_le_bytes()));
+            }
+            // swapping y and z mirrors the mesh, so the winding has to be flipped as well
+            let indices: Vec<u8> = mesh
+                .indices
+                .chunks_exact(3)
+                .flat_map(|t| [t[0], t[2], t[1]])
+                .flat_map(|i| i.to_le_bytes())
+                .collect();
+
+            let count = mesh.vertices.len();
+            let mut add_accessor = |data: &[u8], target: u32, accessor: Value| {
+                let view = buffer_views.len();
+                buffer_views.push(json!({
+                    "buffer": 0,
+                    "byteOffset": bin.len(),
+                    "byteLength": data.len(),
+                    "target": target,
+                }));
+                bin.extend_from_slice(data);
+                let mut accessor = accessor;
+                accessor["bufferView"] = json!(view);
+                accessors.push(accessor);
+                accessors.len() - 1
+            };
+            let position = add_accessor(
+                &positions,
+                TARGET_ARRAY_BUFFER,
+                json!({"componentType": COMPONENT_FLOAT, "count": count, "type": "VEC3", "min": min, "max": max}),
+            );
+            let normal = add_accessor(
+                &normals,
+                TARGET_ARRAY_BUFFER,
+                json!({"componentType": COMPONENT_FLOAT, "count": count, "type": "VEC3"}),
+            );
+            let uv = add_accessor(
+                &uvs,
+                TARGET_ARRAY_BUFFER,
+                json!({"componentType": COMPONENT_FLOAT, "count": count, "type": "VEC2"}),
+            );
+            let index = add_accessor(
+                &indices,
+                TARGET_ELEMENT_ARRAY_BUFFER,
+                json!({"componentType": COMPONENT_UNSIGNED_INT, "count": indices.len() / 4, "type": "SCALAR"}),
+            );
+
+            meshes.push(json!({
+                "name": mesh.name,
+                "primitives": [{
+                    "attributes": {"POSITION": position, "NORMAL": normal, "TEXCOORD_0": uv},
+                    "indices": index,
+                    "material": material,
+                }],
+            }));
+            nodes.push(json!({"name": mesh.name, "mesh": meshes.len() - 1}));
+            children.push(nodes.len() - 1);
+        }
+        nodes.push(json!({"name": layer.name, "children": children}));
+        scene_nodes.push(nodes.len() - 1);
+    }
+
+    let mut gltf = json!({
+        "asset": {"version": "2.0", "generator": "vpin"},
+        "scene": 0,
+        "scenes": [{"nodes": scene_nodes}],
+        "nodes": nodes,
+    });
+    // empty arrays are not allowed by the glTF schema
+    if !meshes.is_empty() {
+        gltf["materials"] = json!(materials);
+        gltf["meshes"] = json!(meshes);
+        gltf["accessors"] = json!(accessors);
+        gltf["bufferViews"] = json!(buffer_views);
+        gltf["buffers"] = json!([{"byteLength": bin.len()}]);
+    }
+    if !images.is_empty() {
+        gltf["images"] = json!(images);
+        gltf["textures"] = json!(textures);
+    }
+    if !samplers.is_empty() {
+        gltf["samplers"] = json!(samplers);
+    }
+
+    let mut json_chunk = serde_json::to_vec(&gltf)?;
+    pad(&mut json_chunk, b' ');
+    pad(&mut bin, 0);
+    let mut length = 12 + 8 + json_chunk.len();
+    if !bin.is_empty() {
+        length += 8 + bin.len();
+    }
+
+    writer.write_all(&GLB_MAGIC.to_le_bytes())?;
+    writer.write_all(&GLB_VERSION.to_le_bytes())?;
+    writer.write_all(&(length as u32).to_le_bytes())?;
+    writer.write_all(&(json_chunk.len() as u32).to_le_bytes())?;
+    writer.write_all(&CHUNK_JSON.to_le_bytes())?;
+    writer.write_all(&json_chunk)?;
+    if !bin.is_empty() {
+        writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+        writer.write_all(&CHUNK_BIN.to_le_bytes())?;
+        writer.write_all(&bin)?;
+    }
+    Ok(())
+}
+
+/// glTF has no anisotropic filtering in its core, that falls back to trilinear. Values
+/// without a glTF counterpart are left out so the viewer decides.
+fn gltf_sampler(sampler: &Sampler) -> Value {
+    let mut gltf_sampler = json!({});
+    let filters = match sampler.filter {
+        SamplerFilter::None | SamplerFilter::Point => Some((FILTER_NEAREST, FILTER_NEAREST)),
+        SamplerFilter::Bilinear => Some((FILTER_LINEAR, FILTER_LINEAR)),
+        SamplerFilter::Trilinear | SamplerFilter::Anisotropic => {
+            Some((FILTER_LINEAR, FILTER_LINEAR_MIPMAP_LINEAR))
+        }
+        SamplerFilter::Other(_) => None,
+    };
+    if let Some((mag_filter, min_filter)) = filters {
+        gltf_sampler["magFilter"] = json!(mag_filter);
+        gltf_sampler["minFilter"] = json!(min_filter);
+    }
+    let wrap = |address_mode: SamplerAddressMode| match address_mode {
+        SamplerAddressMode::Repeat => Some(WRAP_REPEAT),
+        SamplerAddressMode::Clamp => Some(WRAP_CLAMP_TO_EDGE),
+        SamplerAddressMode::Mirror => Some(WRAP_MIRRORED_REPEAT),
+        SamplerAddressMode::Other(_) => None,
+    };
+    if let Some(wrap_s) = wrap(sampler.address_u) {
+        gltf_sampler["wrapS"] = json!(wrap_s);
+    }
+    if let Some(wrap_t) = wrap(sampler.address_v) {
+        gltf_sampler["wrapT"] = json!(wrap_t);
+    }
+    gltf_sampler
+}
+
+/// Chunks have to be aligned to 4 bytes
+fn pad(data: &mut Vec<u8>, with: u8) {
+    while !data.len().is_multiple_of(4) {
+        data.push(with);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::mesh::Vertex;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn table_material_color_is_linear() {
+        let mut vpx = VPX::default();
+        let mut material = crate::vpx::material::Material::default();
+        material.name = "Plastic".to_string();
+        material.base_color = crate::vpx::color::Color::rgb(255, 128, 0);
+        vpx.gamedata.materials = Some(vec![material]);
+
+        let glb_material = GlbMaterial::from_table(&vpx, "plastic", "");
+        let [r, g, b, a] = glb_material.color;
+        assert_eq!((r, b, a), (1.0, 0.0, 1.0));
+        assert!((g - 0.2158605).abs() < 1e-6);
+    }
+
+    #[test]
+    fn write_layers() {
+        let triangle = Mesh {
+            name: "triangle".to_string(),
+            vertices: [[0.0, 0.0, 0.0], [1000.0, 0.0, 0.0], [0.0, 1000.0, 500.0]]
+                .map(|position| Vertex {
+                    position,
+                    ..Default::default()
+                })
+                .to_vec(),
+            indices: vec![0, 1, 2],
+        };
+        let textured = GlbMaterial {
+            texture: Some(Texture {
+                mime_type: "image/png".to_string(),
+                data: vec![1, 2, 3],
+                sampler: Some(Sampler {
+                    filter: SamplerFilter::Point,
+                    address_u: SamplerAddressMode::Clamp,
+                    address_v: SamplerAddressMode::Other(7),
+                }),
+            }),
+            ..GlbMaterial::color("textured", [1.0, 1.0, 1.0, 1.0])
+        };
+        let transparent = GlbMaterial::color("transparent", [1.0, 0.0, 0.0, 0.5]);
+        let layer_mesh = |mesh: &Mesh, material: &GlbMaterial| LayerMesh {
+            mesh: mesh.clone(),
+            material: material.clone(),
+        };
+        let layers = vec![
+            Layer {
+                name: "visuals".to_string(),
+                meshes: vec![
+                    layer_mesh(&triangle, &textured),
+                    layer_mesh(&Mesh::default(), &transparent),
+                ],
+            },
+            Layer {
+                name: "colliders".to_string(),
+                meshes: vec![
+                    layer_mesh(&triangle, &transparent),
+                    layer_mesh(&triangle, &transparent),
+                ],
+            },
+        ];
+        let mut glb = Vec::new();
+        write_glb(&layers, &mut glb).unwrap();
+
+        assert_eq!(u32_at(&glb, 0), GLB_MAGIC);
+        assert_eq!(u32_at(&glb, 8) as usize, glb.len());
+        let json_length = u32_at(&glb, 12) as usize;
+        assert_eq!(u32_at(&glb, 16), CHUNK_JSON);
+        let gltf: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        let bin_length = u32_at(&glb, 20 + json_length) as usize;
+        assert_eq!(u32_at(&glb, 24 + json_length), CHUNK_BIN);
+        assert_eq!(glb.len(), 28 + json_length + bin_length);
+
+        assert_eq!(gltf["scenes"][0]["nodes"], json!([1, 4]));
+        assert_eq!(
+            gltf["nodes"][1],
+            json!({"name": "visuals", "children": [0]})
+        );
+        assert_eq!(gltf["meshes"].as_array().unwrap().len(), 3);
+        assert_eq!(gltf["materials"].as_array().unwrap().len(), 2);
+        assert_eq!(gltf["materials"][0]["alphaMode"], "OPAQUE");
+        assert_eq!(gltf["materials"][1]["alphaMode"], "BLEND");
+        assert_eq!(
+            gltf["materials"][0]["pbrMetallicRoughness"]["baseColorTexture"],
+            json!({"index": 0})
+        );
+        assert_eq!(
+            gltf["images"][0],
+            json!({"bufferView": 0, "mimeType": "image/png"})
+        );
+        assert_eq!(gltf["textures"][0], json!({"source": 0, "sampler": 0}));
+        assert_eq!(
+            gltf["samplers"][0],
+            json!({"magFilter": 9728, "minFilter": 9728, "wrapS": 33071})
+        );
+        assert_eq!(gltf["buffers"][0]["byteLength"], bin_length);
+        assert_eq!(gltf["bufferViews"][1]["byteOffset"], 4);
+        let max = gltf["accessors"][0]["max"].as_array().unwrap();
+        let max: Vec<f64> = max.iter().map(|v| v.as_f64().unwrap()).collect();
+        assert!((max[0] - 0.53975).abs() < 1e-6);
+        assert!((max[1] - 0.269875).abs() < 1e-6);
+        assert!((max[2] - 0.53975).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shared_texture_cache() -> io::Result<()> {
+        let dir = testdir!();
+        let cache = TextureCache::new(dir.join("textures"), "../textures/");
+        let material = GlbMaterial {
+            texture: Some(Texture {
+                mime_type: "image/jpeg".to_string(),
+                data: vec![1, 2, 3],
+                sampler: None,
+            }),
+            ..GlbMaterial::color("textured", [1.0, 1.0, 1.0, 1.0])
+        };
+        let layers = vec![Layer {
+            name: "visuals".to_string(),
+            meshes: vec![LayerMesh {
+                mesh: Mesh {
+                    name: "triangle".to_string(),
+                    vertices: vec![Vertex::default(); 3],
+                    indices: vec![0, 1, 2],
+                },
+                material,
+            }],
+        }];
+        let mut first = Vec::new();
+        write_glb_with_cache(&layers, &mut first, &cache)?;
+        let mut second = Vec::new();
+        write_glb_with_cache(&layers, &mut second, &cache)?;
+        assert_eq!(first, second);
+
+        let files: Vec<_> = std::fs::read_dir(cache.dir())?.collect::<io::Result<_>>()?;
+        assert_eq!(files.len(), 1);
+        let file_name = files[0].file_name().to_string_lossy().to_string();
+        assert!(file_name.ends_with(".jpg"));
+        assert_eq!(std::fs::read(files[0].path())?, vec![1, 2, 3]);
+
+        let json_length = u32_at(&first, 12) as usize;
+        let gltf: Value = serde_json::from_slice(&first[20..20 + json_length])?;
+        assert_eq!(
+            gltf["images"][0],
+            json!({"uri": format!("../textures/{}", file_name), "mimeType": "image/jpeg"})
+        );
+        assert_eq!(gltf["bufferViews"].as_array().unwrap().len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+//! Generating meshes for table elements
+//!
+//! Meshes are in vpx coordinates, x to the right, y towards the player and z up, in
+//! Visual Pinball Units.
+//!
+//! Texture coordinates that depend on the table size, like those of the playfield, wall tops
+//! and world aligned ramps, are derived from the
+//! [`TableDimensions`](crate::vpx::gamedata::TableDimensions) of the table. Code building its
+//! own meshes should use the same methods to line up with these.
+
+use super::expanded::{
+    compress_data, decompress_data, BYTES_PER_VERTEX, MAX_VERTICES_FOR_2_BYTE_INDEX,
+};
+use super::gameitem::dragpoint::DragPoint;
+use super::gameitem::primitive::Primitive;
+use bytes::{Buf, BufMut, BytesMut};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::io;
+
+pub mod collision;
+pub mod external;
+pub mod fix;
+pub use self::fix::{check_primitive, fix_winding, flip_v};
+pub mod generator;
+pub mod glb;
+pub mod playfield;
+pub mod ramp;
+pub mod render;
+pub mod simplify;
+pub use self::simplify::simplify;
+pub mod surface;
+pub mod wall;
+
+/// Number of line segments used for a curve between smooth drag points
+const SPLINE_STEPS: usize = 8;
+/// Number of sides used for rubbers and wires
+pub(crate) const TUBE_SIDES: usize = 8;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+/// A triangle mesh, every 3 indices form a triangle
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Mesh {
+    pub name: String,
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Adds the triangles of another mesh to this one
+    pub fn append(&mut self, other: Mesh) {
+        let offset = self.vertices.len() as u32;
+        self.vertices.extend(other.vertices);
+        self.indices
+            .extend(other.indices.iter().map(|i| i + offset));
+    }
+
+    /// Merges vertices that are equal within the tolerance, see [`weld`].
+    pub fn weld(&self, tolerance: f32) -> Mesh {
+        let attributes: Vec<[f32; 8]> = self
+            .vertices
+            .iter()
+            .map(|v| {
+                let [x, y, z] = v.position;
+                let [nx, ny, nz] = v.normal;
+                let [u, w] = v.uv;
+                [x, y, z, nx, ny, nz, u, w]
+            })
+            .collect();
+        let (indices, kept) = weld(&attributes, &self.indices, tolerance);
+        Mesh {
+            name: self.name.clone(),
+            vertices: kept.iter().map(|&i| self.vertices[i]).collect(),
+            indices,
+        }
+    }
+
+    /// Decodes the mesh stored in a primitive.
+    ///
+    /// Returns `None` for primitives that do not use a 3D mesh. The vertices are not
+    /// transformed, they are relative to the primitive position.
+    pub fn from_primitive(primitive: &Primitive) -> io::Result<Option<Mesh>> {
+        let (Some(vertices_data), Some(indices_data)) = (
+            &primitive.compressed_vertices_data,
+            &primitive.compressed_indices_data,
+        ) else {
+            return Ok(None);
+        };
+        if !primitive.use_3d_mesh {
+            return Ok(None);
+        }
+        let raw_vertices = decompress_data(vertices_data)?;
+        let raw_indices = decompress_data(indices_data)?;
+        let num_vertices = raw_vertices.len() / BYTES_PER_VERTEX;
+
+        let mut buff = BytesMut::from(raw_vertices.as_slice());
+        let vertices = (0..num_vertices)
+            .map(|_| Vertex {
+                position: [buff.get_f32_le(), buff.get_f32_le(), buff.get_f32_le()],
+                normal: [buff.get_f32_le(), buff.get_f32_le(), buff.get_f32_le()],
+                uv: [buff.get_f32_le(), buff.get_f32_le()],
+            })
+            .collect();
+
+        let mut buff = BytesMut::from(raw_indices.as_slice());
+        let indices = if num_vertices > MAX_VERTICES_FOR_2_BYTE_INDEX {
+            (0..raw_indices.len() / 4)
+                .map(|_| buff.get_u32_le())
+                .collect()
+        } else {
+            (0..raw_indices.len() / 2)
+                .map(|_| buff.get_u16_le() as u32)
+                .collect()
+        };
+        Ok(Some(Mesh {
+            name: primitive.name.clone(),
+            vertices,
+            indices,
+        }))
+    }
+
+    /// Stores the mesh in a primitive, replacing its 3D mesh.
+    ///
+    /// The vertices are stored as they are, so they should be relative to the primitive position.
+    pub fn write_to_primitive(&self, primitive: &mut Primitive) -> io::Result<()> {
+        let mut vertices = BytesMut::with_capacity(self.vertices.len() * BYTES_PER_VERTEX);
+        for vertex in &self.vertices {
+            for value in vertex
+                .position
+                .iter()
+                .chain(&vertex.normal)
+                .chain(&vertex.uv)
+            {
+                vertices.put_f32_le(*value);
+            }
+        }
+        let mut indices = BytesMut::new();
+        for index in &self.indices {
+            if self.vertices.len() > MAX_VERTICES_FOR_2_BYTE_INDEX {
+                indices.put_u32_le(*index);
+            } else {
+                indices.put_u16_le(*index as u16);
+            }
+        }
+        let compressed_vertices = compress_data(&vertices)?;
+        let compressed_indices = compress_data(&indices)?;
+        primitive.use_3d_mesh = true;
+        primitive.num_vertices = Some(self.vertices.len() as u32);
+        primitive.compressed_vertices_len = Some(compressed_vertices.len() as u32);
+        primitive.compressed_vertices_data = Some(compressed_vertices);
+        primitive.num_indices = Some(self.indices.len() as u32);
+        primitive.compressed_indices_len = Some(compressed_indices.len() as u32);
+        primitive.compressed_indices_data = Some(compressed_indices);
+        Ok(())
+    }
+
+    /// Decodes the mesh stored in a primitive and moves it to its place on the table.
+    ///
+    /// Applies size, rotation, translation and object rotation the same way Visual Pinball does.
+    pub fn from_primitive_transformed(primitive: &Primitive) -> io::Result<Option<Mesh>> {
+        let Some(mut mesh) = Mesh::from_primitive(primitive)? else {
+            return Ok(None);
+        };
+        let size = [primitive.size.x, primitive.size.y, primitive.size.z];
+        for vertex in &mut mesh.vertices {
+            vertex.position = place_primitive_vertex(primitive, vertex.position);
+
+            let mut n = vertex.normal;
+            for axis in 0..3 {
+                if size[axis] != 0.0 {
+                    n[axis] /= size[axis];
+                }
+            }
+            vertex.normal = normalize(rotate_primitive(primitive, n));
+        }
+        Ok(Some(mesh))
+    }
+}
+
+/// Moves a vertex of a primitive mesh to its place on the table, see
+/// [`Mesh::from_primitive_transformed`]
+pub(crate) fn place_primitive_vertex(primitive: &Primitive, vertex: [f32; 3]) -> [f32; 3] {
+    let size = [primitive.size.x, primitive.size.y, primitive.size.z];
+    let rt = primitive.rot_and_tra;
+    let mut p = vertex;
+    for axis in 0..3 {
+        p[axis] = p[axis] * size[axis] + rt[3 + axis];
+    }
+    let [x, y, z] = rotate_primitive(primitive, p);
+    let position = &primitive.position;
+    [x + position.x, y + position.y, z + position.z]
+}
+
+fn rotate_primitive(primitive: &Primitive, mut v: [f32; 3]) -> [f32; 3] {
+    let rt = primitive.rot_and_tra;
+    let rotations = [
+        (Axis::Z, rt[2]),
+        (Axis::Y, rt[1]),
+        (Axis::X, rt[0]),
+        (Axis::Z, rt[8]),
+        (Axis::Y, rt[7]),
+        (Axis::X, rt[6]),
+    ];
+    for (axis, degrees) in rotations {
+        v = rotate(v, axis, degrees.to_radians());
+    }
+    v
+}
+
+/// Merges vertices with equal position, normal and texture coordinates and re-indexes them.
+///
+/// Every vertex is given as `[x, y, z, nx, ny, nz, u, v]`. Values are compared by rounding
+/// them to a multiple of the tolerance, a tolerance of 0 only merges exact duplicates.
+/// Vertices are renumbered in the order they are first used, unused vertices are dropped and
+/// so are triangles that collapse to a line or point.
+///
+/// Returns the new indices and for every new vertex the index of the original vertex.
+pub(crate) fn weld(
+    attributes: &[[f32; 8]],
+    indices: &[u32],
+    tolerance: f32,
+) -> (Vec<u32>, Vec<usize>) {
+    let key = |values: &[f32; 8]| {
+        values.map(|value| {
+            let rounded = if tolerance > 0.0 {
+                (value / tolerance).round()
+            } else {
+                value
+            };
+            // adding 0 turns -0.0 into 0.0
+            (rounded + 0.0).to_bits()
+        })
+    };
+    let mut new_index: HashMap<[u32; 8], u32> = HashMap::new();
+    let mut kept = Vec::new();
+    let mut welded = Vec::with_capacity(indices.len());
+    let mut map = |i: u32| {
+        let i = i as usize;
+        *new_index.entry(key(&attributes[i])).or_insert_with(|| {
+            kept.push(i);
+            kept.len() as u32 - 1
+        })
+    };
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [map(triangle[0]), map(triangle[1]), map(triangle[2])];
+        if a != b && b != c && a != c {
+            welded.extend([a, b, c]);
+        }
+    }
+    (welded, kept)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+fn rotate(v: [f32; 3], axis: Axis, radians: f32) -> [f32; 3] {
+    let (sin, cos) = radians.sin_cos();
+    let [x, y, z] = v;
+    match axis {
+        Axis::X => [x, y * cos - z * sin, y * sin + z * cos],
+        Axis::Y => [x * cos + z * sin, y, -x * sin + z * cos],
+        Axis::Z => [x * cos - y * sin, x * sin + y * cos, z],
+    }
+}
+
+pub(crate) fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if length == 0.0 {
+        v
+    } else {
+        [v[0] / length, v[1] / length, v[2] / length]
+    }
+}
+
+/// Interpolates the curve through a list of drag points.
+///
+/// Segments next to a smooth point are Catmull-Rom curves, others are straight lines.
+/// For a closed curve the last point connects back to the first one and is not repeated.
+pub(crate) fn spline(points: &[DragPoint], closed: bool) -> Vec<[f32; 3]> {
+    spline_with_controls(points, closed).0
+}
+
+/// Like [`spline`], also returns the index in the curve of every drag point
+pub(crate) fn spline_with_controls(
+    points: &[DragPoint],
+    closed: bool,
+) -> (Vec<[f32; 3]>, Vec<usize>) {
+    let count = points.len();
+    if count < 2 {
+        let curve = points.iter().map(|p| [p.x, p.y, p.z]).collect();
+        return (curve, (0..count).collect());
+    }
+    let at = |index: usize| {
+        let point = &points[index % count];
+        [point.x, point.y, point.z]
+    };
+    let segments = if closed { count } else { count - 1 };
+    let mut result = Vec::new();
+    let mut controls = Vec::with_capacity(count);
+    for i in 0..segments {
+        controls.push(result.len());
+        let p1 = at(i);
+        let p2 = at(i + 1);
+        let p0 = match (points[i].smooth, closed, i) {
+            (false, _, _) | (true, false, 0) => p1,
+            _ => at(i + count - 1),
+        };
+        let p3 = match (points[(i + 1) % count].smooth, closed) {
+            (true, true) => at(i + 2),
+            (true, false) if i + 2 < count => at(i + 2),
+            _ => p2,
+        };
+        let steps = if points[i].smooth || points[(i + 1) % count].smooth {
+            SPLINE_STEPS
+        } else {
+            1
+        };
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            result.push(catmull_rom(p0, p1, p2, p3, t));
+        }
+    }
+    if !closed {
+        controls.push(result.len());
+        result.push(at(count - 1));
+    }
+    (result, controls)
+}
+
+/// A tube with a circular cross section around a path.
+///
+/// Open tubes get flat caps at both ends.
+pub(crate) fn tube(name: &str, path: &[[f32; 3]], radius: f32, sides: usize, closed: bool) -> Mesh {
+    let mut mesh = Mesh {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    let count = path.len();
+    if count < 2 {
+        return mesh;
+    }
+    let tangent = |i: usize| {
+        let (prev, next) = match (closed, i) {
+            (true, _) => ((i + count - 1) % count, (i + 1) % count),
+            (false, 0) => (0, 1),
+            (false, i) if i == count - 1 => (i - 1, i),
+            (false, i) => (i - 1, i + 1),
+        };
+        normalize(sub(path[next], path[prev]))
+    };
+    for (i, center) in path.iter().enumerate() {
+        let t = tangent(i);
+        // for a vertical tangent any horizontal side vector will do
+        let side = match normalize(cross(t, [0.0, 0.0, 1.0])) {
+            [0.0, 0.0, 0.0] => [1.0, 0.0, 0.0],
+            side => side,
+        };
+        let up = cross(side, t);
+        for k in 0..sides {
+            let angle = 2.0 * PI * k as f32 / sides as f32;
+            let (sin, cos) = angle.sin_cos();
+            let normal = [
+                side[0] * cos + up[0] * sin,
+                side[1] * cos + up[1] * sin,
+                side[2] * cos + up[2] * sin,
+            ];
+            mesh.vertices.push(Vertex {
+                position: [
+                    center[0] + normal[0] * radius,
+                    center[1] + normal[1] * radius,
+                    center[2] + normal[2] * radius,
+                ],
+                normal,
+                uv: [i as f32 / count as f32, k as f32 / sides as f32],
+            });
+        }
+    }
+    let sides = sides as u32;
+    let rings = if closed { count } else { count - 1 } as u32;
+    for i in 0..rings {
+        let ring = i * sides;
+        let next_ring = ((i + 1) % count as u32) * sides;
+        for k in 0..sides {
+            let next_k = (k + 1) % sides;
+            mesh.indices.extend([
+                ring + k,
+                next_ring + k,
+                next_ring + next_k,
+                ring + k,
+                next_ring + next_k,
+                ring + next_k,
+            ]);
+        }
+    }
+    if !closed {
+        for (ring, direction) in [(0, -1.0), (count - 1, 1.0)] {
+            let t = tangent(ring);
+            let normal = [t[0] * direction, t[1] * direction, t[2] * direction];
+            let center = mesh.vertices.len() as u32;
+            mesh.vertices.push(Vertex {
+                position: path[ring],
+                normal,
+                uv: [ring as f32 / count as f32, 0.5],
+            });
+            let ring = ring as u32 * sides;
+            for k in 0..sides {
+                let next_k = (k + 1) % sides;
+                if direction < 0.0 {
+                    mesh.indices.extend([center, ring + next_k, ring + k]);
+                } else {
+                    mesh.indices.extend([center, ring + k, ring + next_k]);
+                }
+            }
+        }
+    }
+    mesh
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn catmull_rom(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3], p3: [f32; 3], t: f32) -> [f32; 3] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let mut result = [0.0; 3];
+    for axis in 0..3 {
+        let (a, b, c, d) = (p0[axis], p1[axis], p2[axis], p3[axis]);
+        result[axis] = 0.5
+            * (2.0 * b
+                + (c - a) * t
+                + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+                + (3.0 * b - a - 3.0 * c + d) * t3);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn point(x: f32, y: f32, smooth: bool) -> DragPoint {
+        DragPoint {
+            x,
+            y,
+            smooth,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spline_straight_and_smooth() {
+        let square = [
+            point(0.0, 0.0, false),
+            point(10.0, 0.0, false),
+            point(10.0, 10.0, false),
+            point(0.0, 10.0, false),
+        ];
+        assert_eq!(spline(&square, true).len(), 4);
+        assert_eq!(spline(&square, false).len(), 4);
+
+        let curve = [
+            point(0.0, 0.0, false),
+            point(10.0, 10.0, true),
+            point(20.0, 0.0, false),
+        ];
+        let points = spline(&curve, false);
+        assert_eq!(points.len(), 2 * SPLINE_STEPS + 1);
+        assert_eq!(points[SPLINE_STEPS], [10.0, 10.0, 0.0]);
+        assert_eq!(points.last(), Some(&[20.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn tube_caps() {
+        let path = [[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [20.0, 0.0, 5.0]];
+        let open = tube("wire", &path, 1.0, 4, false);
+        assert_eq!(open.vertices.len(), 3 * 4 + 2);
+        assert_eq!(open.triangle_count(), 2 * 4 * 2 + 2 * 4);
+        assert_eq!(open.vertices[12].normal, [-1.0, 0.0, 0.0]);
+        // the first ring is upright around the start of the path
+        assert_eq!(open.vertices[0].position, [0.0, -1.0, 0.0]);
+
+        let closed = tube("ring", &path, 1.0, 4, true);
+        assert_eq!(closed.vertices.len(), 3 * 4);
+        assert_eq!(closed.triangle_count(), 3 * 4 * 2);
+    }
+
+    #[test]
+    fn weld_duplicates() {
+        let vertex = |x: f32, y: f32| Vertex {
+            position: [x, y, 0.0],
+            normal: [0.0, 0.0, 1.0],
+            uv: [x, y],
+        };
+        // two triangles sharing an edge with duplicated vertices and an unused vertex
+        let mesh = Mesh {
+            name: "quad".to_string(),
+            vertices: vec![
+                vertex(5.0, 5.0),
+                vertex(0.0, 0.0),
+                vertex(1.0, 0.0),
+                vertex(1.0, 1.0),
+                vertex(0.0, 0.0001),
+                vertex(1.0, 1.0),
+                vertex(0.0, 1.0),
+            ],
+            indices: vec![1, 2, 3, 4, 5, 6, 1, 4, 3],
+        };
+
+        let exact = mesh.weld(0.0);
+        assert_eq!(exact.vertices.len(), 5);
+        assert_eq!(exact.indices, vec![0, 1, 2, 3, 2, 4, 0, 3, 2]);
+
+        let welded = mesh.weld(0.001);
+        assert_eq!(welded.vertices.len(), 4);
+        assert_eq!(welded.vertices[0], vertex(0.0, 0.0));
+        // the last triangle collapsed
+        assert_eq!(welded.indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn rotate_axes() {
+        let [x, y, z] = rotate([1.0, 0.0, 0.0], Axis::Z, 90f32.to_radians());
+        assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6 && z == 0.0);
+        let [x, y, z] = rotate([0.0, 1.0, 0.0], Axis::X, 90f32.to_radians());
+        assert!(x == 0.0 && y.abs() < 1e-6 && (z - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,263 @@
+//! The playfield mesh
+//!
+//! Since 10.8 a table can replace the generated playfield with a primitive named
+//! `playfield_mesh`. Otherwise the playfield is a flat surface covering the table
+//! dimensions, optionally with holes for the kickers.
+
+use super::{Mesh, Vertex};
+use crate::vpx::gamedata::TableDimensions;
+use crate::vpx::gameitem::kicker::KickerType;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+use std::io;
+
+/// The name Visual Pinball uses to find the primitive that replaces the playfield
+pub const PLAYFIELD_PRIMITIVE_NAME: &str = "playfield_mesh";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlayfieldOptions {
+    /// Leave out the area under kickers that are on the playfield and not invisible
+    pub kicker_holes: bool,
+    /// Precision of the hole outlines in VPU, smaller values give more triangles
+    pub hole_precision: f32,
+    /// Use the `playfield_mesh` primitive if the table has one
+    pub use_playfield_primitive: bool,
+}
+
+impl Default for PlayfieldOptions {
+    fn default() -> Self {
+        PlayfieldOptions {
+            kicker_holes: false,
+            hole_precision: 5.0,
+            use_playfield_primitive: true,
+        }
+    }
+}
+
+/// Builds the playfield mesh for a table.
+///
+/// For a `playfield_mesh` primitive the position and size are applied, rotations are not.
+/// Holes are made by leaving out strips of the playfield, so their outlines follow a grid
+/// with the size of [`PlayfieldOptions::hole_precision`].
+pub fn playfield(vpx: &VPX, options: &PlayfieldOptions) -> io::Result<Mesh> {
+    if options.use_playfield_primitive {
+        let primitive = vpx.gameitems.iter().find_map(|item| match item {
+            GameItemEnum::Primitive(primitive)
+                if primitive
+                    .name
+                    .eq_ignore_ascii_case(PLAYFIELD_PRIMITIVE_NAME) =>
+            {
+                Some(primitive)
+            }
+            _ => None,
+        });
+        if let Some(primitive) = primitive {
+            if let Some(mut mesh) = Mesh::from_primitive(primitive)? {
+                let position = [
+                    primitive.position.x,
+                    primitive.position.y,
+                    primitive.position.z,
+                ];
+                let size = [primitive.size.x, primitive.size.y, primitive.size.z];
+                for vertex in &mut mesh.vertices {
+                    for axis in 0..3 {
+                        vertex.position[axis] = vertex.position[axis] * size[axis] + position[axis];
+                    }
+                }
+                return Ok(mesh);
+            }
+        }
+    }
+
+    let gamedata = &vpx.gamedata;
+    let holes: Vec<(f32, f32, f32)> = if options.kicker_holes {
+        vpx.gameitems
+            .iter()
+            .filter_map(|item| match item {
+                GameItemEnum::Kicker(kicker)
+                    if kicker.kicker_type != KickerType::Invisible && kicker.surface.is_empty() =>
+                {
+                    Some((kicker.center.x, kicker.center.y, kicker.radius))
+                }
+                _ => None,
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+    Ok(flat_playfield(
+        &gamedata.dimensions(),
+        &holes,
+        options.hole_precision.max(0.1),
+    ))
+}
+
+fn flat_playfield(dimensions: &TableDimensions, holes: &[(f32, f32, f32)], precision: f32) -> Mesh {
+    let mut mesh = Mesh {
+        name: "playfield".to_string(),
+        ..Default::default()
+    };
+    let TableDimensions {
+        left,
+        top,
+        right,
+        bottom,
+    } = *dimensions;
+    let width = dimensions.width();
+    let height = dimensions.height();
+    let mut add_quad = |x0: f32, y0: f32, x1: f32, y1: f32| {
+        let start = mesh.vertices.len() as u32;
+        for (x, y) in [(x0, y0), (x1, y0), (x1, y1), (x0, y1)] {
+            mesh.vertices.push(Vertex {
+                position: [x, y, 0.0],
+                normal: [0.0, 0.0, 1.0],
+                uv: dimensions.uv(x, y),
+            });
+        }
+        mesh.indices
+            .extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    };
+
+    if holes.is_empty() {
+        add_quad(left, top, right, bottom);
+        return mesh;
+    }
+
+    // split the playfield in rows, every row is split in quads around the holes
+    let rows = (height / precision).ceil().max(1.0) as usize;
+    let columns = (width / precision).ceil().max(1.0) as usize;
+    let cell_width = width / columns as f32;
+    let cell_height = height / rows as f32;
+    let in_hole = |x: f32, y: f32| {
+        holes
+            .iter()
+            .any(|(hx, hy, radius)| (x - hx).powi(2) + (y - hy).powi(2) < radius.powi(2))
+    };
+    for row in 0..rows {
+        let y0 = top + row as f32 * cell_height;
+        let y1 = if row + 1 == rows {
+            bottom
+        } else {
+            y0 + cell_height
+        };
+        let center_y = (y0 + y1) / 2.0;
+        let mut run_start: Option<usize> = None;
+        for column in 0..=columns {
+            let solid =
+                column < columns && !in_hole(left + (column as f32 + 0.5) * cell_width, center_y);
+            match (solid, run_start) {
+                (true, None) => run_start = Some(column),
+                (false, Some(start)) => {
+                    let x0 = left + start as f32 * cell_width;
+                    let x1 = if column == columns {
+                        right
+                    } else {
+                        left + column as f32 * cell_width
+                    };
+                    add_quad(x0, y0, x1, y1);
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::kicker::Kicker;
+    use crate::vpx::gameitem::primitive::Primitive;
+    use crate::vpx::gameitem::vertex2d::Vertex2D;
+    use crate::vpx::gameitem::vertex3d::Vertex3D;
+    use fake::{Fake, Faker};
+    use flate2::write::ZlibEncoder;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    fn table() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.gamedata.left = 0.0;
+        vpx.gamedata.top = 0.0;
+        vpx.gamedata.right = 100.0;
+        vpx.gamedata.bottom = 200.0;
+        vpx
+    }
+
+    #[test]
+    fn flat() {
+        let mesh = playfield(&table(), &PlayfieldOptions::default()).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.vertices[2].position, [100.0, 200.0, 0.0]);
+        assert_eq!(mesh.vertices[2].uv, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn kicker_holes() {
+        let mut vpx = table();
+        let mut kicker = Kicker::default();
+        kicker.center = Vertex2D { x: 50.0, y: 100.0 };
+        kicker.radius = 20.0;
+        kicker.kicker_type = KickerType::Hole;
+        vpx.add_game_item(GameItemEnum::Kicker(kicker));
+        let options = PlayfieldOptions {
+            kicker_holes: true,
+            ..Default::default()
+        };
+
+        let mesh = playfield(&vpx, &options).unwrap();
+
+        // rows crossing the hole have 2 quads, the other ones 1
+        let rows_with_hole = 8;
+        assert_eq!(mesh.triangle_count(), (40 + rows_with_hole) * 2);
+        let area: f32 = mesh
+            .vertices
+            .chunks(4)
+            .map(|quad| {
+                (quad[1].position[0] - quad[0].position[0])
+                    * (quad[2].position[1] - quad[1].position[1])
+            })
+            .sum();
+        let hole_area = 100.0 * 200.0 - area;
+        assert!((hole_area - std::f32::consts::PI * 400.0).abs() < 200.0);
+    }
+
+    #[test]
+    fn playfield_primitive() {
+        let mut vpx = table();
+        let mut vertices = Vec::new();
+        for (x, y) in [(0.0f32, 0.0f32), (1.0, 0.0), (0.0, 1.0)] {
+            for value in [x, y, 0.0, 0.0, 0.0, 1.0, x, y] {
+                vertices.extend(value.to_le_bytes());
+            }
+        }
+        let indices: Vec<u8> = [0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()).collect();
+        let compress = |data: &[u8]| {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let mut primitive: Primitive = Faker.fake();
+        primitive.name = "Playfield_Mesh".to_string();
+        primitive.use_3d_mesh = true;
+        primitive.position = Vertex3D::new(10.0, 20.0, 0.0);
+        primitive.size = Vertex3D::new(100.0, 200.0, 1.0);
+        primitive.compressed_vertices_data = Some(compress(&vertices));
+        primitive.compressed_indices_data = Some(compress(&indices));
+        vpx.add_game_item(GameItemEnum::Primitive(primitive));
+
+        let mesh = playfield(&vpx, &PlayfieldOptions::default()).unwrap();
+
+        assert_eq!(mesh.name, "Playfield_Mesh");
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.vertices[1].position, [110.0, 20.0, 0.0]);
+        assert_eq!(mesh.vertices[2].position, [10.0, 220.0, 0.0]);
+
+        let options = PlayfieldOptions {
+            use_playfield_primitive: false,
+            ..Default::default()
+        };
+        assert_eq!(playfield(&vpx, &options).unwrap().name, "playfield");
+    }
+}
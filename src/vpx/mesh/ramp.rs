@@ -0,0 +1,272 @@
+//! Ramp meshes
+//!
+//! Flat ramps get a floor and the visible part of the side walls, wire ramps (habitrails)
+//! get a tube per wire with caps at both ends.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::mesh::glb::write_glb;
+//! use vpin::vpx::mesh::ramp::ramps_layer;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let mut glb = Vec::new();
+//! write_glb(&[ramps_layer(&vpx)], &mut glb).unwrap();
+//! ```
+
+use super::glb::{GlbMaterial, Layer, LayerMesh};
+use super::{normalize, spline, tube, Mesh, Vertex, TUBE_SIDES};
+use crate::vpx::gameitem::ramp::{Ramp, RampType};
+use crate::vpx::gameitem::ramp_image_alignment::RampImageAlignment;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+
+/// One point on the center line of a ramp
+pub(crate) struct Section {
+    pub(crate) center: [f32; 3],
+    /// Horizontal unit vector pointing to the right when going up the ramp
+    pub(crate) right: [f32; 3],
+    pub(crate) width: f32,
+    /// Relative distance from the start of the ramp, 0 to 1
+    progress: f32,
+}
+
+/// Builds the visual mesh for a ramp
+pub fn ramp(vpx: &VPX, ramp: &Ramp) -> Mesh {
+    let sections = sections(ramp);
+    let mut mesh = Mesh {
+        name: ramp.name.clone(),
+        ..Default::default()
+    };
+    if sections.len() < 2 {
+        return mesh;
+    }
+    match ramp.ramp_type {
+        RampType::Flat => {
+            let dimensions = vpx.gamedata.dimensions();
+            let uv = |position: [f32; 3], across: f32, progress: f32| match ramp.image_alignment {
+                RampImageAlignment::World => dimensions.world_uv(position[0], position[1]),
+                _ => [across, progress],
+            };
+            let edge = |section: &Section, side: f32, height: f32| {
+                let offset = side * section.width / 2.0;
+                [
+                    section.center[0] + section.right[0] * offset,
+                    section.center[1] + section.right[1] * offset,
+                    section.center[2] + height,
+                ]
+            };
+            let mut strip = |side_a: f32, height_a: f32, side_b: f32, height_b: f32| {
+                let start = mesh.vertices.len() as u32;
+                for section in &sections {
+                    for (side, height) in [(side_a, height_a), (side_b, height_b)] {
+                        let position = edge(section, side, height);
+                        let normal = if height_a == height_b {
+                            [0.0, 0.0, 1.0]
+                        } else {
+                            section.right.map(|v| v * side)
+                        };
+                        mesh.vertices.push(Vertex {
+                            position,
+                            normal,
+                            uv: uv(position, (side + 1.0) / 2.0, section.progress),
+                        });
+                    }
+                }
+                for i in 0..sections.len() as u32 - 1 {
+                    let a = start + i * 2;
+                    mesh.indices.extend([a, a + 2, a + 3, a, a + 3, a + 1]);
+                }
+            };
+            strip(-1.0, 0.0, 1.0, 0.0);
+            if ramp.left_wall_height_visible > 0.0 {
+                strip(-1.0, ramp.left_wall_height_visible, -1.0, 0.0);
+            }
+            if ramp.right_wall_height_visible > 0.0 {
+                strip(1.0, 0.0, 1.0, ramp.right_wall_height_visible);
+            }
+        }
+        _ => {
+            let radius = ramp.wire_diameter / 2.0;
+            for (offset, height) in wires(ramp) {
+                let path: Vec<[f32; 3]> = sections
+                    .iter()
+                    .map(|section| {
+                        [
+                            section.center[0] + section.right[0] * offset,
+                            section.center[1] + section.right[1] * offset,
+                            section.center[2] + height + radius,
+                        ]
+                    })
+                    .collect();
+                mesh.append(tube(&ramp.name, &path, radius, TUBE_SIDES, false));
+            }
+        }
+    }
+    mesh
+}
+
+/// Sideways offset and height of every wire of a wire ramp
+fn wires(ramp: &Ramp) -> Vec<(f32, f32)> {
+    let x = ramp.wire_distance_x / 2.0;
+    let y = ramp.wire_distance_y;
+    match ramp.ramp_type {
+        RampType::Flat => vec![],
+        RampType::OneWire => vec![(0.0, 0.0)],
+        RampType::TwoWire => vec![(-x, 0.0), (x, 0.0)],
+        RampType::ThreeWireLeft => vec![(-x, 0.0), (x, 0.0), (-x, y)],
+        RampType::ThreeWireRight => vec![(-x, 0.0), (x, 0.0), (x, y)],
+        RampType::FourWire => vec![(-x, 0.0), (x, 0.0), (-x, y), (x, y)],
+    }
+}
+
+/// Interpolates width and height along the center line by the covered distance
+pub(crate) fn sections(ramp: &Ramp) -> Vec<Section> {
+    let mut points = spline(&ramp.drag_points, false);
+    points.dedup();
+    let mut distances = vec![0.0f32];
+    for pair in points.windows(2) {
+        let length = ((pair[1][0] - pair[0][0]).powi(2) + (pair[1][1] - pair[0][1]).powi(2)).sqrt();
+        distances.push(distances.last().unwrap() + length);
+    }
+    let total = distances.last().copied().unwrap_or(0.0);
+    let count = points.len();
+    (0..count)
+        .map(|i| {
+            let progress = if total > 0.0 {
+                distances[i] / total
+            } else {
+                0.0
+            };
+            let previous = points[i.saturating_sub(1)];
+            let next = points[(i + 1).min(count - 1)];
+            let [tx, ty, _] = normalize([next[0] - previous[0], next[1] - previous[1], 0.0]);
+            let point = points[i];
+            Section {
+                center: [
+                    point[0],
+                    point[1],
+                    point[2]
+                        + ramp.height_bottom
+                        + progress * (ramp.height_top - ramp.height_bottom),
+                ],
+                right: [-ty, tx, 0.0],
+                width: ramp.width_bottom + progress * (ramp.width_top - ramp.width_bottom),
+                progress,
+            }
+        })
+        .collect()
+}
+
+/// The material of a ramp with the opacity of the table material and the ramp image, see
+/// [`GlbMaterial::from_table`]
+pub fn ramp_material(vpx: &VPX, ramp: &Ramp) -> GlbMaterial {
+    GlbMaterial::from_table(vpx, &ramp.material, &ramp.image)
+}
+
+/// All visible ramps with their materials in a layer named `ramps`
+pub fn ramps_layer(vpx: &VPX) -> Layer {
+    let meshes = vpx
+        .gameitems
+        .iter()
+        .filter_map(|item| match item {
+            GameItemEnum::Ramp(r) if r.is_visible => Some(LayerMesh {
+                mesh: ramp(vpx, r),
+                material: ramp_material(vpx, r),
+            }),
+            _ => None,
+        })
+        .collect();
+    Layer {
+        name: "ramps".to_string(),
+        meshes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use crate::vpx::material::Material;
+    use pretty_assertions::assert_eq;
+
+    fn straight_ramp(ramp_type: RampType) -> Ramp {
+        let mut ramp = Ramp {
+            name: "Ramp".to_string(),
+            ramp_type,
+            height_bottom: 0.0,
+            height_top: 100.0,
+            width_bottom: 50.0,
+            width_top: 30.0,
+            left_wall_height_visible: 20.0,
+            right_wall_height_visible: 0.0,
+            wire_diameter: 6.0,
+            wire_distance_x: 38.0,
+            wire_distance_y: 88.0,
+            image_alignment: RampImageAlignment::Wrap,
+            material: "Plastic".to_string(),
+            ..Default::default()
+        };
+        ramp.drag_points = [(100.0, 500.0), (100.0, 400.0), (100.0, 300.0)]
+            .iter()
+            .map(|&(x, y)| DragPoint {
+                x,
+                y,
+                ..Default::default()
+            })
+            .collect();
+        ramp
+    }
+
+    #[test]
+    fn flat_ramp() {
+        let mesh = ramp(&VPX::default(), &straight_ramp(RampType::Flat));
+        // floor and left wall, 2 segments each
+        assert_eq!(mesh.triangle_count(), 8);
+        // going up the screen the right side is at a larger x
+        assert_eq!(mesh.vertices[0].position, [75.0, 500.0, 0.0]);
+        assert_eq!(mesh.vertices[1].position, [125.0, 500.0, 0.0]);
+        assert_eq!(mesh.vertices[5].position, [115.0, 300.0, 100.0]);
+        assert_eq!(mesh.vertices[5].uv, [1.0, 1.0]);
+        assert_eq!(mesh.vertices[6].position, [75.0, 500.0, 20.0]);
+    }
+
+    #[test]
+    fn wire_ramp_with_caps() {
+        let mesh = ramp(&VPX::default(), &straight_ramp(RampType::FourWire));
+        let rings = 3 * TUBE_SIDES;
+        assert_eq!(mesh.vertices.len(), 4 * (rings + 2));
+        assert_eq!(
+            mesh.triangle_count(),
+            4 * (2 * TUBE_SIDES * 2 + 2 * TUBE_SIDES)
+        );
+        // cap center of the first upper wire
+        assert_eq!(
+            mesh.vertices[2 * (rings + 2) + rings].position,
+            [81.0, 500.0, 91.0]
+        );
+    }
+
+    #[test]
+    fn transparent_material() {
+        let mut vpx = VPX::default();
+        let mut plastic = Material::default();
+        plastic.name = "plastic".to_string();
+        plastic.opacity_active = true;
+        plastic.opacity = 0.25;
+        vpx.gamedata.materials = Some(vec![plastic]);
+        let ramp = straight_ramp(RampType::Flat);
+
+        let material = ramp_material(&vpx, &ramp);
+
+        assert_eq!(material.name, "Plastic");
+        assert_eq!(material.color[3], 0.25);
+        assert!(material.blend);
+        assert_eq!(material.texture, None);
+
+        vpx.gamedata.materials.as_mut().unwrap()[0].opacity_active = false;
+        assert!(!ramp_material(&vpx, &ramp).blend);
+    }
+}
@@ -0,0 +1,398 @@
+//! Turning the exported layers into preview images
+//!
+//! [`SceneRenderer`] is the hook for renderers, a crate with a GPU renderer can implement it
+//! to produce proper screenshots. [`SoftwareRenderer`] is a small reference implementation
+//! that runs on the CPU without any dependencies, good enough for table previews in CI.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::mesh::render::{SceneRenderer, SoftwareRenderer, View};
+//! use vpin::vpx::mesh::wall::walls_layer;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let layers = vec![walls_layer(&vpx)];
+//! let mut renderer = SoftwareRenderer::default();
+//! let image = renderer.render(&layers, View::Playfield, 200, 400).unwrap();
+//! assert_eq!(image.dimensions(), (200, 400));
+//! ```
+
+use super::glb::Layer;
+use super::{normalize, Vertex};
+use image::{Rgba, RgbaImage};
+use std::io;
+
+/// The point of view for a render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    /// Looking straight down on the table, without perspective, the far end at the top
+    Playfield,
+    /// From where the player stands, looking towards the backglass
+    Player,
+}
+
+/// Something that can render the layers of a table, see [`super::glb`].
+///
+/// The meshes are in vpx coordinates. Implementations choose the camera position based on
+/// the bounds of the meshes so that the whole scene is visible.
+pub trait SceneRenderer {
+    fn render(
+        &mut self,
+        layers: &[Layer],
+        view: View,
+        width: u32,
+        height: u32,
+    ) -> io::Result<RgbaImage>;
+}
+
+/// Flat shaded rasterizer with a depth buffer.
+///
+/// Textures are not sampled, meshes get the color of their material, lit by a single
+/// light from above. Transparent materials are blended after the opaque ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftwareRenderer {
+    pub background: [u8; 4],
+}
+
+impl Default for SoftwareRenderer {
+    fn default() -> Self {
+        SoftwareRenderer {
+            background: [0, 0, 0, 255],
+        }
+    }
+}
+
+/// Comes from above and slightly from the player
+const LIGHT_DIRECTION: [f32; 3] = [0.0, 0.4472136, 0.8944272];
+const AMBIENT: f32 = 0.3;
+const PLAYER_FIELD_OF_VIEW_DEGREES: f32 = 45.0;
+const NEAR: f32 = 1.0;
+
+enum Projection {
+    Orthographic { scale: f32 },
+    Perspective { focal_length: f32 },
+}
+
+struct Camera {
+    eye: [f32; 3],
+    right: [f32; 3],
+    up: [f32; 3],
+    forward: [f32; 3],
+    projection: Projection,
+    width: f32,
+    height: f32,
+}
+
+impl Camera {
+    fn new(view: View, (min, max): ([f32; 3], [f32; 3]), width: u32, height: u32) -> Camera {
+        let (width, height) = (width as f32, height as f32);
+        let center = [
+            (min[0] + max[0]) / 2.0,
+            (min[1] + max[1]) / 2.0,
+            (min[2] + max[2]) / 2.0,
+        ];
+        let size = [max[0] - min[0], max[1] - min[1], max[2] - min[2]];
+        match view {
+            View::Playfield => {
+                let scale =
+                    (width / size[0].max(f32::EPSILON)).min(height / size[1].max(f32::EPSILON));
+                Camera::look_at(
+                    [center[0], center[1], max[2] + NEAR + 1.0],
+                    [0.0, 0.0, -1.0],
+                    [0.0, -1.0, 0.0],
+                    Projection::Orthographic { scale },
+                    width,
+                    height,
+                )
+            }
+            View::Player => {
+                let length = size[0].max(size[1]);
+                let eye = [center[0], max[1] + length * 0.6, max[2] + length * 0.8];
+                let forward = normalize(sub([center[0], center[1], min[2]], eye));
+                let half_fov = PLAYER_FIELD_OF_VIEW_DEGREES.to_radians() / 2.0;
+                Camera::look_at(
+                    eye,
+                    forward,
+                    [0.0, 0.0, 1.0],
+                    Projection::Perspective {
+                        focal_length: height / 2.0 / half_fov.tan(),
+                    },
+                    width,
+                    height,
+                )
+            }
+        }
+    }
+
+    fn look_at(
+        eye: [f32; 3],
+        forward: [f32; 3],
+        up: [f32; 3],
+        projection: Projection,
+        width: f32,
+        height: f32,
+    ) -> Camera {
+        // vpx coordinates are left handed
+        let right = normalize(cross(up, forward));
+        let up = cross(forward, right);
+        Camera {
+            eye,
+            right,
+            up,
+            forward,
+            projection,
+            width,
+            height,
+        }
+    }
+
+    /// Screen x, y and depth, `None` for points behind the camera
+    fn project(&self, position: [f32; 3]) -> Option<[f32; 3]> {
+        let d = sub(position, self.eye);
+        let (x, y, depth) = (dot(d, self.right), dot(d, self.up), dot(d, self.forward));
+        if depth < NEAR {
+            return None;
+        }
+        let scale = match self.projection {
+            Projection::Orthographic { scale } => scale,
+            Projection::Perspective { focal_length } => focal_length / depth,
+        };
+        Some([
+            self.width / 2.0 + x * scale,
+            self.height / 2.0 - y * scale,
+            depth,
+        ])
+    }
+}
+
+impl SceneRenderer for SoftwareRenderer {
+    fn render(
+        &mut self,
+        layers: &[Layer],
+        view: View,
+        width: u32,
+        height: u32,
+    ) -> io::Result<RgbaImage> {
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid image size {}x{}", width, height),
+            ));
+        }
+        let mut image = RgbaImage::from_pixel(width, height, Rgba(self.background));
+        let Some(bounds) = bounds(layers) else {
+            return Ok(image);
+        };
+        let camera = Camera::new(view, bounds, width, height);
+        let mut depth_buffer = vec![f32::INFINITY; (width * height) as usize];
+
+        let meshes = layers.iter().flat_map(|layer| &layer.meshes);
+        let (blended, opaque): (Vec<_>, Vec<_>) = meshes.partition(|m| m.material.blend);
+        for layer_mesh in opaque.into_iter().chain(blended) {
+            let mesh = &layer_mesh.mesh;
+            let color = layer_mesh.material.color;
+            for triangle in mesh.indices.chunks_exact(3) {
+                let vertices = [
+                    vertex(&mesh.vertices, triangle[0])?,
+                    vertex(&mesh.vertices, triangle[1])?,
+                    vertex(&mesh.vertices, triangle[2])?,
+                ];
+                let normal = normalize(cross(
+                    sub(vertices[1].position, vertices[0].position),
+                    sub(vertices[2].position, vertices[0].position),
+                ));
+                // two sided, meshes are not consistent in their winding
+                let light = AMBIENT + (1.0 - AMBIENT) * dot(normal, LIGHT_DIRECTION).abs();
+                let shaded = [
+                    color[0] * light,
+                    color[1] * light,
+                    color[2] * light,
+                    color[3],
+                ];
+                let (Some(a), Some(b), Some(c)) = (
+                    camera.project(vertices[0].position),
+                    camera.project(vertices[1].position),
+                    camera.project(vertices[2].position),
+                ) else {
+                    continue;
+                };
+                rasterize(
+                    &mut image,
+                    &mut depth_buffer,
+                    [a, b, c],
+                    shaded,
+                    layer_mesh.material.blend,
+                );
+            }
+        }
+        Ok(image)
+    }
+}
+
+pub(crate) fn vertex(vertices: &[Vertex], index: u32) -> io::Result<&Vertex> {
+    vertices.get(index as usize).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Vertex index {} out of range", index),
+        )
+    })
+}
+
+fn bounds(layers: &[Layer]) -> Option<([f32; 3], [f32; 3])> {
+    let mut positions = layers
+        .iter()
+        .flat_map(|layer| &layer.meshes)
+        .flat_map(|m| &m.mesh.vertices)
+        .map(|v| v.position);
+    let first = positions.next()?;
+    Some(positions.fold((first, first), |(min, max), p| {
+        (
+            [min[0].min(p[0]), min[1].min(p[1]), min[2].min(p[2])],
+            [max[0].max(p[0]), max[1].max(p[1]), max[2].max(p[2])],
+        )
+    }))
+}
+
+fn rasterize(
+    image: &mut RgbaImage,
+    depth_buffer: &mut [f32],
+    [a, b, c]: [[f32; 3]; 3],
+    color: [f32; 4],
+    blend: bool,
+) {
+    let area = edge(a, b, c);
+    if area.abs() < f32::EPSILON {
+        return;
+    }
+    let (width, height) = image.dimensions();
+    let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+    let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+    let max_x = (a[0].max(b[0]).max(c[0]).ceil().max(0.0) as u32).min(width);
+    let max_y = (a[1].max(b[1]).max(c[1]).ceil().max(0.0) as u32).min(height);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+            let (wa, wb, wc) = (
+                edge(b, c, p) / area,
+                edge(c, a, p) / area,
+                edge(a, b, p) / area,
+            );
+            if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                continue;
+            }
+            let depth = wa * a[2] + wb * b[2] + wc * c[2];
+            let index = (y * width + x) as usize;
+            if depth >= depth_buffer[index] {
+                continue;
+            }
+            let pixel = image.get_pixel_mut(x, y);
+            let alpha = if blend { color[3].clamp(0.0, 1.0) } else { 1.0 };
+            for (target, source) in pixel.0.iter_mut().zip(&color[..3]) {
+                let source = source.clamp(0.0, 1.0) * 255.0;
+                *target = (source * alpha + *target as f32 * (1.0 - alpha)).round() as u8;
+            }
+            if !blend {
+                pixel.0[3] = 255;
+                depth_buffer[index] = depth;
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle, on screen
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::mesh::glb::{GlbMaterial, LayerMesh};
+    use crate::vpx::mesh::Mesh;
+    use pretty_assertions::assert_eq;
+
+    /// A red square on the playfield with a green block on its far half
+    fn layers() -> Vec<Layer> {
+        let quad = |name: &str, [x0, y0, x1, y1]: [f32; 4], z: f32| Mesh {
+            name: name.to_string(),
+            vertices: [[x0, y0], [x1, y0], [x1, y1], [x0, y1]]
+                .iter()
+                .map(|&[x, y]| Vertex {
+                    position: [x, y, z],
+                    normal: [0.0, 0.0, 1.0],
+                    uv: [0.0, 0.0],
+                })
+                .collect(),
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+        vec![Layer {
+            name: "test".to_string(),
+            meshes: vec![
+                LayerMesh {
+                    mesh: quad("block", [0.0, 0.0, 100.0, 50.0], 10.0),
+                    material: GlbMaterial::color("green", [0.0, 1.0, 0.0, 1.0]),
+                },
+                LayerMesh {
+                    mesh: quad("playfield", [0.0, 0.0, 100.0, 100.0], 0.0),
+                    material: GlbMaterial::color("red", [1.0, 0.0, 0.0, 1.0]),
+                },
+            ],
+        }]
+    }
+
+    #[test]
+    fn render_playfield() -> io::Result<()> {
+        let mut renderer = SoftwareRenderer::default();
+        let image = renderer.render(&layers(), View::Playfield, 20, 40)?;
+
+        // the square fills the width and is centered vertically
+        assert_eq!(image.get_pixel(10, 5), &Rgba(renderer.background));
+        // the block is closer to the camera than the playfield, lit from above
+        assert_eq!(image.get_pixel(10, 12), &Rgba([0, 236, 0, 255]));
+        assert_eq!(image.get_pixel(10, 28), &Rgba([236, 0, 0, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn render_player_view() -> io::Result<()> {
+        let mut renderer: Box<dyn SceneRenderer> = Box::new(SoftwareRenderer::default());
+        let image = renderer.render(&layers(), View::Player, 64, 48)?;
+
+        let covered = image.pixels().filter(|p| p.0 != [0, 0, 0, 255]).count();
+        assert!(covered > 0);
+        // the far end is at the top of the image
+        let top_green = (0..24)
+            .flat_map(|y| (0..64).map(move |x| (x, y)))
+            .any(|(x, y)| image.get_pixel(x, y).0[1] > 0);
+        assert!(top_green);
+        Ok(())
+    }
+
+    #[test]
+    fn render_empty_scene() -> io::Result<()> {
+        let image = SoftwareRenderer::default().render(&[], View::Player, 4, 4)?;
+        assert!(image.pixels().all(|p| p.0 == [0, 0, 0, 255]));
+        assert!(SoftwareRenderer::default()
+            .render(&[], View::Player, 0, 4)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,489 @@
+//! Mesh decimation using quadric error metrics
+//!
+//! Edges are collapsed in order of the error they introduce, as described in
+//! "Surface Simplification Using Quadric Error Metrics" by Garland and Heckbert.
+//! Open borders and texture seams are kept in place by extra quadrics on boundary edges.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::gameitem::GameItemEnum;
+//!
+//! let mut vpx = vpx::read(&PathBuf::from("table.vpx")).unwrap();
+//! for item in &mut vpx.gameitems {
+//!     if let GameItemEnum::Primitive(primitive) = item {
+//!         if primitive.num_indices.unwrap_or(0) > 300_000 {
+//!             vpx::mesh::simplify(primitive, 0.25).unwrap();
+//!         }
+//!     }
+//! }
+//! ```
+
+use super::{normalize, Mesh};
+use crate::vpx::gameitem::primitive::Primitive;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::io;
+
+/// Makes moving a boundary edge much more expensive than moving a surface
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Reduces the number of triangles of the primitive mesh to about `target_ratio` of the
+/// original and stores the result back in the primitive.
+///
+/// Primitives with animation frames are refused, the frames would no longer match.
+pub fn simplify(primitive: &mut Primitive, target_ratio: f32) -> io::Result<()> {
+    if primitive.compressed_animation_vertices_data.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Primitive {} has animation frames and can not be simplified",
+                primitive.name
+            ),
+        ));
+    }
+    let Some(mesh) = Mesh::from_primitive(primitive)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Primitive {} has no 3D mesh", primitive.name),
+        ));
+    };
+    mesh.simplify(target_ratio).write_to_primitive(primitive)
+}
+
+/// Symmetric 4x4 matrix stored as its upper triangle
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn plane(normal: [f64; 3], d: f64, weight: f64) -> Quadric {
+        let [a, b, c] = normal;
+        Quadric(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other) in self.0.iter_mut().zip(other.0) {
+            *value += other;
+        }
+    }
+
+    fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+        let [a2, ab, ac, ad, b2, bc, bd, c2, cd, d2] = self.0;
+        a2 * x * x
+            + 2.0 * ab * x * y
+            + 2.0 * ac * x * z
+            + 2.0 * ad * x
+            + b2 * y * y
+            + 2.0 * bc * y * z
+            + 2.0 * bd * y
+            + c2 * z * z
+            + 2.0 * cd * z
+            + d2
+    }
+}
+
+/// A possible edge collapse, moving `from` into `into`
+struct Candidate {
+    cost: f64,
+    into: usize,
+    from: usize,
+    /// Where on the edge the merged vertex ends up, 0 is at `into`
+    t: f64,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    /// Reversed so the cheapest collapse is on top of the heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier<'a> {
+    mesh: &'a Mesh,
+    positions: Vec<[f64; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    quadrics: Vec<Quadric>,
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    vertex_faces: Vec<Vec<usize>>,
+    versions: Vec<u32>,
+    removed: Vec<bool>,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl Mesh {
+    /// Collapses edges until about `target_ratio` of the triangles is left.
+    ///
+    /// The ratio is clamped between 0 and 1. Fewer triangles are removed when further collapses
+    /// would flip triangles.
+    pub fn simplify(&self, target_ratio: f32) -> Mesh {
+        let target = (self.triangle_count() as f32 * target_ratio.clamp(0.0, 1.0)).ceil() as usize;
+        let mut simplifier = Simplifier::new(self);
+        simplifier.run(target);
+        simplifier.into_mesh()
+    }
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(mesh: &'a Mesh) -> Self {
+        let vertex_count = mesh.vertices.len();
+        let faces: Vec<[usize; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+            .collect();
+        let mut simplifier = Simplifier {
+            mesh,
+            positions: mesh
+                .vertices
+                .iter()
+                .map(|v| v.position.map(|p| p as f64))
+                .collect(),
+            normals: mesh.vertices.iter().map(|v| v.normal).collect(),
+            uvs: mesh.vertices.iter().map(|v| v.uv).collect(),
+            quadrics: vec![Quadric::default(); vertex_count],
+            face_alive: vec![true; faces.len()],
+            vertex_faces: vec![Vec::new(); vertex_count],
+            versions: vec![0; vertex_count],
+            removed: vec![false; vertex_count],
+            heap: BinaryHeap::new(),
+            faces,
+        };
+
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (index, face) in simplifier.faces.iter().enumerate() {
+            for corner in 0..3 {
+                simplifier.vertex_faces[face[corner]].push(index);
+                let (a, b) = (face[corner], face[(corner + 1) % 3]);
+                edge_faces
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(index);
+            }
+            let Some((normal, area)) = simplifier.face_normal(face, None) else {
+                continue;
+            };
+            let d = -dot(normal, simplifier.positions[face[0]]);
+            let quadric = Quadric::plane(normal, d, area);
+            for corner in face {
+                simplifier.quadrics[*corner].add(&quadric);
+            }
+        }
+        for ((a, b), faces) in &edge_faces {
+            if faces.len() != 1 {
+                continue;
+            }
+            let face = simplifier.faces[faces[0]];
+            let Some((normal, _)) = simplifier.face_normal(&face, None) else {
+                continue;
+            };
+            let edge = sub(simplifier.positions[*b], simplifier.positions[*a]);
+            let Some(border_normal) = unit(cross(edge, normal)) else {
+                continue;
+            };
+            let d = -dot(border_normal, simplifier.positions[*a]);
+            let weight = BOUNDARY_WEIGHT * dot(edge, edge);
+            let quadric = Quadric::plane(border_normal, d, weight);
+            simplifier.quadrics[*a].add(&quadric);
+            simplifier.quadrics[*b].add(&quadric);
+        }
+        // sorted so equal costs are collapsed in the same order on every run
+        let mut edges: Vec<(usize, usize)> = edge_faces.into_keys().collect();
+        edges.sort_unstable();
+        for (a, b) in edges {
+            simplifier.push_candidate(a, b);
+        }
+        simplifier
+    }
+
+    /// Unit normal and area, `moved` replaces the position of one vertex
+    fn face_normal(
+        &self,
+        face: &[usize; 3],
+        moved: Option<(usize, [f64; 3])>,
+    ) -> Option<([f64; 3], f64)> {
+        let position = |vertex: usize| match moved {
+            Some((moved, position)) if moved == vertex => position,
+            _ => self.positions[vertex],
+        };
+        let [a, b, c] = face.map(position);
+        let normal = cross(sub(b, a), sub(c, a));
+        let length = dot(normal, normal).sqrt();
+        (length > 0.0).then(|| (normal.map(|n| n / length), length / 2.0))
+    }
+
+    fn push_candidate(&mut self, a: usize, b: usize) {
+        let mut quadric = self.quadrics[a];
+        quadric.add(&self.quadrics[b]);
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let (cost, t) = [0.0, 0.5, 1.0]
+            .into_iter()
+            .map(|t| (quadric.error(lerp(pa, pb, t)), t))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .unwrap();
+        self.heap.push(Candidate {
+            cost,
+            into: a,
+            from: b,
+            t,
+            versions: (self.versions[a], self.versions[b]),
+        });
+    }
+
+    fn run(&mut self, target: usize) {
+        let mut alive = self.faces.len();
+        while alive > target {
+            let Some(candidate) = self.heap.pop() else {
+                break;
+            };
+            let Candidate { into, from, t, .. } = candidate;
+            if self.removed[into]
+                || self.removed[from]
+                || candidate.versions != (self.versions[into], self.versions[from])
+            {
+                continue;
+            }
+            let position = lerp(self.positions[into], self.positions[from], t);
+            if self.flips(into, from, position) {
+                continue;
+            }
+            alive -= self.collapse(into, from, position, t);
+        }
+    }
+
+    /// Whether moving both vertices to the position turns any remaining triangle around
+    fn flips(&self, into: usize, from: usize, position: [f64; 3]) -> bool {
+        [into, from].iter().any(|&vertex| {
+            self.vertex_faces[vertex].iter().any(|&face_index| {
+                let face = &self.faces[face_index];
+                if !self.face_alive[face_index] || (face.contains(&into) && face.contains(&from)) {
+                    return false;
+                }
+                match (
+                    self.face_normal(face, None),
+                    self.face_normal(face, Some((vertex, position))),
+                ) {
+                    (Some((before, _)), Some((after, _))) => dot(before, after) <= 0.0,
+                    _ => true,
+                }
+            })
+        })
+    }
+
+    /// Returns the number of removed faces
+    fn collapse(&mut self, into: usize, from: usize, position: [f64; 3], t: f64) -> usize {
+        let mut removed_faces = 0;
+        for face_index in std::mem::take(&mut self.vertex_faces[from]) {
+            if !self.face_alive[face_index] {
+                continue;
+            }
+            let face = &mut self.faces[face_index];
+            if face.contains(&into) {
+                self.face_alive[face_index] = false;
+                removed_faces += 1;
+            } else {
+                for corner in face.iter_mut().filter(|corner| **corner == from) {
+                    *corner = into;
+                }
+                self.vertex_faces[into].push(face_index);
+            }
+        }
+        let face_alive = &self.face_alive;
+        self.vertex_faces[into].retain(|face| face_alive[*face]);
+
+        let t32 = t as f32;
+        self.positions[into] = position;
+        let (na, nb) = (self.normals[into], self.normals[from]);
+        self.normals[into] = normalize([0, 1, 2].map(|i| na[i] + (nb[i] - na[i]) * t32));
+        let (ua, ub) = (self.uvs[into], self.uvs[from]);
+        self.uvs[into] = [0, 1].map(|i| ua[i] + (ub[i] - ua[i]) * t32);
+        let quadric = self.quadrics[from];
+        self.quadrics[into].add(&quadric);
+        self.removed[from] = true;
+        self.versions[into] += 1;
+        self.versions[from] += 1;
+
+        let mut neighbours: Vec<usize> = self.vertex_faces[into]
+            .iter()
+            .flat_map(|face| self.faces[*face])
+            .filter(|vertex| *vertex != into)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for neighbour in neighbours {
+            self.push_candidate(into, neighbour);
+        }
+        removed_faces
+    }
+
+    fn into_mesh(self) -> Mesh {
+        let mut new_index = vec![None; self.positions.len()];
+        let mut mesh = Mesh {
+            name: self.mesh.name.clone(),
+            ..Default::default()
+        };
+        for (face, _) in self
+            .faces
+            .iter()
+            .zip(&self.face_alive)
+            .filter(|(_, alive)| **alive)
+        {
+            for vertex in face {
+                let index = *new_index[*vertex].get_or_insert_with(|| {
+                    let mut new_vertex = self.mesh.vertices[*vertex];
+                    new_vertex.position = self.positions[*vertex].map(|p| p as f32);
+                    new_vertex.normal = self.normals[*vertex];
+                    new_vertex.uv = self.uvs[*vertex];
+                    mesh.vertices.push(new_vertex);
+                    mesh.vertices.len() as u32 - 1
+                });
+                mesh.indices.push(index);
+            }
+        }
+        mesh
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn unit(v: [f64; 3]) -> Option<[f64; 3]> {
+    let length = dot(v, v).sqrt();
+    (length > 0.0).then(|| v.map(|value| value / length))
+}
+
+fn lerp(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::mesh::Vertex;
+    use fake::{Fake, Faker};
+    use pretty_assertions::assert_eq;
+
+    /// A flat square of 10 by 10 made of 200 triangles
+    fn grid() -> Mesh {
+        let size = 10;
+        let mut mesh = Mesh {
+            name: "grid".to_string(),
+            ..Default::default()
+        };
+        for y in 0..=size {
+            for x in 0..=size {
+                mesh.vertices.push(Vertex {
+                    position: [x as f32, y as f32, 0.0],
+                    normal: [0.0, 0.0, 1.0],
+                    uv: [x as f32 / size as f32, y as f32 / size as f32],
+                });
+            }
+        }
+        let index = |x: u32, y: u32| y * (size + 1) + x;
+        for y in 0..size {
+            for x in 0..size {
+                let (a, b, c, d) = (
+                    index(x, y),
+                    index(x + 1, y),
+                    index(x + 1, y + 1),
+                    index(x, y + 1),
+                );
+                mesh.indices.extend([a, b, c, a, c, d]);
+            }
+        }
+        mesh
+    }
+
+    fn area(mesh: &Mesh) -> f32 {
+        mesh.indices
+            .chunks_exact(3)
+            .map(|t| {
+                let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].position);
+                ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn simplify_flat_grid() {
+        let mesh = grid();
+        assert_eq!(mesh.triangle_count(), 200);
+
+        let simplified = mesh.simplify(0.1);
+
+        assert!(simplified.triangle_count() <= 20);
+        assert!(simplified.triangle_count() >= 2);
+        // the square keeps its shape and no triangles are flipped
+        assert!((area(&simplified) - 100.0).abs() < 1e-3);
+        for vertex in &simplified.vertices {
+            let [x, y, _] = vertex.position;
+            assert!((0.0..=10.0).contains(&x) && (0.0..=10.0).contains(&y));
+            assert!((vertex.uv[0] - x / 10.0).abs() < 1e-5);
+        }
+        assert_eq!(mesh.simplify(1.0).triangle_count(), 200);
+    }
+
+    #[test]
+    fn simplify_primitive() {
+        let mut primitive: Primitive = Faker.fake();
+        primitive.compressed_animation_vertices_len = None;
+        primitive.compressed_animation_vertices_data = None;
+        grid().write_to_primitive(&mut primitive).unwrap();
+
+        simplify(&mut primitive, 0.5).unwrap();
+
+        let mesh = Mesh::from_primitive(&primitive).unwrap().unwrap();
+        assert!(mesh.triangle_count() <= 100);
+        assert_eq!(primitive.num_indices, Some(mesh.indices.len() as u32));
+        assert_eq!(primitive.num_vertices, Some(mesh.vertices.len() as u32));
+
+        primitive.compressed_animation_vertices_data = Some(vec![]);
+        let error = simplify(&mut primitive, 0.5).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,311 @@
+//! Heights of the surfaces items are placed on
+//!
+//! Items like bumpers, flippers and kickers name the wall or ramp they stand on in their
+//! `surface` field, an empty name means the playfield at height 0. Upper playfields are
+//! usually walls with a raised top, so everything placed on them has to start at that
+//! height instead of 0. Wall heights and ramp heights are absolute, they are not stacked on
+//! the surface below them.
+//!
+//! [`SurfaceHeightResolver`] looks up the surfaces by name, [`crate::vpx::bounds::placed_bounds`]
+//! uses it to put items at their height on the table.
+//!
+//! [`floor_height`] combines all walls and ramps into a height field, the height the ball
+//! rolls on at any point of the table. [`heightfield_layer`] exports it as a mesh.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::mesh::surface::surface_height;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for item in &vpx.gameitems {
+//!     if let Some(surface) = item.surface() {
+//!         println!("{} at {}", item.name(), surface_height(&vpx, surface, 0.0, 0.0));
+//!     }
+//! }
+//! ```
+
+use super::glb::{GlbMaterial, Layer, LayerMesh};
+use super::ramp::sections;
+use super::{spline, Mesh, Vertex};
+use crate::vpx::gameitem::ramp::Ramp;
+use crate::vpx::gameitem::wall::Wall;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+use std::collections::HashMap;
+
+/// Color of the [`heightfield_layer`], semi transparent so the table stays visible
+const HEIGHTFIELD_COLOR: [f32; 4] = [0.0, 0.5, 1.0, 0.5];
+
+/// Looks up the height of surfaces by name, built once from the walls and ramps of a table.
+///
+/// Walls are flat at their top height, ramps use the height of the point on their center
+/// line closest to the given point, like Visual Pinball does. Unknown names fall back to
+/// the playfield at 0. Names are compared case-insensitive, the first item with a name wins.
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceHeightResolver {
+    walls: HashMap<String, f32>,
+    /// The center line of every ramp
+    ramps: HashMap<String, Vec<[f32; 3]>>,
+}
+
+impl SurfaceHeightResolver {
+    pub fn new(vpx: &VPX) -> Self {
+        let mut resolver = SurfaceHeightResolver::default();
+        for item in &vpx.gameitems {
+            let name = item.name().to_lowercase();
+            if resolver.walls.contains_key(&name) || resolver.ramps.contains_key(&name) {
+                continue;
+            }
+            match item {
+                GameItemEnum::Wall(wall) => {
+                    resolver.walls.insert(name, wall.height_top);
+                }
+                GameItemEnum::Ramp(ramp) => {
+                    let center_line = sections(ramp).iter().map(|s| s.center).collect();
+                    resolver.ramps.insert(name, center_line);
+                }
+                _ => {}
+            }
+        }
+        resolver
+    }
+
+    /// The height of the named surface at a point, 0 for the playfield
+    pub fn height(&self, surface: &str, x: f32, y: f32) -> f32 {
+        if surface.is_empty() {
+            return 0.0;
+        }
+        let name = surface.to_lowercase();
+        if let Some(height) = self.walls.get(&name) {
+            return *height;
+        }
+        self.ramps
+            .get(&name)
+            .and_then(|center_line| center_line_height(center_line, x, y))
+            .map(|(_, height)| height)
+            .unwrap_or(0.0)
+    }
+}
+
+/// The height of the named surface at a point, see [`SurfaceHeightResolver`].
+///
+/// Builds a resolver on every call, keep one around when looking up many items.
+pub fn surface_height(vpx: &VPX, surface: &str, x: f32, y: f32) -> f32 {
+    SurfaceHeightResolver::new(vpx).height(surface, x, y)
+}
+
+/// The height the ball rolls on at a point: the highest top of the collidable walls and the
+/// highest floor of the collidable ramps covering the point, 0 for the playfield.
+pub fn floor_height(vpx: &VPX, x: f32, y: f32) -> f32 {
+    vpx.gameitems
+        .iter()
+        .filter_map(|item| match item {
+            GameItemEnum::Wall(wall) if wall.is_collidable && wall_contains(wall, x, y) => {
+                Some(wall.height_top)
+            }
+            GameItemEnum::Ramp(ramp) if ramp.is_collidable => ramp_floor_height(ramp, x, y),
+            _ => None,
+        })
+        .fold(0.0, f32::max)
+}
+
+/// A grid over the playfield with [`floor_height`] at every corner, `cell_size` in vpx
+/// units. Steep edges between cells show where walls and ramps start.
+pub fn heightfield(vpx: &VPX, cell_size: f32) -> Mesh {
+    let gamedata = &vpx.gamedata;
+    let dimensions = gamedata.dimensions();
+    let mut mesh = Mesh {
+        name: "heightfield".to_string(),
+        ..Default::default()
+    };
+    let (width, height) = (
+        gamedata.right - gamedata.left,
+        gamedata.bottom - gamedata.top,
+    );
+    if cell_size <= 0.0 || width <= 0.0 || height <= 0.0 {
+        return mesh;
+    }
+    let columns = (width / cell_size).ceil() as u32;
+    let rows = (height / cell_size).ceil() as u32;
+    for row in 0..=rows {
+        let y = (gamedata.top + row as f32 * cell_size).min(gamedata.bottom);
+        for column in 0..=columns {
+            let x = (gamedata.left + column as f32 * cell_size).min(gamedata.right);
+            mesh.vertices.push(Vertex {
+                position: [x, y, floor_height(vpx, x, y)],
+                normal: [0.0, 0.0, 1.0],
+                uv: dimensions.world_uv(x, y),
+            });
+        }
+    }
+    let stride = columns + 1;
+    for row in 0..rows {
+        for column in 0..columns {
+            let a = row * stride + column;
+            mesh.indices
+                .extend([a, a + 1, a + stride, a + 1, a + stride + 1, a + stride]);
+        }
+    }
+    mesh
+}
+
+/// The [`heightfield`] in a layer named `heightfield`
+pub fn heightfield_layer(vpx: &VPX, cell_size: f32) -> Layer {
+    Layer {
+        name: "heightfield".to_string(),
+        meshes: vec![LayerMesh {
+            mesh: heightfield(vpx, cell_size),
+            material: GlbMaterial::color("heightfield", HEIGHTFIELD_COLOR),
+        }],
+    }
+}
+
+/// Even-odd test against the outline of the wall
+fn wall_contains(wall: &Wall, x: f32, y: f32) -> bool {
+    let outline = spline(&wall.drag_points, true);
+    if outline.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    for (i, a) in outline.iter().enumerate() {
+        let b = outline[(i + 1) % outline.len()];
+        if (a[1] > y) != (b[1] > y) && x < a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// The floor height of a ramp at a point, `None` if the point is not on the ramp
+fn ramp_floor_height(ramp: &Ramp, x: f32, y: f32) -> Option<f32> {
+    let center_line: Vec<[f32; 3]> = sections(ramp).iter().map(|s| s.center).collect();
+    let (distance, height) = center_line_height(&center_line, x, y)?;
+    let half_width = ramp.width_bottom.max(ramp.width_top) / 2.0;
+    (distance <= half_width).then_some(height)
+}
+
+/// The distance to the closest point on the center line of a ramp and the height there
+fn center_line_height(center_line: &[[f32; 3]], x: f32, y: f32) -> Option<(f32, f32)> {
+    if let [[cx, cy, cz]] = center_line {
+        return Some((((x - cx).powi(2) + (y - cy).powi(2)).sqrt(), *cz));
+    }
+    center_line
+        .windows(2)
+        .map(|pair| {
+            let [ax, ay, az] = pair[0];
+            let [bx, by, bz] = pair[1];
+            let (dx, dy) = (bx - ax, by - ay);
+            let length_squared = dx * dx + dy * dy;
+            let t = if length_squared > 0.0 {
+                (((x - ax) * dx + (y - ay) * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let (px, py) = (ax + t * dx, ay + t * dy);
+            let distance = ((x - px).powi(2) + (y - py).powi(2)).sqrt();
+            (distance, az + t * (bz - az))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::bumper::Bumper;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use pretty_assertions::assert_eq;
+
+    fn drag_point(x: f32, y: f32) -> DragPoint {
+        DragPoint {
+            x,
+            y,
+            ..Default::default()
+        }
+    }
+
+    /// An upper playfield from (100, 100) to (300, 300) with a ramp going up to it
+    fn upper_playfield_table() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.gamedata.left = 0.0;
+        vpx.gamedata.top = 0.0;
+        vpx.gamedata.right = 400.0;
+        vpx.gamedata.bottom = 800.0;
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "UpperPF".to_string(),
+            height_bottom: 0.0,
+            height_top: 60.0,
+            is_collidable: true,
+            drag_points: vec![
+                drag_point(100.0, 100.0),
+                drag_point(300.0, 100.0),
+                drag_point(300.0, 300.0),
+                drag_point(100.0, 300.0),
+            ],
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::Ramp(Ramp {
+            name: "UpRamp".to_string(),
+            height_bottom: 0.0,
+            height_top: 60.0,
+            width_bottom: 50.0,
+            width_top: 50.0,
+            is_collidable: true,
+            drag_points: vec![drag_point(200.0, 700.0), drag_point(200.0, 300.0)],
+            ..Default::default()
+        }));
+        vpx.add_game_item(GameItemEnum::Bumper(Bumper {
+            name: "UpperBumper".to_string(),
+            surface: "upperpf".to_string(),
+            ..Default::default()
+        }));
+        vpx
+    }
+
+    #[test]
+    fn surface_heights() {
+        let vpx = upper_playfield_table();
+        assert_eq!(surface_height(&vpx, "", 200.0, 200.0), 0.0);
+        assert_eq!(surface_height(&vpx, "upperpf", 0.0, 0.0), 60.0);
+        assert_eq!(surface_height(&vpx, "UpRamp", 200.0, 500.0), 30.0);
+        // closest to the top of the ramp
+        assert_eq!(surface_height(&vpx, "UpRamp", 250.0, 100.0), 60.0);
+        assert_eq!(surface_height(&vpx, "Missing", 200.0, 500.0), 0.0);
+        let resolver = SurfaceHeightResolver::new(&vpx);
+        assert_eq!(resolver.height("UPPERPF", 0.0, 0.0), 60.0);
+        assert_eq!(resolver.height("upramp", 200.0, 700.0), 0.0);
+        let bumper = &vpx.gameitems[2];
+        assert_eq!(
+            surface_height(&vpx, bumper.surface().unwrap(), 200.0, 200.0),
+            60.0
+        );
+    }
+
+    #[test]
+    fn floor_heights() {
+        let vpx = upper_playfield_table();
+        assert_eq!(floor_height(&vpx, 50.0, 50.0), 0.0);
+        assert_eq!(floor_height(&vpx, 200.0, 200.0), 60.0);
+        assert_eq!(floor_height(&vpx, 210.0, 600.0), 15.0);
+        assert_eq!(floor_height(&vpx, 300.0, 600.0), 0.0);
+    }
+
+    #[test]
+    fn heightfield_grid() {
+        let vpx = upper_playfield_table();
+        let mesh = heightfield(&vpx, 100.0);
+        assert_eq!(mesh.vertices.len(), 5 * 9);
+        assert_eq!(mesh.triangle_count(), 4 * 8 * 2);
+        let at = |x: f32, y: f32| {
+            mesh.vertices
+                .iter()
+                .find(|v| v.position[0] == x && v.position[1] == y)
+                .map(|v| v.position[2])
+        };
+        assert_eq!(at(200.0, 200.0), Some(60.0));
+        assert_eq!(at(0.0, 0.0), Some(0.0));
+        assert_eq!(heightfield(&vpx, 0.0).vertices.len(), 0);
+    }
+}
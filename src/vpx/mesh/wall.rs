@@ -0,0 +1,337 @@
+//! Wall meshes with the texture coordinates Visual Pinball uses
+//!
+//! The top image is mapped in table coordinates, so it lines up with the playfield when both
+//! use the same picture. The side image runs along the outline from the top (v = 0) to the
+//! bottom (v = 1). Drag points without `has_auto_texture` pin their `tex_coord` and the
+//! points in between are placed by their distance along the outline. Without pinned points
+//! the image is stretched once around the whole wall.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::mesh::glb::write_glb;
+//! use vpin::vpx::mesh::wall::walls_layer;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let mut glb = Vec::new();
+//! write_glb(&[walls_layer(&vpx)], &mut glb).unwrap();
+//! ```
+
+use super::glb::{GlbMaterial, Layer, LayerMesh};
+use super::{normalize, spline_with_controls, Mesh, Vertex};
+use crate::vpx::gameitem::wall::Wall;
+use crate::vpx::gameitem::GameItemEnum;
+use crate::vpx::VPX;
+
+/// The parts of a wall that have their own material and image
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct WallMeshes {
+    /// The sides from bottom to top height, 4 vertices per segment
+    pub side: Mesh,
+    pub top: Mesh,
+}
+
+/// Builds the sides and top of a wall, both are empty for walls with less than 3 points
+pub fn wall(vpx: &VPX, wall: &Wall) -> WallMeshes {
+    let mut meshes = WallMeshes {
+        side: Mesh {
+            name: wall.name.clone(),
+            ..Default::default()
+        },
+        top: Mesh {
+            name: wall.name.clone(),
+            ..Default::default()
+        },
+    };
+    let (curve, controls) = spline_with_controls(&wall.drag_points, true);
+    // every point of the outline with the texture coordinate pinned by its drag point
+    let mut outline: Vec<([f32; 2], Option<f32>)> =
+        curve.iter().map(|p| ([p[0], p[1]], None)).collect();
+    for (point, index) in wall.drag_points.iter().zip(controls) {
+        if !point.has_auto_texture {
+            outline[index].1 = Some(point.tex_coord);
+        }
+    }
+    outline.dedup_by(|next, previous| {
+        if next.0 == previous.0 {
+            previous.1 = previous.1.or(next.1);
+        }
+        next.0 == previous.0
+    });
+    if outline.len() > 1 && outline[0].0 == outline[outline.len() - 1].0 {
+        outline.pop();
+    }
+    if outline.len() < 3 {
+        return meshes;
+    }
+    let points: Vec<[f32; 2]> = outline.iter().map(|(point, _)| *point).collect();
+    let pinned: Vec<(usize, f32)> = outline
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (_, coordinate))| coordinate.map(|c| (index, c)))
+        .collect();
+    let mut coordinates = side_texture_coordinates(&points, &pinned);
+    let mut points = points;
+    if signed_area(&points) < 0.0 {
+        points.reverse();
+        coordinates.reverse();
+    }
+
+    let count = points.len();
+    let side = &mut meshes.side;
+    for i in 0..count {
+        let next = (i + 1) % count;
+        let [x0, y0] = points[i];
+        let [x1, y1] = points[next];
+        let normal = normalize([y1 - y0, x0 - x1, 0.0]);
+        let start = side.vertices.len() as u32;
+        for ([x, y], z, uv) in [
+            ([x0, y0], wall.height_bottom, [coordinates[i], 1.0]),
+            ([x1, y1], wall.height_bottom, [coordinates[next], 1.0]),
+            ([x1, y1], wall.height_top, [coordinates[next], 0.0]),
+            ([x0, y0], wall.height_top, [coordinates[i], 0.0]),
+        ] {
+            side.vertices.push(Vertex {
+                position: [x, y, z],
+                normal,
+                uv,
+            });
+        }
+        side.indices
+            .extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    let dimensions = vpx.gamedata.dimensions();
+    let top = &mut meshes.top;
+    top.vertices.extend(points.iter().map(|[x, y]| Vertex {
+        position: [*x, *y, wall.height_top],
+        normal: [0.0, 0.0, 1.0],
+        uv: dimensions.world_uv(*x, *y),
+    }));
+    top.indices.extend(triangulate(&points));
+    meshes
+}
+
+/// The visible parts of a wall with their materials
+pub fn visible_wall_meshes(vpx: &VPX, w: &Wall) -> Vec<LayerMesh> {
+    let WallMeshes { side, top } = wall(vpx, w);
+    let mut meshes = Vec::new();
+    if w.is_side_visible {
+        meshes.push(LayerMesh {
+            mesh: side,
+            material: GlbMaterial::from_table(vpx, &w.side_material, &w.side_image),
+        });
+    }
+    if w.is_top_bottom_visible {
+        meshes.push(LayerMesh {
+            mesh: top,
+            material: GlbMaterial::from_table(vpx, &w.top_material, &w.image),
+        });
+    }
+    meshes
+}
+
+/// All walls with their visible parts and materials in a layer named `walls`
+pub fn walls_layer(vpx: &VPX) -> Layer {
+    let meshes = vpx
+        .gameitems
+        .iter()
+        .flat_map(|item| match item {
+            GameItemEnum::Wall(w) => visible_wall_meshes(vpx, w),
+            _ => vec![],
+        })
+        .collect();
+    Layer {
+        name: "walls".to_string(),
+        meshes,
+    }
+}
+
+/// The u texture coordinate of every point of a closed outline.
+///
+/// Follows `IHaveDragPoints::GetTextureCoords` of Visual Pinball: between two pinned points
+/// the coordinate is interpolated by length along the outline, from the last pinned point it
+/// wraps around to the first one. Without pinned points the outline runs from 0 to 1, which
+/// makes the last segment go back from the end of the image to the start.
+fn side_texture_coordinates(points: &[[f32; 2]], pinned: &[(usize, f32)]) -> Vec<f32> {
+    let count = points.len();
+    let lengths: Vec<f32> = (0..count)
+        .map(|i| {
+            let [x0, y0] = points[i];
+            let [x1, y1] = points[(i + 1) % count];
+            ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt()
+        })
+        .collect();
+    let spans: Vec<(usize, f32, usize, f32)> = if pinned.is_empty() {
+        vec![(0, 0.0, count, 1.0)]
+    } else {
+        (0..pinned.len())
+            .map(|i| {
+                let (start, start_coordinate) = pinned[i];
+                let (end, end_coordinate) = pinned[(i + 1) % pinned.len()];
+                let end = if end <= start { end + count } else { end };
+                (start, start_coordinate, end, end_coordinate)
+            })
+            .collect()
+    };
+    let mut coordinates = vec![0.0; count];
+    for (start, start_coordinate, end, end_coordinate) in spans {
+        let total: f32 = (start..end).map(|l| lengths[l % count]).sum();
+        let mut partial = 0.0;
+        for l in start..end {
+            let progress = if total > 0.0 { partial / total } else { 0.0 };
+            coordinates[l % count] =
+                start_coordinate + progress * (end_coordinate - start_coordinate);
+            partial += lengths[l % count];
+        }
+    }
+    coordinates
+}
+
+fn signed_area(polygon: &[[f32; 2]]) -> f32 {
+    let count = polygon.len();
+    (0..count)
+        .map(|i| {
+            let [x0, y0] = polygon[i];
+            let [x1, y1] = polygon[(i + 1) % count];
+            x0 * y1 - x1 * y0
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+/// Ear clipping triangulation of a simple polygon with positive signed area
+fn triangulate(polygon: &[[f32; 2]]) -> Vec<u32> {
+    let cross = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| {
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+    };
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut indices = Vec::new();
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let a = polygon[remaining[(i + count - 1) % count]];
+            let b = polygon[remaining[i]];
+            let c = polygon[remaining[(i + 1) % count]];
+            if cross(a, b, c) <= 0.0 {
+                return false;
+            }
+            remaining.iter().all(|&other| {
+                let p = polygon[other];
+                p == a
+                    || p == b
+                    || p == c
+                    || cross(a, b, p) < 0.0
+                    || cross(b, c, p) < 0.0
+                    || cross(c, a, p) < 0.0
+            })
+        });
+        // degenerate polygons have no ear, fall back to clipping the first vertex
+        let i = ear.unwrap_or(0);
+        indices.extend([
+            remaining[(i + count - 1) % count] as u32,
+            remaining[i] as u32,
+            remaining[(i + 1) % count] as u32,
+        ]);
+        remaining.remove(i);
+    }
+    if remaining.len() == 3 {
+        indices.extend(remaining.iter().map(|&i| i as u32));
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::dragpoint::DragPoint;
+    use pretty_assertions::assert_eq;
+
+    fn square_wall(pinned: &[(usize, f32)]) -> Wall {
+        let drag_points = [
+            (100.0, 200.0),
+            (200.0, 200.0),
+            (200.0, 300.0),
+            (100.0, 300.0),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(index, &(x, y))| {
+            let pin = pinned.iter().find(|(i, _)| *i == index);
+            DragPoint {
+                x,
+                y,
+                has_auto_texture: pin.is_none(),
+                tex_coord: pin.map(|(_, c)| *c).unwrap_or(0.0),
+                ..Default::default()
+            }
+        })
+        .collect();
+        Wall {
+            name: "Wall".to_string(),
+            height_bottom: 0.0,
+            height_top: 50.0,
+            drag_points,
+            ..Default::default()
+        }
+    }
+
+    fn side_us(meshes: &WallMeshes) -> Vec<f32> {
+        // the bottom left vertex of every side quad
+        meshes
+            .side
+            .vertices
+            .chunks(4)
+            .map(|quad| quad[0].uv[0])
+            .collect()
+    }
+
+    #[test]
+    fn texture_coordinates() {
+        let mut vpx = VPX::default();
+        vpx.gamedata.left = 0.0;
+        vpx.gamedata.top = 0.0;
+        vpx.gamedata.right = 1000.0;
+        vpx.gamedata.bottom = 2000.0;
+
+        let meshes = wall(&vpx, &square_wall(&[]));
+        assert_eq!(side_us(&meshes), vec![0.0, 0.25, 0.5, 0.75]);
+        // the last side runs back to the start of the image
+        assert_eq!(meshes.side.vertices[13].uv, [0.0, 1.0]);
+        assert_eq!(meshes.side.vertices[14].uv, [0.0, 0.0]);
+        assert_eq!(meshes.top.vertices[0].uv, [0.1, 0.1]);
+        assert_eq!(meshes.top.vertices[2].uv, [0.2, 0.15]);
+        assert_eq!(meshes.top.triangle_count(), 2);
+
+        let meshes = wall(&vpx, &square_wall(&[(0, 0.0), (2, 2.0)]));
+        assert_eq!(side_us(&meshes), vec![0.0, 1.0, 2.0, 1.0]);
+
+        let meshes = wall(&vpx, &square_wall(&[(1, 0.5)]));
+        assert_eq!(side_us(&meshes), vec![0.5, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn layer_with_visible_parts() {
+        let mut vpx = VPX::default();
+        let mut visible = square_wall(&[]);
+        visible.side_material = "Metal".to_string();
+        visible.is_side_visible = true;
+        visible.is_top_bottom_visible = true;
+        vpx.add_game_item(GameItemEnum::Wall(visible));
+        let mut hidden = square_wall(&[]);
+        hidden.is_side_visible = false;
+        hidden.is_top_bottom_visible = false;
+        vpx.add_game_item(GameItemEnum::Wall(hidden));
+
+        let layer = walls_layer(&vpx);
+
+        let materials: Vec<&str> = layer
+            .meshes
+            .iter()
+            .map(|mesh| mesh.material.name.as_str())
+            .collect();
+        assert_eq!(materials, vec!["Metal", ""]);
+    }
+}
@@ -17,13 +17,14 @@
 use ::image::ImageFormat;
 use std::fs::OpenOptions;
 use std::io::{self, Error, Read, Seek, Write};
-use std::path::MAIN_SEPARATOR_STR;
+use std::path::{Component, MAIN_SEPARATOR_STR};
 use std::{
     fs::File,
     path::{Path, PathBuf},
 };
 
 use cfb::CompoundFile;
+use regex::Regex;
 
 use md2::{Digest, Md2};
 
@@ -31,8 +32,8 @@ use crate::vpx::biff::BiffReader;
 
 use crate::vpx::expanded::vpx_image_to_dynamic_image;
 use crate::vpx::image::ImageDataJpeg;
-use crate::vpx::tableinfo::read_tableinfo;
-use tableinfo::{write_tableinfo, TableInfo};
+use crate::vpx::tableinfo::{read_tableinfo, read_tableinfo_with_encoding, TableInfoEncoding};
+use tableinfo::{write_tableinfo, LocalizedField, TableInfo};
 use version::Version;
 
 use self::biff::{BiffRead, BiffWrite, BiffWriter};
@@ -42,29 +43,79 @@ use self::font::FontData;
 use self::gamedata::GameData;
 use self::gameitem::GameItemEnum;
 use self::image::ImageData;
+use self::scores::ScoreCapture;
 use self::sound::SoundData;
 use self::version::{read_version, write_version};
 
+pub mod assetmeta;
+pub mod audit;
+pub mod backup;
 pub mod biff;
+pub mod bounds;
+pub use self::bounds::bounds;
+pub mod builder;
+pub use self::builder::VpxBuilder;
+pub mod card;
+pub use self::card::card;
+pub mod changelog;
 pub mod collection;
 pub mod color;
 pub mod custominfotags;
+pub mod dependencies;
+pub mod edit;
+pub mod element;
+pub mod envelope;
+pub mod events;
 pub mod expanded;
 pub mod font;
 pub mod gamedata;
 pub mod gameitem;
 pub mod image;
+pub mod imageusage;
+pub mod import;
+pub mod inject;
 pub mod jsonmodel;
 pub mod math;
+pub mod matlib;
+pub mod mesh;
 pub mod model;
+pub mod protection;
+pub mod scores;
 pub mod sound;
+pub mod spatial;
+pub mod stats;
 pub mod tableinfo;
+pub mod templates;
+pub mod textbundle;
 pub mod version;
 
 pub mod material;
 
 pub mod renderprobe;
 
+pub mod search;
+pub use self::search::search;
+
+pub mod repair;
+
+pub mod sequencer;
+
+pub mod split;
+pub use self::split::{join, split};
+
+pub mod rules;
+pub use self::rules::rules;
+
+pub mod validate;
+
+pub mod profile;
+#[cfg(feature = "profiling")]
+use self::profile::Profile;
+use self::profile::Profiler;
+
+use self::backup::BackupPolicy;
+use self::changelog::ModificationLog;
+
 pub(crate) mod json;
 
 // we have to make this public for the integration tests
@@ -101,6 +152,43 @@ pub struct VPX {
     pub sounds: Vec<SoundData>,
     pub fonts: Vec<FontData>,
     pub collections: Vec<Collection>,
+    /// Streams Visual Pinball does not know about, added by third party tools
+    pub extra_streams: Vec<ExtraStream>,
+    /// Changes made by the operations in this library, not stored in the file, see
+    /// [`changelog::write_to_info`]
+    pub changelog: ModificationLog,
+}
+
+/// A stream in the compound file that is not part of the table format
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExtraStream {
+    /// Path inside the compound file with `/` as separator, eg `GameStg/PinUP`
+    pub path: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// Write the [`VPX::extra_streams`], enabled by default
+    pub preserve_extra_streams: bool,
+    /// Write to a temporary file next to the target and rename it when done, so a failed
+    /// write never leaves a half written table behind. Disabled by default.
+    pub atomic: bool,
+    /// Write the number of game items, images, sounds, fonts and collections as they are in
+    /// the vectors instead of the counts in [`VPX::gamedata`], enabled by default. Visual
+    /// Pinball reads as many as the counts say, wrong counts make it skip or fail to load
+    /// parts of the table.
+    pub sync_counts: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            preserve_extra_streams: true,
+            atomic: false,
+            sync_counts: true,
+        }
+    }
 }
 
 pub enum AddImageResult {
@@ -115,17 +203,49 @@ impl VPX {
         self
     }
 
+    /// Sets a translation of a table info text, see [`TableInfo::set_localized`]
+    pub fn set_localized_info(&mut self, field: LocalizedField, lang: &str, text: String) {
+        let key = self.info.set_localized(field, lang, text);
+        if !self.custominfotags.contains(&key) {
+            self.custominfotags.push(key);
+        }
+    }
+
+    /// The type ids of game items this library does not know about, they are kept as
+    /// [`GameItemEnum::Generic`] and written back unchanged.
+    pub fn unknown_item_types(&self) -> Vec<u32> {
+        gameitem::unknown_item_types(&self.gameitems)
+    }
+
     pub fn set_script(&mut self, script: String) -> &Self {
         self.gamedata.set_code(script);
         self
     }
 
+    /// Names of all images used by the table settings and the game items.
+    ///
+    /// Duplicates are removed case-insensitive, keeping the first occurrence.
+    pub fn referenced_images(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        let all = self
+            .gamedata
+            .referenced_images()
+            .into_iter()
+            .chain(self.gameitems.iter().flat_map(|i| i.referenced_images()));
+        for name in all {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
     pub fn add_or_replace_image(&mut self, image: ImageData) -> AddImageResult {
         // make sure there is a unique name
         let existing_pos = self
             .images
             .iter()
-            .position(|i| i.name.to_ascii_lowercase() == image.name.to_ascii_lowercase());
+            .position(|i| i.name.eq_ignore_ascii_case(&image.name));
         match existing_pos {
             Some(pos) => {
                 let existing = self.images[pos].clone();
@@ -139,6 +259,48 @@ impl VPX {
             }
         }
     }
+
+    /// Embeds a TrueType font, replacing the font with the same name, which is returned.
+    ///
+    /// Names are compared case-insensitive.
+    pub fn add_font(&mut self, name: &str, ttf: Vec<u8>) -> Option<FontData> {
+        let font = FontData {
+            name: name.to_string(),
+            path: format!("{}.ttf", name),
+            data: ttf,
+        };
+        let replaced = match self.font_position(name) {
+            Some(pos) => Some(std::mem::replace(&mut self.fonts[pos], font)),
+            None => {
+                self.fonts.push(font);
+                None
+            }
+        };
+        self.gamedata.fonts_size = self.fonts.len() as u32;
+        let action = if replaced.is_some() {
+            "Replaced"
+        } else {
+            "Added"
+        };
+        self.changelog
+            .record("add_font", format!("{} font {}", action, name));
+        replaced
+    }
+
+    /// Removes an embedded font, names are compared case-insensitive
+    pub fn remove_font(&mut self, name: &str) -> Option<FontData> {
+        let removed = self.fonts.remove(self.font_position(name)?);
+        self.gamedata.fonts_size = self.fonts.len() as u32;
+        self.changelog
+            .record("remove_font", format!("Removed font {}", removed.name));
+        Some(removed)
+    }
+
+    fn font_position(&self, name: &str) -> Option<usize> {
+        self.fonts
+            .iter()
+            .position(|f| f.name.eq_ignore_ascii_case(name))
+    }
 }
 
 #[derive(Debug)]
@@ -150,7 +312,70 @@ pub enum ExtractResult {
 #[derive(Eq, PartialEq, Debug)]
 pub enum VerifyResult {
     Ok(PathBuf),
-    Failed(PathBuf, String),
+    Failed(PathBuf, VerifyError),
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum VerifyError {
+    /// The file could not be read, contains the error message
+    Read(String),
+    MacMismatch(MacMismatch),
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::Read(message) => write!(f, "{}", message),
+            VerifyError::MacMismatch(mismatch) => write!(
+                f,
+                "MAC mismatch: {:?} != {:?}",
+                mismatch.expected, mismatch.computed
+            ),
+        }
+    }
+}
+
+/// The stored MAC does not match the table contents.
+///
+/// The MAC is a single hash over many streams so it can not tell what changed. Comparing the
+/// stream hashes with the [`stream_hashes`] of a known good copy of the table shows which
+/// streams were modified. A modified table can be signed again with
+/// [`repair::regenerate_mac`].
+#[derive(Eq, PartialEq, Debug)]
+pub struct MacMismatch {
+    /// The MAC stored in the file
+    pub expected: Vec<u8>,
+    /// The MAC computed from the table contents
+    pub computed: Vec<u8>,
+    pub streams: Vec<StreamHash>,
+}
+
+impl MacMismatch {
+    /// Paths of the streams that are missing, added or different compared to a reference
+    pub fn differing_streams(&self, reference: &[StreamHash]) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .streams
+            .iter()
+            .filter(|stream| !reference.contains(stream))
+            .map(|stream| stream.path.clone())
+            .collect();
+        paths.extend(
+            reference
+                .iter()
+                .filter(|stream| !self.streams.iter().any(|s| s.path == stream.path))
+                .map(|stream| stream.path.clone()),
+        );
+        paths
+    }
+}
+
+/// Hash of the part of a stream that is included in the MAC
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct StreamHash {
+    /// Path inside the compound file, eg `GameStg/GameData`
+    pub path: String,
+    /// MD2 hash, the same algorithm as the MAC
+    pub hash: Vec<u8>,
 }
 
 /// Handle to an underlying VPX file
@@ -175,21 +400,62 @@ pub enum VerifyResult {
 pub struct VpxFile<F> {
     // keep this private
     compound_file: CompoundFile<F>,
+    read_only: bool,
 }
 
 impl<F: Read + Seek + Write> VpxFile<F> {
     /// Opens an existing compound file, using the underlying reader.  If the
     /// underlying reader also supports the `Write` trait, then the
     /// `CompoundFile` object will be writable as well.
+    ///
+    /// Use [`VpxFile::open_read_only`] to reject modifications up front.
     pub fn open(inner: F) -> io::Result<VpxFile<F>> {
-        // TODO the fact that this is read only should be reflected in the VpxFile type
-        let compound_file = CompoundFile::open_strict(inner)?;
-        Ok(VpxFile { compound_file })
+        Self::open_read_write(inner)
     }
 
+    /// Opens an existing compound file for reading and writing, see
+    /// [`VpxFile::open_read_write`]
     pub fn open_rw(inner: F) -> io::Result<VpxFile<F>> {
+        Self::open_read_write(inner)
+    }
+
+    /// Opens an existing compound file for reading.
+    ///
+    /// Operations that modify the file fail with [`io::ErrorKind::PermissionDenied`], even if
+    /// the underlying reader is writable.
+    pub fn open_read_only(inner: F) -> io::Result<VpxFile<F>> {
         let compound_file = CompoundFile::open_strict(inner)?;
-        Ok(VpxFile { compound_file })
+        Ok(VpxFile {
+            compound_file,
+            read_only: true,
+        })
+    }
+
+    /// Opens an existing compound file for reading and writing, the underlying reader has to
+    /// be writable.
+    pub fn open_read_write(inner: F) -> io::Result<VpxFile<F>> {
+        let compound_file = CompoundFile::open_strict(inner)?;
+        Ok(VpxFile {
+            compound_file,
+            read_only: false,
+        })
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn ensure_writable(&self, operation: &str) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "Can not {}, the vpx file is opened read only, use open_read_write instead",
+                    operation
+                ),
+            ));
+        }
+        Ok(())
     }
 
     pub fn read_version(&mut self) -> io::Result<Version> {
@@ -200,6 +466,15 @@ impl<F: Read + Seek + Write> VpxFile<F> {
         read_tableinfo(&mut self.compound_file)
     }
 
+    /// Reads the table info decoding strings with a specific encoding, for tables where
+    /// the automatic detection of [`VpxFile::read_tableinfo`] picks the wrong one.
+    pub fn read_tableinfo_with_encoding(
+        &mut self,
+        encoding: TableInfoEncoding,
+    ) -> io::Result<TableInfo> {
+        read_tableinfo_with_encoding(&mut self.compound_file, encoding)
+    }
+
     pub fn read_gamedata(&mut self) -> io::Result<GameData> {
         let version = self.read_version()?;
         read_gamedata(&mut self.compound_file, &version)
@@ -210,6 +485,14 @@ impl<F: Read + Seek + Write> VpxFile<F> {
         read_gameitems(&mut self.compound_file, &gamedata)
     }
 
+    /// Reads the game items without failing on items that can not be parsed.
+    ///
+    /// Items that fail to parse are returned as [`GameItemEnum::Corrupt`].
+    pub fn read_gameitems_forgiving(&mut self) -> io::Result<Vec<GameItemEnum>> {
+        let gamedata = self.read_gamedata()?;
+        read_gameitems_forgiving(&mut self.compound_file, &gamedata)
+    }
+
     pub fn read_images(&mut self) -> io::Result<Vec<ImageData>> {
         let gamedata = self.read_gamedata()?;
         read_images(&mut self.compound_file, &gamedata)
@@ -235,6 +518,28 @@ impl<F: Read + Seek + Write> VpxFile<F> {
         read_custominfotags(&mut self.compound_file)
     }
 
+    /// Reads the [`scores`] stream without reading the rest of the table
+    pub fn read_scores(&mut self) -> io::Result<Option<ScoreCapture>> {
+        let path = Path::new(MAIN_SEPARATOR_STR).join(scores::SCORES_STREAM);
+        if !self.compound_file.is_stream(&path) {
+            return Ok(None);
+        }
+        let mut data = Vec::new();
+        self.compound_file
+            .open_stream(&path)?
+            .read_to_end(&mut data)?;
+        ScoreCapture::from_json(&data).map(Some)
+    }
+
+    /// Replaces the [`scores`] stream, the rest of the table is left as it is
+    pub fn write_scores(&mut self, capture: &ScoreCapture) -> io::Result<()> {
+        self.ensure_writable("write scores")?;
+        let data = capture.to_json()?;
+        let path = Path::new(MAIN_SEPARATOR_STR).join(scores::SCORES_STREAM);
+        self.compound_file.create_stream(&path)?.write_all(&data)?;
+        self.compound_file.flush()
+    }
+
     /// Convert all PNG and BMP images to WebP format and write them back to the VPX file.
     /// This will overwrite the existing images.
     /// The images will be converted to lossless WebP.
@@ -243,6 +548,7 @@ impl<F: Read + Seek + Write> VpxFile<F> {
     ///
     /// Returns a list of conversions that were made.
     pub fn images_to_webp(&mut self) -> io::Result<Vec<ImageToWebpConversion>> {
+        self.ensure_writable("convert images to webp")?;
         // We need to make sure we have read access, or we will get a: Bad file descriptor (os error 9)
         let gamedata = self.read_gamedata()?;
         let results = images_to_webp(&mut self.compound_file, &gamedata)?;
@@ -292,14 +598,46 @@ fn compact_cfb<P: AsRef<Path>>(in_path: P) -> io::Result<()> {
     std::fs::rename(&out_path, &in_path)
 }
 
-/// Opens a handle to an existing VPX file
+/// Opens a handle to an existing VPX file for reading, see [`open_read_only`]
+///
+/// The file is opened read only, so this is the same as [`open_read_only`].
 pub fn open<P: AsRef<Path>>(path: P) -> io::Result<VpxFile<File>> {
-    VpxFile::open(File::open(path)?)
+    open_read_only(path)
 }
 
+/// Opens a handle to an existing VPX file for reading and writing, see [`open_read_write`]
 pub fn open_rw<P: AsRef<Path>>(path: P) -> io::Result<VpxFile<File>> {
-    let file = OpenOptions::new().read(true).write(true).open(path)?;
-    VpxFile::open_rw(file)
+    open_read_write(path)
+}
+
+/// Opens a handle to an existing VPX file for reading
+pub fn open_read_only<P: AsRef<Path>>(path: P) -> io::Result<VpxFile<File>> {
+    VpxFile::open_read_only(File::open(path)?)
+}
+
+/// Opens a handle to an existing VPX file for reading and writing.
+///
+/// Fails with [`io::ErrorKind::PermissionDenied`] if the file is write protected.
+pub fn open_read_write<P: AsRef<Path>>(path: P) -> io::Result<VpxFile<File>> {
+    let path = path.as_ref();
+    let write_protected = || {
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is write protected", path.display()),
+        )
+    };
+    if std::fs::metadata(path)?.permissions().readonly() {
+        return Err(write_protected());
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => write_protected(),
+            _ => e,
+        })?;
+    VpxFile::open_read_write(file)
 }
 
 /// Reads a VPX file from disk to memory
@@ -323,26 +661,129 @@ pub fn read(path: &PathBuf) -> io::Result<VPX> {
 ///
 /// see also [`read()`]
 pub fn write<P: AsRef<Path>>(path: P, vpx: &VPX) -> io::Result<()> {
-    let file = File::options()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(path)?;
-    let mut comp = CompoundFile::create(file)?;
-    write_vpx(&mut comp, vpx)
+    write_with_options(path, vpx, &WriteOptions::default())
+}
+
+/// Writes a VPX file from memory to disk
+///
+/// see also [`write()`]
+pub fn write_with_options<P: AsRef<Path>>(
+    path: P,
+    vpx: &VPX,
+    options: &WriteOptions,
+) -> io::Result<()> {
+    write_compound_file(path.as_ref(), options.atomic, |comp| {
+        write_vpx(comp, vpx, options)
+    })
+}
+
+/// Creates the compound file and writes it with `f`, see [`WriteOptions::atomic`]
+fn write_compound_file<T, W>(path: &Path, atomic: bool, f: W) -> io::Result<T>
+where
+    W: FnOnce(&mut CompoundFile<File>) -> io::Result<T>,
+{
+    let create = |path: &Path| {
+        File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+    };
+    if !atomic {
+        let mut comp = CompoundFile::create(create(path)?)?;
+        return f(&mut comp);
+    }
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Not a file path: {}", path.display()),
+        )
+    })?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    let written = create(&temp_path).and_then(|file| {
+        let mut comp = CompoundFile::create(file)?;
+        let result = f(&mut comp)?;
+        comp.flush()?;
+        comp.into_inner().sync_all()?;
+        Ok(result)
+    });
+    let result = written.and_then(|result| {
+        std::fs::rename(&temp_path, path)?;
+        Ok(result)
+    });
+    match result {
+        Ok(result) => {
+            sync_parent_dir(path);
+            Ok(result)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Makes the rename durable, only possible on unix
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+/// Like [`read()`] but also returns how long reading every part of the table took
+#[cfg(feature = "profiling")]
+pub fn read_profiled(path: &PathBuf) -> io::Result<(VPX, Profile)> {
+    let mut profiler = Profiler::default();
+    let mut comp = profiler.time("open", || CompoundFile::open_strict(File::open(path)?))?;
+    let vpx = read_vpx_profiled(&mut comp, &mut profiler)?;
+    Ok((vpx, profiler.finish()))
+}
+
+/// Like [`write_with_options()`] but returns how long writing every part of the table took
+#[cfg(feature = "profiling")]
+pub fn write_profiled<P: AsRef<Path>>(
+    path: P,
+    vpx: &VPX,
+    options: &WriteOptions,
+) -> io::Result<Profile> {
+    write_compound_file(path.as_ref(), options.atomic, |comp| {
+        let mut profiler = Profiler::default();
+        write_vpx_profiled(comp, vpx, options, &mut profiler)?;
+        profiler.time("flush", || comp.flush())?;
+        Ok(profiler.finish())
+    })
 }
 
 fn read_vpx<F: Read + Write + Seek>(comp: &mut CompoundFile<F>) -> io::Result<VPX> {
-    let custominfotags = read_custominfotags(comp)?;
-    let info = read_tableinfo(comp)?;
-    let version = read_version(comp)?;
-    let gamedata = read_gamedata(comp, &version)?;
-    let gameitems = read_gameitems(comp, &gamedata)?;
-    let images = read_images(comp, &gamedata)?;
-    let sounds = read_sounds(comp, &gamedata, &version)?;
-    let fonts = read_fonts(comp, &gamedata)?;
-    let collections = read_collections(comp, &gamedata)?;
+    read_vpx_profiled(comp, &mut Profiler::default())
+}
+
+fn read_vpx_profiled<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+    profiler: &mut Profiler,
+) -> io::Result<VPX> {
+    let custominfotags = profiler.time("custominfotags", || read_custominfotags(comp))?;
+    let info = profiler.time("tableinfo", || read_tableinfo(comp))?;
+    let version = profiler.time("version", || read_version(comp))?;
+    let gamedata = profiler.time("gamedata", || read_gamedata(comp, &version))?;
+    let gameitems = profiler.time("gameitems", || read_gameitems(comp, &gamedata))?;
+    let images = profiler.time("images", || read_images(comp, &gamedata))?;
+    let sounds = profiler.time("sounds", || read_sounds(comp, &gamedata, &version))?;
+    let fonts = profiler.time("fonts", || read_fonts(comp, &gamedata))?;
+    let collections = profiler.time("collections", || read_collections(comp, &gamedata))?;
+    let extra_streams = profiler.time("extra_streams", || read_extra_streams(comp))?;
     Ok(VPX {
         custominfotags,
         info,
@@ -353,25 +794,143 @@ fn read_vpx<F: Read + Write + Seek>(comp: &mut CompoundFile<F>) -> io::Result<VP
         sounds,
         fonts,
         collections,
+        extra_streams,
+        changelog: ModificationLog::default(),
     })
 }
 
-fn write_vpx<F: Read + Write + Seek>(comp: &mut CompoundFile<F>, vpx: &VPX) -> io::Result<()> {
+fn write_vpx<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+    vpx: &VPX,
+    options: &WriteOptions,
+) -> io::Result<()> {
+    write_vpx_profiled(comp, vpx, options, &mut Profiler::default())
+}
+
+fn write_vpx_profiled<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+    vpx: &VPX,
+    options: &WriteOptions,
+    profiler: &mut Profiler,
+) -> io::Result<()> {
     create_game_storage(comp)?;
-    write_custominfotags(comp, &vpx.custominfotags)?;
-    write_tableinfo(comp, &vpx.info)?;
-    write_version(comp, &vpx.version)?;
-    write_game_data(comp, &vpx.gamedata, &vpx.version)?;
-    write_game_items(comp, &vpx.gameitems)?;
-    write_images(comp, &vpx.images)?;
-    write_sounds(comp, &vpx.sounds, &vpx.version)?;
-    write_fonts(comp, &vpx.fonts)?;
-    write_collections(comp, &vpx.collections)?;
-    let mac = generate_mac(comp)?;
-    write_mac(comp, &mac)
+    profiler.time("custominfotags", || {
+        write_custominfotags(comp, &vpx.custominfotags)
+    })?;
+    profiler.time("tableinfo", || write_tableinfo(comp, &vpx.info))?;
+    profiler.time("version", || write_version(comp, &vpx.version))?;
+    let synced = options.sync_counts.then(|| synced_counts(vpx)).flatten();
+    let gamedata = synced.as_ref().unwrap_or(&vpx.gamedata);
+    profiler.time("gamedata", || write_game_data(comp, gamedata, &vpx.version))?;
+    profiler.time("gameitems", || write_game_items(comp, &vpx.gameitems))?;
+    profiler.time("images", || write_images(comp, &vpx.images))?;
+    profiler.time("sounds", || write_sounds(comp, &vpx.sounds, &vpx.version))?;
+    profiler.time("fonts", || write_fonts(comp, &vpx.fonts))?;
+    profiler.time("collections", || write_collections(comp, &vpx.collections))?;
+    profiler.time("mac", || {
+        let mac = generate_mac(comp)?;
+        write_mac(comp, &mac)
+    })?;
+    // not part of the mac, Visual Pinball ignores these streams
+    if options.preserve_extra_streams {
+        profiler.time("extra_streams", || {
+            write_extra_streams(comp, &vpx.extra_streams)
+        })?;
+    }
+    Ok(())
+}
+
+/// A copy of the game data with the counts of the vectors, `None` if they already match
+fn synced_counts(vpx: &VPX) -> Option<GameData> {
+    let counts = [
+        vpx.gameitems.len(),
+        vpx.images.len(),
+        vpx.sounds.len(),
+        vpx.fonts.len(),
+        vpx.collections.len(),
+    ]
+    .map(|len| len as u32);
+    let gamedata = &vpx.gamedata;
+    let current = [
+        gamedata.gameitems_size,
+        gamedata.images_size,
+        gamedata.sounds_size,
+        gamedata.fonts_size,
+        gamedata.collections_size,
+    ];
+    if counts == current {
+        return None;
+    }
+    let mut gamedata = gamedata.clone();
+    [
+        gamedata.gameitems_size,
+        gamedata.images_size,
+        gamedata.sounds_size,
+        gamedata.fonts_size,
+        gamedata.collections_size,
+    ] = counts;
+    Some(gamedata)
+}
+
+/// Streams outside `TableInfo` that are not written by [`write_vpx`]
+fn read_extra_streams<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<ExtraStream>> {
+    let known_indexed = Regex::new(r"^(GameItem|Image|Sound|Font|Collection)\d+$").unwrap();
+    let paths: Vec<String> = comp
+        .walk()
+        .filter(|entry| entry.is_stream())
+        .map(|entry| {
+            entry
+                .path()
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .filter(|path| {
+            let parts: Vec<&str> = path.split('/').collect();
+            match parts[..] {
+                ["TableInfo", _] => false,
+                ["GameStg", "Version" | "GameData" | "CustomInfoTags" | "MAC"] => false,
+                ["GameStg", name] => !known_indexed.is_match(name),
+                _ => true,
+            }
+        })
+        .collect();
+    paths
+        .into_iter()
+        .map(|path| {
+            let mut data = Vec::new();
+            let stream_path = Path::new(MAIN_SEPARATOR_STR).join(&path);
+            comp.open_stream(&stream_path)?.read_to_end(&mut data)?;
+            Ok(ExtraStream { path, data })
+        })
+        .collect()
+}
+
+fn write_extra_streams<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+    extra_streams: &[ExtraStream],
+) -> io::Result<()> {
+    for extra_stream in extra_streams {
+        let mut path = Path::new(MAIN_SEPARATOR_STR).to_path_buf();
+        let parts: Vec<&str> = extra_stream.path.split('/').collect();
+        for storage in &parts[..parts.len() - 1] {
+            path = path.join(storage);
+            if !comp.is_storage(&path) {
+                comp.create_storage(&path)?;
+            }
+        }
+        let path = path.join(parts[parts.len() - 1]);
+        comp.create_new_stream(&path)?
+            .write_all(&extra_stream.data)?;
+    }
+    Ok(())
 }
 
-/// Writes a minimal `vpx` file
+/// Writes a minimal `vpx` file, see [`VpxBuilder`] for creating a table with contents
 pub fn new_minimal_vpx<P: AsRef<Path>>(vpx_file_path: P) -> io::Result<()> {
     let file = File::options()
         .read(true)
@@ -420,6 +979,7 @@ pub fn extractvbs(
         let mut comp = cfb::open(vpx_file_path)?;
         let version = read_version(&mut comp)?;
         let gamedata = read_gamedata(&mut comp, &version)?;
+        protection::check_script_readable(&gamedata)?;
         extract_script(&gamedata, &script_path)?;
         Ok(ExtractResult::Extracted(script_path))
     } else {
@@ -435,6 +995,16 @@ pub fn extractvbs(
 ///
 /// see also [extractvbs]
 pub fn importvbs(vpx_file_path: &PathBuf, vbs_file_path: Option<PathBuf>) -> io::Result<PathBuf> {
+    importvbs_with_backup(vpx_file_path, vbs_file_path, None)
+}
+
+/// Like [`importvbs`] but first backs up the `vpx` file when a backup policy is given,
+/// see [`backup::snapshot_with_policy`]
+pub fn importvbs_with_backup(
+    vpx_file_path: &PathBuf,
+    vbs_file_path: Option<PathBuf>,
+    backup: Option<&BackupPolicy>,
+) -> io::Result<PathBuf> {
     let script_path = match vbs_file_path {
         Some(vbs_file_path) => vbs_file_path,
         None => vbs_path_for(vpx_file_path),
@@ -445,6 +1015,9 @@ pub fn importvbs(vpx_file_path: &PathBuf, vbs_file_path: Option<PathBuf>) -> io:
             format!("Script file not found: {}", script_path.display()),
         ));
     }
+    if let Some(policy) = backup {
+        backup::snapshot_with_policy(vpx_file_path, policy)?;
+    }
     let mut comp = cfb::open_rw(vpx_file_path)?;
     let version = read_version(&mut comp)?;
     let mut gamedata = read_gamedata(&mut comp, &version)?;
@@ -458,31 +1031,217 @@ pub fn importvbs(vpx_file_path: &PathBuf, vbs_file_path: Option<PathBuf>) -> io:
 }
 
 /// Verifies the MAC signature of a VPX file
+///
+/// On a mismatch the result contains the hashes of all signed streams, see [`MacMismatch`].
 pub fn verify(vpx_file_path: &PathBuf) -> VerifyResult {
     let result = move || -> io::Result<_> {
         let mut comp = cfb::open(vpx_file_path)?;
         let mac = read_mac(&mut comp)?;
-        let generated_mac = generate_mac(&mut comp)?;
-        Ok((mac, generated_mac))
+        let contributions = mac_contributions(&mut comp)?;
+        Ok((mac, contributions))
     }();
     match result {
-        Ok((mac, generated_mac)) => {
+        Ok((mac, contributions)) => {
+            let generated_mac = mac_from_contributions(&contributions);
             if mac == generated_mac {
                 VerifyResult::Ok(vpx_file_path.clone())
             } else {
                 VerifyResult::Failed(
                     vpx_file_path.clone(),
-                    format!("MAC mismatch: {:?} != {:?}", mac, generated_mac),
+                    VerifyError::MacMismatch(MacMismatch {
+                        expected: mac,
+                        computed: generated_mac,
+                        streams: hash_contributions(&contributions),
+                    }),
                 )
             }
         }
         Err(e) => VerifyResult::Failed(
             vpx_file_path.clone(),
-            format!("Failed to read VPX file {}: {}", vpx_file_path.display(), e),
+            VerifyError::Read(format!(
+                "Failed to read VPX file {}: {}",
+                vpx_file_path.display(),
+                e
+            )),
         ),
     }
 }
 
+/// Recomputes the MAC of an existing file and rewrites only the MAC stream, for example after
+/// streams were edited with another tool.
+///
+/// Before signing the file is checked to be structurally valid: the game data can be read,
+/// its counts match the streams in the file and all BIFF streams end with an `ENDB` record.
+/// A file that fails these checks is left untouched and an [`io::ErrorKind::InvalidData`]
+/// error is returned, see [`repair`] for fixing these problems.
+///
+/// Returns `true` if the MAC was changed.
+pub fn resign<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    let mut comp = cfb::open_rw(path)?;
+    check_structure(&mut comp)?;
+    let generated_mac = generate_mac(&mut comp)?;
+    if read_mac(&mut comp).is_ok_and(|mac| mac == generated_mac) {
+        return Ok(false);
+    }
+    write_mac(&mut comp, &generated_mac)?;
+    comp.flush()?;
+    Ok(true)
+}
+
+fn check_structure<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let version = read_version(comp)?;
+    let game_data_path = Path::new(MAIN_SEPARATOR_STR)
+        .join("GameStg")
+        .join("GameData");
+    let game_data = read_bytes_at(&game_data_path, comp)?;
+    let gamedata = gamedata::try_read_all_gamedata_records(&game_data, &version)
+        .map_err(|e| invalid(format!("GameData can not be read: {}", e)))?;
+    for (name, prefix, count) in [
+        ("gameitems", "GameItem", gamedata.gameitems_size),
+        ("sounds", "Sound", gamedata.sounds_size),
+        ("images", "Image", gamedata.images_size),
+        ("fonts", "Font", gamedata.fonts_size),
+        ("collections", "Collection", gamedata.collections_size),
+    ] {
+        let actual = repair::count_streams(comp, prefix);
+        if actual != count {
+            return Err(invalid(format!(
+                "GameData {} count is {} but the file contains {} streams",
+                name, count, actual
+            )));
+        }
+    }
+    let paths: Vec<PathBuf> = comp
+        .walk()
+        .filter(|entry| entry.is_stream())
+        .map(|entry| entry.path().to_path_buf())
+        .filter(|path| biff::is_biff_stream(path))
+        .collect();
+    for path in paths {
+        let data = read_bytes_at(&path, comp)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        // game items start with a 32 bit item type
+        let skip = if name.starts_with("GameItem") { 4 } else { 0 };
+        let biff = data.get(skip..).unwrap_or_default();
+        match biff::biff_data_len(biff) {
+            Ok(len) if skip + len == data.len() => {}
+            _ => {
+                return Err(invalid(format!(
+                    "{} is not a valid BIFF stream",
+                    path.display()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Hashes of all streams that are signed by the MAC, in signing order
+pub fn stream_hashes<P: AsRef<Path>>(vpx_file_path: P) -> io::Result<Vec<StreamHash>> {
+    let mut comp = cfb::open(vpx_file_path)?;
+    let contributions = mac_contributions(&mut comp)?;
+    Ok(hash_contributions(&contributions))
+}
+
+fn hash_contributions(contributions: &[(String, Vec<u8>)]) -> Vec<StreamHash> {
+    contributions
+        .iter()
+        .map(|(path, bytes)| StreamHash {
+            path: path.clone(),
+            hash: Md2::digest(bytes).to_vec(),
+        })
+        .collect()
+}
+
+/// Computes a hash of the table contents, formatted as hex.
+///
+/// Fields that change on every save, like the save date, save revision and the MAC, are not
+/// part of the hash. This makes it possible to find the same table saved under a different
+/// file name. Saving with another Visual Pinball version can change the fingerprint.
+pub fn fingerprint(vpx: &VPX) -> String {
+    let mut hasher = Fnv64::new();
+    let info = &vpx.info;
+    for value in [
+        &info.table_name,
+        &info.author_name,
+        &info.table_version,
+        &info.release_date,
+        &info.author_email,
+        &info.author_website,
+        &info.table_blurb,
+        &info.table_description,
+        &info.table_rules,
+    ] {
+        hasher.update_option(value.as_ref().map(String::as_bytes));
+    }
+    hasher.update_option(info.screenshot.as_deref());
+    for name in &vpx.custominfotags {
+        hasher.update(name.as_bytes());
+        hasher.update_option(info.properties.get(name).map(String::as_bytes));
+    }
+    hasher.update(&gamedata::write_all_gamedata_records(
+        &vpx.gamedata,
+        &vpx.version,
+    ));
+    for gameitem in &vpx.gameitems {
+        hasher.update(&gameitem::write(gameitem));
+    }
+    for image in &vpx.images {
+        let mut writer = BiffWriter::new();
+        image.biff_write(&mut writer);
+        hasher.update(writer.get_data());
+    }
+    for sound in &vpx.sounds {
+        let mut writer = BiffWriter::new();
+        sound::write(&vpx.version, sound, &mut writer);
+        hasher.update(writer.get_data());
+    }
+    for font in &vpx.fonts {
+        hasher.update(&font::write(font));
+    }
+    for collection in &vpx.collections {
+        hasher.update(&collection::write(collection));
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// 64 bit FNV-1a, stable across platforms and Rust versions unlike `DefaultHasher`
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Fnv64(0xcbf29ce484222325)
+    }
+
+    /// The length is hashed as well so that moving bytes between fields changes the hash
+    fn update(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn update_option(&mut self, bytes: Option<&[u8]>) {
+        match bytes {
+            Some(bytes) => {
+                self.write(&[1]);
+                self.update(bytes);
+            }
+            None => self.write(&[0]),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 /// Returns the path to the sidecar script for a given `vpx` file
 pub fn vbs_path_for(vpx_file_path: &PathBuf) -> PathBuf {
     path_for(vpx_file_path, "vbs")
@@ -537,6 +1296,24 @@ impl FileStructureItem {
 }
 
 fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8>> {
+    let contributions = mac_contributions(comp)?;
+    Ok(mac_from_contributions(&contributions))
+}
+
+fn mac_from_contributions(contributions: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut hasher = Md2::new();
+    // header is always there.
+    hasher.update(b"Visual Pinball");
+    for (_, bytes) in contributions {
+        hasher.update(bytes);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// The bytes that are hashed for the MAC per stream, in hashing order
+fn mac_contributions<F: Read + Seek>(
+    comp: &mut CompoundFile<F>,
+) -> io::Result<Vec<(String, Vec<u8>)>> {
     // Regarding mac generation, see
     //  https://github.com/freezy/VisualPinball.Engine/blob/ec1e9765cd4832c134e889d6e6d03320bc404bd5/VisualPinball.Engine/VPT/Table/TableWriter.cs#L42
     //  https://github.com/vbousquet/vpx_lightmapper/blob/ca5fddd4c2a0fbe817fd546c5f4db609f9d0da9f/addons/vpx_lightmapper/vlm_export.py#L906-L913
@@ -596,10 +1373,7 @@ fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8
     //append_structure(&mut file_structure, comp, "GameStg/Font", Biff, false);
     append_structure(&mut file_structure, comp, "GameStg/Collection", Biff, true);
 
-    let mut hasher = Md2::new();
-
-    // header is always there.
-    hasher.update(b"Visual Pinball");
+    let mut contributions = Vec::new();
 
     for item in file_structure {
         if !item.hashed {
@@ -611,9 +1385,10 @@ fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8
         match item.file_type {
             UnstructuredBytes => {
                 let bytes = read_bytes_at(&item.path, comp)?;
-                hasher.update(&bytes);
+                contributions.push((item.path.to_string_lossy().to_string(), bytes));
             }
             Biff => {
+                let mut hashed = Vec::new();
                 // println!("reading biff: {:?}", item.path);
                 let bytes = read_bytes_at(&item.path, comp)?;
                 let mut biff = BiffReader::new(&bytes);
@@ -629,19 +1404,20 @@ fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8
                     match tag_str {
                         "CODE" => {
                             //  For some reason, the code length info is not hashed, just the tag and code string
-                            hasher.update(b"CODE");
+                            hashed.extend_from_slice(b"CODE");
                             // code is a special case, it indicates a length of 4 (only the tag)
                             // so already 0 bytes remaining
                             let code_length = biff.get_u32_no_remaining_update();
                             let code = biff.get_no_remaining_update(code_length as usize);
-                            hasher.update(code);
+                            hashed.extend_from_slice(code);
                         }
                         _other => {
                             // Biff tags and data are hashed but not their size
-                            hasher.update(biff.get_record_data(true));
+                            hashed.extend_from_slice(&biff.get_record_data(true));
                         }
                     }
                 }
+                contributions.push((item.path.to_string_lossy().to_string(), hashed));
             }
         }
 
@@ -660,7 +1436,7 @@ fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8
                     let path = format!("TableInfo/{}", cust_name);
                     if comp.exists(&path) {
                         let data = read_bytes_at(&path, comp)?;
-                        hasher.update(&data);
+                        contributions.push((path, data));
                     }
                 } else {
                     biff.skip_tag();
@@ -668,8 +1444,7 @@ fn generate_mac<F: Read + Seek>(comp: &mut CompoundFile<F>) -> io::Result<Vec<u8
             }
         }
     }
-    let result = hasher.finalize();
-    Ok(result.to_vec())
+    Ok(contributions)
 }
 
 // TODO this is not very efficient as we copy the bytes around a lot
@@ -681,8 +1456,7 @@ fn read_bytes_at<F: Read + Seek, P: AsRef<Path>>(
     let mut bytes = Vec::new();
     let mut stream = comp.open_stream(&path)?;
     stream.read_to_end(&mut bytes).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
+        io::Error::other(
             format!("Failed to read bytes at {:?}, this might be because the file is open in write only mode. {}", path.as_ref(), e),
         )
     })?;
@@ -730,7 +1504,7 @@ fn read_gameitems<F: Read + Seek>(
     gamedata: &GameData,
 ) -> io::Result<Vec<GameItemEnum>> {
     let gamestg = Path::new(MAIN_SEPARATOR_STR).join("GameStg");
-    (0..gamedata.gameitems_size)
+    let gameitems = (0..gamedata.gameitems_size)
         .map(|index| {
             let path = gamestg.join(format!("GameItem{}", index));
             let mut input = Vec::new();
@@ -739,6 +1513,30 @@ fn read_gameitems<F: Read + Seek>(
             let game_item = gameitem::read(&input);
             Ok(game_item)
         })
+        .collect::<io::Result<Vec<GameItemEnum>>>()?;
+    let unknown = gameitem::unknown_item_types(&gameitems);
+    if !unknown.is_empty() {
+        eprintln!(
+            "Warning: unknown game item types {:?}, they will be written back unchanged",
+            unknown
+        );
+    }
+    Ok(gameitems)
+}
+
+fn read_gameitems_forgiving<F: Read + Seek>(
+    comp: &mut CompoundFile<F>,
+    gamedata: &GameData,
+) -> io::Result<Vec<GameItemEnum>> {
+    let gamestg = Path::new(MAIN_SEPARATOR_STR).join("GameStg");
+    (0..gamedata.gameitems_size)
+        .map(|index| {
+            let path = gamestg.join(format!("GameItem{}", index));
+            let mut input = Vec::new();
+            let mut stream = comp.open_stream(&path)?;
+            stream.read_to_end(&mut input)?;
+            Ok(gameitem::read_forgiving(index, &input))
+        })
         .collect()
 }
 
@@ -882,6 +1680,11 @@ fn images_to_webp<F: Read + Write + Seek>(
         let mut image_data = read_image(comp, index)?;
         match image_data.ext().to_lowercase().as_str() {
             "png" => {
+                if image_data.animation().is_some() {
+                    // the conversion would only keep the first frame
+                    println!("Skipping animated image {}", image_data.name);
+                    continue;
+                }
                 // convert the image to webp
                 image_data.change_extension("webp");
                 if let Some(jpeg) = &mut image_data.jpeg {
@@ -902,7 +1705,7 @@ fn images_to_webp<F: Read + Write + Seek>(
                     // should be lossless according to the docs
                     dynamic_image
                         .write_to(&mut cursor, ImageFormat::WebP)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+                        .map_err(|e| io::Error::other(e.to_string()))?;
                     jpeg.data = webp;
                     write_image(comp, index as usize, &image_data)?;
                     conversions.push(ImageToWebpConversion {
@@ -930,7 +1733,7 @@ fn images_to_webp<F: Read + Write + Seek>(
                     // should be lossless according to the docs
                     dynamic_image
                         .write_to(&mut cursor, ImageFormat::WebP)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+                        .map_err(|e| io::Error::other(e.to_string()))?;
                     let jpg = ImageDataJpeg {
                         path: image_data.path.clone(),
                         name: image_data.name.clone(),
@@ -1111,6 +1914,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn read_gameitems_forgiving_corrupt_item() -> io::Result<()> {
+        let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+        let mut comp = cfb::open(path)?;
+        let version = read_version(&mut comp)?;
+        let gamedata = read_gamedata(&mut comp, &version)?;
+        let original = read_gameitems(&mut comp, &gamedata)?;
+
+        let buff = Cursor::new(vec![0; 15]);
+        let mut comp = CompoundFile::create(buff)?;
+        create_game_storage(&mut comp)?;
+        write_game_items(&mut comp, &original)?;
+        // truncate the second item
+        let corrupt_path = Path::new(MAIN_SEPARATOR_STR)
+            .join("GameStg")
+            .join("GameItem1");
+        let data = gameitem::write(&original[1]);
+        comp.create_stream(&corrupt_path)?
+            .write_all(&data[..data.len() / 2])?;
+
+        let read = read_gameitems_forgiving(&mut comp, &gamedata)?;
+
+        assert_eq!(read.len(), original.len());
+        assert_eq!(read[0], original[0]);
+        assert!(matches!(read[1], GameItemEnum::Corrupt(1, _, _)));
+        assert_eq!(read[2..], original[2..]);
+        Ok(())
+    }
+
     #[test]
     fn read() -> io::Result<()> {
         let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
@@ -1156,6 +1988,179 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn preserve_extra_streams() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let source_path = dir.join("source.vpx");
+        let mut comp = cfb::create(&source_path)?;
+        write_minimal_vpx(&mut comp)?;
+        comp.create_new_stream("/GameStg/ToolData")?
+            .write_all(&[1, 2, 3])?;
+        comp.create_storage("/PinUP")?;
+        comp.create_new_stream("/PinUP/Settings")?
+            .write_all(b"video")?;
+        comp.flush()?;
+        drop(comp);
+
+        let vpx = super::read(&source_path)?;
+        let expected = vec![
+            ExtraStream {
+                path: "GameStg/ToolData".to_string(),
+                data: vec![1, 2, 3],
+            },
+            ExtraStream {
+                path: "PinUP/Settings".to_string(),
+                data: b"video".to_vec(),
+            },
+        ];
+        let mut extra_streams = vpx.extra_streams.clone();
+        extra_streams.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(extra_streams, expected);
+
+        let written_path = dir.join("written.vpx");
+        write(&written_path, &vpx)?;
+        assert_eq!(super::read(&written_path)?.extra_streams, vpx.extra_streams);
+        assert!(matches!(verify(&written_path), VerifyResult::Ok(_)));
+
+        let stripped_path = dir.join("stripped.vpx");
+        let options = WriteOptions {
+            preserve_extra_streams: false,
+            ..Default::default()
+        };
+        write_with_options(&stripped_path, &vpx, &options)?;
+        assert_eq!(super::read(&stripped_path)?.extra_streams, vec![]);
+
+        let blank = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+        assert_eq!(super::read(&blank)?.extra_streams, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn write_syncs_counts() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let mut vpx = VPX::default();
+        vpx.gameitems.push(GameItemEnum::Wall(Default::default()));
+        vpx.images.push(ImageData::default());
+        vpx.collections.push(Collection {
+            name: "Group".to_string(),
+            items: vec![],
+            fire_events: false,
+            stop_single_events: false,
+            group_elements: true,
+        });
+        vpx.gamedata.fonts_size = 3;
+
+        let path = dir.join("synced.vpx");
+        write(&path, &vpx)?;
+        let read = super::read(&path)?;
+        assert_eq!(read.gamedata.gameitems_size, 1);
+        assert_eq!(read.gamedata.images_size, 1);
+        assert_eq!(read.gamedata.fonts_size, 0);
+        assert_eq!(read.gamedata.collections_size, 1);
+        assert_eq!(read.gameitems.len(), 1);
+        assert_eq!(read.collections.len(), 1);
+        assert!(matches!(verify(&path), VerifyResult::Ok(_)));
+
+        // without syncing the counts of the game data are written as they are
+        let unsynced_path = dir.join("unsynced.vpx");
+        let options = WriteOptions {
+            sync_counts: false,
+            ..Default::default()
+        };
+        vpx.gamedata.fonts_size = 0;
+        write_with_options(&unsynced_path, &vpx, &options)?;
+        let read = super::read(&unsynced_path)?;
+        assert_eq!(read.gamedata.gameitems_size, 0);
+        assert!(read.gameitems.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_write() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let path = dir.join("table.vpx");
+        write(&path, &VPX::default())?;
+        let mut vpx = super::read(&path)?;
+        vpx.info.table_name = Some("Atomic".to_string());
+        let options = WriteOptions {
+            atomic: true,
+            ..Default::default()
+        };
+        write_with_options(&path, &vpx, &options)?;
+        assert_eq!(super::read(&path)?, vpx);
+        assert!(matches!(verify(&path), VerifyResult::Ok(_)));
+
+        // renaming on top of a directory fails, the temporary file is cleaned up
+        let dir_path = dir.join("dir.vpx");
+        std::fs::create_dir(&dir_path)?;
+        assert!(write_with_options(&dir_path, &vpx, &options).is_err());
+        let mut names: Vec<_> = std::fs::read_dir(&dir)?
+            .map(|entry| entry.map(|e| e.file_name()))
+            .collect::<io::Result<_>>()?;
+        names.sort();
+        assert_eq!(names, vec!["dir.vpx", "table.vpx"]);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_item_types_round_trip() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let path = dir.join("future.vpx");
+        let mut vpx = VPX::default();
+        vpx.add_game_item(GameItemEnum::Generic(
+            42,
+            gameitem::generic::Generic {
+                name: "Future".to_string(),
+                data: {
+                    let mut writer = BiffWriter::new();
+                    writer.write_tagged_wide_string("NAME", "Future");
+                    writer.write_tagged_data("FUTR", &[1, 2, 3, 4]);
+                    writer.close(true);
+                    writer.get_data().to_vec()
+                },
+            },
+        ));
+        write(&path, &vpx)?;
+        let read_vpx = super::read(&path)?;
+        assert_eq!(read_vpx.unknown_item_types(), vec![42]);
+        assert_eq!(read_vpx, vpx);
+        Ok(())
+    }
+
+    #[test]
+    fn read_only_handles() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let path = dir.join("test.vpx");
+        write(&path, &VPX::default())?;
+
+        let mut vpx = open_read_only(&path)?;
+        assert!(vpx.is_read_only());
+        let error = vpx.images_to_webp().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            error.to_string(),
+            "Can not convert images to webp, the vpx file is opened read only, use open_read_write instead"
+        );
+
+        assert!(!open_read_write(&path)?.is_read_only());
+        // opening a writable reader keeps it writable
+        let inner = std::io::Cursor::new(std::fs::read(&path)?);
+        assert!(!VpxFile::open(inner)?.is_read_only());
+
+        let mut permissions = path.metadata()?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions)?;
+        let error = open_read_write(&path).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            error.to_string(),
+            format!("{} is write protected", path.display())
+        );
+        // the read only handle still works
+        assert_eq!(open(&path)?.read_version()?, VPX::default().version);
+        Ok(())
+    }
+
     #[test]
     fn images_to_webp_and_compact() -> io::Result<()> {
         let dir: PathBuf = testdir!();
@@ -1233,6 +2238,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_importvbs_with_backup() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let test_vpx_path = dir.join("test.vpx");
+        write(&test_vpx_path, &VPX::default())?;
+        let original = std::fs::read(&test_vpx_path)?;
+        std::fs::write(vbs_path_for(&test_vpx_path), "Option Explicit\r\n")?;
+        let policy = BackupPolicy::default();
+
+        importvbs_with_backup(&test_vpx_path, None, Some(&policy))?;
+
+        let backups = backup::list(&test_vpx_path, &policy)?;
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read(&backups[0])?, original);
+        assert_eq!(
+            super::read(&test_vpx_path)?.gamedata.code.string,
+            "Option Explicit\r\n"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_extractvbs_empty_file() {
         let dir: PathBuf = testdir!();
@@ -1261,12 +2287,116 @@ mod tests {
             result,
             VerifyResult::Failed(
                 test_vpx_path.clone(),
-                format!(
+                VerifyError::Read(format!(
                     "Failed to read VPX file {}: Invalid CFB file (0 bytes is too small)",
                     test_vpx_path.display()
-                )
+                ))
             ),
         );
         assert!(!script_path.exists());
     }
+
+    #[test]
+    fn test_verify_mac_mismatch() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let test_vpx_path = dir.join("test.vpx");
+        new_minimal_vpx(&test_vpx_path)?;
+        let reference = stream_hashes(&test_vpx_path)?;
+        assert_eq!(
+            verify(&test_vpx_path),
+            VerifyResult::Ok(test_vpx_path.clone())
+        );
+
+        let mut comp = cfb::open_rw(&test_vpx_path)?;
+        comp.create_new_stream("/TableInfo/TableName")?
+            .write_all(&[b'X', 0])?;
+        comp.flush()?;
+        drop(comp);
+
+        let VerifyResult::Failed(_, VerifyError::MacMismatch(mismatch)) = verify(&test_vpx_path)
+        else {
+            panic!("expected a MAC mismatch");
+        };
+        assert_ne!(mismatch.expected, mismatch.computed);
+        assert_eq!(
+            mismatch.differing_streams(&reference),
+            vec!["TableInfo/TableName".to_string()]
+        );
+
+        repair::regenerate_mac(&test_vpx_path)?;
+        assert_eq!(
+            verify(&test_vpx_path),
+            VerifyResult::Ok(test_vpx_path.clone())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resign() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let test_vpx_path = dir.join("test.vpx");
+        std::fs::copy("testdata/completely_blank_table_10_7_4.vpx", &test_vpx_path)?;
+        assert!(!resign(&test_vpx_path)?);
+
+        let mut comp = cfb::open_rw(&test_vpx_path)?;
+        comp.create_stream("/TableInfo/TableName")?
+            .write_all(&[b'X', 0])?;
+        comp.flush()?;
+        drop(comp);
+        assert!(resign(&test_vpx_path)?);
+        assert_eq!(
+            verify(&test_vpx_path),
+            VerifyResult::Ok(test_vpx_path.clone())
+        );
+
+        let mut comp = cfb::open_rw(&test_vpx_path)?;
+        let mut stream = comp.open_stream("/GameStg/GameItem0")?;
+        stream.seek(io::SeekFrom::End(0))?;
+        stream.write_all(&[1, 2, 3])?;
+        drop(stream);
+        comp.flush()?;
+        drop(comp);
+        let damaged = std::fs::read(&test_vpx_path)?;
+        let error = resign(&test_vpx_path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(std::fs::read(&test_vpx_path)?, damaged);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_save_info() -> io::Result<()> {
+        let dir: PathBuf = testdir!();
+        let mut vpx =
+            crate::vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx"))?;
+        let original = fingerprint(&vpx);
+        assert_eq!(original.len(), 16);
+
+        vpx.info.table_save_date = Some("Sun Jan 01 00:00:00 2023".to_string());
+        vpx.info.table_save_rev = Some("42".to_string());
+        assert_eq!(fingerprint(&vpx), original);
+
+        let renamed = dir.join("renamed.vpx");
+        crate::vpx::write(&renamed, &vpx)?;
+        assert_eq!(fingerprint(&crate::vpx::read(&renamed)?), original);
+
+        vpx.set_script("' changed".to_string());
+        assert_ne!(fingerprint(&vpx), original);
+
+        let changed = fingerprint(&vpx);
+        vpx.add_game_item(GameItemEnum::Generic(
+            99,
+            gameitem::generic::Generic {
+                name: "Unknown".to_string(),
+                data: {
+                    let mut writer = BiffWriter::new();
+                    writer.write_tagged_wide_string("NAME", "Unknown");
+                    writer.write_tagged_data("ABCD", &[1, 2, 3]);
+                    writer.close(true);
+                    writer.get_data().to_vec()
+                },
+            },
+        ));
+        assert_ne!(fingerprint(&vpx), changed);
+        Ok(())
+    }
 }
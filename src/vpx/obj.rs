@@ -1,5 +1,6 @@
 //! Wavefront OBJ file reader and writer
 
+use crate::vpx::math::canonical_f64;
 use crate::vpx::model::Vertex3dNoTex2;
 use std::error::Error;
 use std::fs::File;
@@ -92,9 +93,9 @@ pub(crate) fn write_obj(
     // write all vertices to the wavefront obj file
     for (_, vertex) in vertices {
         let vertex = Entity::Vertex {
-            x: vertex.x as f64,
-            y: vertex.y as f64,
-            z: vertex.z as f64,
+            x: canonical_f64(vertex.x),
+            y: canonical_f64(vertex.y),
+            z: canonical_f64(vertex.z),
             w: None,
         };
         obj_writer.write(&mut writer, &vertex)?;
@@ -102,8 +103,8 @@ pub(crate) fn write_obj(
     // write all vertex texture coordinates to the wavefront obj file
     for (_, vertex) in vertices {
         let vertex = Entity::VertexTexture {
-            u: vertex.tu as f64,
-            v: Some(vertex.tv as f64),
+            u: canonical_f64(vertex.tu),
+            v: Some(canonical_f64(vertex.tv)),
             w: None,
         };
         obj_writer.write(&mut writer, &vertex)?;
@@ -122,17 +123,17 @@ pub(crate) fn write_obj(
             x: if vertex.nx.is_nan() {
                 0.0
             } else {
-                vertex.nx as f64
+                canonical_f64(vertex.nx)
             },
             y: if vertex.ny.is_nan() {
                 0.0
             } else {
-                vertex.ny as f64
+                canonical_f64(vertex.ny)
             },
             z: if vertex.nz.is_nan() {
                 0.0
             } else {
-                vertex.nz as f64
+                canonical_f64(vertex.nz)
             },
         };
         obj_writer.write(&mut writer, &vertex)?;
@@ -167,6 +168,7 @@ pub(crate) fn read_obj_file(obj_file_path: &PathBuf) -> Result<ObjData, Box<dyn
 
 pub(crate) fn read_obj<R: BufRead>(mut reader: &mut R) -> Result<ObjData, Box<dyn Error>> {
     let mut indices: Vec<i64> = Vec::new();
+    let mut face_vertices: Vec<FaceVertexIndices> = Vec::new();
     let mut vertices: Vec<(f64, f64, f64, Option<f64>)> = Vec::new();
     let mut texture_coordinates: Vec<(f64, Option<f64>, Option<f64>)> = Vec::new();
     let mut normals: Vec<ObjNormal> = Vec::new();
@@ -199,6 +201,13 @@ pub(crate) fn read_obj<R: BufRead>(mut reader: &mut R) -> Result<ObjData, Box<dy
                 indices.push(vertices[0].vertex - 1);
                 indices.push(vertices[1].vertex - 1);
                 indices.push(vertices[2].vertex - 1);
+                for corner in &vertices[..3] {
+                    face_vertices.push((
+                        corner.vertex - 1,
+                        corner.texture.map(|t| t - 1),
+                        corner.normal.map(|n| n - 1),
+                    ));
+                }
             }
             Entity::Comment { content } => {
                 // ignored
@@ -229,11 +238,15 @@ pub(crate) fn read_obj<R: BufRead>(mut reader: &mut R) -> Result<ObjData, Box<dy
         texture_coordinates,
         normals,
         indices,
+        face_vertices,
     })
 }
 
 pub type ObjNormal = ((f64, f64, f64), Option<VpxNormalBytes>);
 
+/// The vertex, texture coordinate and normal index of a face corner, 0-based
+pub(crate) type FaceVertexIndices = (i64, Option<i64>, Option<i64>);
+
 #[derive(Debug, PartialEq)]
 pub(crate) struct ObjData {
     pub name: String,
@@ -245,6 +258,9 @@ pub(crate) struct ObjData {
     ///
     /// Here they are 0-based, in obj files they are 1-based
     pub indices: Vec<i64>,
+    /// The indices of every face corner, the texture coordinate and normal index can differ
+    /// from the vertex index in files written by modelling tools
+    pub face_vertices: Vec<FaceVertexIndices>,
 }
 
 #[cfg(test)]
@@ -272,6 +288,7 @@ f 1/1/1 1/1/1 1/1/1
             texture_coordinates: vec![(2.0, Some(4.0), None)],
             normals: vec![((0.0, 1.0, 0.0), None)],
             indices: vec![0, 0, 0],
+            face_vertices: vec![(0, Some(0), Some(0)); 3],
         };
         assert_eq!(read_data, expected);
         Ok(())
@@ -297,6 +314,7 @@ f 1/1/1 1/1/1 1/1/1
                 Some([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
             )],
             indices: vec![0, 0, 0],
+            face_vertices: vec![(0, Some(0), Some(0)); 3],
         };
         // we can't compare a structure with NaN values
         assert_eq!(read_data.name, expected.name);
@@ -0,0 +1,158 @@
+//! Timing of the parts of reading and writing a table
+//!
+//! Enable the `profiling` feature to get [`super::read_profiled`], [`super::write_profiled`],
+//! [`super::expanded::read_profiled`] and [`super::expanded::write_profiled`]. They return a
+//! [`Profile`] with the time spent on every category, like the game items or the images, in
+//! the order they were processed. Without the feature no time is measured.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "profiling")]
+//! # {
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//!
+//! let (vpx, profile) =
+//!     vpx::read_profiled(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! println!("{}", profile);
+//! # }
+//! ```
+
+#[cfg(feature = "profiling")]
+use std::fmt;
+#[cfg(feature = "profiling")]
+use std::time::{Duration, Instant};
+
+/// The time spent on one category
+#[cfg(feature = "profiling")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProfileEntry {
+    /// eg `gameitems`, `images` or `mac`
+    pub category: &'static str,
+    pub duration: Duration,
+}
+
+/// Timings of a single read or write
+#[cfg(feature = "profiling")]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Profile {
+    /// In the order the categories were processed
+    pub entries: Vec<ProfileEntry>,
+}
+
+#[cfg(feature = "profiling")]
+impl Profile {
+    pub fn total(&self) -> Duration {
+        self.entries.iter().map(|entry| entry.duration).sum()
+    }
+
+    pub fn get(&self, category: &str) -> Option<Duration> {
+        self.entries
+            .iter()
+            .find(|entry| entry.category == category)
+            .map(|entry| entry.duration)
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:<16} {:>10.3} ms",
+                entry.category,
+                entry.duration.as_secs_f64() * 1000.0
+            )?;
+        }
+        write!(
+            f,
+            "{:<16} {:>10.3} ms",
+            "total",
+            self.total().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Records the timings, does nothing without the `profiling` feature
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    #[cfg(feature = "profiling")]
+    profile: Profile,
+}
+
+impl Profiler {
+    #[cfg(feature = "profiling")]
+    pub(crate) fn time<T, F: FnOnce() -> T>(&mut self, category: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.profile.entries.push(ProfileEntry {
+            category,
+            duration: start.elapsed(),
+        });
+        result
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    #[inline(always)]
+    pub(crate) fn time<T, F: FnOnce() -> T>(&mut self, _category: &'static str, f: F) -> T {
+        f()
+    }
+
+    #[cfg(feature = "profiling")]
+    pub(crate) fn finish(self) -> Profile {
+        self.profile
+    }
+}
+
+#[cfg(all(test, feature = "profiling"))]
+mod tests {
+    use super::*;
+    use crate::vpx;
+    use crate::vpx::expanded;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+    use testdir::testdir;
+
+    #[test]
+    fn profile_totals() {
+        let mut profiler = Profiler::default();
+        let value = profiler.time("first", || 42);
+        profiler.time("second", || std::thread::sleep(Duration::from_millis(2)));
+        let profile = profiler.finish();
+
+        assert_eq!(value, 42);
+        let categories: Vec<&str> = profile.entries.iter().map(|e| e.category).collect();
+        assert_eq!(categories, vec!["first", "second"]);
+        assert!(profile.get("second").unwrap() >= Duration::from_millis(2));
+        assert_eq!(
+            profile.total(),
+            profile.entries.iter().map(|e| e.duration).sum()
+        );
+        assert!(profile.to_string().ends_with(" ms"));
+        assert_eq!(profile.to_string().lines().count(), 3);
+    }
+
+    #[test]
+    fn profile_read_and_write() -> testresult::TestResult {
+        let dir = testdir!();
+        let path = PathBuf::from("testdata/completely_blank_table_10_7_4.vpx");
+        let (table, profile) = vpx::read_profiled(&path)?;
+        let categories: Vec<&str> = profile.entries.iter().map(|e| e.category).collect();
+        assert_eq!(categories[..3], ["open", "custominfotags", "tableinfo"]);
+        assert!(profile.get("gameitems").is_some());
+
+        let profile = vpx::write_profiled(dir.join("test.vpx"), &table, &Default::default())?;
+        assert!(profile.get("mac").is_some());
+
+        let expanded_dir = dir.join("expanded");
+        std::fs::create_dir(&expanded_dir)?;
+        let profile = expanded::write_profiled(&table, &expanded_dir, &Default::default())?;
+        assert!(profile.get("images").is_some());
+        let (read, profile) = expanded::read_profiled(&expanded_dir, &Default::default())?;
+        assert_eq!(read.gameitems.len(), table.gameitems.len());
+        assert!(profile.get("gamedata").is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! Detecting locked and protected tables
+//!
+//! Visual Pinball 10.8 can lock a table against editing, [`GameData::locked`] counts the lock
+//! toggles so an odd count means locked. Visual Pinball 9 and older could protect a table
+//! with a password, the settings are stored in [`GameData::protection_data`]. With script
+//! editing disabled the script was stored encrypted with the Windows CryptoAPI. That is not a
+//! simple XOR and decrypting it is not supported.
+//!
+//! [`super::extractvbs`] fails with a [`ProtectionError`] for an encrypted script, use
+//! [`protection_error`] to get it back from the [`io::Error`].
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::protection::check_editable;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! if let Err(error) = check_editable(&vpx.gamedata) {
+//!     println!("{}", error);
+//! }
+//! ```
+
+use super::gamedata::GameData;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub const DISABLE_TABLE_SAVE: u32 = 0x0000_0001;
+pub const DISABLE_SCRIPT_EDITING: u32 = 0x0000_0002;
+pub const DISABLE_OPEN_MANAGERS: u32 = 0x0000_0004;
+pub const DISABLE_CUT_COPY_PASTE: u32 = 0x0000_0008;
+pub const DISABLE_TABLE_VIEW: u32 = 0x0000_0010;
+pub const DISABLE_TABLE_SAVE_PROTECTED: u32 = 0x0000_0020;
+pub const DISABLE_DEBUGGER: u32 = 0x0000_0040;
+pub const DISABLE_EVERYTHING: u32 = 0x8000_0000;
+
+/// Size of the `SECB` record: versions, size, the encrypted password, flags and spare fields
+const PROTECTION_DATA_LEN: usize = 48;
+const FLAGS_OFFSET: usize = 32;
+
+/// The password protection settings of a Visual Pinball 9 table
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ProtectionData {
+    pub file_version: i32,
+    /// One or more of the `DISABLE_*` flags
+    pub flags: u32,
+    pub key_version: i32,
+}
+
+impl ProtectionData {
+    /// `None` if the data is too short
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < PROTECTION_DATA_LEN {
+            return None;
+        }
+        let i32_at =
+            |offset: usize| i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        Some(ProtectionData {
+            file_version: i32_at(0),
+            flags: i32_at(FLAGS_OFFSET) as u32,
+            key_version: i32_at(FLAGS_OFFSET + 4),
+        })
+    }
+
+    pub fn has(&self, flag: u32) -> bool {
+        self.flags & (flag | DISABLE_EVERYTHING) != 0
+    }
+
+    /// The script is stored encrypted
+    pub fn script_encrypted(&self) -> bool {
+        self.has(DISABLE_SCRIPT_EDITING)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ProtectionError {
+    /// Locked in Visual Pinball 10.8 or later
+    Locked { lock_count: u32 },
+    /// Password protected in Visual Pinball 9 or older
+    Protected { flags: u32 },
+    /// The script can not be read, it is encrypted
+    ScriptEncrypted,
+}
+
+impl fmt::Display for ProtectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtectionError::Locked { lock_count } => {
+                write!(f, "The table is locked (lock count {})", lock_count)
+            }
+            ProtectionError::Protected { flags } => {
+                write!(f, "The table is password protected (flags {:#010x})", flags)
+            }
+            ProtectionError::ScriptEncrypted => {
+                write!(f, "The table script is encrypted by the table protection")
+            }
+        }
+    }
+}
+
+impl Error for ProtectionError {}
+
+impl From<ProtectionError> for io::Error {
+    fn from(error: ProtectionError) -> Self {
+        let kind = match error {
+            ProtectionError::ScriptEncrypted => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::PermissionDenied,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// The [`ProtectionError`] an operation failed with, if any
+pub fn protection_error(error: &io::Error) -> Option<&ProtectionError> {
+    error.get_ref()?.downcast_ref()
+}
+
+impl GameData {
+    /// Locked against editing in Visual Pinball 10.8 or later
+    pub fn is_locked(&self) -> bool {
+        self.locked.is_some_and(|count| count % 2 == 1)
+    }
+
+    /// The Visual Pinball 9 protection, `None` for tables that are not protected
+    pub fn protection(&self) -> Option<ProtectionData> {
+        self.protection_data
+            .as_deref()
+            .and_then(ProtectionData::parse)
+            .filter(|protection| protection.flags != 0)
+    }
+
+    pub fn script_encrypted(&self) -> bool {
+        self.protection()
+            .is_some_and(|protection| protection.script_encrypted())
+    }
+}
+
+/// Fails if the table is locked or password protected
+pub fn check_editable(gamedata: &GameData) -> Result<(), ProtectionError> {
+    if gamedata.is_locked() {
+        return Err(ProtectionError::Locked {
+            lock_count: gamedata.locked.unwrap_or_default(),
+        });
+    }
+    if let Some(protection) = gamedata.protection() {
+        return Err(ProtectionError::Protected {
+            flags: protection.flags,
+        });
+    }
+    Ok(())
+}
+
+/// Fails if the script is encrypted
+pub fn check_script_readable(gamedata: &GameData) -> Result<(), ProtectionError> {
+    if gamedata.script_encrypted() {
+        return Err(ProtectionError::ScriptEncrypted);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::{extractvbs, write, VPX};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    fn protection_data(flags: u32) -> Vec<u8> {
+        let mut data = vec![0; PROTECTION_DATA_LEN];
+        data[0..4].copy_from_slice(&1i32.to_le_bytes());
+        data[FLAGS_OFFSET..FLAGS_OFFSET + 4].copy_from_slice(&flags.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn detect_protection() {
+        let mut gamedata = GameData::default();
+        assert_eq!(check_editable(&gamedata), Ok(()));
+
+        gamedata.locked = Some(2);
+        assert!(!gamedata.is_locked());
+        gamedata.locked = Some(3);
+        assert_eq!(
+            check_editable(&gamedata),
+            Err(ProtectionError::Locked { lock_count: 3 })
+        );
+
+        gamedata.locked = None;
+        gamedata.protection_data = Some(protection_data(0));
+        assert_eq!(gamedata.protection(), None);
+        gamedata.protection_data = Some(protection_data(DISABLE_TABLE_SAVE));
+        assert_eq!(
+            check_editable(&gamedata),
+            Err(ProtectionError::Protected {
+                flags: DISABLE_TABLE_SAVE
+            })
+        );
+        assert_eq!(check_script_readable(&gamedata), Ok(()));
+        gamedata.protection_data = Some(protection_data(DISABLE_EVERYTHING));
+        assert!(gamedata.script_encrypted());
+        gamedata.protection_data = Some(vec![1, 2, 3]);
+        assert_eq!(gamedata.protection(), None);
+    }
+
+    #[test]
+    fn extract_encrypted_script() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("protected.vpx");
+        let mut vpx = VPX::default();
+        vpx.gamedata.protection_data = Some(protection_data(DISABLE_SCRIPT_EDITING));
+        write(&path, &vpx)?;
+
+        let error = extractvbs(&path, None, false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            protection_error(&error),
+            Some(&ProtectionError::ScriptEncrypted)
+        );
+        Ok(())
+    }
+}
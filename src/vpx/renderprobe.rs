@@ -1,4 +1,4 @@
-use crate::vpx::biff::{BiffRead, BiffWrite, BiffWriter};
+use crate::vpx::biff::{BiffRead, BiffWrite, BiffWriter, CheckedFrom};
 use crate::vpx::gameitem::vertex4d::Vertex4D;
 use fake::Dummy;
 use serde::{Deserialize, Serialize};
@@ -9,16 +9,24 @@ enum RenderProbeType {
     ScreenSpaceTransparency = 1,
 }
 
-impl From<u32> for RenderProbeType {
-    fn from(i: u32) -> Self {
+impl CheckedFrom<u32> for RenderProbeType {
+    const FALLBACK: Self = RenderProbeType::PlaneReflection;
+
+    fn checked_from(i: u32) -> Result<Self, String> {
         match i {
-            0 => RenderProbeType::PlaneReflection,
-            1 => RenderProbeType::ScreenSpaceTransparency,
-            _ => panic!("Unknown MaterialType {}", i),
+            0 => Ok(RenderProbeType::PlaneReflection),
+            1 => Ok(RenderProbeType::ScreenSpaceTransparency),
+            _ => Err(format!("Unknown MaterialType {}", i)),
         }
     }
 }
 
+impl From<u32> for RenderProbeType {
+    fn from(i: u32) -> Self {
+        Self::checked_from(i).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&RenderProbeType> for u32 {
     fn from(r: &RenderProbeType) -> Self {
         match r {
@@ -107,21 +115,29 @@ enum ReflectionMode {
     Unknown = 6,
 }
 
-impl From<u32> for ReflectionMode {
-    fn from(i: u32) -> Self {
+impl CheckedFrom<u32> for ReflectionMode {
+    const FALLBACK: Self = ReflectionMode::None;
+
+    fn checked_from(i: u32) -> Result<Self, String> {
         match i {
-            0 => ReflectionMode::None,
-            1 => ReflectionMode::Balls,
-            2 => ReflectionMode::Static,
-            3 => ReflectionMode::StaticNBalls,
-            4 => ReflectionMode::StaticNDynamic,
-            5 => ReflectionMode::Dynamic,
-            6 => ReflectionMode::Unknown,
-            _ => panic!("Unknown ReflectionMode {}", i),
+            0 => Ok(ReflectionMode::None),
+            1 => Ok(ReflectionMode::Balls),
+            2 => Ok(ReflectionMode::Static),
+            3 => Ok(ReflectionMode::StaticNBalls),
+            4 => Ok(ReflectionMode::StaticNDynamic),
+            5 => Ok(ReflectionMode::Dynamic),
+            6 => Ok(ReflectionMode::Unknown),
+            _ => Err(format!("Unknown ReflectionMode {}", i)),
         }
     }
 }
 
+impl From<u32> for ReflectionMode {
+    fn from(i: u32) -> Self {
+        Self::checked_from(i).unwrap_or_else(|message| panic!("{}", message))
+    }
+}
+
 impl From<&ReflectionMode> for u32 {
     fn from(r: &ReflectionMode) -> Self {
         match r {
@@ -323,12 +339,12 @@ impl BiffRead for RenderProbe {
             let tag = reader.tag();
             let tag_str = tag.as_str();
             match tag_str {
-                "TYPE" => render_probe.type_ = reader.get_u32().into(),
+                "TYPE" => render_probe.type_ = reader.get_u32_checked(),
                 "NAME" => render_probe.name = reader.get_string(),
                 "RBAS" => render_probe.roughness = reader.get_u32(),
                 "RCLE" => render_probe.roughness_clear = Some(reader.get_u32()),
                 "RPLA" => render_probe.reflection_plane = Vertex4D::biff_read(reader),
-                "RMOD" => render_probe.reflection_mode = reader.get_u32().into(),
+                "RMOD" => render_probe.reflection_mode = reader.get_u32_checked(),
                 "RLMP" => render_probe.disable_light_reflection = Some(reader.get_bool()),
                 _ => {
                     println!(
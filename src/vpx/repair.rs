@@ -0,0 +1,326 @@
+//! Repair operations for damaged `vpx` files.
+//!
+//! All operations work in place on an existing file and report the fixes that were applied.
+//! Each operation can be run on its own, or use [`repair`] to run them all in the right order.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use vpin::vpx::repair;
+//!
+//! let fixes = repair::repair("broken.vpx").unwrap();
+//! for fix in fixes {
+//!     println!("{}", fix);
+//! }
+//! ```
+
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf, MAIN_SEPARATOR_STR};
+
+use cfb::CompoundFile;
+use utf16string::{LittleEndian, WString};
+
+use super::backup::{self, BackupPolicy};
+use super::biff::{biff_data_len, is_biff_stream};
+use super::version::read_version;
+use super::{generate_mac, read_gamedata, read_mac, write_game_data, write_mac};
+
+/// A fix that was applied by one of the repair operations
+#[derive(Debug, PartialEq, Clone)]
+pub enum Repair {
+    /// A count in the game data did not match the number of streams in the file
+    GameDataCount {
+        name: &'static str,
+        old: u32,
+        new: u32,
+    },
+    /// Bytes after the `ENDB` record of a BIFF stream were removed
+    TrailingGarbageTruncated { path: PathBuf, bytes: usize },
+    /// A table info stream was not valid UTF-16 and has been rewritten
+    InvalidInfoString { path: PathBuf, value: String },
+    /// The MAC did not match the file contents and has been regenerated
+    MacRegenerated,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Repair::GameDataCount { name, old, new } => {
+                write!(f, "GameData {} count changed from {} to {}", name, old, new)
+            }
+            Repair::TrailingGarbageTruncated { path, bytes } => {
+                write!(
+                    f,
+                    "Truncated {} trailing bytes from {}",
+                    bytes,
+                    path.display()
+                )
+            }
+            Repair::InvalidInfoString { path, value } => {
+                write!(f, "Fixed invalid string in {}: {}", path.display(), value)
+            }
+            Repair::MacRegenerated => write!(f, "Regenerated MAC"),
+        }
+    }
+}
+
+/// Runs all repair operations on a `vpx` file.
+///
+/// The MAC is regenerated last so that it covers all other fixes.
+pub fn repair<P: AsRef<Path>>(path: P) -> io::Result<Vec<Repair>> {
+    let mut comp = cfb::open_rw(path)?;
+    let mut repairs = Vec::new();
+    repairs.extend(truncate_trailing_garbage_cfb(&mut comp)?);
+    repairs.extend(rebuild_gamedata_counts_cfb(&mut comp)?);
+    repairs.extend(fix_info_strings_cfb(&mut comp)?);
+    repairs.extend(regenerate_mac_cfb(&mut comp)?);
+    comp.flush()?;
+    Ok(repairs)
+}
+
+/// Like [`repair`] but first backs up the file, see [`backup::snapshot_with_policy`]
+pub fn repair_with_backup<P: AsRef<Path>>(
+    path: P,
+    policy: &BackupPolicy,
+) -> io::Result<Vec<Repair>> {
+    backup::snapshot_with_policy(&path, policy)?;
+    repair(path)
+}
+
+/// Updates the game item, sound, image, font and collection counts in the game data to
+/// match the streams that are actually present in the file.
+///
+/// **Note:** this does not update the MAC, see [`regenerate_mac`].
+pub fn rebuild_gamedata_counts<P: AsRef<Path>>(path: P) -> io::Result<Vec<Repair>> {
+    let mut comp = cfb::open_rw(path)?;
+    let repairs = rebuild_gamedata_counts_cfb(&mut comp)?;
+    comp.flush()?;
+    Ok(repairs)
+}
+
+/// Removes any data found after the `ENDB` record of the BIFF streams.
+///
+/// Streams that can not be parsed at all are left untouched.
+///
+/// **Note:** this does not update the MAC, see [`regenerate_mac`].
+pub fn truncate_trailing_garbage<P: AsRef<Path>>(path: P) -> io::Result<Vec<Repair>> {
+    let mut comp = cfb::open_rw(path)?;
+    let repairs = truncate_trailing_garbage_cfb(&mut comp)?;
+    comp.flush()?;
+    Ok(repairs)
+}
+
+/// Rewrites table info streams that are not valid UTF-16, replacing invalid characters with
+/// the unicode replacement character.
+///
+/// **Note:** this does not update the MAC, see [`regenerate_mac`].
+pub fn fix_info_strings<P: AsRef<Path>>(path: P) -> io::Result<Vec<Repair>> {
+    let mut comp = cfb::open_rw(path)?;
+    let repairs = fix_info_strings_cfb(&mut comp)?;
+    comp.flush()?;
+    Ok(repairs)
+}
+
+/// Regenerates the MAC if it does not match the file contents.
+pub fn regenerate_mac<P: AsRef<Path>>(path: P) -> io::Result<Vec<Repair>> {
+    let mut comp = cfb::open_rw(path)?;
+    let repairs = regenerate_mac_cfb(&mut comp)?;
+    comp.flush()?;
+    Ok(repairs)
+}
+
+fn rebuild_gamedata_counts_cfb<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+) -> io::Result<Vec<Repair>> {
+    let version = read_version(comp)?;
+    let mut gamedata = read_gamedata(comp, &version)?;
+    let mut repairs = Vec::new();
+    let mut check = |name: &'static str, prefix: &str, count: &mut u32| {
+        let actual = count_streams(comp, prefix);
+        if actual != *count {
+            repairs.push(Repair::GameDataCount {
+                name,
+                old: *count,
+                new: actual,
+            });
+            *count = actual;
+        }
+    };
+    check("gameitems", "GameItem", &mut gamedata.gameitems_size);
+    check("sounds", "Sound", &mut gamedata.sounds_size);
+    check("images", "Image", &mut gamedata.images_size);
+    check("fonts", "Font", &mut gamedata.fonts_size);
+    check("collections", "Collection", &mut gamedata.collections_size);
+    if !repairs.is_empty() {
+        write_game_data(comp, &gamedata, &version)?;
+    }
+    Ok(repairs)
+}
+
+/// Counts the consecutive streams `GameStg/{prefix}0`, `GameStg/{prefix}1`, ...
+pub(crate) fn count_streams<F: Read + Seek>(comp: &CompoundFile<F>, prefix: &str) -> u32 {
+    let gamestg = Path::new(MAIN_SEPARATOR_STR).join("GameStg");
+    let mut count = 0;
+    while comp.is_stream(gamestg.join(format!("{}{}", prefix, count))) {
+        count += 1;
+    }
+    count
+}
+
+fn truncate_trailing_garbage_cfb<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+) -> io::Result<Vec<Repair>> {
+    let paths: Vec<PathBuf> = comp
+        .walk()
+        .filter(|entry| entry.is_stream())
+        .map(|entry| entry.path().to_path_buf())
+        .filter(|path| is_biff_stream(path))
+        .collect();
+    let mut repairs = Vec::new();
+    for path in paths {
+        let mut data = Vec::new();
+        comp.open_stream(&path)?.read_to_end(&mut data)?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        // game items start with a 32 bit item type
+        let skip = if name.starts_with("GameItem") { 4 } else { 0 };
+        let biff = data.get(skip..).unwrap_or_default();
+        let Ok(len) = biff_data_len(biff) else {
+            continue;
+        };
+        let end = skip + len;
+        if end < data.len() {
+            let mut stream = comp.open_stream(&path)?;
+            stream.set_len(end as u64)?;
+            repairs.push(Repair::TrailingGarbageTruncated {
+                path,
+                bytes: data.len() - end,
+            });
+        }
+    }
+    Ok(repairs)
+}
+
+fn fix_info_strings_cfb<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+) -> io::Result<Vec<Repair>> {
+    let table_info_path = Path::new(MAIN_SEPARATOR_STR).join("TableInfo");
+    if !comp.is_storage(&table_info_path) {
+        return Ok(Vec::new());
+    }
+    let paths: Vec<PathBuf> = comp
+        .read_storage(&table_info_path)?
+        .filter(|entry| entry.is_stream() && entry.name() != "Screenshot")
+        .map(|entry| entry.path().to_path_buf())
+        .collect();
+    let mut repairs = Vec::new();
+    for path in paths {
+        let mut data = Vec::new();
+        comp.open_stream(&path)?.read_to_end(&mut data)?;
+        if utf16string::WStr::from_utf16le(&data).is_ok() {
+            continue;
+        }
+        // a trailing odd byte can not be part of a utf-16 character and is dropped
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        let value = String::from_utf16_lossy(&units);
+        let wide: WString<LittleEndian> = WString::from(&value);
+        comp.create_stream(&path)?.write_all(wide.as_bytes())?;
+        repairs.push(Repair::InvalidInfoString { path, value });
+    }
+    Ok(repairs)
+}
+
+fn regenerate_mac_cfb<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+) -> io::Result<Vec<Repair>> {
+    let generated_mac = generate_mac(comp)?;
+    match read_mac(comp) {
+        Ok(mac) if mac == generated_mac => Ok(Vec::new()),
+        _ => {
+            write_mac(comp, &generated_mac)?;
+            Ok(vec![Repair::MacRegenerated])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::{self, verify, VerifyResult};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    fn copy_blank_table() -> PathBuf {
+        let dir: PathBuf = testdir!();
+        let path = dir.join("test.vpx");
+        std::fs::copy("testdata/completely_blank_table_10_7_4.vpx", &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn repair_intact_table() -> io::Result<()> {
+        let path = copy_blank_table();
+        let repairs = repair(&path)?;
+        assert_eq!(repairs, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn repair_damaged_table() -> io::Result<()> {
+        let path = copy_blank_table();
+        {
+            let mut comp = cfb::open_rw(&path)?;
+            let gameitem_path = Path::new(MAIN_SEPARATOR_STR)
+                .join("GameStg")
+                .join("GameItem0");
+            let mut stream = comp.open_stream(&gameitem_path)?;
+            stream.seek(io::SeekFrom::End(0))?;
+            stream.write_all(&[1, 2, 3])?;
+            drop(stream);
+            let version = read_version(&mut comp)?;
+            let mut gamedata = read_gamedata(&mut comp, &version)?;
+            gamedata.collections_size = 7;
+            write_game_data(&mut comp, &gamedata, &version)?;
+            let name_path = Path::new(MAIN_SEPARATOR_STR)
+                .join("TableInfo")
+                .join("TableName");
+            comp.create_stream(&name_path)?
+                .write_all(&[b'A', 0, b'B'])?;
+            comp.flush()?;
+        }
+
+        let repairs = repair(&path)?;
+
+        assert_eq!(
+            repairs,
+            vec![
+                Repair::TrailingGarbageTruncated {
+                    path: Path::new(MAIN_SEPARATOR_STR)
+                        .join("GameStg")
+                        .join("GameItem0"),
+                    bytes: 3
+                },
+                Repair::GameDataCount {
+                    name: "collections",
+                    old: 7,
+                    new: 9
+                },
+                Repair::InvalidInfoString {
+                    path: Path::new(MAIN_SEPARATOR_STR)
+                        .join("TableInfo")
+                        .join("TableName"),
+                    value: "A".to_string()
+                },
+                Repair::MacRegenerated,
+            ]
+        );
+        assert_eq!(verify(&path), VerifyResult::Ok(path.clone()));
+        let vpx = vpx::read(&path)?;
+        assert_eq!(vpx.collections.len(), 9);
+        assert_eq!(vpx.info.table_name, Some("A".to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Collects everything in a table that looks like rules or instructions
+//!
+//! Besides the rules in the table info, authors often put the rules in a text box or an
+//! instruction card image. These are found by name, so this is a best effort.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let rules = vpx::rules(&vpx);
+//! if rules.is_empty() {
+//!     println!("no rules found");
+//! }
+//! ```
+
+use super::card::clean_text;
+use super::gameitem::GameItemEnum;
+use super::VPX;
+
+/// Parts of names that point to rules, matched ignoring case
+const RULES_NAMES: [&str; 3] = ["rule", "instruction", "instcard"];
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Rules {
+    /// The rules from the table info
+    pub table_info: Option<String>,
+    pub text_boxes: Vec<RulesText>,
+    pub images: Vec<RulesImage>,
+}
+
+impl Rules {
+    pub fn is_empty(&self) -> bool {
+        self.table_info.is_none() && self.text_boxes.is_empty() && self.images.is_empty()
+    }
+}
+
+/// A text box that contains rules
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RulesText {
+    pub name: String,
+    pub text: String,
+}
+
+/// An image that looks like an instruction card
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RulesImage {
+    /// Index in [`VPX::images`]
+    pub index: usize,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Collects the table info rules, text boxes named like `rules` and images named like
+/// `instructions`.
+///
+/// Texts are cleaned up with [`clean_text`], empty texts are left out.
+pub fn rules(vpx: &VPX) -> Rules {
+    let table_info = vpx
+        .info
+        .table_rules
+        .as_deref()
+        .map(clean_text)
+        .filter(|text| !text.is_empty());
+    let text_boxes = vpx
+        .gameitems
+        .iter()
+        .filter_map(|item| match item {
+            GameItemEnum::TextBox(text_box) if is_rules_name(&text_box.name) => Some(RulesText {
+                name: text_box.name.clone(),
+                text: clean_text(&text_box.text),
+            }),
+            _ => None,
+        })
+        .filter(|text| !text.text.is_empty())
+        .collect();
+    let images = vpx
+        .images
+        .iter()
+        .enumerate()
+        .filter(|(_, image)| is_rules_name(&image.name))
+        .map(|(index, image)| RulesImage {
+            index,
+            name: image.name.clone(),
+            width: image.width,
+            height: image.height,
+        })
+        .collect();
+    Rules {
+        table_info,
+        text_boxes,
+        images,
+    }
+}
+
+fn is_rules_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    RULES_NAMES.iter().any(|part| name.contains(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::textbox::TextBox;
+    use crate::vpx::image::ImageData;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn collect_rules() {
+        let mut vpx = VPX::default();
+        vpx.info.table_rules = Some(" \r\n".to_string());
+        let mut rules_box = TextBox::default();
+        rules_box.name = "TextRules".to_string();
+        rules_box.text = "Lock 3 balls\r\nfor multiball".to_string();
+        vpx.add_game_item(GameItemEnum::TextBox(rules_box));
+        let mut score_box = TextBox::default();
+        score_box.name = "Score".to_string();
+        score_box.text = "000000".to_string();
+        vpx.add_game_item(GameItemEnum::TextBox(score_box));
+        for name in ["Playfield", "InstructionCardLeft"] {
+            vpx.images.push(ImageData {
+                name: name.to_string(),
+                width: 400,
+                height: 200,
+                ..Default::default()
+            });
+        }
+
+        let rules = rules(&vpx);
+
+        assert_eq!(
+            rules,
+            Rules {
+                table_info: None,
+                text_boxes: vec![RulesText {
+                    name: "TextRules".to_string(),
+                    text: "Lock 3 balls for multiball".to_string(),
+                }],
+                images: vec![RulesImage {
+                    index: 1,
+                    name: "InstructionCardLeft".to_string(),
+                    width: 400,
+                    height: 200,
+                }],
+            }
+        );
+        assert!(!rules.is_empty());
+        assert!(super::rules(&VPX::default()).is_empty());
+    }
+}
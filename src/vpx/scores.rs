@@ -0,0 +1,212 @@
+//! High scores and statistics recorded in the table file
+//!
+//! Tables driven by a ROM keep their high scores in the NVRAM of the ROM, original tables
+//! usually write them to the Visual Pinball registry or a file of their own, so frontends
+//! have no standard place to read them. This module defines a small json stream
+//! [`SCORES_STREAM`] that runtime mods and tools can write the scores to.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "high_scores": [
+//!     { "label": "Grand Champion", "initials": "ABC", "score": 12000000 }
+//!   ],
+//!   "stats": { "games_played": 42 }
+//! }
+//! ```
+//!
+//! Visual Pinball does not know the stream, it is not part of the table hash and saving the
+//! table in the editor drops it. This library keeps it as one of the [`VPX::extra_streams`].
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::scores::{scores, set_scores, HighScore, ScoreCapture};
+//! use vpin::vpx::VPX;
+//!
+//! let mut vpx = VPX::default();
+//! let mut capture = ScoreCapture::default();
+//! capture.high_scores.push(HighScore {
+//!     label: Some("Grand Champion".to_string()),
+//!     initials: "ABC".to_string(),
+//!     score: 12_000_000,
+//!     date: None,
+//! });
+//! set_scores(&mut vpx, &capture).unwrap();
+//! assert_eq!(scores(&vpx).unwrap(), Some(capture));
+//! ```
+
+use super::{ExtraStream, VPX};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+
+/// Path of the stream in the compound file
+pub const SCORES_STREAM: &str = "GameStg/ScoreCapture";
+
+/// The version written by this library, newer versions can not be read
+pub const SCORES_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    /// What the score is for, eg `Grand Champion` or `Loop Champion`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub initials: String,
+    pub score: u64,
+    /// When the score was made, ISO 8601
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ScoreCapture {
+    pub version: u32,
+    /// Best first
+    #[serde(default)]
+    pub high_scores: Vec<HighScore>,
+    /// Free form statistics, eg `games_played`
+    #[serde(default)]
+    pub stats: BTreeMap<String, Value>,
+}
+
+impl Default for ScoreCapture {
+    fn default() -> Self {
+        ScoreCapture {
+            version: SCORES_VERSION,
+            high_scores: vec![],
+            stats: BTreeMap::new(),
+        }
+    }
+}
+
+impl ScoreCapture {
+    pub fn from_json(data: &[u8]) -> io::Result<Self> {
+        let capture: ScoreCapture = serde_json::from_slice(data)?;
+        if capture.version > SCORES_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Score capture version {} is newer than the supported version {}",
+                    capture.version, SCORES_VERSION
+                ),
+            ));
+        }
+        Ok(capture)
+    }
+
+    pub fn to_json(&self) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// The scores stored in the table, `None` if there are none
+pub fn scores(vpx: &VPX) -> io::Result<Option<ScoreCapture>> {
+    vpx.extra_streams
+        .iter()
+        .find(|stream| stream.path == SCORES_STREAM)
+        .map(|stream| ScoreCapture::from_json(&stream.data))
+        .transpose()
+}
+
+/// Stores the scores in the table, replacing the ones that are there
+pub fn set_scores(vpx: &mut VPX, capture: &ScoreCapture) -> io::Result<()> {
+    let data = capture.to_json()?;
+    match vpx
+        .extra_streams
+        .iter_mut()
+        .find(|stream| stream.path == SCORES_STREAM)
+    {
+        Some(stream) => stream.data = data,
+        None => vpx.extra_streams.push(ExtraStream {
+            path: SCORES_STREAM.to_string(),
+            data,
+        }),
+    }
+    Ok(())
+}
+
+/// Removes the scores from the table, returns `false` if there were none
+pub fn remove_scores(vpx: &mut VPX) -> bool {
+    let len = vpx.extra_streams.len();
+    vpx.extra_streams
+        .retain(|stream| stream.path != SCORES_STREAM);
+    vpx.extra_streams.len() != len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use testdir::testdir;
+
+    fn capture() -> ScoreCapture {
+        let mut capture = ScoreCapture::default();
+        capture.high_scores.push(HighScore {
+            label: Some("Grand Champion".to_string()),
+            initials: "ABC".to_string(),
+            score: 12_000_000,
+            date: Some("2024-05-01T20:15:00Z".to_string()),
+        });
+        capture.stats.insert("games_played".to_string(), json!(42));
+        capture
+    }
+
+    #[test]
+    fn json_format() -> io::Result<()> {
+        let json: Value = serde_json::from_slice(&capture().to_json()?)?;
+        assert_eq!(
+            json,
+            json!({
+                "version": 1,
+                "high_scores": [{
+                    "label": "Grand Champion",
+                    "initials": "ABC",
+                    "score": 12000000,
+                    "date": "2024-05-01T20:15:00Z"
+                }],
+                "stats": { "games_played": 42 }
+            })
+        );
+        let minimal = ScoreCapture::from_json(br#"{"version": 1}"#)?;
+        assert_eq!(minimal, ScoreCapture::default());
+        let error = ScoreCapture::from_json(br#"{"version": 2}"#).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    #[test]
+    fn stored_in_table() -> io::Result<()> {
+        let dir = testdir!();
+        let mut table = VPX::default();
+        assert_eq!(scores(&table)?, None);
+        set_scores(&mut table, &ScoreCapture::default())?;
+        set_scores(&mut table, &capture())?;
+        assert_eq!(table.extra_streams.len(), 1);
+
+        let path = dir.join("scores.vpx");
+        vpx::write(&path, &table)?;
+        let read = vpx::read(&path)?;
+        assert_eq!(scores(&read)?, Some(capture()));
+
+        let mut file = vpx::open(&path)?;
+        assert_eq!(file.read_scores()?, Some(capture()));
+        drop(file);
+
+        let mut file = vpx::open_rw(&path)?;
+        let mut updated = capture();
+        updated.high_scores[0].score += 1;
+        file.write_scores(&updated)?;
+        drop(file);
+        assert!(matches!(vpx::verify(&path), vpx::VerifyResult::Ok(_)));
+        let mut read = vpx::read(&path)?;
+        assert_eq!(scores(&read)?, Some(updated));
+
+        assert!(remove_scores(&mut read));
+        assert!(!remove_scores(&mut read));
+        Ok(())
+    }
+}
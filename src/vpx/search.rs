@@ -0,0 +1,222 @@
+//! Case-insensitive text search across the contents of a table
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! for hit in vpx::search(&vpx, "option explicit") {
+//!     println!("{:?}: {}", hit.location, hit.text);
+//! }
+//! ```
+
+use super::VPX;
+
+/// Where a [`SearchHit`] was found
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SearchLocation {
+    /// Line and column are 1-based, the column counts characters
+    Script {
+        line: usize,
+        column: usize,
+    },
+    /// A table info field like `table_name`, or the name of a custom info property
+    TableInfo {
+        field: String,
+    },
+    GameItem {
+        index: usize,
+        item_type: String,
+    },
+    Image {
+        index: usize,
+    },
+    Sound {
+        index: usize,
+    },
+    Collection {
+        index: usize,
+    },
+    /// An item in a collection, `name` is the name of the collection
+    CollectionItem {
+        name: String,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SearchHit {
+    pub location: SearchLocation,
+    /// The matching script line or value
+    pub text: String,
+}
+
+/// Searches the script, table info, game item names, image and sound names and
+/// collections for a text, ignoring ASCII case.
+///
+/// Every occurrence in the script is a separate hit, other values are reported once.
+/// An empty query matches nothing.
+pub fn search(vpx: &VPX, query: &str) -> Vec<SearchHit> {
+    if query.is_empty() {
+        return vec![];
+    }
+    let query = query.to_ascii_lowercase();
+    let contains = |value: &str| value.to_ascii_lowercase().contains(&query);
+    let mut hits = Vec::new();
+
+    for (line_index, line) in vpx.gamedata.code.string.lines().enumerate() {
+        let lower = line.to_ascii_lowercase();
+        for (offset, _) in lower.match_indices(&query) {
+            hits.push(SearchHit {
+                location: SearchLocation::Script {
+                    line: line_index + 1,
+                    column: line[..offset].chars().count() + 1,
+                },
+                text: line.to_string(),
+            });
+        }
+    }
+
+    let info = &vpx.info;
+    let info_fields = [
+        ("table_name", &info.table_name),
+        ("author_name", &info.author_name),
+        ("table_version", &info.table_version),
+        ("release_date", &info.release_date),
+        ("author_email", &info.author_email),
+        ("author_website", &info.author_website),
+        ("table_blurb", &info.table_blurb),
+        ("table_description", &info.table_description),
+        ("table_rules", &info.table_rules),
+    ];
+    for (field, value) in info_fields {
+        if let Some(value) = value.as_ref().filter(|v| contains(v)) {
+            hits.push(SearchHit {
+                location: SearchLocation::TableInfo {
+                    field: field.to_string(),
+                },
+                text: value.clone(),
+            });
+        }
+    }
+    // custom info tags define the ordering of the properties
+    for name in &vpx.custominfotags {
+        if let Some(value) = info.properties.get(name).filter(|v| contains(v)) {
+            hits.push(SearchHit {
+                location: SearchLocation::TableInfo {
+                    field: name.clone(),
+                },
+                text: value.clone(),
+            });
+        }
+    }
+
+    for (index, gameitem) in vpx.gameitems.iter().enumerate() {
+        if contains(gameitem.name()) {
+            hits.push(SearchHit {
+                location: SearchLocation::GameItem {
+                    index,
+                    item_type: gameitem.type_name(),
+                },
+                text: gameitem.name().to_string(),
+            });
+        }
+    }
+    for (index, image) in vpx.images.iter().enumerate() {
+        if contains(&image.name) {
+            hits.push(SearchHit {
+                location: SearchLocation::Image { index },
+                text: image.name.clone(),
+            });
+        }
+    }
+    for (index, sound) in vpx.sounds.iter().enumerate() {
+        if contains(&sound.name) {
+            hits.push(SearchHit {
+                location: SearchLocation::Sound { index },
+                text: sound.name.clone(),
+            });
+        }
+    }
+    for (index, collection) in vpx.collections.iter().enumerate() {
+        if contains(&collection.name) {
+            hits.push(SearchHit {
+                location: SearchLocation::Collection { index },
+                text: collection.name.clone(),
+            });
+        }
+        for item in collection.items.iter().filter(|item| contains(item)) {
+            hits.push(SearchHit {
+                location: SearchLocation::CollectionItem {
+                    name: collection.name.clone(),
+                },
+                text: item.clone(),
+            });
+        }
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::collection::Collection;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::GameItemEnum;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn search_everywhere() {
+        let mut vpx = VPX::default();
+        vpx.set_script(
+            "' nFozzy physics\nSub Foo : nfozzy.Init : NFozzy.Update : End Sub".to_string(),
+        );
+        vpx.info.table_description = Some("Uses nFozzy 3.0".to_string());
+        let light = Light {
+            name: "NFozzyLight".to_string(),
+            ..Default::default()
+        };
+        vpx.add_game_item(GameItemEnum::Light(light));
+        vpx.collections.push(Collection {
+            name: "Lights".to_string(),
+            items: vec!["NFozzyLight".to_string(), "Other".to_string()],
+            fire_events: false,
+            stop_single_events: false,
+            group_elements: true,
+        });
+
+        let hits = search(&vpx, "NFOZZY");
+
+        let locations = hits
+            .iter()
+            .map(|hit| hit.location.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            vec![
+                SearchLocation::Script { line: 1, column: 3 },
+                SearchLocation::Script {
+                    line: 2,
+                    column: 11
+                },
+                SearchLocation::Script {
+                    line: 2,
+                    column: 25
+                },
+                SearchLocation::TableInfo {
+                    field: "table_description".to_string()
+                },
+                SearchLocation::GameItem {
+                    index: 0,
+                    item_type: "Light".to_string()
+                },
+                SearchLocation::CollectionItem {
+                    name: "Lights".to_string()
+                },
+            ]
+        );
+        assert_eq!(hits[3].text, "Uses nFozzy 3.0");
+        assert_eq!(search(&vpx, ""), vec![]);
+    }
+}
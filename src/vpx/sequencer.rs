@@ -0,0 +1,467 @@
+//! Light sequencer animations and a preview of the light states they produce
+//!
+//! A light sequencer only stores which collection of lights it drives, the file has no
+//! animations. Those are started from the script with calls like
+//! `LightSeq1.Play SeqUpOn, 25, 1, 0`, which [`script_steps`] collects. [`evaluate`] turns
+//! the steps into an on/off timeline per light.
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::gameitem::lightsequencer::LightSequencer;
+//! use vpin::vpx::sequencer::{evaluate, script_steps};
+//! use vpin::vpx::VPX;
+//!
+//! let vpx = VPX::default();
+//! let steps = script_steps("LightSeq1.Play SeqBlinking, , 3, 100", "LightSeq1");
+//! let timeline = evaluate(&vpx, &LightSequencer::default(), &steps);
+//! println!("{} ms", timeline.duration);
+//! ```
+
+use super::edit::code_length;
+use super::gameitem::lightsequencer::LightSequencer;
+use super::gameitem::GameItemEnum;
+use super::VPX;
+
+/// Distance in Visual Pinball Units a wave moves per update, the grid size Visual Pinball uses
+const GRID_SIZE: f32 = 20.0;
+
+/// The `Seq...` animation constants that can be passed to `LightSequencer.Play`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Animation {
+    /// `SeqAllOff`, all lights off during the pause
+    AllOff,
+    /// `SeqAllOn`, all lights on during the pause
+    AllOn,
+    /// `SeqBlinking`, all lights blink `repeat` times, on and off for the pause each
+    Blinking,
+    /// `SeqRandom`, `tail_length` random lights are on per update, for `repeat` updates
+    Random,
+    /// A line or circle moving over the lights, turning them on or off, eg `SeqUpOn`
+    Wave { pattern: WavePattern, on: bool },
+    /// Any other constant, these are not evaluated
+    Other(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WavePattern {
+    Up,
+    Down,
+    Right,
+    Left,
+    DiagUpRight,
+    DiagUpLeft,
+    DiagDownRight,
+    DiagDownLeft,
+    /// From the center of the sequencer to the left and right
+    MiddleOutHoriz,
+    MiddleInHoriz,
+    /// From the center of the sequencer to the top and bottom
+    MiddleOutVert,
+    MiddleInVert,
+    /// Growing around the center of the sequencer
+    CircleOut,
+    CircleIn,
+}
+
+const WAVE_PATTERNS: [(&str, WavePattern); 14] = [
+    ("Up", WavePattern::Up),
+    ("Down", WavePattern::Down),
+    ("Right", WavePattern::Right),
+    ("Left", WavePattern::Left),
+    ("DiagUpRight", WavePattern::DiagUpRight),
+    ("DiagUpLeft", WavePattern::DiagUpLeft),
+    ("DiagDownRight", WavePattern::DiagDownRight),
+    ("DiagDownLeft", WavePattern::DiagDownLeft),
+    ("MiddleOutHoriz", WavePattern::MiddleOutHoriz),
+    ("MiddleInHoriz", WavePattern::MiddleInHoriz),
+    ("MiddleOutVert", WavePattern::MiddleOutVert),
+    ("MiddleInVert", WavePattern::MiddleInVert),
+    ("CircleOut", WavePattern::CircleOut),
+    ("CircleIn", WavePattern::CircleIn),
+];
+
+impl Animation {
+    /// Parses a script constant like `SeqUpOn`, ignoring case
+    pub fn from_name(name: &str) -> Animation {
+        let lower = name.to_ascii_lowercase();
+        let Some(rest) = lower.strip_prefix("seq") else {
+            return Animation::Other(name.to_string());
+        };
+        match rest {
+            "alloff" => return Animation::AllOff,
+            "allon" => return Animation::AllOn,
+            "blinking" => return Animation::Blinking,
+            "random" => return Animation::Random,
+            _ => {}
+        }
+        let (pattern, on) = match (rest.strip_suffix("on"), rest.strip_suffix("off")) {
+            (Some(pattern), _) => (pattern, true),
+            (_, Some(pattern)) => (pattern, false),
+            _ => return Animation::Other(name.to_string()),
+        };
+        WAVE_PATTERNS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(pattern))
+            .map(|(_, pattern)| Animation::Wave {
+                pattern: *pattern,
+                on,
+            })
+            .unwrap_or_else(|| Animation::Other(name.to_string()))
+    }
+}
+
+/// One call to `LightSequencer.Play`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SequenceStep {
+    pub animation: Animation,
+    /// For waves the number of updates a light stays on (or off) before going back, 0 keeps
+    /// it until the end of the step
+    pub tail_length: u32,
+    /// Number of times the step is played, at least once
+    pub repeat: u32,
+    /// Milliseconds to wait after the step
+    pub pause: u32,
+}
+
+/// The states of all lights of a sequencer over time
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Timeline {
+    pub lights: Vec<LightTimeline>,
+    /// Milliseconds until the last step ends
+    pub duration: u32,
+    /// Animations of steps that were skipped because they are not evaluated
+    pub skipped: Vec<String>,
+}
+
+/// The changes of a single light, which starts off
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LightTimeline {
+    pub name: String,
+    /// Time in milliseconds and the new state, sorted by time
+    pub changes: Vec<(u32, bool)>,
+}
+
+impl LightTimeline {
+    /// Whether the light is on at a time in milliseconds
+    pub fn is_on(&self, time: u32) -> bool {
+        self.changes
+            .iter()
+            .take_while(|(change_time, _)| *change_time <= time)
+            .last()
+            .is_some_and(|(_, on)| *on)
+    }
+
+    fn set(&mut self, time: u32, on: bool) {
+        if let Some((last_time, _)) = self.changes.last() {
+            if *last_time == time {
+                self.changes.pop();
+            }
+        }
+        let current = self.changes.last().is_some_and(|(_, on)| *on);
+        if current != on {
+            self.changes.push((time, on));
+        }
+    }
+}
+
+/// Collects the `Play` calls on a sequencer from a script, in the order they appear.
+///
+/// Arguments that are left out or are not numbers default to a tail length of 0, a repeat of
+/// 1 and a pause of 0. Calls in comments are ignored.
+pub fn script_steps(script: &str, sequencer: &str) -> Vec<SequenceStep> {
+    let call = format!("{}.play", sequencer.to_ascii_lowercase());
+    let mut steps = Vec::new();
+    for line in script.lines() {
+        let code = &line[..code_length(line)];
+        let lower = code.to_ascii_lowercase();
+        for (start, _) in lower.match_indices(&call) {
+            let identifier_start = start == 0
+                || !lower[..start]
+                    .ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            let rest = &code[start + call.len()..];
+            if !identifier_start || rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+                continue;
+            }
+            let rest = rest.trim_start().trim_start_matches('(');
+            let arguments = rest.split(':').next().unwrap_or_default();
+            let arguments: Vec<&str> = arguments
+                .trim_end()
+                .trim_end_matches(')')
+                .split(',')
+                .map(str::trim)
+                .collect();
+            let number = |index: usize| {
+                arguments
+                    .get(index)
+                    .and_then(|argument| argument.parse::<u32>().ok())
+            };
+            steps.push(SequenceStep {
+                animation: Animation::from_name(arguments[0]),
+                tail_length: number(1).unwrap_or(0),
+                repeat: number(2).unwrap_or(1).max(1),
+                pause: number(3).unwrap_or(0),
+            });
+        }
+    }
+    steps
+}
+
+/// Plays the steps on the lights in the collection of the sequencer.
+///
+/// Every update of [`LightSequencer::update_interval`] milliseconds a wave moves
+/// 20 Visual Pinball Units. Before a wave that turns lights on all lights are turned off and
+/// the other way around. At the end the lights go back to off, their state before the
+/// sequence.
+///
+/// This is a preview, timings and the order in which lights change follow the description
+/// of the animations and not the exact implementation of Visual Pinball. Random uses a fixed
+/// seed so previews are repeatable.
+pub fn evaluate(vpx: &VPX, sequencer: &LightSequencer, steps: &[SequenceStep]) -> Timeline {
+    let lights = collection_lights(vpx, &sequencer.collection);
+    let interval = sequencer.update_interval.max(1);
+    let mut timeline = Timeline {
+        lights: lights
+            .iter()
+            .map(|(name, _)| LightTimeline {
+                name: name.clone(),
+                changes: Vec::new(),
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut random = 0x2545_f491u32;
+    let mut time = 0u32;
+    for step in steps {
+        for _ in 0..step.repeat.max(1) {
+            let length = match &step.animation {
+                Animation::AllOff | Animation::AllOn => {
+                    let on = step.animation == Animation::AllOn;
+                    timeline.lights.iter_mut().for_each(|l| l.set(time, on));
+                    0
+                }
+                Animation::Blinking => {
+                    let half = step.pause.max(interval);
+                    for light in &mut timeline.lights {
+                        light.set(time, true);
+                        light.set(time + half, false);
+                    }
+                    // blinking repeats are counted per blink, the pause is the blink speed
+                    2 * half
+                }
+                Animation::Random => {
+                    let on = (step.tail_length.max(1) as usize).min(lights.len());
+                    for light in &mut timeline.lights {
+                        light.set(time, false);
+                    }
+                    let mut chosen: Vec<usize> = Vec::new();
+                    for _ in 0..on {
+                        // xorshift, good enough for picking lights
+                        random ^= random << 13;
+                        random ^= random >> 17;
+                        random ^= random << 5;
+                        chosen.push(random as usize % lights.len());
+                    }
+                    for index in chosen {
+                        timeline.lights[index].set(time, true);
+                    }
+                    interval
+                }
+                Animation::Wave { pattern, on } => {
+                    let distances = wave_distances(sequencer, &lights, *pattern);
+                    let mut last_update = 0;
+                    for (light, distance) in timeline.lights.iter_mut().zip(distances) {
+                        let update = (distance / GRID_SIZE).floor() as u32;
+                        light.set(time, !on);
+                        light.set(time + update * interval, *on);
+                        if step.tail_length > 0 {
+                            light.set(time + (update + step.tail_length) * interval, !on);
+                        }
+                        last_update = last_update.max(update + step.tail_length);
+                    }
+                    (last_update + 1) * interval
+                }
+                Animation::Other(name) => {
+                    timeline.skipped.push(name.clone());
+                    break;
+                }
+            };
+            time += length;
+            // the pause of blinking is the blink speed, not a wait after the step
+            if step.animation != Animation::Blinking {
+                time += step.pause;
+            }
+        }
+    }
+    for light in &mut timeline.lights {
+        light.set(time, false);
+    }
+    timeline.duration = time;
+    timeline
+}
+
+/// Names and positions of the lights in a collection, in collection order
+fn collection_lights(vpx: &VPX, collection: &str) -> Vec<(String, [f32; 2])> {
+    let Some(collection) = vpx
+        .collections
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(collection))
+    else {
+        return Vec::new();
+    };
+    collection
+        .items
+        .iter()
+        .filter_map(|name| {
+            vpx.gameitems.iter().find_map(|item| match item {
+                GameItemEnum::Light(light) if light.name.eq_ignore_ascii_case(name) => {
+                    Some((light.name.clone(), [light.center.x, light.center.y]))
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Distance every light is from the start of the wave
+fn wave_distances(
+    sequencer: &LightSequencer,
+    lights: &[(String, [f32; 2])],
+    pattern: WavePattern,
+) -> Vec<f32> {
+    let positions: Vec<[f32; 2]> = lights.iter().map(|(_, position)| *position).collect();
+    let min_x = positions.iter().map(|p| p[0]).fold(f32::INFINITY, f32::min);
+    let max_x = positions
+        .iter()
+        .map(|p| p[0])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let min_y = positions.iter().map(|p| p[1]).fold(f32::INFINITY, f32::min);
+    let max_y = positions
+        .iter()
+        .map(|p| p[1])
+        .fold(f32::NEG_INFINITY, f32::max);
+    let center = &sequencer.center;
+    let diagonal = |dx: f32, dy: f32| (dx + dy) / std::f32::consts::SQRT_2;
+    let distances: Vec<f32> = positions
+        .iter()
+        .map(|&[x, y]| match pattern {
+            // y grows towards the player, up starts at the bottom
+            WavePattern::Up => max_y - y,
+            WavePattern::Down => y - min_y,
+            WavePattern::Right => x - min_x,
+            WavePattern::Left => max_x - x,
+            WavePattern::DiagUpRight => diagonal(x - min_x, max_y - y),
+            WavePattern::DiagUpLeft => diagonal(max_x - x, max_y - y),
+            WavePattern::DiagDownRight => diagonal(x - min_x, y - min_y),
+            WavePattern::DiagDownLeft => diagonal(max_x - x, y - min_y),
+            WavePattern::MiddleOutHoriz | WavePattern::MiddleInHoriz => (x - center.x).abs(),
+            WavePattern::MiddleOutVert | WavePattern::MiddleInVert => (y - center.y).abs(),
+            WavePattern::CircleOut | WavePattern::CircleIn => {
+                ((x - center.x).powi(2) + (y - center.y).powi(2)).sqrt()
+            }
+        })
+        .collect();
+    match pattern {
+        WavePattern::MiddleInHoriz | WavePattern::MiddleInVert | WavePattern::CircleIn => {
+            let furthest = distances.iter().copied().fold(0.0, f32::max);
+            distances.iter().map(|d| furthest - d).collect()
+        }
+        _ => distances,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::collection::Collection;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::vertex2d::Vertex2D;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_script() {
+        let script = "Sub Start\r\n\
+            \tLightSeq1.UpdateInterval = 10\r\n\
+            \tLightSeq1.Play SeqUpOn, 2, , 100 : LightSeq1.Play SeqBlinking,,3,50\r\n\
+            \t' LightSeq1.Play SeqAllOn\r\n\
+            \tOtherLightSeq1.Play SeqAllOn\r\n\
+            \tlightseq1.play(SeqScrewRightOn)\r\n\
+            End Sub";
+        assert_eq!(
+            script_steps(script, "LightSeq1"),
+            vec![
+                SequenceStep {
+                    animation: Animation::Wave {
+                        pattern: WavePattern::Up,
+                        on: true
+                    },
+                    tail_length: 2,
+                    repeat: 1,
+                    pause: 100,
+                },
+                SequenceStep {
+                    animation: Animation::Blinking,
+                    tail_length: 0,
+                    repeat: 3,
+                    pause: 50,
+                },
+                SequenceStep {
+                    animation: Animation::Other("SeqScrewRightOn".to_string()),
+                    tail_length: 0,
+                    repeat: 1,
+                    pause: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            Animation::from_name("seqcircleinoff"),
+            Animation::Wave {
+                pattern: WavePattern::CircleIn,
+                on: false
+            }
+        );
+    }
+
+    #[test]
+    fn evaluate_up_wave() {
+        let mut vpx = VPX::default();
+        for (name, y) in [("Top", 100.0), ("Bottom", 145.0)] {
+            let light = Light {
+                name: name.to_string(),
+                center: Vertex2D { x: 50.0, y },
+                ..Default::default()
+            };
+            vpx.add_game_item(GameItemEnum::Light(light));
+        }
+        vpx.collections.push(Collection {
+            name: "Lights".to_string(),
+            items: vec!["Top".to_string(), "Bottom".to_string()],
+            fire_events: false,
+            stop_single_events: false,
+            group_elements: false,
+        });
+        let mut sequencer = LightSequencer::default();
+        sequencer.collection = "lights".to_string();
+        sequencer.update_interval = 10;
+        let steps = script_steps(
+            "LightSeq1.Play SeqUpOn, 1\nLightSeq1.Play SeqUnknown\nLightSeq1.Play SeqAllOn,,,40",
+            "LightSeq1",
+        );
+
+        let timeline = evaluate(&vpx, &sequencer, &steps);
+
+        // the bottom light is reached first, the top one 2 updates later, each on for 1
+        assert_eq!(
+            timeline.lights[1].changes,
+            vec![(0, true), (10, false), (40, true), (80, false)]
+        );
+        assert_eq!(
+            timeline.lights[0].changes,
+            vec![(20, true), (30, false), (40, true), (80, false)]
+        );
+        assert!(timeline.lights[0].is_on(25));
+        assert!(!timeline.lights[0].is_on(35));
+        assert_eq!(timeline.duration, 80);
+        assert_eq!(timeline.skipped, vec!["SeqUnknown".to_string()]);
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt;
+use std::io;
 
+use crate::vpx::assetmeta::AssetMetadata;
 use crate::vpx::wav::{read_wav_header, write_wav_header, WavHeader};
 use bytes::{BufMut, BytesMut};
 use fake::Dummy;
@@ -86,8 +88,12 @@ impl<'de> Deserialize<'de> for OutputTarget {
     }
 }
 
+/// Volume, balance and fade are stored since this version
 const NEW_SOUND_FORMAT_VERSION: u32 = 1031;
 
+/// Non-wav sounds are expected to play from this version on, see [`check_writable`]
+const NON_WAV_SOUND_VERSION: u32 = 1070;
+
 impl fmt::Debug for SoundData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // avoid writing the data to the debug output
@@ -105,7 +111,7 @@ impl fmt::Debug for SoundData {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct SoundData {
     pub name: String,
     pub path: String,
@@ -124,7 +130,7 @@ pub struct SoundData {
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub(crate) struct SoundDataJson {
-    name: String,
+    pub(crate) name: String,
     path: String,
     internal_name: String,
     fade: u32,
@@ -134,6 +140,12 @@ pub(crate) struct SoundDataJson {
     // in case we have a duplicate name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) name_dedup: Option<String>,
+    // file name when it is not based on the name, see `WriteOptions::content_addressed_files`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) file: Option<String>,
+    // provenance, stored in the vpx file in an extra stream, see `crate::vpx::assetmeta`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) metadata: Option<AssetMetadata>,
 }
 
 impl SoundDataJson {
@@ -147,6 +159,8 @@ impl SoundDataJson {
             balance: sound_data.balance,
             output_target: sound_data.output_target.clone(),
             name_dedup: None,
+            file: None,
+            metadata: None,
         }
     }
     pub fn to_sound_data(&self) -> SoundData {
@@ -255,7 +269,7 @@ pub fn read_sound(data: &[u8], sound_data: &mut SoundData) {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct WaveForm {
     // Format type
     pub format_tag: u16,
@@ -297,13 +311,27 @@ impl Default for WaveForm {
 impl SoundData {
     pub(crate) fn ext(&self) -> String {
         // TODO we might want to also check the jpeg fsPath
-        match self.path.split('.').last() {
+        match self.path.split('.').next_back() {
             Some(ext) => ext.to_string(),
             None => "bin".to_string(),
         }
     }
 }
 
+/// Reads a sound, the stream is not a real BIFF record but a fixed list of values:
+///
+/// | value         | versions                                       |
+/// |---------------|------------------------------------------------|
+/// | name          | all                                            |
+/// | path          | all                                            |
+/// | internal name | all, an empty dummy in newer versions          |
+/// | wave form     | only for wav files, all sounds before 10.7     |
+/// | data          | all, a wav file without header for wav files   |
+/// | output target | all, a boolean "to backglass" before 1031      |
+/// | volume        | 1031 and up                                    |
+/// | balance       | 1031 and up                                    |
+/// | fade          | 1031 and up                                    |
+/// | volume        | 1031 and up                                    |
 pub(crate) fn read(file_version: &Version, reader: &mut BiffReader) -> SoundData {
     let mut name: String = "".to_string();
     let mut path: String = "".to_string();
@@ -394,6 +422,27 @@ fn is_wav(path: &str) -> bool {
     }
 }
 
+/// Checks that a sound is likely to play with a file version, for tools that convert tables
+/// to an older version.
+///
+/// Non-wav sounds (ogg, mp3) are reported for versions before 10.7. Writing a table does not
+/// run this check, sounds are always written back as they were read.
+pub fn check_writable(file_version: &Version, sound: &SoundData) -> io::Result<()> {
+    if file_version.u32() < NON_WAV_SOUND_VERSION && !is_wav(&sound.path) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Sound {} is not a wav file, this is not supported before version 10.7 (file version {})",
+                sound.name, file_version
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Writes a sound in the layout of the file version, see [`read`].
+///
+/// Volume, balance and fade are dropped for versions before 1031.
 pub(crate) fn write(file_version: &Version, sound: &SoundData, writer: &mut BiffWriter) {
     writer.write_string(&sound.name);
     writer.write_string(&sound.path);
@@ -506,6 +555,76 @@ mod test {
         assert_eq!(sound, sound_read);
     }
 
+    #[test]
+    fn test_write_read_biff_old_format() {
+        let sound: SoundData = SoundData {
+            name: "test name".to_string(),
+            path: "test path.wav".to_string(),
+            data: vec![1, 2, 3, 4],
+            wave_form: WaveForm::default(),
+            internal_name: "test internalname".to_string(),
+            fade: 1,
+            volume: 2,
+            balance: 3,
+            output_target: OutputTarget::Backglass,
+        };
+        let version = Version::new(1030);
+        let mut writer = BiffWriter::new();
+        write(&version, &sound, &mut writer);
+        let mut new_writer = BiffWriter::new();
+        write(&Version::new(1031), &sound, &mut new_writer);
+        assert_eq!(writer.get_data().len() + 16, new_writer.get_data().len());
+
+        let sound_read = read(&version, &mut BiffReader::new(writer.get_data()));
+        let expected = SoundData {
+            fade: 0,
+            volume: 0,
+            balance: 0,
+            ..sound
+        };
+        assert_eq!(sound_read, expected);
+    }
+
+    #[test]
+    fn test_check_writable() {
+        let ogg = sound_with_path("music.ogg");
+        assert!(check_writable(&Version::new(1070), &ogg).is_ok());
+        assert_eq!(
+            check_writable(&Version::new(1060), &ogg)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+        assert!(check_writable(&Version::new(1030), &sound_with_path("sound.wav")).is_ok());
+    }
+
+    #[test]
+    fn test_write_read_non_wav_old_version() {
+        let version = Version::new(1060);
+        let ogg = SoundData {
+            data: vec![1, 2, 3],
+            ..sound_with_path("music.ogg")
+        };
+        let mut writer = BiffWriter::new();
+        write(&version, &ogg, &mut writer);
+        let mut reader = BiffReader::new(writer.get_data());
+        assert_eq!(read(&version, &mut reader), ogg);
+    }
+
+    fn sound_with_path(path: &str) -> SoundData {
+        SoundData {
+            name: "test name".to_string(),
+            path: path.to_string(),
+            data: vec![],
+            wave_form: WaveForm::default(),
+            internal_name: "".to_string(),
+            fade: 0,
+            volume: 0,
+            balance: 0,
+            output_target: OutputTarget::Table,
+        }
+    }
+
     #[test]
     fn test_write_read_sound() {
         let data = vec![4, 3, 2, 1, 0];
@@ -0,0 +1,199 @@
+//! A spatial index over the items on the playfield
+//!
+//! Items are put in a grid of square cells by their [bounding box](super::bounds), so
+//! queries only look at the items in the cells they touch. Queries are in x and y, heights
+//! are ignored.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::spatial::Index;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let index = Index::build(&vpx);
+//! for (item, distance) in index.near_point([500.0, 1800.0], 50.0) {
+//!     println!("{} at {}", vpx.gameitems[item].name(), distance);
+//! }
+//! ```
+
+use super::bounds::{bounds, Aabb};
+use super::VPX;
+use std::collections::HashMap;
+
+/// Size of a grid cell in Visual Pinball Units, about the size of a flipper
+const CELL_SIZE: f32 = 100.0;
+/// Items covering more cells are kept in a separate list that every query checks
+const MAX_CELLS_PER_ITEM: i64 = 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    /// Index in [`VPX::gameitems`] and bounds of every item that has bounds
+    entries: Vec<(usize, Aabb)>,
+    /// Positions in `entries` per cell
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// Positions in `entries` of items that are too large for the grid
+    large: Vec<usize>,
+}
+
+impl Index {
+    /// Indexes all items that have [`bounds`], the index does not follow later changes
+    pub fn build(vpx: &VPX) -> Index {
+        let mut index = Index::default();
+        for (item_index, item) in vpx.gameitems.iter().enumerate() {
+            let Some(item_bounds) = bounds(item) else {
+                continue;
+            };
+            let entry = index.entries.len();
+            index.entries.push((item_index, item_bounds));
+            match cell_range(&item_bounds) {
+                Some(((x0, y0), (x1, y1)))
+                    if (x1 as i64 - x0 as i64 + 1) * (y1 as i64 - y0 as i64 + 1)
+                        <= MAX_CELLS_PER_ITEM =>
+                {
+                    for x in x0..=x1 {
+                        for y in y0..=y1 {
+                            index.cells.entry((x, y)).or_default().push(entry);
+                        }
+                    }
+                }
+                _ => index.large.push(entry),
+            }
+        }
+        index
+    }
+
+    /// Number of indexed items
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The bounds of an item by its index in [`VPX::gameitems`]
+    pub fn bounds(&self, item: usize) -> Option<Aabb> {
+        self.entries
+            .iter()
+            .find(|(index, _)| *index == item)
+            .map(|(_, bounds)| *bounds)
+    }
+
+    /// Items whose bounds overlap the region, as indices in [`VPX::gameitems`] in order
+    pub fn in_region(&self, region: &Aabb) -> Vec<usize> {
+        self.candidates(region)
+            .into_iter()
+            .filter(|(_, bounds)| bounds.intersects_xy(region))
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    /// Items whose bounds are at most `distance` away from a point, closest first.
+    ///
+    /// Returns the index in [`VPX::gameitems`] and the distance, which is 0 for items the
+    /// point lies on.
+    pub fn near_point(&self, point: [f32; 2], distance: f32) -> Vec<(usize, f32)> {
+        let region = Aabb {
+            min: [point[0] - distance, point[1] - distance, 0.0],
+            max: [point[0] + distance, point[1] + distance, 0.0],
+        };
+        let mut near: Vec<(usize, f32)> = self
+            .candidates(&region)
+            .into_iter()
+            .map(|(item, bounds)| (item, bounds.distance_xy(point)))
+            .filter(|(_, item_distance)| *item_distance <= distance)
+            .collect();
+        near.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        near
+    }
+
+    /// Items in the cells the region touches, sorted by item index without duplicates
+    fn candidates(&self, region: &Aabb) -> Vec<(usize, Aabb)> {
+        let mut entries = self.large.clone();
+        match cell_range(region) {
+            Some(((x0, y0), (x1, y1)))
+                if (x1 as i64 - x0 as i64 + 1) * (y1 as i64 - y0 as i64 + 1)
+                    <= self.cells.len() as i64 =>
+            {
+                for x in x0..=x1 {
+                    for y in y0..=y1 {
+                        if let Some(cell) = self.cells.get(&(x, y)) {
+                            entries.extend(cell);
+                        }
+                    }
+                }
+            }
+            // cheaper to check everything than to visit that many empty cells
+            _ => entries = (0..self.entries.len()).collect(),
+        }
+        entries.sort_unstable();
+        entries.dedup();
+        entries
+            .into_iter()
+            .map(|entry| self.entries[entry])
+            .collect()
+    }
+}
+
+/// The first and last cell a box touches, `None` for boxes that are not finite
+fn cell_range(bounds: &Aabb) -> Option<((i32, i32), (i32, i32))> {
+    let values = [bounds.min[0], bounds.min[1], bounds.max[0], bounds.max[1]];
+    if !values.iter().all(|value| value.is_finite()) {
+        return None;
+    }
+    let cell = |value: f32| (value / CELL_SIZE).floor() as i32;
+    Some((
+        (cell(bounds.min[0]), cell(bounds.min[1])),
+        (cell(bounds.max[0]), cell(bounds.max[1])),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::kicker::Kicker;
+    use crate::vpx::gameitem::timer::Timer;
+    use crate::vpx::gameitem::vertex2d::Vertex2D;
+    use crate::vpx::gameitem::GameItemEnum;
+    use pretty_assertions::assert_eq;
+
+    fn kicker(x: f32, y: f32) -> GameItemEnum {
+        let mut kicker = Kicker::default();
+        kicker.center = Vertex2D { x, y };
+        kicker.radius = 25.0;
+        GameItemEnum::Kicker(kicker)
+    }
+
+    #[test]
+    fn queries() {
+        let mut vpx = VPX::default();
+        vpx.add_game_item(kicker(100.0, 100.0));
+        vpx.add_game_item(GameItemEnum::Timer(Timer::default()));
+        vpx.add_game_item(kicker(400.0, 100.0));
+        vpx.add_game_item(kicker(130.0, 100.0));
+        vpx.add_game_item(kicker(f32::INFINITY, 100.0));
+
+        let index = Index::build(&vpx);
+
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.bounds(1), None);
+        assert_eq!(
+            index.near_point([100.0, 100.0], 10.0),
+            vec![(0, 0.0), (3, 5.0)]
+        );
+        assert_eq!(index.near_point([180.0, 100.0], 40.0), vec![(3, 25.0)]);
+        let region = Aabb {
+            min: [300.0, 0.0, 0.0],
+            max: [500.0, 200.0, 0.0],
+        };
+        assert_eq!(index.in_region(&region), vec![2]);
+        let everything = Aabb {
+            min: [-1e9, -1e9, 0.0],
+            max: [1e9, 1e9, 0.0],
+        };
+        // the kicker at infinity is indexed but never found
+        assert_eq!(index.in_region(&everything), vec![0, 2, 3]);
+    }
+}
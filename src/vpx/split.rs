@@ -0,0 +1,378 @@
+//! Splitting a vpx file into parts for sharing platforms that limit the file size
+//!
+//! Every part starts with a header that tells which file it belongs to, where the data goes
+//! and the hashes of both the part and the whole file. The parts can be joined in any order
+//! and missing, foreign or damaged parts are reported instead of producing a broken table.
+//!
+//! A part is laid out as the magic bytes [`PART_MAGIC`], the length of the header as a
+//! little endian `u32`, the header as json and then the data. Parts are named after the file
+//! with a three digit part number appended, eg `table.vpx.001`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//!
+//! let parts = vpx::split(&PathBuf::from("table.vpx"), 25 * 1024 * 1024).unwrap();
+//! // on the receiving side, after removing the original
+//! let joined = vpx::join(&parts).unwrap();
+//! assert_eq!(joined, PathBuf::from("table.vpx"));
+//! ```
+
+use super::Fnv64;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The first bytes of every part
+pub const PART_MAGIC: &[u8; 8] = b"VPXPART1";
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Headers are a few hundred bytes, anything larger is not a part
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// The header at the start of every part
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PartHeader {
+    /// File name of the original file, without directory
+    pub file_name: String,
+    /// Part number, starting at 1
+    pub part: u32,
+    pub parts: u32,
+    /// Position of the data of this part in the original file
+    pub offset: u64,
+    /// Length of the data in this part
+    pub len: u64,
+    pub file_len: u64,
+    /// 64 bit FNV-1a of the data in this part, formatted as hex
+    pub part_hash: String,
+    /// 64 bit FNV-1a of the original file, formatted as hex
+    pub file_hash: String,
+}
+
+/// Splits a file into parts of at most `chunk_size` bytes of data, written next to it.
+///
+/// The header makes every part a bit larger than `chunk_size`. Returns the paths of the parts
+/// in order. Existing parts are overwritten.
+pub fn split<P: AsRef<Path>>(path: P, chunk_size: u64) -> io::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if chunk_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Chunk size must be larger than 0",
+        ));
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a file: {}", path.display()),
+            )
+        })?;
+
+    // first pass for the hashes, they are needed in every header
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut file_hash = Fnv64::new();
+    let mut part_hashes = Vec::new();
+    let mut file_len = 0;
+    loop {
+        let mut part_hash = Fnv64::new();
+        let len = copy_hashed(
+            &mut reader,
+            chunk_size,
+            &mut [&mut file_hash, &mut part_hash],
+        )?;
+        // an empty file still gets one part
+        if len == 0 && !part_hashes.is_empty() {
+            break;
+        }
+        part_hashes.push((len, part_hash.finish()));
+        file_len += len;
+        if len < chunk_size {
+            break;
+        }
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let parts = part_hashes.len() as u32;
+    let mut offset = 0;
+    let mut paths = Vec::with_capacity(part_hashes.len());
+    for (index, (len, part_hash)) in part_hashes.into_iter().enumerate() {
+        let header = PartHeader {
+            file_name: file_name.clone(),
+            part: index as u32 + 1,
+            parts,
+            offset,
+            len,
+            file_len,
+            part_hash: format!("{:016x}", part_hash),
+            file_hash: format!("{:016x}", file_hash.finish()),
+        };
+        let part_path = path.with_file_name(format!("{}.{:03}", file_name, header.part));
+        let mut writer = BufWriter::new(File::create(&part_path)?);
+        write_header(&mut writer, &header)?;
+        let copied = io::copy(&mut (&mut reader).take(len), &mut writer)?;
+        if copied != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} changed while splitting", path.display()),
+            ));
+        }
+        writer.flush()?;
+        offset += len;
+        paths.push(part_path);
+    }
+    Ok(paths)
+}
+
+/// Joins the parts created by [`split`], they can be passed in any order.
+///
+/// The file is written next to the first part with its original name, which fails if that
+/// file already exists. All parts have to be present and their hashes have to match. Returns
+/// the path of the joined file.
+pub fn join<P: AsRef<Path>>(parts: &[P]) -> io::Result<PathBuf> {
+    let first = parts
+        .first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No parts to join"))?;
+    let mut headers = parts
+        .iter()
+        .map(|part| Ok((read_part_header(part.as_ref())?, part.as_ref())))
+        .collect::<io::Result<Vec<_>>>()?;
+    headers.sort_by_key(|(header, _)| header.part);
+
+    let (expected, _) = &headers[0];
+    let mut offset = 0;
+    for (index, (header, path)) in headers.iter().enumerate() {
+        if header.file_name != expected.file_name
+            || header.file_hash != expected.file_hash
+            || header.parts != expected.parts
+        {
+            return Err(invalid(format!(
+                "{} is a part of another file",
+                path.display()
+            )));
+        }
+        if header.part != index as u32 + 1 || header.offset != offset {
+            return Err(invalid(format!(
+                "Part {} of {} is missing",
+                index + 1,
+                expected.file_name
+            )));
+        }
+        offset += header.len;
+    }
+    if headers.len() as u32 != expected.parts || offset != expected.file_len {
+        return Err(invalid(format!(
+            "Part {} of {} is missing",
+            headers.len() + 1,
+            expected.file_name
+        )));
+    }
+
+    let file_name = Path::new(&expected.file_name)
+        .file_name()
+        .ok_or_else(|| invalid(format!("Invalid file name {}", expected.file_name)))?;
+    let out_path = first.as_ref().with_file_name(file_name);
+    let out_file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&out_path)?;
+    if let Err(error) = join_parts(&headers, out_file) {
+        std::fs::remove_file(&out_path)?;
+        return Err(error);
+    }
+    Ok(out_path)
+}
+
+/// Reads the header of a part
+pub fn read_part_header<P: AsRef<Path>>(path: P) -> io::Result<PartHeader> {
+    let path = path.as_ref();
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader, path)
+}
+
+fn join_parts(headers: &[(PartHeader, &Path)], out_file: File) -> io::Result<()> {
+    let mut writer = BufWriter::new(out_file);
+    let mut file_hash = Fnv64::new();
+    for (header, path) in headers {
+        let mut reader = BufReader::new(File::open(path)?);
+        read_header(&mut reader, path)?;
+        let mut part_hash = Fnv64::new();
+        let mut hashing = HashingWriter {
+            inner: &mut writer,
+            hashes: [&mut file_hash, &mut part_hash],
+        };
+        let len = io::copy(&mut (&mut reader).take(header.len), &mut hashing)?;
+        if len != header.len || format!("{:016x}", part_hash.finish()) != header.part_hash {
+            return Err(invalid(format!("{} is damaged", path.display())));
+        }
+    }
+    writer.flush()?;
+    let expected = &headers[0].0;
+    if format!("{:016x}", file_hash.finish()) != expected.file_hash {
+        return Err(invalid(format!(
+            "Joined {} does not match the original",
+            expected.file_name
+        )));
+    }
+    Ok(())
+}
+
+fn write_header<W: Write>(writer: &mut W, header: &PartHeader) -> io::Result<()> {
+    let json = serde_json::to_vec(header)?;
+    writer.write_all(PART_MAGIC)?;
+    writer.write_all(&(json.len() as u32).to_le_bytes())?;
+    writer.write_all(&json)
+}
+
+fn read_header<R: Read>(reader: &mut R, path: &Path) -> io::Result<PartHeader> {
+    let not_a_part = || invalid(format!("{} is not a vpx part", path.display()));
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic).map_err(|_| not_a_part())?;
+    if &magic != PART_MAGIC {
+        return Err(not_a_part());
+    }
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_HEADER_SIZE {
+        return Err(not_a_part());
+    }
+    let mut json = vec![0u8; len];
+    reader.read_exact(&mut json)?;
+    serde_json::from_slice(&json).map_err(|_| not_a_part())
+}
+
+/// Reads up to `len` bytes, returns the number of bytes read
+fn copy_hashed<R: Read>(reader: &mut R, len: u64, hashes: &mut [&mut Fnv64]) -> io::Result<u64> {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0;
+    let mut limited = reader.take(len);
+    loop {
+        let read = limited.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(total);
+        }
+        hashes
+            .iter_mut()
+            .for_each(|hash| hash.write(&buffer[..read]));
+        total += read as u64;
+    }
+}
+
+struct HashingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hashes: [&'a mut Fnv64; 2],
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hashes
+            .iter_mut()
+            .for_each(|hash| hash.write(&buf[..written]));
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn split_and_join() -> io::Result<()> {
+        let dir = testdir!();
+        let original = std::fs::read("testdata/completely_blank_table_10_7_4.vpx")?;
+        let path = dir.join("table.vpx");
+        std::fs::write(&path, &original)?;
+
+        let chunk_size = original.len() as u64 / 3 + 1;
+        let parts = split(&path, chunk_size)?;
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], dir.join("table.vpx.001"));
+        let header = read_part_header(&parts[2])?;
+        assert_eq!(header.part, 3);
+        assert_eq!(header.offset, 2 * chunk_size);
+        assert_eq!(header.file_len, original.len() as u64);
+
+        std::fs::remove_file(&path)?;
+        let reversed: Vec<PathBuf> = parts.iter().rev().cloned().collect();
+        assert_eq!(join(&reversed)?, path);
+        assert_eq!(std::fs::read(&path)?, original);
+
+        // the joined file is not overwritten
+        let error = join(&parts).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert!(path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn join_incomplete_or_damaged() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("table.vpx");
+        std::fs::write(&path, (0..100u8).collect::<Vec<_>>())?;
+        let parts = split(&path, 40)?;
+        std::fs::remove_file(&path)?;
+
+        let error = join(&parts[..2]).unwrap_err();
+        assert_eq!(error.to_string(), "Part 3 of table.vpx is missing");
+        let error = join(&[&parts[0], &parts[2]]).unwrap_err();
+        assert_eq!(error.to_string(), "Part 2 of table.vpx is missing");
+
+        let mut damaged = std::fs::read(&parts[1])?;
+        *damaged.last_mut().unwrap() ^= 0xff;
+        std::fs::write(&parts[1], damaged)?;
+        let error = join(&parts).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{} is damaged", parts[1].display())
+        );
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn split_empty_file() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("empty.vpx");
+        std::fs::write(&path, [])?;
+        let parts = split(&path, 10)?;
+        assert_eq!(parts.len(), 1);
+        std::fs::remove_file(&path)?;
+        join(&parts)?;
+        assert_eq!(std::fs::read(&path)?, Vec::<u8>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn read_header_with_huge_length() -> io::Result<()> {
+        let dir = testdir!();
+        let path = dir.join("table.vpx.001");
+        let mut part = PART_MAGIC.to_vec();
+        part.extend_from_slice(&u32::MAX.to_le_bytes());
+        part.extend_from_slice(b"{}");
+        std::fs::write(&path, part)?;
+        let error = read_part_header(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{} is not a vpx part", path.display())
+        );
+        Ok(())
+    }
+}
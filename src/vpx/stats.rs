@@ -0,0 +1,205 @@
+//! Cheap estimates of the geometry a table needs, without generating meshes
+//!
+//! The counts follow the mesh generators in [`crate::vpx::mesh`], so they match what an
+//! export would produce.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::stats::table_geometry_estimate;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let estimate = table_geometry_estimate(&vpx);
+//! println!("{} triangles", estimate.triangles);
+//! ```
+
+use super::gameitem::dragpoint::DragPoint;
+use super::gameitem::ramp::RampType;
+use super::gameitem::GameItemEnum;
+use super::mesh::{spline, TUBE_SIDES};
+use super::VPX;
+use std::ops::Add;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct GeometryEstimate {
+    pub vertices: usize,
+    pub triangles: usize,
+}
+
+impl Add for GeometryEstimate {
+    type Output = GeometryEstimate;
+
+    fn add(self, other: GeometryEstimate) -> GeometryEstimate {
+        GeometryEstimate {
+            vertices: self.vertices + other.vertices,
+            triangles: self.triangles + other.triangles,
+        }
+    }
+}
+
+/// Estimates the geometry of a single item.
+///
+/// Returns `None` for item types without a mesh generator. Primitives with a 3D mesh use
+/// the vertex and index counts stored in the table.
+pub fn geometry_estimate(item: &GameItemEnum) -> Option<GeometryEstimate> {
+    let estimate = match item {
+        GameItemEnum::Wall(wall) => {
+            // sides with 4 vertices per segment and a triangulated top
+            let n = spline_points(&wall.drag_points, true);
+            if n < 3 {
+                return Some(GeometryEstimate::default());
+            }
+            GeometryEstimate {
+                vertices: 4 * n + n,
+                triangles: 2 * n + n - 2,
+            }
+        }
+        GameItemEnum::Rubber(rubber) => {
+            let n = spline_points(&rubber.drag_points, true);
+            if n < 2 {
+                return Some(GeometryEstimate::default());
+            }
+            GeometryEstimate {
+                vertices: n * TUBE_SIDES,
+                triangles: 2 * n * TUBE_SIDES,
+            }
+        }
+        GameItemEnum::Ramp(ramp) => {
+            let n = spline_points(&ramp.drag_points, false);
+            if n < 2 {
+                return Some(GeometryEstimate::default());
+            }
+            let (parts, per_part) = match &ramp.ramp_type {
+                RampType::Flat => {
+                    let walls = [
+                        ramp.left_wall_height_visible,
+                        ramp.right_wall_height_visible,
+                    ]
+                    .iter()
+                    .filter(|height| **height > 0.0)
+                    .count();
+                    let strip = GeometryEstimate {
+                        vertices: 2 * n,
+                        triangles: 2 * (n - 1),
+                    };
+                    (1 + walls, strip)
+                }
+                ramp_type => {
+                    let wires = match ramp_type {
+                        RampType::OneWire => 1,
+                        RampType::TwoWire => 2,
+                        RampType::ThreeWireLeft | RampType::ThreeWireRight => 3,
+                        _ => 4,
+                    };
+                    // tube with a cap at both ends
+                    let wire = GeometryEstimate {
+                        vertices: n * TUBE_SIDES + 2,
+                        triangles: 2 * (n - 1) * TUBE_SIDES + 2 * TUBE_SIDES,
+                    };
+                    (wires, wire)
+                }
+            };
+            GeometryEstimate {
+                vertices: parts * per_part.vertices,
+                triangles: parts * per_part.triangles,
+            }
+        }
+        GameItemEnum::Flasher(flasher) => {
+            let n = spline_points(&flasher.drag_points, true);
+            GeometryEstimate {
+                vertices: n,
+                triangles: n.saturating_sub(2),
+            }
+        }
+        GameItemEnum::Primitive(primitive) if primitive.use_3d_mesh => GeometryEstimate {
+            vertices: primitive.num_vertices.unwrap_or(0) as usize,
+            triangles: primitive.num_indices.unwrap_or(0) as usize / 3,
+        },
+        GameItemEnum::Primitive(primitive) => {
+            // the built-in shape Visual Pinball generates from the number of sides
+            let sides = primitive.sides as usize;
+            GeometryEstimate {
+                vertices: 4 * sides + 2,
+                triangles: 4 * sides,
+            }
+        }
+        _ => return None,
+    };
+    Some(estimate)
+}
+
+/// The sum of the estimates of all items, items without an estimate are skipped
+pub fn table_geometry_estimate(vpx: &VPX) -> GeometryEstimate {
+    vpx.gameitems
+        .iter()
+        .filter_map(geometry_estimate)
+        .fold(GeometryEstimate::default(), Add::add)
+}
+
+fn spline_points(points: &[DragPoint], closed: bool) -> usize {
+    let mut points = spline(points, closed);
+    points.dedup();
+    points.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::light::Light;
+    use crate::vpx::gameitem::ramp::Ramp;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::mesh;
+    use pretty_assertions::assert_eq;
+
+    fn points(coordinates: &[(f32, f32)]) -> Vec<DragPoint> {
+        coordinates
+            .iter()
+            .map(|&(x, y)| DragPoint {
+                x,
+                y,
+                smooth: true,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimates_match_generated_meshes() {
+        let mut vpx = VPX::default();
+        let mut ramp = Ramp {
+            ramp_type: RampType::ThreeWireLeft,
+            ..Default::default()
+        };
+        ramp.drag_points = points(&[(0.0, 100.0), (50.0, 50.0), (0.0, 0.0)]);
+        let ramp_mesh = mesh::ramp::ramp(&vpx, &ramp);
+        let mut wall = Wall {
+            is_collidable: true,
+            ..Default::default()
+        };
+        wall.drag_points = points(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        vpx.add_game_item(GameItemEnum::Ramp(ramp));
+        vpx.add_game_item(GameItemEnum::Wall(wall));
+        vpx.add_game_item(GameItemEnum::Light(Light::default()));
+        let wall_mesh = &mesh::collision::colliders(&vpx).unwrap()[0].mesh;
+
+        let estimates: Vec<Option<GeometryEstimate>> =
+            vpx.gameitems.iter().map(geometry_estimate).collect();
+
+        let from_mesh = |mesh: &mesh::Mesh| {
+            Some(GeometryEstimate {
+                vertices: mesh.vertices.len(),
+                triangles: mesh.triangle_count(),
+            })
+        };
+        assert_eq!(
+            estimates,
+            vec![from_mesh(&ramp_mesh), from_mesh(wall_mesh), None]
+        );
+        assert_eq!(
+            table_geometry_estimate(&vpx),
+            estimates[0].unwrap() + estimates[1].unwrap()
+        );
+    }
+}
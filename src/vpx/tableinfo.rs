@@ -11,6 +11,19 @@ use utf16string::{LittleEndian, WStr, WString};
 // >    "/TableInfo/AuthorEmail",
 // >    "/TableInfo/ReleaseDate",
 
+/// How the strings in the table info are decoded
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TableInfoEncoding {
+    /// UTF-16 like the editor writes, falling back to UTF-8 or Windows-1252 for tables
+    /// written by old or third party tools.
+    #[default]
+    Auto,
+    Utf16Le,
+    Utf8,
+    /// Also used for Latin-1, the two only differ in rarely used control characters
+    Windows1252,
+}
+
 #[derive(PartialEq, Debug)]
 pub struct TableInfo {
     pub table_name: Option<String>,
@@ -57,6 +70,104 @@ impl Default for TableInfo {
     }
 }
 
+/// A table info text that can have translations.
+///
+/// Translations are stored as custom properties named after the field with the language
+/// appended, eg `TableRules.de` or `TableDescription.pt-br`, see [`localized_key`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LocalizedField {
+    Description,
+    Rules,
+    Blurb,
+}
+
+impl LocalizedField {
+    /// The name of the table info stream with the untranslated text
+    pub fn stream_name(&self) -> &'static str {
+        match self {
+            LocalizedField::Description => "TableDescription",
+            LocalizedField::Rules => "TableRules",
+            LocalizedField::Blurb => "TableBlurb",
+        }
+    }
+}
+
+/// The custom property name for a translation, languages are lowercase, eg `TableRules.de`
+pub fn localized_key(field: LocalizedField, lang: &str) -> String {
+    format!("{}.{}", field.stream_name(), lang.to_lowercase())
+}
+
+impl TableInfo {
+    /// The description in a language, see [`TableInfo::localized`]
+    pub fn description(&self, lang: &str) -> Option<&str> {
+        self.localized(LocalizedField::Description, lang)
+    }
+
+    /// The rules in a language, see [`TableInfo::localized`]
+    pub fn rules(&self, lang: &str) -> Option<&str> {
+        self.localized(LocalizedField::Rules, lang)
+    }
+
+    /// The blurb in a language, see [`TableInfo::localized`]
+    pub fn blurb(&self, lang: &str) -> Option<&str> {
+        self.localized(LocalizedField::Blurb, lang)
+    }
+
+    /// The text in a language like `de` or `pt-BR`, ignoring case.
+    ///
+    /// Falls back to the main language (`pt` for `pt-BR`) and then to the untranslated text.
+    pub fn localized(&self, field: LocalizedField, lang: &str) -> Option<&str> {
+        let mut candidates = vec![localized_key(field, lang)];
+        if let Some((main, _)) = lang.split_once(['-', '_']) {
+            candidates.push(localized_key(field, main));
+        }
+        candidates
+            .iter()
+            .find_map(|key| self.properties.get(key))
+            .or(match field {
+                LocalizedField::Description => self.table_description.as_ref(),
+                LocalizedField::Rules => self.table_rules.as_ref(),
+                LocalizedField::Blurb => self.table_blurb.as_ref(),
+            })
+            .map(String::as_str)
+    }
+
+    /// Sets a translation, returns the custom property name that was used.
+    ///
+    /// The name also has to be added to [`crate::vpx::VPX::custominfotags`] for Visual
+    /// Pinball to show it, [`crate::vpx::VPX::set_localized_info`] does both.
+    pub fn set_localized(&mut self, field: LocalizedField, lang: &str, text: String) -> String {
+        let key = localized_key(field, lang);
+        self.properties.insert(key.clone(), text);
+        key
+    }
+
+    /// The languages that have a translation for any of the fields, sorted
+    pub fn languages(&self) -> Vec<String> {
+        let fields = [
+            LocalizedField::Description,
+            LocalizedField::Rules,
+            LocalizedField::Blurb,
+        ];
+        let mut languages: Vec<String> = self
+            .properties
+            .keys()
+            .filter_map(|key| {
+                let (name, lang) = key.split_once('.')?;
+                fields
+                    .iter()
+                    .any(|field| field.stream_name() == name)
+                    .then(|| lang.to_string())
+            })
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+}
+
+/// Strings are always written as UTF-16 like the editor does, whatever encoding they were
+/// read with.
 pub(crate) fn write_tableinfo<F: Read + Write + Seek>(
     comp: &mut CompoundFile<F>,
     table_info: &TableInfo,
@@ -208,6 +319,13 @@ pub(crate) fn write_tableinfo<F: Read + Write + Seek>(
 
 pub(crate) fn read_tableinfo<F: Read + Write + Seek>(
     comp: &mut CompoundFile<F>,
+) -> std::io::Result<TableInfo> {
+    read_tableinfo_with_encoding(comp, TableInfoEncoding::Auto)
+}
+
+pub(crate) fn read_tableinfo_with_encoding<F: Read + Write + Seek>(
+    comp: &mut CompoundFile<F>,
+    encoding: TableInfoEncoding,
 ) -> std::io::Result<TableInfo> {
     // create path to table info using path separator
     let table_info_path = Path::new(MAIN_SEPARATOR_STR).join("TableInfo");
@@ -231,46 +349,35 @@ pub(crate) fn read_tableinfo<F: Read + Write + Seek>(
                 .map(|s| s.to_str().unwrap_or("[not unicode]"))
                 .unwrap_or("..");
             match file_name {
-                "TableName" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_name = Some(s))
-                }
-                "AuthorName" => {
-                    read_stream_string(comp, path).map(|s| table_info.author_name = Some(s))
-                }
+                "TableName" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_name = Some(s)),
+                "AuthorName" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.author_name = Some(s)),
                 "Screenshot" => {
                     // seems to be a full image file, eg if there is no jpeg data in the image this is a full png
                     // but how do we know the extension?
                     read_stream_binary(comp, path).map(|v| table_info.screenshot = Some(v))
                 }
-                "TableBlurb" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_blurb = Some(s))
-                }
-                "TableRules" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_rules = Some(s))
-                }
-                "AuthorEmail" => {
-                    read_stream_string(comp, path).map(|s| table_info.author_email = Some(s))
-                }
-                "ReleaseDate" => {
-                    read_stream_string(comp, path).map(|s| table_info.release_date = Some(s))
-                }
-                "TableSaveRev" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_save_rev = Some(s))
-                }
-                "TableVersion" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_version = Some(s))
-                }
-                "AuthorWebSite" => {
-                    read_stream_string(comp, path).map(|s| table_info.author_website = Some(s))
-                }
-                "TableSaveDate" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_save_date = Some(s))
-                }
-                "TableDescription" => {
-                    read_stream_string(comp, path).map(|s| table_info.table_description = Some(s))
-                }
+                "TableBlurb" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_blurb = Some(s)),
+                "TableRules" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_rules = Some(s)),
+                "AuthorEmail" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.author_email = Some(s)),
+                "ReleaseDate" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.release_date = Some(s)),
+                "TableSaveRev" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_save_rev = Some(s)),
+                "TableVersion" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_version = Some(s)),
+                "AuthorWebSite" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.author_website = Some(s)),
+                "TableSaveDate" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_save_date = Some(s)),
+                "TableDescription" => read_stream_string(comp, path, encoding)
+                    .map(|s| table_info.table_description = Some(s)),
                 other => {
-                    let str = read_stream_string(comp, path)?;
+                    let str = read_stream_string(comp, path, encoding)?;
                     table_info.properties.insert(other.to_string(), str);
                     Ok(())
                 }
@@ -284,20 +391,94 @@ pub(crate) fn read_tableinfo<F: Read + Write + Seek>(
 fn read_stream_string<F: Read + Write + Seek>(
     comp: &mut CompoundFile<F>,
     path: &Path,
+    encoding: TableInfoEncoding,
 ) -> Result<String, std::io::Error> {
     let mut stream = comp.open_stream(path).unwrap();
     let mut buffer = Vec::new();
     stream.read_to_end(&mut buffer).unwrap();
 
-    match WStr::from_utf16le(&buffer) {
-        Ok(str) => Ok(str.to_utf8()),
-        Err(e) => Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Error reading stream as utf16le for path: ".to_owned()
+    decode_string(&buffer, encoding).map_err(|e| {
+        std::io::Error::other(
+            "Error reading stream as ".to_owned()
+                + &format!("{:?}", encoding).to_lowercase()
+                + " for path: "
                 + path.to_str().unwrap_or("[not unicode]")
                 + " "
                 + &e.to_string(),
-        )),
+        )
+    })
+}
+
+/// Decodes a table info string.
+///
+/// With [`TableInfoEncoding::Auto`] a byte order mark decides, otherwise the data is
+/// UTF-16 unless it is not valid UTF-16, decodes to private use characters or looks like
+/// single byte text: no control characters other than tabs and line breaks, so no zero
+/// bytes which UTF-16 encoded ASCII always has, and either valid UTF-8 or at least three
+/// quarters ASCII. UTF-16 text without any ASCII, like Cyrillic, has no zero bytes but
+/// decodes to control characters as single byte text. UTF-16 CJK text can look like single
+/// byte text, so data that is not valid UTF-8 but decodes to UTF-16 without control
+/// characters is UTF-16 too. Short Windows-1252 text like `Über` is then misread, which a
+/// byte order mark avoids. Single byte text is decoded as UTF-8 when valid and as
+/// Windows-1252 otherwise.
+pub fn decode_string(bytes: &[u8], encoding: TableInfoEncoding) -> std::io::Result<String> {
+    let utf16 = |bytes: &[u8]| {
+        WStr::from_utf16le(bytes)
+            .map(|str| str.to_utf8())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    };
+    let utf8 = |bytes: &[u8]| {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    };
+    let windows_1252 = |bytes: &[u8]| bytes.iter().map(|b| windows_1252_char(*b)).collect();
+    match encoding {
+        TableInfoEncoding::Utf16Le => utf16(bytes),
+        TableInfoEncoding::Utf8 => utf8(bytes),
+        TableInfoEncoding::Windows1252 => Ok(windows_1252(bytes)),
+        TableInfoEncoding::Auto => {
+            if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+                return utf16(rest);
+            }
+            if let Some(rest) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+                return Ok(utf8(rest).unwrap_or_else(|_| windows_1252(rest)));
+            }
+            let valid_utf8 = std::str::from_utf8(bytes).is_ok();
+            let mostly_ascii = bytes.iter().filter(|b| b.is_ascii()).count() * 4 >= bytes.len() * 3;
+            let control = bytes
+                .iter()
+                .any(|b| *b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r'));
+            let single_byte = !control && (valid_utf8 || mostly_ascii);
+            let utf16_text = utf16(bytes).ok().filter(|decoded| {
+                bytes.len().is_multiple_of(2)
+                    && !decoded
+                        .chars()
+                        .any(|c| ('\u{e000}'..='\u{f8ff}').contains(&c))
+            });
+            let utf16_printable = utf16_text.as_ref().is_some_and(|decoded| {
+                !decoded
+                    .chars()
+                    .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+            });
+            match utf16_text {
+                Some(decoded) if !single_byte || (utf16_printable && !valid_utf8) => Ok(decoded),
+                _ if valid_utf8 => utf8(bytes),
+                _ => Ok(windows_1252(bytes)),
+            }
+        }
+    }
+}
+
+/// Windows-1252 is Latin-1 with printable characters instead of the C1 control codes
+pub(crate) fn windows_1252_char(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž',
+        '\u{8f}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9f => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
     }
 }
 
@@ -364,6 +545,107 @@ mod tests {
         assert_eq!(table_info_read, table_info);
     }
 
+    #[test]
+    fn test_localized() {
+        let mut table_info = TableInfo {
+            table_rules: Some("rules".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            table_info.set_localized(LocalizedField::Rules, "DE", "Regeln".to_string()),
+            "TableRules.de"
+        );
+        table_info.set_localized(
+            LocalizedField::Description,
+            "pt-BR",
+            "descrição".to_string(),
+        );
+        table_info
+            .properties
+            .insert("Other.fr".to_string(), "autre".to_string());
+
+        assert_eq!(table_info.rules("de"), Some("Regeln"));
+        assert_eq!(table_info.rules("de-AT"), Some("Regeln"));
+        assert_eq!(table_info.rules("fr"), Some("rules"));
+        assert_eq!(table_info.description("pt-br"), Some("descrição"));
+        assert_eq!(table_info.description("pt"), None);
+        assert_eq!(table_info.blurb("de"), None);
+        assert_eq!(table_info.languages(), vec!["de", "pt-br"]);
+    }
+
+    #[test]
+    fn test_decode_string() {
+        let utf16: Vec<u8> = "Café 日本"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let auto = TableInfoEncoding::Auto;
+        assert_eq!(decode_string(&utf16, auto).unwrap(), "Café 日本");
+        assert_eq!(
+            decode_string(&[0xff, 0xfe, b'h', 0, b'i', 0], auto).unwrap(),
+            "hi"
+        );
+        // single byte text from old tables
+        assert_eq!(decode_string(b"Table", auto).unwrap(), "Table");
+        assert_eq!(
+            decode_string(b"Caf\xe9 \x93x\x94", auto).unwrap(),
+            "Café “x”"
+        );
+        assert_eq!(decode_string("Café".as_bytes(), auto).unwrap(), "Café");
+        assert_eq!(
+            decode_string(b"Caf\xe9", TableInfoEncoding::Utf16Le).unwrap(),
+            "\u{6143}\u{e966}"
+        );
+        assert!(decode_string(b"abc", TableInfoEncoding::Utf16Le).is_err());
+    }
+
+    #[test]
+    fn test_decode_string_utf16_without_ascii() {
+        for text in ["Иван", "Γεια", "Иван Петров"] {
+            let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            assert_eq!(
+                decode_string(&utf16, TableInfoEncoding::Auto).unwrap(),
+                text
+            );
+        }
+        assert_eq!(
+            decode_string(b"Line 1\r\n\tLine 2", TableInfoEncoding::Auto).unwrap(),
+            "Line 1\r\n\tLine 2"
+        );
+    }
+
+    #[test]
+    fn test_decode_string_utf16_cjk() {
+        for text in ["中文", "日本", "日本語のテーブル", "中文 2"] {
+            let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            assert_eq!(
+                decode_string(&utf16, TableInfoEncoding::Auto).unwrap(),
+                text
+            );
+        }
+        // valid UTF-8 stays single byte text even when it is valid UTF-16
+        assert_eq!(
+            decode_string(b"Test", TableInfoEncoding::Auto).unwrap(),
+            "Test"
+        );
+    }
+
+    #[test]
+    fn test_read_latin1() {
+        let buff = Cursor::new(vec![0; 15]);
+        let mut comp = CompoundFile::create(buff).unwrap();
+        let table_info_path = Path::new(MAIN_SEPARATOR_STR).join("TableInfo");
+        comp.create_storage(&table_info_path).unwrap();
+        write_stream_binary(&mut comp, &table_info_path.join("AuthorName"), b"J\xf6rg").unwrap();
+
+        let table_info = read_tableinfo(&mut comp).unwrap();
+        assert_eq!(table_info.author_name, Some("Jörg".to_string()));
+
+        let table_info =
+            read_tableinfo_with_encoding(&mut comp, TableInfoEncoding::Utf8).unwrap_err();
+        assert_eq!(table_info.kind(), std::io::ErrorKind::Other);
+    }
+
     // #[test]
     // fn test_bad_add() {
     //     // This assert would fire and test will fail.
@@ -0,0 +1,114 @@
+//! Starter tables to scaffold a new table from
+//!
+//! The templates are json files with the game items, in the format of the expanded
+//! `gameitems` files, and a script. They are embedded in the library, [`instantiate`] builds
+//! a table from them with [`VpxBuilder`].
+//!
+//! # Example
+//!
+//! ```
+//! use vpin::vpx::templates::{instantiate, TemplateKind};
+//!
+//! let vpx = instantiate(TemplateKind::BlankSolidState);
+//! for item in &vpx.gameitems {
+//!     println!("{}", item.name());
+//! }
+//! ```
+
+use super::gameitem::GameItemEnum;
+use super::{VpxBuilder, VPX};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TemplateKind {
+    /// An empty playfield with a plunger, ball release and drain, scripted without a ROM
+    BlankEm,
+    /// [`TemplateKind::BlankEm`] with flippers and slingshots, and the controller setup for a
+    /// ROM commented out in the script
+    BlankSolidState,
+}
+
+impl TemplateKind {
+    pub const ALL: [TemplateKind; 2] = [TemplateKind::BlankEm, TemplateKind::BlankSolidState];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TemplateKind::BlankEm => "Blank electro-mechanical table",
+            TemplateKind::BlankSolidState => "Blank solid state table",
+        }
+    }
+
+    fn gameitems_json(&self) -> &'static str {
+        match self {
+            TemplateKind::BlankEm => include_str!("templates/blank_em.json"),
+            TemplateKind::BlankSolidState => include_str!("templates/blank_ss.json"),
+        }
+    }
+
+    fn script(&self) -> &'static str {
+        match self {
+            TemplateKind::BlankEm => include_str!("templates/blank_em.vbs"),
+            TemplateKind::BlankSolidState => include_str!("templates/blank_ss.vbs"),
+        }
+    }
+}
+
+/// Creates a new table from a template
+pub fn instantiate(kind: TemplateKind) -> VPX {
+    let gameitems: Vec<GameItemEnum> =
+        serde_json::from_str(kind.gameitems_json()).expect("embedded templates are valid");
+    gameitems
+        .into_iter()
+        .fold(
+            VpxBuilder::new()
+                .table_name(kind.description())
+                .script(kind.script()),
+            |builder, item| builder.game_item(item),
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::events::{script_events, EventIssue};
+    use crate::vpx::{read, write};
+    use pretty_assertions::assert_eq;
+    use testdir::testdir;
+
+    #[test]
+    fn instantiate_all() -> std::io::Result<()> {
+        let dir = testdir!();
+        for kind in TemplateKind::ALL {
+            let vpx = instantiate(kind);
+            assert_eq!(vpx.gamedata.gameitems_size as usize, vpx.gameitems.len());
+            // every item the script handles events for exists
+            assert!(!script_events(&vpx)
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, EventIssue::OrphanHandler { .. })));
+
+            let path = dir.join(format!("{:?}.vpx", kind));
+            write(&path, &vpx)?;
+            assert_eq!(read(&path)?, vpx);
+        }
+        let solid_state = instantiate(TemplateKind::BlankSolidState);
+        let names: Vec<&str> = solid_state
+            .gameitems
+            .iter()
+            .map(|item| item.name())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "LeftFlipper",
+                "RightFlipper",
+                "LeftSlingshot",
+                "RightSlingshot",
+                "Plunger",
+                "BallRelease",
+                "Drain",
+            ]
+        );
+        Ok(())
+    }
+}
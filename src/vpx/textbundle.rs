@@ -0,0 +1,272 @@
+//! A single plain text document describing a table, for code review and text based tooling
+//!
+//! The bundle contains the table info, the materials, the script and a summary of the
+//! properties of every game item. Images, sounds, meshes and other binary data are left out.
+//! Collections of values like drag points are summarized by their length.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::textbundle::{text_bundle, TextBundleOptions};
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let options = TextBundleOptions {
+//!     max_bytes: Some(64 * 1024),
+//! };
+//! let bundle = text_bundle(&vpx, &options);
+//! assert!(bundle.len() <= 64 * 1024);
+//! println!("{}", bundle);
+//! ```
+
+use super::math::canonical_f64;
+use super::VPX;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Options for [`text_bundle`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextBundleOptions {
+    /// Cut off the bundle at a line boundary so it is at most this number of bytes,
+    /// including the line that marks the cut. The marker itself is cut short when it does
+    /// not fit.
+    pub max_bytes: Option<usize>,
+}
+
+impl Default for TextBundleOptions {
+    fn default() -> Self {
+        TextBundleOptions {
+            max_bytes: Some(1024 * 1024),
+        }
+    }
+}
+
+/// Arrays and objects with a compact json representation longer than this are summarized
+const MAX_INLINE_VALUE_LEN: usize = 80;
+
+/// Writes the table as a markdown like text document.
+///
+/// The sections are, in this order: table info, materials, script and game items. If the
+/// bundle is cut off the last line tells how many bytes were left out.
+pub fn text_bundle(vpx: &VPX, options: &TextBundleOptions) -> String {
+    let mut out = String::new();
+    let info = &vpx.info;
+    let _ = writeln!(out, "# Table");
+    let fields = [
+        ("name", &info.table_name),
+        ("author", &info.author_name),
+        ("version", &info.table_version),
+        ("release date", &info.release_date),
+        ("description", &info.table_description),
+        ("rules", &info.table_rules),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            let _ = writeln!(out, "{}: {}", label, single_line(value));
+        }
+    }
+    let _ = writeln!(out, "file version: {}", vpx.version);
+    let _ = writeln!(
+        out,
+        "items: {}, images: {}, sounds: {}, fonts: {}, collections: {}",
+        vpx.gameitems.len(),
+        vpx.images.len(),
+        vpx.sounds.len(),
+        vpx.fonts.len(),
+        vpx.collections.len()
+    );
+
+    let _ = writeln!(out, "\n# Materials");
+    match &vpx.gamedata.materials {
+        Some(materials) => {
+            for material in materials {
+                let _ = writeln!(
+                    out,
+                    "- {}: {:?}, base {}, glossy {}, opacity {}",
+                    material.name,
+                    material.type_,
+                    material.base_color,
+                    material.glossy_color,
+                    canonical_f64(material.opacity)
+                );
+            }
+        }
+        None => {
+            for material in &vpx.gamedata.materials_old {
+                let _ = writeln!(
+                    out,
+                    "- {}: base {}, glossy {}",
+                    material.name, material.base_color, material.glossy_color
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(out, "\n# Script\n```vbscript");
+    for line in vpx.gamedata.code.string.lines() {
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = writeln!(out, "```");
+
+    let _ = writeln!(out, "\n# Game items");
+    for item in &vpx.gameitems {
+        let _ = writeln!(out, "\n## {} {}", item.type_name(), item.name());
+        // items serialize as an object with the type name as the only key
+        if let Ok(Value::Object(outer)) = serde_json::to_value(item) {
+            if let Some(Value::Object(properties)) = outer.into_iter().next().map(|(_, v)| v) {
+                for (key, value) in properties {
+                    if key != "name" {
+                        let _ = writeln!(out, "{}: {}", key, summarize(&value));
+                    }
+                }
+            }
+        }
+    }
+
+    match options.max_bytes {
+        Some(max_bytes) => truncate(out, max_bytes),
+        None => out,
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn summarize(value: &Value) -> String {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            canonical_f64(number.as_f64().unwrap_or_default() as f32).to_string()
+        }
+        Value::String(string) => single_line(string),
+        Value::Array(values) => {
+            let compact = inline(value);
+            if compact.len() <= MAX_INLINE_VALUE_LEN {
+                compact
+            } else {
+                format!("[{} values]", values.len())
+            }
+        }
+        Value::Object(map) => {
+            let compact = inline(value);
+            if compact.len() <= MAX_INLINE_VALUE_LEN {
+                compact
+            } else {
+                format!("{{{} fields}}", map.len())
+            }
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// Compact json with floats in their shortest form
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            canonical_f64(number.as_f64().unwrap_or_default() as f32).to_string()
+        }
+        Value::Array(values) => {
+            let values: Vec<String> = values.iter().map(inline).collect();
+            format!("[{}]", values.join(","))
+        }
+        Value::Object(map) => {
+            let fields: Vec<String> = map
+                .iter()
+                .map(|(key, value)| format!("{}:{}", key, inline(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn truncate(text: String, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    // the marker is at most this long as the number of omitted bytes fits in 20 digits
+    let marker_len = "\n[truncated, 00000000000000000000 bytes omitted]\n".len();
+    let mut end = max_bytes.saturating_sub(marker_len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = text[..end].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let mut truncated = text[..end].to_string();
+    let _ = writeln!(truncated, "[truncated, {} bytes omitted]", text.len() - end);
+    // only with a budget smaller than the marker, which is ASCII
+    truncated.truncate(max_bytes);
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gameitem::wall::Wall;
+    use crate::vpx::gameitem::GameItemEnum;
+    use crate::vpx::model::StringWithEncoding;
+    use pretty_assertions::assert_eq;
+
+    fn table() -> VPX {
+        let mut vpx = VPX::default();
+        vpx.info.table_name = Some("Test Table".to_string());
+        vpx.gamedata.code = StringWithEncoding::new("Option Explicit\r\nDim x\r\n".to_string());
+        vpx.add_game_item(GameItemEnum::Wall(Wall {
+            name: "Wall001".to_string(),
+            height_top: 50.5,
+            ..Default::default()
+        }));
+        vpx
+    }
+
+    #[test]
+    fn bundle_sections() {
+        let bundle = text_bundle(&table(), &TextBundleOptions { max_bytes: None });
+
+        assert!(bundle.starts_with("# Table\nname: Test Table\n"));
+        assert!(bundle.contains("# Script\n```vbscript\nOption Explicit\nDim x\n```\n"));
+        assert!(bundle.contains("\n## Wall Wall001\n"));
+        assert!(bundle.contains("\nheight_top: 50.5\n"));
+        assert!(!bundle.contains("\nname: Wall001\n"));
+    }
+
+    #[test]
+    fn bundle_truncated() {
+        let full = text_bundle(&table(), &TextBundleOptions { max_bytes: None });
+        let bundle = text_bundle(
+            &table(),
+            &TextBundleOptions {
+                max_bytes: Some(200),
+            },
+        );
+
+        assert!(bundle.len() <= 200);
+        assert!(full.starts_with(bundle.lines().next().unwrap()));
+        let last_line = bundle.lines().last().unwrap();
+        let kept = bundle.len() - last_line.len() - 1;
+        assert_eq!(
+            last_line,
+            format!("[truncated, {} bytes omitted]", full.len() - kept)
+        );
+    }
+
+    #[test]
+    fn truncate_below_marker_length() {
+        let text = "line 1\nline 2\n".repeat(10);
+        for max_bytes in [0, 5, 20] {
+            let truncated = truncate(text.clone(), max_bytes);
+            assert_eq!(truncated.len(), max_bytes);
+            assert!("[truncated, 140 bytes omitted]\n".starts_with(&truncated));
+        }
+    }
+
+    #[test]
+    fn summarize_values() {
+        let value: Value = serde_json::from_str("[1, 2.5, 3]").unwrap();
+        assert_eq!(summarize(&value), "[1,2.5,3]");
+        let value = Value::Array(vec![Value::from(1000000); 20]);
+        assert_eq!(summarize(&value), "[20 values]");
+        let value = Value::from(0.1f32);
+        assert_eq!(summarize(&value), "0.1");
+    }
+}
@@ -0,0 +1,497 @@
+//! Checks for known problems in a table
+//!
+//! [`validate`] runs all checks. [`standalone_compat`] only checks for problems running the
+//! table on Visual Pinball standalone. Standalone runs the table script with its own VBScript engine and only provides a fixed
+//! set of COM objects. It renders with OpenGL ES on many devices, which limits the texture
+//! size. These checks are based on what is known to break, a table that passes can still
+//! have problems.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use vpin::vpx;
+//! use vpin::vpx::validate::standalone_compat;
+//!
+//! let vpx = vpx::read(&PathBuf::from("testdata/completely_blank_table_10_7_4.vpx")).unwrap();
+//! let report = standalone_compat(&vpx);
+//! println!("standalone: {}", if report.passed() { "pass" } else { "fail" });
+//! for issue in &report.issues {
+//!     println!("{}: {}", issue, issue.remediation());
+//! }
+//! ```
+
+use super::audit::{audit_images, ImageAuditSettings, ImageIssue};
+use super::dependencies::strip_comment;
+use super::events::{script_events, EventIssue};
+use super::gamedata::MissingImage;
+use super::gameitem::decal::DecalType;
+use super::gameitem::GameItemEnum;
+use super::mesh::external::{missing_mesh_files, MissingMeshFile};
+use super::VPX;
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// Textures wider or higher than this fail to load on OpenGL ES devices
+pub const STANDALONE_MAX_TEXTURE_SIZE: u32 = 4096;
+
+/// COM objects that standalone provides an implementation for, compared case-insensitively
+const SHIMMED_COM_OBJECTS: [&str; 11] = [
+    "B2S.Server",
+    "FlexDMD.FlexDMD",
+    "PinUpPlayer.PinDisplay",
+    "PUPDMDControl.DMD",
+    "Scripting.Dictionary",
+    "Scripting.FileSystemObject",
+    "UltraDMD.DMDObject",
+    "VBScript.RegExp",
+    "VPinMAME.Controller",
+    "WScript.Shell",
+    "WMPlayer.OCX",
+];
+
+/// Fonts that come with every Windows installation, compared case-insensitively
+const WINDOWS_FONTS: [&str; 16] = [
+    "Arial",
+    "Arial Black",
+    "Comic Sans MS",
+    "Consolas",
+    "Courier New",
+    "Georgia",
+    "Impact",
+    "Lucida Console",
+    "Microsoft Sans Serif",
+    "MS Sans Serif",
+    "Segoe UI",
+    "Symbol",
+    "Tahoma",
+    "Times New Roman",
+    "Trebuchet MS",
+    "Verdana",
+];
+
+/// Script functions that are not available in the standalone VBScript engine
+const UNSUPPORTED_FUNCTIONS: [&str; 2] = ["GetObject", "LoadPicture"];
+
+/// A problem found by [`standalone_compat`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum StandaloneIssue {
+    /// `CreateObject` with a COM object standalone has no implementation for
+    ComObject { prog_id: String, line: usize },
+    /// A call to a function the standalone script engine does not support
+    UnsupportedFunction { function: String, line: usize },
+    /// Sounds with more than one channel can not be positioned on the playfield
+    StereoSound { name: String, channels: u16 },
+    OversizedTexture {
+        name: String,
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    },
+}
+
+impl StandaloneIssue {
+    /// How to fix the issue
+    pub fn remediation(&self) -> String {
+        match self {
+            StandaloneIssue::ComObject { prog_id, .. } => format!(
+                "Remove {} or only create it when not running on standalone, it is not available there",
+                prog_id
+            ),
+            StandaloneIssue::UnsupportedFunction { function, .. } => {
+                match function.to_ascii_lowercase().as_str() {
+                    "getobject" => "Use CreateObject instead".to_string(),
+                    "loadpicture" => "Use images stored in the table instead".to_string(),
+                    _ => format!("Remove the call to {}", function),
+                }
+            }
+            StandaloneIssue::StereoSound { .. } => {
+                "Convert the sound to mono, positional sounds need a single channel".to_string()
+            }
+            StandaloneIssue::OversizedTexture { max_dimension, .. } => format!(
+                "Scale the image down to at most {}x{}",
+                max_dimension, max_dimension
+            ),
+        }
+    }
+}
+
+impl fmt::Display for StandaloneIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StandaloneIssue::ComObject { prog_id, line } => write!(
+                f,
+                "Script line {} creates unsupported COM object {}",
+                line, prog_id
+            ),
+            StandaloneIssue::UnsupportedFunction { function, line } => write!(
+                f,
+                "Script line {} calls unsupported function {}",
+                line, function
+            ),
+            StandaloneIssue::StereoSound { name, channels } => {
+                write!(f, "Sound {} has {} channels", name, channels)
+            }
+            StandaloneIssue::OversizedTexture {
+                name,
+                width,
+                height,
+                max_dimension,
+            } => write!(
+                f,
+                "Image {} is {}x{}, larger than {}",
+                name, width, height, max_dimension
+            ),
+        }
+    }
+}
+
+/// The result of [`standalone_compat`]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct StandaloneReport {
+    pub issues: Vec<StandaloneIssue>,
+}
+
+impl StandaloneReport {
+    pub fn passed(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A text box or text decal using a font that is not embedded in the table
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MissingFont {
+    pub item: String,
+    pub font: String,
+}
+
+/// The result of [`validate`]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ValidationReport {
+    pub standalone: StandaloneReport,
+    /// Missing and orphan script event handlers, see [`super::events`]
+    pub script_events: Vec<EventIssue>,
+    /// Table settings like the ball image that point to an image that does not exist
+    pub missing_images: Vec<MissingImage>,
+    /// Primitives referring to an `obj` file that is not there, only checked by
+    /// [`validate_with_dir`]. Not taken into account by [`ValidationReport::passed`], the
+    /// file is only needed to re-import the mesh.
+    pub missing_mesh_files: Vec<MissingMeshFile>,
+    /// Fonts that are neither embedded nor part of Windows, see [`missing_fonts`]
+    pub missing_fonts: Vec<MissingFont>,
+}
+
+impl ValidationReport {
+    pub fn passed(&self) -> bool {
+        self.standalone.passed()
+            && self.script_events.is_empty()
+            && self.missing_images.is_empty()
+            && self.missing_fonts.is_empty()
+    }
+}
+
+/// Runs all checks on a table
+pub fn validate(vpx: &VPX) -> ValidationReport {
+    ValidationReport {
+        standalone: standalone_compat(vpx),
+        script_events: script_events(vpx).issues,
+        missing_images: vpx.gamedata.missing_images(&vpx.images),
+        missing_mesh_files: vec![],
+        missing_fonts: missing_fonts(vpx),
+    }
+}
+
+/// Text boxes and text decals with a font that is not embedded with [`VPX::add_font`] and
+/// does not come with Windows. Visual Pinball falls back to a default font for these.
+///
+/// Embedded fonts match on their name or the file name they were imported from.
+pub fn missing_fonts(vpx: &VPX) -> Vec<MissingFont> {
+    let available = |font: &str| {
+        WINDOWS_FONTS.iter().any(|f| f.eq_ignore_ascii_case(font))
+            || vpx.fonts.iter().any(|embedded| {
+                let stem = Path::new(&embedded.path)
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default();
+                embedded.name.eq_ignore_ascii_case(font) || stem.eq_ignore_ascii_case(font)
+            })
+    };
+    vpx.gameitems
+        .iter()
+        .filter_map(|item| match item {
+            GameItemEnum::TextBox(textbox) => Some((&textbox.name, textbox.font())),
+            GameItemEnum::Decal(decal) if decal.decal_type == DecalType::Text => {
+                Some((&decal.name, decal.font()))
+            }
+            _ => None,
+        })
+        .filter(|(_, font)| !available(font.name()))
+        .map(|(item, font)| MissingFont {
+            item: item.clone(),
+            font: font.name().to_string(),
+        })
+        .collect()
+}
+
+/// Like [`validate`] but also checks the files the table refers to, relative paths are
+/// relative to `table_dir`
+pub fn validate_with_dir(vpx: &VPX, table_dir: &Path) -> ValidationReport {
+    ValidationReport {
+        missing_mesh_files: missing_mesh_files(vpx, table_dir),
+        ..validate(vpx)
+    }
+}
+
+/// Checks a table for known standalone blockers.
+///
+/// Only string literals passed to `CreateObject` are checked, commented out code is ignored.
+pub fn standalone_compat(vpx: &VPX) -> StandaloneReport {
+    let mut issues = script_issues(&vpx.gamedata.code.string);
+    issues.extend(
+        vpx.sounds
+            .iter()
+            .filter(|sound| sound.ext().eq_ignore_ascii_case("wav"))
+            .filter(|sound| sound.wave_form.channels > 1)
+            .map(|sound| StandaloneIssue::StereoSound {
+                name: sound.name.clone(),
+                channels: sound.wave_form.channels,
+            }),
+    );
+    let settings = ImageAuditSettings {
+        max_dimension: STANDALONE_MAX_TEXTURE_SIZE,
+        check_power_of_two: false,
+        vram_budget: None,
+    };
+    issues.extend(
+        audit_images(&vpx.images, &settings)
+            .issues
+            .into_iter()
+            .filter_map(|issue| match issue {
+                ImageIssue::Oversized {
+                    name,
+                    width,
+                    height,
+                    max_dimension,
+                } => Some(StandaloneIssue::OversizedTexture {
+                    name,
+                    width,
+                    height,
+                    max_dimension,
+                }),
+                _ => None,
+            }),
+    );
+    StandaloneReport { issues }
+}
+
+fn script_issues(script: &str) -> Vec<StandaloneIssue> {
+    let create_object = Regex::new(r#"(?i)\bCreateObject\s*\(\s*"([^"]+)""#).unwrap();
+    let function = Regex::new(&format!(r"(?i)\b({})\b", UNSUPPORTED_FUNCTIONS.join("|"))).unwrap();
+    let mut issues = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let code = strip_comment(line);
+        let line = index + 1;
+        for c in create_object.captures_iter(code) {
+            let prog_id = c[1].trim();
+            if !SHIMMED_COM_OBJECTS
+                .iter()
+                .any(|shimmed| shimmed.eq_ignore_ascii_case(prog_id))
+            {
+                issues.push(StandaloneIssue::ComObject {
+                    prog_id: prog_id.to_string(),
+                    line,
+                });
+            }
+        }
+        for c in function.captures_iter(&blank_strings(code)) {
+            issues.push(StandaloneIssue::UnsupportedFunction {
+                function: c[1].to_string(),
+                line,
+            });
+        }
+    }
+    issues
+}
+
+/// Replaces the contents of string literals so they don't match as code
+fn blank_strings(code: &str) -> String {
+    let mut in_string = false;
+    code.chars()
+        .map(|c| {
+            if c == '"' {
+                in_string = !in_string;
+                c
+            } else if in_string {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vpx::gamedata::TableImage;
+    use crate::vpx::gameitem::decal::Decal;
+    use crate::vpx::gameitem::font::{Font, CHARSET_ANSI};
+    use crate::vpx::gameitem::textbox::TextBox;
+    use crate::vpx::image::{ImageData, ImageDataBits};
+    use crate::vpx::sound::{OutputTarget, SoundData, WaveForm};
+    use pretty_assertions::assert_eq;
+    use std::collections::HashSet;
+
+    #[test]
+    fn script_blockers() {
+        let script = r#"
+Set Controller = CreateObject("VPinMAME.Controller")
+Set dict = CreateObject( "scripting.dictionary" )
+Set proc = CreateObject("VPROC.Controller")
+' Set other = CreateObject("Commented.Out")
+Set shell = GetObject("winmgmts:")
+MsgBox "Call LoadPicture yourself"
+"#;
+        assert_eq!(
+            script_issues(script),
+            vec![
+                StandaloneIssue::ComObject {
+                    prog_id: "VPROC.Controller".to_string(),
+                    line: 4,
+                },
+                StandaloneIssue::UnsupportedFunction {
+                    function: "GetObject".to_string(),
+                    line: 6,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sounds_and_textures() {
+        let mut vpx = VPX::default();
+        for (name, channels) in [("mono", 1), ("stereo", 2)] {
+            let mut wave_form = WaveForm::new();
+            wave_form.channels = channels;
+            vpx.sounds.push(SoundData {
+                name: name.to_string(),
+                path: format!("{}.wav", name),
+                wave_form,
+                data: vec![],
+                internal_name: String::new(),
+                fade: 0,
+                volume: 0,
+                balance: 0,
+                output_target: OutputTarget::Table,
+            });
+        }
+        vpx.images.push(ImageData {
+            name: "huge".to_string(),
+            width: 8192,
+            height: 1024,
+            bits: Some(ImageDataBits {
+                lzw_compressed_data: vec![],
+            }),
+            ..Default::default()
+        });
+
+        let report = standalone_compat(&vpx);
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.issues,
+            vec![
+                StandaloneIssue::StereoSound {
+                    name: "stereo".to_string(),
+                    channels: 2,
+                },
+                StandaloneIssue::OversizedTexture {
+                    name: "huge".to_string(),
+                    width: 8192,
+                    height: 1024,
+                    max_dimension: STANDALONE_MAX_TEXTURE_SIZE,
+                },
+            ]
+        );
+        assert_eq!(
+            report.issues[1].remediation(),
+            "Scale the image down to at most 4096x4096"
+        );
+    }
+
+    #[test]
+    fn blank_table_passes() {
+        assert!(standalone_compat(&VPX::default()).passed());
+        assert!(validate(&VPX::default()).passed());
+    }
+
+    #[test]
+    fn validate_script_events() {
+        let mut vpx = VPX::default();
+        vpx.set_script("Sub Missing_Hit()\nEnd Sub\n".to_string());
+
+        let report = validate(&vpx);
+
+        assert!(report.standalone.passed());
+        assert!(!report.passed());
+        assert_eq!(
+            report.script_events,
+            vec![EventIssue::OrphanHandler {
+                sub: "Missing_Hit".to_string(),
+                line: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_missing_ball_images() {
+        let mut vpx = VPX::default();
+        vpx.gamedata
+            .set_ball_decal_image(Some("Scratches".to_string()));
+
+        let report = validate(&vpx);
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.missing_images,
+            vec![MissingImage {
+                usage: TableImage::BallDecal,
+                name: "Scratches".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_missing_fonts() {
+        let mut vpx = VPX::default();
+        let font = |name: &str| Font::new(CHARSET_ANSI, HashSet::new(), 400, 12, name.to_string());
+        let mut textbox = TextBox::default();
+        textbox.name = "Score".to_string();
+        textbox.set_font(font("Digital-7"));
+        vpx.add_game_item(GameItemEnum::TextBox(textbox));
+        let mut label = Decal::default();
+        label.name = "Label".to_string();
+        label.decal_type = DecalType::Text;
+        label.set_font(font("tahoma"));
+        vpx.add_game_item(GameItemEnum::Decal(label));
+        let mut image_decal = Decal::default();
+        image_decal.set_font(font("Unused"));
+        vpx.add_game_item(GameItemEnum::Decal(image_decal));
+
+        let report = validate(&vpx);
+        assert!(!report.passed());
+        assert_eq!(
+            report.missing_fonts,
+            vec![MissingFont {
+                item: "Score".to_string(),
+                font: "Digital-7".to_string(),
+            }]
+        );
+
+        vpx.add_font("Digital-7", vec![0, 1, 0, 0]);
+        assert_eq!(missing_fonts(&vpx), vec![]);
+        vpx.remove_font("DIGITAL-7");
+        assert_eq!(missing_fonts(&vpx).len(), 1);
+    }
+}
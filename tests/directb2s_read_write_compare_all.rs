@@ -5,7 +5,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::fmt::Write;
 use std::hash::{Hash, Hasher};
 use std::io;
-use std::io::{Error, ErrorKind, Read};
+use std::io::{Error, Read};
 use std::path::PathBuf;
 use testresult::TestResult;
 use vpin::directb2s;
@@ -161,7 +161,7 @@ fn read_directb2s(path: &PathBuf) -> Result<DirectB2SData, Error> {
     let reader = std::io::BufReader::new(file);
     directb2s::read(reader).map_err(|e| {
         let msg = format!("Error for {}: {}", path.display(), e);
-        io::Error::new(ErrorKind::Other, msg)
+        io::Error::other(msg)
     })
 }
 